@@ -0,0 +1,70 @@
+//! Text of pages as exposed to assistive technologies, e.g. screen readers.
+//!
+//! Pages are exposed as plain text in reading order, with lines and
+//! paragraphs taken from the text structure extracted by pdfium.
+
+use pdfium::doc::{Document, TextStructure};
+
+/// Extract the text of the given page for screen readers.
+///
+/// This is blocking and should be run on the executor.
+pub fn page_text(doc: &Document, index: usize) -> pdfium::Result<String> {
+    let page = doc.pages().get(index as _)?;
+    let text = page.text()?;
+
+    Ok(structure_text(&text.structure()?))
+}
+
+/// Join the lines of the given text structure, separating lines by line
+/// breaks and paragraphs by blank lines.
+fn structure_text(text: &TextStructure) -> String {
+    let paragraphs: Vec<_> = text
+        .paragraphs
+        .iter()
+        .map(|para| {
+            let lines: Vec<_> = text.lines[para.lines.clone()]
+                .iter()
+                .map(|line| line.text())
+                .collect();
+
+            lines.join("\n")
+        })
+        .collect();
+
+    paragraphs.join("\n\n")
+}
+
+#[cfg(test)]
+mod test {
+    use pdfium::types::Rect;
+
+    use super::*;
+
+    #[test]
+    fn paragraphs_are_separated() {
+        let lines = [("one two", 700.0), ("three", 688.0), ("four", 600.0)];
+
+        let mut chars = Vec::new();
+        let mut index = 0;
+
+        for (text, top) in lines {
+            for (x, c) in text.chars().enumerate() {
+                let rect = Rect {
+                    left: x as f32 * 10.0,
+                    top,
+                    right: x as f32 * 10.0 + 8.0,
+                    bottom: top - 10.0,
+                };
+
+                chars.push((index, c, (!c.is_whitespace()).then_some(rect)));
+                index += 1;
+            }
+
+            chars.push((index, '\n', None));
+            index += 1;
+        }
+
+        let text = TextStructure::from_chars(chars);
+        assert_eq!(structure_text(&text), "one two\nthree\n\nfour");
+    }
+}
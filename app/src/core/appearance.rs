@@ -0,0 +1,123 @@
+//! Appearance of pages, i.e. whether they are shown with light or dark colors.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk::gio;
+use gtk::prelude::{DBusProxyExt, ObjectExt};
+
+use pdfium::bitmap::{Color, ColorScheme};
+
+/// Background of pages rendered with night colors.
+pub const NIGHT_BACKGROUND: Color = Color::new_rgb(0x24, 0x24, 0x24);
+
+/// Colors of text and paths of pages rendered for night reading. Filled areas
+/// stay dark, so that they do not cover the text drawn on top of them.
+pub const NIGHT_COLORS: ColorScheme = ColorScheme {
+    path_fill_color: Color::new_rgb(0x3d, 0x3d, 0x3d),
+    path_stroke_color: Color::new_rgb(0xc0, 0xbf, 0xbc),
+    text_fill_color: Color::new_rgb(0xde, 0xdd, 0xda),
+    text_stroke_color: Color::new_rgb(0xde, 0xdd, 0xda),
+};
+
+/// How pages of a document are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageStyle {
+    /// Follow the desktop style and, if enabled, the night light.
+    #[default]
+    Auto,
+
+    Light,
+    Dark,
+}
+
+impl PageStyle {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PageStyle::Auto => "auto",
+            PageStyle::Light => "light",
+            PageStyle::Dark => "dark",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(PageStyle::Auto),
+            "light" => Some(PageStyle::Light),
+            "dark" => Some(PageStyle::Dark),
+            _ => None,
+        }
+    }
+
+    /// Whether pages are dark, given whether they would be dark automatically.
+    pub fn is_dark(&self, auto: bool) -> bool {
+        match self {
+            PageStyle::Auto => auto,
+            PageStyle::Light => false,
+            PageStyle::Dark => true,
+        }
+    }
+}
+
+/// State of the night light, i.e. the reduction of blue light in the evening,
+/// as provided by the GNOME settings daemon.
+#[derive(Debug, Clone, Default)]
+pub struct NightLight {
+    proxy: Rc<RefCell<Option<gio::DBusProxy>>>,
+}
+
+impl NightLight {
+    const NAME: &'static str = "org.gnome.SettingsDaemon.Color";
+    const PATH: &'static str = "/org/gnome/SettingsDaemon/Color";
+    const INTERFACE: &'static str = "org.gnome.SettingsDaemon.Color";
+
+    /// Connect to the settings daemon, calling the given function whenever
+    /// the night light state changes.
+    ///
+    /// The night light is considered inactive if the settings daemon is not
+    /// available, e.g. on other desktops.
+    pub fn connect<F: Fn() + 'static>(f: F) -> Self {
+        let night_light = Self::default();
+        let slot = night_light.proxy.clone();
+        let f = Rc::new(f);
+
+        gio::DBusProxy::for_bus(
+            gio::BusType::Session,
+            gio::DBusProxyFlags::DO_NOT_AUTO_START,
+            None,
+            Self::NAME,
+            Self::PATH,
+            Self::INTERFACE,
+            None::<&gio::Cancellable>,
+            move |result| {
+                let proxy = match result {
+                    Ok(proxy) => proxy,
+                    Err(err) => {
+                        tracing::debug!(error=%err, "night light state not available");
+                        return;
+                    }
+                };
+
+                let notify = f.clone();
+                proxy.connect_local("g-properties-changed", false, move |_| {
+                    notify();
+                    None
+                });
+
+                slot.replace(Some(proxy));
+                f();
+            },
+        );
+
+        night_light
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.proxy
+            .borrow()
+            .as_ref()
+            .and_then(|proxy| proxy.cached_property("NightLightActive"))
+            .and_then(|value| value.get::<bool>())
+            .unwrap_or(false)
+    }
+}
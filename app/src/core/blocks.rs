@@ -0,0 +1,234 @@
+//! Text blocks of a page in reading order, for stepping through pages block
+//! by block, e.g. through both columns of a two-column paper at high zoom.
+
+use nalgebra::{point, vector, Vector2};
+
+use pdfium::doc::Document;
+
+use crate::types::Rect;
+
+use super::textlayer::{self, BBox};
+
+/// Minimum fraction of lines lying entirely in the right half of the content
+/// for a page to be considered as having two columns.
+const MIN_RIGHT_COLUMN_FRACTION: f64 = 0.2;
+
+/// Vertical gap between lines, relative to the median line height, starting
+/// a new block.
+const BLOCK_GAP: f64 = 1.0;
+
+/// Margin around blocks when scrolling to them, in PDF points.
+const MARGIN: f64 = 12.0;
+
+/// Fraction of the view kept visible when scrolling within a block.
+const OVERLAP: f64 = 0.1;
+
+/// Tolerance when comparing positions, in PDF points.
+const EPSILON: f64 = 1.0;
+
+/// Result of a navigation step.
+#[derive(Debug, Clone, Copy)]
+pub enum Step {
+    /// Show the given part of the current page, in page coordinates, with
+    /// the block at the given index becoming the current block.
+    Show(usize, Rect<f64>),
+
+    /// Continue on the adjacent page, in direction of the step.
+    AdjacentPage,
+}
+
+/// Compute the text blocks of a page in reading order, in page coordinates
+/// (PDF points).
+///
+/// Pages without text are treated as a single block covering the full page,
+/// so that they can still be stepped through. Returns no blocks if the page
+/// fails to load.
+pub fn detect(doc: &Document, index: usize) -> Vec<Rect<f64>> {
+    let page = match doc.pages().get(index as _) {
+        Ok(page) => page,
+        Err(err) => {
+            tracing::warn!(page = index, error=%err, "failed to load page");
+            return Vec::new();
+        }
+    };
+
+    let text = page
+        .text()
+        .and_then(|text_page| textlayer::page_text(&page, &text_page, index));
+
+    let lines = match text {
+        Ok(text) => text.lines.iter().map(|line| line.bbox).collect(),
+        Err(err) => {
+            tracing::warn!(page = index, error=%err, "failed to load page text");
+            Vec::new()
+        }
+    };
+
+    let blocks = group(lines);
+
+    if blocks.is_empty() {
+        let size = vector![page.width() as f64, page.height() as f64];
+        vec![Rect::new(point![0.0, 0.0], size)]
+    } else {
+        blocks
+    }
+}
+
+/// Group lines into blocks in reading order: Full-width sections top to
+/// bottom, and within two-column sections the left column before the right
+/// one.
+fn group(mut lines: Vec<BBox>) -> Vec<Rect<f64>> {
+    if lines.is_empty() {
+        return Vec::new();
+    }
+
+    lines.sort_by(|a, b| a.y0.total_cmp(&b.y0));
+
+    // horizontal extent of the content
+    let left = lines.iter().map(|b| b.x0).fold(f64::INFINITY, f64::min);
+    let right = lines.iter().map(|b| b.x1).fold(f64::NEG_INFINITY, f64::max);
+    let mid = (left + right) / 2.0;
+
+    let mut heights: Vec<_> = lines.iter().map(|b| b.y1 - b.y0).collect();
+    heights.sort_by(f64::total_cmp);
+    let gap = heights[heights.len() / 2] * BLOCK_GAP;
+
+    // only split into columns if there is a substantial right column
+    let right_lines = lines.iter().filter(|b| b.x0 >= mid).count();
+    let two_columns = right_lines as f64 >= lines.len() as f64 * MIN_RIGHT_COLUMN_FRACTION;
+
+    let mut blocks = Vec::new();
+    let mut full = Vec::new();
+    let mut columns = (Vec::new(), Vec::new());
+
+    for line in lines {
+        let in_left = line.x1 <= mid;
+        let in_right = line.x0 >= mid;
+
+        if !two_columns || !(in_left || in_right) {
+            // full-width line ends the current two-column section
+            split(&columns.0, gap, &mut blocks);
+            split(&columns.1, gap, &mut blocks);
+            columns.0.clear();
+            columns.1.clear();
+
+            full.push(line);
+        } else {
+            // column line ends the current full-width section
+            split(&full, gap, &mut blocks);
+            full.clear();
+
+            if in_left {
+                columns.0.push(line);
+            } else {
+                columns.1.push(line);
+            }
+        }
+    }
+
+    split(&full, gap, &mut blocks);
+    split(&columns.0, gap, &mut blocks);
+    split(&columns.1, gap, &mut blocks);
+
+    blocks
+}
+
+/// Split sorted lines into blocks at vertical gaps larger than `gap`.
+fn split(lines: &[BBox], gap: f64, blocks: &mut Vec<Rect<f64>>) {
+    let mut block: Option<BBox> = None;
+
+    for line in lines {
+        block = match block {
+            Some(b) if line.y0 - b.y1 <= gap => Some(BBox {
+                x0: b.x0.min(line.x0),
+                y0: b.y0.min(line.y0),
+                x1: b.x1.max(line.x1),
+                y1: b.y1.max(line.y1),
+            }),
+            Some(b) => {
+                blocks.push(to_rect(&b));
+                Some(*line)
+            }
+            None => Some(*line),
+        };
+    }
+
+    blocks.extend(block.as_ref().map(to_rect));
+}
+
+fn to_rect(b: &BBox) -> Rect<f64> {
+    Rect::new(point![b.x0, b.y0], vector![b.x1 - b.x0, b.y1 - b.y0])
+}
+
+/// Compute the next view when stepping forward or backward through the
+/// blocks of a page.
+///
+/// Blocks larger than the view are scrolled through before moving on to
+/// the next block. The current block is kept as long as it is in view,
+/// otherwise the first block in view is used.
+pub fn step(blocks: &[Rect<f64>], current: Option<usize>, view: &Rect<f64>, forward: bool) -> Step {
+    let current = current
+        .filter(|&i| blocks.get(i).is_some_and(|b| b.intersects(view)))
+        .or_else(|| blocks.iter().position(|b| b.intersects(view)));
+
+    let current = match current {
+        Some(current) => current,
+        None => {
+            // nothing in view, find the closest block in direction of the step
+            let next = if forward {
+                blocks.iter().position(|b| b.offs.y >= view.offs.y)
+            } else {
+                blocks.iter().rposition(|b| b.offs.y < view.offs.y)
+            };
+
+            return match next {
+                Some(i) => Step::Show(i, align(&blocks[i], &view.size, forward)),
+                None => Step::AdjacentPage,
+            };
+        }
+    };
+
+    let block = &blocks[current];
+
+    if forward {
+        // scroll within the block until its end is in view
+        if block.offs.y + block.size.y > view.offs.y + view.size.y + EPSILON {
+            let y = view.offs.y + view.size.y * (1.0 - OVERLAP);
+            return Step::Show(current, Rect::new(point![view.offs.x, y], view.size));
+        }
+
+        match blocks.get(current + 1) {
+            Some(next) => Step::Show(current + 1, align(next, &view.size, true)),
+            None => Step::AdjacentPage,
+        }
+    } else {
+        // scroll within the block until its start is in view
+        if block.offs.y < view.offs.y - EPSILON {
+            let y = (view.offs.y - view.size.y * (1.0 - OVERLAP)).max(block.offs.y - MARGIN);
+            return Step::Show(current, Rect::new(point![view.offs.x, y], view.size));
+        }
+
+        match current.checked_sub(1) {
+            Some(prev) => Step::Show(prev, align(&blocks[prev], &view.size, false)),
+            None => Step::AdjacentPage,
+        }
+    }
+}
+
+/// View of the given size showing the start (or the end) of the given block.
+/// Blocks narrower than the view are centered horizontally.
+pub fn align(block: &Rect<f64>, view_size: &Vector2<f64>, start: bool) -> Rect<f64> {
+    let x = if block.size.x + 2.0 * MARGIN <= view_size.x {
+        block.offs.x + (block.size.x - view_size.x) / 2.0
+    } else {
+        block.offs.x - MARGIN
+    };
+
+    let y = if start || block.size.y + 2.0 * MARGIN <= view_size.y {
+        block.offs.y - MARGIN
+    } else {
+        block.offs.y + block.size.y + MARGIN - view_size.y
+    };
+
+    Rect::new(point![x, y], *view_size)
+}
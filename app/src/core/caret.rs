@@ -0,0 +1,321 @@
+//! Caret navigation through the text of a page, for selecting text with the
+//! keyboard only.
+
+use std::ops::Range;
+
+use nalgebra::{point, vector, Point2};
+
+use pdfium::doc::Document;
+
+use crate::types::{Bounds, Rect};
+
+/// A character of a page and its bounds, in page coordinates (PDF points).
+#[derive(Debug, Clone, Copy)]
+struct Glyph {
+    c: char,
+    rect: Rect<f64>,
+}
+
+/// The text of a page, divided into lines, for moving a caret through it.
+///
+/// Caret positions are indices between characters, i.e. position `i` is in
+/// front of the `i`-th character and position `len()` is behind the last one.
+/// Line breaks are kept as characters at the end of their lines, so that the
+/// caret can be placed behind the last character of each line.
+#[derive(Debug, Clone, Default)]
+pub struct CaretText {
+    glyphs: Vec<Glyph>,
+    lines: Vec<Range<usize>>,
+}
+
+/// A movement of the caret.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordLeft,
+    WordRight,
+    LineStart,
+    LineEnd,
+    PageStart,
+    PageEnd,
+}
+
+/// Position of the caret and, if text is being selected, the position the
+/// selection has been started at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Caret {
+    pub page: usize,
+    pub pos: usize,
+    pub anchor: Option<usize>,
+}
+
+/// What to show of the caret on the canvas.
+#[derive(Debug, Clone)]
+pub struct CaretMarks {
+    /// Index of the page the caret is on.
+    pub page: usize,
+
+    /// Bounds of the caret, in page coordinates.
+    pub caret: Rect<f64>,
+
+    /// Bounds of the selected text per line, in page coordinates.
+    pub selection: Vec<Rect<f64>>,
+}
+
+impl Motion {
+    /// Whether the motion moves the caret towards the end of the document.
+    pub fn is_forward(&self) -> bool {
+        matches!(
+            self,
+            Motion::Right | Motion::Down | Motion::WordRight | Motion::LineEnd | Motion::PageEnd
+        )
+    }
+}
+
+impl Caret {
+    /// The selected range of characters, if any.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.anchor?;
+        let range = anchor.min(self.pos)..anchor.max(self.pos);
+
+        (!range.is_empty()).then_some(range)
+    }
+}
+
+impl CaretText {
+    /// Load the text of the given page. Pages that fail to load are treated
+    /// as having no text.
+    pub fn load(doc: &Document, index: usize) -> Self {
+        match Self::try_load(doc, index) {
+            Ok(text) => text,
+            Err(err) => {
+                tracing::warn!(page = index, error=%err, "failed to load page text");
+                Self::default()
+            }
+        }
+    }
+
+    fn try_load(doc: &Document, index: usize) -> pdfium::Result<Self> {
+        let page = doc.pages().get(index as _)?;
+        let height = page.height() as f64;
+
+        let text_page = page.text()?;
+        let text = text_page.text()?;
+
+        let mut glyphs: Vec<Glyph> = Vec::new();
+        let mut lines = Vec::new();
+        let mut start = 0;
+
+        // character indices correspond to UTF-16 code units
+        let mut char_index = 0;
+
+        for c in text.chars() {
+            let index = char_index;
+            char_index += c.len_utf16() as u32;
+
+            // line breaks are reported as "\r\n", keep only one character
+            if c == '\r' {
+                continue;
+            }
+
+            // generated characters, e.g. line breaks, do not have bounds, so
+            // place them behind the previous character
+            let rect = match text_page.char_box(index) {
+                Ok(r) if c != '\n' => Rect::new(
+                    point![r.left as f64, height - r.top as f64],
+                    vector![(r.right - r.left) as f64, (r.top - r.bottom) as f64],
+                ),
+                _ => match glyphs.last() {
+                    Some(prev) => Rect::new(
+                        point![prev.rect.offs.x + prev.rect.size.x, prev.rect.offs.y],
+                        vector![0.0, prev.rect.size.y],
+                    ),
+                    None => continue,
+                },
+            };
+
+            glyphs.push(Glyph { c, rect });
+
+            if c == '\n' {
+                lines.push(start..glyphs.len());
+                start = glyphs.len();
+            }
+        }
+
+        if start < glyphs.len() {
+            lines.push(start..glyphs.len());
+        }
+
+        Ok(Self { glyphs, lines })
+    }
+
+    /// Number of characters, i.e. the last caret position.
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    /// Bounds of the caret at the given position, as zero-width rectangle in
+    /// page coordinates.
+    pub fn caret_rect(&self, pos: usize) -> Option<Rect<f64>> {
+        let rect = match self.glyphs.get(pos) {
+            Some(glyph) => Rect::new(glyph.rect.offs, vector![0.0, glyph.rect.size.y]),
+            None => {
+                let last = self.glyphs.last()?.rect;
+                Rect::new(
+                    point![last.offs.x + last.size.x, last.offs.y],
+                    vector![0.0, last.size.y],
+                )
+            }
+        };
+
+        Some(rect)
+    }
+
+    /// Bounds of the given range of characters, one rectangle per line, in
+    /// page coordinates.
+    pub fn selection_rects(&self, range: &Range<usize>) -> Vec<Rect<f64>> {
+        self.lines
+            .iter()
+            .filter_map(|line| {
+                let start = range.start.max(line.start);
+                let end = range.end.min(line.end);
+
+                let bounds = self.glyphs.get(start..end)?.iter().map(|g| g.rect.bounds());
+
+                bounds
+                    .reduce(|a, b| Bounds {
+                        x_min: a.x_min.min(b.x_min),
+                        y_min: a.y_min.min(b.y_min),
+                        x_max: a.x_max.max(b.x_max),
+                        y_max: a.y_max.max(b.y_max),
+                    })
+                    .map(Rect::from)
+            })
+            .collect()
+    }
+
+    /// The text of the given range of characters.
+    pub fn text(&self, range: &Range<usize>) -> String {
+        let end = range.end.min(self.glyphs.len());
+        let start = range.start.min(end);
+
+        self.glyphs[start..end].iter().map(|g| g.c).collect()
+    }
+
+    /// Caret position closest to the given point, in page coordinates, i.e.
+    /// the position on the closest line in front of or behind the character
+    /// at the point.
+    pub fn pos_at(&self, point: Point2<f64>) -> Option<usize> {
+        let distance = |line: &&Range<usize>| {
+            let glyphs = self.glyphs[line.start..line.end].iter();
+            let y_min = glyphs
+                .clone()
+                .map(|g| g.rect.offs.y)
+                .fold(f64::INFINITY, f64::min);
+            let y_max = glyphs
+                .map(|g| g.rect.offs.y + g.rect.size.y)
+                .fold(f64::NEG_INFINITY, f64::max);
+
+            (y_min - point.y).max(point.y - y_max).max(0.0)
+        };
+
+        let line = self
+            .lines
+            .iter()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))?;
+
+        self.closest_in_line(line, point.x)
+    }
+
+    /// Move the caret from the given position. Returns `None` if the caret
+    /// leaves the page in direction of the motion.
+    pub fn step(&self, pos: usize, motion: Motion) -> Option<usize> {
+        let pos = pos.min(self.len());
+
+        match motion {
+            Motion::Left => pos.checked_sub(1),
+            Motion::Right => (pos < self.len()).then_some(pos + 1),
+            Motion::Up | Motion::Down => {
+                let line = self.line_at(pos)?;
+                let target = if motion == Motion::Up {
+                    line.checked_sub(1)?
+                } else {
+                    line + 1
+                };
+
+                let x = self.caret_rect(pos)?.offs.x;
+                self.closest_in_line(self.lines.get(target)?, x)
+            }
+            Motion::WordLeft => {
+                let mut p = pos.checked_sub(1)?;
+
+                while p > 0 && self.glyphs[p].c.is_whitespace() {
+                    p -= 1;
+                }
+                while p > 0 && !self.glyphs[p - 1].c.is_whitespace() {
+                    p -= 1;
+                }
+
+                Some(p)
+            }
+            Motion::WordRight => {
+                if pos >= self.len() {
+                    return None;
+                }
+
+                let mut p = pos;
+
+                while p < self.len() && !self.glyphs[p].c.is_whitespace() {
+                    p += 1;
+                }
+                while p < self.len() && self.glyphs[p].c.is_whitespace() {
+                    p += 1;
+                }
+
+                Some(p)
+            }
+            Motion::LineStart => match self.line_at(pos) {
+                Some(line) => Some(self.lines[line].start),
+                None => Some(pos),
+            },
+            Motion::LineEnd => match self.line_at(pos) {
+                Some(line) => Some(self.line_end(&self.lines[line])),
+                None => Some(pos),
+            },
+            Motion::PageStart => Some(0),
+            Motion::PageEnd => Some(self.len()),
+        }
+    }
+
+    /// Index of the line the caret is on at the given position.
+    fn line_at(&self, pos: usize) -> Option<usize> {
+        if pos >= self.len() {
+            return self.lines.len().checked_sub(1);
+        }
+
+        self.lines.iter().position(|line| line.contains(&pos))
+    }
+
+    /// Last caret position on the given line, i.e. in front of its line
+    /// break, if it has one.
+    fn line_end(&self, line: &Range<usize>) -> usize {
+        match self.glyphs.get(line.end - 1) {
+            Some(glyph) if glyph.c == '\n' => line.end - 1,
+            _ => line.end,
+        }
+    }
+
+    /// Caret position on the given line closest to the given horizontal
+    /// coordinate.
+    fn closest_in_line(&self, line: &Range<usize>, x: f64) -> Option<usize> {
+        let distance = |pos: &usize| match self.caret_rect(*pos) {
+            Some(rect) => (rect.offs.x - x).abs(),
+            None => f64::INFINITY,
+        };
+
+        (line.start..=self.line_end(line)).min_by(|a, b| distance(a).total_cmp(&distance(b)))
+    }
+}
@@ -0,0 +1,424 @@
+//! Detection of intra-document references, e.g. citations like "[12]" or
+//! "Section 4.2", and their targets.
+//!
+//! This is used to synthesize internal links for documents that do not
+//! provide link annotations themselves.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use nalgebra::{point, vector};
+use pdfium::doc::{AnnotationSubtype, Document, Page, TextPage};
+
+use crate::types::Rect;
+
+/// Maximum length of the contents of a bracketed citation, in bytes.
+const MAX_CITATION_LEN: usize = 32;
+
+/// Keywords introducing a section reference.
+const SECTION_KEYWORDS: &[&str] = &["Section", "Sections", "Sec.", "§"];
+
+/// Target of a reference.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Target {
+    /// Bibliography entry with the given label, e.g. "12".
+    Reference(String),
+
+    /// Section with the given number, e.g. "4.2".
+    Section(String),
+}
+
+/// A reference or target found in a text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    /// Byte range of the match in the text
+    pub range: Range<usize>,
+
+    /// The referenced target
+    pub target: Target,
+}
+
+/// Location in the document, as page index and bounds in page coordinates.
+#[derive(Debug, Clone, Copy)]
+pub struct Location {
+    pub page: usize,
+    pub rect: Rect<f64>,
+}
+
+/// A link synthesized from a reference in the document text.
+#[derive(Debug, Clone, Copy)]
+pub struct Link {
+    /// Page index and bounds of the reference
+    pub source: Location,
+
+    /// Page index and bounds of the referenced target
+    pub target: Location,
+}
+
+/// Find references in running text, e.g. "[12]", "[3, 5]", or "Section 4.2".
+pub fn find_references(text: &str) -> Vec<Match> {
+    let mut matches = Vec::new();
+
+    // bracketed citations, each number is referenced separately
+    for (open, _) in text.match_indices('[') {
+        // bracketed labels at the start of a line are bibliography entries
+        if is_line_start(text, open) {
+            continue;
+        }
+
+        let close = match text[open..].find(']') {
+            Some(len) if len <= MAX_CITATION_LEN => open + len,
+            _ => continue,
+        };
+
+        if let Some(numbers) = citation_numbers(text, open + 1..close) {
+            matches.extend(numbers.into_iter().map(|range| Match {
+                target: Target::Reference(text[range.clone()].into()),
+                range,
+            }));
+        }
+    }
+
+    // section references
+    for keyword in SECTION_KEYWORDS {
+        for (start, _) in text.match_indices(keyword) {
+            // require a word boundary before the keyword
+            let boundary = text[..start]
+                .chars()
+                .next_back()
+                .map(|c| !c.is_alphanumeric())
+                .unwrap_or(true);
+
+            if !boundary {
+                continue;
+            }
+
+            let rest = &text[start + keyword.len()..];
+            let number_start = text.len() - rest.trim_start().len();
+
+            if let Some(end) = section_number_end(text, number_start) {
+                matches.push(Match {
+                    range: start..end,
+                    target: Target::Section(text[number_start..end].into()),
+                });
+            }
+        }
+    }
+
+    matches.sort_by_key(|m| m.range.start);
+    matches
+}
+
+/// Find reference targets, i.e. bibliography entries like "[12] Author, ..."
+/// and numbered section headings like "4.2 Results", at the start of lines.
+pub fn find_targets(text: &str) -> Vec<Match> {
+    let mut matches = Vec::new();
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let start = offset + (line.len() - line.trim_start().len());
+        offset += line.len();
+
+        // bibliography entry
+        if text[start..].starts_with('[') {
+            let close = match text[start..].find(']') {
+                Some(len) if len <= MAX_CITATION_LEN => start + len,
+                _ => continue,
+            };
+
+            let label = &text[start + 1..close];
+            if !label.is_empty() && label.bytes().all(|b| b.is_ascii_digit()) {
+                matches.push(Match {
+                    range: start..close + 1,
+                    target: Target::Reference(label.into()),
+                });
+            }
+
+            continue;
+        }
+
+        // section heading: number, optional dot, and a capitalized title
+        if let Some(end) = section_number_end(text, start) {
+            let rest = text[end..].strip_prefix('.').unwrap_or(&text[end..]);
+            let title = rest.trim_start_matches([' ', '\t']);
+
+            let capitalized = matches!(title.chars().next(), Some(c) if c.is_uppercase());
+            let is_heading = capitalized && title.len() < rest.len();
+
+            if is_heading {
+                matches.push(Match {
+                    range: start..end,
+                    target: Target::Section(text[start..end].into()),
+                });
+            }
+        }
+    }
+
+    matches
+}
+
+/// Synthesize links from references to their targets.
+///
+/// Pages providing link annotations are expected to link their references
+/// already and are therefore skipped. If a target occurs multiple times, e.g.
+/// in the table of contents and as actual heading, the last occurrence is
+/// used.
+pub fn synthesize_links(doc: &Document) -> Vec<Link> {
+    let mut targets = HashMap::new();
+    let mut references = Vec::new();
+
+    for index in 0..doc.pages().count() {
+        let page = match doc.pages().get(index) {
+            Ok(page) => page,
+            Err(err) => {
+                tracing::warn!(page = index, error=%err, "failed to load page");
+                continue;
+            }
+        };
+
+        let text_page = match page.text() {
+            Ok(text_page) => text_page,
+            Err(err) => {
+                tracing::warn!(page = index, error=%err, "failed to load page text");
+                continue;
+            }
+        };
+
+        let text = match text_page.text() {
+            Ok(text) => text,
+            Err(err) => {
+                tracing::warn!(page = index, error=%err, "failed to load page text");
+                continue;
+            }
+        };
+
+        let index = index as usize;
+
+        for m in find_targets(&text) {
+            if let Some(rect) = text_bounds(&page, &text_page, &text, &m.range) {
+                targets.insert(m.target, Location { page: index, rect });
+            }
+        }
+
+        let has_links = page
+            .annotations()
+            .any(|annot| annot.subtype() == AnnotationSubtype::Link);
+
+        if has_links {
+            continue;
+        }
+
+        for m in find_references(&text) {
+            if let Some(rect) = text_bounds(&page, &text_page, &text, &m.range) {
+                references.push((Location { page: index, rect }, m.target));
+            }
+        }
+    }
+
+    references
+        .into_iter()
+        .filter_map(|(source, target)| {
+            let target = *targets.get(&target)?;
+            Some(Link { source, target })
+        })
+        .collect()
+}
+
+fn is_line_start(text: &str, pos: usize) -> bool {
+    text[..pos]
+        .trim_end_matches([' ', '\t'])
+        .chars()
+        .next_back()
+        .map(|c| c == '\n' || c == '\r')
+        .unwrap_or(true)
+}
+
+/// Parse a section number, e.g. "4" or "4.2.1", starting at the given byte
+/// offset and return the offset of its end.
+fn section_number_end(text: &str, start: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let digits = |i: usize| bytes[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+
+    let mut end = start + digits(start);
+    if end == start {
+        return None;
+    }
+
+    while end + 1 < bytes.len() && bytes[end] == b'.' && bytes[end + 1].is_ascii_digit() {
+        end += 1 + digits(end + 1);
+    }
+
+    Some(end)
+}
+
+/// Parse the contents of a bracketed citation, e.g. "3, 5-7", and return the
+/// byte ranges of all numbers. Returns `None` if this is not a citation.
+fn citation_numbers(text: &str, range: Range<usize>) -> Option<Vec<Range<usize>>> {
+    let mut numbers: Vec<Range<usize>> = Vec::new();
+
+    for (i, c) in text[range.clone()].char_indices() {
+        let pos = range.start + i;
+
+        if c.is_ascii_digit() {
+            match numbers.last_mut() {
+                Some(number) if number.end == pos => number.end += 1,
+                _ => numbers.push(pos..pos + 1),
+            }
+        } else if ![',', ';', '-', '–', ' '].contains(&c) {
+            return None;
+        }
+    }
+
+    if numbers.is_empty() {
+        None
+    } else {
+        Some(numbers)
+    }
+}
+
+/// Compute the bounds of the given text range in page coordinates.
+fn text_bounds(
+    page: &Page,
+    text_page: &TextPage,
+    text: &str,
+    range: &Range<usize>,
+) -> Option<Rect<f64>> {
+    // character indices correspond to UTF-16 code units
+    let start = text[..range.start].encode_utf16().count() as u32;
+    let end = start + text[range.clone()].encode_utf16().count() as u32;
+
+    let mut bounds: Option<pdfium::types::Rect> = None;
+
+    for index in start..end {
+        let r = match text_page.char_box(index) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
+
+        bounds = Some(match bounds {
+            Some(b) => pdfium::types::Rect {
+                left: b.left.min(r.left),
+                top: b.top.max(r.top),
+                right: b.right.max(r.right),
+                bottom: b.bottom.min(r.bottom),
+            },
+            None => r,
+        });
+    }
+
+    // PDF coordinates have their origin at the bottom left corner
+    let b = bounds?;
+    let height = page.height() as f64;
+
+    Some(Rect::new(
+        point![b.left as f64, height - b.top as f64],
+        vector![(b.right - b.left) as f64, (b.top - b.bottom) as f64],
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The text and target of each match.
+    fn matched<'a>(text: &'a str, matches: &[Match]) -> Vec<(&'a str, Target)> {
+        matches
+            .iter()
+            .map(|m| (&text[m.range.clone()], m.target.clone()))
+            .collect()
+    }
+
+    fn reference(label: &str) -> Target {
+        Target::Reference(label.into())
+    }
+
+    fn section(number: &str) -> Target {
+        Target::Section(number.into())
+    }
+
+    #[test]
+    fn bracketed_citations() {
+        let text = "as shown in [12], but see [a] and [1234567890123456789012345678901234].\n\
+                    [4] An entry of the bibliography.";
+
+        assert_eq!(
+            matched(text, &find_references(text)),
+            [("12", reference("12"))]
+        );
+    }
+
+    #[test]
+    fn citation_ranges_and_lists() {
+        let text = "in [3-5], [1, 4], [2–7] and [8; 9, 10]";
+
+        assert_eq!(
+            matched(text, &find_references(text)),
+            [
+                ("3", reference("3")),
+                ("5", reference("5")),
+                ("1", reference("1")),
+                ("4", reference("4")),
+                ("2", reference("2")),
+                ("7", reference("7")),
+                ("8", reference("8")),
+                ("9", reference("9")),
+                ("10", reference("10")),
+            ]
+        );
+    }
+
+    #[test]
+    fn section_references() {
+        let text = "see Section 4.2.1. Then Sec. 3, Sections 5 and 6, §2, Subsection 7, \
+                    and XSection 8.";
+
+        assert_eq!(
+            matched(text, &find_references(text)),
+            [
+                ("Section 4.2.1", section("4.2.1")),
+                ("Sec. 3", section("3")),
+                ("Sections 5", section("5")),
+                ("§2", section("2")),
+            ]
+        );
+    }
+
+    #[test]
+    fn reference_targets() {
+        let text = "[12] Author, Title.\n\
+                    4.2.1. Results\n\
+                    4.2 results in lowercase\n  \
+                    5 Conclusion\n\
+                    3.";
+
+        assert_eq!(
+            matched(text, &find_targets(text)),
+            [
+                ("[12]", reference("12")),
+                ("4.2.1", section("4.2.1")),
+                ("5", section("5")),
+            ]
+        );
+    }
+
+    #[test]
+    fn non_ascii_text() {
+        let text = "Größe ü[7] – „siehe § 3“, Section 4.2é, [ü], [1–ä] and Section ñ.";
+
+        assert_eq!(
+            matched(text, &find_references(text)),
+            [
+                ("7", reference("7")),
+                ("§ 3", section("3")),
+                ("Section 4.2", section("4.2")),
+            ]
+        );
+
+        let text = "\u{3000}[3] Référence\n\u{3000}2.1 Überblick\nñ 3 Schluss";
+
+        assert_eq!(
+            matched(text, &find_targets(text)),
+            [("[3]", reference("3")), ("2.1", section("2.1"))]
+        );
+    }
+}
@@ -0,0 +1,355 @@
+//! Command line options of the application.
+//!
+//! Options are parsed by hand rather than via GApplication, so that they can
+//! be checked before starting the UI, e.g. for rendering pages headless, and
+//! parsed again by the primary instance from the forwarded arguments.
+
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use gtk::glib;
+use pdfium::Library;
+
+use crate::core::export::{self, POINTS_PER_INCH};
+use crate::core::textlayer;
+
+pub const USAGE: &str = "\
+Usage: papr [OPTION...] [FILE...]
+       papr --render-to OUTPUT [--page N] [--zoom X%] FILE
+       papr --export-text FORMAT OUTPUT FILE
+       papr --thumbnail [-s SIZE] INPUT OUTPUT
+
+Options:
+  --page N               Show page N, starting at one
+  --zoom MODE            Zoom to fit-width, fit-page, or a percentage, e.g. 150%
+  --fullscreen           Start in fullscreen mode
+  --render-to OUTPUT     Render pages to PNG files without starting the UI,
+                         numbering the files if rendering multiple pages
+  --export-text FORMAT OUTPUT
+                         Export the text layer as json or hocr without
+                         starting the UI, writing to standard output if
+                         OUTPUT is -
+  -h, --help             Show this help";
+
+/// Initial zoom of opened documents.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Zoom {
+    FitWidth,
+    FitPage,
+
+    /// Scale in pixels per PDF point, i.e. 1.0 for 100%.
+    Scale(f64),
+}
+
+/// Options given on the command line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Options {
+    /// Files or position links to open.
+    pub files: Vec<OsString>,
+
+    /// Index of the page to show, starting at zero.
+    pub page: Option<usize>,
+
+    pub zoom: Option<Zoom>,
+    pub fullscreen: bool,
+
+    /// File to render pages to instead of starting the UI.
+    pub render_to: Option<PathBuf>,
+
+    /// Format and file to export the text layer to instead of starting the
+    /// UI.
+    pub export_text: Option<(textlayer::Format, PathBuf)>,
+
+    pub help: bool,
+}
+
+/// Reason why the command line could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// An option that requires a value is missing one.
+    MissingValue(String),
+
+    /// The value given for an option is not valid.
+    InvalidValue(String, String),
+
+    /// The option is not known.
+    UnknownOption(String),
+}
+
+impl Zoom {
+    /// Parse a zoom mode, i.e. `fit-width`, `fit-page`, or a percentage.
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "fit-width" => Some(Self::FitWidth),
+            "fit-page" => Some(Self::FitPage),
+            _ => {
+                let percent: f64 = text.strip_suffix('%')?.trim().parse().ok()?;
+
+                (percent.is_finite() && percent > 0.0).then(|| Self::Scale(percent / 100.0))
+            }
+        }
+    }
+}
+
+impl Options {
+    /// Parse the given arguments, excluding the program name.
+    pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<Self, ParseError> {
+        let mut opts = Options::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let text = match arg.to_str() {
+                Some(text) if text.starts_with('-') && text != "-" => text,
+                _ => {
+                    opts.files.push(arg);
+                    continue;
+                }
+            };
+
+            // values may be given as separate argument or after '='
+            let (name, inline) = match text.split_once('=') {
+                Some((name, value)) => (name, Some(OsString::from(value))),
+                None => (text, None),
+            };
+            let name = name.to_owned();
+
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| ParseError::MissingValue(name.clone()))
+            };
+
+            match name.as_str() {
+                "--" => {
+                    opts.files.extend(args);
+                    break;
+                }
+                "--page" => {
+                    let value = value()?;
+                    let page = value.to_str().and_then(|v| v.parse::<usize>().ok());
+
+                    opts.page = match page {
+                        Some(page) if page > 0 => Some(page - 1),
+                        _ => return Err(ParseError::invalid(&name, &value)),
+                    };
+                }
+                "--zoom" => {
+                    let value = value()?;
+                    let zoom = value.to_str().and_then(Zoom::parse);
+
+                    opts.zoom = Some(zoom.ok_or_else(|| ParseError::invalid(&name, &value))?);
+                }
+                "--render-to" => opts.render_to = Some(value()?.into()),
+                "--export-text" => {
+                    let value = value()?;
+                    let format = value.to_str().and_then(textlayer::Format::parse);
+                    let format = format.ok_or_else(|| ParseError::invalid(&name, &value))?;
+
+                    let output = args.next().ok_or(ParseError::MissingValue(name))?;
+
+                    opts.export_text = Some((format, output.into()));
+                }
+                "--fullscreen" if inline.is_none() => opts.fullscreen = true,
+                "-h" | "--help" if inline.is_none() => opts.help = true,
+                _ => return Err(ParseError::UnknownOption(text.to_owned())),
+            }
+        }
+
+        Ok(opts)
+    }
+}
+
+impl ParseError {
+    fn invalid(name: &str, value: &OsString) -> Self {
+        Self::InvalidValue(name.to_owned(), value.to_string_lossy().into_owned())
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::MissingValue(name) => write!(f, "missing value for {name}"),
+            ParseError::InvalidValue(name, value) => write!(f, "invalid value for {name}: {value}"),
+            ParseError::UnknownOption(name) => write!(f, "unknown option {name}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Render pages of the single given file to PNG files without starting the
+/// UI, i.e. the given page or all pages, at the given zoom percentage or the
+/// default export resolution.
+pub fn run_render(opts: &Options, output: &Path) -> glib::ExitCode {
+    let input = match &opts.files[..] {
+        [input] => Path::new(input),
+        _ => {
+            eprintln!("Error: --render-to requires exactly one input file\n\n{USAGE}");
+            return glib::ExitCode::FAILURE;
+        }
+    };
+
+    let dpi = match opts.zoom {
+        None => export::DEFAULT_DPI,
+        Some(Zoom::Scale(scale)) => scale * POINTS_PER_INCH,
+        Some(_) => {
+            eprintln!("Error: only percentages are supported for --zoom when rendering");
+            return glib::ExitCode::FAILURE;
+        }
+    };
+
+    let doc = match Library::init().and_then(|lib| lib.load_file(input, None)) {
+        Ok(doc) => doc,
+        Err(err) => {
+            tracing::error!(file=?input, error=%err, "failed to load document");
+            return glib::ExitCode::FAILURE;
+        }
+    };
+
+    let count = doc.pages().count() as usize;
+    let pages: Vec<_> = match opts.page {
+        Some(page) if page < count => vec![page],
+        Some(page) => {
+            eprintln!(
+                "Error: page {} does not exist, the document has {count}",
+                page + 1
+            );
+            return glib::ExitCode::FAILURE;
+        }
+        None => (0..count).collect(),
+    };
+
+    match export::export_pages(&doc, &pages, dpi, output) {
+        Ok(n) => {
+            tracing::info!(file=?input, pages = n, "rendered pages");
+            glib::ExitCode::SUCCESS
+        }
+        Err(err) => {
+            tracing::error!(file=?input, error=%err, "failed to render pages");
+            glib::ExitCode::FAILURE
+        }
+    }
+}
+
+/// Export the text layer of the single given file without starting the UI,
+/// writing it to the given file or to standard output for `-`.
+pub fn run_export_text(opts: &Options, format: textlayer::Format, output: &Path) -> glib::ExitCode {
+    let input = match &opts.files[..] {
+        [input] => Path::new(input),
+        _ => {
+            eprintln!("Error: --export-text requires exactly one input file\n\n{USAGE}");
+            return glib::ExitCode::FAILURE;
+        }
+    };
+
+    let doc = match Library::init().and_then(|lib| lib.load_file(input, None)) {
+        Ok(doc) => doc,
+        Err(err) => {
+            tracing::error!(file=?input, error=%err, "failed to load document");
+            return glib::ExitCode::FAILURE;
+        }
+    };
+
+    let result = if output == Path::new("-") {
+        textlayer::export(&doc, format, &mut std::io::stdout().lock())
+    } else {
+        std::fs::File::create(output)
+            .map(std::io::BufWriter::new)
+            .and_then(|mut out| textlayer::export(&doc, format, &mut out))
+    };
+
+    match result {
+        Ok(()) => {
+            tracing::info!(file=?input, ?format, "text layer exported");
+            glib::ExitCode::SUCCESS
+        }
+        Err(err) => {
+            tracing::error!(file=?input, error=%err, "failed to export text layer");
+            glib::ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, ParseError> {
+        Options::parse(args.iter().map(OsString::from))
+    }
+
+    #[test]
+    fn options() {
+        let args = [
+            "a.pdf",
+            "--page",
+            "3",
+            "--zoom=150%",
+            "--fullscreen",
+            "b.pdf",
+        ];
+        let opts = parse(&args).unwrap();
+
+        assert_eq!(opts.files, ["a.pdf", "b.pdf"]);
+        assert_eq!(opts.page, Some(2));
+        assert_eq!(opts.zoom, Some(Zoom::Scale(1.5)));
+        assert!(opts.fullscreen);
+        assert_eq!(opts.render_to, None);
+
+        let args = [
+            "--render-to",
+            "out.png",
+            "--zoom",
+            "fit-page",
+            "--",
+            "-a.pdf",
+        ];
+        let opts = parse(&args).unwrap();
+
+        assert_eq!(opts.files, ["-a.pdf"]);
+        assert_eq!(opts.zoom, Some(Zoom::FitPage));
+        assert_eq!(opts.render_to, Some(PathBuf::from("out.png")));
+
+        let opts = parse(&["--export-text=hocr", "out.html", "a.pdf"]).unwrap();
+
+        assert_eq!(opts.files, ["a.pdf"]);
+        assert_eq!(
+            opts.export_text,
+            Some((textlayer::Format::Hocr, PathBuf::from("out.html")))
+        );
+
+        let opts = parse(&["--export-text", "json", "-", "a.pdf"]).unwrap();
+        assert_eq!(
+            opts.export_text,
+            Some((textlayer::Format::Json, PathBuf::from("-")))
+        );
+    }
+
+    #[test]
+    fn invalid_options() {
+        let err = |name: &str, value: &str| ParseError::InvalidValue(name.into(), value.into());
+        let unknown = |name: &str| ParseError::UnknownOption(name.into());
+
+        assert_eq!(parse(&["--page", "0"]), Err(err("--page", "0")));
+        assert_eq!(parse(&["--zoom", "150"]), Err(err("--zoom", "150")));
+        assert_eq!(parse(&["--zoom=-5%"]), Err(err("--zoom", "-5%")));
+        assert_eq!(
+            parse(&["--fullscreen=yes"]),
+            Err(unknown("--fullscreen=yes"))
+        );
+        assert_eq!(parse(&["--frobnicate"]), Err(unknown("--frobnicate")));
+
+        assert_eq!(
+            parse(&["--export-text", "pdf", "out"]),
+            Err(err("--export-text", "pdf"))
+        );
+
+        let missing = ParseError::MissingValue("--page".into());
+        assert_eq!(parse(&["--page"]), Err(missing));
+
+        let missing = ParseError::MissingValue("--export-text".into());
+        assert_eq!(parse(&["--export-text", "json"]), Err(missing));
+    }
+}
@@ -0,0 +1,125 @@
+//! Detection of the main text column of a document, i.e. the horizontal
+//! extent of the text body shared by most pages.
+//!
+//! This is used to zoom to the width of the text, excluding page margins. For
+//! multi-column layouts, the detected column spans all text columns.
+
+use pdfium::doc::{Document, TextPage};
+
+/// Maximum number of pages to sample, evenly distributed over the document.
+const MAX_SAMPLES: u32 = 16;
+
+/// Minimum number of pages with text required for a reliable detection.
+const MIN_SAMPLES: usize = 3;
+
+/// Minimum number of characters for a page to be considered.
+const MIN_CHARS: usize = 100;
+
+/// Fraction of characters at either side ignored as outliers, e.g. margin
+/// notes or line numbers.
+const OUTLIER_FRACTION: f64 = 0.01;
+
+/// Maximum median deviation of content widths, relative to the median width,
+/// for a detection to be considered reliable.
+const MAX_DEVIATION: f64 = 0.1;
+
+/// Horizontal extent of the text body, in page coordinates (PDF points).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextColumn {
+    /// Offset of the text body from the left edge of the page
+    pub left: f64,
+
+    /// Width of the text body
+    pub width: f64,
+}
+
+/// Detect the main text column of the document from the content bounds of
+/// sampled pages.
+///
+/// Returns `None` if there are not enough pages with text or if the content
+/// widths vary too much for the result to be meaningful.
+pub fn detect(doc: &Document) -> Option<TextColumn> {
+    let count = doc.pages().count();
+    let step = (count / MAX_SAMPLES).max(1);
+
+    let mut lefts = Vec::new();
+    let mut widths = Vec::new();
+
+    for index in (0..count).step_by(step as usize) {
+        let page = match doc.pages().get(index) {
+            Ok(page) => page,
+            Err(err) => {
+                tracing::warn!(page = index, error=%err, "failed to load page");
+                continue;
+            }
+        };
+
+        let text_page = match page.text() {
+            Ok(text_page) => text_page,
+            Err(err) => {
+                tracing::warn!(page = index, error=%err, "failed to load page text");
+                continue;
+            }
+        };
+
+        if let Some((left, right)) = content_bounds(&text_page) {
+            lefts.push(left);
+            widths.push(right - left);
+        }
+    }
+
+    if widths.len() < MIN_SAMPLES {
+        return None;
+    }
+
+    let width = median(&mut widths);
+    let left = median(&mut lefts);
+
+    // check that most pages agree on the width
+    let mut deviations: Vec<_> = widths.iter().map(|w| (w - width).abs()).collect();
+    if width <= 0.0 || median(&mut deviations) > width * MAX_DEVIATION {
+        return None;
+    }
+
+    Some(TextColumn { left, width })
+}
+
+/// Compute the horizontal content bounds of a page from its characters,
+/// ignoring outliers at either side.
+fn content_bounds(text_page: &TextPage) -> Option<(f64, f64)> {
+    let mut lefts = Vec::new();
+    let mut rights = Vec::new();
+
+    for index in 0..text_page.count() {
+        let r = match text_page.char_box(index) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
+
+        // skip generated or empty characters, e.g. spaces and line breaks
+        if r.right <= r.left {
+            continue;
+        }
+
+        lefts.push(r.left as f64);
+        rights.push(r.right as f64);
+    }
+
+    if lefts.len() < MIN_CHARS {
+        return None;
+    }
+
+    lefts.sort_by(f64::total_cmp);
+    rights.sort_by(f64::total_cmp);
+
+    let skip = (lefts.len() as f64 * OUTLIER_FRACTION) as usize;
+    let left = lefts[skip];
+    let right = rights[rights.len() - 1 - skip];
+
+    Some((left, right))
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    values[values.len() / 2]
+}
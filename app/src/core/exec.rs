@@ -0,0 +1,36 @@
+//! Background execution of blocking work, awaitable from the main loop.
+
+use executor::exec::priority::Priority;
+
+pub type Executor = executor::exec::priority::Executor<TaskPriority>;
+pub type JoinHandle<R> = executor::exec::future::JoinHandle<TaskPriority, R>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TaskPriority {
+    Low,
+    Normal,
+    High,
+}
+
+impl Priority for TaskPriority {
+    fn count() -> u8 {
+        3
+    }
+
+    fn from_value(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(TaskPriority::Low),
+            1 => Some(TaskPriority::Normal),
+            2 => Some(TaskPriority::High),
+            _ => None,
+        }
+    }
+
+    fn as_value(&self) -> u8 {
+        match self {
+            TaskPriority::Low => 0,
+            TaskPriority::Normal => 1,
+            TaskPriority::High => 2,
+        }
+    }
+}
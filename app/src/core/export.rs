@@ -0,0 +1,167 @@
+//! Export of pages as image files, e.g. for using them in other documents.
+//!
+//! Only PNG is supported for now. Vector formats such as SVG would require a
+//! vector render backend, which pdfium does not provide.
+
+use std::path::{Path, PathBuf};
+
+use nalgebra::{point, vector};
+
+use pdfium::bitmap::Color;
+use pdfium::doc::{Document, PageRotation, RenderFlags};
+
+use crate::core::render::pdfium::{render_page_rect, RenderOptions};
+use crate::core::render::preview;
+use crate::types::Rect;
+
+/// Default resolution of exported images, in dots per inch.
+pub const DEFAULT_DPI: f64 = 150.0;
+
+/// Minimum resolution of exported images, in dots per inch.
+pub const MIN_DPI: f64 = 18.0;
+
+/// Maximum resolution of exported images, in dots per inch. Beyond this,
+/// images of large pages quickly exceed what can be rendered in memory.
+pub const MAX_DPI: f64 = 600.0;
+
+/// PDF points per inch.
+pub const POINTS_PER_INCH: f64 = 72.0;
+
+/// Reason why pages could not be exported.
+#[derive(Debug)]
+pub enum ExportError {
+    /// The page could not be rendered.
+    Render { page: usize, err: pdfium::Error },
+
+    /// The image file could not be written.
+    Save {
+        path: PathBuf,
+        err: gtk::glib::BoolError,
+    },
+}
+
+/// Parse a selection of pages such as `1-3, 5`, given by page numbers
+/// starting at one, into page indices. Ranges may be open ended, e.g. `7-`
+/// for all pages from the seventh.
+///
+/// Returns `None` if the selection is empty, malformed, or refers to pages
+/// beyond the given page count.
+pub fn parse_pages(text: &str, count: usize) -> Option<Vec<usize>> {
+    let mut pages = Vec::new();
+
+    for part in text.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => {
+                let first = match first.trim() {
+                    "" => 1,
+                    first => first.parse().ok()?,
+                };
+                let last = match last.trim() {
+                    "" => count,
+                    last => last.parse().ok()?,
+                };
+                (first, last)
+            }
+            None => {
+                let page = part.parse().ok()?;
+                (page, page)
+            }
+        };
+
+        if first == 0 || first > last || last > count {
+            return None;
+        }
+
+        for page in first - 1..last {
+            if !pages.contains(&page) {
+                pages.push(page);
+            }
+        }
+    }
+
+    (!pages.is_empty()).then_some(pages)
+}
+
+/// Path of the image for the given page, numbering the file chosen by the
+/// user if multiple pages are exported.
+pub fn page_path(path: &Path, page: usize, multiple: bool) -> PathBuf {
+    if !multiple {
+        return path.to_owned();
+    }
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path.extension().map(|e| e.to_string_lossy());
+
+    let name = match ext {
+        Some(ext) => format!("{stem}-{}.{ext}", page + 1),
+        None => format!("{stem}-{}", page + 1),
+    };
+
+    path.with_file_name(name)
+}
+
+/// Render the given pages at the given resolution and save them as PNG
+/// files. Returns the number of pages exported.
+///
+/// This is blocking and should be run on the executor.
+pub fn export_pages(
+    doc: &Document,
+    pages: &[usize],
+    dpi: f64,
+    path: &Path,
+) -> Result<usize, ExportError> {
+    let scale = dpi.clamp(MIN_DPI, MAX_DPI) / POINTS_PER_INCH;
+
+    let opts = RenderOptions {
+        flags: RenderFlags::Annotations,
+        background: Color::WHITE,
+        color_scheme: None,
+        rotation: PageRotation::None,
+    };
+
+    for &page in pages {
+        let render = || {
+            let page = doc.pages().get(page as _)?;
+
+            let size = vector![page.width() as f64, page.height() as f64] * scale;
+            let size = vector![
+                (size.x.round() as i64).max(1),
+                (size.y.round() as i64).max(1)
+            ];
+
+            let rect = Rect::new(point![0, 0], size);
+            render_page_rect(&page, &size, &rect, &opts)
+        };
+
+        let bmp = render().map_err(|err| ExportError::Render { page, err })?;
+
+        let target = page_path(path, page, pages.len() > 1);
+        if let Err(err) = preview::save_png(bmp, &target) {
+            return Err(ExportError::Save { path: target, err });
+        }
+    }
+
+    Ok(pages.len())
+}
+
+impl std::fmt::Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::Render { page, err } => {
+                write!(f, "failed to render page {}: {err}", page + 1)
+            }
+            ExportError::Save { path, err } => {
+                write!(f, "failed to save \"{}\": {err}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExportError::Render { err, .. } => Some(err),
+            ExportError::Save { err, .. } => Some(err),
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! Kinetic scrolling ("flings"): continuing released movements with
+//! deceleration, and predicting where kinetic scrolling comes to rest, e.g.
+//! for rendering the content there ahead of time.
+
+use nalgebra::{Point2, Vector2};
+
+/// Minimum speed for considering movement a fling, in pixels per second.
+const MIN_VELOCITY: f64 = 500.0;
+
+/// Maximum time between two samples of a continuous movement, in
+/// microseconds. Longer gaps reset the prediction.
+const MAX_SAMPLE_GAP_US: i64 = 100_000;
+
+/// Range of accepted friction coefficients, in 1/s. Kinetic scrolling in GTK
+/// decelerates with a friction of 4.
+const FRICTION_RANGE: (f64, f64) = (1.0, 10.0);
+
+/// Weight of a new friction estimate relative to the previous ones.
+const FRICTION_SMOOTHING: f64 = 0.5;
+
+/// Friction of kinetic panning, in 1/s, matching kinetic scrolling in GTK.
+const PAN_FRICTION: f64 = 4.0;
+
+/// Minimum speed for continuing released movements, in pixels per second.
+const PAN_MIN_VELOCITY: f64 = 100.0;
+
+/// Speed at which kinetic panning stops, in pixels per second.
+const PAN_STOP_VELOCITY: f64 = 10.0;
+
+/// Weight of a new velocity sample relative to the previous ones.
+const VELOCITY_SMOOTHING: f64 = 0.6;
+
+/// Predicts the resting position of a decelerating viewport from its offsets
+/// over time.
+///
+/// Kinetic scrolling decelerates exponentially, i.e. the velocity decays with
+/// `v(t) = v0 * exp(-k * t)` for some friction `k`. The remaining distance is
+/// therefore `v / k`, with `k` being estimated from how fast the velocity
+/// decays between samples.
+#[derive(Debug, Clone, Default)]
+pub struct FlingPredictor {
+    offset: Option<(i64, Point2<f64>)>,
+    velocity: Option<(i64, Vector2<f64>)>,
+    friction: Option<f64>,
+}
+
+/// Tracks the velocity of a movement, e.g. of touch points dragging the
+/// viewport, for continuing it once released.
+#[derive(Debug, Clone, Default)]
+pub struct VelocityTracker {
+    position: Option<(i64, Point2<f64>)>,
+    velocity: Vector2<f64>,
+}
+
+/// Movement continued after being released, decelerating exponentially with
+/// the same friction as kinetic scrolling in GTK.
+#[derive(Debug, Clone, Copy)]
+pub struct KineticPan {
+    velocity: Vector2<f64>,
+    time: Option<i64>,
+}
+
+impl VelocityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the position at the given time, in microseconds.
+    pub fn sample(&mut self, time: i64, position: Point2<f64>) {
+        let (t_prev, position_prev) = match self.position.replace((time, position)) {
+            Some(sample) => sample,
+            None => return,
+        };
+
+        // the movement has been interrupted, start over
+        if time - t_prev > MAX_SAMPLE_GAP_US {
+            self.velocity = Vector2::zeros();
+            return;
+        }
+
+        // multiple samples at the same time, e.g. for multiple touch points
+        if time <= t_prev {
+            self.position = Some((t_prev, position_prev));
+            return;
+        }
+
+        let dt = (time - t_prev) as f64 / 1e6;
+        let velocity = (position - position_prev) / dt;
+
+        self.velocity += (velocity - self.velocity) * VELOCITY_SMOOTHING;
+    }
+
+    /// Velocity of the movement at the given time, in pixels per second.
+    /// Movements that have come to a halt before have no velocity.
+    pub fn velocity(&self, time: i64) -> Vector2<f64> {
+        match self.position {
+            Some((t, _)) if time - t <= MAX_SAMPLE_GAP_US => self.velocity,
+            _ => Vector2::zeros(),
+        }
+    }
+}
+
+impl KineticPan {
+    /// Continue a movement with the given velocity, in pixels per second, if
+    /// it has been fast enough.
+    pub fn new(velocity: Vector2<f64>) -> Option<Self> {
+        (velocity.norm() >= PAN_MIN_VELOCITY).then_some(Self {
+            velocity,
+            time: None,
+        })
+    }
+
+    /// Advance to the given frame time, in microseconds, and return the
+    /// distance moved since the previous frame. Returns `None` once the
+    /// movement has stopped.
+    pub fn step(&mut self, time: i64) -> Option<Vector2<f64>> {
+        if self.velocity.norm() < PAN_STOP_VELOCITY {
+            return None;
+        }
+
+        let t_prev = match self.time.replace(time) {
+            Some(t) => t,
+            None => return Some(Vector2::zeros()),
+        };
+
+        // integrate the exponentially decaying velocity over the frame
+        let dt = (time - t_prev).max(0) as f64 / 1e6;
+        let decay = (-PAN_FRICTION * dt).exp();
+
+        let distance = self.velocity * (1.0 - decay) / PAN_FRICTION;
+        self.velocity *= decay;
+
+        Some(distance)
+    }
+
+    /// Stop the movement along the given axes, e.g. when reaching the bounds
+    /// of the content.
+    pub fn halt(&mut self, x: bool, y: bool) {
+        if x {
+            self.velocity.x = 0.0;
+        }
+        if y {
+            self.velocity.y = 0.0;
+        }
+    }
+}
+
+impl FlingPredictor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the viewport offset at the given frame time (in microseconds)
+    /// and return the predicted resting offset if the viewport is currently
+    /// decelerating fast enough to be considered a fling.
+    pub fn sample(&mut self, time: i64, offset: Point2<f64>) -> Option<Point2<f64>> {
+        let (t_prev, offset_prev) = match self.offset {
+            // multiple samples in the same frame: nothing changed
+            Some((t, _)) if t == time => return None,
+            Some(sample) => sample,
+            None => {
+                self.offset = Some((time, offset));
+                return None;
+            }
+        };
+
+        self.offset = Some((time, offset));
+
+        // the movement has been interrupted, start over
+        if time - t_prev > MAX_SAMPLE_GAP_US {
+            self.velocity = None;
+            self.friction = None;
+            return None;
+        }
+
+        let dt = (time - t_prev) as f64 / 1e6;
+        let velocity = (offset - offset_prev) / dt;
+
+        let (tv_prev, velocity_prev) = self.velocity.replace((time, velocity))?;
+
+        // only predict while decelerating in the same direction
+        let speed = velocity.norm();
+        let speed_prev = velocity_prev.norm();
+
+        if speed < MIN_VELOCITY || speed >= speed_prev || velocity.dot(&velocity_prev) <= 0.0 {
+            self.friction = None;
+            return None;
+        }
+
+        // estimate friction from the decay of the velocity
+        let dt = (time - tv_prev) as f64 / 1e6;
+        let friction = ((speed_prev / speed).ln() / dt).clamp(FRICTION_RANGE.0, FRICTION_RANGE.1);
+
+        let friction = match self.friction {
+            Some(k) => k + (friction - k) * FRICTION_SMOOTHING,
+            None => friction,
+        };
+        self.friction = Some(friction);
+
+        Some(offset + velocity / friction)
+    }
+
+    /// Check if the viewport has stopped moving as of the last sample, or if
+    /// there has not been any movement yet.
+    pub fn is_stopped(&self) -> bool {
+        match self.velocity {
+            Some((_, v)) => v.norm() < 1.0,
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use nalgebra::{point, vector};
+
+    #[test]
+    fn velocity_tracker() {
+        let mut tracker = VelocityTracker::new();
+        assert_eq!(tracker.velocity(0), Vector2::zeros());
+
+        // steady movement of 10 px per 10 ms
+        for i in 0..10 {
+            tracker.sample(i * 10_000, point![i as f64 * 10.0, 0.0]);
+        }
+
+        let velocity = tracker.velocity(90_000);
+        assert!((velocity.x - 1000.0).abs() < 1.0 && velocity.y.abs() < 1e-6);
+
+        // holding still before releasing stops the movement
+        assert_eq!(tracker.velocity(300_000), Vector2::zeros());
+    }
+
+    #[test]
+    fn kinetic_pan() {
+        assert!(KineticPan::new(vector![PAN_MIN_VELOCITY / 2.0, 0.0]).is_none());
+
+        let mut pan = KineticPan::new(vector![0.0, 1000.0]).unwrap();
+        let mut distance = Vector2::zeros();
+        let mut time = 0;
+
+        while let Some(step) = pan.step(time) {
+            distance += step;
+            time += 16_000;
+        }
+
+        // comes to rest close to v / k, within a few seconds
+        let expected = (1000.0 - PAN_STOP_VELOCITY) / PAN_FRICTION;
+        assert!((distance.y - expected).abs() < 1.0 && distance.x == 0.0);
+        assert!(time < 2_000_000);
+    }
+}
@@ -0,0 +1,52 @@
+//! Fonts of a page that are not embedded in the document and are therefore
+//! substituted by a system font when rendering. Substituted fonts are the most
+//! common reason for a document looking different than in other viewers.
+
+use std::collections::BTreeSet;
+
+use nalgebra::{point, vector};
+use pdfium::doc::Document;
+
+use crate::types::Rect;
+
+/// Text drawn with substituted fonts on a page.
+#[derive(Debug, Clone, Default)]
+pub struct FontReport {
+    /// Names of the fonts used by the page that are not embedded, sorted.
+    pub substituted: Vec<String>,
+
+    /// Bounds of text drawn with these fonts, in page coordinates (PDF points).
+    pub regions: Vec<Rect<f64>>,
+}
+
+impl FontReport {
+    /// Inspect the fonts of the given page.
+    pub fn analyze(doc: &Document, index: usize) -> pdfium::Result<Self> {
+        let page = doc.pages().get(index as _)?;
+        let height = page.height() as f64;
+
+        let mut substituted = BTreeSet::new();
+        let mut regions = Vec::new();
+
+        for obj in page.text_objects()? {
+            if obj.font.is_embedded {
+                continue;
+            }
+
+            // PDF coordinates have their origin at the bottom left corner
+            let r = obj.bounds;
+            let rect = Rect::new(
+                point![r.left as f64, height - r.top as f64],
+                vector![(r.right - r.left) as f64, (r.top - r.bottom) as f64],
+            );
+
+            substituted.insert(obj.font.name);
+            regions.push(rect);
+        }
+
+        Ok(Self {
+            substituted: substituted.into_iter().collect(),
+            regions,
+        })
+    }
+}
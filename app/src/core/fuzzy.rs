@@ -0,0 +1,63 @@
+//! Fuzzy string matching, e.g. for filtering lists by user input.
+
+/// Bonus for a match directly following the previous one.
+const BONUS_CONSECUTIVE: i64 = 8;
+
+/// Bonus for a match at the start of a word.
+const BONUS_WORD_START: i64 = 10;
+
+/// Penalty per skipped character between two matches.
+const PENALTY_GAP: i64 = 1;
+
+/// Match the given pattern against a text.
+///
+/// Returns `None` if not all characters of the pattern occur in the text in
+/// the same order (ignoring case and whitespace in the pattern), otherwise a
+/// score where higher values indicate better matches. An empty pattern matches
+/// everything with a score of zero.
+pub fn score(pattern: &str, text: &str) -> Option<i64> {
+    let mut pattern = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .peekable();
+
+    let mut score = 0;
+    let mut prev: Option<char> = None;
+    let mut last_match: Option<usize> = None;
+
+    for (i, c) in text.chars().enumerate() {
+        let p = match pattern.peek() {
+            Some(p) => *p,
+            None => break,
+        };
+
+        if c.to_lowercase().eq(std::iter::once(p)) {
+            score += 1;
+
+            // favor matches at the start of words
+            if prev.map(|c| !c.is_alphanumeric()).unwrap_or(true) {
+                score += BONUS_WORD_START;
+            }
+
+            // favor consecutive matches, penalize gaps
+            match last_match {
+                Some(j) if j + 1 == i => score += BONUS_CONSECUTIVE,
+                Some(j) => score -= (i - j - 1) as i64 * PENALTY_GAP,
+                None => {}
+            }
+
+            last_match = Some(i);
+            pattern.next();
+        }
+
+        prev = Some(c);
+    }
+
+    // fail if there are unmatched pattern characters left
+    if pattern.peek().is_some() {
+        return None;
+    }
+
+    Some(score)
+}
@@ -0,0 +1,93 @@
+//! Link hints, i.e. short labels for following links via keyboard.
+//!
+//! All labels have the same length, so no label is the prefix of another one
+//! and a link can be followed as soon as its label has been typed.
+
+/// Characters labels are made of, on the home row of most keyboard layouts.
+const ALPHABET: &[char] = &['a', 's', 'd', 'f', 'g', 'h', 'j', 'k', 'l'];
+
+/// Labeled targets and the characters typed so far.
+#[derive(Debug, Clone)]
+pub struct LinkHints<T> {
+    hints: Vec<(String, T)>,
+    typed: String,
+}
+
+/// Result of typing a character while link hints are shown.
+#[derive(Debug, Clone)]
+pub enum HintInput<T> {
+    /// The characters typed so far start at least one label.
+    Pending,
+
+    /// The characters typed so far are the label of the given target.
+    Matched(T),
+
+    /// No label starts with the characters typed so far.
+    Mismatch,
+}
+
+impl<T: Clone> LinkHints<T> {
+    /// Label the given targets, in order.
+    pub fn new(targets: Vec<T>) -> Self {
+        let hints = labels(targets.len()).into_iter().zip(targets).collect();
+
+        Self {
+            hints,
+            typed: String::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hints.is_empty()
+    }
+
+    /// The characters typed so far.
+    pub fn typed(&self) -> &str {
+        &self.typed
+    }
+
+    /// Targets with labels starting with the characters typed so far, and
+    /// their labels.
+    pub fn matching(&self) -> impl Iterator<Item = (&str, &T)> + '_ {
+        self.hints
+            .iter()
+            .filter(|(label, _)| label.starts_with(&self.typed))
+            .map(|(label, target)| (label.as_str(), target))
+    }
+
+    /// Type the given character, ignoring case.
+    pub fn input(&mut self, c: char) -> HintInput<T> {
+        self.typed.extend(c.to_lowercase());
+
+        let mut matching = self.matching();
+
+        match matching.next() {
+            Some((label, target)) if label == self.typed => HintInput::Matched(target.clone()),
+            Some(_) => HintInput::Pending,
+            None => HintInput::Mismatch,
+        }
+    }
+}
+
+/// Generate the given number of labels of equal, minimal length.
+fn labels(n: usize) -> Vec<String> {
+    let base = ALPHABET.len();
+
+    let mut len = 1;
+    while base.pow(len) < n {
+        len += 1;
+    }
+
+    (0..n)
+        .map(|mut i| {
+            let mut label = vec![ALPHABET[0]; len as usize];
+
+            for c in label.iter_mut().rev() {
+                *c = ALPHABET[i % base];
+                i /= base;
+            }
+
+            label.into_iter().collect()
+        })
+        .collect()
+}
@@ -0,0 +1,64 @@
+//! Back/forward navigation history.
+
+/// Maximum number of entries kept for going back.
+const MAX_ENTRIES: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct History<T> {
+    back: Vec<T>,
+    forward: Vec<T>,
+}
+
+impl<T> History<T> {
+    pub fn new() -> Self {
+        Self {
+            back: Vec::new(),
+            forward: Vec::new(),
+        }
+    }
+
+    /// Record the current location before navigating away from it.
+    pub fn push(&mut self, current: T) {
+        if self.back.len() >= MAX_ENTRIES {
+            self.back.remove(0);
+        }
+
+        self.back.push(current);
+        self.forward.clear();
+    }
+
+    /// Go back to the previous location, storing the current one for going
+    /// forward again.
+    pub fn back(&mut self, current: T) -> Option<T> {
+        let previous = self.back.pop()?;
+        self.forward.push(current);
+        Some(previous)
+    }
+
+    /// Go forward to the next location, storing the current one for going
+    /// back again.
+    pub fn forward(&mut self, current: T) -> Option<T> {
+        let next = self.forward.pop()?;
+        self.back.push(current);
+        Some(next)
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.back.clear();
+        self.forward.clear();
+    }
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,139 @@
+//! Freehand strokes drawn on pages with the pen tool.
+//!
+//! Strokes are collected in page coordinates while drawing and written to the
+//! document as ink annotations once finished.
+
+use nalgebra::{point, vector, Point2};
+
+use pdfium::bitmap::Color;
+use pdfium::doc::{AnnotationSubtype, Page};
+use pdfium::types as pdf;
+
+use crate::types::{Bounds, Rect};
+
+/// Minimum distance between consecutive points of a stroke, in PDF points.
+/// Closer points are dropped to keep strokes small.
+const MIN_DISTANCE: f64 = 0.5;
+
+/// Color and width of strokes drawn with the pen tool.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pen {
+    pub color: Color,
+
+    /// Stroke width, in PDF points.
+    pub width: f64,
+}
+
+/// A stroke drawn on a page.
+#[derive(Debug, Clone)]
+pub struct Stroke {
+    pub page: usize,
+    pub pen: Pen,
+
+    /// Points of the stroke, in page coordinates (PDF points).
+    pub points: Vec<Point2<f64>>,
+}
+
+impl Default for Pen {
+    fn default() -> Self {
+        Self {
+            color: Color::new_rgb(0x1c, 0x71, 0xd8),
+            width: 2.0,
+        }
+    }
+}
+
+impl Stroke {
+    pub fn new(page: usize, pen: Pen, start: Point2<f64>) -> Self {
+        Self {
+            page,
+            pen,
+            points: vec![start],
+        }
+    }
+
+    /// Extend the stroke to the given point, unless it is too close to the
+    /// last one. Returns `true` if the point has been added.
+    pub fn push(&mut self, point: Point2<f64>) -> bool {
+        if let Some(last) = self.points.last() {
+            if (point - last).norm() < MIN_DISTANCE {
+                return false;
+            }
+        }
+
+        self.points.push(point);
+        true
+    }
+
+    /// Check if the stroke has any visible extent, i.e. more than one point.
+    pub fn is_visible(&self) -> bool {
+        self.points.len() > 1
+    }
+
+    /// Bounds of the stroke including its width, in page coordinates.
+    pub fn bounds(&self) -> Rect<f64> {
+        let half = self.pen.width / 2.0;
+
+        let bounds = self.points.iter().fold(
+            Bounds {
+                x_min: f64::INFINITY,
+                x_max: f64::NEG_INFINITY,
+                y_min: f64::INFINITY,
+                y_max: f64::NEG_INFINITY,
+            },
+            |b, p| Bounds {
+                x_min: b.x_min.min(p.x),
+                x_max: b.x_max.max(p.x),
+                y_min: b.y_min.min(p.y),
+                y_max: b.y_max.max(p.y),
+            },
+        );
+
+        Rect::new(
+            point![bounds.x_min - half, bounds.y_min - half],
+            vector![bounds.x_max - bounds.x_min, bounds.y_max - bounds.y_min]
+                + vector![half, half] * 2.0,
+        )
+    }
+
+    /// Add the stroke to the given page as ink annotation. The page height is
+    /// required to transform page coordinates to PDF coordinates.
+    pub fn write(&self, page: &Page, height: f64) -> pdfium::Result<()> {
+        // PDF coordinates have their origin at the bottom left corner
+        let points: Vec<_> = self
+            .points
+            .iter()
+            .map(|p| pdf::Point2::new(p.x as f32, (height - p.y) as f32))
+            .collect();
+
+        let bounds = self.bounds();
+        let rect = pdf::Rect {
+            left: bounds.offs.x as f32,
+            top: (height - bounds.offs.y) as f32,
+            right: (bounds.offs.x + bounds.size.x) as f32,
+            bottom: (height - bounds.offs.y - bounds.size.y) as f32,
+        };
+
+        let annot = page.create_annotation(AnnotationSubtype::Ink)?;
+
+        // the ink list is what other viewers use, the path is what pdfium
+        // renders
+        let result = annot
+            .set_rect(&rect)
+            .and_then(|_| annot.set_color(self.pen.color))
+            .and_then(|_| annot.add_ink_stroke(&points))
+            .and_then(|_| annot.append_path(&points, self.pen.color, self.pen.width as _));
+
+        drop(annot);
+
+        // don't leave incomplete annotations behind
+        if result.is_err() {
+            let count = page.annotation_count();
+            if count > 0 {
+                page.remove_annotation(count - 1)?;
+            }
+        }
+
+        result
+    }
+}
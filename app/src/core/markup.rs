@@ -0,0 +1,83 @@
+//! Text markup annotations, i.e. highlights, underlines, and strike-outs of
+//! selected text.
+
+use pdfium::bitmap::Color;
+use pdfium::doc::{AnnotationSubtype, Page};
+use pdfium::types as pdf;
+
+use crate::types::Rect;
+
+/// Kind of markup applied to text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkupKind {
+    Highlight,
+    Underline,
+    StrikeOut,
+}
+
+impl MarkupKind {
+    fn subtype(self) -> AnnotationSubtype {
+        match self {
+            Self::Highlight => AnnotationSubtype::Highlight,
+            Self::Underline => AnnotationSubtype::Underline,
+            Self::StrikeOut => AnnotationSubtype::StrikeOut,
+        }
+    }
+}
+
+/// Add a markup annotation covering the given lines of text, each given by
+/// its bounds in page coordinates, to the given page. The page height is
+/// required to transform page coordinates to PDF coordinates.
+pub fn write(
+    page: &Page,
+    height: f64,
+    kind: MarkupKind,
+    color: Color,
+    lines: &[Rect<f64>],
+) -> pdfium::Result<()> {
+    if lines.is_empty() {
+        return Err(pdfium::Error::InvalidArgument);
+    }
+
+    // PDF coordinates have their origin at the bottom left corner
+    let lines: Vec<_> = lines
+        .iter()
+        .map(|r| pdf::Rect {
+            left: r.offs.x as f32,
+            top: (height - r.offs.y) as f32,
+            right: (r.offs.x + r.size.x) as f32,
+            bottom: (height - r.offs.y - r.size.y) as f32,
+        })
+        .collect();
+
+    let bounds = lines.iter().skip(1).fold(lines[0], |b, r| pdf::Rect {
+        left: b.left.min(r.left),
+        top: b.top.max(r.top),
+        right: b.right.max(r.right),
+        bottom: b.bottom.min(r.bottom),
+    });
+
+    let annot = page.create_annotation(kind.subtype())?;
+
+    // pdfium generates the appearance from the attachment points and color
+    let result = annot
+        .set_rect(&bounds)
+        .and_then(|_| annot.set_color(color))
+        .and_then(|_| {
+            lines
+                .iter()
+                .try_for_each(|r| annot.append_attachment_points(&pdf::Quad::from(r)))
+        });
+
+    drop(annot);
+
+    // don't leave incomplete annotations behind
+    if result.is_err() {
+        let count = page.annotation_count();
+        if count > 0 {
+            page.remove_annotation(count - 1)?;
+        }
+    }
+
+    result
+}
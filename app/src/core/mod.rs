@@ -1 +1,38 @@
+pub mod accessible;
+pub mod appearance;
+pub mod blocks;
+pub mod caret;
+pub mod citations;
+pub mod cli;
+pub mod columns;
+pub mod exec;
+pub mod export;
+pub mod fling;
+pub mod fonts;
+pub mod fuzzy;
+pub mod hints;
+pub mod history;
+pub mod ink;
+pub mod markup;
+pub mod pin;
+pub mod position;
+pub mod power;
+pub mod progress;
+pub mod recovery;
+pub mod reflow;
 pub mod render;
+pub mod search;
+pub mod selection;
+pub mod session;
+pub mod signatures;
+pub mod stamps;
+pub mod stats;
+pub mod storage;
+pub mod textlayer;
+pub mod thumbnails;
+pub mod timeline;
+pub mod trim;
+pub mod trust;
+pub mod view;
+pub mod watch;
+pub mod xfdf;
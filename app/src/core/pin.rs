@@ -0,0 +1,205 @@
+use crate::types::Rect;
+
+/// A pinned region on a document page.
+///
+/// Pins are re-resolved whenever the document they refer to is loaded again,
+/// e.g. after it has been regenerated by an external tool. Resolution prefers
+/// the named destination preceding the pinned page, as destinations (e.g.
+/// chapter anchors) move along with the content, then the page label, as
+/// labels tend to stay stable when pages are inserted before the pinned one,
+/// and falls back to the page index otherwise.
+#[derive(Debug, Clone)]
+pub struct Pin {
+    /// Index of the pinned page.
+    pub page: usize,
+
+    /// Label of the pinned page, if the document provides one.
+    pub label: Option<String>,
+
+    /// Named destination at or before the pinned page, if any.
+    pub anchor: Option<Anchor>,
+
+    /// Pinned region in page coordinates (PDF points).
+    pub rect: Rect<f64>,
+
+    /// Number of pages of the document at the time the pin was created.
+    pub page_count: usize,
+}
+
+/// A named destination a pin is anchored to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anchor {
+    /// Name of the destination.
+    pub name: String,
+
+    /// Number of pages between the destination and the pinned page.
+    pub offset: usize,
+}
+
+/// A pin resolved against a (re-)loaded document.
+#[derive(Debug, Clone)]
+pub struct ResolvedPin {
+    /// Index of the page the pin has been resolved to.
+    pub page: usize,
+
+    /// Pinned region in page coordinates (PDF points).
+    pub rect: Rect<f64>,
+
+    /// Change in the number of pages since the pin has been created.
+    pub page_count_delta: i64,
+}
+
+impl Anchor {
+    /// Find the named destination closest to the given page, looking only at
+    /// destinations on or before it. Destinations are given as pairs of name
+    /// and page index.
+    pub fn find(destinations: &[(String, usize)], page: usize) -> Option<Self> {
+        destinations
+            .iter()
+            .filter(|(_, p)| *p <= page)
+            .max_by(|(name_a, a), (name_b, b)| a.cmp(b).then(name_b.cmp(name_a)))
+            .map(|(name, p)| Self {
+                name: name.clone(),
+                offset: page - p,
+            })
+    }
+}
+
+impl Pin {
+    /// Resolve this pin against the page labels and named destinations of a
+    /// document.
+    ///
+    /// Returns `None` if the document does not have any pages.
+    pub fn resolve(
+        &self,
+        labels: &[Option<String>],
+        destinations: &[(String, usize)],
+    ) -> Option<ResolvedPin> {
+        if labels.is_empty() {
+            return None;
+        }
+
+        let last = labels.len() - 1;
+
+        // Look up the page relative to the destination it is anchored to.
+        let by_anchor = self.anchor.as_ref().and_then(|anchor| {
+            destinations
+                .iter()
+                .find(|(name, _)| *name == anchor.name)
+                .map(|(_, page)| (page + anchor.offset).min(last))
+        });
+
+        // Look up the page by its label. Labels are not necessarily unique, so
+        // pick the matching page closest to the original index.
+        let by_label = || {
+            self.label.as_ref().and_then(|label| {
+                labels
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, l)| l.as_ref() == Some(label))
+                    .min_by_key(|(i, _)| i.abs_diff(self.page))
+                    .map(|(i, _)| i)
+            })
+        };
+
+        // Fall back to the page index, clamped to the new page range.
+        let page = by_anchor
+            .or_else(by_label)
+            .unwrap_or_else(|| self.page.min(last));
+
+        Some(ResolvedPin {
+            page,
+            rect: self.rect,
+            page_count_delta: labels.len() as i64 - self.page_count as i64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::{point, vector};
+
+    use super::*;
+
+    fn pin(page: usize, label: Option<&str>, anchor: Option<(&str, usize)>) -> Pin {
+        Pin {
+            page,
+            label: label.map(String::from),
+            anchor: anchor.map(|(name, offset)| Anchor {
+                name: name.into(),
+                offset,
+            }),
+            rect: Rect::new(point![10.0, 20.0], vector![100.0, 50.0]),
+            page_count: 10,
+        }
+    }
+
+    fn labels(labels: &[&str]) -> Vec<Option<String>> {
+        labels.iter().map(|l| Some(l.to_string())).collect()
+    }
+
+    fn dests(dests: &[(&str, usize)]) -> Vec<(String, usize)> {
+        dests.iter().map(|(n, p)| (n.to_string(), *p)).collect()
+    }
+
+    #[test]
+    fn anchor_is_closest_preceding_destination() {
+        let dests = dests(&[("intro", 0), ("ch1", 3), ("ch2", 7)]);
+
+        let anchor = Anchor::find(&dests, 5).unwrap();
+        assert_eq!((anchor.name.as_str(), anchor.offset), ("ch1", 2));
+
+        let anchor = Anchor::find(&dests, 7).unwrap();
+        assert_eq!((anchor.name.as_str(), anchor.offset), ("ch2", 0));
+
+        assert_eq!(Anchor::find(&dests[1..], 2), None);
+    }
+
+    #[test]
+    fn resolve_by_destination() {
+        let pin = pin(5, Some("v"), Some(("ch1", 2)));
+
+        // pages inserted before the chapter, labels shifted along
+        let labels = labels(&[
+            "i", "ii", "iii", "iv", "v", "vi", "vii", "viii", "ix", "x", "xi",
+        ]);
+        let dests = dests(&[("ch1", 4)]);
+
+        let resolved = pin.resolve(&labels, &dests).unwrap();
+        assert_eq!(resolved.page, 6);
+        assert_eq!(resolved.page_count_delta, 1);
+        assert_eq!(resolved.rect, pin.rect);
+
+        // clamp to the page range
+        let resolved = pin.resolve(&labels[..5], &dests).unwrap();
+        assert_eq!(resolved.page, 4);
+    }
+
+    #[test]
+    fn resolve_by_label() {
+        let pin = pin(5, Some("b"), Some(("gone", 2)));
+
+        // destination missing, pick the matching label closest to the page
+        let labels = labels(&["a", "b", "c", "d", "e", "f", "b", "g"]);
+
+        let resolved = pin.resolve(&labels, &dests(&[("other", 1)])).unwrap();
+        assert_eq!(resolved.page, 6);
+        assert_eq!(resolved.page_count_delta, -2);
+    }
+
+    #[test]
+    fn resolve_by_index() {
+        let pin = pin(5, Some("missing"), None);
+        let labels = vec![None; 8];
+
+        let resolved = pin.resolve(&labels, &[]).unwrap();
+        assert_eq!(resolved.page, 5);
+
+        // clamp to the page range
+        let resolved = pin.resolve(&labels[..3], &[]).unwrap();
+        assert_eq!(resolved.page, 2);
+
+        // nothing to resolve against
+        assert!(pin.resolve(&[], &[]).is_none());
+    }
+}
@@ -0,0 +1,81 @@
+//! Links to a position in a document, e.g. for referencing it from notes.
+//!
+//! Links use a custom URI scheme of the form
+//! `papr://open?file=/path/to/doc.pdf&page=12&x=…&y=…&zoom=…`, with the page
+//! number starting at one and the position given in page coordinates (PDF
+//! points).
+
+use std::path::PathBuf;
+
+use gtk::glib;
+use nalgebra::Point2;
+
+/// URI scheme of position links.
+pub const SCHEME: &str = "papr";
+
+/// A position in a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionLink {
+    pub file: PathBuf,
+
+    /// Index of the page, starting at zero.
+    pub page: usize,
+
+    /// Center of the view, in page coordinates (PDF points).
+    pub center: Point2<f64>,
+
+    /// Scale of the view, in pixels per PDF point.
+    pub zoom: f64,
+}
+
+impl PositionLink {
+    /// Parse a position link, returning `None` if the given URI is not a
+    /// (valid) position link.
+    pub fn parse(uri: &str) -> Option<Self> {
+        let uri = glib::Uri::parse(uri, glib::UriFlags::ENCODED_QUERY).ok()?;
+
+        if uri.scheme() != SCHEME || uri.host().as_deref() != Some("open") {
+            return None;
+        }
+
+        let mut file = None;
+        let mut page = None;
+        let (mut x, mut y) = (0.0, 0.0);
+        let mut zoom = None;
+
+        for param in uri.query()?.split('&') {
+            let (key, value) = param.split_once('=')?;
+            let value = glib::Uri::unescape_string(value, None)?;
+
+            match key {
+                "file" => file = Some(PathBuf::from(value.as_str())),
+                "page" => page = value.parse::<usize>().ok().filter(|p| *p > 0),
+                "x" => x = value.parse().ok()?,
+                "y" => y = value.parse().ok()?,
+                "zoom" => zoom = value.parse().ok().filter(|z: &f64| *z > 0.0),
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            file: file.filter(|f| f.is_absolute())?,
+            page: page.unwrap_or(1) - 1,
+            center: Point2::new(x, y),
+            zoom: zoom.unwrap_or(1.0),
+        })
+    }
+
+    /// The URI representing this position.
+    pub fn to_uri(&self) -> String {
+        let file = self.file.to_string_lossy();
+        let file = glib::Uri::escape_string(&file, Some("/"), true);
+
+        format!(
+            "{SCHEME}://open?file={file}&page={}&x={:.1}&y={:.1}&zoom={:.3}",
+            self.page + 1,
+            self.center.x,
+            self.center.y,
+            self.zoom,
+        )
+    }
+}
@@ -0,0 +1,85 @@
+//! Power state of the system, used to throttle background rendering while
+//! running on battery or with power saving enabled.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk::gio;
+use gtk::prelude::{DBusProxyExt, ObjectExt, PowerProfileMonitorExt};
+
+/// Whether the system should save power, i.e. is running on battery or has
+/// the power-saver profile enabled.
+#[derive(Debug, Clone)]
+pub struct PowerMonitor {
+    profile: gio::PowerProfileMonitor,
+    upower: Rc<RefCell<Option<gio::DBusProxy>>>,
+}
+
+impl PowerMonitor {
+    const NAME: &'static str = "org.freedesktop.UPower";
+    const PATH: &'static str = "/org/freedesktop/UPower";
+    const INTERFACE: &'static str = "org.freedesktop.UPower";
+
+    /// Start monitoring the power state, calling the given function whenever
+    /// it may have changed.
+    ///
+    /// The battery state is considered unknown, i.e. the system is assumed to
+    /// run on AC, if UPower is not available.
+    pub fn connect<F: Fn() + 'static>(f: F) -> Self {
+        let f = Rc::new(f);
+
+        let profile = gio::PowerProfileMonitor::get_default();
+        let notify = f.clone();
+        profile.connect_power_saver_enabled_notify(move |_| notify());
+
+        let upower = Rc::new(RefCell::new(None));
+        let slot = upower.clone();
+
+        gio::DBusProxy::for_bus(
+            gio::BusType::System,
+            gio::DBusProxyFlags::DO_NOT_AUTO_START,
+            None,
+            Self::NAME,
+            Self::PATH,
+            Self::INTERFACE,
+            None::<&gio::Cancellable>,
+            move |result| {
+                let proxy = match result {
+                    Ok(proxy) => proxy,
+                    Err(err) => {
+                        tracing::debug!(error=%err, "battery state not available");
+                        return;
+                    }
+                };
+
+                let notify = f.clone();
+                proxy.connect_local("g-properties-changed", false, move |_| {
+                    notify();
+                    None
+                });
+
+                slot.replace(Some(proxy));
+                f();
+            },
+        );
+
+        Self { profile, upower }
+    }
+
+    pub fn is_power_saver_enabled(&self) -> bool {
+        self.profile.is_power_saver_enabled()
+    }
+
+    pub fn is_on_battery(&self) -> bool {
+        self.upower
+            .borrow()
+            .as_ref()
+            .and_then(|proxy| proxy.cached_property("OnBattery"))
+            .and_then(|value| value.get::<bool>())
+            .unwrap_or(false)
+    }
+
+    pub fn is_low_power(&self) -> bool {
+        self.is_power_saver_enabled() || self.is_on_battery()
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Reading progress tracker with estimation of the remaining reading time.
+///
+/// The reading speed is estimated from the scroll position over a sliding
+/// time window. Backwards movement and large jumps (e.g. navigating to a
+/// different chapter) restart the measurement.
+#[derive(Debug, Clone)]
+pub struct ReadingProgress {
+    samples: VecDeque<(Instant, f64)>,
+    max_step: f64,
+    progress: f64,
+    speed: Option<f64>,
+}
+
+impl ReadingProgress {
+    /// Time window over which the reading speed is measured.
+    const WINDOW: Duration = Duration::from_secs(5 * 60);
+
+    /// Minimum time span required for a measurement.
+    const MIN_SPAN: Duration = Duration::from_secs(30);
+
+    /// Maximum number of pages advanced in one step before the measurement
+    /// is restarted.
+    const MAX_PAGES_STEP: f64 = 3.0;
+
+    /// Create a new tracker for a document with the given number of pages,
+    /// optionally continuing from a previously saved progress and reading
+    /// speed.
+    pub fn new(page_count: usize, progress: Option<f64>, speed: Option<f64>) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            max_step: Self::MAX_PAGES_STEP / page_count.max(1) as f64,
+            progress: progress.unwrap_or(0.0).clamp(0.0, 1.0),
+            speed,
+        }
+    }
+
+    /// Fraction of the document that has been scrolled through.
+    pub fn progress(&self) -> f64 {
+        self.progress
+    }
+
+    /// Estimated reading speed, in document fractions per second.
+    pub fn speed(&self) -> Option<f64> {
+        self.speed
+    }
+
+    /// Estimated time remaining until the end of the document.
+    pub fn remaining(&self) -> Option<Duration> {
+        let speed = self.speed.filter(|s| *s > 0.0)?;
+        Some(Duration::from_secs_f64((1.0 - self.progress) / speed))
+    }
+
+    /// Update the current position.
+    pub fn update(&mut self, now: Instant, progress: f64) {
+        let progress = progress.clamp(0.0, 1.0);
+
+        // ignore updates without movement
+        if progress == self.progress && !self.samples.is_empty() {
+            return;
+        }
+
+        // restart measurement on backwards movement or large jumps
+        let step = progress - self.progress;
+        if step < 0.0 || step > self.max_step {
+            self.samples.clear();
+        }
+
+        self.progress = progress;
+        self.samples.push_back((now, progress));
+
+        // drop samples outside of the measurement window
+        while let Some((t, _)) = self.samples.front() {
+            if now.duration_since(*t) <= Self::WINDOW {
+                break;
+            }
+            self.samples.pop_front();
+        }
+
+        // estimate speed if we have enough data
+        let (t0, p0) = *self.samples.front().unwrap();
+        let span = now.duration_since(t0);
+
+        if span >= Self::MIN_SPAN && progress > p0 {
+            self.speed = Some((progress - p0) / span.as_secs_f64());
+        }
+    }
+}
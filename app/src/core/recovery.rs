@@ -0,0 +1,90 @@
+//! Recovery of open documents and their positions after a crash.
+//!
+//! While running, we keep a marker file in the recovery area together with a
+//! record of the position of each open document, updated as the user scrolls
+//! and zooms. Both are removed on a clean shutdown, so finding the marker on
+//! startup means that the previous run has not exited cleanly and that the
+//! record describes where the user has left off.
+
+use std::fs;
+use std::io;
+
+use super::position::PositionLink;
+use super::storage::{Area, Storage};
+
+/// Marker of a running instance.
+const MARKER_FILE: &str = "running";
+
+/// Positions of open documents, one position link per line.
+const POSITIONS_FILE: &str = "positions";
+
+/// Record of open documents for restoring them after a crash.
+#[derive(Debug, Clone)]
+pub struct RecoveryStore {
+    storage: Storage,
+}
+
+impl RecoveryStore {
+    pub fn new(storage: &Storage) -> Self {
+        Self {
+            storage: storage.clone(),
+        }
+    }
+
+    /// Mark the application as running. Returns the positions of the
+    /// documents that have been open when the previous run crashed, if it did.
+    pub fn start(&self) -> Vec<PositionLink> {
+        let crashed = self.storage.path(Area::Recovery, MARKER_FILE).exists();
+        let positions = if crashed { self.load() } else { Vec::new() };
+
+        if let Err(err) = self.storage.write(Area::Recovery, MARKER_FILE, &[]) {
+            tracing::warn!(error=%err, "failed to write crash marker");
+        }
+
+        if crashed {
+            let n = positions.len();
+            tracing::info!(documents = n, "previous run did not exit cleanly");
+        }
+
+        positions
+    }
+
+    /// Record the positions of all open documents, replacing the previous
+    /// record.
+    pub fn save(&self, positions: &[PositionLink]) -> io::Result<()> {
+        let data: String = positions
+            .iter()
+            .map(|pos| format!("{}\n", pos.to_uri()))
+            .collect();
+
+        self.storage
+            .write(Area::Recovery, POSITIONS_FILE, data.as_bytes())
+            .map(|_| ())
+    }
+
+    /// Mark a clean shutdown, i.e. remove the marker and the record.
+    pub fn finish(&self) {
+        for name in [POSITIONS_FILE, MARKER_FILE] {
+            let path = self.storage.path(Area::Recovery, name);
+
+            match fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => tracing::warn!(file=?path, error=%err, "failed to remove file"),
+            }
+        }
+    }
+
+    fn load(&self) -> Vec<PositionLink> {
+        let path = self.storage.path(Area::Recovery, POSITIONS_FILE);
+
+        match fs::read_to_string(&path) {
+            Ok(data) => data.lines().filter_map(PositionLink::parse).collect(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                tracing::warn!(file=?path, error=%err, "failed to load recovery record");
+                Vec::new()
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Reflowable text of documents for the reader mode.
+//!
+//! Pages are reduced to paragraphs of running text, taken from the text
+//! structure extracted by pdfium. Lines of a paragraph are joined so that the
+//! text can be wrapped to any width, undoing hyphenation at line breaks.
+
+use pdfium::doc::{Document, TextStructure};
+
+/// Extract the paragraphs of all pages, in reading order. Pages without text
+/// or that fail to load have no paragraphs.
+///
+/// This is blocking and loads the text of every page, so it should be run on
+/// the executor.
+pub fn document_text(doc: &Document) -> Vec<Vec<String>> {
+    let count = doc.pages().count();
+
+    (0..count)
+        .map(|index| match page_text(doc, index) {
+            Ok(paragraphs) => paragraphs,
+            Err(err) => {
+                tracing::warn!(page = index, error=%err, "failed to extract page text");
+                Vec::new()
+            }
+        })
+        .collect()
+}
+
+/// Extract the paragraphs of the given page.
+fn page_text(doc: &Document, index: u32) -> pdfium::Result<Vec<String>> {
+    let page = doc.pages().get(index)?;
+    let text = page.text()?;
+
+    Ok(paragraphs(&text.structure()?))
+}
+
+/// Reflow the paragraphs of the given text structure, skipping empty ones.
+fn paragraphs(text: &TextStructure) -> Vec<String> {
+    text.paragraphs
+        .iter()
+        .map(|para| {
+            let lines: Vec<_> = text.lines[para.lines.clone()]
+                .iter()
+                .map(|line| line.text())
+                .collect();
+
+            join_lines(&lines)
+        })
+        .filter(|para| !para.is_empty())
+        .collect()
+}
+
+/// Join the lines of a paragraph into a single line. Words hyphenated at the
+/// end of a line are joined without the hyphen if the next line continues in
+/// lower case, other lines are separated by spaces.
+fn join_lines<S: AsRef<str>>(lines: &[S]) -> String {
+    let mut text = String::new();
+
+    for line in lines {
+        let line = line.as_ref().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let continued = line.chars().next().is_some_and(char::is_lowercase);
+
+        if text.ends_with('-') && continued && !text.ends_with(" -") {
+            text.pop();
+        } else if !text.is_empty() {
+            text.push(' ');
+        }
+
+        text.push_str(line);
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lines_are_joined() {
+        let lines = ["The quick brown", "fox jumps over the", "lazy dog."];
+        assert_eq!(
+            join_lines(&lines),
+            "The quick brown fox jumps over the lazy dog."
+        );
+
+        // hyphenation is undone for words continued on the next line only
+        let lines = ["a hyphen-", "ated word", "and a Foo-", "Bar - -", "dash"];
+        assert_eq!(
+            join_lines(&lines),
+            "a hyphenated word and a Foo- Bar - - dash"
+        );
+
+        // empty lines are dropped
+        let lines = ["", "  one ", "", "two"];
+        assert_eq!(join_lines(&lines), "one two");
+    }
+}
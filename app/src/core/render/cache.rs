@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use pdfium::doc::{Document, Page};
+use pdfium::{Error, ErrorCode, Result};
+
+/// Least-recently-used cache for loaded pages of a document.
+///
+/// Pages are pinned while in use (see [`PinnedPage`]) and pinned pages are
+/// never evicted. The capacity is therefore a soft limit: if more pages are
+/// pinned than fit into the cache, it will temporarily grow beyond it.
+#[derive(Clone)]
+pub struct PageCache {
+    inner: Arc<Mutex<Inner>>,
+}
+
+/// A page pinned in the cache, unpinned when dropped.
+pub struct PinnedPage {
+    cache: Arc<Mutex<Inner>>,
+    index: usize,
+    page: Page,
+    render_lock: Arc<Mutex<()>>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+struct Inner {
+    document: Document,
+    capacity: usize,
+    entries: HashMap<usize, Entry>,
+    clock: u64,
+    stats: CacheStats,
+}
+
+struct Entry {
+    page: Page,
+    render_lock: Arc<Mutex<()>>,
+    pins: usize,
+    last_used: u64,
+}
+
+impl PageCache {
+    pub fn new(document: Document, capacity: usize) -> Self {
+        let inner = Inner {
+            document,
+            capacity,
+            entries: HashMap::new(),
+            clock: 0,
+            stats: CacheStats::default(),
+        };
+
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+        }
+    }
+
+    /// Get the page with the given index, loading it if it is not cached.
+    ///
+    /// The page stays pinned, i.e. will not be evicted, until the returned
+    /// handle is dropped. Loading is retried once on errors that may be
+    /// transient. Pages that fail to load are not cached, i.e. loading them
+    /// will be attempted again on the next call.
+    ///
+    /// Pages are loaded without holding the cache lock, so that other render
+    /// threads can use cached pages in the meantime.
+    pub fn get(&self, index: usize) -> Result<PinnedPage> {
+        let document = {
+            let mut inner = self.inner.lock().unwrap();
+
+            if let Some((page, render_lock)) = inner.pin(index) {
+                inner.stats.hits += 1;
+                return Ok(self.pinned(index, page, render_lock));
+            }
+
+            inner.document.clone()
+        };
+
+        let page = match document.pages().get(index as _) {
+            Err(err) if is_transient(&err) => {
+                tracing::debug!(page = index, error = %err, "failed to load page, retrying");
+                document.pages().get(index as _)?
+            }
+            result => result?,
+        };
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.stats.misses += 1;
+
+        // another thread may have loaded the same page in the meantime, in
+        // which case we drop ours
+        let (page, render_lock) = match inner.pin(index) {
+            Some(pinned) => pinned,
+            None => {
+                let render_lock = Arc::new(Mutex::new(()));
+
+                let entry = Entry {
+                    page: page.clone(),
+                    render_lock: render_lock.clone(),
+                    pins: 1,
+                    last_used: inner.clock,
+                };
+
+                inner.entries.insert(index, entry);
+                inner.evict();
+                (page, render_lock)
+            }
+        };
+
+        Ok(self.pinned(index, page, render_lock))
+    }
+
+    fn pinned(&self, index: usize, page: Page, render_lock: Arc<Mutex<()>>) -> PinnedPage {
+        PinnedPage {
+            cache: self.inner.clone(),
+            index,
+            page,
+            render_lock,
+        }
+    }
+
+    /// Drop all pages that are not pinned, e.g. to release memory while the
+    /// document is not shown.
+    pub fn clear(&self) {
+        self.inner.lock().unwrap().entries.retain(|_, e| e.pins > 0);
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.inner.lock().unwrap().stats
+    }
+}
+
+/// Check if loading a page may succeed when being retried.
+///
+/// Pdfium does not report why a page failed to load, so this is mostly the
+/// case for errors without a specific cause.
+fn is_transient(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::IoError(_) | Error::ErrorCode(ErrorCode::Unknown)
+    )
+}
+
+impl Inner {
+    /// Pin the given page if it is cached, marking it as used. Returns the
+    /// page and its render lock.
+    fn pin(&mut self, index: usize) -> Option<(Page, Arc<Mutex<()>>)> {
+        self.clock += 1;
+        let clock = self.clock;
+
+        let entry = self.entries.get_mut(&index)?;
+        entry.pins += 1;
+        entry.last_used = clock;
+
+        Some((entry.page.clone(), entry.render_lock.clone()))
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            // find the least recently used unpinned page
+            let lru = self
+                .entries
+                .iter()
+                .filter(|(_, e)| e.pins == 0)
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(i, _)| *i);
+
+            // stop if all pages are in use
+            let index = match lru {
+                Some(index) => index,
+                None => break,
+            };
+
+            self.entries.remove(&index);
+            self.stats.evictions += 1;
+        }
+    }
+
+    fn unpin(&mut self, index: usize) {
+        if let Some(entry) = self.entries.get_mut(&index) {
+            entry.pins -= 1;
+        }
+
+        // we may have grown beyond capacity while the page was pinned
+        if self.entries.len() > self.capacity {
+            self.evict();
+        }
+    }
+}
+
+impl PinnedPage {
+    /// Lock serializing progressive renders of this page.
+    ///
+    /// Cached pages are shared between render threads, but pdfium keeps only
+    /// a single progressive render per page, i.e. starting another one
+    /// replaces and frees the one in progress. The lock has to be held from
+    /// starting a progressive render until it has been closed.
+    pub fn render_lock(&self) -> &Arc<Mutex<()>> {
+        &self.render_lock
+    }
+}
+
+impl Deref for PinnedPage {
+    type Target = Page;
+
+    fn deref(&self) -> &Page {
+        &self.page
+    }
+}
+
+impl Drop for PinnedPage {
+    fn drop(&mut self) {
+        self.cache.lock().unwrap().unpin(self.index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pdfium::Library;
+
+    use super::super::pdfium::test::{build_document, PDFIUM};
+    use super::*;
+
+    /// Build a document with the given number of empty pages.
+    fn document(pages: usize) -> Vec<u8> {
+        let kids: Vec<_> = (0..pages).map(|i| format!("{} 0 R", i + 3)).collect();
+        let catalog = "<< /Type /Catalog /Pages 2 0 R >>".to_owned();
+        let tree = format!(
+            "<< /Type /Pages /Kids [{}] /Count {pages} >>",
+            kids.join(" ")
+        );
+        let page = "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 200 300] >>".to_owned();
+
+        let objects: Vec<_> = [catalog, tree]
+            .into_iter()
+            .chain((0..pages).map(|_| page.clone()))
+            .collect();
+
+        build_document(&objects.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+
+    fn cached(cache: &PageCache) -> Vec<usize> {
+        let mut pages: Vec<_> = cache
+            .inner
+            .lock()
+            .unwrap()
+            .entries
+            .keys()
+            .copied()
+            .collect();
+        pages.sort_unstable();
+        pages
+    }
+
+    #[test]
+    fn pinned_pages_are_not_evicted() {
+        let _guard = PDFIUM.lock().unwrap();
+
+        let lib = Library::init().unwrap();
+        let doc = lib.load_buffer(document(4), None).unwrap();
+        let cache = PageCache::new(doc, 2);
+
+        // the cache grows beyond its capacity while all pages are pinned
+        let pinned: Vec<_> = (0..3).map(|i| cache.get(i).unwrap()).collect();
+        assert_eq!(cached(&cache), [0, 1, 2]);
+        assert_eq!(cache.stats().evictions, 0);
+
+        // pages are pinned until all of their handles are dropped
+        let again = cache.get(0).unwrap();
+        assert!(Arc::ptr_eq(again.render_lock(), pinned[0].render_lock()));
+
+        let mut pinned = pinned.into_iter();
+        drop(pinned.next());
+        assert_eq!(cached(&cache), [0, 1, 2]);
+
+        // and shrinks back once unpinned, keeping pages that are still in use
+        drop(again);
+        assert_eq!(cached(&cache), [1, 2]);
+        assert_eq!(cache.stats().evictions, 1);
+
+        drop(pinned);
+        assert_eq!(cached(&cache), [1, 2]);
+    }
+
+    #[test]
+    fn capacity_and_eviction_order() {
+        let _guard = PDFIUM.lock().unwrap();
+
+        let lib = Library::init().unwrap();
+        let doc = lib.load_buffer(document(4), None).unwrap();
+        let cache = PageCache::new(doc, 2);
+
+        for i in [0, 1, 0] {
+            drop(cache.get(i).unwrap());
+        }
+        assert_eq!(cached(&cache), [0, 1]);
+
+        // the least recently used page is evicted first
+        drop(cache.get(2).unwrap());
+        assert_eq!(cached(&cache), [0, 2]);
+
+        drop(cache.get(3).unwrap());
+        assert_eq!(cached(&cache), [2, 3]);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (1, 4, 2));
+    }
+
+    #[test]
+    fn clear_keeps_pinned_pages() {
+        let _guard = PDFIUM.lock().unwrap();
+
+        let lib = Library::init().unwrap();
+        let doc = lib.load_buffer(document(4), None).unwrap();
+        let cache = PageCache::new(doc, 4);
+
+        let pinned = cache.get(1).unwrap();
+        for i in [0, 2, 3] {
+            drop(cache.get(i).unwrap());
+        }
+
+        cache.clear();
+        assert_eq!(cached(&cache), [1]);
+
+        // the pinned page is still usable and cached
+        assert_eq!(pinned.width(), 200.0);
+        drop(cache.get(1).unwrap());
+        assert_eq!(cache.stats().hits, 1);
+
+        drop(pinned);
+        assert_eq!(cached(&cache), [1]);
+    }
+}
@@ -1,12 +1,26 @@
 use std::ops::Range;
 
+use nalgebra::Vector2;
+
 use crate::types::Rect;
 
+/// A crop of a page, restricting the part of the page that is shown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageCrop {
+    /// The size of the full (uncropped) page in PDF points.
+    pub page_size: Vector2<f64>,
+
+    /// The visible part of the page in PDF points, relative to the page
+    /// origin.
+    pub rect: Rect<f64>,
+}
+
 pub struct PageData<'a, F>
 where
     F: Fn(&Rect<f64>) -> Rect<f64>,
 {
     pub layout: &'a [Rect<f64>],
+    pub crops: &'a [Option<PageCrop>],
     pub visible: &'a Range<usize>,
     pub transform: &'a F,
 }
@@ -18,8 +32,28 @@ where
     pub fn new(layout: &'a [Rect<f64>], visible: &'a Range<usize>, transform: &'a F) -> Self {
         Self {
             layout,
+            crops: &[],
             visible,
             transform,
         }
     }
+
+    /// Use the given page crops, indexed like the page layout.
+    pub fn with_crops(mut self, crops: &'a [Option<PageCrop>]) -> Self {
+        self.crops = crops;
+        self
+    }
+
+    /// Return the full page size in PDF points and the crop rectangle of
+    /// the given page, given its (possibly cropped) bounds in the layout.
+    pub fn page_size(
+        &self,
+        index: usize,
+        page_rect_pt: &Rect<f64>,
+    ) -> (Vector2<f64>, Option<&Rect<f64>>) {
+        match self.crops.get(index).and_then(Option::as_ref) {
+            Some(crop) => (crop.page_size, Some(&crop.rect)),
+            None => (page_rect_pt.size, None),
+        }
+    }
 }
@@ -5,7 +5,7 @@ use nalgebra::{point, vector, Vector2};
 
 use crate::types::{Rect, Viewport};
 
-use super::{TileHandle, TilePriority, TileSource, PageData};
+use super::{TileHandle, TilePriority, TileRequest, TileSource, PageData};
 
 #[derive(Clone, Copy, Debug)]
 pub struct FallbackSpec {
@@ -19,20 +19,39 @@ pub struct FallbackSpec {
     pub render_limits: Vector2<i64>,
 }
 
+/// Limits for requesting fallbacks, keeping bursts of renders in check, e.g.
+/// when zooming far out makes hundreds of pages visible.
+#[derive(Clone, Copy, Debug)]
+pub struct FallbackLimits {
+    /// Maximum number of fallbacks pending at once, over all levels
+    pub pending: usize,
+
+    /// Maximum number of fallbacks requested per update
+    pub requests: usize,
+}
+
 pub struct FallbackManager<H: TileHandle> {
     levels: Vec<Level<H>>,
+    halo_limit: Option<usize>,
+    halo_minimum: usize,
+    limits: Option<FallbackLimits>,
+    deferred: bool,
 }
 
 struct Level<H: TileHandle> {
     spec: FallbackSpec,
     cache: HashMap<usize, CacheEntry<H>>,
     snapshot: Option<Snapshot>,
+
+    // outdated fallbacks, shown until they have been rendered again
+    stale: HashMap<usize, H::Data>,
 }
 
 enum CacheEntry<H: TileHandle> {
     Empty,
     Cached(H::Data),
     Pending(H),
+    Failed,
 }
 
 struct Snapshot {
@@ -51,12 +70,78 @@ where
                 spec: *spec,
                 cache: HashMap::new(),
                 snapshot: None,
+                stale: HashMap::new(),
             })
             .collect();
 
         levels.sort_by_key(|x| (x.spec.render_limits.x, x.spec.render_limits.y));
 
-        FallbackManager { levels }
+        FallbackManager {
+            levels,
+            halo_limit: None,
+            halo_minimum: 0,
+            limits: None,
+            deferred: false,
+        }
+    }
+
+    /// Limit the number of fallbacks requested at once. Requests exceeding
+    /// the limits are deferred to later updates.
+    pub fn set_limits(&mut self, limits: Option<FallbackLimits>) {
+        self.limits = limits;
+    }
+
+    /// Check if fallbacks have been deferred due to the request limits in the
+    /// last update, i.e. if further updates are needed once pending ones have
+    /// been rendered.
+    pub fn is_deferred(&self) -> bool {
+        self.deferred
+    }
+
+    /// Limit the number of pages around the visible range for which
+    /// fallbacks are rendered, regardless of the level specification.
+    pub fn set_halo_limit(&mut self, limit: Option<usize>) {
+        self.halo_limit = limit;
+    }
+
+    /// Render fallbacks of at least the given number of pages around the
+    /// visible range on all levels, e.g. so that neighboring pages can be
+    /// shown at full resolution right away. Takes precedence over the limit.
+    pub fn set_halo_minimum(&mut self, minimum: usize) {
+        self.halo_minimum = minimum;
+    }
+
+    /// Drop cached and pending fallbacks of all but the lowest-resolution
+    /// level, e.g. to release memory while the document is not shown.
+    pub fn shrink(&mut self) {
+        for level in self.levels.iter_mut().skip(1) {
+            level.cache.clear();
+            level.stale.clear();
+            level.snapshot = None;
+        }
+    }
+
+    /// Drop all cached and pending fallbacks, e.g. after the content of the
+    /// document has changed.
+    pub fn clear(&mut self) {
+        for level in &mut self.levels {
+            level.cache.clear();
+            level.stale.clear();
+            level.snapshot = None;
+        }
+    }
+
+    /// Render the fallbacks of the given page again, e.g. after its content
+    /// has been changed. Rendered fallbacks are still shown until they have
+    /// been replaced.
+    pub fn invalidate_page(&mut self, page_index: usize) {
+        for level in &mut self.levels {
+            if let Some(CacheEntry::Cached(data)) = level.cache.remove(&page_index) {
+                level.stale.insert(page_index, data);
+            }
+
+            level.snapshot = None;
+        }
     }
 
     pub fn update<F, S, O>(
@@ -69,10 +154,20 @@ where
         F: Fn(&Rect<f64>) -> Rect<f64>,
         S: TileSource<Handle = H, RequestOptions = O>,
     {
+        // requests of all levels, as level index, request, and distance of
+        // the page to the viewport center
+        let mut candidates = Vec::new();
+        let center = vp.r.size / 2.0;
+
         // process LoD levels from highest to lowest resolution
-        for level in self.levels.iter_mut().rev() {
+        for (level_index, level) in self.levels.iter_mut().enumerate().rev() {
             // page range for which the fallbacks should be computed
-            let range = level.spec.range(pages.layout.len(), pages.visible);
+            let range = level.spec.range(
+                pages.layout.len(),
+                pages.visible,
+                self.halo_limit,
+                self.halo_minimum,
+            );
 
             // check if the level needs to be updated
             if !level.outdated(vp, &range) {
@@ -81,8 +176,9 @@ where
 
             // remove fallbacks for out-of-scope pages
             level.cache.retain(|i, _| range.contains(i));
+            level.stale.retain(|i, _| range.contains(i));
 
-            // request new fallbacks
+            // collect new fallbacks to request
             let mut complete = true;
 
             for (page_index, page_rect_pt) in range.clone().zip(&pages.layout[range.clone()]) {
@@ -94,19 +190,21 @@ where
                     && page_rect.size.y < level.spec.render_threshold.y
                 {
                     level.cache.remove(&page_index);
+                    level.stale.remove(&page_index);
                     continue;
                 }
 
                 let fallback = level.cache.entry(page_index).or_insert(CacheEntry::Empty);
 
-                // if we already have a rendered result, skip
-                if let CacheEntry::Cached(_) = fallback {
+                // if we already have a rendered result or the render failed, skip
+                if let CacheEntry::Cached(_) | CacheEntry::Failed = fallback {
                     continue;
                 }
 
                 // check if a pending fallback has finished rendering and move it
                 if fallback.is_render_finished() {
-                    fallback.move_to_cached();
+                    fallback.move_to_cached(page_index);
+                    level.stale.remove(&page_index);
                     continue;
                 }
 
@@ -123,17 +221,8 @@ where
                 }
 
                 // compute page size for given limits
-                let (page_size, rect) = {
-                    let scale_x = level.spec.render_limits.x as f64 / page_rect_pt.size.x;
-                    let scale_y = level.spec.render_limits.y as f64 / page_rect_pt.size.y;
-                    let scale = scale_x.min(scale_y);
-
-                    let page_size = page_rect_pt.size * scale;
-                    let page_size = vector![page_size.x.round() as i64, page_size.y.round() as i64];
-                    let rect = Rect::new(point![0, 0], page_size);
-
-                    (page_size, rect)
-                };
+                let (page_size_pt, crop) = pages.page_size(page_index, page_rect_pt);
+                let (page_size, rect) = level.spec.render_rect(&page_size_pt, crop);
 
                 // set priority based on visibility
                 let priority = if pages.visible.contains(&page_index) {
@@ -142,9 +231,16 @@ where
                     TilePriority::Low
                 };
 
-                // request tile
-                let task = source.request(page_index, page_size, rect, request_opts, priority);
-                *fallback = CacheEntry::Pending(task);
+                let request = TileRequest {
+                    page_index,
+                    page_size,
+                    rect,
+                    priority,
+                    generation: None,
+                };
+
+                let distance = (page_rect.offs.coords + page_rect.size / 2.0 - center).norm();
+                candidates.push((level_index, request, distance));
 
                 complete = false;
             }
@@ -160,6 +256,99 @@ where
 
             level.snapshot = snapshot
         }
+
+        // request visible pages first, nearest to the viewport center first,
+        // and lower resolutions before higher ones for the same page
+        candidates.sort_by(|(la, a, da), (lb, b, db)| {
+            b.priority
+                .cmp(&a.priority)
+                .then(da.total_cmp(db))
+                .then(la.cmp(lb))
+        });
+
+        // defer requests exceeding the limits to later updates
+        let budget = match self.limits {
+            Some(limits) => {
+                let pending = limits.pending.saturating_sub(self.pending_count());
+                pending.min(limits.requests)
+            }
+            None => candidates.len(),
+        };
+
+        self.deferred = candidates.len() > budget;
+        candidates.truncate(budget);
+
+        // request fallbacks as single batch and store their handles
+        let requests = candidates.iter().map(|(_, req, _)| *req);
+        let handles = source.request_batch(requests, request_opts);
+
+        for ((level_index, req, _), task) in candidates.iter().zip(handles) {
+            let cache = &mut self.levels[*level_index].cache;
+            cache.insert(req.page_index, CacheEntry::Pending(task));
+        }
+    }
+
+    /// Request the fallbacks of the given page with high priority, e.g.
+    /// right before it is brought into view.
+    ///
+    /// Fallbacks that have already been rendered are kept, pending ones are
+    /// moved to the highest priority.
+    pub fn prefetch<F, S, O>(
+        &mut self,
+        source: &mut S,
+        pages: &PageData<'_, F>,
+        page_index: usize,
+        request_opts: &O,
+    ) where
+        F: Fn(&Rect<f64>) -> Rect<f64>,
+        S: TileSource<Handle = H, RequestOptions = O>,
+    {
+        let page_rect_pt = match pages.layout.get(page_index) {
+            Some(rect) => rect,
+            None => return,
+        };
+
+        // transform page bounds to viewport
+        let page_rect = (pages.transform)(page_rect_pt);
+
+        let mut levels = Vec::new();
+        let mut requests = Vec::new();
+
+        for (i, level) in self.levels.iter().enumerate() {
+            // skip levels that would not be rendered for the page
+            if page_rect.size.x < level.spec.render_threshold.x
+                && page_rect.size.y < level.spec.render_threshold.y
+            {
+                continue;
+            }
+
+            match level.cache.get(&page_index) {
+                Some(CacheEntry::Pending(task)) => task.set_priority(TilePriority::High),
+                Some(CacheEntry::Cached(_)) | Some(CacheEntry::Failed) => {}
+                Some(CacheEntry::Empty) | None => {
+                    let (page_size_pt, crop) = pages.page_size(page_index, page_rect_pt);
+                    let (page_size, rect) = level.spec.render_rect(&page_size_pt, crop);
+
+                    levels.push(i);
+                    requests.push(TileRequest {
+                        page_index,
+                        page_size,
+                        rect,
+                        priority: TilePriority::High,
+                        generation: None,
+                    });
+                }
+            }
+        }
+
+        let handles = source.request_batch(requests, request_opts);
+
+        for (i, task) in levels.into_iter().zip(handles) {
+            let level = &mut self.levels[i];
+
+            level.cache.insert(page_index, CacheEntry::Pending(task));
+            level.snapshot = None;
+        }
     }
 
     pub fn fallback(&self, page_index: usize) -> Option<&H::Data> {
@@ -170,14 +359,104 @@ where
             }
         }
 
+        // fall back to outdated ones while these are rendered again
+        for level in self.levels.iter().rev() {
+            if let Some(tex) = level.stale.get(&page_index) {
+                return Some(tex);
+            }
+        }
+
         None
     }
+
+    /// The fallback of the given page with the lowest resolution, if it has
+    /// been rendered, e.g. for small previews.
+    pub fn lowest(&self, page_index: usize) -> Option<&H::Data> {
+        let level = self.levels.first()?;
+
+        match level.cache.get(&page_index) {
+            Some(CacheEntry::Cached(tex)) => Some(tex),
+            _ => level.stale.get(&page_index),
+        }
+    }
+
+    /// Number of fallbacks that have been requested but not rendered yet,
+    /// over all levels.
+    fn pending_count(&self) -> usize {
+        self.levels
+            .iter()
+            .flat_map(|level| level.cache.values())
+            .filter(|entry| matches!(entry, CacheEntry::Pending(task) if !task.is_finished()))
+            .count()
+    }
+
+    /// Check if a fallback for the given page has been requested but none is
+    /// available yet, e.g. shortly after the document has been opened.
+    pub fn is_pending(&self, page_index: usize) -> bool {
+        let mut pending = false;
+
+        for level in &self.levels {
+            if level.stale.contains_key(&page_index) {
+                return false;
+            }
+
+            match level.cache.get(&page_index) {
+                Some(CacheEntry::Cached(_)) => return false,
+                // empty entries have been deferred due to the request limits
+                Some(CacheEntry::Pending(_)) | Some(CacheEntry::Empty) => pending = true,
+                _ => {}
+            }
+        }
+
+        pending
+    }
 }
 
 impl FallbackSpec {
-    fn range(&self, n: usize, base: &Range<usize>) -> Range<usize> {
-        let start = base.start.saturating_sub(self.halo);
-        let end = usize::min(base.end.saturating_add(self.halo), n);
+    /// Page size and render rectangle for a page with the given size (in PDF
+    /// points), scaled to fit into the render limits.
+    ///
+    /// For cropped pages, only the crop rectangle (in PDF points) is rendered
+    /// and scaled to fit into the limits.
+    fn render_rect(
+        &self,
+        page_size_pt: &Vector2<f64>,
+        crop: Option<&Rect<f64>>,
+    ) -> (Vector2<i64>, Rect<i64>) {
+        let visible_pt = crop.map(|c| c.size).unwrap_or(*page_size_pt);
+
+        let scale_x = self.render_limits.x as f64 / visible_pt.x;
+        let scale_y = self.render_limits.y as f64 / visible_pt.y;
+        let scale = scale_x.min(scale_y);
+
+        // render at least a single pixel, even for extremely skewed pages
+        let page_size = page_size_pt * scale;
+        let page_size = vector![page_size.x.round() as i64, page_size.y.round() as i64];
+        let page_size = page_size.sup(&vector![1, 1]);
+
+        let rect = match crop {
+            Some(crop) => {
+                let rect: Rect<i64> = crop.scale(scale).round().cast_unchecked();
+                Rect::new(rect.offs, rect.size.sup(&vector![1, 1]))
+            }
+            None => Rect::new(point![0, 0], page_size),
+        };
+
+        (page_size, rect)
+    }
+
+    fn range(
+        &self,
+        n: usize,
+        base: &Range<usize>,
+        limit: Option<usize>,
+        minimum: usize,
+    ) -> Range<usize> {
+        let halo = limit.map_or(self.halo, |limit| self.halo.min(limit));
+        let halo = halo.max(minimum);
+
+        let start = base.start.saturating_sub(halo);
+        let end = usize::min(base.end.saturating_add(halo), n);
         start..end
     }
 }
@@ -194,11 +473,18 @@ where
         }
     }
 
-    fn move_to_cached(&mut self) {
+    fn move_to_cached(&mut self, page_index: usize) {
         match std::mem::replace(self, CacheEntry::Empty) {
             CacheEntry::Empty => {}
             CacheEntry::Cached(tex) => *self = CacheEntry::Cached(tex),
-            CacheEntry::Pending(task) => *self = CacheEntry::Cached(task.join()),
+            CacheEntry::Pending(task) => match task.join() {
+                Ok(tex) => *self = CacheEntry::Cached(tex),
+                Err(err) => {
+                    tracing::warn!(page = page_index, error = %err, "failed to render fallback");
+                    *self = CacheEntry::Failed;
+                }
+            },
+            CacheEntry::Failed => *self = CacheEntry::Failed,
         }
     }
 }
@@ -1,21 +1,32 @@
 use std::collections::{HashMap, HashSet};
 
-use nalgebra::{point, Vector2};
+use nalgebra::{point, Point2, Vector2};
 
 use crate::types::{Bounds, Rect, Viewport};
 
-use super::{TileHandle, TileId, TilePriority, TileSource, TilingScheme, PageData};
+use super::{TileHandle, TileId, TilePriority, TileRequest, TileSource, TilingScheme, PageData};
 
 pub struct TileManager<S, H: TileHandle> {
     scheme: S,
     cache: HashMap<usize, Cache<H>>,
     halo: Vector2<i64>,
     min_retain_size: Vector2<f64>,
+    warm_scale: Option<f64>,
+    complete: bool,
+    upload_limit: Option<usize>,
+    uploads: usize,
+    deferred: bool,
+    generation: Option<u64>,
+    levels: HashMap<usize, i64>,
 }
 
 struct Cache<H: TileHandle> {
     cached: HashMap<TileId, H::Data>,
     pending: HashMap<TileId, Option<H>>,
+    failed: HashSet<TileId>,
+    warm: HashSet<i64>,
+    prefetch: HashSet<TileId>,
+    generation: HashSet<TileId>,
 }
 
 impl<S, H> TileManager<S, H>
@@ -29,9 +40,66 @@ where
             cache: HashMap::new(),
             halo,
             min_retain_size,
+            warm_scale: None,
+            complete: false,
+            upload_limit: None,
+            uploads: 0,
+            deferred: false,
+            generation: None,
+            levels: HashMap::new(),
         }
     }
 
+    /// Replace the tiling scheme, dropping all cached and pending tiles as
+    /// these are specific to the previous scheme.
+    pub fn set_scheme(&mut self, scheme: S) {
+        self.scheme = scheme;
+        self.cache.clear();
+        self.levels.clear();
+        self.warm_scale = None;
+        self.complete = false;
+    }
+
+    /// Drop all cached and pending tiles, e.g. to release memory while the
+    /// document is not shown.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.warm_scale = None;
+        self.complete = false;
+    }
+
+    /// Drop all cached and pending tiles of the given page, e.g. after its
+    /// content has been changed, so that they are rendered again.
+    pub fn invalidate_page(&mut self, page_index: usize) {
+        self.cache.remove(&page_index);
+        self.complete = false;
+    }
+
+    /// Set the number of tiles around the viewport which are rendered ahead
+    /// of time and kept cached.
+    pub fn set_halo(&mut self, halo: Vector2<i64>) {
+        self.halo = halo;
+    }
+
+    /// Check if all tiles in view have been rendered as of the last update.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Limit the number of newly rendered tiles taken over per update, e.g.
+    /// to reduce the number of textures uploaded per frame. Pass `None` to
+    /// take over all rendered tiles.
+    pub fn set_upload_limit(&mut self, limit: Option<usize>) {
+        self.upload_limit = limit;
+    }
+
+    /// Check if rendered tiles have been held back during the last update
+    /// due to the upload limit, i.e. if another update is required to show
+    /// them.
+    pub fn has_deferred(&self) -> bool {
+        self.deferred
+    }
+
     pub fn update<F, T, O>(
         &mut self,
         source: &mut T,
@@ -42,30 +110,75 @@ where
         F: Fn(&Rect<f64>) -> Rect<f64>,
         T: TileSource<Handle = H, RequestOptions = O>,
     {
-        // remove out-of-view pages from cache
-        self.cache.retain(|page, _| pages.visible.contains(page));
+        // remove out-of-view pages from cache, unless tiles are prefetched
+        self.cache
+            .retain(|page, entry| pages.visible.contains(page) || !entry.prefetch.is_empty());
+
+        // once the scale changes, warmed tiles are no longer special and are
+        // treated like any other tiles of a different z-level
+        if self.warm_scale.is_some() && self.warm_scale != Some(vp.scale) {
+            for entry in self.cache.values_mut() {
+                entry.warm.clear();
+            }
+            self.warm_scale = None;
+        }
+
+        self.complete = true;
+        self.uploads = 0;
+        self.deferred = false;
 
-        // update tiles for all visible pages
         let iter = pages
             .visible
             .clone()
             .zip(&pages.layout[pages.visible.clone()]);
 
+        // cancel tiles requested for the previous zoom level all at once
+        // when the z-level of any page changes, instead of dropping them one
+        // by one below
+        let levels: HashMap<_, _> = iter
+            .clone()
+            .map(|(page_index, page_rect_pt)| {
+                let page_rect = (pages.transform)(page_rect_pt);
+                (page_index, self.page_level(vp, &page_rect, page_rect_pt))
+            })
+            .collect();
+
+        let zoomed = levels
+            .iter()
+            .any(|(page, z)| matches!(self.levels.get(page), Some(prev) if prev != z));
+
+        if zoomed {
+            self.cancel_generation(source);
+        }
+        if self.generation.is_none() {
+            self.generation = source.new_generation();
+        }
+        self.levels = levels;
+
+        // update tiles for all visible pages
         for (page_index, page_rect_pt) in iter {
             // transform page bounds to viewport
             let page_rect = (pages.transform)(page_rect_pt);
 
+            // nothing to render for pages smaller than a pixel
+            if page_rect.size.x < 1.0 || page_rect.size.y < 1.0 {
+                continue;
+            }
+
             // recompute scale for rounded page
             let scale = page_rect.size.x / page_rect_pt.size.x;
             let vp_adj = Viewport { r: vp.r, scale };
 
+            // full page size and crop for rendering
+            let (page_size_pt, crop) = pages.page_size(page_index, page_rect_pt);
+
             // update tiles for page
             self.update_page(
                 source,
                 &vp_adj,
                 page_index,
                 &page_rect,
-                page_rect_pt,
+                (&page_size_pt, crop),
                 request_opts,
             );
         }
@@ -77,7 +190,7 @@ where
         vp: &Viewport,
         page_index: usize,
         page_rect: &Rect<f64>,
-        page_rect_pt: &Rect<f64>,
+        (page_size_pt, crop): (&Vector2<f64>, Option<&Rect<f64>>),
         request_opts: &O,
     ) where
         T: TileSource<Handle = H, RequestOptions = O>,
@@ -110,14 +223,24 @@ where
 
         // get cached tiles for this page
         let entry = self.cache.entry(page_index).or_insert_with(Cache::empty);
+        let generation = self.generation;
+
+        // new tile requests, submitted as a single batch
+        let mut ids = Vec::new();
+        let mut requests = Vec::new();
 
-        // helper for requesting tiles
+        // helper for collecting tile requests
         let mut request_tiles = |tile_rect: &Bounds<i64>, priority| {
             for (x, y) in tile_rect.range_iter() {
                 let id = TileId::new(page_index, x, y, tiles.z);
 
-                // check if we already have the tile
-                if entry.cached.contains_key(&id) {
+                // check if we already have or are about to request the tile
+                if entry.cached.contains_key(&id) || ids.contains(&id) {
+                    continue;
+                }
+
+                // don't retry tiles that failed to render at this z-level
+                if entry.failed.contains(&id) {
                     continue;
                 }
 
@@ -132,13 +255,16 @@ where
                 // compute page size and tile bounds
                 let (page_size, rect) =
                     self.scheme
-                        .render_rect(&page_rect_pt.size, &page_rect.size, &id);
-
-                // request tile
-                let handle = source.request(page_index, page_size, rect, request_opts, priority);
-
-                // store handle to the render task
-                entry.pending.insert(id, Some(handle));
+                        .render_rect(page_size_pt, &page_rect.size, crop, &id);
+
+                ids.push(id);
+                requests.push(TileRequest {
+                    page_index,
+                    page_size,
+                    rect,
+                    priority,
+                    generation,
+                });
             }
         };
 
@@ -181,15 +307,41 @@ where
             request_tiles(&right, TilePriority::Low);
         }
 
-        // move newly rendered tiles to cached map
+        // request tiles and store handles to the render tasks
+        let handles = source.request_batch(requests, request_opts);
+        for (id, handle) in ids.into_iter().zip(handles) {
+            entry.pending.insert(id, Some(handle));
+            entry.generation.insert(id);
+        }
+
+        // move newly rendered tiles to cached map, remember failed ones
         for (id, task) in &mut entry.pending {
             if task.is_some() && task.as_ref().unwrap().is_finished() {
-                entry
-                    .cached
-                    .insert(*id, std::mem::take(task).unwrap().join());
+                // leave tiles over the limit for the next update
+                if matches!(self.upload_limit, Some(limit) if self.uploads >= limit) {
+                    self.deferred = true;
+                    continue;
+                }
+                self.uploads += 1;
+
+                match std::mem::take(task).unwrap().join() {
+                    Ok(tile) => {
+                        entry.cached.insert(*id, tile);
+                    }
+                    Err(err) => {
+                        tracing::warn!(page = page_index, tile = ?id, error = %err, "failed to render tile");
+                        entry.failed.insert(*id);
+                    }
+                }
             }
         }
 
+        // forget failures on other z-levels, these are retried once we get
+        // back to them
+        entry
+            .failed
+            .retain(|id| id.z == tiles.z || entry.warm.contains(&id.z));
+
         // find unused/occluded pending tiles and remove them
         entry.pending.retain(|id, task| {
             // remove any tasks that have already been completed
@@ -197,25 +349,48 @@ where
                 return false;
             }
 
-            // stop loading anything that is not on the current zoom level
+            // keep prefetched tiles, these are managed by prefetch()
+            if entry.prefetch.contains(id) {
+                return true;
+            }
+
+            // stop loading anything that is not on the current or a warmed
+            // zoom level
             if id.z != tiles.z {
-                return false;
+                return entry.warm.contains(&id.z);
             }
 
             // otherwise: check if tile is in the extended viewport
             tiles_vp.contains_point(&id.xy())
         });
+        entry.generation.retain(|id| entry.pending.contains_key(id));
+
+        // check if all tiles in view are present
+        self.complete &= !entry
+            .pending
+            .keys()
+            .any(|id| id.z == tiles.z && tiles.rect.contains_point(&id.xy()));
 
         // find unused/occluded cached tiles and remove them
         let cached_keys: HashSet<_> = entry.cached.keys().cloned().collect();
 
         entry.cached.retain(|id, _tile| {
+            // keep prefetched tiles, these are managed by prefetch()
+            if entry.prefetch.contains(id) {
+                return true;
+            }
+
             // if the tile is on the current level: keep it if it is in the
             // extended viewport, drop it if not
             if id.z == tiles.z {
                 return tiles_vp.contains_point(&id.xy());
             }
 
+            // keep warmed tiles, these are managed by warm()
+            if entry.warm.contains(&id.z) {
+                return true;
+            }
+
             // compute tile bounds
             let tile_rect = self.scheme.screen_rect(vp, page_rect, id);
             let tile_rect = tile_rect.bounds().round_outwards();
@@ -252,6 +427,262 @@ where
         });
     }
 
+    /// Pre-render the tiles in view for the given zoom factors relative to
+    /// the current scale, e.g. to make the next zoom step feel instant.
+    ///
+    /// Tiles are requested with the lowest priority and only as long as their
+    /// total size stays within the given budget (in pixels). Warmed tiles are
+    /// retained until the next call or until the scale changes, at which
+    /// point they are treated like cached tiles of any other z-level. This
+    /// should be called after [`update()`](Self::update) with the same
+    /// viewport.
+    pub fn warm<F, T, O>(
+        &mut self,
+        source: &mut T,
+        pages: &PageData<'_, F>,
+        vp: &Viewport,
+        factors: &[f64],
+        budget: i64,
+        request_opts: &O,
+    ) where
+        F: Fn(&Rect<f64>) -> Rect<f64>,
+        T: TileSource<Handle = H, RequestOptions = O>,
+    {
+        self.warm_scale = Some(vp.scale);
+
+        let mut used = 0;
+
+        // zooming keeps the viewport center fixed
+        let center = point![0.0, 0.0] + vp.r.size / 2.0;
+
+        let iter = pages
+            .visible
+            .clone()
+            .zip(&pages.layout[pages.visible.clone()]);
+
+        for (page_index, page_rect_pt) in iter {
+            let page_rect = (pages.transform)(page_rect_pt);
+            let (page_size_pt, crop) = pages.page_size(page_index, page_rect_pt);
+            let z = self.page_level(vp, &page_rect, page_rect_pt);
+
+            let entry = self.cache.entry(page_index).or_insert_with(Cache::empty);
+
+            let mut levels = HashSet::new();
+            let mut wanted = HashSet::new();
+            let mut ids = Vec::new();
+            let mut requests = Vec::new();
+
+            for factor in factors {
+                // page bounds in the zoomed viewport
+                let page_rect = Rect::new(
+                    center + (page_rect.offs - center) * *factor,
+                    page_rect.size * *factor,
+                )
+                .round();
+
+                let scale = page_rect.size.x / page_rect_pt.size.x;
+                let vp_adj = Viewport { r: vp.r, scale };
+
+                // area of the page visible in the zoomed viewport
+                let visible_page = Rect::new(-page_rect.offs, vp.r.size)
+                    .clip(&Rect::new(point![0.0, 0.0], page_rect.size))
+                    .bounds();
+
+                if visible_page.x_min >= visible_page.x_max
+                    || visible_page.y_min >= visible_page.y_max
+                {
+                    continue;
+                }
+
+                let tiles = self.scheme.tiles(&vp_adj, &page_rect, &visible_page);
+
+                // nothing to do if the zoom step does not change the z-level
+                if tiles.z == z {
+                    continue;
+                }
+
+                levels.insert(tiles.z);
+
+                for (x, y) in tiles.rect.range_iter() {
+                    let id = TileId::new(page_index, x, y, tiles.z);
+
+                    let (page_size, rect) =
+                        self.scheme
+                            .render_rect(&page_size_pt, &page_rect.size, crop, &id);
+
+                    // stop once we have exceeded our budget
+                    used += rect.size.x * rect.size.y;
+                    if used > budget {
+                        break;
+                    }
+
+                    wanted.insert(id);
+
+                    if entry.cached.contains_key(&id)
+                        || entry.pending.contains_key(&id)
+                        || entry.failed.contains(&id)
+                    {
+                        continue;
+                    }
+
+                    ids.push(id);
+                    requests.push(TileRequest {
+                        page_index,
+                        page_size,
+                        rect,
+                        priority: TilePriority::Low,
+                        generation: None,
+                    });
+                }
+            }
+
+            // request tiles and store handles to the render tasks
+            let handles = source.request_batch(requests, request_opts);
+            for (id, handle) in ids.into_iter().zip(handles) {
+                entry.pending.insert(id, Some(handle));
+            }
+
+            // drop previously warmed tiles that are no longer wanted
+            let is_stale = |id: &TileId| {
+                let warmed = entry.warm.contains(&id.z) || levels.contains(&id.z);
+                id.z != z && warmed && !wanted.contains(id)
+            };
+
+            entry.pending.retain(|id, _| !is_stale(id));
+            entry.cached.retain(|id, _| !is_stale(id));
+
+            entry.warm = levels;
+        }
+    }
+
+    /// Pre-render the tiles in view of the given viewport, e.g. where the
+    /// viewport is predicted to come to rest after a fling.
+    ///
+    /// Tiles are requested with medium priority. Prefetched tiles are
+    /// retained until the next call or until
+    /// [`clear_prefetch()`](Self::clear_prefetch) is called, at which point
+    /// they are treated like any other tiles. The given page data should be
+    /// computed for the predicted viewport.
+    pub fn prefetch<F, T, O>(
+        &mut self,
+        source: &mut T,
+        pages: &PageData<'_, F>,
+        vp: &Viewport,
+        request_opts: &O,
+    ) where
+        F: Fn(&Rect<f64>) -> Rect<f64>,
+        T: TileSource<Handle = H, RequestOptions = O>,
+    {
+        // forget previous predictions for pages no longer predicted to be in view
+        for (page, entry) in self.cache.iter_mut() {
+            if !pages.visible.contains(page) {
+                entry.prefetch.clear();
+            }
+        }
+
+        let iter = pages
+            .visible
+            .clone()
+            .zip(&pages.layout[pages.visible.clone()]);
+
+        for (page_index, page_rect_pt) in iter {
+            let page_rect = (pages.transform)(page_rect_pt);
+            let (page_size_pt, crop) = pages.page_size(page_index, page_rect_pt);
+            let scale = page_rect.size.x / page_rect_pt.size.x;
+            let vp_adj = Viewport { r: vp.r, scale };
+
+            // area of the page visible in the predicted viewport
+            let visible_page = Rect::new(-page_rect.offs, vp.r.size)
+                .clip(&Rect::new(point![0.0, 0.0], page_rect.size))
+                .bounds();
+
+            let tiles = self.scheme.tiles(&vp_adj, &page_rect, &visible_page);
+            let entry = self.cache.entry(page_index).or_insert_with(Cache::empty);
+
+            let mut wanted = HashSet::new();
+            let mut ids = Vec::new();
+            let mut requests = Vec::new();
+
+            for (x, y) in tiles.rect.range_iter() {
+                let id = TileId::new(page_index, x, y, tiles.z);
+                wanted.insert(id);
+
+                if entry.cached.contains_key(&id) || entry.failed.contains(&id) {
+                    continue;
+                }
+
+                // halo tiles may already be pending with a lower priority
+                if let Some(task) = entry.pending.get(&id) {
+                    if let Some(task) = task {
+                        task.set_priority(TilePriority::Medium);
+                    }
+                    continue;
+                }
+
+                let (page_size, rect) =
+                    self.scheme
+                        .render_rect(&page_size_pt, &page_rect.size, crop, &id);
+
+                ids.push(id);
+                requests.push(TileRequest {
+                    page_index,
+                    page_size,
+                    rect,
+                    priority: TilePriority::Medium,
+                    generation: None,
+                });
+            }
+
+            // request tiles and store handles to the render tasks
+            let handles = source.request_batch(requests, request_opts);
+            for (id, handle) in ids.into_iter().zip(handles) {
+                entry.pending.insert(id, Some(handle));
+            }
+
+            entry.prefetch = wanted;
+        }
+    }
+
+    /// Stop retaining prefetched tiles, e.g. once the viewport has come to
+    /// rest.
+    pub fn clear_prefetch(&mut self) {
+        for entry in self.cache.values_mut() {
+            entry.prefetch.clear();
+        }
+    }
+
+    /// The z-level of the tiles of the given page at the given viewport,
+    /// given its bounds in viewport and page coordinates.
+    fn page_level(&self, vp: &Viewport, page_rect: &Rect<f64>, page_rect_pt: &Rect<f64>) -> i64 {
+        let scale = page_rect.size.x / page_rect_pt.size.x;
+        let vp_adj = Viewport { r: vp.r, scale };
+        let page_bounds = Rect::new(point![0.0, 0.0], page_rect.size).bounds();
+        self.scheme.tiles(&vp_adj, page_rect, &page_bounds).z
+    }
+
+    /// Cancel all queued tiles requested by [`update()`](Self::update) in
+    /// the current generation and forget about them, starting a new
+    /// generation with the next update.
+    fn cancel_generation<T>(&mut self, source: &mut T)
+    where
+        T: TileSource<Handle = H>,
+    {
+        let generation = match self.generation.take() {
+            Some(generation) => generation,
+            None => return,
+        };
+
+        source.cancel_generation(generation);
+
+        // tiles that are already being rendered are aborted once their
+        // handles are dropped
+        for entry in self.cache.values_mut() {
+            for id in entry.generation.drain() {
+                entry.pending.remove(&id);
+            }
+        }
+    }
+
     pub fn tiles(
         &self,
         vp: &Viewport,
@@ -280,8 +711,8 @@ where
             .filter(|(id, _)| {
                 // if the tile has a different z-level we assume that it is
                 // required (otherwise, it should have been removed in the
-                // update)
-                id.z != tiles.z ||
+                // update), unless it has been warmed for a future zoom level
+                (id.z != tiles.z && !entry.warm.contains(&id.z)) ||
                 // if z-levels match, check if the tile is inside the viewport
                 tiles.rect.contains_point(&id.xy())
             })
@@ -323,6 +754,23 @@ where
             })
             .collect()
     }
+
+    /// The topmost cached tile covering the given point, i.e. the tile that
+    /// is shown at that point. Arguments and returned rectangle are in
+    /// viewport coordinates, as for [`tiles()`](Self::tiles).
+    pub fn tile_at(
+        &self,
+        vp: &Viewport,
+        page_index: usize,
+        page_rect: &Rect<f64>,
+        point: &Point2<f64>,
+    ) -> Option<(Rect<f64>, &H::Data)> {
+        // tiles are drawn in order, so the last one covering the point is on top
+        self.tiles(vp, page_index, page_rect)
+            .into_iter()
+            .rev()
+            .find(|(rect, _)| rect.contains_point(point))
+    }
 }
 
 impl<T: TileHandle> Cache<T> {
@@ -330,6 +778,10 @@ impl<T: TileHandle> Cache<T> {
         Self {
             cached: HashMap::new(),
             pending: HashMap::new(),
+            failed: HashSet::new(),
+            warm: HashSet::new(),
+            prefetch: HashSet::new(),
+            generation: HashSet::new(),
         }
     }
 }
@@ -1,17 +1,17 @@
 mod common;
-pub use common::PageData;
+pub use common::{PageCrop, PageData};
 
 mod fallback;
-pub use fallback::{FallbackManager, FallbackSpec};
+pub use fallback::{FallbackLimits, FallbackManager, FallbackSpec};
 
 mod manager;
 pub use manager::TileManager;
 
 mod scheme;
-pub use scheme::{ExactLevelTilingScheme, HybridTilingScheme, QuadTreeTilingScheme, TilingScheme};
+pub use scheme::{DynamicTilingScheme, TilingScheme, TilingSchemeKind};
 
 mod source;
-pub use source::{TileHandle, TilePriority, TileProvider, TileSource};
+pub use source::{TileHandle, TilePriority, TileProvider, TileRequest, TileSource};
 
 mod tile;
 pub use tile::{TileId, TileRect};
@@ -23,7 +23,7 @@ pub trait TilingScheme {
     /// - `vp`: The [`Viewport`] used for rendering.
     /// - `page`: The page bounds in viewport coordinates.
     /// - `rect`: The area for which the required tiles should be returned, in
-    ///    viewport coordinates aligned at the page origin.
+    ///   viewport coordinates aligned at the page origin.
     fn tiles(&self, vp: &Viewport, page: &Rect<f64>, rect: &Bounds<f64>) -> TileRect;
 
     /// Area on screen covered by the given tile in pixels, adjusted for the
@@ -43,18 +43,59 @@ pub trait TilingScheme {
     /// with size `page_size` (in pixels), where the tile is the result of that
     /// operation if one would crop out only the returned `tile_rect`.
     ///
+    /// For cropped pages, tiles are aligned at the origin of the crop
+    /// rectangle. The returned `page_size` still refers to the full page, with
+    /// the `tile_rect` translated by the crop offset.
+    ///
     /// # Arguments
-    /// - `page_size_pt`: The page size in PDF points.
-    /// - `page_size_vp`: The page size in viewport coordinates.
+    /// - `page_size_pt`: The full (uncropped) page size in PDF points.
+    /// - `page_size_vp`: The size of the visible (cropped) part of the page
+    ///   in viewport coordinates.
+    /// - `crop`: The visible part of the page in PDF points, if cropped.
     /// - `id`: The tile ID.
     fn render_rect(
         &self,
         page_size_pt: &Vector2<f64>,
         page_size_vp: &Vector2<f64>,
+        crop: Option<&Rect<f64>>,
         id: &TileId,
     ) -> (Vector2<i64>, Rect<i64>);
 }
 
+/// Apply the crop of a page to the page size and tile rectangle computed for
+/// the uncropped case, using the given scale from PDF points to pixels.
+fn apply_crop(
+    page_size_pt: &Vector2<f64>,
+    crop: Option<&Rect<f64>>,
+    scale: f64,
+    page_size: Vector2<i64>,
+    tile_rect: Rect<i64>,
+) -> (Vector2<i64>, Rect<i64>) {
+    let crop = match crop {
+        Some(crop) => crop,
+        None => return (page_size, tile_rect),
+    };
+
+    let page_size = page_size_pt * scale;
+    let page_size = vector![page_size.x.round() as i64, page_size.y.round() as i64];
+
+    let offs = crop.offs * scale;
+    let offs = vector![offs.x.round() as i64, offs.y.round() as i64];
+
+    (page_size, tile_rect.translate(&offs))
+}
+
+/// Scale from PDF points to viewport coordinates, given the size of the
+/// visible part of the page in both.
+fn visible_scale(
+    page_size_pt: &Vector2<f64>,
+    page_size_vp: &Vector2<f64>,
+    crop: Option<&Rect<f64>>,
+) -> f64 {
+    let visible_pt = crop.map(|c| c.size).unwrap_or(*page_size_pt);
+    page_size_vp.x / visible_pt.x
+}
+
 /// A hybrid tiling-scheme.
 ///
 /// Divides a page into tiles if it is larger than a specified threshold and
@@ -76,10 +117,10 @@ impl HybridTilingScheme {
     /// - `min_size`: The minimum page size for when a page should be tiled.
     ///
     ///    If the maximum dimension (i.e., maximum of width and height) of a
-    ///    page in viewport coordinates is larger than this threshold, the page
-    ///    will be divided into (multiple) tiles. Otherwise, it will be
-    ///    rendered as a single tile (with size equals to the page size in
-    ///    viewport coordinates).
+    ///   page in viewport coordinates is larger than this threshold, the page
+    ///   will be divided into (multiple) tiles. Otherwise, it will be
+    ///   rendered as a single tile (with size equals to the page size in
+    ///   viewport coordinates).
     pub fn new(tile_size: Vector2<i64>, min_size: i64) -> Self {
         Self {
             tile_size,
@@ -118,8 +159,9 @@ impl TilingScheme for HybridTilingScheme {
     #[inline]
     fn render_rect(
         &self,
-        _page_size_pt: &Vector2<f64>,
+        page_size_pt: &Vector2<f64>,
         page_size_vp: &Vector2<f64>,
+        crop: Option<&Rect<f64>>,
         id: &TileId,
     ) -> (Vector2<i64>, Rect<i64>) {
         let page_size: Vector2<i64> = na::convert_unchecked(*page_size_vp);
@@ -135,7 +177,8 @@ impl TilingScheme for HybridTilingScheme {
             Rect::new(point![0, 0], page_size)
         };
 
-        (page_size, tile_rect)
+        let scale = visible_scale(page_size_pt, page_size_vp, crop);
+        apply_crop(page_size_pt, crop, scale, page_size, tile_rect)
     }
 }
 
@@ -150,7 +193,6 @@ pub struct ExactLevelTilingScheme {
     tile_size: Vector2<i64>,
 }
 
-#[allow(unused)]
 impl ExactLevelTilingScheme {
     /// Creates a new exact-level tiling-scheme with the specified tile size.
     pub fn new(tile_size: Vector2<i64>) -> Self {
@@ -179,15 +221,17 @@ impl TilingScheme for ExactLevelTilingScheme {
     #[inline]
     fn render_rect(
         &self,
-        _page_size_pt: &Vector2<f64>,
+        page_size_pt: &Vector2<f64>,
         page_size_vp: &Vector2<f64>,
+        crop: Option<&Rect<f64>>,
         id: &TileId,
     ) -> (Vector2<i64>, Rect<i64>) {
         let page_size = na::convert_unchecked(*page_size_vp);
         let tile_offs = vector![id.x, id.y].component_mul(&self.tile_size);
         let tile_rect = Rect::new(tile_offs.into(), self.tile_size);
 
-        (page_size, tile_rect)
+        let scale = visible_scale(page_size_pt, page_size_vp, crop);
+        apply_crop(page_size_pt, crop, scale, page_size, tile_rect)
     }
 }
 
@@ -200,7 +244,6 @@ pub struct QuadTreeTilingScheme {
     tile_size: Vector2<i64>,
 }
 
-#[allow(unused)]
 impl QuadTreeTilingScheme {
     /// Creates a new quad-tree tiling-scheme with the specified tile size.
     pub fn new(tile_size: Vector2<i64>) -> Self {
@@ -234,6 +277,7 @@ impl TilingScheme for QuadTreeTilingScheme {
         &self,
         page_size_pt: &Vector2<f64>,
         _page_size_vp: &Vector2<f64>,
+        crop: Option<&Rect<f64>>,
         id: &TileId,
     ) -> (Vector2<i64>, Rect<i64>) {
         let scale = (id.z as f64).exp2();
@@ -244,6 +288,206 @@ impl TilingScheme for QuadTreeTilingScheme {
         let tile_offs = vector![id.x, id.y].component_mul(&self.tile_size);
         let tile_rect = Rect::new(tile_offs.into(), self.tile_size);
 
-        (page_size, tile_rect)
+        apply_crop(page_size_pt, crop, scale, page_size, tile_rect)
+    }
+}
+
+/// The kind of a tiling scheme, e.g. for selecting it at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TilingSchemeKind {
+    #[default]
+    Hybrid,
+    ExactLevel,
+    QuadTree,
+}
+
+impl TilingSchemeKind {
+    pub const ALL: [TilingSchemeKind; 3] = [
+        TilingSchemeKind::Hybrid,
+        TilingSchemeKind::ExactLevel,
+        TilingSchemeKind::QuadTree,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TilingSchemeKind::Hybrid => "hybrid",
+            TilingSchemeKind::ExactLevel => "exact",
+            TilingSchemeKind::QuadTree => "quadtree",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "hybrid" => Some(TilingSchemeKind::Hybrid),
+            "exact" => Some(TilingSchemeKind::ExactLevel),
+            "quadtree" => Some(TilingSchemeKind::QuadTree),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TilingSchemeKind::Hybrid => "Hybrid",
+            TilingSchemeKind::ExactLevel => "Exact Level",
+            TilingSchemeKind::QuadTree => "Quad-Tree",
+        }
+    }
+}
+
+/// A tiling scheme selected at runtime.
+///
+/// Dispatches to one of the tiling schemes above, allowing the scheme of a
+/// [`TileManager`](super::TileManager) to be switched without changing its
+/// type.
+#[derive(Debug, Clone)]
+pub enum DynamicTilingScheme {
+    Hybrid(HybridTilingScheme),
+    ExactLevel(ExactLevelTilingScheme),
+    QuadTree(QuadTreeTilingScheme),
+}
+
+impl DynamicTilingScheme {
+    /// Create a new tiling scheme of the given kind.
+    ///
+    /// # Arguments
+    /// - `kind`: The kind of the tiling scheme.
+    /// - `tile_size`: The size of the tiles.
+    /// - `min_size`: The minimum page size for when a page should be tiled,
+    ///   only used by the hybrid scheme, see [`HybridTilingScheme::new()`].
+    pub fn new(kind: TilingSchemeKind, tile_size: Vector2<i64>, min_size: i64) -> Self {
+        match kind {
+            TilingSchemeKind::Hybrid => Self::Hybrid(HybridTilingScheme::new(tile_size, min_size)),
+            TilingSchemeKind::ExactLevel => {
+                Self::ExactLevel(ExactLevelTilingScheme::new(tile_size))
+            }
+            TilingSchemeKind::QuadTree => Self::QuadTree(QuadTreeTilingScheme::new(tile_size)),
+        }
+    }
+}
+
+impl TilingScheme for DynamicTilingScheme {
+    #[inline]
+    fn tiles(&self, vp: &Viewport, page: &Rect<f64>, rect: &Bounds<f64>) -> TileRect {
+        match self {
+            Self::Hybrid(scheme) => scheme.tiles(vp, page, rect),
+            Self::ExactLevel(scheme) => scheme.tiles(vp, page, rect),
+            Self::QuadTree(scheme) => scheme.tiles(vp, page, rect),
+        }
+    }
+
+    #[inline]
+    fn screen_rect(&self, vp: &Viewport, page: &Rect<f64>, id: &TileId) -> Rect<f64> {
+        match self {
+            Self::Hybrid(scheme) => scheme.screen_rect(vp, page, id),
+            Self::ExactLevel(scheme) => scheme.screen_rect(vp, page, id),
+            Self::QuadTree(scheme) => scheme.screen_rect(vp, page, id),
+        }
+    }
+
+    #[inline]
+    fn render_rect(
+        &self,
+        page_size_pt: &Vector2<f64>,
+        page_size_vp: &Vector2<f64>,
+        crop: Option<&Rect<f64>>,
+        id: &TileId,
+    ) -> (Vector2<i64>, Rect<i64>) {
+        match self {
+            Self::Hybrid(scheme) => scheme.render_rect(page_size_pt, page_size_vp, crop, id),
+            Self::ExactLevel(scheme) => scheme.render_rect(page_size_pt, page_size_vp, crop, id),
+            Self::QuadTree(scheme) => scheme.render_rect(page_size_pt, page_size_vp, crop, id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn page_size_pt() -> Vector2<f64> {
+        vector![200.0, 300.0]
+    }
+
+    fn crop() -> Rect<f64> {
+        Rect::new(point![20.0, 30.0], vector![100.0, 150.0])
+    }
+
+    #[test]
+    fn hybrid_render_rect() {
+        let scheme = HybridTilingScheme::new(vector![64, 64], 1024);
+        let id = TileId::new(0, 0, 0, 600);
+
+        // uncropped page, rendered as single tile
+        let (page_size, rect) =
+            scheme.render_rect(&page_size_pt(), &vector![400.0, 600.0], None, &id);
+        assert_eq!(page_size, vector![400, 600]);
+        assert_eq!(rect, Rect::new(point![0, 0], vector![400, 600]));
+
+        // cropped page, the tile covers only the crop of the full page
+        let (page_size, rect) =
+            scheme.render_rect(&page_size_pt(), &vector![200.0, 300.0], Some(&crop()), &id);
+        assert_eq!(page_size, vector![400, 600]);
+        assert_eq!(rect, Rect::new(point![40, 60], vector![200, 300]));
+
+        // cropped page, divided into tiles
+        let id = TileId::new(0, 1, 2, 1500);
+        let (page_size, rect) = scheme.render_rect(
+            &page_size_pt(),
+            &vector![1000.0, 1500.0],
+            Some(&crop()),
+            &id,
+        );
+        assert_eq!(page_size, vector![2000, 3000]);
+        assert_eq!(rect, Rect::new(point![264, 428], vector![64, 64]));
+    }
+
+    #[test]
+    fn exact_level_render_rect() {
+        let scheme = ExactLevelTilingScheme::new(vector![64, 64]);
+        let id = TileId::new(0, 1, 0, 400);
+
+        let (page_size, rect) =
+            scheme.render_rect(&page_size_pt(), &vector![400.0, 600.0], None, &id);
+        assert_eq!(page_size, vector![400, 600]);
+        assert_eq!(rect, Rect::new(point![64, 0], vector![64, 64]));
+
+        let (page_size, rect) =
+            scheme.render_rect(&page_size_pt(), &vector![200.0, 300.0], Some(&crop()), &id);
+        assert_eq!(page_size, vector![400, 600]);
+        assert_eq!(rect, Rect::new(point![104, 60], vector![64, 64]));
+    }
+
+    #[test]
+    fn quad_tree_render_rect() {
+        let scheme = QuadTreeTilingScheme::new(vector![64, 64]);
+        let id = TileId::new(0, 0, 1, 1);
+
+        let (page_size, rect) =
+            scheme.render_rect(&page_size_pt(), &vector![400.0, 600.0], None, &id);
+        assert_eq!(page_size, vector![400, 600]);
+        assert_eq!(rect, Rect::new(point![0, 64], vector![64, 64]));
+
+        // the viewport size does not matter, tiles are rendered at z-level scale
+        let (page_size, rect) =
+            scheme.render_rect(&page_size_pt(), &vector![150.0, 225.0], Some(&crop()), &id);
+        assert_eq!(page_size, vector![400, 600]);
+        assert_eq!(rect, Rect::new(point![40, 124], vector![64, 64]));
+    }
+
+    #[test]
+    fn dynamic_render_rect() {
+        let vp_size = vector![200.0, 300.0];
+
+        for kind in TilingSchemeKind::ALL {
+            let scheme = DynamicTilingScheme::new(kind, vector![64, 64], 1024);
+            let id = TileId::new(0, 0, 0, 1);
+
+            let (page_size, rect) =
+                scheme.render_rect(&page_size_pt(), &vp_size, Some(&crop()), &id);
+
+            // crops are translated into the full page in all schemes
+            assert_eq!(page_size, vector![400, 600], "{kind:?}");
+            assert_eq!(rect.offs, point![40, 60], "{kind:?}");
+        }
     }
 }
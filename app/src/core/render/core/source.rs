@@ -1,3 +1,4 @@
+use std::convert::Infallible;
 use std::ops::Range;
 
 use executor::exec::priority::DropHandle;
@@ -26,18 +27,60 @@ pub trait TileSource {
         opts: &Self::RequestOptions,
         priority: TilePriority,
     ) -> Self::Handle;
+
+    /// Request multiple tiles at once, returning their handles in request
+    /// order.
+    ///
+    /// Sources may override this to share work between requests, e.g. by
+    /// grouping them by page.
+    fn request_batch<I>(&mut self, requests: I, opts: &Self::RequestOptions) -> Vec<Self::Handle>
+    where
+        I: IntoIterator<Item = TileRequest>,
+    {
+        requests
+            .into_iter()
+            .map(|r| self.request(r.page_index, r.page_size, r.rect, opts, r.priority))
+            .collect()
+    }
+
+    /// Start a new generation of requests, returning its ID, or `None` if
+    /// generations are not supported by this source.
+    fn new_generation(&mut self) -> Option<u64> {
+        None
+    }
+
+    /// Cancel all queued requests of the given generation at once, e.g. once
+    /// the zoom level has changed and their tiles have become obsolete.
+    fn cancel_generation(&mut self, _generation: u64) {}
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TileRequest {
+    pub page_index: usize,
+    pub page_size: Vector2<i64>,
+    pub rect: Rect<i64>,
+    pub priority: TilePriority,
+
+    /// Generation the request is part of, if any, for canceling it together
+    /// with other requests of that generation.
+    pub generation: Option<u64>,
 }
 
 pub trait TileHandle {
     type Data;
+    type Error: std::fmt::Display;
 
     fn is_finished(&self) -> bool;
     fn set_priority(&self, priority: TilePriority);
-    fn join(self) -> Self::Data;
+
+    /// Wait for the tile to be rendered, returning its data or the reason
+    /// why it could not be rendered.
+    fn join(self) -> Result<Self::Data, Self::Error>;
 }
 
 impl<T: Send> TileHandle for DropHandle<TilePriority, T> {
     type Data = T;
+    type Error = Infallible;
 
     fn is_finished(&self) -> bool {
         DropHandle::is_finished(self)
@@ -47,8 +90,8 @@ impl<T: Send> TileHandle for DropHandle<TilePriority, T> {
         DropHandle::set_priority(self, priority)
     }
 
-    fn join(self) -> T {
-        DropHandle::join(self)
+    fn join(self) -> Result<T, Infallible> {
+        Ok(DropHandle::join(self))
     }
 }
 
@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use executor::exec::priority::DropHandle;
+use executor::exec::Monitor;
+
+use nalgebra::{point, vector, Vector2};
+
+use pdfium::doc::Document;
+
+use crate::types::Rect;
+
+use super::core::TilePriority;
+use super::interop::{Bitmap, TileFactory};
+use super::pdfium::{render_page_rect, Executor, RenderOptions};
+
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    /// Per-channel difference below which pixels are considered equal
+    pub threshold: u8,
+
+    /// Maximum bitmap size for the rendered pages
+    pub render_limits: Vector2<i64>,
+
+    /// Options used for rendering the pages to compare
+    pub render: RenderOptions,
+}
+
+/// Pixel-based page differences between two documents.
+///
+/// Pages of both documents are rendered with the same size and compared
+/// pixel-wise. The result is a heat map in premultiplied BGRA format, which
+/// is transparent where both pages match and red where they differ. Pages
+/// missing in the other document are marked as entirely different, pages
+/// missing in the base document cannot be shown and are reported via
+/// [`added_pages()`](Self::added_pages) instead.
+///
+/// Diffs are rendered on the executor of the base document, alongside its
+/// tiles, so that rendering is suspended and resumed together with them.
+/// Diffs that failed to render are retried on later updates, waiting longer
+/// after each failure.
+pub struct PdfDiffProvider<M, F: TileFactory> {
+    executor: Arc<Executor>,
+    monitor: M,
+    factory: F,
+    base: Document,
+    other: Document,
+    opts: DiffOptions,
+    cache: HashMap<usize, CacheEntry<F::Data>>,
+}
+
+enum CacheEntry<T> {
+    Pending {
+        task: DropHandle<TilePriority, Option<T>>,
+        attempt: u32,
+    },
+    Cached(T),
+    Failed {
+        attempt: u32,
+        time: Instant,
+    },
+}
+
+/// Delay before retrying a diff that failed to render, doubled with each
+/// further attempt.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Maximum number of times the retry delay is doubled.
+const RETRY_BACKOFF_LIMIT: u32 = 6;
+
+impl<M, F> PdfDiffProvider<M, F>
+where
+    M: Monitor + Send + Clone + 'static,
+    F: TileFactory + Send + Clone + 'static,
+    F::Data: Send,
+{
+    pub fn new(
+        executor: Arc<Executor>,
+        monitor: M,
+        factory: F,
+        base: Document,
+        other: Document,
+        opts: DiffOptions,
+    ) -> Self {
+        Self {
+            executor,
+            monitor,
+            factory,
+            base,
+            other,
+            opts,
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn update(&mut self, layout: &[Rect<f64>], visible: &Range<usize>) {
+        // remove diffs for pages that are no longer visible, cancel pending ones
+        self.cache.retain(|i, _| visible.contains(i));
+
+        for (page_index, page_rect) in visible.clone().zip(&layout[visible.clone()]) {
+            // move finished diffs to cache, retry failed ones once their
+            // delay has passed
+            let attempt = match self.cache.remove(&page_index) {
+                Some(CacheEntry::Pending { task, attempt }) if task.is_finished() => {
+                    let entry = match task.join() {
+                        Some(diff) => CacheEntry::Cached(diff),
+                        None => CacheEntry::Failed {
+                            attempt,
+                            time: Instant::now(),
+                        },
+                    };
+
+                    self.cache.insert(page_index, entry);
+                    continue;
+                }
+                Some(CacheEntry::Failed { attempt, time })
+                    if time.elapsed() >= retry_delay(attempt) =>
+                {
+                    attempt + 1
+                }
+                Some(entry) => {
+                    self.cache.insert(page_index, entry);
+                    continue;
+                }
+                None => 0,
+            };
+
+            // compute page size for given limits
+            let page_size = {
+                let scale_x = self.opts.render_limits.x as f64 / page_rect.size.x;
+                let scale_y = self.opts.render_limits.y as f64 / page_rect.size.y;
+                let scale = scale_x.min(scale_y);
+
+                let page_size = page_rect.size * scale;
+                vector![page_size.x.round() as i64, page_size.y.round() as i64]
+            };
+
+            let task = self.task(page_index, page_size);
+            let task = self
+                .executor
+                .submit_with(self.monitor.clone(), TilePriority::High, task)
+                .cancel_on_drop();
+
+            self.cache
+                .insert(page_index, CacheEntry::Pending { task, attempt });
+        }
+    }
+
+    /// Drop all cached and pending diffs, e.g. while rendering is suspended.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Pages that only exist in the other document, i.e. that have no page
+    /// in the base document to be shown on.
+    pub fn added_pages(&self) -> Range<usize> {
+        let n_base = self.base.pages().count() as usize;
+        let n_other = self.other.pages().count() as usize;
+
+        n_base..n_other.max(n_base)
+    }
+
+    pub fn diff(&self, page_index: usize) -> Option<&F::Data> {
+        match self.cache.get(&page_index) {
+            Some(CacheEntry::Cached(data)) => Some(data),
+            _ => None,
+        }
+    }
+
+    fn task(&self, page_index: usize, page_size: Vector2<i64>) -> impl FnOnce() -> Option<F::Data> {
+        let factory = self.factory.clone();
+        let base = self.base.clone();
+        let other = self.other.clone();
+        let opts = self.opts.clone();
+
+        move || {
+            let rect = Rect::new(point![0, 0], page_size);
+
+            let render = |doc: &Document| -> pdfium::Result<Bitmap> {
+                let page = doc.pages().get(page_index as _)?;
+                render_page_rect(&page, &page_size, &rect, &opts.render)
+            };
+
+            // pages past the end of the other document have nothing to be
+            // compared to, so they are entirely different; pages only in the
+            // other document are not shown, see `added_pages()`
+            let bmp_base = render(&base);
+            let bmp_other = if page_index < other.pages().count() as usize {
+                render(&other).map(Some)
+            } else {
+                Ok(None)
+            };
+
+            let (bmp_base, bmp_other) = match (bmp_base, bmp_other) {
+                (Ok(base), Ok(other)) => (base, other),
+                (Err(err), _) | (_, Err(err)) => {
+                    tracing::warn!(page = page_index, error = %err, "failed to render page for diff");
+                    return None;
+                }
+            };
+
+            let diff = heat_map(&bmp_base, bmp_other.as_ref(), opts.threshold);
+            Some(factory.create(diff))
+        }
+    }
+}
+
+/// Delay before the given retry of a failed diff.
+fn retry_delay(attempt: u32) -> Duration {
+    RETRY_DELAY * 2u32.pow(attempt.min(RETRY_BACKOFF_LIMIT))
+}
+
+fn heat_map(base: &Bitmap, other: Option<&Bitmap>, threshold: u8) -> Bitmap {
+    let stride = base.size.x as usize * 4;
+    let mut buffer = vec![0; stride * base.size.y as usize];
+
+    for y in 0..base.size.y as usize {
+        for x in 0..base.size.x as usize {
+            let i = y * base.stride as usize + x * 3;
+
+            // maximum per-channel difference, both bitmaps are BGR
+            let diff = match other {
+                Some(other) => {
+                    let j = y * other.stride as usize + x * 3;
+
+                    (0..3)
+                        .map(|c| base.buffer[i + c].abs_diff(other.buffer[j + c]))
+                        .max()
+                        .unwrap()
+                }
+                None => u8::MAX,
+            };
+
+            if diff <= threshold {
+                continue;
+            }
+
+            // premultiplied red, opacity scaling with the difference
+            let alpha = (96 + diff as u32 * 159 / 255) as u8;
+
+            let k = y * stride + x * 4;
+            buffer[k + 2] = alpha;
+            buffer[k + 3] = alpha;
+        }
+    }
+
+    Bitmap {
+        buffer: buffer.into(),
+        size: base.size,
+        stride: stride as _,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A BGR bitmap of the given pixels in a single row, with padding at the
+    /// end of the row.
+    fn bitmap(pixels: &[[u8; 3]]) -> Bitmap {
+        let stride = pixels.len() * 3 + 2;
+
+        let mut buffer = vec![0xff; stride];
+        for (x, px) in pixels.iter().enumerate() {
+            buffer[x * 3..x * 3 + 3].copy_from_slice(px);
+        }
+
+        Bitmap {
+            buffer: buffer.into(),
+            size: vector![pixels.len() as u32, 1],
+            stride: stride as _,
+        }
+    }
+
+    #[test]
+    fn heat_map_threshold() {
+        let base = bitmap(&[[10, 10, 10], [0, 0, 0], [255, 255, 255]]);
+        let other = bitmap(&[[12, 10, 8], [0, 200, 0], [255, 255, 255]]);
+
+        let diff = heat_map(&base, Some(&other), 5);
+        assert_eq!(diff.size, vector![3, 1]);
+        assert_eq!(diff.stride, 12);
+
+        // differences up to the threshold are transparent, others red with
+        // an opacity scaling with the largest channel difference
+        assert_eq!(diff.buffer[0..4], [0, 0, 0, 0]);
+        assert_eq!(diff.buffer[4..8], [0, 0, 220, 220]);
+        assert_eq!(diff.buffer[8..12], [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn heat_map_missing_page() {
+        let base = bitmap(&[[10, 10, 10], [0, 0, 0]]);
+
+        // pages without counterpart are entirely different
+        let diff = heat_map(&base, None, 5);
+        assert_eq!(diff.buffer[..], [0, 0, 255, 255, 0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn retry_backoff() {
+        assert_eq!(retry_delay(0), RETRY_DELAY);
+        assert_eq!(retry_delay(1), RETRY_DELAY * 2);
+        assert_eq!(retry_delay(100), retry_delay(RETRY_BACKOFF_LIMIT));
+    }
+}
@@ -0,0 +1,251 @@
+//! Persistent cache of rendered tiles.
+//!
+//! Low-resolution renders, i.e. thumbnails and the lowest fallback levels, are
+//! stored on disk, so that reopening a document shows them right away instead
+//! of rendering every page again. Entries are identified by the content hash
+//! of the document, the rendered region of the page, and the render options.
+//! Old entries are evicted by age and total size, see [`Area::TileCache`].
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use nalgebra::Vector2;
+
+use crate::core::storage::{Area, Storage};
+use crate::types::Rect;
+
+use super::interop::Bitmap;
+use super::pdfium::RenderOptions;
+
+/// Magic bytes at the start of each cache file, including a format version.
+const MAGIC: &[u8; 8] = b"PAPRTL01";
+
+/// Size of the file header: magic, width, and height.
+const HEADER_SIZE: usize = MAGIC.len() + 8;
+
+/// Bytes per pixel of cached bitmaps, which are always BGR.
+const BYTES_PER_PIXEL: usize = 3;
+
+/// Disk cache for tiles of a single document.
+///
+/// Clones share the same set of modified pages, so the same cache can be used
+/// for the document view and thumbnails.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    storage: Storage,
+    document: u64,
+    max_size: i64,
+
+    // pages changed since the document has been loaded, for which neither the
+    // cached tiles are valid nor new ones should be stored
+    modified: Mutex<HashSet<usize>>,
+}
+
+/// Identifies a rendered tile across runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileKey {
+    page_index: usize,
+    hash: u64,
+}
+
+impl TileKey {
+    pub fn new(
+        page_index: usize,
+        page_size: &Vector2<i64>,
+        rect: &Rect<i64>,
+        opts: &RenderOptions,
+    ) -> Self {
+        let mut hasher = DefaultHasher::new();
+        page_index.hash(&mut hasher);
+        page_size.hash(&mut hasher);
+        rect.offs.hash(&mut hasher);
+        rect.size.hash(&mut hasher);
+        opts.flags.bits().hash(&mut hasher);
+        opts.rotation.hash(&mut hasher);
+
+        let colors = opts.color_scheme.map(|cs| {
+            [
+                cs.path_fill_color,
+                cs.path_stroke_color,
+                cs.text_fill_color,
+                cs.text_stroke_color,
+            ]
+        });
+
+        let colors = std::iter::once(opts.background).chain(colors.into_iter().flatten());
+        for c in colors {
+            [c.r, c.g, c.b, c.a].hash(&mut hasher);
+        }
+
+        Self {
+            page_index,
+            hash: hasher.finish(),
+        }
+    }
+}
+
+impl DiskCache {
+    /// Cache for the document with the given content hash, storing renders
+    /// of pages no larger than the given size, in pixels.
+    pub fn new(storage: Storage, document: u64, max_size: i64) -> Self {
+        let inner = Inner {
+            storage,
+            document,
+            max_size,
+            modified: Mutex::new(HashSet::new()),
+        };
+
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Check if tiles of the given page rendered at the given size are
+    /// stored in this cache.
+    pub fn accepts(&self, page_index: usize, page_size: &Vector2<i64>) -> bool {
+        page_size.max() <= self.inner.max_size && !self.is_modified(page_index)
+    }
+
+    /// Mark the given page as modified, e.g. after adding an annotation.
+    /// Tiles of it are neither loaded from nor stored in the cache anymore.
+    pub fn invalidate_page(&self, page_index: usize) {
+        self.inner.modified.lock().unwrap().insert(page_index);
+    }
+
+    /// Load the given tile, if it has been stored before.
+    pub fn load(&self, key: &TileKey) -> Option<Bitmap> {
+        if self.is_modified(key.page_index) {
+            return None;
+        }
+
+        let path = self
+            .inner
+            .storage
+            .path(Area::TileCache, &self.file_name(key));
+
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return None,
+            Err(err) => {
+                tracing::debug!(file=?path, error=%err, "failed to read cached tile");
+                return None;
+            }
+        };
+
+        let bmp = decode(data);
+        if bmp.is_none() {
+            tracing::debug!(file=?path, "ignoring invalid cached tile");
+        }
+
+        bmp
+    }
+
+    /// Store the given tile, replacing any previous version of it.
+    ///
+    /// The size limit of the cache is not enforced here, as this is called
+    /// for each tile. Call [`evict()`](Self::evict) once in a while instead.
+    pub fn store(&self, key: &TileKey, bmp: &Bitmap) {
+        if self.is_modified(key.page_index) {
+            return;
+        }
+
+        let name = self.file_name(key);
+        let storage = &self.inner.storage;
+
+        if let Err(err) = storage.write_without_eviction(Area::TileCache, &name, &encode(bmp)) {
+            tracing::debug!(file = name, error=%err, "failed to store tile in cache");
+        }
+    }
+
+    /// Remove old entries and enforce the size limit of the cache.
+    pub fn evict(&self) {
+        if let Err(err) = self.inner.storage.enforce_limit(Area::TileCache, None) {
+            tracing::warn!(error=%err, "failed to evict cached tiles");
+        }
+    }
+
+    fn is_modified(&self, page_index: usize) -> bool {
+        self.inner.modified.lock().unwrap().contains(&page_index)
+    }
+
+    fn file_name(&self, key: &TileKey) -> String {
+        format!("{:016x}/{:016x}", self.inner.document, key.hash)
+    }
+}
+
+fn encode(bmp: &Bitmap) -> Vec<u8> {
+    let row = bmp.size.x as usize * BYTES_PER_PIXEL;
+
+    let mut data = Vec::with_capacity(HEADER_SIZE + row * bmp.size.y as usize);
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&bmp.size.x.to_le_bytes());
+    data.extend_from_slice(&bmp.size.y.to_le_bytes());
+
+    // store rows without any padding
+    for y in 0..bmp.size.y as usize {
+        let start = y * bmp.stride as usize;
+        data.extend_from_slice(&bmp.buffer[start..start + row]);
+    }
+
+    data
+}
+
+fn decode(mut data: Vec<u8>) -> Option<Bitmap> {
+    if data.len() < HEADER_SIZE || &data[..MAGIC.len()] != MAGIC {
+        return None;
+    }
+
+    let int = |offs: usize| u32::from_le_bytes(data[offs..offs + 4].try_into().unwrap());
+    let size = Vector2::new(int(MAGIC.len()), int(MAGIC.len() + 4));
+
+    let stride = size.x as usize * BYTES_PER_PIXEL;
+    if data.len() != HEADER_SIZE + stride * size.y as usize {
+        return None;
+    }
+
+    data.drain(..HEADER_SIZE);
+
+    Some(Bitmap {
+        buffer: data.into(),
+        size,
+        stride: stride as _,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_decode() {
+        // rows are padded in rendered bitmaps, but not in cache files
+        let bmp = Bitmap {
+            buffer: (0..16).collect(),
+            size: Vector2::new(2, 2),
+            stride: 8,
+        };
+
+        let data = encode(&bmp);
+        assert_eq!(data.len(), HEADER_SIZE + 12);
+
+        let decoded = decode(data.clone()).unwrap();
+        assert_eq!(decoded.size, bmp.size);
+        assert_eq!(decoded.stride, 6);
+        assert_eq!(
+            &decoded.buffer[..],
+            &[0, 1, 2, 3, 4, 5, 8, 9, 10, 11, 12, 13]
+        );
+
+        // truncated files are rejected
+        assert!(decode(data[..data.len() - 1].to_vec()).is_none());
+        assert!(decode(Vec::new()).is_none());
+    }
+}
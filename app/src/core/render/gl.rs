@@ -0,0 +1,338 @@
+//! Upload of rendered tiles to GL textures on the render threads.
+//!
+//! Memory textures are converted and uploaded by the renderer on the main
+//! thread when first drawn, which adds noticeable latency for large tiles.
+//! Instead, tiles can be uploaded right after rendering them, via GL contexts
+//! shared with the renderer. Each context streams bitmaps through its own
+//! pixel buffer, which is reused across uploads.
+//!
+//! GL functions are resolved via libepoxy, which GTK uses for its own GL
+//! calls, so that no further GL bindings are required.
+
+use std::ffi::c_void;
+use std::sync::{Arc, Mutex};
+
+use gtk::gdk;
+use gtk::prelude::{Cast, GLContextExt, SurfaceExt};
+
+use super::interop::Bitmap;
+
+/// Minimum GL version required for uploads, for mapping pixel buffers.
+const MIN_GL_VERSION: (i32, i32) = (3, 0);
+
+const GL_TEXTURE_2D: u32 = 0x0de1;
+const GL_TEXTURE_MAG_FILTER: u32 = 0x2800;
+const GL_TEXTURE_MIN_FILTER: u32 = 0x2801;
+const GL_TEXTURE_WRAP_S: u32 = 0x2802;
+const GL_TEXTURE_WRAP_T: u32 = 0x2803;
+const GL_LINEAR: i32 = 0x2601;
+const GL_CLAMP_TO_EDGE: i32 = 0x812f;
+const GL_UNPACK_ALIGNMENT: u32 = 0x0cf5;
+const GL_RGB8: i32 = 0x8051;
+const GL_BGR: u32 = 0x80e0;
+const GL_UNSIGNED_BYTE: u32 = 0x1401;
+const GL_PIXEL_UNPACK_BUFFER: u32 = 0x88ec;
+const GL_STREAM_DRAW: u32 = 0x88e0;
+const GL_MAP_WRITE_BIT: u32 = 0x0002;
+const GL_MAP_INVALIDATE_BUFFER_BIT: u32 = 0x0008;
+
+/// Reason why tiles cannot be uploaded to GL textures.
+#[derive(Debug)]
+pub enum UploadError {
+    /// The GL functions could not be loaded.
+    Load(libloading::Error),
+
+    /// No GL context could be created.
+    Context(gtk::glib::Error),
+
+    /// The GL context does not support uploads, e.g. GLES or too old.
+    Unsupported,
+}
+
+type GenFn = unsafe extern "C" fn(i32, *mut u32);
+type DeleteFn = unsafe extern "C" fn(i32, *const u32);
+type BindFn = unsafe extern "C" fn(u32, u32);
+type ParameterFn = unsafe extern "C" fn(u32, u32, i32);
+type PixelStoreFn = unsafe extern "C" fn(u32, i32);
+type TexImageFn = unsafe extern "C" fn(u32, i32, i32, i32, i32, i32, u32, u32, *const c_void);
+type BufferDataFn = unsafe extern "C" fn(u32, isize, *const c_void, u32);
+type MapBufferRangeFn = unsafe extern "C" fn(u32, isize, isize, u32) -> *mut c_void;
+type UnmapBufferFn = unsafe extern "C" fn(u32) -> u8;
+type FinishFn = unsafe extern "C" fn();
+
+/// GL functions used for uploads.
+///
+/// libepoxy exports its functions as global function pointers, which are
+/// replaced by the resolved function on their first call. Hence, pointers
+/// to these variables are stored and read on every call.
+struct GlFunctions {
+    _lib: libloading::Library,
+    gen_textures: *const GenFn,
+    delete_textures: *const DeleteFn,
+    bind_texture: *const BindFn,
+    tex_parameter: *const ParameterFn,
+    pixel_store: *const PixelStoreFn,
+    tex_image: *const TexImageFn,
+    gen_buffers: *const GenFn,
+    delete_buffers: *const DeleteFn,
+    bind_buffer: *const BindFn,
+    buffer_data: *const BufferDataFn,
+    map_buffer_range: *const MapBufferRangeFn,
+    unmap_buffer: *const UnmapBufferFn,
+    finish: *const FinishFn,
+}
+
+// SAFETY: The function pointers refer to global variables of libepoxy, which
+// stay valid as long as the library is loaded, and can be used from any
+// thread with a current GL context.
+unsafe impl Send for GlFunctions {}
+unsafe impl Sync for GlFunctions {}
+
+impl GlFunctions {
+    fn load() -> Result<Self, libloading::Error> {
+        // SAFETY: libepoxy has no initialization routines with side effects.
+        let lib = unsafe { libloading::Library::new("libepoxy.so.0")? };
+
+        // SAFETY: The symbols are function pointer variables of the given
+        // types, see the OpenGL specification.
+        unsafe {
+            Ok(Self {
+                gen_textures: symbol(&lib, b"epoxy_glGenTextures\0")?,
+                delete_textures: symbol(&lib, b"epoxy_glDeleteTextures\0")?,
+                bind_texture: symbol(&lib, b"epoxy_glBindTexture\0")?,
+                tex_parameter: symbol(&lib, b"epoxy_glTexParameteri\0")?,
+                pixel_store: symbol(&lib, b"epoxy_glPixelStorei\0")?,
+                tex_image: symbol(&lib, b"epoxy_glTexImage2D\0")?,
+                gen_buffers: symbol(&lib, b"epoxy_glGenBuffers\0")?,
+                delete_buffers: symbol(&lib, b"epoxy_glDeleteBuffers\0")?,
+                bind_buffer: symbol(&lib, b"epoxy_glBindBuffer\0")?,
+                buffer_data: symbol(&lib, b"epoxy_glBufferData\0")?,
+                map_buffer_range: symbol(&lib, b"epoxy_glMapBufferRange\0")?,
+                unmap_buffer: symbol(&lib, b"epoxy_glUnmapBuffer\0")?,
+                finish: symbol(&lib, b"epoxy_glFinish\0")?,
+                _lib: lib,
+            })
+        }
+    }
+}
+
+/// Pointer to the function pointer variable with the given name.
+unsafe fn symbol<T>(lib: &libloading::Library, name: &[u8]) -> Result<*const T, libloading::Error> {
+    let var = lib.get::<*const T>(name)?;
+    Ok(*var)
+}
+
+/// A GL context used for uploads, with its pixel buffer and the capacity of
+/// it in bytes, if created already.
+struct UploadContext {
+    context: gdk::GLContext,
+    buffer: Option<(u32, usize)>,
+}
+
+// SAFETY: GDK allows using GL contexts on any thread, as long as they are
+// current on one thread at a time only. Upload contexts are taken out of the
+// pool while in use, which ensures exactly that.
+unsafe impl Send for UploadContext {}
+
+/// Uploader for rendered tiles, using a pool of GL contexts shared with the
+/// renderer of a surface.
+pub struct GlUploader {
+    gl: GlFunctions,
+    contexts: Mutex<Vec<UploadContext>>,
+
+    // textures that are no longer used, deleted on the next upload as this
+    // requires a current context, textures released after the uploader has
+    // been dropped are left to the driver
+    released: Arc<Mutex<Vec<u32>>>,
+}
+
+impl GlUploader {
+    /// Create an uploader with the given number of contexts, i.e. the
+    /// number of threads that can upload at the same time.
+    ///
+    /// Must be called on the main thread.
+    pub fn new(surface: &gdk::Surface, count: usize) -> Result<Self, UploadError> {
+        let gl = GlFunctions::load().map_err(UploadError::Load)?;
+
+        let mut contexts = Vec::with_capacity(count);
+        for _ in 0..count {
+            let context = surface.create_gl_context().map_err(UploadError::Context)?;
+            context.realize().map_err(UploadError::Context)?;
+
+            if context.uses_es() || context.version() < MIN_GL_VERSION {
+                return Err(UploadError::Unsupported);
+            }
+
+            contexts.push(UploadContext {
+                context,
+                buffer: None,
+            });
+        }
+
+        Ok(Self {
+            gl,
+            contexts: Mutex::new(contexts),
+            released: Arc::new(Mutex::new(Vec::new())),
+        })
+    }
+
+    /// Upload the given bitmap to a GL texture. Hands the bitmap back if it
+    /// cannot be uploaded, e.g. because all contexts are in use.
+    pub fn upload(&self, bmp: Bitmap) -> Result<gdk::Texture, Bitmap> {
+        let ctx = self.contexts.lock().unwrap().pop();
+
+        let mut ctx = match ctx {
+            Some(ctx) => ctx,
+            None => return Err(bmp),
+        };
+
+        ctx.context.make_current();
+
+        // SAFETY: The context of the texture is current on this thread.
+        let texture = unsafe { self.upload_current(&mut ctx, &bmp) };
+
+        gdk::GLContext::clear_current();
+        self.contexts.lock().unwrap().push(ctx);
+
+        texture.ok_or(bmp)
+    }
+
+    unsafe fn upload_current(&self, ctx: &mut UploadContext, bmp: &Bitmap) -> Option<gdk::Texture> {
+        let gl = &self.gl;
+
+        // delete textures released in the meantime, names are shared across
+        // contexts so any context will do
+        let released = std::mem::take(&mut *self.released.lock().unwrap());
+        if !released.is_empty() {
+            (*gl.delete_textures)(released.len() as _, released.as_ptr());
+        }
+
+        let (width, height) = (bmp.size.x as usize, bmp.size.y as usize);
+        let row = width * 3;
+        let len = row * height;
+
+        // grow the pixel buffer if needed, keeping it for further uploads
+        let buffer = match ctx.buffer {
+            Some((buffer, capacity)) if capacity >= len => buffer,
+            previous => {
+                if let Some((buffer, _)) = previous {
+                    (*gl.delete_buffers)(1, &buffer);
+                }
+
+                let mut buffer = 0;
+                (*gl.gen_buffers)(1, &mut buffer);
+                ctx.buffer = Some((buffer, len));
+
+                let data = std::ptr::null();
+                (*gl.bind_buffer)(GL_PIXEL_UNPACK_BUFFER, buffer);
+                (*gl.buffer_data)(GL_PIXEL_UNPACK_BUFFER, len as _, data, GL_STREAM_DRAW);
+                buffer
+            }
+        };
+
+        // copy rows tightly packed, as bitmap strides need not be a multiple
+        // of the pixel size
+        (*gl.bind_buffer)(GL_PIXEL_UNPACK_BUFFER, buffer);
+
+        let access = GL_MAP_WRITE_BIT | GL_MAP_INVALIDATE_BUFFER_BIT;
+        let data = (*gl.map_buffer_range)(GL_PIXEL_UNPACK_BUFFER, 0, len as _, access);
+
+        if data.is_null() {
+            (*gl.bind_buffer)(GL_PIXEL_UNPACK_BUFFER, 0);
+            return None;
+        }
+
+        let data = std::slice::from_raw_parts_mut(data as *mut u8, len);
+        for (y, dst) in data.chunks_exact_mut(row).enumerate() {
+            dst.copy_from_slice(&bmp.buffer[y * bmp.stride as usize..][..row]);
+        }
+
+        (*gl.unmap_buffer)(GL_PIXEL_UNPACK_BUFFER);
+
+        let mut texture = 0;
+        (*gl.gen_textures)(1, &mut texture);
+        (*gl.bind_texture)(GL_TEXTURE_2D, texture);
+
+        (*gl.tex_parameter)(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_LINEAR);
+        (*gl.tex_parameter)(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_LINEAR);
+        (*gl.tex_parameter)(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
+        (*gl.tex_parameter)(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
+
+        // rendered bitmaps are BGR, read from the pixel buffer at offset zero
+        (*gl.pixel_store)(GL_UNPACK_ALIGNMENT, 1);
+        (*gl.tex_image)(
+            GL_TEXTURE_2D,
+            0,
+            GL_RGB8,
+            width as _,
+            height as _,
+            0,
+            GL_BGR,
+            GL_UNSIGNED_BYTE,
+            std::ptr::null(),
+        );
+
+        (*gl.bind_texture)(GL_TEXTURE_2D, 0);
+        (*gl.bind_buffer)(GL_PIXEL_UNPACK_BUFFER, 0);
+
+        // the renderer uses the texture from another context, so it has to
+        // be complete before handing it over
+        (*gl.finish)();
+
+        let released = self.released.clone();
+        let texture = gdk::GLTexture::with_release_func(
+            &ctx.context,
+            texture,
+            width as _,
+            height as _,
+            move || released.lock().unwrap().push(texture),
+        );
+
+        Some(texture.upcast())
+    }
+}
+
+impl Drop for GlUploader {
+    fn drop(&mut self) {
+        let gl = &self.gl;
+
+        for ctx in self.contexts.get_mut().unwrap().drain(..) {
+            if let Some((buffer, _)) = ctx.buffer {
+                ctx.context.make_current();
+
+                // SAFETY: The context owning the buffer is current.
+                unsafe { (*gl.delete_buffers)(1, &buffer) };
+            }
+        }
+
+        gdk::GLContext::clear_current();
+    }
+}
+
+impl std::fmt::Debug for GlUploader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GlUploader")
+            .field("contexts", &self.contexts.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl std::fmt::Display for UploadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UploadError::Load(err) => write!(f, "failed to load GL functions: {err}"),
+            UploadError::Context(err) => write!(f, "failed to create GL context: {err}"),
+            UploadError::Unsupported => write!(f, "GL context does not support uploads"),
+        }
+    }
+}
+
+impl std::error::Error for UploadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UploadError::Load(err) => Some(err),
+            UploadError::Context(err) => Some(err),
+            UploadError::Unsupported => None,
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Rendering of page regions at high resolution, for close inspection of fine
+//! print and figure details.
+
+use std::sync::atomic::AtomicBool;
+
+use nalgebra::{point, vector};
+
+use pdfium::doc::Page;
+
+use crate::types::Rect;
+
+use super::interop::Bitmap;
+use super::pdfium::{render_page_rect_cancelable, RenderOptions};
+
+/// Resolutions at which a region is rendered, in dots per inch. Regions are
+/// rendered progressively, so that a preview can be shown while the final
+/// resolution is still being rendered.
+pub const PASSES: [f64; 3] = [150.0, 300.0, 600.0];
+
+/// Maximum number of pixels of a rendered region. Larger regions are rendered
+/// at a reduced resolution.
+const MAX_PIXELS: f64 = 48.0 * 1024.0 * 1024.0;
+
+/// Resolution at which the given region, in page coordinates (PDF points),
+/// is actually rendered when requesting the given resolution.
+pub fn effective_dpi(region: &Rect<f64>, dpi: f64) -> f64 {
+    let pixels = region.size.x * region.size.y * (dpi / 72.0).powi(2);
+
+    if pixels > MAX_PIXELS {
+        dpi * (MAX_PIXELS / pixels).sqrt()
+    } else {
+        dpi
+    }
+}
+
+/// Render the given region of a page, in page coordinates (PDF points), at
+/// the given resolution, limited by [`effective_dpi()`].
+///
+/// Returns `None` if the page is empty, if rendering failed, or if the render
+/// has been aborted due to the `canceled` flag being set.
+pub fn render_region(
+    page: &Page,
+    region: &Rect<f64>,
+    dpi: f64,
+    opts: &RenderOptions,
+    canceled: &AtomicBool,
+) -> Option<Bitmap> {
+    let scale = effective_dpi(region, dpi) / 72.0;
+
+    // page size in pixels
+    let page_size = vector![
+        (page.width() as f64 * scale).round() as i64,
+        (page.height() as f64 * scale).round() as i64
+    ];
+
+    if page_size.x <= 0 || page_size.y <= 0 {
+        return None;
+    }
+
+    // region in pixels, clipped to the page and with at least one pixel
+    let x0 = ((region.offs.x * scale).floor() as i64).clamp(0, page_size.x - 1);
+    let y0 = ((region.offs.y * scale).floor() as i64).clamp(0, page_size.y - 1);
+    let x1 = (((region.offs.x + region.size.x) * scale).ceil() as i64).clamp(x0 + 1, page_size.x);
+    let y1 = (((region.offs.y + region.size.y) * scale).ceil() as i64).clamp(y0 + 1, page_size.y);
+
+    let rect = Rect::new(point![x0, y0], vector![x1 - x0, y1 - y0]);
+
+    match render_page_rect_cancelable(page, None, &page_size, &rect, opts, canceled, None) {
+        Ok(bmp) => bmp,
+        Err(err) => {
+            tracing::warn!(error = %err, "failed to render region");
+            None
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use nalgebra::Vector2;
 
+use super::pool::Buffer;
+
 pub struct Bitmap {
-    pub buffer: Box<[u8]>,
+    pub buffer: Buffer,
     pub size: Vector2<u32>,
     pub stride: u32,
 }
@@ -1,25 +1,202 @@
 use nalgebra::{point, vector};
 
+use pdfium::doc::Document;
+
 use crate::types::{Bounds, Rect};
 
+use super::core::PageCrop;
+
+/// Minimum and maximum page dimension in PDF points, as allowed by the PDF
+/// specification (ISO 32000-1, annex C).
+const PAGE_SIZE_RANGE: (f64, f64) = (3.0, 14_400.0);
+
+/// Size used for pages without a valid size, i.e., US Letter.
+const DEFAULT_PAGE_SIZE: (f64, f64) = (612.0, 792.0);
+
+/// A problem with the size of a page, corrected when computing the layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageSizeIssue {
+    /// The page has no area or its size is not a finite number. It is laid
+    /// out with a default size instead.
+    Invalid,
+
+    /// The page is smaller or larger than allowed, e.g. extremely skewed. Its
+    /// dimensions are clamped to the allowed range.
+    Clamped,
+}
+
 pub struct Layout {
     pub bounds: Bounds<f64>,
     pub rects: Vec<Rect<f64>>,
+
+    /// Pages of which the size had to be corrected, with their index.
+    pub issues: Vec<(usize, PageSizeIssue)>,
+
+    /// Crops of the pages, indexed like `rects`. Cropped pages are laid out
+    /// with the size of their crop rectangle.
+    pub crops: Vec<Option<PageCrop>>,
 }
 
 pub trait LayoutProvider {
     fn compute(&self, page_sizes: impl IntoIterator<Item = (f64, f64)>, space: f64) -> Layout;
+
+    /// Compute the layout for pages given by their full size and an optional
+    /// crop rectangle, both in PDF points.
+    fn compute_cropped(
+        &self,
+        pages: impl IntoIterator<Item = ((f64, f64), Option<Rect<f64>>)>,
+        space: f64,
+    ) -> Layout {
+        let pages: Vec<_> = pages.into_iter().collect();
+
+        let sizes = pages.iter().map(|(size, crop)| match crop {
+            Some(rect) => (rect.size.x, rect.size.y),
+            None => *size,
+        });
+
+        let mut layout = self.compute(sizes, space);
+
+        layout.crops = pages
+            .iter()
+            .map(|((w, h), crop)| {
+                crop.map(|rect| PageCrop {
+                    page_size: vector![*w, *h],
+                    rect,
+                })
+            })
+            .collect();
+
+        layout
+    }
 }
 
 pub struct VerticalLayout;
 pub struct HorizontalLayout;
 
+/// Two pages side by side, like the spreads of a book. Left pages are
+/// aligned to the right, right pages to the left, with the space in between.
+pub struct DualPageLayout {
+    /// Show the first page on its own on the right, like the cover of a book,
+    /// so that even pages are on the left.
+    pub cover: bool,
+}
+
+/// Pages in rows of a fixed number of columns, each page centered in its cell.
+pub struct GridLayout {
+    pub columns: usize,
+}
+
+/// Number of columns of the grid layout selected at runtime.
+const GRID_COLUMNS: usize = 3;
+
+/// How pages are arranged, selected at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayoutKind {
+    #[default]
+    Vertical,
+    Horizontal,
+    DualPage,
+    DualPageCover,
+    Grid,
+}
+
+impl LayoutKind {
+    pub const ALL: [LayoutKind; 5] = [
+        LayoutKind::Vertical,
+        LayoutKind::Horizontal,
+        LayoutKind::DualPage,
+        LayoutKind::DualPageCover,
+        LayoutKind::Grid,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LayoutKind::Vertical => "vertical",
+            LayoutKind::Horizontal => "horizontal",
+            LayoutKind::DualPage => "dual",
+            LayoutKind::DualPageCover => "dual-cover",
+            LayoutKind::Grid => "grid",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "vertical" => Some(LayoutKind::Vertical),
+            "horizontal" => Some(LayoutKind::Horizontal),
+            "dual" => Some(LayoutKind::DualPage),
+            "dual-cover" => Some(LayoutKind::DualPageCover),
+            "grid" => Some(LayoutKind::Grid),
+            _ => None,
+        }
+    }
+}
+
+/// Sizes of all pages of the given document in PDF points, as displayed.
+///
+/// Sizes reported by pdfium honor the `/Rotate` entry of pages, i.e. width
+/// and height are swapped for pages rotated by 90 or 270 degrees. Pages of
+/// which the size cannot be determined are reported with zero size, to be
+/// laid out with a default size.
+pub fn page_sizes(doc: &Document) -> Vec<(f64, f64)> {
+    (0..doc.pages().count())
+        .map(|i| match doc.pages().get_size(i) {
+            Ok(size) => size,
+            Err(err) => {
+                tracing::warn!(page = i, error=%err, "failed to get page size");
+                (0.0, 0.0)
+            }
+        })
+        .collect()
+}
+
+/// Validate the given page size (width, height), returning a size that can
+/// be laid out and the issue with the original size, if any.
+fn sanitize_page_size((w, h): (f64, f64)) -> ((f64, f64), Option<PageSizeIssue>) {
+    if !(w.is_finite() && h.is_finite() && w > 0.0 && h > 0.0) {
+        return (DEFAULT_PAGE_SIZE, Some(PageSizeIssue::Invalid));
+    }
+
+    let (min, max) = PAGE_SIZE_RANGE;
+    let size = (w.clamp(min, max), h.clamp(min, max));
+    let issue = (size != (w, h)).then_some(PageSizeIssue::Clamped);
+
+    (size, issue)
+}
+
+/// Page rectangles at the origin for the given page sizes, corrected where
+/// necessary, and the issues found.
+fn page_rects(
+    page_sizes: impl IntoIterator<Item = (f64, f64)>,
+) -> (Vec<Rect<f64>>, Vec<(usize, PageSizeIssue)>) {
+    let mut issues = Vec::new();
+
+    let rects = page_sizes
+        .into_iter()
+        .enumerate()
+        .map(|(i, size)| {
+            let ((w, h), issue) = sanitize_page_size(size);
+
+            if let Some(issue) = issue {
+                tracing::warn!(
+                    page = i,
+                    width = size.0,
+                    height = size.1,
+                    ?issue,
+                    "invalid page size"
+                );
+                issues.push((i, issue));
+            }
+
+            Rect::new(point![0.0, 0.0], vector![w, h])
+        })
+        .collect();
+
+    (rects, issues)
+}
+
 impl LayoutProvider for VerticalLayout {
     fn compute(&self, page_sizes: impl IntoIterator<Item = (f64, f64)>, space: f64) -> Layout {
-        let mut rects: Vec<Rect<f64>> = page_sizes
-            .into_iter()
-            .map(|(w, h)| Rect::new(point![0.0, 0.0], vector![w, h]))
-            .collect();
+        let (mut rects, issues) = page_rects(page_sizes);
 
         let mut bounds = Bounds::zero();
         bounds.x_max = rects
@@ -41,16 +218,20 @@ impl LayoutProvider for VerticalLayout {
             bounds.y_max += r.size.y;
         }
 
-        Layout { bounds, rects }
+        let crops = vec![None; rects.len()];
+
+        Layout {
+            bounds,
+            rects,
+            issues,
+            crops,
+        }
     }
 }
 
 impl LayoutProvider for HorizontalLayout {
     fn compute(&self, page_sizes: impl IntoIterator<Item = (f64, f64)>, space: f64) -> Layout {
-        let mut rects: Vec<Rect<f64>> = page_sizes
-            .into_iter()
-            .map(|(w, h)| Rect::new(point![0.0, 0.0], vector![w, h]))
-            .collect();
+        let (mut rects, issues) = page_rects(page_sizes);
 
         let mut bounds = Bounds::zero();
         bounds.y_max = rects
@@ -72,6 +253,252 @@ impl LayoutProvider for HorizontalLayout {
             bounds.x_max += r.size.x;
         }
 
-        Layout { bounds, rects }
+        let crops = vec![None; rects.len()];
+
+        Layout {
+            bounds,
+            rects,
+            issues,
+            crops,
+        }
+    }
+}
+
+impl LayoutProvider for DualPageLayout {
+    fn compute(&self, page_sizes: impl IntoIterator<Item = (f64, f64)>, space: f64) -> Layout {
+        let (mut rects, issues) = page_rects(page_sizes);
+
+        // left pages are in column zero, the cover takes the place of a right
+        // page
+        let column = |i: usize| (i + self.cover as usize) % 2;
+
+        let (mut left, mut right) = (0.0_f64, 0.0_f64);
+        for (i, r) in rects.iter().enumerate() {
+            if column(i) == 0 {
+                left = left.max(r.size.x);
+            } else {
+                right = right.max(r.size.x);
+            }
+        }
+
+        // pages of a spread share a row, the cover has a row on its own
+        let row = |i: usize| (i + self.cover as usize) / 2;
+        let rows = rects.len().checked_sub(1).map_or(0, |i| row(i) + 1);
+
+        let mut heights = vec![0.0_f64; rows];
+        for (i, r) in rects.iter().enumerate() {
+            heights[row(i)] = heights[row(i)].max(r.size.y);
+        }
+
+        let mut bounds = Bounds::zero();
+        bounds.x_max = left + space + right;
+
+        let mut tops = Vec::with_capacity(rows);
+        for (n, height) in heights.iter().enumerate() {
+            if n > 0 {
+                bounds.y_max += space;
+            }
+
+            tops.push(bounds.y_max);
+            bounds.y_max += height;
+        }
+
+        for (i, r) in rects.iter_mut().enumerate() {
+            let x = if column(i) == 0 {
+                left - r.size.x
+            } else {
+                left + space
+            };
+            let y = tops[row(i)] + (heights[row(i)] - r.size.y) / 2.0;
+
+            r.offs = point![x, y];
+        }
+
+        let crops = vec![None; rects.len()];
+
+        Layout {
+            bounds,
+            rects,
+            issues,
+            crops,
+        }
+    }
+}
+
+impl LayoutProvider for GridLayout {
+    fn compute(&self, page_sizes: impl IntoIterator<Item = (f64, f64)>, space: f64) -> Layout {
+        let (mut rects, issues) = page_rects(page_sizes);
+        let columns = self.columns.max(1);
+
+        let width = rects
+            .iter()
+            .fold(0.0, |x: f64, r: &Rect<f64>| x.max(r.size.x));
+
+        let mut bounds = Bounds::zero();
+        if !rects.is_empty() {
+            let n = columns.min(rects.len()) as f64;
+            bounds.x_max = n * width + (n - 1.0) * space;
+        }
+
+        for (n, row) in rects.chunks_mut(columns).enumerate() {
+            if n > 0 {
+                bounds.y_max += space;
+            }
+
+            let height = row.iter().fold(0.0_f64, |h, r| h.max(r.size.y));
+
+            for (col, r) in row.iter_mut().enumerate() {
+                let x = col as f64 * (width + space) + (width - r.size.x) / 2.0;
+                let y = bounds.y_max + (height - r.size.y) / 2.0;
+
+                r.offs = point![x, y];
+            }
+
+            bounds.y_max += height;
+        }
+
+        let crops = vec![None; rects.len()];
+
+        Layout {
+            bounds,
+            rects,
+            issues,
+            crops,
+        }
+    }
+}
+
+impl LayoutProvider for LayoutKind {
+    fn compute(&self, page_sizes: impl IntoIterator<Item = (f64, f64)>, space: f64) -> Layout {
+        match self {
+            LayoutKind::Vertical => VerticalLayout.compute(page_sizes, space),
+            LayoutKind::Horizontal => HorizontalLayout.compute(page_sizes, space),
+            LayoutKind::DualPage => DualPageLayout { cover: false }.compute(page_sizes, space),
+            LayoutKind::DualPageCover => DualPageLayout { cover: true }.compute(page_sizes, space),
+            LayoutKind::Grid => GridLayout {
+                columns: GRID_COLUMNS,
+            }
+            .compute(page_sizes, space),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_finite(layout: &Layout) {
+        assert!(layout.bounds.x_max.is_finite() && layout.bounds.y_max.is_finite());
+
+        for r in &layout.rects {
+            assert!(r.offs.x.is_finite() && r.offs.y.is_finite());
+            assert!(r.size.x >= PAGE_SIZE_RANGE.0 && r.size.y >= PAGE_SIZE_RANGE.0);
+            assert!(r.size.x <= PAGE_SIZE_RANGE.1 && r.size.y <= PAGE_SIZE_RANGE.1);
+        }
+    }
+
+    #[test]
+    fn valid_pages() {
+        let layout = VerticalLayout.compute([(200.0, 300.0), (300.0, 200.0)], 10.0);
+
+        assert!(layout.issues.is_empty());
+        assert_eq!(layout.rects[0].size, vector![200.0, 300.0]);
+        assert_eq!(layout.rects[1].size, vector![300.0, 200.0]);
+        assert_eq!(layout.bounds.x_max, 300.0);
+        assert_eq!(layout.bounds.y_max, 510.0);
+    }
+
+    #[test]
+    fn zero_sized_pages() {
+        let sizes = [(0.0, 0.0), (200.0, 300.0), (0.0, 300.0)];
+
+        for layout in [
+            VerticalLayout.compute(sizes, 10.0),
+            HorizontalLayout.compute(sizes, 10.0),
+        ] {
+            assert_finite(&layout);
+            assert_eq!(
+                layout.issues,
+                [(0, PageSizeIssue::Invalid), (2, PageSizeIssue::Invalid)]
+            );
+
+            let (w, h) = DEFAULT_PAGE_SIZE;
+            assert_eq!(layout.rects[0].size, vector![w, h]);
+            assert_eq!(layout.rects[1].size, vector![200.0, 300.0]);
+        }
+    }
+
+    #[test]
+    fn non_finite_pages() {
+        let sizes = [(f64::NAN, 300.0), (200.0, f64::INFINITY), (-200.0, 300.0)];
+        let layout = VerticalLayout.compute(sizes, 10.0);
+
+        assert_finite(&layout);
+        assert_eq!(layout.issues.len(), 3);
+        assert!(layout
+            .issues
+            .iter()
+            .all(|(_, i)| *i == PageSizeIssue::Invalid));
+    }
+
+    #[test]
+    fn skewed_pages() {
+        let sizes = [(1e-3, 300.0), (1e6, 200.0)];
+        let layout = VerticalLayout.compute(sizes, 10.0);
+
+        assert_finite(&layout);
+        assert_eq!(
+            layout.issues,
+            [(0, PageSizeIssue::Clamped), (1, PageSizeIssue::Clamped)]
+        );
+        assert_eq!(layout.rects[0].size, vector![3.0, 300.0]);
+        assert_eq!(layout.rects[1].size, vector![14_400.0, 200.0]);
+    }
+
+    #[test]
+    fn empty_document() {
+        for kind in LayoutKind::ALL {
+            let layout = kind.compute([], 10.0);
+
+            assert!(layout.rects.is_empty());
+            assert_eq!(layout.bounds.x_max, 0.0);
+            assert_eq!(layout.bounds.y_max, 0.0);
+        }
+    }
+
+    #[test]
+    fn dual_pages() {
+        let sizes = [(200.0, 300.0), (100.0, 200.0), (200.0, 300.0)];
+
+        // spreads of pages 0-1 and 2, aligned at the space in between
+        let layout = DualPageLayout { cover: false }.compute(sizes, 10.0);
+        assert_eq!(layout.bounds.x_max, 310.0);
+        assert_eq!(layout.bounds.y_max, 610.0);
+        assert_eq!(layout.rects[0].offs, point![0.0, 0.0]);
+        assert_eq!(layout.rects[1].offs, point![210.0, 50.0]);
+        assert_eq!(layout.rects[2].offs, point![0.0, 310.0]);
+
+        // the cover is shown on its own on the right
+        let layout = DualPageLayout { cover: true }.compute(sizes, 10.0);
+        assert_eq!(layout.bounds.x_max, 310.0);
+        assert_eq!(layout.bounds.y_max, 610.0);
+        assert_eq!(layout.rects[0].offs, point![110.0, 0.0]);
+        assert_eq!(layout.rects[1].offs, point![0.0, 360.0]);
+        assert_eq!(layout.rects[2].offs, point![110.0, 310.0]);
+    }
+
+    #[test]
+    fn grid_pages() {
+        let sizes = [(200.0, 300.0), (100.0, 200.0), (200.0, 300.0)];
+        let layout = GridLayout { columns: 2 }.compute(sizes, 10.0);
+
+        assert!(layout
+            .rects
+            .iter()
+            .all(|r| layout.bounds.contains(&Bounds::from(*r))));
+        assert_eq!(layout.bounds.x_max, 410.0);
+        assert_eq!(layout.bounds.y_max, 610.0);
+        assert_eq!(layout.rects[1].offs, point![260.0, 50.0]);
+        assert_eq!(layout.rects[2].offs, point![0.0, 310.0]);
     }
 }
@@ -1,4 +1,14 @@
+pub mod cache;
 pub mod core;
+pub mod diff;
+pub mod disk;
+pub mod gl;
+pub mod inspect;
 pub mod interop;
 pub mod layout;
 pub mod pdfium;
+pub mod pool;
+pub mod preview;
+pub mod print;
+pub mod rotation;
+pub mod telemetry;
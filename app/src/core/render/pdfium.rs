@@ -1,107 +1,214 @@
+use std::cell::Cell;
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use executor::exec::Monitor;
+use executor::exec::priority::DropHandle;
+use executor::exec::{Metrics, Monitor};
 
 use nalgebra as na;
 use nalgebra::Vector2;
 
-use pdfium::bitmap::{BitmapFormat, Color};
-use pdfium::doc::{Document, Page, PageRenderLayout, PageRotation, RenderFlags};
+use pdfium::bitmap::{BitmapFormat, Color, ColorScheme};
+use pdfium::doc::{
+    Document, Page, PageRenderLayout, PageRotation, ProgressiveRenderStatus, RenderFlags,
+};
 
 use crate::types::Rect;
 
+use super::cache::{PageCache, PinnedPage};
+use super::disk::{DiskCache, TileKey};
 use super::interop::{Bitmap, TileFactory};
-use super::core::{TilePriority, TileProvider, TileSource};
+use super::pool::BufferPool;
+use super::core::{TileHandle, TilePriority, TileProvider, TileRequest, TileSource};
+use super::telemetry::RenderStats;
 
 pub type Executor = executor::exec::priority::Executor<TilePriority>;
-pub type Handle<R> = executor::exec::priority::DropHandle<TilePriority, R>;
+
+/// Number of threads to render tiles of a document with, based on the
+/// available parallelism.
+///
+/// Renders only overlap while paused, see [`RENDER_SLICE`], so more threads
+/// than [`MAX_RENDER_THREADS`] mostly add contention on the pdfium lock.
+pub fn render_threads() -> u32 {
+    let n = std::thread::available_parallelism().map_or(1, |n| n.get());
+    (n as u32).clamp(1, MAX_RENDER_THREADS)
+}
+
+/// Tiles with more pixels than this are rendered progressively, so that their
+/// render can be aborted when the task gets canceled during execution.
+const PROGRESSIVE_RENDER_THRESHOLD: i64 = 512 * 512;
+
+/// Maximum time a progressive render runs before pausing.
+///
+/// Calls into pdfium are serialized by a global lock, which is released while
+/// a render is paused. Pausing regularly therefore lets other render threads,
+/// e.g. loading pages or rendering small tiles, as well as the UI interleave
+/// with long renders instead of waiting for them to finish.
+const RENDER_SLICE: Duration = Duration::from_millis(8);
+
+/// Maximum number of render threads per document.
+const MAX_RENDER_THREADS: u32 = 4;
+
+/// Handle for a render task, canceling the task when dropped.
+///
+/// In contrast to a plain task handle, this also aborts the render if the task
+/// is already being executed.
+pub struct Handle<R> {
+    task: DropHandle<TilePriority, Option<Result<R, TileError>>>,
+    _cancel: CancelOnDrop,
+}
+
+struct CancelOnDrop {
+    canceled: Arc<AtomicBool>,
+}
+
+/// Reason why a tile could not be rendered.
+#[derive(Debug)]
+pub enum TileError {
+    /// The page could not be loaded, e.g. because its page object is corrupt.
+    PageLoad(pdfium::Error),
+
+    /// The page has been loaded but rendering it failed.
+    Render(pdfium::Error),
+
+    /// The render has been aborted before completing.
+    Aborted,
+}
+
+/// Region of a page affected by a render task, passed as context to the
+/// monitor of the task.
+#[derive(Debug, Clone, Copy)]
+pub struct Damage {
+    pub page_index: usize,
+
+    /// Size of the full page, in pixels
+    pub page_size: Vector2<i64>,
+
+    /// Rendered part of the page, in pixels
+    pub rect: Rect<i64>,
+}
 
 pub struct PdfTileProvider<M, F> {
-    executor: Executor,
+    executor: Arc<Executor>,
     monitor: M,
     factory: F,
     document: Document,
-    page_cache: Arc<Mutex<HashMap<usize, Page>>>,
+    page_cache: PageCache,
+    disk_cache: Option<DiskCache>,
+    buffers: BufferPool,
+    stats: RenderStats,
 }
 
 pub struct PdfTileSource<'a, M, F> {
     provider: &'a mut PdfTileProvider<M, F>,
-    pages: Range<usize>,
 }
 
 #[derive(Debug, Clone)]
 pub struct RenderOptions {
     pub flags: RenderFlags,
     pub background: Color,
+
+    /// Colors forced onto text and paths, e.g. for night reading. Images are
+    /// rendered with their original colors.
+    pub color_scheme: Option<ColorScheme>,
+
+    /// Rotation of pages. Page sizes given for rendering are those of the
+    /// rotated page.
+    pub rotation: PageRotation,
 }
 
 impl<M, F> PdfTileProvider<M, F> {
-    pub fn new(executor: Executor, monitor: M, factory: F, document: Document) -> Self {
+    pub fn new(
+        executor: Executor,
+        monitor: M,
+        factory: F,
+        document: Document,
+        page_cache_capacity: usize,
+    ) -> Self {
         Self {
-            executor,
+            executor: Arc::new(executor),
             monitor,
             factory,
+            page_cache: PageCache::new(document.clone(), page_cache_capacity),
+            disk_cache: None,
             document,
-            page_cache: Arc::new(Mutex::new(HashMap::new())),
+            buffers: BufferPool::default(),
+            stats: RenderStats::new(),
         }
     }
-}
 
-impl<M, T> TileProvider for PdfTileProvider<M, T>
-where
-    M: Monitor + Send + Clone + 'static,
-    T: TileFactory + Send + Clone + 'static,
-    T::Data: Send,
-{
-    type Source<'a> = PdfTileSource<'a, M, T>;
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
 
-    fn request<F, R>(&mut self, pages: &Range<usize>, f: F) -> R
-    where
-        F: FnOnce(&mut Self::Source<'_>) -> R,
-    {
-        f(&mut PdfTileSource::new(self, pages.clone()))
+    pub fn page_cache(&self) -> &PageCache {
+        &self.page_cache
     }
-}
 
-impl<'a, M, F> PdfTileSource<'a, M, F> {
-    fn new(provider: &'a mut PdfTileProvider<M, F>, pages: Range<usize>) -> Self {
-        let mut source = Self { provider, pages };
-        source.prepare();
-        source
+    /// The executor running the render tasks, e.g. for sharing it with other
+    /// tasks on the document.
+    pub fn executor(&self) -> &Arc<Executor> {
+        &self.executor
     }
 
-    fn prepare(&mut self) {
-        // remove any cached pages that are no longer visible
-        let cache = self.provider.page_cache.clone();
-        let pages = self.pages.clone();
+    /// Load tiles from and store them in the given disk cache, if they fit
+    /// into it.
+    pub fn set_disk_cache(&mut self, cache: Option<DiskCache>) {
+        self.disk_cache = cache;
+    }
 
-        self.provider.executor.submit(TilePriority::High, move || {
-            cache.lock().unwrap().retain(|i, _| pages.contains(i));
-        });
+    pub fn disk_cache(&self) -> Option<&DiskCache> {
+        self.disk_cache.as_ref()
     }
 
-    fn release(&mut self) {
-        // remove any cached pages that are no longer visible
-        let cache = self.provider.page_cache.clone();
-        let pages = self.pages.clone();
+    /// Pool of the buffers tiles are rendered into.
+    pub fn buffers(&self) -> &BufferPool {
+        &self.buffers
+    }
 
-        self.provider.executor.submit(TilePriority::Low, move || {
-            cache.lock().unwrap().retain(|i, _| pages.contains(i));
-        });
+    /// Render durations of recently completed tiles.
+    pub fn stats(&self) -> &RenderStats {
+        &self.stats
+    }
+
+    /// Queue depths and latencies of the render tasks.
+    pub fn metrics(&self) -> Metrics {
+        self.executor.metrics()
+    }
+
+    /// Hold off rendering requested tiles until unparked.
+    pub fn park(&self) {
+        self.executor.park()
+    }
+
+    pub fn unpark(&self) {
+        self.executor.unpark()
     }
 }
 
-impl<'a, M, F> Drop for PdfTileSource<'a, M, F> {
-    fn drop(&mut self) {
-        self.release()
+impl<M, T> TileProvider for PdfTileProvider<M, T>
+where
+    M: Monitor<Damage> + Send + Clone + 'static,
+    T: TileFactory + Send + Clone + 'static,
+    T::Data: Send,
+{
+    type Source<'a> = PdfTileSource<'a, M, T>;
+
+    fn request<F, R>(&mut self, _pages: &Range<usize>, f: F) -> R
+    where
+        F: FnOnce(&mut Self::Source<'_>) -> R,
+    {
+        f(&mut PdfTileSource { provider: self })
     }
 }
 
 impl<'a, M, F> TileSource for PdfTileSource<'a, M, F>
 where
-    M: Monitor + Send + Clone + 'static,
+    M: Monitor<Damage> + Send + Clone + 'static,
     F: TileFactory + Send + Clone + 'static,
     F::Data: Send,
 {
@@ -118,50 +225,291 @@ where
         priority: TilePriority,
     ) -> Self::Handle {
         let factory = self.provider.factory.clone();
-        let doc = self.provider.document.clone();
         let cache = self.provider.page_cache.clone();
-        let visible = self.pages.clone();
+        let disk = self.disk_cache(page_index, &page_size, &rect, opts);
+        let buffers = self.provider.buffers.clone();
+        let stats = self.provider.stats.clone();
         let opts = opts.clone();
 
-        let task = move || {
-            let mut cache = cache.lock().unwrap();
-
-            // look up page in cache, storing it if visible
-            let page = if visible.contains(&page_index) {
-                cache
-                    .entry(page_index)
-                    .or_insert_with(|| doc.pages().get(page_index as _).unwrap())
-                    .clone()
-            } else {
-                cache
-                    .get(&page_index)
-                    .cloned()
-                    .unwrap_or_else(|| doc.pages().get(page_index as _).unwrap())
-            };
+        let task = move |canceled: &AtomicBool| {
+            if let Some(bmp) = disk.as_ref().and_then(|(disk, key)| disk.load(key)) {
+                return Ok(Some(factory.create(bmp)));
+            }
+
+            let page = cache.get(page_index).map_err(TileError::PageLoad)?;
+            let lock = Some(&**page.render_lock());
 
             // render page to buffer
-            let bmp = render_page_rect(&page, &page_size, &rect, &opts);
+            let start = Instant::now();
+            let buffers = Some(&buffers);
+            let bmp = render_page_rect_cancelable(
+                &page, lock, &page_size, &rect, &opts, canceled, buffers,
+            )
+            .map_err(TileError::Render)?;
+            let bmp = match bmp {
+                Some(bmp) => bmp,
+                None => return Ok(None),
+            };
+            stats.record(page_size.max(), start.elapsed());
+
+            if let Some((disk, key)) = &disk {
+                disk.store(key, &bmp);
+            }
 
             // create return value
-            factory.create(bmp)
+            Ok(Some(factory.create(bmp)))
         };
 
-        self.provider
-            .executor
-            .submit_with(self.provider.monitor.clone(), priority, task)
-            .cancel_on_drop()
+        let damage = Damage {
+            page_index,
+            page_size,
+            rect,
+        };
+
+        self.submit(priority, None, damage, task)
+    }
+
+    fn request_batch<I>(&mut self, requests: I, opts: &Self::RequestOptions) -> Vec<Self::Handle>
+    where
+        I: IntoIterator<Item = TileRequest>,
+    {
+        let requests: Vec<_> = requests.into_iter().collect();
+
+        let opts = Arc::new(opts.clone());
+
+        // group requests by page, each group only looks up its page once and
+        // keeps it pinned until all of its tasks have been dropped
+        let mut slots: HashMap<usize, Arc<Mutex<Option<PinnedPage>>>> = HashMap::new();
+
+        // submit in order of priority, then page, so that tiles of the same
+        // page are rendered together
+        let mut order: Vec<_> = (0..requests.len()).collect();
+        order.sort_by_key(|&i| (Reverse(requests[i].priority), requests[i].page_index));
+
+        let mut handles: Vec<_> = requests.iter().map(|_| None).collect();
+
+        for i in order {
+            let req = requests[i];
+
+            let factory = self.provider.factory.clone();
+            let cache = self.provider.page_cache.clone();
+            let buffers = self.provider.buffers.clone();
+            let stats = self.provider.stats.clone();
+            let opts = opts.clone();
+            let slot = slots.entry(req.page_index).or_default().clone();
+            let disk = self.disk_cache(req.page_index, &req.page_size, &req.rect, &opts);
+
+            let task = move |canceled: &AtomicBool| {
+                // cached tiles do not need the page to be loaded at all
+                if let Some(bmp) = disk.as_ref().and_then(|(disk, key)| disk.load(key)) {
+                    return Ok(Some(factory.create(bmp)));
+                }
+
+                let (page, lock) = {
+                    let mut slot = slot.lock().unwrap();
+
+                    let page = match slot.as_ref() {
+                        Some(page) => page,
+                        None => {
+                            let page = cache.get(req.page_index).map_err(TileError::PageLoad)?;
+                            slot.insert(page)
+                        }
+                    };
+
+                    (Page::clone(page), page.render_lock().clone())
+                };
+
+                // render page to buffer
+                let start = Instant::now();
+                let (size, rect) = (&req.page_size, &req.rect);
+                let buffers = Some(&buffers);
+                let bmp = render_page_rect_cancelable(
+                    &page,
+                    Some(&lock),
+                    size,
+                    rect,
+                    &opts,
+                    canceled,
+                    buffers,
+                )
+                .map_err(TileError::Render)?;
+                let bmp = match bmp {
+                    Some(bmp) => bmp,
+                    None => return Ok(None),
+                };
+                stats.record(req.page_size.max(), start.elapsed());
+
+                if let Some((disk, key)) = &disk {
+                    disk.store(key, &bmp);
+                }
+
+                // create return value
+                Ok(Some(factory.create(bmp)))
+            };
+
+            let damage = Damage {
+                page_index: req.page_index,
+                page_size: req.page_size,
+                rect: req.rect,
+            };
+
+            handles[i] = Some(self.submit(req.priority, req.generation, damage, task));
+        }
+
+        handles.into_iter().map(Option::unwrap).collect()
+    }
+
+    fn new_generation(&mut self) -> Option<u64> {
+        Some(self.provider.executor.new_generation())
+    }
+
+    fn cancel_generation(&mut self, generation: u64) {
+        self.provider.executor.cancel_generation(generation);
     }
 }
 
-fn render_page_rect(
+impl<'a, M, F> PdfTileSource<'a, M, F>
+where
+    M: Monitor<Damage> + Send + Clone + 'static,
+{
+    /// The disk cache and key for the given tile, if it is to be cached.
+    fn disk_cache(
+        &self,
+        page_index: usize,
+        page_size: &Vector2<i64>,
+        rect: &Rect<i64>,
+        opts: &RenderOptions,
+    ) -> Option<(DiskCache, TileKey)> {
+        let disk = self.provider.disk_cache.as_ref()?;
+
+        if !disk.accepts(page_index, page_size) {
+            return None;
+        }
+
+        let key = TileKey::new(page_index, page_size, rect, opts);
+        Some((disk.clone(), key))
+    }
+
+    fn submit<R, T>(
+        &self,
+        priority: TilePriority,
+        generation: Option<u64>,
+        damage: Damage,
+        task: T,
+    ) -> Handle<R>
+    where
+        R: Send + 'static,
+        T: FnOnce(&AtomicBool) -> Result<Option<R>, TileError> + Send + 'static,
+    {
+        let canceled = Arc::new(AtomicBool::new(false));
+
+        let flag = canceled.clone();
+        let task = move || task(&flag).transpose();
+
+        let monitor = self.provider.monitor.clone();
+
+        let exec = &self.provider.executor;
+        let task = if let Some(generation) = generation {
+            exec.submit_with_generation(monitor, damage, generation, priority, task)
+        } else {
+            exec.submit_with_context(monitor, damage, priority, task)
+        };
+        let task = task.cancel_on_drop();
+
+        Handle {
+            task,
+            _cancel: CancelOnDrop { canceled },
+        }
+    }
+}
+
+impl<R: Send> TileHandle for Handle<R> {
+    type Data = R;
+    type Error = TileError;
+
+    fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    fn set_priority(&self, priority: TilePriority) {
+        self.task.set_priority(priority)
+    }
+
+    fn join(self) -> Result<R, TileError> {
+        // the render should only be aborted after the handle has been
+        // dropped, but do not take down the caller if it has been anyway
+        self.task.join().unwrap_or(Err(TileError::Aborted))
+    }
+}
+
+impl std::fmt::Display for TileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TileError::PageLoad(err) => write!(f, "failed to load page: {err}"),
+            TileError::Render(err) => write!(f, "failed to render page: {err}"),
+            TileError::Aborted => write!(f, "render has been aborted"),
+        }
+    }
+}
+
+impl std::error::Error for TileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TileError::PageLoad(err) | TileError::Render(err) => Some(err),
+            TileError::Aborted => None,
+        }
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.canceled.store(true, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn render_page_rect(
     page: &Page,
     page_size: &Vector2<i64>,
     rect: &Rect<i64>,
     opts: &RenderOptions,
-) -> Bitmap {
-    // allocate tile bitmap buffer
+) -> pdfium::Result<Bitmap> {
+    let canceled = AtomicBool::new(false);
+    let bmp = render_page_rect_cancelable(page, None, page_size, rect, opts, &canceled, None)?;
+    Ok(bmp.expect("render without cancellation has been aborted"))
+}
+
+/// Render the given part of a page, returning `None` if the render has been
+/// aborted due to the `canceled` flag being set. The bitmap buffer is taken
+/// from the given pool, if any.
+///
+/// Pages shared between threads, i.e. those of the page cache, must be given
+/// with their render lock, see [`PinnedPage::render_lock()`], as pdfium only
+/// supports a single progressive render per page at a time.
+///
+/// The rectangle is given in pixels relative to the full page rendered at
+/// `page_size`. Its offset translates the start of the page render layout, so
+/// that any part of the page can be rendered, e.g. the crop of a page.
+///
+/// Only large tiles are rendered progressively and can be aborted while
+/// rendering, see [`PROGRESSIVE_RENDER_THRESHOLD`].
+pub(super) fn render_page_rect_cancelable(
+    page: &Page,
+    render_lock: Option<&Mutex<()>>,
+    page_size: &Vector2<i64>,
+    rect: &Rect<i64>,
+    opts: &RenderOptions,
+    canceled: &AtomicBool,
+    pool: Option<&BufferPool>,
+) -> pdfium::Result<Option<Bitmap>> {
+    // allocate tile bitmap buffer, reused buffers are cleared with the
+    // background color below
     let stride = rect.size.x as usize * 3;
-    let mut buffer = vec![0; stride * rect.size.y as usize];
+    let len = stride * rect.size.y as usize;
+
+    let mut buffer = match pool {
+        Some(pool) => pool.take(len),
+        None => vec![0; len].into(),
+    };
 
     // wrap buffer in bitmap
     let mut bmp = pdfium::bitmap::Bitmap::from_buf(
@@ -171,7 +519,7 @@ fn render_page_rect(
         BitmapFormat::Bgr,
         &mut buffer[..],
         stride as _,
-    ).unwrap();
+    )?;
 
     // clear bitmap with background color
     bmp.fill_rect(0, 0, rect.size.x as _, rect.size.y as _, opts.background);
@@ -180,19 +528,439 @@ fn render_page_rect(
     let layout = PageRenderLayout {
         start: na::convert::<_, Vector2<i32>>(-rect.offs.coords).into(),
         size: na::convert(*page_size),
-        rotate: PageRotation::None,
+        rotate: opts.rotation,
     };
 
-    // render page to bitmap
-    page.render(&mut bmp, &layout, opts.flags);
+    // render page to bitmap, pause after each slice to release the pdfium
+    // lock, and abort the render if canceled; pdfium only supports color
+    // schemes for progressive renders, so these are used for all tiles in
+    // night mode, relying on the render lock for tiles of the same page
+    if rect.size.x * rect.size.y > PROGRESSIVE_RENDER_THRESHOLD || opts.color_scheme.is_some() {
+        let is_canceled = || canceled.load(Ordering::Relaxed);
+
+        // keep other threads from starting a render of the same page while
+        // ours is paused, declared first so that it is released only after
+        // the render has been closed
+        let _render_guard = render_lock.map(|lock| lock.lock().unwrap());
+
+        let slice = Cell::new(Instant::now());
+        let should_pause = || is_canceled() || slice.get().elapsed() > RENDER_SLICE;
+
+        let mut render = match &opts.color_scheme {
+            Some(colors) => page.render_progressive_with_colorscheme(
+                &mut bmp,
+                &layout,
+                opts.flags,
+                colors,
+                should_pause,
+            )?,
+            None => page.render_progressive(&mut bmp, &layout, opts.flags, should_pause)?,
+        };
+
+        loop {
+            match render.status() {
+                ProgressiveRenderStatus::Complete => break,
+                ProgressiveRenderStatus::Incomplete if is_canceled() => return Ok(None),
+                ProgressiveRenderStatus::Incomplete => {}
+            }
+
+            // give threads waiting for the lock a chance to take it
+            std::thread::yield_now();
+
+            // a failed render is reported as error, do not return the partly
+            // drawn bitmap in that case
+            slice.set(Instant::now());
+            render.render_continue()?;
+        }
+    } else {
+        page.render(&mut bmp, &layout, opts.flags);
+    }
 
     // drop the wrapping bitmap
     drop(bmp);
 
     // construct bitmap
-    Bitmap {
-        buffer: buffer.into_boxed_slice(),
+    Ok(Some(Bitmap {
+        buffer,
         size: na::convert_unchecked(rect.size),
         stride: stride as _,
+    }))
+}
+
+#[cfg(test)]
+pub(super) mod test {
+    use std::sync::Mutex;
+
+    use nalgebra::{point, vector};
+
+    use pdfium::Library;
+
+    use super::*;
+
+    /// Pdfium keeps global state, so only one test may use it at a time.
+    pub(in crate::core::render) static PDFIUM: Mutex<()> = Mutex::new(());
+
+    #[derive(Clone)]
+    struct SizeFactory;
+
+    impl TileFactory for SizeFactory {
+        type Data = Vector2<u32>;
+
+        fn create(&self, bmp: Bitmap) -> Self::Data {
+            bmp.size
+        }
+    }
+
+    /// Build a document with two pages, where the second page refers to an
+    /// object that is not a page dictionary.
+    fn corrupt_document() -> Vec<u8> {
+        build_document(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 200 300] >>",
+            "(not a page)",
+        ])
+    }
+
+    /// Build a document with a single white page, where the top-left
+    /// 100x100 points are filled black.
+    fn marked_document() -> Vec<u8> {
+        let content = "0 g 0 200 100 100 re f";
+        let stream = format!(
+            "<< /Length {} >>\nstream\n{content}\nendstream",
+            content.len()
+        );
+
+        build_document(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 200 300] /Contents 4 0 R >>",
+            &stream,
+        ])
+    }
+
+    /// Build a document with a page rotated by 90 degrees and an extremely
+    /// skewed page.
+    fn degenerate_document() -> Vec<u8> {
+        build_document(&[
+            "<< /Type /Catalog /Pages 2 0 R >>",
+            "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 200 300] /Rotate 90 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 0.5 1000] >>",
+        ])
+    }
+
+    pub(in crate::core::render) fn build_document(objects: &[&str]) -> Vec<u8> {
+        let mut data = b"%PDF-1.7\n".to_vec();
+        let mut offsets = Vec::new();
+
+        for (i, obj) in objects.iter().enumerate() {
+            offsets.push(data.len());
+            data.extend(format!("{} 0 obj\n{obj}\nendobj\n", i + 1).as_bytes());
+        }
+
+        let xref = data.len();
+        let size = objects.len() + 1;
+
+        data.extend(format!("xref\n0 {size}\n0000000000 65535 f \n").as_bytes());
+        for offs in offsets {
+            data.extend(format!("{offs:010} 00000 n \n").as_bytes());
+        }
+
+        data.extend(format!("trailer\n<< /Size {size} /Root 1 0 R >>\n").as_bytes());
+        data.extend(format!("startxref\n{xref}\n%%EOF\n").as_bytes());
+        data
+    }
+
+    /// Color of the given pixel of a rendered bitmap.
+    fn pixel(bmp: &Bitmap, x: usize, y: usize) -> [u8; 3] {
+        let i = y * bmp.stride as usize + x * 3;
+        [bmp.buffer[i], bmp.buffer[i + 1], bmp.buffer[i + 2]]
+    }
+
+    fn render_opts() -> RenderOptions {
+        RenderOptions {
+            flags: RenderFlags::empty(),
+            background: Color::WHITE,
+            color_scheme: None,
+            rotation: PageRotation::None,
+        }
+    }
+
+    #[test]
+    fn page_cache_load_error() {
+        let _guard = PDFIUM.lock().unwrap();
+
+        let lib = Library::init().unwrap();
+        let doc = lib.load_buffer(corrupt_document(), None).unwrap();
+        assert_eq!(doc.pages().count(), 2);
+
+        let cache = PageCache::new(doc, 4);
+
+        assert!(cache.get(0).is_ok());
+        assert!(cache.get(1).is_err());
+
+        // failed pages are not cached and loading them fails again
+        assert!(cache.get(1).is_err());
+        assert!(cache.get(0).is_ok());
+
+        let stats = cache.stats();
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hits, 1);
+    }
+
+    #[test]
+    fn tile_page_load_error() {
+        let _guard = PDFIUM.lock().unwrap();
+
+        let lib = Library::init().unwrap();
+        let doc = lib.load_buffer(corrupt_document(), None).unwrap();
+
+        let mut provider = PdfTileProvider::new(Executor::new(1), (), SizeFactory, doc, 4);
+
+        let page_size = vector![200, 300];
+        let rect = Rect::new(point![0, 0], vector![100, 100]);
+        let opts = render_opts();
+
+        // single requests
+        let (valid, corrupt) = provider.request(&(0..2), |source| {
+            let valid = source.request(0, page_size, rect, &opts, TilePriority::High);
+            let corrupt = source.request(1, page_size, rect, &opts, TilePriority::High);
+            (valid, corrupt)
+        });
+
+        assert_eq!(valid.join().unwrap(), vector![100, 100]);
+        assert!(matches!(corrupt.join(), Err(TileError::PageLoad(_))));
+
+        // batched requests, each page is looked up once per batch
+        let requests = (0..2).flat_map(|page_index| {
+            [TilePriority::High, TilePriority::Low].map(|priority| TileRequest {
+                page_index,
+                page_size,
+                rect,
+                priority,
+                generation: None,
+            })
+        });
+
+        let handles = provider.request(&(0..2), |source| source.request_batch(requests, &opts));
+        let results: Vec<_> = handles.into_iter().map(TileHandle::join).collect();
+
+        assert!(results[0].is_ok());
+        assert!(results[1].is_ok());
+        assert!(matches!(results[2], Err(TileError::PageLoad(_))));
+        assert!(matches!(results[3], Err(TileError::PageLoad(_))));
+    }
+
+    #[test]
+    fn tile_concurrent_progressive_renders() {
+        use crate::core::appearance::NIGHT_COLORS;
+
+        #[derive(Clone)]
+        struct BufferFactory;
+
+        impl TileFactory for BufferFactory {
+            type Data = Vec<u8>;
+
+            fn create(&self, bmp: Bitmap) -> Self::Data {
+                bmp.buffer.to_vec()
+            }
+        }
+
+        let _guard = PDFIUM.lock().unwrap();
+
+        let lib = Library::init().unwrap();
+        let doc = lib.load_buffer(marked_document(), None).unwrap();
+
+        // reference renders use their own page, i.e. do not share it with the
+        // render threads
+        let page = doc.pages().get(0).unwrap();
+
+        let mut provider =
+            PdfTileProvider::new(Executor::new(4), (), BufferFactory, doc.clone(), 4);
+
+        // large tiles of the same page, all rendered progressively at once
+        let page_size = vector![2000, 3000];
+        let tiles: Vec<_> = (0..16)
+            .map(|i| Rect::new(point![(i % 4) * 500, (i / 4) * 600], vector![500, 600]))
+            .collect();
+
+        for color_scheme in [None, Some(NIGHT_COLORS)] {
+            let opts = RenderOptions {
+                color_scheme,
+                ..render_opts()
+            };
+
+            let requests = tiles.iter().map(|&rect| TileRequest {
+                page_index: 0,
+                page_size,
+                rect,
+                priority: TilePriority::High,
+                generation: None,
+            });
+
+            let handles = provider.request(&(0..1), |source| source.request_batch(requests, &opts));
+
+            for (rect, handle) in tiles.iter().zip(handles) {
+                let expected = render_page_rect(&page, &page_size, rect, &opts).unwrap();
+                assert!(handle.join().unwrap() == expected.buffer[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn render_page_rect_offset() {
+        let _guard = PDFIUM.lock().unwrap();
+
+        let lib = Library::init().unwrap();
+        let doc = lib.load_buffer(marked_document(), None).unwrap();
+        let page = doc.pages().get(0).unwrap();
+
+        let page_size = vector![200, 300];
+        let opts = render_opts();
+
+        let black = [0, 0, 0];
+        let white = [255, 255, 255];
+
+        // the marked part of the page
+        let rect = Rect::new(point![0, 0], vector![100, 100]);
+        let bmp = render_page_rect(&page, &page_size, &rect, &opts).unwrap();
+
+        assert_eq!(pixel(&bmp, 1, 1), black);
+        assert_eq!(pixel(&bmp, 98, 98), black);
+
+        // a part of the page next to it
+        let rect = Rect::new(point![100, 0], vector![100, 100]);
+        let bmp = render_page_rect(&page, &page_size, &rect, &opts).unwrap();
+
+        assert_eq!(pixel(&bmp, 1, 1), white);
+        assert_eq!(pixel(&bmp, 98, 98), white);
+
+        // a crop overlapping the marked part, translated into the page
+        let rect = Rect::new(point![50, 50], vector![100, 100]);
+        let bmp = render_page_rect(&page, &page_size, &rect, &opts).unwrap();
+
+        assert_eq!(bmp.size, vector![100, 100]);
+        assert_eq!(pixel(&bmp, 1, 1), black);
+        assert_eq!(pixel(&bmp, 48, 48), black);
+        assert_eq!(pixel(&bmp, 51, 51), white);
+        assert_eq!(pixel(&bmp, 98, 1), white);
+        assert_eq!(pixel(&bmp, 1, 98), white);
+    }
+
+    #[test]
+    fn layout_degenerate_pages() {
+        use crate::core::render::layout::{
+            page_sizes, LayoutProvider, PageSizeIssue, VerticalLayout,
+        };
+
+        let _guard = PDFIUM.lock().unwrap();
+
+        let lib = Library::init().unwrap();
+        let doc = lib.load_buffer(degenerate_document(), None).unwrap();
+
+        // rotated pages are laid out with their displayed size
+        let sizes = page_sizes(&doc);
+        assert_eq!(sizes[0], (300.0, 200.0));
+
+        let layout = VerticalLayout.compute(sizes, 10.0);
+        assert_eq!(layout.issues, [(1, PageSizeIssue::Clamped)]);
+        assert_eq!(layout.rects[0].size, vector![300.0, 200.0]);
+        assert_eq!(layout.rects[1].size, vector![3.0, 1000.0]);
+        assert_eq!(layout.bounds.y_max, 1210.0);
+    }
+
+    #[test]
+    fn view_render_and_input() {
+        use std::time::Duration;
+
+        use crate::core::render::core::{FallbackSpec, TilingSchemeKind};
+        use crate::core::render::layout::LayoutKind;
+        use crate::core::render::telemetry::TileSizePolicy;
+        use crate::core::view::{DocumentView, Event, FrameHints, Input, ViewOptions};
+        use crate::types::Viewport;
+
+        let _guard = PDFIUM.lock().unwrap();
+
+        let lib = Library::init().unwrap();
+        let doc = lib.load_buffer(marked_document(), None).unwrap();
+
+        let opts = ViewOptions {
+            page_spacing: 10.0,
+            layout: LayoutKind::Vertical,
+            fallback_specs: vec![FallbackSpec {
+                halo: usize::MAX,
+                render_threshold: vector![0.0, 0.0],
+                render_limits: vector![64, 64],
+            }],
+            render_main: render_opts(),
+            render_fallback: render_opts(),
+            page_cache_capacity: 4,
+            render_threads: 2,
+            render_thread_limit: None,
+            tile_halo: vector![0, 0],
+            fallback_halo_limit: None,
+            fallback_halo_minimum: 0,
+            fallback_limits: None,
+            upload_limit: None,
+            warm_factors: Vec::new(),
+            warm_budget: 0,
+        };
+
+        let budget = Duration::from_millis(80);
+        let policy = TileSizePolicy::new(TilingSchemeKind::Hybrid, 256, 768, budget, 2);
+
+        let mut view = DocumentView::open(doc, (), SizeFactory, policy, opts);
+        assert_eq!(view.page_count(), 1);
+
+        // show the page at its natural size
+        let page_rect = Rect::new(point![0.0, 0.0], vector![200.0, 300.0]);
+        let vp = Viewport {
+            r: page_rect,
+            scale: 1.0,
+        };
+        view.set_viewport(&vp, 1.0);
+
+        // render until the page is covered by tiles
+        let start = Instant::now();
+        loop {
+            let frame = view.render(&FrameHints::default());
+            assert_eq!(frame.pages.len(), 1);
+
+            let page = &frame.pages[0];
+            assert_eq!(page.rect, page_rect);
+
+            if !page.tiles.is_empty() {
+                assert!(page.tiles.iter().all(|(r, _)| page_rect.contains(r)));
+                break;
+            }
+
+            assert!(start.elapsed() < Duration::from_secs(10));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        // hit-testing
+        let hit = view.page_at(point![50.0, 60.0]);
+        assert_eq!(hit, Some((0, point![50.0, 60.0])));
+        assert_eq!(view.page_at(point![250.0, 60.0]), None);
+
+        // selected regions are clipped to the page
+        view.handle_input(Input::SelectBegin(point![150.0, 250.0]));
+        view.handle_input(Input::SelectUpdate(vector![10.0, 10.0]));
+        assert!(view.render(&FrameHints::default()).selection.is_some());
+
+        match view.handle_input(Input::SelectEnd(vector![100.0, -50.0])) {
+            Some(Event::RegionSelected { page, rect }) => {
+                assert_eq!(page, 0);
+                assert_eq!(rect, Rect::new(point![150.0, 200.0], vector![50.0, 50.0]));
+            }
+            _ => panic!("expected selected region"),
+        }
+
+        // accidental clicks do not select anything
+        view.handle_input(Input::SelectBegin(point![10.0, 10.0]));
+        let event = view.handle_input(Input::SelectEnd(vector![1.0, 1.0]));
+        assert!(event.is_none());
+        assert!(view.render(&FrameHints::default()).selection.is_none());
     }
 }
@@ -0,0 +1,190 @@
+//! Pool of bitmap buffers, recycled across tile renders.
+//!
+//! Tiles are mostly rendered at a handful of sizes, so buffers of tiles that
+//! are no longer used can be reused for new tiles of the same size instead of
+//! allocating fresh ones, e.g. while scrolling quickly.
+
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+/// Maximum number of bytes kept in a pool by default.
+pub const DEFAULT_POOL_CAPACITY: usize = 64 << 20;
+
+/// Thread-safe pool of buffers, keyed by their length, i.e. by the size of
+/// the tiles they hold. Clones share the same buffers.
+#[derive(Debug, Clone)]
+pub struct BufferPool {
+    inner: Arc<Mutex<PoolInner>>,
+}
+
+#[derive(Debug)]
+struct PoolInner {
+    buffers: HashMap<usize, Vec<Box<[u8]>>>,
+    capacity: usize,
+    len: usize,
+}
+
+/// Pixel data of a bitmap, returned to the pool it has been taken from once
+/// dropped, if any.
+pub struct Buffer {
+    data: Box<[u8]>,
+    pool: Option<BufferPool>,
+}
+
+impl BufferPool {
+    /// Create a pool keeping at most the given number of bytes.
+    pub fn new(capacity: usize) -> Self {
+        let inner = PoolInner {
+            buffers: HashMap::new(),
+            capacity,
+            len: 0,
+        };
+
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+        }
+    }
+
+    /// Take a buffer of the given length from the pool, allocating a new one
+    /// if none is available. Reused buffers are not cleared.
+    pub fn take(&self, len: usize) -> Buffer {
+        let data = {
+            let mut inner = self.inner.lock().unwrap();
+
+            let data = inner.buffers.get_mut(&len).and_then(Vec::pop);
+            if data.is_some() {
+                inner.len -= len;
+            }
+            data
+        };
+
+        Buffer {
+            data: data.unwrap_or_else(|| vec![0; len].into_boxed_slice()),
+            pool: Some(self.clone()),
+        }
+    }
+
+    /// Number of bytes currently kept in the pool.
+    pub fn bytes(&self) -> usize {
+        self.inner.lock().unwrap().len
+    }
+
+    /// Drop all buffers kept in the pool.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+
+        inner.buffers.clear();
+        inner.len = 0;
+    }
+
+    fn recycle(&self, data: Box<[u8]>) {
+        let mut inner = self.inner.lock().unwrap();
+
+        // drop buffers beyond the capacity of the pool
+        if data.is_empty() || inner.len + data.len() > inner.capacity {
+            return;
+        }
+
+        inner.len += data.len();
+        inner.buffers.entry(data.len()).or_default().push(data);
+    }
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_POOL_CAPACITY)
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            pool.recycle(std::mem::take(&mut self.data));
+        }
+    }
+}
+
+impl Deref for Buffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl DerefMut for Buffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+}
+
+impl AsRef<[u8]> for Buffer {
+    fn as_ref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl From<Box<[u8]>> for Buffer {
+    fn from(data: Box<[u8]>) -> Self {
+        Self { data, pool: None }
+    }
+}
+
+impl From<Vec<u8>> for Buffer {
+    fn from(data: Vec<u8>) -> Self {
+        data.into_boxed_slice().into()
+    }
+}
+
+impl FromIterator<u8> for Buffer {
+    fn from_iter<T: IntoIterator<Item = u8>>(iter: T) -> Self {
+        iter.into_iter().collect::<Vec<_>>().into()
+    }
+}
+
+impl std::fmt::Debug for Buffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Buffer")
+            .field("len", &self.data.len())
+            .field("pooled", &self.pool.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn buffers_are_recycled() {
+        let pool = BufferPool::new(24);
+
+        let mut a = pool.take(8);
+        a[0] = 42;
+        let ptr = a.as_ptr();
+        drop(a);
+        assert_eq!(pool.bytes(), 8);
+
+        // buffers are reused for the same length only
+        let b = pool.take(16);
+        assert_eq!(pool.bytes(), 8);
+
+        let a = pool.take(8);
+        assert_eq!(a.as_ptr(), ptr);
+        assert_eq!(a[0], 42);
+        assert_eq!(pool.bytes(), 0);
+
+        // buffers beyond the capacity are dropped
+        let c = pool.take(16);
+        drop((a, b, c));
+        assert_eq!(pool.bytes(), 24);
+
+        // buffers not taken from a pool are not recycled
+        drop(Buffer::from(vec![0; 8]));
+        assert_eq!(pool.bytes(), 24);
+
+        pool.clear();
+        assert_eq!(pool.bytes(), 0);
+    }
+}
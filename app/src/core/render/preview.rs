@@ -0,0 +1,122 @@
+//! Previews of document files, i.e. their first page rendered at thumbnail
+//! size, e.g. for file choosers or thumbnails shown by file managers.
+
+use std::ffi::OsString;
+use std::path::Path;
+
+use gtk::gdk;
+use gtk::glib;
+use gtk::prelude::TextureExt;
+use nalgebra::{point, vector};
+
+use pdfium::bitmap::Color;
+use pdfium::doc::{Document, PageRotation, RenderFlags};
+use pdfium::Library;
+
+use crate::types::Rect;
+
+use super::interop::Bitmap;
+use super::pdfium::{render_page_rect, RenderOptions};
+
+/// Default size of previews, in pixels. This is the size of "large"
+/// thumbnails according to the freedesktop.org thumbnail specification.
+pub const DEFAULT_SIZE: u32 = 256;
+
+/// Render the first page of the given file to fit into a square of the given
+/// size, in pixels.
+///
+/// The file is loaded with its own library and document instance, so this is
+/// independent of any document opened in the application.
+pub fn render_file(path: &Path, size: u32) -> pdfium::Result<Bitmap> {
+    let lib = Library::init()?;
+    let doc = lib.load_file(path, None)?;
+
+    render_first_page(&doc, size)
+}
+
+/// Render the first page of the given document to fit into a square of the
+/// given size, in pixels.
+pub fn render_first_page(doc: &Document, size: u32) -> pdfium::Result<Bitmap> {
+    let page = doc.pages().get(0)?;
+
+    let (width, height) = (page.width() as f64, page.height() as f64);
+    if width <= 0.0 || height <= 0.0 {
+        return Err(pdfium::Error::InvalidArgument);
+    }
+
+    let scale = size as f64 / width.max(height);
+    let page_size = vector![
+        ((width * scale).round() as i64).max(1),
+        ((height * scale).round() as i64).max(1)
+    ];
+
+    let rect = Rect::new(point![0, 0], page_size);
+    let opts = RenderOptions {
+        flags: RenderFlags::Annotations,
+        background: Color::WHITE,
+        color_scheme: None,
+        rotation: PageRotation::None,
+    };
+
+    render_page_rect(&page, &page_size, &rect, &opts)
+}
+
+/// Save a rendered preview as PNG file.
+pub fn save_png(bmp: Bitmap, path: &Path) -> Result<(), glib::BoolError> {
+    let bytes = glib::Bytes::from_owned(bmp.buffer);
+
+    let texture = gdk::MemoryTexture::new(
+        bmp.size.x as _,
+        bmp.size.y as _,
+        gdk::MemoryFormat::B8g8r8,
+        &bytes,
+        bmp.stride as _,
+    );
+
+    texture.save_to_png(path)
+}
+
+/// Run as thumbnailer for file managers, i.e. write the preview of a file to
+/// a PNG file, given the arguments `[-s SIZE] INPUT OUTPUT`.
+pub fn run_thumbnailer(args: impl IntoIterator<Item = OsString>) -> glib::ExitCode {
+    let mut args = args.into_iter();
+    let mut size = DEFAULT_SIZE;
+    let mut paths = Vec::new();
+
+    while let Some(arg) = args.next() {
+        if arg == "-s" {
+            size = match args.next().and_then(|s| s.to_str()?.parse().ok()) {
+                Some(size) if size > 0 => size,
+                _ => {
+                    eprintln!("Error: invalid thumbnail size");
+                    return glib::ExitCode::FAILURE;
+                }
+            };
+        } else {
+            paths.push(arg);
+        }
+    }
+
+    let (input, output) = match &paths[..] {
+        [input, output] => (Path::new(input), Path::new(output)),
+        _ => {
+            eprintln!("Usage: papr --thumbnail [-s SIZE] INPUT OUTPUT");
+            return glib::ExitCode::FAILURE;
+        }
+    };
+
+    let bmp = match render_file(input, size) {
+        Ok(bmp) => bmp,
+        Err(err) => {
+            tracing::error!(file=?input, error=%err, "failed to render thumbnail");
+            return glib::ExitCode::FAILURE;
+        }
+    };
+
+    if let Err(err) = save_png(bmp, output) {
+        tracing::error!(file=?output, error=%err, "failed to save thumbnail");
+        return glib::ExitCode::FAILURE;
+    }
+
+    glib::ExitCode::SUCCESS
+}
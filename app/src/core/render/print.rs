@@ -0,0 +1,199 @@
+//! Rendering of pages for printing, i.e. at printer resolution and placed on
+//! the printable area of the paper.
+
+use gtk::cairo;
+use nalgebra::{point, vector, Vector2};
+
+use pdfium::bitmap::Color;
+use pdfium::doc::{Document, PageRotation, RenderFlags};
+
+use crate::types::Rect;
+
+use super::interop::Bitmap;
+use super::pdfium::{render_page_rect, RenderOptions};
+
+/// Maximum resolution to render pages at, in dots per inch.
+///
+/// Printers often report resolutions far beyond what is discernible on paper,
+/// which would only inflate the size of the rendered bitmaps and print jobs.
+const MAX_PRINT_DPI: f64 = 300.0;
+
+/// PDF points per inch.
+const POINTS_PER_INCH: f64 = 72.0;
+
+/// How pages are scaled onto the printable area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrintScaling {
+    /// Scale pages up or down to fill the printable area.
+    Fit,
+
+    /// Scale pages down if they exceed the printable area, keep them at
+    /// their actual size otherwise.
+    #[default]
+    Shrink,
+
+    /// Keep pages at their actual size, cutting off what does not fit.
+    ActualSize,
+}
+
+impl PrintScaling {
+    pub const ALL: [PrintScaling; 3] = [
+        PrintScaling::Fit,
+        PrintScaling::Shrink,
+        PrintScaling::ActualSize,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PrintScaling::Fit => "Fit to Printable Area",
+            PrintScaling::Shrink => "Shrink Oversized Pages",
+            PrintScaling::ActualSize => "Actual Size",
+        }
+    }
+}
+
+/// A page rendered for printing.
+pub struct PrintedPage {
+    pub bitmap: Bitmap,
+
+    /// Where to draw the bitmap, in points relative to the printable area.
+    pub rect: Rect<f64>,
+}
+
+/// Where to place a page of the given size on the printable area, both in
+/// points. The page is centered on the area.
+pub fn placement(page: Vector2<f64>, area: Vector2<f64>, scaling: PrintScaling) -> Rect<f64> {
+    let fit = (area.x / page.x).min(area.y / page.y);
+
+    let scale = match scaling {
+        PrintScaling::Fit => fit,
+        PrintScaling::Shrink => fit.min(1.0),
+        PrintScaling::ActualSize => 1.0,
+    };
+
+    let size = page * scale;
+    let offs = (area - size) / 2.0;
+
+    Rect::new(offs.into(), size)
+}
+
+/// Render the given page for the printable area of the given size in points,
+/// at the given printer resolution in dots per inch.
+///
+/// This is blocking and should be run on the executor.
+pub fn render_page(
+    doc: &Document,
+    index: u32,
+    area: Vector2<f64>,
+    dpi: f64,
+    scaling: PrintScaling,
+) -> pdfium::Result<PrintedPage> {
+    let page = doc.pages().get(index)?;
+
+    let (width, height) = (page.width() as f64, page.height() as f64);
+    if width <= 0.0 || height <= 0.0 {
+        return Err(pdfium::Error::InvalidArgument);
+    }
+
+    let rect = placement(vector![width, height], area, scaling);
+
+    let dpi = dpi.clamp(1.0, MAX_PRINT_DPI);
+    let pixels = rect.size * (dpi / POINTS_PER_INCH);
+    let page_size = vector![
+        (pixels.x.round() as i64).max(1),
+        (pixels.y.round() as i64).max(1)
+    ];
+
+    let opts = RenderOptions {
+        flags: RenderFlags::Annotations | RenderFlags::Print,
+        background: Color::WHITE,
+        color_scheme: None,
+        rotation: PageRotation::None,
+    };
+
+    let full = Rect::new(point![0, 0], page_size);
+    let bitmap = render_page_rect(&page, &page_size, &full, &opts)?;
+
+    Ok(PrintedPage { bitmap, rect })
+}
+
+/// Draw a rendered page onto the cairo context of a print operation, with
+/// units in points relative to the printable area.
+pub fn draw(cr: &cairo::Context, page: &PrintedPage) -> Result<(), cairo::Error> {
+    let surface = to_surface(&page.bitmap)?;
+
+    let size = page.bitmap.size;
+    let scale = vector![
+        page.rect.size.x / size.x as f64,
+        page.rect.size.y / size.y as f64
+    ];
+
+    cr.save()?;
+    cr.translate(page.rect.offs.x, page.rect.offs.y);
+    cr.scale(scale.x, scale.y);
+    cr.set_source_surface(&surface, 0.0, 0.0)?;
+    cr.paint()?;
+    cr.restore()
+}
+
+/// Convert a rendered page to a cairo surface.
+fn to_surface(bmp: &Bitmap) -> Result<cairo::ImageSurface, cairo::Error> {
+    let (width, height) = (bmp.size.x as usize, bmp.size.y as usize);
+
+    // rendered bitmaps are BGR, cairo expects 32 bit pixels in native byte
+    // order with the upper 8 bits unused
+    let stride = width * 4;
+    let mut data = vec![0; stride * height];
+
+    for y in 0..height {
+        let src = &bmp.buffer[y * bmp.stride as usize..][..width * 3];
+        let dst = &mut data[y * stride..][..stride];
+
+        for (s, d) in src.chunks_exact(3).zip(dst.chunks_exact_mut(4)) {
+            let px = u32::from_le_bytes([s[0], s[1], s[2], 0xff]);
+            d.copy_from_slice(&px.to_ne_bytes());
+        }
+    }
+
+    cairo::ImageSurface::create_for_data(
+        data,
+        cairo::Format::Rgb24,
+        width as _,
+        height as _,
+        stride as _,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn placement_scaling() {
+        let area = vector![500.0, 800.0];
+
+        // small pages are scaled up only when fitting them
+        let page = vector![250.0, 200.0];
+
+        let fit = placement(page, area, PrintScaling::Fit);
+        assert_eq!(fit, Rect::new(point![0.0, 200.0], vector![500.0, 400.0]));
+
+        let shrink = placement(page, area, PrintScaling::Shrink);
+        assert_eq!(
+            shrink,
+            Rect::new(point![125.0, 300.0], vector![250.0, 200.0])
+        );
+
+        // large pages are shrunk unless printed at actual size
+        let page = vector![1000.0, 800.0];
+
+        let shrink = placement(page, area, PrintScaling::Shrink);
+        assert_eq!(shrink, Rect::new(point![0.0, 200.0], vector![500.0, 400.0]));
+
+        let actual = placement(page, area, PrintScaling::ActualSize);
+        assert_eq!(
+            actual,
+            Rect::new(point![-250.0, 0.0], vector![1000.0, 800.0])
+        );
+    }
+}
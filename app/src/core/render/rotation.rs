@@ -0,0 +1,160 @@
+//! Rotation of pages in the view.
+//!
+//! Pages are rotated on top of their own `/Rotate` entry, i.e. page sizes
+//! reported by pdfium are those of the unrotated page here. Page coordinates
+//! always refer to the unrotated page, so that text, links, and annotations
+//! can be looked up regardless of how pages are shown.
+
+use nalgebra::{point, Point2, Vector2};
+
+use pdfium::doc::PageRotation;
+
+use crate::types::{Bounds, Rect};
+
+/// The given rotation, turned by another 90 degrees clockwise.
+pub fn clockwise(rotation: PageRotation) -> PageRotation {
+    match rotation {
+        PageRotation::None => PageRotation::Deg90,
+        PageRotation::Deg90 => PageRotation::Deg180,
+        PageRotation::Deg180 => PageRotation::Deg270,
+        PageRotation::Deg270 => PageRotation::None,
+    }
+}
+
+/// The given rotation, turned by another 90 degrees counter-clockwise.
+pub fn counter_clockwise(rotation: PageRotation) -> PageRotation {
+    inverse(clockwise(inverse(rotation)))
+}
+
+/// The rotation undoing the given one.
+pub fn inverse(rotation: PageRotation) -> PageRotation {
+    match rotation {
+        PageRotation::None => PageRotation::None,
+        PageRotation::Deg90 => PageRotation::Deg270,
+        PageRotation::Deg180 => PageRotation::Deg180,
+        PageRotation::Deg270 => PageRotation::Deg90,
+    }
+}
+
+/// The rotation from `from` to `to`.
+pub fn difference(from: PageRotation, to: PageRotation) -> PageRotation {
+    let mut turn = PageRotation::None;
+    let mut current = from;
+
+    while current != to {
+        current = clockwise(current);
+        turn = clockwise(turn);
+    }
+
+    turn
+}
+
+/// Check if the given rotation swaps width and height of pages.
+pub fn is_sideways(rotation: PageRotation) -> bool {
+    matches!(rotation, PageRotation::Deg90 | PageRotation::Deg270)
+}
+
+/// Size of a page of the given size after rotating it.
+pub fn rotate_size(size: &Vector2<f64>, rotation: PageRotation) -> Vector2<f64> {
+    if is_sideways(rotation) {
+        size.yx()
+    } else {
+        *size
+    }
+}
+
+/// Transform a point on a page of the given size to the rotated page.
+pub fn rotate_point(
+    point: &Point2<f64>,
+    size: &Vector2<f64>,
+    rotation: PageRotation,
+) -> Point2<f64> {
+    match rotation {
+        PageRotation::None => *point,
+        PageRotation::Deg90 => point![size.y - point.y, point.x],
+        PageRotation::Deg180 => point![size.x - point.x, size.y - point.y],
+        PageRotation::Deg270 => point![point.y, size.x - point.x],
+    }
+}
+
+/// Transform a point on the rotated page back to the page of the given,
+/// unrotated size.
+pub fn unrotate_point(
+    point: &Point2<f64>,
+    size: &Vector2<f64>,
+    rotation: PageRotation,
+) -> Point2<f64> {
+    rotate_point(point, &rotate_size(size, rotation), inverse(rotation))
+}
+
+/// Transform a rectangle on a page of the given size to the rotated page.
+pub fn rotate_rect(rect: &Rect<f64>, size: &Vector2<f64>, rotation: PageRotation) -> Rect<f64> {
+    let a = rotate_point(&rect.offs, size, rotation);
+    let b = rotate_point(&(rect.offs + rect.size), size, rotation);
+
+    Bounds {
+        x_min: a.x.min(b.x),
+        y_min: a.y.min(b.y),
+        x_max: a.x.max(b.x),
+        y_max: a.y.max(b.y),
+    }
+    .rect()
+}
+
+/// Transform a rectangle on the rotated page back to the page of the given,
+/// unrotated size.
+pub fn unrotate_rect(rect: &Rect<f64>, size: &Vector2<f64>, rotation: PageRotation) -> Rect<f64> {
+    rotate_rect(rect, &rotate_size(size, rotation), inverse(rotation))
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra::vector;
+
+    use super::*;
+
+    const ALL: [PageRotation; 4] = [
+        PageRotation::None,
+        PageRotation::Deg90,
+        PageRotation::Deg180,
+        PageRotation::Deg270,
+    ];
+
+    #[test]
+    fn turns() {
+        for r in ALL {
+            assert_eq!(counter_clockwise(clockwise(r)), r);
+            assert_eq!(difference(r, r), PageRotation::None);
+            assert_eq!(difference(PageRotation::None, r), r);
+            assert_eq!(difference(r, PageRotation::None), inverse(r));
+        }
+
+        assert_eq!(clockwise(PageRotation::Deg270), PageRotation::None);
+        assert_eq!(counter_clockwise(PageRotation::None), PageRotation::Deg270);
+    }
+
+    #[test]
+    fn points_and_rects() {
+        let size = vector![200.0, 100.0];
+
+        // the top left corner ends up at the top right after a clockwise turn
+        let p = rotate_point(&point![0.0, 0.0], &size, PageRotation::Deg90);
+        assert_eq!(p, point![100.0, 0.0]);
+
+        let rect = Rect::new(point![10.0, 20.0], vector![30.0, 40.0]);
+
+        let r = rotate_rect(&rect, &size, PageRotation::Deg90);
+        assert_eq!(r, Rect::new(point![40.0, 10.0], vector![40.0, 30.0]));
+
+        let r = rotate_rect(&rect, &size, PageRotation::Deg180);
+        assert_eq!(r, Rect::new(point![160.0, 40.0], vector![30.0, 40.0]));
+
+        for r in ALL {
+            let rotated = rotate_rect(&rect, &size, r);
+            let page = Rect::new(point![0.0, 0.0], rotate_size(&size, r));
+
+            assert!(page.contains(&rotated));
+            assert_eq!(unrotate_rect(&rotated, &size, r), rect);
+        }
+    }
+}
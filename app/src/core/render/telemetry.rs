@@ -0,0 +1,242 @@
+//! Render time statistics and adaptation of the tiling scheme to the
+//! complexity of the rendered pages, as well as of the rendering effort to
+//! the time taken per frame.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use nalgebra::vector;
+
+use super::core::{DynamicTilingScheme, TilingSchemeKind};
+
+/// Number of recent samples kept for statistics.
+const MAX_SAMPLES: usize = 64;
+
+/// Minimum number of samples on a z-level required for adapting the tile size.
+const MIN_SAMPLES: usize = 8;
+
+/// Number of consecutive frames over budget after which load is shed.
+const SHED_FRAMES: u32 = 8;
+
+/// Number of consecutive frames within the recovery threshold after which
+/// full quality is restored.
+const RECOVER_FRAMES: u32 = 60;
+
+/// Fraction of the frame budget frames have to stay below for recovery. Shed
+/// frames are cheaper, so this avoids restoring quality only to shed it again
+/// right away.
+const RECOVER_THRESHOLD: f64 = 0.75;
+
+/// Render durations of recently completed tiles.
+#[derive(Clone, Default)]
+pub struct RenderStats {
+    samples: Arc<Mutex<VecDeque<Sample>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    z: i64,
+    duration: Duration,
+}
+
+impl RenderStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the render duration of a tile with the given z-level.
+    pub fn record(&self, z: i64, duration: Duration) {
+        let mut samples = self.samples.lock().unwrap();
+
+        if samples.len() >= MAX_SAMPLES {
+            samples.pop_front();
+        }
+
+        samples.push_back(Sample { z, duration });
+    }
+
+    /// Median render duration of recent tiles with the given z-level, if
+    /// there are at least `min_samples` such tiles.
+    pub fn median(&self, z: i64, min_samples: usize) -> Option<Duration> {
+        let mut durations: Vec<_> = self
+            .samples
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| s.z == z)
+            .map(|s| s.duration)
+            .collect();
+
+        if durations.is_empty() || durations.len() < min_samples {
+            return None;
+        }
+
+        durations.sort_unstable();
+        Some(durations[durations.len() / 2])
+    }
+
+    pub fn clear(&self) {
+        self.samples.lock().unwrap().clear()
+    }
+}
+
+/// Policy for the tile size of a document.
+///
+/// Starts out with the base tile size and halves it (along with the minimum
+/// page size for tiling) whenever the median render time of tiles exceeds the
+/// time budget, e.g. for very complex vector graphics. Smaller tiles keep the
+/// viewer responsive, as tiles in view can be shown earlier and tiles that
+/// have been scrolled out of view can be canceled sooner.
+#[derive(Debug, Clone, Copy)]
+pub struct TileSizePolicy {
+    kind: TilingSchemeKind,
+    base_size: i64,
+    base_min_z: i64,
+    budget: Duration,
+    max_level: u32,
+    level: u32,
+}
+
+impl TileSizePolicy {
+    /// Create a new policy.
+    ///
+    /// # Arguments
+    /// - `kind`: The kind of tiling scheme to use.
+    /// - `base_size`: The initial tile size.
+    /// - `base_min_z`: The initial minimum page size for tiling, see
+    ///   [`HybridTilingScheme::new()`](crate::core::render::core::scheme::HybridTilingScheme::new).
+    /// - `budget`: The maximum median render time of a tile.
+    /// - `max_level`: The maximum number of times the tile size is halved.
+    pub fn new(
+        kind: TilingSchemeKind,
+        base_size: i64,
+        base_min_z: i64,
+        budget: Duration,
+        max_level: u32,
+    ) -> Self {
+        Self {
+            kind,
+            base_size,
+            base_min_z,
+            budget,
+            max_level,
+            level: 0,
+        }
+    }
+
+    pub fn kind(&self) -> TilingSchemeKind {
+        self.kind
+    }
+
+    /// Number of times the tile size has been halved.
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    pub fn tile_size(&self) -> i64 {
+        self.base_size >> self.level
+    }
+
+    pub fn min_tile_z(&self) -> i64 {
+        self.base_min_z >> self.level
+    }
+
+    /// The tiling scheme for the current tile size.
+    pub fn scheme(&self) -> DynamicTilingScheme {
+        let size = self.tile_size();
+        DynamicTilingScheme::new(self.kind, vector![size, size], self.min_tile_z())
+    }
+
+    /// Reduce the tile size if recent tiles on the given z-level took too
+    /// long to render. Returns `true` if the tile size has been changed.
+    ///
+    /// Statistics are cleared on change, as they no longer reflect the
+    /// current tile size.
+    pub fn adapt(&mut self, stats: &RenderStats, z: i64) -> bool {
+        if self.level >= self.max_level {
+            return false;
+        }
+
+        match stats.median(z, MIN_SAMPLES) {
+            Some(median) if median > self.budget => {
+                self.level += 1;
+                stats.clear();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Watchdog for the time taken to build frames.
+///
+/// Switches to shedding load once frames consistently take longer than the
+/// budget, and back once they consistently stay well within it. What load
+/// is shed is up to the user of the watchdog, e.g. uploading fewer tiles per
+/// frame or skipping decorations.
+#[derive(Debug, Clone)]
+pub struct FrameWatchdog {
+    budget: Duration,
+    over: u32,
+    under: u32,
+    shedding: bool,
+}
+
+impl FrameWatchdog {
+    /// Create a new watchdog with the given time budget per frame.
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            over: 0,
+            under: 0,
+            shedding: false,
+        }
+    }
+
+    /// Whether load should currently be shed.
+    pub fn is_shedding(&self) -> bool {
+        self.shedding
+    }
+
+    /// Record the time taken by a frame. Returns `true` if this changes
+    /// whether load should be shed.
+    pub fn record(&mut self, duration: Duration) -> bool {
+        if duration > self.budget {
+            self.over += 1;
+            self.under = 0;
+        } else if duration.as_secs_f64() < self.budget.as_secs_f64() * RECOVER_THRESHOLD {
+            self.under += 1;
+            self.over = 0;
+        } else {
+            self.over = 0;
+        }
+
+        let shedding = if self.shedding {
+            self.under < RECOVER_FRAMES
+        } else {
+            self.over >= SHED_FRAMES
+        };
+
+        if shedding != self.shedding {
+            self.shedding = shedding;
+            self.over = 0;
+            self.under = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Forget recorded frames and restore full quality, e.g. when a new
+    /// document is shown.
+    pub fn reset(&mut self) {
+        self.over = 0;
+        self.under = 0;
+        self.shedding = false;
+    }
+}
@@ -0,0 +1,136 @@
+//! Full-text search through all pages of a document.
+//!
+//! Each page is searched by its own task on the executor of the document,
+//! starting at a given page and wrapping around at the end of the document.
+//! Results are collected as tasks complete, and the monitor of each task is
+//! notified with the index of the respective page, e.g. for showing results as
+//! they come in.
+
+use std::sync::{Arc, Mutex};
+
+use executor::exec::priority::DropHandle;
+use executor::exec::Monitor;
+use nalgebra::{point, vector};
+
+use pdfium::doc::{Document, SearchFlags};
+
+use crate::core::render::core::TilePriority;
+use crate::core::render::pdfium::Executor;
+use crate::types::{Bounds, Rect};
+
+/// An occurrence of the searched text.
+#[derive(Debug, Clone)]
+pub struct SearchMatch {
+    pub page: usize,
+
+    /// Bounds of the matched text, one rectangle per line, in page
+    /// coordinates.
+    pub rects: Vec<Rect<f64>>,
+}
+
+impl SearchMatch {
+    /// Bounds of all of the matched text, in page coordinates.
+    pub fn bounds(&self) -> Option<Rect<f64>> {
+        self.rects
+            .iter()
+            .map(|r| r.bounds())
+            .reduce(|a, b| Bounds {
+                x_min: a.x_min.min(b.x_min),
+                y_min: a.y_min.min(b.y_min),
+                x_max: a.x_max.max(b.x_max),
+                y_max: a.y_max.max(b.y_max),
+            })
+            .map(Rect::from)
+    }
+}
+
+/// A search through all pages of a document, running in the background.
+/// Remaining tasks are canceled when dropped.
+pub struct Search {
+    results: Arc<Mutex<Vec<Option<Vec<SearchMatch>>>>>,
+    _tasks: Vec<DropHandle<TilePriority, ()>>,
+}
+
+impl Search {
+    /// Search all pages of the given document for the given text, ignoring
+    /// case, starting at the given page. Pages are searched with low priority
+    /// on the given executor, i.e. the one rendering the document, so that
+    /// searching does not hold up rendering.
+    pub fn start<M>(
+        executor: &Executor,
+        doc: &Document,
+        query: &str,
+        first: usize,
+        monitor: M,
+    ) -> Self
+    where
+        M: Monitor<usize> + Clone + Send + 'static,
+    {
+        let count = doc.pages().count() as usize;
+        let results = Arc::new(Mutex::new(vec![None; count]));
+
+        let pages = (first.min(count)..count).chain(0..first.min(count));
+
+        let tasks = pages
+            .map(|page| {
+                let doc = doc.clone();
+                let query = query.to_owned();
+                let results = results.clone();
+
+                let task = move || {
+                    let matches = search_page(&doc, page, &query).unwrap_or_else(|err| {
+                        tracing::warn!(page, error=%err, "failed to search page");
+                        Vec::new()
+                    });
+
+                    results.lock().unwrap()[page] = Some(matches);
+                };
+
+                executor
+                    .submit_with_context(monitor.clone(), page, TilePriority::Low, task)
+                    .cancel_on_drop()
+            })
+            .collect();
+
+        Self {
+            results,
+            _tasks: tasks,
+        }
+    }
+
+    /// Whether all pages have been searched.
+    pub fn is_done(&self) -> bool {
+        self.results.lock().unwrap().iter().all(Option::is_some)
+    }
+
+    /// All matches found so far, in document order.
+    pub fn matches(&self) -> Vec<SearchMatch> {
+        let results = self.results.lock().unwrap();
+        results.iter().flatten().flatten().cloned().collect()
+    }
+}
+
+fn search_page(doc: &Document, index: usize, query: &str) -> pdfium::Result<Vec<SearchMatch>> {
+    let page = doc.pages().get(index as _)?;
+    let height = page.height() as f64;
+
+    let text = page.text()?;
+    let mut matches = Vec::new();
+
+    for range in text.find(query, SearchFlags::empty())? {
+        let rects = text
+            .rects(range.start, range.end - range.start)?
+            .into_iter()
+            .map(|r| {
+                Rect::new(
+                    point![r.left as f64, height - r.top as f64],
+                    vector![(r.right - r.left) as f64, (r.top - r.bottom) as f64],
+                )
+            })
+            .collect();
+
+        matches.push(SearchMatch { page: index, rects });
+    }
+
+    Ok(matches)
+}
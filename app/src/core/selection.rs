@@ -0,0 +1,125 @@
+//! Selection of text with the pointer, possibly spanning multiple pages.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+use nalgebra::Point2;
+
+use pdfium::doc::Document;
+
+use crate::core::caret::CaretText;
+use crate::types::Rect;
+
+/// A position between two characters of the text of a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TextPos {
+    pub page: usize,
+    pub pos: usize,
+}
+
+/// Text selected with the pointer, spanning from the position the selection
+/// has been started at to the current one.
+///
+/// The text of each page is loaded once it becomes part of the selection and
+/// kept for as long as the selection exists.
+#[derive(Debug, Clone)]
+pub struct TextSelection {
+    anchor: TextPos,
+    focus: TextPos,
+    texts: HashMap<usize, CaretText>,
+    rects: BTreeMap<usize, Vec<Rect<f64>>>,
+}
+
+impl TextSelection {
+    /// Start selecting text at the given point, in coordinates of the given
+    /// page. Returns `None` if the page does not have any text.
+    pub fn begin(doc: &Document, page: usize, point: Point2<f64>) -> Option<Self> {
+        let text = CaretText::load(doc, page);
+
+        let anchor = TextPos {
+            page,
+            pos: text.pos_at(point)?,
+        };
+
+        Some(Self {
+            anchor,
+            focus: anchor,
+            texts: HashMap::from([(page, text)]),
+            rects: BTreeMap::new(),
+        })
+    }
+
+    /// Extend the selection to the given point, in coordinates of the given
+    /// page. Points on pages without text leave the selection unchanged.
+    pub fn update(&mut self, doc: &Document, page: usize, point: Point2<f64>) {
+        let pos = match self.load(doc, page).pos_at(point) {
+            Some(pos) => pos,
+            None => return,
+        };
+
+        self.focus = TextPos { page, pos };
+
+        // load the text of all pages in between
+        let (start, end) = self.bounds();
+        for page in start.page..=end.page {
+            self.load(doc, page);
+        }
+
+        self.rects = self
+            .ranges()
+            .map(|(page, range)| (page, self.texts[&page].selection_rects(&range)))
+            .collect();
+    }
+
+    /// Whether no text is selected.
+    pub fn is_empty(&self) -> bool {
+        self.anchor == self.focus
+    }
+
+    /// Bounds of the selected text on the given page, one rectangle per line,
+    /// in page coordinates.
+    pub fn rects(&self, page: usize) -> &[Rect<f64>] {
+        self.rects.get(&page).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Bounds of the selected text on all pages, in page order.
+    pub fn pages(&self) -> impl Iterator<Item = (usize, &[Rect<f64>])> + '_ {
+        self.rects
+            .iter()
+            .map(|(page, rects)| (*page, rects.as_slice()))
+    }
+
+    /// The selected text, with pages separated by line breaks.
+    pub fn text(&self) -> String {
+        self.ranges()
+            .map(|(page, range)| self.texts[&page].text(&range))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn load(&mut self, doc: &Document, page: usize) -> &CaretText {
+        self.texts
+            .entry(page)
+            .or_insert_with(|| CaretText::load(doc, page))
+    }
+
+    fn bounds(&self) -> (TextPos, TextPos) {
+        (self.anchor.min(self.focus), self.anchor.max(self.focus))
+    }
+
+    /// The selected range of characters of each page, in order.
+    fn ranges(&self) -> impl Iterator<Item = (usize, Range<usize>)> + '_ {
+        let (start, end) = self.bounds();
+
+        (start.page..=end.page).map(move |page| {
+            let first = if page == start.page { start.pos } else { 0 };
+            let last = if page == end.page {
+                end.pos
+            } else {
+                self.texts.get(&page).map_or(0, CaretText::len)
+            };
+
+            (page, first..last)
+        })
+    }
+}
@@ -0,0 +1,413 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use gtk::{gio, glib, prelude::FileExt};
+use nalgebra::Point2;
+
+use super::appearance::PageStyle;
+use super::render::core::TilingSchemeKind;
+use super::render::layout::LayoutKind;
+use super::storage::{Area, Storage};
+
+const FILE_NAME: &str = "session.ini";
+
+/// Prefix of the key file groups storing the state of a document.
+const DOCUMENT_GROUP_PREFIX: &str = "document ";
+
+/// Persistent application state, e.g. per-document reading progress.
+///
+/// The state is stored as key file in the session storage area. Each document
+/// is stored in its own group, identified by its URI.
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+    storage: Storage,
+    file: glib::KeyFile,
+}
+
+/// Persistent state of a single document.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentState {
+    /// Fraction of the document that has been scrolled through.
+    pub progress: Option<f64>,
+
+    /// Estimated reading speed, in document fractions per second.
+    pub reading_speed: Option<f64>,
+
+    /// Whether the document may run actions reaching outside of it, e.g.
+    /// open web links, if the user has decided so.
+    pub trusted: Option<bool>,
+
+    /// Time spent viewing each page, in seconds.
+    pub page_times: Option<Vec<f64>>,
+
+    /// Whether pages are shown with light or dark colors.
+    pub page_style: Option<PageStyle>,
+
+    /// Index of the page last viewed.
+    pub page: Option<usize>,
+
+    /// Center of the view when last viewed, in page coordinates (PDF points).
+    pub center: Option<Point2<f64>>,
+
+    /// Scale of the view when last viewed, in pixels per PDF point.
+    pub zoom: Option<f64>,
+
+    /// How pages have been arranged when last viewed.
+    pub layout: Option<LayoutKind>,
+
+    /// Whether page margins have been trimmed when last viewed.
+    pub trim_margins: Option<bool>,
+
+    /// When the document has last been opened, in seconds since the Unix
+    /// epoch.
+    pub last_opened: Option<i64>,
+}
+
+/// A recently opened document.
+#[derive(Debug, Clone)]
+pub struct RecentDocument {
+    pub path: PathBuf,
+
+    /// When the document has last been opened, in seconds since the Unix
+    /// epoch.
+    pub last_opened: i64,
+
+    /// Fraction of the document that has been scrolled through.
+    pub progress: Option<f64>,
+}
+
+/// Spacing of pages on the canvas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayoutSettings {
+    /// Space between pages, in PDF points.
+    pub page_spacing: f64,
+
+    /// Margin left and right of the document, in pixels.
+    pub margin_x: f64,
+
+    /// Margin above and below the document, in pixels.
+    pub margin_y: f64,
+}
+
+impl Default for LayoutSettings {
+    fn default() -> Self {
+        Self {
+            page_spacing: 10.0,
+            margin_x: 50.0,
+            margin_y: 100.0,
+        }
+    }
+}
+
+/// Advanced settings for tiled rendering, e.g. for tuning performance on slow
+/// GPUs or high-resolution monitors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TilingSettings {
+    /// How pages are divided into tiles.
+    pub scheme: TilingSchemeKind,
+
+    /// Size of the tiles, in pixels, before adapting it to complex pages.
+    pub tile_size: i64,
+
+    /// Whether low-resolution renders, e.g. thumbnails, are cached on disk.
+    pub disk_cache: bool,
+
+    /// Whether tiles are uploaded to GL textures on the render threads, if
+    /// supported by the renderer.
+    pub gl_upload: bool,
+}
+
+impl TilingSettings {
+    /// Tile sizes that can be selected.
+    pub const TILE_SIZES: [i64; 4] = [256, 512, 1024, 2048];
+}
+
+impl Default for TilingSettings {
+    fn default() -> Self {
+        Self {
+            scheme: TilingSchemeKind::Hybrid,
+            tile_size: 1024,
+            disk_cache: true,
+            gl_upload: true,
+        }
+    }
+}
+
+impl SessionStore {
+    pub fn load(storage: &Storage) -> Self {
+        let path = storage.path(Area::Session, FILE_NAME);
+        let file = glib::KeyFile::new();
+
+        if let Err(err) = file.load_from_file(&path, glib::KeyFileFlags::NONE) {
+            if !err.matches(glib::FileError::Noent) {
+                tracing::warn!(file=?path, error=%err, "failed to load session state");
+            }
+        }
+
+        Self {
+            storage: storage.clone(),
+            file,
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let data = self.file.to_data();
+
+        self.storage
+            .write(Area::Session, FILE_NAME, data.as_bytes())
+            .map(|_| ())
+    }
+
+    pub fn document(&self, path: &Path) -> DocumentState {
+        let group = Self::document_group(path);
+
+        let center = match (
+            self.file.double(&group, "center-x"),
+            self.file.double(&group, "center-y"),
+        ) {
+            (Ok(x), Ok(y)) => Some(Point2::new(x, y)),
+            _ => None,
+        };
+
+        DocumentState {
+            progress: self.file.double(&group, "progress").ok(),
+            reading_speed: self.file.double(&group, "reading-speed").ok(),
+            trusted: self.file.boolean(&group, "trusted").ok(),
+            page_times: self.file.double_list(&group, "page-times").ok(),
+            page_style: self
+                .file
+                .string(&group, "page-style")
+                .ok()
+                .and_then(|style| PageStyle::parse(&style)),
+            page: self
+                .file
+                .uint64(&group, "page")
+                .ok()
+                .and_then(|page| usize::try_from(page).ok()),
+            center,
+            zoom: self
+                .file
+                .double(&group, "zoom")
+                .ok()
+                .filter(|zoom| *zoom > 0.0),
+            layout: self
+                .file
+                .string(&group, "layout")
+                .ok()
+                .and_then(|layout| LayoutKind::parse(&layout)),
+            trim_margins: self.file.boolean(&group, "trim-margins").ok(),
+            last_opened: self.file.int64(&group, "last-opened").ok(),
+        }
+    }
+
+    pub fn set_document(&self, path: &Path, state: &DocumentState) {
+        let group = Self::document_group(path);
+
+        if let Some(progress) = state.progress {
+            self.file.set_double(&group, "progress", progress);
+        }
+
+        if let Some(speed) = state.reading_speed {
+            self.file.set_double(&group, "reading-speed", speed);
+        }
+
+        if let Some(trusted) = state.trusted {
+            self.file.set_boolean(&group, "trusted", trusted);
+        }
+
+        if let Some(times) = &state.page_times {
+            // write as key file list, there is no binding for the list setter
+            let value: String = times.iter().map(|t| format!("{t:.1};")).collect();
+            self.file.set_value(&group, "page-times", &value);
+        }
+
+        if let Some(style) = state.page_style {
+            self.file.set_string(&group, "page-style", style.as_str());
+        }
+
+        if let Some(page) = state.page {
+            self.file.set_uint64(&group, "page", page as u64);
+        }
+
+        if let Some(center) = state.center {
+            self.file.set_double(&group, "center-x", center.x);
+            self.file.set_double(&group, "center-y", center.y);
+        }
+
+        if let Some(zoom) = state.zoom {
+            self.file.set_double(&group, "zoom", zoom);
+        }
+
+        if let Some(layout) = state.layout {
+            self.file.set_string(&group, "layout", layout.as_str());
+        }
+
+        if let Some(trim) = state.trim_margins {
+            self.file.set_boolean(&group, "trim-margins", trim);
+        }
+
+        if let Some(time) = state.last_opened {
+            self.file.set_int64(&group, "last-opened", time);
+        }
+    }
+
+    /// The most recently opened documents, starting with the latest one.
+    /// Documents that no longer exist are skipped.
+    pub fn recent_documents(&self, limit: usize) -> Vec<RecentDocument> {
+        let mut recent: Vec<_> = self
+            .file
+            .groups()
+            .iter()
+            .filter_map(|group| {
+                let group = group.to_str();
+                let uri = group.strip_prefix(DOCUMENT_GROUP_PREFIX)?;
+                let path = gio::File::for_uri(uri).path()?;
+
+                Some(RecentDocument {
+                    last_opened: self.file.int64(group, "last-opened").ok()?,
+                    progress: self.file.double(group, "progress").ok(),
+                    path,
+                })
+            })
+            .filter(|doc| doc.path.exists())
+            .collect();
+
+        recent.sort_by_key(|doc| std::cmp::Reverse(doc.last_opened));
+        recent.truncate(limit);
+        recent
+    }
+
+    /// Calibrated physical resolution of a monitor, in (logical) pixels per
+    /// inch.
+    pub fn display_dpi(&self, monitor: &str) -> Option<f64> {
+        self.file.double("display", &format!("dpi-{monitor}")).ok()
+    }
+
+    pub fn set_display_dpi(&self, monitor: &str, dpi: f64) {
+        self.file
+            .set_double("display", &format!("dpi-{monitor}"), dpi);
+    }
+
+    /// Whether pages are shown with dark colors while the night light is
+    /// active.
+    pub fn follow_night_light(&self) -> bool {
+        self.file
+            .boolean("appearance", "follow-night-light")
+            .unwrap_or(false)
+    }
+
+    pub fn set_follow_night_light(&self, follow: bool) {
+        self.file
+            .set_boolean("appearance", "follow-night-light", follow);
+    }
+
+    /// Whether dark pages are rendered with night colors instead of showing
+    /// them with inverted lightness.
+    pub fn night_colors(&self) -> bool {
+        self.file
+            .boolean("appearance", "night-colors")
+            .unwrap_or(false)
+    }
+
+    pub fn set_night_colors(&self, enabled: bool) {
+        self.file.set_boolean("appearance", "night-colors", enabled);
+    }
+
+    pub fn layout(&self) -> LayoutSettings {
+        let default = LayoutSettings::default();
+        let get = |key, default| self.file.double("layout", key).unwrap_or(default);
+
+        LayoutSettings {
+            page_spacing: get("page-spacing", default.page_spacing),
+            margin_x: get("margin-x", default.margin_x),
+            margin_y: get("margin-y", default.margin_y),
+        }
+    }
+
+    pub fn set_layout(&self, layout: &LayoutSettings) {
+        self.file
+            .set_double("layout", "page-spacing", layout.page_spacing);
+        self.file.set_double("layout", "margin-x", layout.margin_x);
+        self.file.set_double("layout", "margin-y", layout.margin_y);
+    }
+
+    /// How pages are arranged on the canvas.
+    pub fn page_layout(&self) -> LayoutKind {
+        self.file
+            .string("layout", "mode")
+            .ok()
+            .and_then(|mode| LayoutKind::parse(&mode))
+            .unwrap_or_default()
+    }
+
+    pub fn set_page_layout(&self, kind: LayoutKind) {
+        self.file.set_string("layout", "mode", kind.as_str());
+    }
+
+    /// Time after which presentations advance to the next page, if pages are
+    /// advanced automatically.
+    pub fn presentation_interval(&self) -> Option<Duration> {
+        self.file
+            .double("presentation", "advance-interval")
+            .ok()
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            .filter(|interval| !interval.is_zero())
+    }
+
+    pub fn set_presentation_interval(&self, interval: Option<Duration>) {
+        let secs = interval.map_or(0.0, |interval| interval.as_secs_f64());
+        self.file
+            .set_double("presentation", "advance-interval", secs);
+    }
+
+    pub fn tiling(&self) -> TilingSettings {
+        let default = TilingSettings::default();
+
+        let scheme = self
+            .file
+            .string("tiling", "scheme")
+            .ok()
+            .and_then(|scheme| TilingSchemeKind::parse(&scheme))
+            .unwrap_or(default.scheme);
+
+        let tile_size = self
+            .file
+            .int64("tiling", "tile-size")
+            .ok()
+            .filter(|size| TilingSettings::TILE_SIZES.contains(size))
+            .unwrap_or(default.tile_size);
+
+        let disk_cache = self
+            .file
+            .boolean("tiling", "disk-cache")
+            .unwrap_or(default.disk_cache);
+
+        let gl_upload = self
+            .file
+            .boolean("tiling", "gl-upload")
+            .unwrap_or(default.gl_upload);
+
+        TilingSettings {
+            scheme,
+            tile_size,
+            disk_cache,
+            gl_upload,
+        }
+    }
+
+    pub fn set_tiling(&self, tiling: &TilingSettings) {
+        self.file
+            .set_string("tiling", "scheme", tiling.scheme.as_str());
+        self.file.set_int64("tiling", "tile-size", tiling.tile_size);
+        self.file
+            .set_boolean("tiling", "disk-cache", tiling.disk_cache);
+        self.file
+            .set_boolean("tiling", "gl-upload", tiling.gl_upload);
+    }
+
+    fn document_group(path: &Path) -> String {
+        // use the URI as it is escaped and thus a valid group name
+        let uri = gio::File::for_path(path).uri();
+        format!("{DOCUMENT_GROUP_PREFIX}{uri}")
+    }
+}
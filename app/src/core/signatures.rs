@@ -0,0 +1,309 @@
+//! Detection of digital signatures and signature fields, used to warn that
+//! editing a signed document may invalidate its signatures.
+
+use pdfium::doc::{AnnotationSubtype, Document};
+
+/// A digital signature of a document.
+#[derive(Debug, Clone)]
+pub struct SignatureInfo {
+    /// Name of the signer, taken from the signing certificate.
+    pub signer: Option<String>,
+
+    /// Reason for signing, as given by the signer.
+    pub reason: Option<String>,
+
+    /// Time of signing, formatted as `YYYY-MM-DD HH:MM`.
+    pub time: Option<String>,
+}
+
+/// Signatures and signature fields of a document.
+#[derive(Debug, Clone, Default)]
+pub struct Signatures {
+    pub signatures: Vec<SignatureInfo>,
+
+    /// Number of signature fields that have not been signed yet.
+    pub unsigned_fields: usize,
+}
+
+impl Signatures {
+    pub fn is_signed(&self) -> bool {
+        !self.signatures.is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty() && self.unsigned_fields == 0
+    }
+
+    /// Names of all known signers, without duplicates.
+    pub fn signers(&self) -> Vec<&str> {
+        let mut signers: Vec<&str> = Vec::new();
+
+        for name in self.signatures.iter().filter_map(|s| s.signer.as_deref()) {
+            if !signers.contains(&name) {
+                signers.push(name);
+            }
+        }
+
+        signers
+    }
+}
+
+/// Detect the signatures and signature fields of the given document.
+pub fn detect(doc: &Document) -> Signatures {
+    let signatures: Vec<_> = match doc.signatures() {
+        Ok(signatures) => signatures
+            .iter()
+            .map(|sig| SignatureInfo {
+                signer: signer_name(&sig.contents),
+                reason: sig.reason.clone().filter(|r| !r.trim().is_empty()),
+                time: sig.time.as_deref().and_then(format_time),
+            })
+            .collect(),
+        Err(err) => {
+            tracing::warn!(error=%err, "failed to load document signatures");
+            Vec::new()
+        }
+    };
+
+    // signed fields refer to the signatures above
+    let fields = signature_fields(doc);
+    let unsigned_fields = fields.saturating_sub(signatures.len());
+
+    Signatures {
+        signatures,
+        unsigned_fields,
+    }
+}
+
+/// Count the signature fields of the document, i.e. form widgets with field
+/// type "Sig".
+fn signature_fields(doc: &Document) -> usize {
+    let mut count = 0;
+
+    for index in 0..doc.pages().count() {
+        let page = match doc.pages().get(index) {
+            Ok(page) => page,
+            Err(err) => {
+                tracing::warn!(page = index, error=%err, "failed to load page");
+                continue;
+            }
+        };
+
+        count += page
+            .annotations()
+            .filter(|annot| annot.subtype() == AnnotationSubtype::Widget)
+            .filter(|annot| matches!(annot.string_value("FT"), Ok(Some(ty)) if ty == "Sig"))
+            .count();
+    }
+
+    count
+}
+
+/// Format a PDF date string (e.g. `D:20230102150405+01'00'`) as
+/// `YYYY-MM-DD HH:MM`, ignoring the time zone.
+fn format_time(date: &str) -> Option<String> {
+    let digits = date.strip_prefix("D:").unwrap_or(date);
+    let digits: String = digits.chars().take_while(char::is_ascii_digit).collect();
+
+    if digits.len() < 8 {
+        return None;
+    }
+
+    let (y, m, d) = (&digits[0..4], &digits[4..6], &digits[6..8]);
+
+    if digits.len() >= 12 {
+        let (hh, mm) = (&digits[8..10], &digits[10..12]);
+        Some(format!("{y}-{m}-{d} {hh}:{mm}"))
+    } else {
+        Some(format!("{y}-{m}-{d}"))
+    }
+}
+
+/// Extract the common name of the signer from a PKCS#7 signature.
+///
+/// The signer is identified by the issuer and serial number of its
+/// certificate, which is looked up in the certificates embedded in the
+/// signature. Falls back to the first embedded certificate if the signer
+/// cannot be matched.
+fn signer_name(pkcs7: &[u8]) -> Option<String> {
+    // ContentInfo ::= SEQUENCE { contentType, [0] EXPLICIT content }
+    let (_, content_info, _) = der::read(pkcs7)?;
+    let (_, _content_type, rest) = der::read(content_info)?;
+    let (_, signed_data, _) = der::read(rest)?;
+
+    // SignedData ::= SEQUENCE { version, digestAlgorithms, encapContentInfo,
+    //     [0] IMPLICIT certificates OPTIONAL, [1] IMPLICIT crls OPTIONAL,
+    //     signerInfos }
+    let (_, signed_data, _) = der::read(signed_data)?;
+    let items = der::children(signed_data)?;
+
+    let certs = items
+        .iter()
+        .find(|(tag, _)| *tag == der::CONTEXT_0)
+        .and_then(|(_, certs)| der::children(certs))
+        .unwrap_or_default();
+
+    let signer_infos = items.iter().rev().find(|(tag, _)| *tag == der::SET)?.1;
+
+    // SignerInfo ::= SEQUENCE { version, sid, ... }, with
+    // sid ::= IssuerAndSerialNumber ::= SEQUENCE { issuer, serialNumber }
+    let sid = der::children(signer_infos)?
+        .first()
+        .and_then(|(_, info)| der::children(info))
+        .and_then(|info| info.get(1).copied())
+        .filter(|(tag, _)| *tag == der::SEQUENCE)
+        .and_then(|(_, sid)| der::children(sid));
+
+    let certs: Vec<_> = certs
+        .iter()
+        .filter_map(|(_, cert)| Certificate::parse(cert))
+        .collect();
+
+    let cert = match sid.as_deref() {
+        Some([(_, issuer), (_, serial)]) => certs
+            .iter()
+            .find(|c| c.issuer == *issuer && c.serial == *serial)
+            .or_else(|| certs.first()),
+        _ => certs.first(),
+    };
+
+    common_name(cert?.subject)
+}
+
+/// Relevant parts of an X.509 certificate, as DER contents.
+struct Certificate<'a> {
+    serial: &'a [u8],
+    issuer: &'a [u8],
+    subject: &'a [u8],
+}
+
+impl<'a> Certificate<'a> {
+    /// Parse a certificate from the contents of its outer sequence.
+    fn parse(cert: &'a [u8]) -> Option<Self> {
+        // Certificate ::= SEQUENCE { tbsCertificate, ... }
+        let (_, tbs, _) = der::read(cert)?;
+
+        // TBSCertificate ::= SEQUENCE { [0] EXPLICIT version OPTIONAL,
+        //     serialNumber, signature, issuer, validity, subject, ... }
+        let mut items = der::children(tbs)?;
+        if items.first()?.0 == der::CONTEXT_0 {
+            items.remove(0);
+        }
+
+        Some(Self {
+            serial: items.first()?.1,
+            issuer: items.get(2)?.1,
+            subject: items.get(4)?.1,
+        })
+    }
+}
+
+/// Find the common name (OID 2.5.4.3) in the contents of an X.501 name.
+fn common_name(name: &[u8]) -> Option<String> {
+    const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+
+    // Name ::= SEQUENCE OF SET OF SEQUENCE { type, value }
+    for (_, rdn) in der::children(name)? {
+        for (_, attr) in der::children(rdn)? {
+            match der::children(attr)?.as_slice() {
+                [(der::OID, OID_COMMON_NAME), (tag, value)] => return der::string(*tag, value),
+                _ => continue,
+            }
+        }
+    }
+
+    None
+}
+
+/// Minimal reader for DER- (and BER-) encoded ASN.1 data.
+mod der {
+    pub const OID: u8 = 0x06;
+    pub const SEQUENCE: u8 = 0x30;
+    pub const SET: u8 = 0x31;
+    pub const CONTEXT_0: u8 = 0xa0;
+
+    const UTF8_STRING: u8 = 0x0c;
+    const PRINTABLE_STRING: u8 = 0x13;
+    const T61_STRING: u8 = 0x14;
+    const IA5_STRING: u8 = 0x16;
+    const BMP_STRING: u8 = 0x1e;
+
+    /// Read the first element, returning its tag, its contents, and the data
+    /// following it.
+    pub fn read(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+        let (&tag, rest) = data.split_first()?;
+
+        // we only need low tag numbers
+        if tag & 0x1f == 0x1f {
+            return None;
+        }
+
+        let (&len, rest) = rest.split_first()?;
+
+        // indefinite length, contents end with two zero bytes (BER only)
+        if len == 0x80 {
+            let end = indefinite_len(rest)?;
+            return Some((tag, &rest[..end], &rest[end + 2..]));
+        }
+
+        let (len, rest) = if len & 0x80 == 0 {
+            (len as usize, rest)
+        } else {
+            let n = (len & 0x7f) as usize;
+            if n > std::mem::size_of::<usize>() || rest.len() < n {
+                return None;
+            }
+
+            let len = rest[..n].iter().fold(0, |acc, b| (acc << 8) | *b as usize);
+            (len, &rest[n..])
+        };
+
+        if rest.len() < len {
+            return None;
+        }
+
+        Some((tag, &rest[..len], &rest[len..]))
+    }
+
+    /// Length of the contents of an element with indefinite length.
+    fn indefinite_len(data: &[u8]) -> Option<usize> {
+        let mut pos = 0;
+
+        while !data[pos..].starts_with(&[0, 0]) {
+            let (_, _, rest) = read(&data[pos..])?;
+            pos = data.len() - rest.len();
+        }
+
+        Some(pos)
+    }
+
+    /// All elements in the given contents of a constructed element.
+    pub fn children(mut data: &[u8]) -> Option<Vec<(u8, &[u8])>> {
+        let mut items = Vec::new();
+
+        while !data.is_empty() {
+            let (tag, contents, rest) = read(data)?;
+            items.push((tag, contents));
+            data = rest;
+        }
+
+        Some(items)
+    }
+
+    /// Decode the contents of a string element.
+    pub fn string(tag: u8, data: &[u8]) -> Option<String> {
+        match tag {
+            UTF8_STRING | PRINTABLE_STRING | IA5_STRING => String::from_utf8(data.to_vec()).ok(),
+            T61_STRING => Some(data.iter().map(|&b| b as char).collect()),
+            BMP_STRING => {
+                let units: Vec<u16> = data
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+
+                String::from_utf16(&units).ok()
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Stamps placed on pages, e.g. "APPROVED", the current date, or an image.
+//!
+//! Stamps are kept as overlay of the document while being placed, i.e. while
+//! selected, and written to it as stamp annotations once deselected.
+
+use std::path::{Path, PathBuf};
+
+use gtk::gdk_pixbuf::Pixbuf;
+use gtk::glib;
+use nalgebra::{point, Point2, Vector2};
+
+use pdfium::bitmap::{Bitmap, BitmapFormat, Color};
+use pdfium::doc::{AnnotationSubtype, Page};
+use pdfium::types as pdf;
+
+use crate::types::Rect;
+
+/// Minimum width and height of a stamp, in PDF points.
+const MIN_SIZE: f64 = 8.0;
+
+/// Color of text stamps.
+pub const TEXT_COLOR: Color = Color::new_rgb(204, 26, 26);
+
+/// Padding between the text and the border of text stamps, in PDF points.
+pub const TEXT_PADDING: f64 = 8.0;
+
+/// Border width of text stamps, in PDF points.
+pub const TEXT_BORDER: f64 = 2.0;
+
+/// Standard PDF font used for text stamps written to the document.
+const TEXT_FONT: &str = "Helvetica-Bold";
+
+/// Content of a stamp.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StampContent {
+    Text(String),
+
+    /// Image file, e.g. a PNG of a signature.
+    Image(PathBuf),
+}
+
+/// A stamp placed on a page.
+#[derive(Debug, Clone)]
+pub struct Stamp {
+    pub page: usize,
+
+    /// Bounds of the stamp, in page coordinates (PDF points).
+    pub rect: Rect<f64>,
+
+    pub content: StampContent,
+}
+
+/// Corner of a stamp, used for resizing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl StampContent {
+    /// Text stamp with the current date.
+    pub fn date() -> Self {
+        let date = glib::DateTime::now_local()
+            .and_then(|now| now.format("%Y-%m-%d"))
+            .map(|date| date.to_string())
+            .unwrap_or_default();
+
+        Self::Text(date)
+    }
+}
+
+impl Corner {
+    pub const ALL: [Corner; 4] = [
+        Corner::TopLeft,
+        Corner::TopRight,
+        Corner::BottomLeft,
+        Corner::BottomRight,
+    ];
+}
+
+impl Stamp {
+    /// Create a stamp of the given size centered at the given point.
+    pub fn new(
+        page: usize,
+        center: Point2<f64>,
+        size: Vector2<f64>,
+        content: StampContent,
+    ) -> Self {
+        Self {
+            page,
+            rect: Rect::new(center - size / 2.0, size),
+            content,
+        }
+    }
+
+    /// Position of the given corner, in page coordinates.
+    pub fn corner(&self, corner: Corner) -> Point2<f64> {
+        let r = &self.rect;
+
+        match corner {
+            Corner::TopLeft => r.offs,
+            Corner::TopRight => point![r.offs.x + r.size.x, r.offs.y],
+            Corner::BottomLeft => point![r.offs.x, r.offs.y + r.size.y],
+            Corner::BottomRight => r.offs + r.size,
+        }
+    }
+
+    /// Add the stamp to the given page as stamp annotation. The page height is
+    /// required to transform page coordinates to PDF coordinates.
+    pub fn write(&self, page: &Page, height: f64) -> pdfium::Result<()> {
+        // PDF coordinates have their origin at the bottom left corner
+        let r = &self.rect;
+        let rect = pdf::Rect {
+            left: r.offs.x as f32,
+            top: (height - r.offs.y) as f32,
+            right: (r.offs.x + r.size.x) as f32,
+            bottom: (height - r.offs.y - r.size.y) as f32,
+        };
+
+        // load images before creating the annotation, so that failing to do
+        // so leaves nothing behind
+        let image = match &self.content {
+            StampContent::Text(_) => None,
+            StampContent::Image(path) => Some(load_image(page, path)?),
+        };
+
+        let annot = page.create_annotation(AnnotationSubtype::Stamp)?;
+
+        let result = annot.set_rect(&rect).and_then(|_| match &self.content {
+            StampContent::Text(text) => {
+                // draw the border centered on the stamp bounds, as on screen
+                let half = (TEXT_BORDER / 2.0) as f32;
+                let border = [
+                    pdf::Point2::new(rect.left + half, rect.bottom + half),
+                    pdf::Point2::new(rect.right - half, rect.bottom + half),
+                    pdf::Point2::new(rect.right - half, rect.top - half),
+                    pdf::Point2::new(rect.left + half, rect.top - half),
+                    pdf::Point2::new(rect.left + half, rect.bottom + half),
+                ];
+
+                let pad = TEXT_PADDING as f32;
+                let inner = pdf::Rect {
+                    left: rect.left + pad,
+                    top: rect.top - pad,
+                    right: rect.right - pad,
+                    bottom: rect.bottom + pad,
+                };
+
+                annot
+                    .set_color(TEXT_COLOR)
+                    .and_then(|_| annot.set_contents(text))
+                    .and_then(|_| annot.append_path(&border, TEXT_COLOR, TEXT_BORDER as _))
+                    .and_then(|_| annot.append_text(text, TEXT_FONT, TEXT_COLOR, &inner))
+            }
+            StampContent::Image(_) => image
+                .as_ref()
+                .map_or(Ok(()), |image| annot.append_image(image, &rect)),
+        });
+
+        drop(annot);
+
+        // don't leave incomplete annotations behind
+        if result.is_err() {
+            let count = page.annotation_count();
+            if count > 0 {
+                page.remove_annotation(count - 1)?;
+            }
+        }
+
+        result
+    }
+
+    /// The corner within the given distance of the given point, if any.
+    pub fn corner_at(&self, point: &Point2<f64>, tolerance: f64) -> Option<Corner> {
+        Corner::ALL.into_iter().find(|corner| {
+            let d = self.corner(*corner) - point;
+            d.x.abs() <= tolerance && d.y.abs() <= tolerance
+        })
+    }
+
+    /// Move the given corner to the given point, keeping the opposite corner
+    /// in place.
+    pub fn resize(&mut self, corner: Corner, point: Point2<f64>) {
+        let opposite = match corner {
+            Corner::TopLeft => self.corner(Corner::BottomRight),
+            Corner::TopRight => self.corner(Corner::BottomLeft),
+            Corner::BottomLeft => self.corner(Corner::TopRight),
+            Corner::BottomRight => self.corner(Corner::TopLeft),
+        };
+
+        // keep the opposite corner fixed and enforce the minimum size
+        let x = match corner {
+            Corner::TopLeft | Corner::BottomLeft => point.x.min(opposite.x - MIN_SIZE),
+            Corner::TopRight | Corner::BottomRight => point.x.max(opposite.x + MIN_SIZE),
+        };
+
+        let y = match corner {
+            Corner::TopLeft | Corner::TopRight => point.y.min(opposite.y - MIN_SIZE),
+            Corner::BottomLeft | Corner::BottomRight => point.y.max(opposite.y + MIN_SIZE),
+        };
+
+        let x_min = x.min(opposite.x);
+        let y_min = y.min(opposite.y);
+
+        self.rect = Rect::new(
+            point![x_min, y_min],
+            Vector2::new((x - opposite.x).abs(), (y - opposite.y).abs()),
+        );
+    }
+}
+
+/// Load the image of an image stamp as bitmap for writing it to the given
+/// page.
+fn load_image(page: &Page, path: &Path) -> pdfium::Result<Bitmap<Vec<u8>>> {
+    let pixbuf = Pixbuf::from_file(path).map_err(|err| {
+        pdfium::Error::IoError(std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    })?;
+
+    let width = pixbuf.width() as usize;
+    let height = pixbuf.height() as usize;
+    let channels = pixbuf.n_channels() as usize;
+    let rowstride = pixbuf.rowstride() as usize;
+    let pixels = pixbuf.read_pixel_bytes();
+
+    // pixbufs are RGB(A), pdfium expects BGRA
+    let mut buffer = vec![0xff; width * height * 4];
+    for y in 0..height {
+        for x in 0..width {
+            let src = &pixels[y * rowstride + x * channels..][..channels];
+            let dst = &mut buffer[(y * width + x) * 4..][..4];
+
+            dst[0] = src[2];
+            dst[1] = src[1];
+            dst[2] = src[0];
+            if channels == 4 {
+                dst[3] = src[3];
+            }
+        }
+    }
+
+    Bitmap::from_buf(
+        page.library().clone(),
+        width as _,
+        height as _,
+        BitmapFormat::Bgra,
+        buffer,
+        (width * 4) as _,
+    )
+}
@@ -0,0 +1,117 @@
+//! Statistics of a document, e.g. its word count.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use pdfium::doc::{Document, Page, PageObjectType};
+
+/// Statistics of a document.
+///
+/// Counts relying on optional features of pdfium are `None` if these are not
+/// supported by the installed library.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentStats {
+    pub pages: usize,
+
+    /// Number of words of the text layer.
+    pub words: Option<usize>,
+
+    /// Number of characters of the text layer, excluding whitespace.
+    pub characters: Option<usize>,
+
+    pub images: Option<usize>,
+    pub annotations: usize,
+
+    /// Number of page objects, i.e. of text runs, paths, images, etc.
+    pub objects: Option<usize>,
+}
+
+impl DocumentStats {
+    /// Compute the statistics of the given document, page by page.
+    ///
+    /// Calls `progress` with the number of processed and total pages after
+    /// each page. Returns `None` if canceled before being done. Pages that
+    /// fail to load are left out.
+    pub fn compute<F>(doc: &Document, canceled: &AtomicBool, mut progress: F) -> Option<Self>
+    where
+        F: FnMut(usize, usize),
+    {
+        let count = doc.pages().count() as usize;
+
+        let mut stats = DocumentStats {
+            pages: count,
+            words: Some(0),
+            characters: Some(0),
+            images: Some(0),
+            annotations: 0,
+            objects: Some(0),
+        };
+
+        for index in 0..count {
+            if canceled.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            match doc.pages().get(index as _) {
+                Ok(page) => stats.add_page(&page, index),
+                Err(err) => tracing::warn!(page = index, error=%err, "failed to load page"),
+            }
+
+            progress(index + 1, count);
+        }
+
+        Some(stats)
+    }
+
+    fn add_page(&mut self, page: &Page, index: usize) {
+        self.annotations += page.annotation_count() as usize;
+
+        match page.text().and_then(|text| text.text()) {
+            Ok(text) => {
+                let words = text.split_whitespace().count();
+                let chars = text.chars().filter(|c| !c.is_whitespace()).count();
+
+                self.words = self.words.map(|n| n + words);
+                self.characters = self.characters.map(|n| n + chars);
+            }
+            Err(pdfium::Error::Unsupported(_)) => {
+                self.words = None;
+                self.characters = None;
+            }
+            Err(err) => tracing::warn!(page = index, error=%err, "failed to load page text"),
+        }
+
+        match page.object_types() {
+            Ok(types) => {
+                let images = types
+                    .iter()
+                    .filter(|t| **t == PageObjectType::Image)
+                    .count();
+
+                self.images = self.images.map(|n| n + images);
+                self.objects = self.objects.map(|n| n + types.len());
+            }
+            Err(pdfium::Error::Unsupported(_)) => {
+                self.images = None;
+                self.objects = None;
+            }
+            Err(err) => tracing::warn!(page = index, error=%err, "failed to load page objects"),
+        }
+    }
+
+    /// Average number of page objects per page, as a measure of how complex
+    /// pages are to render.
+    pub fn objects_per_page(&self) -> Option<f64> {
+        let objects = self.objects?;
+        (self.pages > 0).then(|| objects as f64 / self.pages as f64)
+    }
+}
+
+/// Hash of the content of a document file, identifying the document
+/// regardless of its path.
+pub fn content_hash(data: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(data);
+    hasher.finish()
+}
@@ -0,0 +1,288 @@
+//! Locations and size limits of all on-disk artifacts.
+//!
+//! Artifacts are grouped into areas, each stored in its own directory below
+//! the XDG base directory matching its purpose: persistent data (e.g. the
+//! session state) goes into the data directory, regenerable data (e.g. cached
+//! tiles) into the cache directory, and transient state (e.g. recovery files)
+//! into the state directory. Base directories can be overridden via the
+//! `PAPR_DATA_DIR`, `PAPR_CACHE_DIR`, and `PAPR_STATE_DIR` environment
+//! variables.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use gtk::glib;
+
+const APP_DIR: &str = "papr";
+
+/// Suffix of temporary files, renamed to their final name once complete.
+const TEMP_SUFFIX: &str = ".tmp";
+
+/// A group of related on-disk artifacts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Area {
+    /// Persistent application state, e.g. reading progress.
+    Session,
+
+    /// Rendered tiles.
+    TileCache,
+
+    /// Results of text recognition.
+    OcrCache,
+
+    /// Files for restoring unsaved changes after a crash.
+    Recovery,
+}
+
+impl Area {
+    pub const ALL: [Area; 4] = [
+        Area::Session,
+        Area::TileCache,
+        Area::OcrCache,
+        Area::Recovery,
+    ];
+
+    /// Human-readable name of this area.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Area::Session => "Session",
+            Area::TileCache => "Tile Cache",
+            Area::OcrCache => "Text Recognition Cache",
+            Area::Recovery => "Recovery Files",
+        }
+    }
+
+    /// Whether this area only contains data that can be regenerated.
+    pub fn is_cache(&self) -> bool {
+        matches!(self, Area::TileCache | Area::OcrCache)
+    }
+
+    /// Size limit of this area, in bytes.
+    pub fn limit(&self) -> Option<u64> {
+        match self {
+            Area::Session => None,
+            Area::TileCache => Some(512 * 1024 * 1024),
+            Area::OcrCache => Some(128 * 1024 * 1024),
+            Area::Recovery => Some(64 * 1024 * 1024),
+        }
+    }
+
+    /// Age after which files of this area are removed, regardless of the
+    /// size limit.
+    pub fn max_age(&self) -> Option<Duration> {
+        match self {
+            Area::TileCache => Some(Duration::from_secs(30 * 24 * 60 * 60)),
+            _ => None,
+        }
+    }
+
+    fn dir_name(&self) -> &'static str {
+        match self {
+            Area::Session => "",
+            Area::TileCache => "tiles",
+            Area::OcrCache => "ocr",
+            Area::Recovery => "recovery",
+        }
+    }
+}
+
+/// Access to the on-disk storage areas.
+#[derive(Debug, Clone)]
+pub struct Storage {
+    data_dir: PathBuf,
+    cache_dir: PathBuf,
+    state_dir: PathBuf,
+}
+
+impl Storage {
+    /// Storage at the XDG base directories of the current user, unless
+    /// overridden via environment variables.
+    pub fn from_env() -> Self {
+        let dir = |var: &str, default: PathBuf| {
+            std::env::var_os(var)
+                .map(PathBuf::from)
+                .filter(|path| path.is_absolute())
+                .unwrap_or_else(|| default.join(APP_DIR))
+        };
+
+        Self::new(
+            dir("PAPR_DATA_DIR", glib::user_data_dir()),
+            dir("PAPR_CACHE_DIR", glib::user_cache_dir()),
+            dir("PAPR_STATE_DIR", user_state_dir()),
+        )
+    }
+
+    pub fn new(data_dir: PathBuf, cache_dir: PathBuf, state_dir: PathBuf) -> Self {
+        Self {
+            data_dir,
+            cache_dir,
+            state_dir,
+        }
+    }
+
+    /// Directory of the given area.
+    pub fn dir(&self, area: Area) -> PathBuf {
+        let base = match area {
+            Area::Session => &self.data_dir,
+            Area::TileCache | Area::OcrCache => &self.cache_dir,
+            Area::Recovery => &self.state_dir,
+        };
+
+        base.join(area.dir_name())
+    }
+
+    /// Path of the file with the given name in the given area.
+    pub fn path(&self, area: Area, name: &str) -> PathBuf {
+        self.dir(area).join(name)
+    }
+
+    /// Write the file with the given name in the given area.
+    ///
+    /// The data is first written to a temporary file, which then replaces the
+    /// target. Readers therefore never see partially written files, even if
+    /// we are interrupted. Afterwards, old files are evicted if the area
+    /// exceeds its size limit.
+    pub fn write(&self, area: Area, name: &str, data: &[u8]) -> io::Result<PathBuf> {
+        let path = self.write_without_eviction(area, name, data)?;
+
+        self.enforce_limit(area, Some(&path))?;
+        Ok(path)
+    }
+
+    /// Write the file with the given name in the given area, like
+    /// [`write()`](Self::write), but without evicting old files. Meant for
+    /// writing many small files, e.g. cached tiles, after which
+    /// [`enforce_limit()`](Self::enforce_limit) should be called.
+    pub fn write_without_eviction(
+        &self,
+        area: Area,
+        name: &str,
+        data: &[u8],
+    ) -> io::Result<PathBuf> {
+        let path = self.path(area, name);
+        let temp = self.path(area, &format!("{name}{TEMP_SUFFIX}"));
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(&temp, data)?;
+        fs::rename(&temp, &path)?;
+
+        Ok(path)
+    }
+
+    /// Total size of all files in the given area, in bytes.
+    pub fn usage(&self, area: Area) -> u64 {
+        self.files(area).iter().map(|f| f.size).sum()
+    }
+
+    /// Remove files of the given area older than its maximum age, then the
+    /// least recently modified files until it is within its size limit. The
+    /// file given as `keep`, if any, is never removed.
+    pub fn enforce_limit(&self, area: Area, keep: Option<&Path>) -> io::Result<()> {
+        if area.limit().is_none() && area.max_age().is_none() {
+            return Ok(());
+        }
+
+        let limit = area.limit().unwrap_or(u64::MAX);
+        let cutoff = area
+            .max_age()
+            .and_then(|age| SystemTime::now().checked_sub(age))
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+
+        let mut files = self.files(area);
+        let mut usage: u64 = files.iter().map(|f| f.size).sum();
+
+        files.sort_by_key(|f| f.modified);
+
+        for file in files {
+            if usage <= limit && file.modified >= cutoff {
+                break;
+            }
+
+            if Some(file.path.as_path()) == keep {
+                continue;
+            }
+
+            fs::remove_file(&file.path)?;
+            usage -= file.size;
+        }
+
+        Ok(())
+    }
+
+    /// Remove all files of the given area.
+    pub fn clear(&self, area: Area) -> io::Result<()> {
+        for file in self.files(area) {
+            fs::remove_file(&file.path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove all files of areas only containing regenerable data.
+    pub fn clear_caches(&self) -> io::Result<()> {
+        for area in Area::ALL.iter().filter(|a| a.is_cache()) {
+            self.clear(*area)?;
+        }
+
+        Ok(())
+    }
+
+    /// All files of the given area. The session area shares its directory
+    /// with the others if they have the same base directory, so only list its
+    /// direct entries.
+    fn files(&self, area: Area) -> Vec<FileInfo> {
+        let mut files = Vec::new();
+        collect_files(&self.dir(area), area != Area::Session, &mut files);
+        files
+    }
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+struct FileInfo {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+fn collect_files(dir: &Path, recursive: bool, files: &mut Vec<FileInfo>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let meta = match entry.metadata() {
+            Ok(meta) => meta,
+            Err(_) => continue,
+        };
+
+        if meta.is_dir() {
+            if recursive {
+                collect_files(&entry.path(), recursive, files);
+            }
+        } else {
+            files.push(FileInfo {
+                path: entry.path(),
+                size: meta.len(),
+                modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+    }
+}
+
+fn user_state_dir() -> PathBuf {
+    std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .unwrap_or_else(|| glib::home_dir().join(".local").join("state"))
+}
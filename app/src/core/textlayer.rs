@@ -0,0 +1,369 @@
+//! Export of the text layer of a document, i.e. its text with bounding boxes,
+//! to structured formats for use in downstream tools.
+
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::Path;
+
+use pdfium::doc::{Document, Page, TextPage};
+use pdfium::types as pdf;
+
+/// Output format of a text layer export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// JSON, with pages containing lines containing words.
+    Json,
+
+    /// hOCR, i.e. HTML annotated with OCR layout information.
+    Hocr,
+}
+
+/// Bounding box in page coordinates (PDF points, origin at the top left).
+#[derive(Debug, Clone, Copy)]
+pub struct BBox {
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub y1: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Word {
+    pub text: String,
+    pub bbox: BBox,
+}
+
+#[derive(Debug, Clone)]
+pub struct Line {
+    pub words: Vec<Word>,
+    pub bbox: BBox,
+}
+
+#[derive(Debug, Clone)]
+pub struct PageText {
+    pub index: usize,
+    pub width: f64,
+    pub height: f64,
+    pub lines: Vec<Line>,
+}
+
+impl Format {
+    /// Parse a format by its name, i.e. `json` or `hocr`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(Self::Json),
+            "hocr" => Some(Self::Hocr),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from the extension of the given path, e.g. ".json".
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        match ext.as_str() {
+            "json" => Some(Self::Json),
+            "hocr" | "html" | "htm" => Some(Self::Hocr),
+            _ => None,
+        }
+    }
+}
+
+/// Export the text layer of all pages of the document.
+pub fn export<W: Write>(doc: &Document, format: Format, out: &mut W) -> std::io::Result<()> {
+    let pages = (0..doc.pages().count()).filter_map(|index| {
+        let result = doc
+            .pages()
+            .get(index)
+            .and_then(|page| page_text(&page, &page.text()?, index as usize));
+
+        match result {
+            Ok(text) => Some(text),
+            Err(err) => {
+                tracing::warn!(page = index, error=%err, "failed to load page text");
+                None
+            }
+        }
+    });
+
+    let output = match format {
+        Format::Json => to_json(pages),
+        Format::Hocr => to_hocr(pages),
+    };
+
+    out.write_all(output.as_bytes())
+}
+
+/// Extract the text of a page, grouped into lines and words.
+pub fn page_text(page: &Page, text_page: &TextPage, index: usize) -> pdfium::Result<PageText> {
+    let height = page.height() as f64;
+
+    // PDF coordinates have their origin at the bottom left corner
+    let bbox = |r: &pdf::Rect| BBox {
+        x0: r.left as f64,
+        y0: height - r.top as f64,
+        x1: r.right as f64,
+        y1: height - r.bottom as f64,
+    };
+
+    let lines = text_page
+        .structure()?
+        .lines
+        .into_iter()
+        .map(|line| Line {
+            bbox: bbox(&line.rect),
+            words: line
+                .words
+                .into_iter()
+                .map(|word| Word {
+                    bbox: bbox(&word.rect),
+                    text: word.text,
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(PageText {
+        index,
+        width: page.width() as f64,
+        height,
+        lines,
+    })
+}
+
+fn to_json(pages: impl Iterator<Item = PageText>) -> String {
+    let mut out = String::from("{\n  \"pages\": [");
+
+    for (i, page) in pages.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+
+        let _ = write!(
+            out,
+            "\n    {{\n      \"index\": {},\n      \"width\": {:.2},\n      \"height\": {:.2},\n      \"lines\": [",
+            page.index, page.width, page.height
+        );
+
+        for (j, line) in page.lines.iter().enumerate() {
+            if j > 0 {
+                out.push(',');
+            }
+
+            let _ = write!(
+                out,
+                "\n        {{\n          \"bbox\": {},\n          \"words\": [",
+                json_bbox(&line.bbox)
+            );
+
+            for (k, word) in line.words.iter().enumerate() {
+                if k > 0 {
+                    out.push(',');
+                }
+
+                let _ = write!(
+                    out,
+                    "\n            {{ \"text\": \"{}\", \"bbox\": {} }}",
+                    json_escape(&word.text),
+                    json_bbox(&word.bbox)
+                );
+            }
+
+            out.push_str("\n          ]\n        }");
+        }
+
+        out.push_str("\n      ]\n    }");
+    }
+
+    out.push_str("\n  ]\n}\n");
+    out
+}
+
+fn json_bbox(b: &BBox) -> String {
+    format!("[{:.2}, {:.2}, {:.2}, {:.2}]", b.x0, b.y0, b.x1, b.y1)
+}
+
+fn json_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+fn to_hocr(pages: impl Iterator<Item = PageText>) -> String {
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Transitional//EN\"\n",
+        "    \"http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd\">\n",
+        "<html xmlns=\"http://www.w3.org/1999/xhtml\">\n",
+        "<head>\n",
+        "  <meta http-equiv=\"Content-Type\" content=\"text/html; charset=utf-8\" />\n",
+        "  <meta name=\"ocr-system\" content=\"papr\" />\n",
+        "  <meta name=\"ocr-capabilities\" content=\"ocr_page ocr_line ocrx_word\" />\n",
+        "</head>\n",
+        "<body>\n",
+    ));
+
+    for page in pages {
+        let bbox = BBox {
+            x0: 0.0,
+            y0: 0.0,
+            x1: page.width,
+            y1: page.height,
+        };
+
+        let _ = writeln!(
+            out,
+            "  <div class=\"ocr_page\" id=\"page_{0}\" title=\"ppageno {0}; {1}\">",
+            page.index,
+            hocr_bbox(&bbox)
+        );
+
+        for (j, line) in page.lines.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "    <span class=\"ocr_line\" id=\"line_{}_{}\" title=\"{}\">",
+                page.index,
+                j,
+                hocr_bbox(&line.bbox)
+            );
+
+            for word in &line.words {
+                let _ = writeln!(
+                    out,
+                    "      <span class=\"ocrx_word\" title=\"{}\">{}</span>",
+                    hocr_bbox(&word.bbox),
+                    html_escape(&word.text)
+                );
+            }
+
+            out.push_str("    </span>\n");
+        }
+
+        out.push_str("  </div>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Bounding box in hOCR notation. hOCR requires integer coordinates.
+fn hocr_bbox(b: &BBox) -> String {
+    format!(
+        "bbox {} {} {} {}",
+        b.x0.floor() as i64,
+        b.y0.floor() as i64,
+        b.x1.ceil() as i64,
+        b.y1.ceil() as i64
+    )
+}
+
+fn html_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bbox(x0: f64, y0: f64, x1: f64, y1: f64) -> BBox {
+        BBox { x0, y0, x1, y1 }
+    }
+
+    fn word(text: &str, bbox: BBox) -> Word {
+        Word {
+            text: text.into(),
+            bbox,
+        }
+    }
+
+    /// A page with a single line of words that need to be escaped.
+    fn page() -> PageText {
+        PageText {
+            index: 2,
+            width: 595.276,
+            height: 841.89,
+            lines: vec![Line {
+                bbox: bbox(10.5, 20.25, 130.333, 40.0),
+                words: vec![
+                    word("say \"hi\"", bbox(10.5, 20.25, 60.0, 40.0)),
+                    word("<a&b>", bbox(65.0, 20.25, 90.0, 40.0)),
+                    word("it's\\\t", bbox(95.0, 20.25, 130.333, 40.0)),
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn json_output() {
+        let expected = r#"{
+  "pages": [
+    {
+      "index": 2,
+      "width": 595.28,
+      "height": 841.89,
+      "lines": [
+        {
+          "bbox": [10.50, 20.25, 130.33, 40.00],
+          "words": [
+            { "text": "say \"hi\"", "bbox": [10.50, 20.25, 60.00, 40.00] },
+            { "text": "<a&b>", "bbox": [65.00, 20.25, 90.00, 40.00] },
+            { "text": "it's\\\u0009", "bbox": [95.00, 20.25, 130.33, 40.00] }
+          ]
+        }
+      ]
+    }
+  ]
+}
+"#;
+
+        assert_eq!(to_json([page()].into_iter()), expected);
+        assert_eq!(to_json(std::iter::empty()), "{\n  \"pages\": [\n  ]\n}\n");
+    }
+
+    #[test]
+    fn hocr_output() {
+        let expected = concat!(
+            "<body>\n",
+            "  <div class=\"ocr_page\" id=\"page_2\" title=\"ppageno 2; bbox 0 0 596 842\">\n",
+            "    <span class=\"ocr_line\" id=\"line_2_0\" title=\"bbox 10 20 131 40\">\n",
+            "      <span class=\"ocrx_word\" title=\"bbox 10 20 60 40\">",
+            "say &quot;hi&quot;</span>\n",
+            "      <span class=\"ocrx_word\" title=\"bbox 65 20 90 40\">",
+            "&lt;a&amp;b&gt;</span>\n",
+            "      <span class=\"ocrx_word\" title=\"bbox 95 20 131 40\">",
+            "it&#39;s\\\t</span>\n",
+            "    </span>\n",
+            "  </div>\n",
+            "</body>\n",
+            "</html>\n",
+        );
+
+        let output = to_hocr([page()].into_iter());
+        assert!(output.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+        assert!(output.ends_with(expected), "unexpected output:\n{output}");
+    }
+}
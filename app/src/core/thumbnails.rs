@@ -0,0 +1,111 @@
+//! Thumbnails of all pages of a document, e.g. for a page overview.
+//!
+//! Thumbnails are rendered like the lowest-resolution fallbacks of a
+//! [`DocumentView`](crate::core::view::DocumentView), but independent of any
+//! viewport: every page is rendered to fit into a square of fixed size, pages
+//! shown by the frontend first, and kept once rendered.
+
+use std::ops::Range;
+
+use executor::exec::Monitor;
+use nalgebra::{point, vector, Vector2};
+
+use pdfium::doc::Document;
+
+use crate::core::render::core::{FallbackManager, FallbackSpec, PageData, TileProvider};
+use crate::core::render::disk::DiskCache;
+use crate::core::render::interop::TileFactory;
+use crate::core::render::layout::{page_sizes, Layout, LayoutProvider, VerticalLayout};
+use crate::core::render::pdfium::{Damage, Executor, Handle, PdfTileProvider, RenderOptions};
+use crate::types::{Rect, Viewport};
+
+/// Number of pages kept loaded while rendering thumbnails.
+const PAGE_CACHE_CAPACITY: usize = 4;
+
+pub struct Thumbnails<M, F>
+where
+    F: TileFactory,
+    F::Data: Send,
+{
+    layout: Layout,
+    provider: PdfTileProvider<M, F>,
+    thumbnails: FallbackManager<Handle<F::Data>>,
+    render: RenderOptions,
+}
+
+impl<M, F> Thumbnails<M, F>
+where
+    M: Monitor<Damage> + Send + Clone + 'static,
+    F: TileFactory + Send + Clone + 'static,
+    F::Data: Send,
+{
+    /// Render thumbnails of the given document, fitting into a square of the
+    /// given size, in pixels.
+    ///
+    /// The monitor is notified whenever a thumbnail has been rendered, after
+    /// which [`update()`](Self::update) moves it to the cache.
+    pub fn new(doc: Document, monitor: M, factory: F, size: i64, render: RenderOptions) -> Self {
+        // page sizes are corrected the same way as for the document view
+        let layout = VerticalLayout.compute(page_sizes(&doc), 0.0);
+
+        let thumbnails = FallbackManager::new(&[FallbackSpec {
+            halo: usize::MAX,
+            render_threshold: vector![0.0, 0.0],
+            render_limits: vector![size, size],
+        }]);
+
+        let executor = Executor::new(1);
+        let provider = PdfTileProvider::new(executor, monitor, factory, doc, PAGE_CACHE_CAPACITY);
+
+        Self {
+            layout,
+            provider,
+            thumbnails,
+            render,
+        }
+    }
+
+    /// Load thumbnails from and store them in the given disk cache.
+    pub fn set_disk_cache(&mut self, cache: Option<DiskCache>) {
+        self.provider.set_disk_cache(cache);
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.layout.rects.len()
+    }
+
+    /// Size of the given page in PDF points, e.g. for reserving space for its
+    /// thumbnail before it has been rendered.
+    pub fn page_size(&self, page: usize) -> Option<Vector2<f64>> {
+        self.layout.rects.get(page).map(|r| r.size)
+    }
+
+    /// Move rendered thumbnails to the cache and request missing ones, with
+    /// those of the given pages first.
+    pub fn update(&mut self, shown: Range<usize>) {
+        let n = self.page_count();
+        let shown = shown.start.min(n)..shown.end.min(n);
+
+        // thumbnails are rendered regardless of any viewport, so neither the
+        // viewport nor the page transformation matter
+        let vp = Viewport {
+            r: Rect::new(point![0.0, 0.0], vector![0.0, 0.0]),
+            scale: 1.0,
+        };
+        let transform = |rect: &Rect<f64>| *rect;
+
+        let layout = &self.layout;
+        let thumbnails = &mut self.thumbnails;
+        let render = &self.render;
+
+        self.provider.request(&shown, |source| {
+            let pages = PageData::new(&layout.rects, &shown, &transform);
+            thumbnails.update(source, &pages, &vp, render);
+        });
+    }
+
+    /// The thumbnail of the given page, if it has been rendered.
+    pub fn get(&self, page: usize) -> Option<&F::Data> {
+        self.thumbnails.fallback(page)
+    }
+}
@@ -0,0 +1,67 @@
+use std::ops::Range;
+use std::time::Duration;
+
+/// Time spent viewing each page of a document.
+///
+/// The visible pages are sampled at a fixed interval, with the sampled time
+/// being split evenly between all pages visible at that moment. This is
+/// coarse, but cheap enough to run continuously and sufficient for finding
+/// pages that have been looked at for a long time.
+#[derive(Debug, Clone)]
+pub struct ViewingTimeline {
+    seconds: Vec<f64>,
+}
+
+impl ViewingTimeline {
+    /// Interval at which the visible pages should be sampled.
+    pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+    /// Create a new timeline for a document with the given number of pages,
+    /// continuing from previously recorded viewing times, if any.
+    ///
+    /// Recorded times are discarded if the page count does not match, as
+    /// they most likely belong to a different version of the document.
+    pub fn new(page_count: usize, recorded: Option<Vec<f64>>) -> Self {
+        let seconds = recorded
+            .filter(|seconds| seconds.len() == page_count)
+            .unwrap_or_else(|| vec![0.0; page_count]);
+
+        Self { seconds }
+    }
+
+    /// Record that the given pages have been visible for the given duration.
+    pub fn record(&mut self, pages: Range<usize>, duration: Duration) {
+        let pages = pages.start..pages.end.min(self.seconds.len());
+        if pages.is_empty() {
+            return;
+        }
+
+        let share = duration.as_secs_f64() / pages.len() as f64;
+
+        for seconds in &mut self.seconds[pages] {
+            *seconds += share;
+        }
+    }
+
+    /// Viewing time per page, in seconds.
+    pub fn seconds(&self) -> &[f64] {
+        &self.seconds
+    }
+
+    /// Viewing intensity per page, relative to the most viewed page, in the
+    /// range `0.0..=1.0`.
+    ///
+    /// Times are compared on a logarithmic scale, so that pages looked at for
+    /// a short while remain distinguishable next to ones that have been
+    /// studied for hours.
+    pub fn intensities(&self) -> Vec<f64> {
+        let max = self.seconds.iter().copied().fold(0.0, f64::max);
+
+        if max <= 0.0 {
+            return vec![0.0; self.seconds.len()];
+        }
+
+        let max = max.ln_1p();
+        self.seconds.iter().map(|s| s.ln_1p() / max).collect()
+    }
+}
@@ -0,0 +1,190 @@
+//! Detection of the content boxes of pages, i.e. the part of each page that
+//! is not blank, for trimming page margins.
+//!
+//! Content boxes are found on low-resolution renders of the pages rather
+//! than via page objects, as scanned documents usually consist of a single
+//! image covering the full page, white margins included.
+
+use nalgebra::{point, vector, Vector2};
+
+use pdfium::bitmap::Color;
+use pdfium::doc::{Document, PageRotation, RenderFlags};
+
+use crate::core::render::interop::Bitmap;
+use crate::core::render::pdfium::{render_page_rect, RenderOptions};
+use crate::types::Rect;
+
+/// Size of the longer side of pages rendered for detection, in pixels.
+const RENDER_SIZE: f64 = 256.0;
+
+/// Maximum brightness of a pixel to be considered content, from 0 to 255.
+/// Scans are rarely perfectly white, so this leaves some headroom.
+const CONTENT_THRESHOLD: u32 = 200;
+
+/// Fraction of the pixels of a row or column that have to be content for it
+/// to be part of the content box, so that speckles of scanned pages do not
+/// count as content.
+const MIN_CONTENT_FRACTION: f64 = 0.005;
+
+/// Space kept around the content, in PDF points.
+const PADDING: f64 = 8.0;
+
+/// Minimum size of a content box, relative to the page size, so that mostly
+/// blank pages are not blown up when trimmed.
+const MIN_SIZE_FRACTION: f64 = 0.25;
+
+/// Detect the content boxes of all pages, in page coordinates (PDF points).
+/// Pages that are blank or fail to render have no content box.
+///
+/// This is blocking and renders every page, so it should be run on the
+/// executor.
+pub fn detect(doc: &Document) -> Vec<Option<Rect<f64>>> {
+    let count = doc.pages().count();
+
+    (0..count)
+        .map(|index| match content_box(doc, index) {
+            Ok(rect) => rect,
+            Err(err) => {
+                tracing::warn!(page = index, error=%err, "failed to detect page content");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Detect the content box of the given page.
+fn content_box(doc: &Document, index: u32) -> pdfium::Result<Option<Rect<f64>>> {
+    let page = doc.pages().get(index)?;
+
+    let size = vector![page.width() as f64, page.height() as f64];
+    if size.min() <= 0.0 {
+        return Ok(None);
+    }
+
+    let scale = RENDER_SIZE / size.max();
+    let pixels = vector![
+        ((size.x * scale).round() as i64).max(1),
+        ((size.y * scale).round() as i64).max(1)
+    ];
+
+    let opts = RenderOptions {
+        flags: RenderFlags::Annotations,
+        background: Color::WHITE,
+        color_scheme: None,
+        rotation: PageRotation::None,
+    };
+
+    let rect = Rect::new(point![0, 0], pixels);
+    let bmp = render_page_rect(&page, &pixels, &rect, &opts)?;
+
+    let rect = match content_bounds(&bmp) {
+        Some(rect) => rect,
+        None => return Ok(None),
+    };
+
+    // transform to page coordinates and keep some space around the content
+    let rect = Rect::new(
+        rect.offs / scale - vector![PADDING, PADDING],
+        rect.size / scale + vector![PADDING, PADDING] * 2.0,
+    );
+
+    Ok(Some(fit_to_page(&rect, &size)))
+}
+
+/// Bounds of the content of the given rendered page, in pixels.
+fn content_bounds(bmp: &Bitmap) -> Option<Rect<f64>> {
+    let (width, height) = (bmp.size.x as usize, bmp.size.y as usize);
+
+    let mut rows = vec![0; height];
+    let mut cols = vec![0; width];
+
+    for (y, row) in rows.iter_mut().enumerate() {
+        let line = &bmp.buffer[y * bmp.stride as usize..][..width * 3];
+
+        // rendered bitmaps are BGR
+        for (x, px) in line.chunks_exact(3).enumerate() {
+            let brightness = (px[0] as u32 + px[1] as u32 + px[2] as u32) / 3;
+
+            if brightness <= CONTENT_THRESHOLD {
+                *row += 1;
+                cols[x] += 1;
+            }
+        }
+    }
+
+    let (y_min, y_max) = content_range(&rows, width)?;
+    let (x_min, x_max) = content_range(&cols, height)?;
+
+    let offs = point![x_min as f64, y_min as f64];
+    let size = vector![(x_max - x_min + 1) as f64, (y_max - y_min + 1) as f64];
+
+    Some(Rect::new(offs, size))
+}
+
+/// First and last index of lines with content, given the number of content
+/// pixels per line and the length of lines.
+fn content_range(counts: &[usize], len: usize) -> Option<(usize, usize)> {
+    let min = ((len as f64 * MIN_CONTENT_FRACTION).ceil() as usize).max(1);
+
+    let first = counts.iter().position(|&n| n >= min)?;
+    let last = counts.iter().rposition(|&n| n >= min)?;
+
+    Some((first, last))
+}
+
+/// Clip the given content box to the page, enlarging it around its center if
+/// it is too small.
+fn fit_to_page(rect: &Rect<f64>, page: &Vector2<f64>) -> Rect<f64> {
+    let min = page * MIN_SIZE_FRACTION;
+    let size = vector![rect.size.x.max(min.x), rect.size.y.max(min.y)];
+
+    let center = rect.offs + rect.size / 2.0;
+    let offs = center - size / 2.0;
+
+    let rect = Rect::new(offs, size);
+    rect.clip(&Rect::new(point![0.0, 0.0], *page))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bitmap(size: (u32, u32), dark: &[(u32, u32)]) -> Bitmap {
+        let stride = size.0 * 3;
+        let mut buffer = vec![0xff; (stride * size.1) as usize];
+
+        for &(x, y) in dark {
+            let offs = (y * stride + x * 3) as usize;
+            buffer[offs..offs + 3].fill(0);
+        }
+
+        Bitmap {
+            buffer: buffer.into(),
+            size: vector![size.0, size.1],
+            stride,
+        }
+    }
+
+    #[test]
+    fn bounds_of_content() {
+        let bmp = bitmap((10, 20), &[(2, 3), (7, 15), (4, 9)]);
+
+        let rect = content_bounds(&bmp).unwrap();
+        assert_eq!(rect, Rect::new(point![2.0, 3.0], vector![6.0, 13.0]));
+
+        // blank pages have no content
+        let bmp = bitmap((10, 20), &[]);
+        assert_eq!(content_bounds(&bmp), None);
+    }
+
+    #[test]
+    fn small_boxes_are_enlarged() {
+        let page = vector![400.0, 800.0];
+
+        let rect = fit_to_page(&Rect::new(point![10.0, 390.0], vector![20.0, 20.0]), &page);
+        assert_eq!(rect, Rect::new(point![0.0, 300.0], vector![70.0, 200.0]));
+
+        let rect = fit_to_page(&Rect::new(point![50.0, 50.0], vector![300.0, 600.0]), &page);
+        assert_eq!(rect, Rect::new(point![50.0, 50.0], vector![300.0, 600.0]));
+    }
+}
@@ -0,0 +1,33 @@
+//! Actions of documents reaching outside of the viewer, e.g. opening web
+//! links. These are only run once the user has decided to trust the
+//! respective document.
+
+use gtk::glib;
+
+/// An action requested by a document that reaches outside of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExternalAction {
+    /// Open the given URI, e.g. a link to a web page.
+    OpenUri(String),
+}
+
+impl ExternalAction {
+    /// Short description of the action for asking the user, completing
+    /// "This document wants to …", e.g. "open example.com".
+    pub fn description(&self) -> String {
+        match self {
+            ExternalAction::OpenUri(uri) => {
+                // show the host only, full URIs can be long and hard to read
+                let host = glib::Uri::parse(uri, glib::UriFlags::NONE)
+                    .ok()
+                    .and_then(|uri| uri.host())
+                    .filter(|host| !host.is_empty());
+
+                match host {
+                    Some(host) => format!("open {host}"),
+                    None => format!("open {uri}"),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,1601 @@
+//! Toolkit-independent view of a document.
+//!
+//! A [`DocumentView`] bundles everything needed for showing a document: its
+//! page layout, the tile and fallback managers rendering it, overlays drawn on
+//! top of its pages, and hit-testing of viewport positions. Frontends only
+//! provide the viewport and input events and draw the pages of each
+//! [`Frame`], which also allows driving the full pipeline without any UI.
+//!
+//! We use three coordinate systems:
+//!
+//! - Viewport coordinates, in pixels relative to the screen with origin
+//!   (0, 0) as upper left corner of the viewport.
+//!
+//! - Canvas coordinates, in PDF points. The relation between viewport and
+//!   canvas coordinates is defined by the scale and viewport offset.
+//!
+//! - Page coordinates, in PDF points, relative to the page. The origin (0, 0)
+//!   is defined as the upper left corner of the respective page. The relation
+//!   between page coordinates and canvas coordinates is defined by the page
+//!   offset in the canvas.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use executor::exec::{Metrics, Monitor};
+use nalgebra::{point, vector, Point2, Similarity2, Translation2, Vector2};
+
+use pdfium::bitmap::Color;
+use pdfium::doc::{Annotation, AnnotationSubtype, Document, LinkTarget, PageRotation};
+
+use crate::core::caret::CaretMarks;
+use crate::core::citations::{Link, Location};
+use crate::core::fonts::FontReport;
+use crate::core::hints::{HintInput, LinkHints};
+use crate::core::ink::Stroke;
+use crate::core::markup::{self, MarkupKind};
+use crate::core::render::core::{
+    DynamicTilingScheme, FallbackLimits, FallbackManager, FallbackSpec, PageCrop, PageData,
+    TileManager, TileProvider,
+};
+use crate::core::render::disk::DiskCache;
+use crate::core::render::interop::TileFactory;
+use crate::core::render::layout::{page_sizes, Layout, LayoutKind, LayoutProvider, PageSizeIssue};
+use crate::core::render::pdfium::{Damage, Executor, Handle, PdfTileProvider, RenderOptions};
+use crate::core::render::pool::BufferPool;
+use crate::core::render::rotation::{self, rotate_point, rotate_rect, rotate_size, unrotate_rect};
+use crate::core::render::telemetry::{RenderStats, TileSizePolicy};
+use crate::core::search::SearchMatch;
+use crate::core::selection::TextSelection;
+use crate::core::stamps::Stamp;
+use crate::types::{Bounds, Rect, Viewport};
+
+/// Minimum width and height of a selected region, in pixels.
+const MIN_SELECTION_SIZE: f64 = 4.0;
+
+/// Options for rendering the pages of a document.
+#[derive(Debug, Clone)]
+pub struct ViewOptions {
+    /// Spacing between pages, in PDF points.
+    pub page_spacing: f64,
+
+    /// How pages are arranged.
+    pub layout: LayoutKind,
+
+    /// Resolution levels of page fallbacks, from lowest to highest.
+    pub fallback_specs: Vec<FallbackSpec>,
+
+    /// Options for rendering tiles.
+    pub render_main: RenderOptions,
+
+    /// Options for rendering fallbacks.
+    pub render_fallback: RenderOptions,
+
+    /// Number of loaded pages kept in memory.
+    pub page_cache_capacity: usize,
+
+    /// Number of threads rendering tiles and fallbacks, see
+    /// [`render_threads()`](crate::core::render::pdfium::render_threads).
+    pub render_threads: u32,
+
+    /// Maximum number of render threads used at a time, e.g. to save power.
+    /// The remaining threads are kept idle.
+    pub render_thread_limit: Option<u32>,
+
+    /// Number of tiles to render around the visible ones.
+    pub tile_halo: Vector2<i64>,
+
+    /// Maximum number of pages around the visible ones to render fallbacks
+    /// for, regardless of the fallback specs.
+    pub fallback_halo_limit: Option<usize>,
+
+    /// Minimum number of pages around the visible ones to render fallbacks
+    /// for on all levels, taking precedence over the limit.
+    pub fallback_halo_minimum: usize,
+
+    /// Limits for requesting fallbacks, e.g. to avoid bursts of renders when
+    /// many pages become visible at once.
+    pub fallback_limits: Option<FallbackLimits>,
+
+    /// Maximum number of new tiles shown per frame.
+    pub upload_limit: Option<usize>,
+
+    /// Zoom factors to pre-render tiles for while idle.
+    pub warm_factors: Vec<f64>,
+
+    /// Maximum number of pixels to pre-render per zoom factor.
+    pub warm_budget: i64,
+}
+
+/// State of the frontend, determining how much work is done for a frame.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameHints {
+    /// The viewport has not changed for a while.
+    pub idle: bool,
+
+    /// The viewport has stopped moving as of this frame.
+    pub stopped: bool,
+
+    /// Frames take too long, postpone work not needed for this frame.
+    pub shedding: bool,
+
+    /// Save power by not rendering anything speculatively.
+    pub low_power: bool,
+
+    /// Offset the viewport is predicted to come to rest at, e.g. after a
+    /// fling, in viewport coordinates.
+    pub rest: Option<Point2<f64>>,
+}
+
+/// Everything to draw for a frame.
+pub struct Frame<'a, T> {
+    /// Visible pages, in order.
+    pub pages: Vec<PageFrame<'a, T>>,
+
+    /// Z-level of the first visible page, in device pixels.
+    pub z: Option<i64>,
+
+    /// Some tiles have been held back due to the upload limit, i.e. another
+    /// frame is needed to show them.
+    pub deferred: bool,
+
+    /// All tiles in view have been rendered.
+    pub complete: bool,
+
+    /// Overlays to draw on top of the pages.
+    pub overlays: &'a Overlays,
+
+    /// Region currently being selected, in viewport coordinates.
+    pub selection: Option<Bounds<f64>>,
+}
+
+/// A visible page and its rendered contents.
+pub struct PageFrame<'a, T> {
+    pub index: usize,
+
+    /// Page bounds, in viewport coordinates.
+    pub rect: Rect<f64>,
+
+    /// Page bounds clipped to the viewport.
+    pub clip: Rect<f64>,
+
+    /// Upper left corner of the page before cropping it, in viewport
+    /// coordinates. Equal to that of `rect` for pages without crop.
+    pub origin: Point2<f64>,
+
+    /// Scale from page to viewport coordinates.
+    pub scale: f64,
+
+    /// Size of the full page in page coordinates, i.e. before rotating or
+    /// cropping it.
+    pub size: Vector2<f64>,
+
+    /// Rotation of the page.
+    pub rotation: PageRotation,
+
+    /// Nothing has been rendered for the page yet.
+    pub pending: bool,
+
+    /// Fallback covering the full page, if any.
+    pub fallback: Option<&'a T>,
+
+    /// Tiles and their bounds in viewport coordinates, to be drawn in order.
+    pub tiles: Vec<(Rect<f64>, &'a T)>,
+}
+
+/// Overlays drawn on top of pages.
+#[derive(Debug, Default)]
+pub struct Overlays {
+    /// Synthesized links, underlined and followed on click.
+    pub links: Vec<Link>,
+
+    /// Substituted fonts of a page, shown for diagnostics.
+    pub fonts: Option<(usize, FontReport)>,
+
+    /// Caret and selected text in caret navigation mode.
+    pub caret: Option<CaretMarks>,
+
+    /// Text selected with the pointer.
+    pub text: Option<TextSelection>,
+
+    /// Results of the current search, in document order.
+    pub search: Vec<SearchMatch>,
+
+    /// Index of the search result currently being shown.
+    pub search_current: Option<usize>,
+
+    /// Labels of visible links while following links via keyboard.
+    pub hints: Option<LinkHints<VisibleLink>>,
+}
+
+/// A link visible in the viewport.
+#[derive(Debug, Clone)]
+pub struct VisibleLink {
+    pub page: usize,
+
+    /// Bounds of the link, in page coordinates.
+    pub rect: Rect<f64>,
+
+    pub action: LinkAction,
+}
+
+/// What following a link does.
+#[derive(Debug, Clone)]
+pub enum LinkAction {
+    /// Go to a location in the document.
+    GoTo(Location),
+
+    /// Open an external resource.
+    OpenUri(String),
+}
+
+/// Input events, in viewport coordinates.
+#[derive(Debug, Clone, Copy)]
+pub enum Input {
+    Click(Point2<f64>),
+
+    /// Start selecting a region at the given point.
+    SelectBegin(Point2<f64>),
+
+    /// Update the region being selected, given the offset from its start.
+    SelectUpdate(Vector2<f64>),
+
+    /// Finish the region being selected, given the offset from its start.
+    SelectEnd(Vector2<f64>),
+
+    /// Start selecting text at the given point.
+    SelectTextBegin(Point2<f64>),
+
+    /// Extend the text being selected, given the offset from its start.
+    SelectTextUpdate(Vector2<f64>),
+
+    /// Finish the text being selected, given the offset from its start.
+    SelectTextEnd(Vector2<f64>),
+
+    /// Label all visible links for following them via keyboard.
+    ShowLinkHints,
+
+    /// Type a character of the label of the link to follow.
+    LinkHintKey(char),
+
+    /// Stop following links via keyboard.
+    HideLinkHints,
+}
+
+/// Events resulting from input.
+pub enum Event {
+    /// A link to a location in the document has been followed.
+    LinkActivated(Location),
+
+    /// A link to an external resource has been followed.
+    UriActivated(String),
+
+    /// A text annotation has been clicked, given with its bounds in page
+    /// coordinates.
+    NoteActivated {
+        page: usize,
+        annot: Annotation,
+        rect: Rect<f64>,
+    },
+
+    /// A region of a page has been selected, in page coordinates.
+    RegionSelected { page: usize, rect: Rect<f64> },
+}
+
+pub struct DocumentView<M, F>
+where
+    F: TileFactory,
+    F::Data: Send,
+{
+    opts: ViewOptions,
+    layout: Layout,
+    provider: PdfTileProvider<M, F>,
+    tile_policy: TileSizePolicy,
+    tiles: TileManager<DynamicTilingScheme, Handle<F::Data>>,
+    fallbacks: FallbackManager<Handle<F::Data>>,
+    overlays: Overlays,
+
+    viewport: Viewport,
+    scale_factor: f64,
+    single_page: Option<usize>,
+    selection: Option<(Point2<f64>, Point2<f64>)>,
+    text_drag: Option<Point2<f64>>,
+}
+
+impl<M, F> DocumentView<M, F>
+where
+    M: Monitor<Damage> + Send + Clone + 'static,
+    F: TileFactory + Send + Clone + 'static,
+    F::Data: Send,
+{
+    /// Open the given document, laying out its pages with degenerate page
+    /// sizes corrected, see [`issues()`](Self::issues).
+    ///
+    /// The monitor is notified whenever a render task completes, and the
+    /// factory creates the data drawn by the frontend from rendered bitmaps.
+    pub fn open(
+        doc: Document,
+        monitor: M,
+        factory: F,
+        tile_policy: TileSizePolicy,
+        opts: ViewOptions,
+    ) -> Self {
+        let rotation = opts.render_main.rotation;
+        let sizes = page_sizes(&doc).into_iter().map(|(w, h)| {
+            let size = rotate_size(&vector![w, h], rotation);
+            (size.x, size.y)
+        });
+
+        let layout = opts.layout.compute(sizes, opts.page_spacing);
+        let tiles = tile_manager(&tile_policy, &opts);
+
+        let mut fallbacks = FallbackManager::new(&opts.fallback_specs);
+        fallbacks.set_halo_limit(opts.fallback_halo_limit);
+        fallbacks.set_halo_minimum(opts.fallback_halo_minimum);
+        fallbacks.set_limits(opts.fallback_limits);
+
+        let executor = Executor::new(opts.render_threads.max(1));
+        if let Some(limit) = opts.render_thread_limit {
+            executor.set_active_threads(limit);
+        }
+        let provider =
+            PdfTileProvider::new(executor, monitor, factory, doc, opts.page_cache_capacity);
+
+        Self {
+            opts,
+            layout,
+            provider,
+            tile_policy,
+            tiles,
+            fallbacks,
+            overlays: Overlays::default(),
+
+            viewport: Viewport {
+                r: Rect::new(point![0.0, 0.0], vector![0.0, 0.0]),
+                scale: 1.0,
+            },
+            scale_factor: 1.0,
+            single_page: None,
+            selection: None,
+            text_drag: None,
+        }
+    }
+
+    pub fn document(&self) -> &Document {
+        self.provider.document()
+    }
+
+    /// The executor rendering the pages of this view.
+    pub fn executor(&self) -> &Arc<Executor> {
+        self.provider.executor()
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Pages shown at a corrected size, as their size is invalid or out of
+    /// range.
+    pub fn issues(&self) -> &[(usize, PageSizeIssue)] {
+        &self.layout.issues
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.layout.rects.len()
+    }
+
+    /// Bounds of the given page, in canvas coordinates.
+    pub fn page_rect(&self, page: usize) -> Option<Rect<f64>> {
+        self.layout.rects.get(page).copied()
+    }
+
+    /// Size of the given page in page coordinates, i.e. of the unrotated
+    /// and uncropped page, in PDF points.
+    pub fn page_size(&self, page: usize) -> Option<Vector2<f64>> {
+        let rect = self.layout.rects.get(page)?;
+
+        let size = match self.page_crop(page) {
+            Some(crop) => crop.page_size,
+            None => rect.size,
+        };
+
+        Some(rotate_size(&size, self.rotation()))
+    }
+
+    /// Crop of the given page, in coordinates of the rotated page.
+    fn page_crop(&self, page: usize) -> Option<&PageCrop> {
+        self.layout.crops.get(page)?.as_ref()
+    }
+
+    /// Upper left corner of the given page before cropping it, in canvas
+    /// coordinates. This is the origin of page coordinates on the canvas.
+    fn page_origin(&self, page: usize) -> Option<Point2<f64>> {
+        let rect = self.layout.rects.get(page)?;
+
+        match self.page_crop(page) {
+            Some(crop) => Some(rect.offs - crop.rect.offs.coords),
+            None => Some(rect.offs),
+        }
+    }
+
+    /// Rotation of all pages.
+    pub fn rotation(&self) -> PageRotation {
+        self.opts.render_main.rotation
+    }
+
+    /// Render durations of recently completed tiles.
+    pub fn stats(&self) -> &RenderStats {
+        self.provider.stats()
+    }
+
+    /// Pool of the buffers tiles are rendered into.
+    pub fn buffers(&self) -> &BufferPool {
+        self.provider.buffers()
+    }
+
+    /// Queue depths and latencies of the render tasks.
+    pub fn render_metrics(&self) -> Metrics {
+        self.provider.metrics()
+    }
+
+    pub fn tile_policy(&self) -> &TileSizePolicy {
+        &self.tile_policy
+    }
+
+    pub fn overlays(&self) -> &Overlays {
+        &self.overlays
+    }
+
+    /// Set the viewport, in viewport coordinates, and the scale factor of
+    /// the monitor it is shown on. Tiles and fallbacks are rendered in device
+    /// pixels, i.e. scaled by that factor.
+    pub fn set_viewport(&mut self, vp: &Viewport, scale_factor: f64) {
+        self.viewport = Viewport {
+            r: vp.r,
+            scale: vp.scale,
+        };
+        self.scale_factor = scale_factor;
+    }
+
+    /// Show only the given page, or all pages if `None`.
+    pub fn set_single_page(&mut self, page: Option<usize>) {
+        self.single_page = page;
+    }
+
+    /// Check if the given page is shown, i.e. not hidden by the single-page
+    /// mode.
+    pub fn is_shown(&self, page: usize) -> bool {
+        is_shown(self.single_page, page)
+    }
+
+    /// Bounds of the scrollable area, in canvas coordinates.
+    pub fn bounds(&self) -> Bounds<f64> {
+        // only the shown page can be scrolled to in single-page mode
+        match self.single_page.and_then(|i| self.layout.rects.get(i)) {
+            Some(rect) => rect.bounds(),
+            None => self.layout.bounds,
+        }
+    }
+
+    /// Recompute the page layout with the given spacing. Returns the shift
+    /// of the first visible page, in canvas coordinates, by which the
+    /// viewport has to be moved to keep that page in place.
+    pub fn set_page_spacing(&mut self, spacing: f64) -> Vector2<f64> {
+        let anchor = self.visible_pages().start;
+
+        self.opts.page_spacing = spacing;
+        self.relayout(anchor, PageRotation::None)
+    }
+
+    /// Arrange pages according to the given layout. Returns the shift of the
+    /// page closest to the center of the viewport, in canvas coordinates, by
+    /// which the viewport has to be moved to keep that page in place.
+    pub fn set_layout(&mut self, kind: LayoutKind) -> Vector2<f64> {
+        let anchor = match self.viewport_page_rect() {
+            Some((page, _)) => page,
+            None => self.visible_pages().start,
+        };
+
+        self.opts.layout = kind;
+        self.relayout(anchor, PageRotation::None)
+    }
+
+    /// Rotate all pages, dropping all rendered tiles and fallbacks. Returns
+    /// the shift of the page closest to the center of the viewport, in
+    /// canvas coordinates, by which the viewport has to be moved to keep that
+    /// page in place.
+    pub fn set_rotation(&mut self, rotation: PageRotation) -> Vector2<f64> {
+        let turn = rotation::difference(self.rotation(), rotation);
+        if turn == PageRotation::None {
+            return vector![0.0, 0.0];
+        }
+
+        let anchor = match self.viewport_page_rect() {
+            Some((page, _)) => page,
+            None => self.visible_pages().start,
+        };
+
+        self.opts.render_main.rotation = rotation;
+        self.opts.render_fallback.rotation = rotation;
+
+        // renders of the previous rotation have a different aspect ratio, so
+        // they cannot be shown in the meantime
+        self.tiles.clear();
+        self.fallbacks.clear();
+
+        self.relayout(anchor, turn)
+    }
+
+    /// Show only the given part of each page, given in page coordinates and
+    /// indexed by page, or the full page if `None`. Pages are laid out with
+    /// the size of their crop. Returns the shift of the page closest to the
+    /// center of the viewport, in canvas coordinates, by which the viewport
+    /// has to be moved to keep that page in place.
+    pub fn set_crops(&mut self, crops: &[Option<Rect<f64>>]) -> Vector2<f64> {
+        let anchor = match self.viewport_page_rect() {
+            Some((page, _)) => page,
+            None => self.visible_pages().start,
+        };
+
+        let rotation = self.rotation();
+        let pages: Vec<_> = (0..self.page_count())
+            .map(|i| {
+                let size = self.page_size(i).unwrap_or_default();
+                let crop = crops.get(i).copied().flatten();
+                let crop = crop.map(|rect| rotate_rect(&rect, &size, rotation));
+
+                let size = rotate_size(&size, rotation);
+                ((size.x, size.y), crop)
+            })
+            .collect();
+
+        // tiles are laid out relative to the (cropped) page bounds, so they
+        // cannot be reused for different crops
+        self.tiles.clear();
+        self.fallbacks.clear();
+
+        let spacing = self.opts.page_spacing;
+        let layout = self.opts.layout.compute_cropped(pages, spacing);
+
+        self.replace_layout(anchor, layout)
+    }
+
+    /// Check if any page is cropped.
+    pub fn is_cropped(&self) -> bool {
+        self.layout.crops.iter().any(Option::is_some)
+    }
+
+    /// Recompute the page layout with the current options and pages turned
+    /// by the given rotation, returning the shift of the given page.
+    fn relayout(&mut self, anchor: usize, turn: PageRotation) -> Vector2<f64> {
+        // without turning pages, page sizes and crops do not change, so
+        // cached tiles and fallbacks stay valid
+        let pages = self.layout.rects.iter().zip(&self.layout.crops);
+        let pages = pages.map(|(r, crop)| match crop {
+            Some(crop) => {
+                let size = rotate_size(&crop.page_size, turn);
+                let rect = rotate_rect(&crop.rect, &crop.page_size, turn);
+                ((size.x, size.y), Some(rect))
+            }
+            None => {
+                let size = rotate_size(&r.size, turn);
+                ((size.x, size.y), None)
+            }
+        });
+        let spacing = self.opts.page_spacing;
+        let layout = self.opts.layout.compute_cropped(pages, spacing);
+
+        self.replace_layout(anchor, layout)
+    }
+
+    /// Replace the page layout, returning the shift of the given page.
+    fn replace_layout(&mut self, anchor: usize, layout: Layout) -> Vector2<f64> {
+        let shift = match (self.layout.rects.get(anchor), layout.rects.get(anchor)) {
+            (Some(old), Some(new)) => new.offs - old.offs,
+            _ => vector![0.0, 0.0],
+        };
+
+        self.layout = layout;
+        shift
+    }
+
+    /// Replace the tiling policy, rebuilding the tile manager. This drops
+    /// all cached and pending tiles, as they are specific to the previous
+    /// tiling scheme.
+    pub fn set_tile_policy(&mut self, policy: TileSizePolicy) {
+        self.tiles = tile_manager(&policy, &self.opts);
+        self.tile_policy = policy;
+
+        // statistics and pooled buffers refer to the previous tile size
+        self.provider.stats().clear();
+        self.provider.buffers().clear();
+    }
+
+    /// Replace the options for rendering tiles and fallbacks, rendering all
+    /// pages again. Fallbacks stay shown until they have been replaced.
+    pub fn set_render_options(&mut self, main: RenderOptions, fallback: RenderOptions) {
+        self.opts.render_main = main;
+        self.opts.render_fallback = fallback;
+
+        for page in 0..self.page_count() {
+            self.tiles.invalidate_page(page);
+            self.fallbacks.invalidate_page(page);
+        }
+    }
+
+    pub fn set_tile_halo(&mut self, halo: Vector2<i64>) {
+        self.opts.tile_halo = halo;
+        self.tiles.set_halo(halo);
+    }
+
+    pub fn set_fallback_halo_limit(&mut self, limit: Option<usize>) {
+        self.opts.fallback_halo_limit = limit;
+        self.fallbacks.set_halo_limit(limit);
+    }
+
+    pub fn set_fallback_halo_minimum(&mut self, minimum: usize) {
+        self.opts.fallback_halo_minimum = minimum;
+        self.fallbacks.set_halo_minimum(minimum);
+    }
+
+    pub fn set_upload_limit(&mut self, limit: Option<usize>) {
+        self.opts.upload_limit = limit;
+        self.tiles.set_upload_limit(limit);
+    }
+
+    pub fn set_render_thread_limit(&mut self, limit: Option<u32>) {
+        self.opts.render_thread_limit = limit;
+
+        let threads = limit.unwrap_or(self.opts.render_threads);
+        self.provider.executor().set_active_threads(threads);
+    }
+
+    /// Release cached tiles and pages, keeping only the lowest-resolution
+    /// fallbacks, and stop rendering until resumed.
+    pub fn suspend(&mut self) {
+        self.provider.park();
+        self.provider.page_cache().clear();
+        self.tiles.clear();
+        self.fallbacks.shrink();
+    }
+
+    /// Resume rendering after [`suspend()`](Self::suspend).
+    pub fn resume(&mut self) {
+        self.provider.unpark();
+    }
+
+    /// Drop all rendered tiles, fallbacks, and loaded pages, so that pages
+    /// are rendered again from scratch.
+    pub fn reload(&mut self) {
+        // tiles cached on disk show the previous content
+        if let Some(disk) = self.provider.disk_cache() {
+            (0..self.page_count()).for_each(|page| disk.invalidate_page(page));
+        }
+
+        self.provider.page_cache().clear();
+        self.tiles.clear();
+        self.fallbacks.clear();
+    }
+
+    /// Load low-resolution tiles from and store them in the given disk
+    /// cache, e.g. to show fallbacks right away when reopening the document.
+    pub fn set_disk_cache(&mut self, cache: Option<DiskCache>) {
+        self.provider.set_disk_cache(cache);
+    }
+
+    /// Drop all rendered tiles and fallbacks of the given page after it has
+    /// been changed, keeping fallbacks shown until they have been rendered
+    /// again.
+    fn invalidate_page(&mut self, page: usize) {
+        if let Some(disk) = self.provider.disk_cache() {
+            disk.invalidate_page(page);
+        }
+
+        self.tiles.invalidate_page(page);
+        self.fallbacks.invalidate_page(page);
+    }
+
+    /// Add the given stroke to its page as ink annotation and render the page
+    /// again.
+    pub fn add_ink_stroke(&mut self, stroke: &Stroke) -> pdfium::Result<()> {
+        let height = match self.page_size(stroke.page) {
+            Some(size) => size.y,
+            None => return Err(pdfium::Error::InvalidArgument),
+        };
+
+        let page = self.provider.page_cache().get(stroke.page)?;
+        stroke.write(&page, height)?;
+
+        self.invalidate_page(stroke.page);
+        Ok(())
+    }
+
+    /// Add the given stamp to its page as stamp annotation and render the page
+    /// again.
+    pub fn add_stamp(&mut self, stamp: &Stamp) -> pdfium::Result<()> {
+        let height = match self.page_size(stamp.page) {
+            Some(size) => size.y,
+            None => return Err(pdfium::Error::InvalidArgument),
+        };
+
+        let page = self.provider.page_cache().get(stamp.page)?;
+        stamp.write(&page, height)?;
+
+        self.invalidate_page(stamp.page);
+        Ok(())
+    }
+
+    /// Mark the text selected with the pointer with annotations of the given
+    /// kind and render the affected pages again. Returns `false` if no text is
+    /// selected.
+    pub fn add_markup(&mut self, kind: MarkupKind, color: Color) -> pdfium::Result<bool> {
+        let selection = match self.overlays.text.as_ref() {
+            Some(selection) if !selection.is_empty() => selection,
+            _ => return Ok(false),
+        };
+
+        let mut changed = Vec::new();
+
+        let result: pdfium::Result<()> = selection
+            .pages()
+            .filter(|(_, lines)| !lines.is_empty())
+            .try_for_each(|(index, lines)| {
+                let height = match self.page_size(index) {
+                    Some(size) => size.y,
+                    None => return Ok(()),
+                };
+
+                let page = self.provider.page_cache().get(index)?;
+                markup::write(&page, height, kind, color, lines)?;
+
+                changed.push(index);
+                Ok(())
+            });
+
+        // pages marked up before any error have been changed nevertheless
+        for index in changed {
+            self.invalidate_page(index);
+        }
+
+        result?;
+
+        // the selection is now shown by the annotations
+        self.overlays.text = None;
+        Ok(true)
+    }
+
+    /// Render the fallbacks of the given page with high priority.
+    pub fn prefetch_page(&mut self, page: usize) {
+        let vp = device_viewport(&self.viewport, self.scale_factor);
+        let transform = page_transform(&vp);
+        let visible = page..page + 1;
+
+        let layout = &self.layout;
+        let fallbacks = &mut self.fallbacks;
+        let opts = &self.opts;
+
+        self.provider.request(&visible, |source| {
+            let pages =
+                PageData::new(&layout.rects, &visible, &transform).with_crops(&layout.crops);
+
+            fallbacks.prefetch(source, &pages, page, &opts.render_fallback);
+        });
+    }
+
+    pub fn set_links(&mut self, links: Vec<Link>) {
+        self.overlays.links = links;
+    }
+
+    pub fn set_font_report(&mut self, report: Option<(usize, FontReport)>) {
+        self.overlays.fonts = report;
+    }
+
+    pub fn set_caret(&mut self, marks: Option<CaretMarks>) {
+        self.overlays.caret = marks;
+    }
+
+    pub fn set_search_results(&mut self, matches: Vec<SearchMatch>, current: Option<usize>) {
+        self.overlays.search = matches;
+        self.overlays.search_current = current;
+    }
+
+    /// Update tile and fallback caches for the current viewport and collect
+    /// everything to draw.
+    pub fn render(&mut self, hints: &FrameHints) -> Frame<'_, F::Data> {
+        let vp = &self.viewport;
+
+        // transformation: page (bounds) from canvas to viewport
+        let transform = page_transform(vp);
+
+        // Tiles and fallbacks are rendered in device pixels, i.e. at the scale
+        // factor of the monitor the viewport is on, and scaled back down to
+        // viewport coordinates when drawing them.
+        let factor = self.scale_factor;
+        let vp_dev = device_viewport(vp, factor);
+        let transform_dev = page_transform(&vp_dev);
+
+        // origin-aligned viewport
+        let screen_rect = Rect::new(point![0.0, 0.0], vp.r.size);
+
+        let rects = &self.layout.rects;
+        let visible = visible_pages_in(rects, self.single_page, &transform, vp);
+
+        // z-level of the first visible page
+        let z = rects.get(visible.clone()).and_then(|rects| {
+            let page_rect = transform_dev(rects.first()?);
+            Some(page_rect.size.x.max(page_rect.size.y) as i64)
+        });
+
+        // reduce the tile size if tiles take too long to render, e.g. for
+        // pages with very complex vector graphics
+        if let Some(z) = z {
+            if self.tile_policy.adapt(self.provider.stats(), z) {
+                tracing::info!(
+                    tile_size = self.tile_policy.tile_size(),
+                    min_tile_z = self.tile_policy.min_tile_z(),
+                    "reducing tile size for complex document"
+                );
+
+                self.tiles.set_scheme(self.tile_policy.scheme());
+            }
+        }
+
+        // update fallback- and tile-caches
+        let layout = &self.layout;
+        let tiles = &mut self.tiles;
+        let fallbacks = &mut self.fallbacks;
+        let opts = &self.opts;
+        let single_page = self.single_page;
+
+        self.provider.request(&visible, |source| {
+            let pages =
+                PageData::new(&layout.rects, &visible, &transform_dev).with_crops(&layout.crops);
+
+            // postpone fallback updates until the viewport stops moving
+            if !hints.shedding || hints.idle {
+                fallbacks.update(source, &pages, &vp_dev, &opts.render_fallback);
+            }
+
+            tiles.update(source, &pages, &vp_dev, &opts.render_main);
+
+            // pre-render the region the viewport is predicted to stop at, so
+            // that it is sharp once the fling settles
+            if let Some(offs) = hints.rest {
+                let vp_rest = Viewport {
+                    r: Rect::new(offs, vp.r.size),
+                    scale: vp.scale,
+                };
+
+                let visible_rest = visible_pages_in(
+                    &layout.rects,
+                    single_page,
+                    &page_transform(&vp_rest),
+                    &vp_rest,
+                );
+
+                let vp_rest = device_viewport(&vp_rest, factor);
+                let transform_rest = page_transform(&vp_rest);
+                let pages_rest = PageData::new(&layout.rects, &visible_rest, &transform_rest)
+                    .with_crops(&layout.crops);
+
+                tiles.prefetch(source, &pages_rest, &vp_rest, &opts.render_main);
+            } else if hints.stopped {
+                tiles.clear_prefetch();
+            }
+
+            // warm tiles for the next zoom steps once everything in view is
+            // done and the user has stopped moving the viewport
+            if hints.idle && !hints.low_power && tiles.is_complete() {
+                tiles.warm(
+                    source,
+                    &pages,
+                    &vp_dev,
+                    &opts.warm_factors,
+                    opts.warm_budget,
+                    &opts.render_main,
+                );
+            }
+        });
+
+        // collect what to draw for each page
+        let rotation = self.rotation();
+        let pages = visible
+            .clone()
+            .zip(&self.layout.rects[visible])
+            .map(|(i, page_rect_pt)| {
+                // transform page bounds to viewport
+                let rect = transform(page_rect_pt);
+
+                // page bounds and viewport in device pixels, for tiles
+                let page_rect_dev = transform_dev(page_rect_pt);
+                let vp_adj = Viewport {
+                    r: vp_dev.r,
+                    scale: page_rect_dev.size.x / page_rect_pt.size.x,
+                };
+
+                let tiles = self.tiles.tiles(&vp_adj, i, &page_rect_dev);
+                let tiles = tiles
+                    .into_iter()
+                    .map(|(r, data)| (r.scale(1.0 / factor), data))
+                    .collect();
+
+                // recompute scale for rounded page
+                let scale = rect.size.x / page_rect_pt.size.x;
+
+                let (origin, size) = match self.page_crop(i) {
+                    Some(crop) => (rect.offs - crop.rect.offs.coords * scale, crop.page_size),
+                    None => (rect.offs, page_rect_pt.size),
+                };
+
+                PageFrame {
+                    index: i,
+                    rect,
+                    clip: rect.clip(&screen_rect),
+                    origin,
+                    scale,
+                    size: rotate_size(&size, rotation),
+                    rotation,
+
+                    pending: self.fallbacks.is_pending(i),
+                    fallback: self.fallbacks.fallback(i),
+                    tiles,
+                }
+            })
+            .collect();
+
+        Frame {
+            pages,
+            z,
+            deferred: self.tiles.has_deferred(),
+            complete: self.tiles.is_complete(),
+            overlays: &self.overlays,
+            selection: self.selection(),
+        }
+    }
+
+    /// Handle the given input, returning the resulting event, if any.
+    pub fn handle_input(&mut self, input: Input) -> Option<Event> {
+        match input {
+            Input::Click(pos) => {
+                self.overlays.text = None;
+                self.overlays.hints = None;
+
+                // follow links, show contents of text annotations otherwise
+                match self.link_at(pos) {
+                    Some(LinkAction::GoTo(target)) => Some(Event::LinkActivated(target)),
+                    Some(LinkAction::OpenUri(uri)) => Some(Event::UriActivated(uri)),
+                    None => {
+                        let (page, annot, rect) = self.text_annotation_at(pos)?;
+                        Some(Event::NoteActivated { page, annot, rect })
+                    }
+                }
+            }
+            Input::SelectBegin(pos) => {
+                self.selection = Some((pos, pos));
+                None
+            }
+            Input::SelectUpdate(offset) => {
+                if let Some((start, _)) = self.selection {
+                    self.selection = Some((start, start + offset));
+                }
+                None
+            }
+            Input::SelectEnd(offset) => {
+                let (start, _) = self.selection.take()?;
+
+                // ignore accidental clicks
+                if offset.x.abs() < MIN_SELECTION_SIZE || offset.y.abs() < MIN_SELECTION_SIZE {
+                    return None;
+                }
+
+                let (page, rect) = self.page_region(start, start + offset)?;
+                Some(Event::RegionSelected { page, rect })
+            }
+            Input::SelectTextBegin(pos) => {
+                let (page, point) = self.page_at(pos)?;
+
+                self.text_drag = Some(pos);
+                self.overlays.text = TextSelection::begin(self.provider.document(), page, point);
+                None
+            }
+            Input::SelectTextUpdate(offset) => {
+                self.update_text_selection(offset);
+                None
+            }
+            Input::SelectTextEnd(offset) => {
+                self.update_text_selection(offset);
+                self.text_drag = None;
+                None
+            }
+            Input::ShowLinkHints => {
+                let hints = LinkHints::new(self.visible_links());
+
+                self.overlays.hints = (!hints.is_empty()).then_some(hints);
+                None
+            }
+            Input::LinkHintKey(c) => {
+                let input = self.overlays.hints.as_mut()?.input(c);
+
+                let link = match input {
+                    HintInput::Pending => return None,
+                    HintInput::Matched(link) => link,
+                    HintInput::Mismatch => {
+                        self.overlays.hints = None;
+                        return None;
+                    }
+                };
+
+                self.overlays.hints = None;
+
+                match link.action {
+                    LinkAction::GoTo(target) => Some(Event::LinkActivated(target)),
+                    LinkAction::OpenUri(uri) => Some(Event::UriActivated(uri)),
+                }
+            }
+            Input::HideLinkHints => {
+                self.overlays.hints = None;
+                None
+            }
+        }
+    }
+
+    /// Whether visible links are labeled for following them via keyboard.
+    pub fn is_showing_link_hints(&self) -> bool {
+        self.overlays.hints.is_some()
+    }
+
+    /// Extend the text being selected to the given offset from its start.
+    /// Positions between pages leave the selection unchanged.
+    fn update_text_selection(&mut self, offset: Vector2<f64>) {
+        let target = self
+            .text_drag
+            .and_then(|start| self.page_at(start + offset));
+
+        if let (Some((page, point)), Some(selection)) = (target, self.overlays.text.as_mut()) {
+            selection.update(self.provider.document(), page, point);
+        }
+    }
+
+    /// The text selected with the pointer, if any.
+    pub fn selected_text(&self) -> Option<String> {
+        let selection = self.overlays.text.as_ref()?;
+        (!selection.is_empty()).then(|| selection.text())
+    }
+
+    /// Region currently being selected, in viewport coordinates.
+    pub fn selection(&self) -> Option<Bounds<f64>> {
+        let (a, b) = self.selection?;
+
+        Some(Bounds {
+            x_min: a.x.min(b.x),
+            y_min: a.y.min(b.y),
+            x_max: a.x.max(b.x),
+            y_max: a.y.max(b.y),
+        })
+    }
+
+    /// Range of pages visible in the viewport.
+    pub fn visible_pages(&self) -> Range<usize> {
+        let vp_rect = self.canvas_viewport();
+
+        let mut visible = self
+            .layout
+            .rects
+            .iter()
+            .enumerate()
+            .filter(|(i, rect)| self.is_shown(*i) && rect.intersects(&vp_rect))
+            .map(|(i, _)| i);
+
+        match visible.next() {
+            Some(first) => first..visible.next_back().unwrap_or(first) + 1,
+            None => 0..0,
+        }
+    }
+
+    /// The page closest to the center of the viewport and its visible part,
+    /// in page coordinates. The full shown part of the page is returned if it
+    /// is not visible.
+    pub fn viewport_page_rect(&self) -> Option<(usize, Rect<f64>)> {
+        let vp_rect = self.canvas_viewport();
+        let center = vp_rect.offs + vp_rect.size / 2.0;
+
+        // find the page closest to the center of the viewport
+        let (index, page_rect) = self
+            .layout
+            .rects
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.is_shown(*i))
+            .min_by(|(_, a), (_, b)| {
+                distance_to_rect(a, &center).total_cmp(&distance_to_rect(b, &center))
+            })?;
+
+        // visible part of the page, in page coordinates
+        let origin = self.page_origin(index)?;
+        let visible = vp_rect.clip(page_rect);
+        let visible = if visible.size.x > 0.0 && visible.size.y > 0.0 {
+            visible.translate(&-origin.coords)
+        } else {
+            page_rect.translate(&-origin.coords)
+        };
+
+        let size = self.page_size(index)?;
+
+        Some((index, unrotate_rect(&visible, &size, self.rotation())))
+    }
+
+    /// The viewport in coordinates of the given page.
+    pub fn viewport_rect(&self, page: usize) -> Option<Rect<f64>> {
+        let origin = self.page_origin(page)?;
+        let size = self.page_size(page)?;
+        let rect = self.canvas_viewport().translate(&-origin.coords);
+
+        Some(unrotate_rect(&rect, &size, self.rotation()))
+    }
+
+    /// Transform a rectangle in coordinates of the given page to canvas
+    /// coordinates.
+    pub fn page_to_canvas(&self, page: usize, rect: &Rect<f64>) -> Option<Rect<f64>> {
+        let origin = self.page_origin(page)?;
+        let size = self.page_size(page)?;
+        let rect = rotate_rect(rect, &size, self.rotation());
+
+        Some(rect.translate(&origin.coords))
+    }
+
+    /// Transform a rectangle in coordinates of the given page to viewport
+    /// coordinates.
+    pub fn page_to_viewport(&self, page: usize, rect: &Rect<f64>) -> Option<Rect<f64>> {
+        let vp = &self.viewport;
+
+        let rect = self
+            .page_to_canvas(page, rect)?
+            .scale(vp.scale)
+            .translate(&-vp.r.offs.coords);
+
+        Some(rect)
+    }
+
+    /// Transform a point in viewport coordinates to coordinates of the given
+    /// page, regardless of whether the point lies on that page.
+    pub fn viewport_to_page(&self, page: usize, pos: Point2<f64>) -> Option<Point2<f64>> {
+        let origin = self.page_origin(page)?;
+        let vp = &self.viewport;
+
+        let point = (vp.r.offs + pos.coords) / vp.scale - origin.coords;
+        Some(self.unrotate_point(page, &point))
+    }
+
+    /// Transform a point on the rotated page to page coordinates.
+    fn unrotate_point(&self, page: usize, point: &Point2<f64>) -> Point2<f64> {
+        match self.page_size(page) {
+            Some(size) => rotation::unrotate_point(point, &size, self.rotation()),
+            None => *point,
+        }
+    }
+
+    /// Transform a point in viewport coordinates to the index of the page it
+    /// is on and the respective page coordinates.
+    pub fn page_at(&self, pos: Point2<f64>) -> Option<(usize, Point2<f64>)> {
+        // transform point to canvas coordinates
+        let vp = &self.viewport;
+        let point = (vp.r.offs + pos.coords) / vp.scale;
+
+        let (index, _) = self
+            .layout
+            .rects
+            .iter()
+            .enumerate()
+            .find(|(i, r)| self.is_shown(*i) && r.contains_point(&point))?;
+
+        // transform point to page coordinates
+        let point = point - self.page_origin(index)?.coords;
+        Some((index, self.unrotate_point(index, &point)))
+    }
+
+    /// The shown page closest to the given vertical position in canvas
+    /// coordinates.
+    pub fn page_at_y(&self, y: f64) -> Option<usize> {
+        self.layout
+            .rects
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.is_shown(*i))
+            .min_by(|(_, a), (_, b)| {
+                let da = distance_to_rect(a, &point![a.offs.x, y]);
+                let db = distance_to_rect(b, &point![b.offs.x, y]);
+                da.total_cmp(&db)
+            })
+            .map(|(i, _)| i)
+    }
+
+    /// Small preview of the given page, if it has been rendered.
+    pub fn page_preview(&self, page: usize) -> Option<&F::Data> {
+        self.fallbacks.lowest(page)
+    }
+
+    /// Transform a rectangle spanned by two points in viewport coordinates to
+    /// the page the first point is on, clipped to that page. Returns the page
+    /// index and the rectangle in page coordinates.
+    pub fn page_region(&self, a: Point2<f64>, b: Point2<f64>) -> Option<(usize, Rect<f64>)> {
+        let (index, a) = self.page_at(a)?;
+        let b = self.viewport_to_page(index, b)?;
+        let size = self.page_size(index)?;
+
+        let region = Bounds {
+            x_min: a.x.min(b.x),
+            y_min: a.y.min(b.y),
+            x_max: a.x.max(b.x),
+            y_max: a.y.max(b.y),
+        };
+
+        let page = Rect::new(point![0.0, 0.0], size);
+        Some((index, region.rect().clip(&page)))
+    }
+
+    /// Find the link at the given point in viewport coordinates, preferring
+    /// synthesized links over those of the document.
+    pub fn link_at(&self, pos: Point2<f64>) -> Option<LinkAction> {
+        let (index, point) = self.page_at(pos)?;
+
+        let synthesized = self
+            .overlays
+            .links
+            .iter()
+            .find(|link| link.source.page == index && link.source.rect.contains_point(&point));
+
+        if let Some(link) = synthesized {
+            return Some(LinkAction::GoTo(link.target));
+        }
+
+        // PDF coordinates have their origin at the bottom left corner
+        let size = self.page_size(index)?;
+        let page = self.provider.page_cache().get(index).ok()?;
+        let link = page.link_at(point.x, size.y - point.y)?;
+
+        self.link_action(link.target)
+    }
+
+    /// What following a link of the document with the given target does, if
+    /// it can be followed at all.
+    fn link_action(&self, target: LinkTarget) -> Option<LinkAction> {
+        match target {
+            LinkTarget::Page(dest) => {
+                let target = self.destination(dest.page as usize, dest.left, dest.top)?;
+                Some(LinkAction::GoTo(target))
+            }
+            LinkTarget::Uri(uri) => Some(LinkAction::OpenUri(uri)),
+            LinkTarget::Other => None,
+        }
+    }
+
+    /// Location of a destination given by its position in PDF page
+    /// coordinates, if specified, or the full page otherwise.
+    ///
+    /// The location spans the viewport from the destination, so that it ends
+    /// up at the top left of the viewport when centered.
+    fn destination(&self, page: usize, left: Option<f32>, top: Option<f32>) -> Option<Location> {
+        let size = self.page_size(page)?;
+
+        if left.is_none() && top.is_none() {
+            let rect = Rect::new(point![0.0, 0.0], size);
+            return Some(Location { page, rect });
+        }
+
+        let viewport = self.canvas_viewport().size;
+
+        // PDF coordinates have their origin at the bottom left corner
+        let y = top.map_or(0.0, |top| size.y - top as f64);
+
+        // without horizontal position, keep the page centered horizontally
+        let (x, width) = match left {
+            Some(left) => (left as f64, viewport.x),
+            None => (0.0, size.x),
+        };
+
+        let rect = Rect::new(point![x, y], vector![width, viewport.y]);
+        Some(Location { page, rect })
+    }
+
+    /// All links at least partially visible in the viewport, both synthesized
+    /// ones and those of the document, in reading order.
+    pub fn visible_links(&self) -> Vec<VisibleLink> {
+        let mut links = Vec::new();
+
+        for index in self.visible_pages() {
+            let (size, viewport) = match (self.page_size(index), self.viewport_rect(index)) {
+                (Some(size), Some(viewport)) => (size, viewport),
+                _ => continue,
+            };
+
+            let synthesized = self
+                .overlays
+                .links
+                .iter()
+                .filter(|link| link.source.page == index)
+                .map(|link| VisibleLink {
+                    page: index,
+                    rect: link.source.rect,
+                    action: LinkAction::GoTo(link.target),
+                });
+
+            links.extend(synthesized.filter(|link| link.rect.intersects(&viewport)));
+
+            let page = match self.provider.page_cache().get(index) {
+                Ok(page) => page,
+                Err(err) => {
+                    tracing::warn!(page = index, error=%err, "failed to load page");
+                    continue;
+                }
+            };
+
+            for link in page.links() {
+                let action = match self.link_action(link.target) {
+                    Some(action) => action,
+                    None => continue,
+                };
+
+                // PDF coordinates have their origin at the bottom left corner
+                let r = link.rect;
+                let rect = Rect::new(
+                    point![r.left as f64, size.y - r.top as f64],
+                    vector![(r.right - r.left) as f64, (r.top - r.bottom) as f64],
+                );
+
+                if rect.intersects(&viewport) {
+                    links.push(VisibleLink {
+                        page: index,
+                        rect,
+                        action,
+                    });
+                }
+            }
+        }
+
+        links.sort_by(|a, b| {
+            (a.page.cmp(&b.page))
+                .then(a.rect.offs.y.total_cmp(&b.rect.offs.y))
+                .then(a.rect.offs.x.total_cmp(&b.rect.offs.x))
+        });
+
+        links
+    }
+
+    /// Find the topmost visible text annotation at the given point in
+    /// viewport coordinates. Returns the page index, the annotation, and its
+    /// bounds in page coordinates.
+    pub fn text_annotation_at(&self, pos: Point2<f64>) -> Option<(usize, Annotation, Rect<f64>)> {
+        let (index, point) = self.page_at(pos)?;
+        let size = self.page_size(index)?;
+
+        let page = self.provider.page_cache().get(index).ok()?;
+        let mut found = None;
+
+        for annot in page.annotations() {
+            if annot.subtype() != AnnotationSubtype::Text || annot.is_hidden() {
+                continue;
+            }
+
+            let r = match annot.rect() {
+                Ok(r) => r,
+                Err(_) => continue,
+            };
+
+            // PDF coordinates have their origin at the bottom left corner
+            let rect = Rect::new(
+                point![r.left as f64, size.y - r.top as f64],
+                vector![(r.right - r.left) as f64, (r.top - r.bottom) as f64],
+            );
+
+            if rect.contains_point(&point) {
+                found = Some((index, annot, rect));
+            }
+        }
+
+        found
+    }
+
+    /// Find the topmost tile drawn at the given point in viewport
+    /// coordinates, or the page fallback if no tile covers the point yet.
+    /// Returns the data and the rectangle it is drawn to.
+    pub fn tile_at(&self, pos: Point2<f64>) -> Option<(Rect<f64>, &F::Data)> {
+        let vp = &self.viewport;
+
+        // find the page at the given point, transformed to viewport
+        // coordinates in the same way as for rendering
+        let (index, page_rect_pt) = self.layout.rects.iter().enumerate().find(|(_, r)| {
+            let offs = Point2::from(r.offs.coords * vp.scale - vp.r.offs.coords);
+            let rect = Rect::new(offs, r.size * vp.scale).round();
+
+            rect.contains_point(&pos)
+        })?;
+
+        // tiles are rendered in device pixels
+        let factor = self.scale_factor;
+        let vp_dev = device_viewport(vp, factor);
+        let page_rect_dev = page_transform(&vp_dev)(page_rect_pt);
+        let pos_dev = pos * factor;
+
+        let scale = page_rect_dev.size.x / page_rect_pt.size.x;
+        let vp_adj = Viewport { r: vp_dev.r, scale };
+
+        let (rect, data) = self
+            .tiles
+            .tile_at(&vp_adj, index, &page_rect_dev, &pos_dev)
+            .or_else(|| Some((page_rect_dev, self.fallbacks.fallback(index)?)))?;
+
+        Some((rect.scale(1.0 / factor), data))
+    }
+
+    /// Check if fallbacks have been deferred due to the request limits, i.e.
+    /// if another frame is needed to request them once pending ones have
+    /// been rendered.
+    pub fn has_deferred_fallbacks(&self) -> bool {
+        self.fallbacks.is_deferred()
+    }
+
+    /// Check if the given region damaged by a render task is visible in the
+    /// viewport.
+    pub fn is_damage_visible(&self, damage: &Damage) -> bool {
+        let index = damage.page_index;
+        let (origin, size) = match (self.page_origin(index), self.page_size(index)) {
+            (Some(origin), Some(size)) => (origin, rotate_size(&size, self.rotation())),
+            _ => return false,
+        };
+
+        // transform damaged region from page pixels to canvas coordinates
+        let scale = size.x / damage.page_size.x as f64;
+        let rect = damage
+            .rect
+            .cast::<f64>()
+            .scale(scale)
+            .translate(&origin.coords);
+
+        // transform to viewport coordinates
+        let vp = &self.viewport;
+        let rect = rect.scale(vp.scale).translate(&-vp.r.offs.coords);
+
+        rect.intersects(&Rect::new(point![0.0, 0.0], vp.r.size))
+    }
+
+    /// The viewport in canvas coordinates.
+    fn canvas_viewport(&self) -> Rect<f64> {
+        let vp = &self.viewport;
+        Rect::new(vp.r.offs / vp.scale, vp.r.size / vp.scale)
+    }
+}
+
+impl<M, F> Drop for DocumentView<M, F>
+where
+    F: TileFactory,
+    F::Data: Send,
+{
+    fn drop(&mut self) {
+        let stats = self.provider.page_cache().stats();
+
+        tracing::debug!(
+            hits = stats.hits,
+            misses = stats.misses,
+            evictions = stats.evictions,
+            "page cache statistics"
+        );
+    }
+}
+
+impl<T> PageFrame<'_, T> {
+    /// Transform a rectangle in page coordinates to viewport coordinates.
+    pub fn to_viewport(&self, rect: &Rect<f64>) -> Rect<f64> {
+        let rect = rotate_rect(rect, &self.size, self.rotation);
+
+        Rect::new(
+            self.origin + rect.offs.coords * self.scale,
+            rect.size * self.scale,
+        )
+    }
+
+    /// Transform a point in page coordinates to viewport coordinates.
+    pub fn point_to_viewport(&self, point: &Point2<f64>) -> Point2<f64> {
+        let point = rotate_point(point, &self.size, self.rotation);
+        self.origin + point.coords * self.scale
+    }
+}
+
+fn tile_manager<H>(
+    policy: &TileSizePolicy,
+    opts: &ViewOptions,
+) -> TileManager<DynamicTilingScheme, H>
+where
+    H: crate::core::render::core::TileHandle,
+{
+    let mut manager = TileManager::new(policy.scheme(), opts.tile_halo, vector![25.0, 25.0]);
+    manager.set_upload_limit(opts.upload_limit);
+    manager
+}
+
+fn is_shown(single_page: Option<usize>, page: usize) -> bool {
+    match single_page {
+        Some(shown) => shown == page,
+        None => true,
+    }
+}
+
+/// Range of pages visible in the given viewport.
+fn visible_pages_in<F>(
+    rects: &[Rect<f64>],
+    single_page: Option<usize>,
+    transform: &F,
+    vp: &Viewport,
+) -> Range<usize>
+where
+    F: Fn(&Rect<f64>) -> Rect<f64>,
+{
+    // origin-aligned viewport
+    let screen_rect = Rect::new(point![0.0, 0.0], vp.r.size);
+
+    #[allow(clippy::reversed_empty_ranges)]
+    let mut visible = usize::MAX..0;
+
+    for (i, page_rect_pt) in rects.iter().enumerate() {
+        // transform page bounds to viewport
+        let page_rect = transform(page_rect_pt);
+
+        // check if the page is visible
+        if is_shown(single_page, i) && page_rect.intersects(&screen_rect) {
+            visible.start = usize::min(visible.start, i);
+            visible.end = usize::max(visible.end, i + 1);
+        }
+    }
+
+    // ensure that we have a valid range if there are no visible pages
+    if visible.start > visible.end {
+        visible = 0..0;
+    }
+
+    visible
+}
+
+/// Transformation of page bounds from canvas to viewport coordinates, rounded
+/// for pixel-perfect rendering.
+fn page_transform(vp: &Viewport) -> impl Fn(&Rect<f64>) -> Rect<f64> {
+    // transformation matrix: canvas to viewport
+    let m_ctv = {
+        let m_scale = Similarity2::from_scaling(vp.scale);
+        let m_trans = Translation2::from(-vp.r.offs.coords);
+        m_trans * m_scale
+    };
+
+    move |page_rect: &Rect<f64>| {
+        // transformation matrix: page to canvas
+        let m_ptc = Translation2::from(page_rect.offs);
+
+        // transformation matrix: page to viewport/screen
+        let m_ptv = m_ctv * m_ptc;
+
+        // convert page bounds to screen coordinates
+        let page_rect = Rect::new(m_ptv * point![0.0, 0.0], m_ptv * page_rect.size);
+
+        // round coordinates for pixel-perfect rendering
+        page_rect.round()
+    }
+}
+
+/// The given viewport in device pixels, for the given scale factor.
+fn device_viewport(vp: &Viewport, factor: f64) -> Viewport {
+    Viewport {
+        r: vp.r.scale(factor),
+        scale: vp.scale * factor,
+    }
+}
+
+fn distance_to_rect(rect: &Rect<f64>, point: &Point2<f64>) -> f64 {
+    let bounds = rect.bounds();
+
+    let dx = (bounds.x_min - point.x)
+        .max(point.x - bounds.x_max)
+        .max(0.0);
+    let dy = (bounds.y_min - point.y)
+        .max(point.y - bounds.y_max)
+        .max(0.0);
+
+    dx.hypot(dy)
+}
@@ -0,0 +1,82 @@
+//! Watching documents for changes made by other programs, e.g. a LaTeX
+//! document being recompiled, for reloading them automatically.
+
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Duration;
+
+use gtk::gio::{self, FileMonitorEvent};
+use gtk::glib;
+use gtk::prelude::{FileExt, FileMonitorExt};
+
+/// Time without further changes after which a file is considered to be
+/// written completely. Files are often written in several steps, e.g. by
+/// LaTeX, and may not be valid in between.
+const SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+/// Watches a file for changes, calling a function once the file has settled
+/// after being changed, replaced, or re-created. Stops watching when dropped.
+#[derive(Debug)]
+pub struct FileWatch {
+    monitor: gio::FileMonitor,
+    timer: Rc<RefCell<Option<glib::SourceId>>>,
+}
+
+impl FileWatch {
+    /// Start watching the given file, calling the given function whenever it
+    /// has been changed.
+    pub fn connect<F: Fn() + 'static>(path: &Path, f: F) -> Result<Self, glib::Error> {
+        let file = gio::File::for_path(path);
+        let monitor =
+            file.monitor_file(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE)?;
+
+        let f = Rc::new(f);
+        let timer = Rc::new(RefCell::new(None::<glib::SourceId>));
+        let pending = timer.clone();
+
+        monitor.connect_changed(move |_, target, other, event| {
+            let changed = match event {
+                FileMonitorEvent::Changed
+                | FileMonitorEvent::ChangesDoneHint
+                | FileMonitorEvent::Created
+                | FileMonitorEvent::MovedIn => true,
+                // files are often replaced by renaming a temporary file
+                FileMonitorEvent::Renamed => other.is_some_and(|o| o.equal(&file)),
+                _ => false,
+            };
+
+            if !changed {
+                return;
+            }
+
+            tracing::trace!(file=?target.path(), ?event, "watched file changed");
+
+            // wait for the file to settle, restarting on each change
+            if let Some(id) = pending.take() {
+                id.remove();
+            }
+
+            let f = f.clone();
+            let slot = pending.clone();
+            let id = glib::timeout_add_local_once(SETTLE_DELAY, move || {
+                slot.take();
+                f();
+            });
+
+            pending.replace(Some(id));
+        });
+
+        Ok(Self { monitor, timer })
+    }
+}
+
+impl Drop for FileWatch {
+    fn drop(&mut self) {
+        self.monitor.cancel();
+
+        if let Some(id) = self.timer.take() {
+            id.remove();
+        }
+    }
+}
@@ -0,0 +1,882 @@
+//! Exchange of annotations with other PDF tools via XFDF (XML Forms Data
+//! Format, ISO 19444-1).
+//!
+//! Imported annotations are only added to the document in memory, the
+//! original file is never modified.
+
+use std::fmt::Write as _;
+use std::io::Write;
+
+use pdfium::bitmap::Color;
+use pdfium::doc::{Annotation, AnnotationSubtype, Document, Page};
+use pdfium::types::Rect;
+
+/// Maximum difference between coordinates of rectangles considered equal, in
+/// PDF points. Coordinates are rounded when being written to XFDF.
+const RECT_TOLERANCE: f32 = 0.01;
+
+/// Annotation flags (PDF 32000-1, section 12.5.3) and their names in XFDF.
+const FLAGS: &[(u32, &str)] = &[
+    (1 << 0, "invisible"),
+    (1 << 1, "hidden"),
+    (1 << 2, "print"),
+    (1 << 3, "nozoom"),
+    (1 << 4, "norotate"),
+    (1 << 5, "noview"),
+    (1 << 6, "readonly"),
+    (1 << 7, "locked"),
+    (1 << 8, "togglenoview"),
+];
+
+/// Reason why annotations could not be imported.
+#[derive(Debug)]
+pub enum XfdfError {
+    /// The file could not be read.
+    Io(std::io::Error),
+
+    /// The input is not well-formed XML or not an XFDF document.
+    Parse { line: usize, message: String },
+
+    /// The annotations could not be added to the document.
+    Pdf(pdfium::Error),
+}
+
+/// Outcome of an import.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Annotations added to the document.
+    pub added: usize,
+
+    /// Existing annotations replaced by a newer version of the same name.
+    pub replaced: usize,
+
+    /// Annotations not imported, because they are already present, are older
+    /// than the ones present, or are not supported.
+    pub skipped: usize,
+}
+
+/// An annotation as exchanged via XFDF.
+///
+/// Only annotations fully described by their rectangle are supported, i.e.
+/// ones without additional geometry like the vertices of ink strokes.
+#[derive(Debug, Clone)]
+struct Entry {
+    subtype: AnnotationSubtype,
+    page: usize,
+    rect: Rect,
+    name: Option<String>,
+    title: Option<String>,
+    date: Option<String>,
+    color: Option<Color>,
+    flags: u32,
+    contents: String,
+}
+
+/// Write the annotations of all pages of the document as XFDF. Returns the
+/// number of exported annotations.
+pub fn export<W: Write>(doc: &Document, out: &mut W) -> std::io::Result<usize> {
+    let mut entries = Vec::new();
+
+    for index in 0..doc.pages().count() {
+        let page = match doc.pages().get(index) {
+            Ok(page) => page,
+            Err(err) => {
+                tracing::warn!(page = index, error=%err, "failed to load page");
+                continue;
+            }
+        };
+
+        for annot in page.annotations() {
+            match Entry::from_annotation(&annot, index as usize) {
+                Ok(Some(entry)) => entries.push(entry),
+                Ok(None) => {
+                    let subtype = annot.subtype();
+                    tracing::debug!(page = index, ?subtype, "skipping unsupported annotation");
+                }
+                Err(err) => {
+                    tracing::warn!(page = index, error=%err, "failed to read annotation");
+                }
+            }
+        }
+    }
+
+    out.write_all(to_xfdf(&entries).as_bytes())?;
+    Ok(entries.len())
+}
+
+/// Add the annotations of the given XFDF document to the document.
+///
+/// Annotations with the same name (`NM`) as one already on the page are
+/// conflicts: The newer one according to the modification date is kept, or
+/// the existing one if that cannot be decided. Unnamed annotations are
+/// skipped if an identical one is already present.
+pub fn import(doc: &Document, input: &str) -> Result<ImportSummary, XfdfError> {
+    let root = xml::parse(input)?;
+
+    if root.name != "xfdf" {
+        return Err(XfdfError::Parse {
+            line: 1,
+            message: format!("expected <xfdf> root element, found <{}>", root.name),
+        });
+    }
+
+    let mut summary = ImportSummary::default();
+
+    let annots = root.children.iter().filter(|e| e.name == "annots");
+    for elem in annots.flat_map(|e| e.children.iter()) {
+        let entry = match Entry::from_element(elem) {
+            Some(entry) if entry.page < doc.pages().count() as usize => entry,
+            _ => {
+                tracing::debug!(element = elem.name, "skipping unsupported annotation");
+                summary.skipped += 1;
+                continue;
+            }
+        };
+
+        let page = doc.pages().get(entry.page as _)?;
+        match merge(&page, &entry)? {
+            Merge::Added => summary.added += 1,
+            Merge::Replaced => summary.replaced += 1,
+            Merge::Skipped => summary.skipped += 1,
+        }
+    }
+
+    Ok(summary)
+}
+
+enum Merge {
+    Added,
+    Replaced,
+    Skipped,
+}
+
+fn merge(page: &Page, entry: &Entry) -> pdfium::Result<Merge> {
+    let existing = (0..page.annotation_count())
+        .filter_map(|i| page.annotation(i).ok().map(|annot| (i, annot)))
+        .find(|(_, annot)| match &entry.name {
+            Some(name) => annot.string_value("NM").ok().flatten().as_ref() == Some(name),
+            None => entry.matches(annot),
+        });
+
+    let (index, annot) = match existing {
+        Some(existing) => existing,
+        None => {
+            entry.create(page)?;
+            return Ok(Merge::Added);
+        }
+    };
+
+    // unnamed annotations can only be identical, not newer
+    if entry.name.is_none() {
+        return Ok(Merge::Skipped);
+    }
+
+    let date = annot.string_value("M")?;
+    match (date_key(entry.date.as_deref()), date_key(date.as_deref())) {
+        (Some(new), Some(old)) if new > old => {}
+        _ => return Ok(Merge::Skipped),
+    }
+
+    // release our handle before removing the annotation it refers to
+    drop(annot);
+
+    page.remove_annotation(index)?;
+    entry.create(page)?;
+
+    Ok(Merge::Replaced)
+}
+
+/// Comparable representation of a PDF date (`D:YYYYMMDDHHmmSS...`), ignoring
+/// the time zone.
+fn date_key(date: Option<&str>) -> Option<String> {
+    let date = date?;
+    let date = date.strip_prefix("D:").unwrap_or(date);
+
+    let mut key: String = date
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .take(14)
+        .collect();
+    if key.len() < 4 {
+        return None;
+    }
+
+    // omitted fields default to their lowest value
+    while key.len() < 14 {
+        key.push('0');
+    }
+
+    Some(key)
+}
+
+impl Entry {
+    fn from_annotation(annot: &Annotation, page: usize) -> pdfium::Result<Option<Self>> {
+        let subtype = annot.subtype();
+        if element_name(subtype).is_none() {
+            return Ok(None);
+        }
+
+        let entry = Self {
+            subtype,
+            page,
+            rect: annot.rect()?,
+            name: annot.string_value("NM")?,
+            title: annot.string_value("T")?,
+            date: annot.string_value("M")?,
+            color: annot.color(),
+            flags: annot.flags(),
+            contents: annot.contents()?.unwrap_or_default(),
+        };
+
+        Ok(Some(entry))
+    }
+
+    fn from_element(elem: &xml::Element) -> Option<Self> {
+        let subtype = subtype_from_name(&elem.name)?;
+        let page = elem.attr("page")?.trim().parse().ok()?;
+        let rect = parse_rect(elem.attr("rect")?)?;
+
+        let color = elem.attr("color").and_then(parse_color).map(|c| {
+            let opacity = elem
+                .attr("opacity")
+                .and_then(|o| o.trim().parse::<f32>().ok());
+            let alpha = opacity.map_or(255, |o| (o.clamp(0.0, 1.0) * 255.0).round() as u8);
+
+            Color::new_rgba(c.r, c.g, c.b, alpha)
+        });
+
+        let flags = elem.attr("flags").map_or(0, |flags| {
+            flags
+                .split(',')
+                .filter_map(|name| FLAGS.iter().find(|(_, n)| *n == name.trim()))
+                .fold(0, |acc, (bit, _)| acc | bit)
+        });
+
+        let contents = elem
+            .children
+            .iter()
+            .find(|e| e.name == "contents")
+            .map(|e| e.text.clone())
+            .unwrap_or_default();
+
+        Some(Self {
+            subtype,
+            page,
+            rect,
+            name: elem.attr("name").map(String::from),
+            title: elem.attr("title").map(String::from),
+            date: elem.attr("date").map(String::from),
+            color,
+            flags,
+            contents,
+        })
+    }
+
+    /// Check if the given annotation looks the same as this one.
+    fn matches(&self, annot: &Annotation) -> bool {
+        let same_rect = |r: &Rect| {
+            (r.left - self.rect.left).abs() < RECT_TOLERANCE
+                && (r.top - self.rect.top).abs() < RECT_TOLERANCE
+                && (r.right - self.rect.right).abs() < RECT_TOLERANCE
+                && (r.bottom - self.rect.bottom).abs() < RECT_TOLERANCE
+        };
+
+        annot.subtype() == self.subtype
+            && annot.rect().map(|r| same_rect(&r)).unwrap_or(false)
+            && annot.contents().ok().flatten().unwrap_or_default() == self.contents
+    }
+
+    fn create(&self, page: &Page) -> pdfium::Result<()> {
+        let annot = page.create_annotation(self.subtype)?;
+
+        annot.set_rect(&self.rect)?;
+        annot.set_flags(self.flags)?;
+
+        if let Some(color) = self.color {
+            annot.set_color(color)?;
+        }
+
+        if !self.contents.is_empty() {
+            annot.set_contents(&self.contents)?;
+        }
+
+        let strings = [("NM", &self.name), ("T", &self.title), ("M", &self.date)];
+        for (key, value) in strings {
+            if let Some(value) = value {
+                annot.set_string_value(key, value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn element_name(subtype: AnnotationSubtype) -> Option<&'static str> {
+    match subtype {
+        AnnotationSubtype::Text => Some("text"),
+        AnnotationSubtype::FreeText => Some("freetext"),
+        AnnotationSubtype::Square => Some("square"),
+        AnnotationSubtype::Circle => Some("circle"),
+        AnnotationSubtype::Stamp => Some("stamp"),
+        _ => None,
+    }
+}
+
+fn subtype_from_name(name: &str) -> Option<AnnotationSubtype> {
+    match name {
+        "text" => Some(AnnotationSubtype::Text),
+        "freetext" => Some(AnnotationSubtype::FreeText),
+        "square" => Some(AnnotationSubtype::Square),
+        "circle" => Some(AnnotationSubtype::Circle),
+        "stamp" => Some(AnnotationSubtype::Stamp),
+        _ => None,
+    }
+}
+
+/// Parse a rectangle given as `x1,y1,x2,y2` in PDF coordinates.
+fn parse_rect(value: &str) -> Option<Rect> {
+    let mut coords = value.split(',').map(|v| v.trim().parse::<f32>());
+
+    let x1 = coords.next()?.ok()?;
+    let y1 = coords.next()?.ok()?;
+    let x2 = coords.next()?.ok()?;
+    let y2 = coords.next()?.ok()?;
+
+    Some(Rect {
+        left: x1.min(x2),
+        top: y1.max(y2),
+        right: x1.max(x2),
+        bottom: y1.min(y2),
+    })
+}
+
+/// Parse a color given as `#RRGGBB`.
+fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.trim().strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    Some(Color::new_rgb(
+        (rgb >> 16) as u8,
+        (rgb >> 8) as u8,
+        rgb as u8,
+    ))
+}
+
+fn to_xfdf(entries: &[Entry]) -> String {
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<xfdf xmlns=\"http://ns.adobe.com/xfdf/\" xml:space=\"preserve\">\n",
+        "  <annots>\n",
+    ));
+
+    for entry in entries {
+        let name = element_name(entry.subtype).unwrap();
+        let r = &entry.rect;
+
+        let _ = write!(
+            out,
+            "    <{} page=\"{}\" rect=\"{:.2},{:.2},{:.2},{:.2}\"",
+            name, entry.page, r.left, r.bottom, r.right, r.top
+        );
+
+        let strings = [
+            ("name", &entry.name),
+            ("title", &entry.title),
+            ("date", &entry.date),
+        ];
+        for (attr, value) in strings {
+            if let Some(value) = value {
+                let _ = write!(out, " {}=\"{}\"", attr, xml::escape(value));
+            }
+        }
+
+        if let Some(c) = entry.color {
+            let _ = write!(out, " color=\"#{:02X}{:02X}{:02X}\"", c.r, c.g, c.b);
+
+            if c.a != 255 {
+                let _ = write!(out, " opacity=\"{:.3}\"", c.a as f32 / 255.0);
+            }
+        }
+
+        let flags: Vec<_> = FLAGS
+            .iter()
+            .filter(|(bit, _)| entry.flags & bit != 0)
+            .map(|(_, name)| *name)
+            .collect();
+
+        if !flags.is_empty() {
+            let _ = write!(out, " flags=\"{}\"", flags.join(","));
+        }
+
+        if entry.contents.is_empty() {
+            out.push_str(" />\n");
+        } else {
+            let _ = write!(
+                out,
+                ">\n      <contents>{}</contents>\n    </{}>\n",
+                xml::escape(&entry.contents),
+                name
+            );
+        }
+    }
+
+    out.push_str("  </annots>\n</xfdf>\n");
+    out
+}
+
+impl std::fmt::Display for XfdfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XfdfError::Parse { line, message } => {
+                write!(f, "invalid XFDF (line {line}): {message}")
+            }
+            XfdfError::Io(err) => write!(f, "failed to read file: {err}"),
+            XfdfError::Pdf(err) => write!(f, "failed to add annotations: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for XfdfError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            XfdfError::Io(err) => Some(err),
+            XfdfError::Parse { .. } => None,
+            XfdfError::Pdf(err) => Some(err),
+        }
+    }
+}
+
+impl From<std::io::Error> for XfdfError {
+    fn from(err: std::io::Error) -> Self {
+        XfdfError::Io(err)
+    }
+}
+
+impl From<pdfium::Error> for XfdfError {
+    fn from(err: pdfium::Error) -> Self {
+        XfdfError::Pdf(err)
+    }
+}
+
+/// A minimal XML reader, sufficient for XFDF.
+///
+/// Namespaces are not resolved, prefixes are simply dropped from names.
+/// Document type declarations are skipped, so only the predefined and
+/// numeric character entities are supported.
+mod xml {
+    use super::XfdfError;
+
+    /// Maximum nesting depth of elements. XFDF documents are shallow, deeper
+    /// nesting is rejected instead of risking to run out of stack space.
+    const MAX_DEPTH: usize = 64;
+
+    #[derive(Debug, Clone, Default)]
+    pub struct Element {
+        pub name: String,
+        pub attrs: Vec<(String, String)>,
+        pub children: Vec<Element>,
+
+        /// Concatenated text directly contained in this element.
+        pub text: String,
+    }
+
+    impl Element {
+        pub fn attr(&self, name: &str) -> Option<&str> {
+            self.attrs
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        }
+    }
+
+    /// Parse the given document and return its root element.
+    pub fn parse(input: &str) -> Result<Element, XfdfError> {
+        let mut reader = Reader { input, pos: 0 };
+
+        reader.skip_misc()?;
+        let root = reader.element(0)?;
+        reader.skip_misc()?;
+
+        if reader.pos < input.len() {
+            return Err(reader.error("unexpected content after root element"));
+        }
+
+        Ok(root)
+    }
+
+    /// Escape text for use in attribute values and element content.
+    pub fn escape(text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+
+        for c in text.chars() {
+            match c {
+                '&' => out.push_str("&amp;"),
+                '<' => out.push_str("&lt;"),
+                '>' => out.push_str("&gt;"),
+                '"' => out.push_str("&quot;"),
+                '\r' => out.push_str("&#13;"),
+                '\n' => out.push_str("&#10;"),
+                c => out.push(c),
+            }
+        }
+
+        out
+    }
+
+    struct Reader<'a> {
+        input: &'a str,
+        pos: usize,
+    }
+
+    impl<'a> Reader<'a> {
+        fn rest(&self) -> &'a str {
+            &self.input[self.pos..]
+        }
+
+        fn error(&self, message: impl Into<String>) -> XfdfError {
+            let line = self.input[..self.pos].matches('\n').count() + 1;
+
+            XfdfError::Parse {
+                line,
+                message: message.into(),
+            }
+        }
+
+        fn expect(&mut self, token: &str) -> Result<(), XfdfError> {
+            if !self.rest().starts_with(token) {
+                return Err(self.error(format!("expected '{token}'")));
+            }
+
+            self.pos += token.len();
+            Ok(())
+        }
+
+        /// Skip to behind the given token.
+        fn skip_past(&mut self, token: &str) -> Result<&'a str, XfdfError> {
+            match self.rest().find(token) {
+                Some(i) => {
+                    let skipped = &self.rest()[..i];
+                    self.pos += i + token.len();
+                    Ok(skipped)
+                }
+                None => Err(self.error(format!("missing '{token}'"))),
+            }
+        }
+
+        fn skip_whitespace(&mut self) {
+            let rest = self.rest();
+            self.pos += rest.len() - rest.trim_start().len();
+        }
+
+        /// Skip whitespace, comments, processing instructions, and document
+        /// type declarations outside of the root element.
+        fn skip_misc(&mut self) -> Result<(), XfdfError> {
+            loop {
+                self.skip_whitespace();
+
+                if self.rest().starts_with("<?") {
+                    self.skip_past("?>")?;
+                } else if self.rest().starts_with("<!--") {
+                    self.skip_past("-->")?;
+                } else if self.rest().starts_with("<!") {
+                    self.skip_past(">")?;
+                } else {
+                    return Ok(());
+                }
+            }
+        }
+
+        fn name(&mut self) -> Result<String, XfdfError> {
+            let rest = self.rest();
+            let len = rest
+                .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '='))
+                .unwrap_or(rest.len());
+
+            if len == 0 {
+                return Err(self.error("expected name"));
+            }
+
+            self.pos += len;
+            Ok(rest[..len].to_owned())
+        }
+
+        /// Parse the element at the current position, nested in the given
+        /// number of parent elements.
+        fn element(&mut self, depth: usize) -> Result<Element, XfdfError> {
+            if depth >= MAX_DEPTH {
+                return Err(self.error("elements nested too deeply"));
+            }
+
+            self.expect("<")?;
+
+            let qname = self.name()?;
+            let mut elem = Element {
+                name: local_name(&qname).to_owned(),
+                ..Default::default()
+            };
+
+            // attributes
+            loop {
+                self.skip_whitespace();
+
+                if self.rest().starts_with("/>") {
+                    self.pos += 2;
+                    return Ok(elem);
+                }
+
+                if self.rest().starts_with('>') {
+                    self.pos += 1;
+                    break;
+                }
+
+                let name = self.name()?;
+                self.skip_whitespace();
+                self.expect("=")?;
+                self.skip_whitespace();
+
+                let quote = match self.rest().chars().next() {
+                    Some(q @ ('"' | '\'')) => q,
+                    _ => return Err(self.error("expected quoted attribute value")),
+                };
+                self.pos += 1;
+
+                let value = self.skip_past(&quote.to_string())?;
+                let value = self.unescape(value)?;
+
+                // namespace declarations are not resolved
+                if name != "xmlns" && !name.starts_with("xmlns:") {
+                    elem.attrs.push((local_name(&name).to_owned(), value));
+                }
+            }
+
+            // content
+            loop {
+                let rest = self.rest();
+
+                if rest.starts_with("</") {
+                    self.pos += 2;
+
+                    let end = self.name()?;
+                    if end != qname {
+                        return Err(self.error(format!("expected </{qname}>, found </{end}>")));
+                    }
+
+                    self.skip_whitespace();
+                    self.expect(">")?;
+                    return Ok(elem);
+                } else if rest.starts_with("<!--") {
+                    self.skip_past("-->")?;
+                } else if rest.starts_with("<![CDATA[") {
+                    self.pos += "<![CDATA[".len();
+                    let text = self.skip_past("]]>")?;
+                    elem.text.push_str(text);
+                } else if rest.starts_with("<?") {
+                    self.skip_past("?>")?;
+                } else if rest.starts_with('<') {
+                    elem.children.push(self.element(depth + 1)?);
+                } else if rest.is_empty() {
+                    return Err(self.error(format!("missing </{qname}>")));
+                } else {
+                    let len = rest.find('<').unwrap_or(rest.len());
+                    let text = self.unescape(&rest[..len])?;
+                    elem.text.push_str(&text);
+                    self.pos += len;
+                }
+            }
+        }
+
+        fn unescape(&self, text: &str) -> Result<String, XfdfError> {
+            let mut out = String::with_capacity(text.len());
+            let mut rest = text;
+
+            while let Some(start) = rest.find('&') {
+                out.push_str(&rest[..start]);
+                rest = &rest[start + 1..];
+
+                let end = match rest.find(';') {
+                    Some(end) => end,
+                    None => return Err(self.error("unterminated entity reference")),
+                };
+
+                let entity = &rest[..end];
+                let c = match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    _ => match entity.strip_prefix('#') {
+                        Some(num) => match num.strip_prefix('x') {
+                            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                            None => num.parse().ok(),
+                        }
+                        .and_then(char::from_u32),
+                        None => None,
+                    },
+                };
+
+                match c {
+                    Some(c) => out.push(c),
+                    None => return Err(self.error(format!("unknown entity '&{entity};'"))),
+                }
+
+                rest = &rest[end + 1..];
+            }
+
+            out.push_str(rest);
+            Ok(out)
+        }
+    }
+
+    fn local_name(name: &str) -> &str {
+        match name.split_once(':') {
+            Some((_, local)) => local,
+            None => name,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse_error(input: &str) -> (usize, String) {
+        match xml::parse(input) {
+            Err(XfdfError::Parse { line, message }) => (line, message),
+            result => panic!("expected parse error, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let entries = [
+            Entry {
+                subtype: AnnotationSubtype::FreeText,
+                page: 3,
+                rect: Rect {
+                    left: 10.5,
+                    top: 80.25,
+                    right: 120.0,
+                    bottom: 20.75,
+                },
+                name: Some("note \"1\"".into()),
+                title: Some("A & B".into()),
+                date: Some("D:20230401120000+02'00'".into()),
+                color: Some(Color::new_rgba(255, 128, 0, 128)),
+                flags: (1 << 2) | (1 << 7),
+                contents: "<first> line\nsecond & \"last\" line".into(),
+            },
+            Entry {
+                subtype: AnnotationSubtype::Square,
+                page: 0,
+                rect: Rect {
+                    left: 0.0,
+                    top: 1.0,
+                    right: 1.0,
+                    bottom: 0.0,
+                },
+                name: None,
+                title: None,
+                date: None,
+                color: None,
+                flags: 0,
+                contents: String::new(),
+            },
+        ];
+
+        let root = xml::parse(&to_xfdf(&entries)).unwrap();
+        assert_eq!(root.name, "xfdf");
+        assert_eq!(root.children.len(), 1);
+
+        let parsed: Vec<_> = root.children[0]
+            .children
+            .iter()
+            .map(|e| Entry::from_element(e).unwrap())
+            .collect();
+
+        assert_eq!(parsed.len(), entries.len());
+
+        for (a, b) in entries.iter().zip(&parsed) {
+            assert_eq!(a.subtype, b.subtype);
+            assert_eq!(a.page, b.page);
+            assert_eq!(a.rect, b.rect);
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.title, b.title);
+            assert_eq!(a.date, b.date);
+            assert_eq!(a.color, b.color);
+            assert_eq!(a.flags, b.flags);
+            assert_eq!(a.contents, b.contents);
+        }
+    }
+
+    #[test]
+    fn unescape_entities() {
+        let root = xml::parse("<a t=\"&amp;&#x41;&#66;&lt;\">x &quot;y&apos; &gt;</a>").unwrap();
+        assert_eq!(root.attr("t"), Some("&AB<"));
+        assert_eq!(root.text, "x \"y' >");
+
+        // unknown, unterminated, and invalid character references
+        let (_, message) = parse_error("<a>&nbsp;</a>");
+        assert_eq!(message, "unknown entity '&nbsp;'");
+
+        let (_, message) = parse_error("<a t=\"&amp\"/>");
+        assert_eq!(message, "unterminated entity reference");
+
+        let (_, message) = parse_error("<a>&#xD800;</a>");
+        assert_eq!(message, "unknown entity '&#xD800;'");
+    }
+
+    #[test]
+    fn namespaced_names() {
+        let input = concat!(
+            "<?xml version=\"1.0\"?>\n",
+            "<x:xfdf xmlns:x=\"http://ns.adobe.com/xfdf/\" xmlns=\"urn:other\">",
+            "<x:annots><x:text x:page=\"1\" rect=\"0,0,1,1\"/></x:annots>",
+            "</x:xfdf>",
+        );
+
+        let root = xml::parse(input).unwrap();
+        assert_eq!(root.name, "xfdf");
+        assert!(root.attrs.is_empty());
+
+        let annot = &root.children[0].children[0];
+        assert_eq!(root.children[0].name, "annots");
+        assert_eq!(annot.name, "text");
+        assert_eq!(annot.attr("page"), Some("1"));
+
+        // end tags have to match including their prefix
+        let (_, message) = parse_error("<x:a></a>");
+        assert_eq!(message, "expected </x:a>, found </a>");
+    }
+
+    #[test]
+    fn malformed_input() {
+        let (line, message) = parse_error("<xfdf>\n  <annots>\n  </annots>\n");
+        assert_eq!((line, message.as_str()), (4, "missing </xfdf>"));
+
+        let (line, message) = parse_error("<xfdf>\n  <text page=1 />\n</xfdf>");
+        assert_eq!(
+            (line, message.as_str()),
+            (2, "expected quoted attribute value")
+        );
+
+        let (_, message) = parse_error("<a t=\"value/>");
+        assert_eq!(message, "missing '\"'");
+
+        let (_, message) = parse_error("<a/><b/>");
+        assert_eq!(message, "unexpected content after root element");
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let nested = |depth| "<a>".repeat(depth) + &"</a>".repeat(depth);
+
+        assert!(xml::parse(&nested(8)).is_ok());
+
+        let (_, message) = parse_error(&nested(100_000));
+        assert_eq!(message, "elements nested too deeply");
+    }
+}
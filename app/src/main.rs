@@ -15,7 +15,38 @@ fn main() -> glib::ExitCode {
     // set up logging
     tracing_subscriber::fmt::init();
 
-    // run application
+    // act as thumbnailer for file managers, without starting the UI
+    let mut args = std::env::args_os().skip(1).peekable();
+    if args.next_if(|arg| arg == "--thumbnail").is_some() {
+        return core::render::preview::run_thumbnailer(args);
+    }
+
+    // check options before starting the UI, as errors cannot be shown from
+    // within the application once the command line has been forwarded
+    let opts = match core::cli::Options::parse(args) {
+        Ok(opts) => opts,
+        Err(err) => {
+            eprintln!("Error: {err}\n\n{}", core::cli::USAGE);
+            return glib::ExitCode::FAILURE;
+        }
+    };
+
+    if opts.help {
+        println!("{}", core::cli::USAGE);
+        return glib::ExitCode::SUCCESS;
+    }
+
+    // render pages to files, without starting the UI
+    if let Some(output) = &opts.render_to {
+        return core::cli::run_render(&opts, output);
+    }
+
+    // export the text layer, without starting the UI
+    if let Some((format, output)) = &opts.export_text {
+        return core::cli::run_export_text(&opts, *format, output);
+    }
+
+    // run application, handling the command line in its primary instance
     let app = ui::app::App::new();
     app.run()
 }
@@ -260,7 +260,7 @@ impl From<Bounds<f64>> for graphene::Rect {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Rect<T: Scalar> {
     pub offs: Point2<T>,
     pub size: Vector2<T>,
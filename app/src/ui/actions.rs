@@ -0,0 +1,153 @@
+//! Central registry of user-facing actions.
+//!
+//! Menus, keyboard shortcuts, and the command palette are all derived from
+//! this registry. Actions themselves are implemented and installed by the
+//! respective widgets, e.g. the application window for `win.*` actions.
+
+use gtk::gio;
+use gtk::prelude::{GtkApplicationExt, IsA};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ActionInfo {
+    /// Detailed action name, e.g. `win.document-open`
+    pub name: &'static str,
+
+    /// Human-readable label
+    pub label: &'static str,
+
+    /// Keyboard accelerators
+    pub accels: &'static [&'static str],
+}
+
+impl ActionInfo {
+    const fn new(name: &'static str, label: &'static str) -> Self {
+        Self {
+            name,
+            label,
+            accels: &[],
+        }
+    }
+
+    const fn with_accels(self, accels: &'static [&'static str]) -> Self {
+        Self { accels, ..self }
+    }
+}
+
+pub const COMMAND_PALETTE: &str = "win.command-palette";
+
+/// All registered actions, grouped into sections as shown in the menu.
+pub const SECTIONS: &[&[ActionInfo]] = &[
+    &[
+        ActionInfo::new("win.document-open", "Open Document…").with_accels(&["<Control>o"]),
+        ActionInfo::new("win.print", "Print…").with_accels(&["<Control>p"]),
+        ActionInfo::new("win.document-close", "Close Document").with_accels(&["<Control>w"]),
+    ],
+    &[
+        ActionInfo::new("win.export-images", "Export Pages as Images…"),
+        ActionInfo::new("win.export-text-layer", "Export Text Layer…"),
+        ActionInfo::new("win.annotations-export", "Export Annotations…"),
+        ActionInfo::new("win.annotations-import", "Import Annotations…"),
+        ActionInfo::new("win.document-properties", "Document Properties")
+            .with_accels(&["<Alt>Return"]),
+        ActionInfo::new("win.document-statistics", "Document Statistics"),
+        ActionInfo::new("win.document-compare", "Compare With…"),
+        ActionInfo::new("win.document-compare-stop", "Stop Comparing"),
+        ActionInfo::new("win.trust-document", "Trust This Document"),
+    ],
+    &[
+        ActionInfo::new("win.zoom-fit-width", "Fit Width").with_accels(&["<Control>2"]),
+        ActionInfo::new("win.zoom-fit-page", "Fit Page").with_accels(&["<Control>0"]),
+        ActionInfo::new("win.zoom-fit-text", "Fit Text Column").with_accels(&["<Control>3"]),
+        ActionInfo::new("win.zoom-actual-size", "Actual Size").with_accels(&["<Control>1"]),
+        ActionInfo::new("win.single-page", "Single Page").with_accels(&["<Control>4"]),
+        ActionInfo::new("win.trim-margins", "Trim Margins"),
+        ActionInfo::new("win.reader-mode", "Reader Mode").with_accels(&["<Control>5"]),
+        ActionInfo::new("win.presentation", "Presentation").with_accels(&["F5"]),
+        ActionInfo::new("win.rotate-clockwise", "Rotate Clockwise").with_accels(&["<Control>r"]),
+        ActionInfo::new("win.rotate-counter-clockwise", "Rotate Counter-Clockwise")
+            .with_accels(&["<Control><Shift>r"]),
+        ActionInfo::new("win.layout::vertical", "Continuous Layout"),
+        ActionInfo::new("win.layout::horizontal", "Horizontal Layout"),
+        ActionInfo::new("win.layout::dual", "Two-Page Layout"),
+        ActionInfo::new("win.layout::dual-cover", "Two-Page Layout With Cover"),
+        ActionInfo::new("win.layout::grid", "Grid Layout"),
+        ActionInfo::new("win.outline", "Outline").with_accels(&["F9"]),
+        ActionInfo::new("win.thumbnails", "Page Thumbnails").with_accels(&["<Shift>F9"]),
+        ActionInfo::new("win.calibrate-display", "Calibrate Display…"),
+    ],
+    &[
+        ActionInfo::new("win.dark-pages", "Dark Pages").with_accels(&["<Control><Shift>n"]),
+        ActionInfo::new("win.follow-night-light", "Dark Pages With Night Light"),
+        ActionInfo::new("win.night-colors", "Render Dark Pages With Night Colors"),
+    ],
+    &[
+        ActionInfo::new("win.search", "Search…").with_accels(&["<Control>f"]),
+        ActionInfo::new("win.search-next", "Next Match").with_accels(&["<Control>g"]),
+        ActionInfo::new("win.search-previous", "Previous Match")
+            .with_accels(&["<Control><Shift>g"]),
+        ActionInfo::new("win.search-all-documents", "Search All Open Documents"),
+    ],
+    &[
+        ActionInfo::new("win.go-to-page", "Go to Page…").with_accels(&["<Control>l"]),
+        ActionInfo::new("win.next-page", "Next Page"),
+        ActionInfo::new("win.previous-page", "Previous Page"),
+        ActionInfo::new("win.go-back", "Go Back").with_accels(&["<Alt>Left"]),
+        ActionInfo::new("win.go-forward", "Go Forward").with_accels(&["<Alt>Right"]),
+        ActionInfo::new("win.link-hints", "Follow Link by Label…"),
+        ActionInfo::new("win.next-block", "Next Text Block"),
+        ActionInfo::new("win.previous-block", "Previous Text Block"),
+        ActionInfo::new("win.caret-navigation", "Caret Navigation").with_accels(&["F7"]),
+    ],
+    &[
+        ActionInfo::new("win.autoscroll", "Auto-Scroll"),
+        ActionInfo::new("win.pin-position", "Pin Position"),
+        ActionInfo::new("win.copy", "Copy Selected Text").with_accels(&["<Control>c"]),
+        ActionInfo::new("win.copy-position-link", "Copy Link to Position"),
+        ActionInfo::new("win.eyedropper", "Pick Color").with_accels(&["<Control><Shift>c"]),
+    ],
+    &[
+        ActionInfo::new("win.stamp-date", "Stamp Date"),
+        ActionInfo::new("win.stamp-approved", "Stamp “Approved”"),
+        ActionInfo::new("win.stamp-image", "Stamp Image…"),
+        ActionInfo::new("win.highlight", "Highlight Selected Text")
+            .with_accels(&["<Control><Shift>h"]),
+        ActionInfo::new("win.underline", "Underline Selected Text"),
+        ActionInfo::new("win.strike-out", "Strike Out Selected Text"),
+        ActionInfo::new("win.pen", "Draw With Pen").with_accels(&["<Control><Shift>e"]),
+    ],
+    &[
+        ActionInfo::new("win.debug-overlay", "Debug Overlay").with_accels(&["<Control><Shift>d"]),
+        ActionInfo::new("win.font-diagnostics", "Font Diagnostics"),
+    ],
+    &[ActionInfo::new("win.preferences", "Preferences").with_accels(&["<Control>comma"])],
+    &[ActionInfo::new(COMMAND_PALETTE, "Command Palette…").with_accels(&["<Control><Shift>p"])],
+];
+
+/// Iterate over all registered actions.
+pub fn all() -> impl Iterator<Item = &'static ActionInfo> {
+    SECTIONS.iter().flat_map(|section| section.iter())
+}
+
+/// Register the keyboard accelerators of all actions with the application.
+pub fn set_accels(app: &impl IsA<gtk::Application>) {
+    for action in all().filter(|a| !a.accels.is_empty()) {
+        app.set_accels_for_action(action.name, action.accels);
+    }
+}
+
+/// Build the application menu.
+pub fn menu_model() -> gio::Menu {
+    let menu = gio::Menu::new();
+
+    for actions in SECTIONS {
+        let section = gio::Menu::new();
+
+        for action in actions.iter() {
+            section.append(Some(action.label), Some(action.name));
+        }
+
+        menu.append_section(None, &section);
+    }
+
+    menu
+}
@@ -1,23 +1,140 @@
+use std::cell::RefCell;
+use std::time::Duration;
+
 use adw::subclass::prelude::AdwApplicationImpl;
 use gtk::{
     gio, glib,
-    prelude::{Cast, StaticType},
+    glib::clone,
+    glib::once_cell::unsync::OnceCell,
+    prelude::{ApplicationCommandLineExt, ApplicationExt, Cast, FileExt, StaticType},
     subclass::prelude::{
         ApplicationImpl, ApplicationImplExt, GtkApplicationImpl, ObjectImpl, ObjectSubclass,
         ObjectSubclassExt,
     },
-    traits::{GtkApplicationExt, WidgetExt},
+    traits::{GtkApplicationExt, GtkWindowExt, WidgetExt},
+};
+
+use crate::core::cli::Options;
+use crate::core::exec::Executor;
+use crate::core::position::{self, PositionLink};
+use crate::core::recovery::RecoveryStore;
+use crate::core::session::SessionStore;
+use crate::core::storage::Storage;
+use crate::ui::{
+    actions, appwindow::AppWindow, canvas::CanvasWidget, document::DocumentView,
+    outline::OutlineView, scrollmarks::ScrollMarks, thumbnails::ThumbnailsView,
+    viewport::ViewportWidget,
 };
 
-use crate::ui::{appwindow::AppWindow, canvas::CanvasWidget, viewport::ViewportWidget};
+#[derive(Default)]
+pub struct App {
+    storage: OnceCell<Storage>,
+    session: OnceCell<SessionStore>,
+    executor: OnceCell<Executor>,
+
+    // record of open documents for restoring them after a crash, pending
+    // update of that record, and positions left open by a crashed run
+    recovery: OnceCell<RecoveryStore>,
+    recovery_timer: RefCell<Option<glib::SourceId>>,
+    restorable: RefCell<Vec<PositionLink>>,
+}
 
-#[derive(Debug, Default)]
-pub struct App {}
+/// Delay for saving the positions of open documents for recovery, coalescing
+/// all changes in the meantime.
+const RECOVERY_SAVE_DELAY: Duration = Duration::from_secs(1);
 
 impl App {
     fn new_appwindow(&self) -> AppWindow {
         AppWindow::new(self.obj().upcast_ref::<adw::Application>())
     }
+
+    /// The active window, or a new one if there is none.
+    fn active_appwindow(&self) -> AppWindow {
+        if let Some(window) = self.obj().active_window() {
+            return window.downcast().unwrap();
+        }
+
+        let window = self.new_appwindow();
+        window.set_visible(true);
+        self.offer_restore(&window);
+        window
+    }
+
+    /// Open the given position link, passed as URI.
+    fn open_link(&self, window: &AppWindow, file: &gio::File) {
+        match PositionLink::parse(&file.uri()) {
+            Some(link) => window.open_position(link),
+            None => tracing::warn!(uri=%file.uri(), "invalid position link"),
+        }
+    }
+
+    pub fn storage(&self) -> &Storage {
+        self.storage.get_or_init(Storage::from_env)
+    }
+
+    pub fn session(&self) -> SessionStore {
+        self.session
+            .get_or_init(|| SessionStore::load(self.storage()))
+            .clone()
+    }
+
+    pub fn executor(&self) -> &Executor {
+        self.executor.get_or_init(|| Executor::new(1))
+    }
+
+    fn recovery(&self) -> &RecoveryStore {
+        self.recovery
+            .get_or_init(|| RecoveryStore::new(self.storage()))
+    }
+
+    /// Save the positions of all open documents for recovery after a crash
+    /// shortly, together with all other changes until then.
+    pub fn schedule_recovery_save(&self) {
+        if self.recovery_timer.borrow().is_some() {
+            return;
+        }
+
+        let timer = glib::timeout_add_local_once(
+            RECOVERY_SAVE_DELAY,
+            clone!(@weak self as app => move || {
+                app.recovery_timer.replace(None);
+                app.save_recovery();
+            }),
+        );
+
+        self.recovery_timer.replace(Some(timer));
+    }
+
+    fn save_recovery(&self) {
+        let positions: Vec<_> = self
+            .obj()
+            .windows()
+            .into_iter()
+            .filter_map(|window| window.downcast::<AppWindow>().ok())
+            .flat_map(|window| window.open_positions())
+            .collect();
+
+        if let Err(err) = self.recovery().save(&positions) {
+            tracing::warn!(error=%err, "failed to save recovery record");
+        }
+    }
+
+    /// Offer restoring the documents left open by a crashed run in the given
+    /// window. This is offered only once.
+    fn offer_restore(&self, window: &AppWindow) {
+        let positions = self.restorable.take();
+
+        if !positions.is_empty() {
+            window.offer_restore(positions);
+        }
+    }
+
+    /// Open the given positions in tabs of the given window.
+    pub fn restore(&self, window: &AppWindow, positions: Vec<PositionLink>) {
+        for link in positions {
+            window.open_position(link);
+        }
+    }
 }
 
 #[glib::object_subclass]
@@ -38,31 +155,102 @@ impl ApplicationImpl for App {
         // register custom widgets
         AppWindow::static_type();
         CanvasWidget::static_type();
+        DocumentView::static_type();
+        OutlineView::static_type();
+        ScrollMarks::static_type();
+        ThumbnailsView::static_type();
         ViewportWidget::static_type();
+
+        // register keyboard shortcuts
+        actions::set_accels(&*self.obj());
+
+        // mark us as running, remembering what was open if the previous run
+        // crashed, and keep track of closed windows
+        self.restorable.replace(self.recovery().start());
+
+        self.obj().connect_window_removed(|app, _| {
+            app.schedule_recovery_save();
+        });
+    }
+
+    fn shutdown(&self) {
+        if let Some(timer) = self.recovery_timer.take() {
+            timer.remove();
+        }
+
+        if let Some(recovery) = self.recovery.get() {
+            recovery.finish();
+        }
+
+        if let Some(session) = self.session.get() {
+            if let Err(err) = session.save() {
+                tracing::warn!(error=%err, "failed to save session state");
+            }
+        }
+
+        self.parent_shutdown();
     }
 
     fn activate(&self) {
         self.parent_activate();
-        self.new_appwindow().set_visible(true);
+
+        let window = self.new_appwindow();
+        window.set_visible(true);
+        self.offer_restore(&window);
     }
 
     fn open(&self, files: &[gio::File], hint: &str) {
         self.parent_open(files, hint);
 
-        // get active window or create new one if we don't have one
-        let window = if let Some(window) = self.obj().active_window() {
-            window.downcast().unwrap()
-        } else {
-            let window = self.new_appwindow();
-            window.set_visible(true);
-            window
+        let window = self.active_appwindow();
+
+        // open files in tabs, position links are passed as URIs
+        for file in files.iter().cloned() {
+            if file.has_uri_scheme(position::SCHEME) {
+                self.open_link(&window, &file);
+            } else {
+                window.open_file(file);
+            }
+        }
+    }
+
+    fn command_line(&self, cmd: &gio::ApplicationCommandLine) -> glib::ExitCode {
+        // options have already been checked by the invoking instance, see
+        // main(), so failing here means they have been forwarded by another
+        // program
+        let opts = match Options::parse(cmd.arguments().into_iter().skip(1)) {
+            Ok(opts) => opts,
+            Err(err) => {
+                tracing::error!(error=%err, "invalid command line");
+                return glib::ExitCode::FAILURE;
+            }
         };
 
-        // open file, if we have one
-        let file = files.first().cloned();
-        if let Some(file) = file {
-            window.open_file(file);
+        if opts.files.is_empty() {
+            self.obj().activate();
+        } else {
+            let window = self.active_appwindow();
+
+            // open files in tabs, relative to the directory of the invoking
+            // instance, going to the requested page and zoom
+            for arg in &opts.files {
+                let file = cmd.create_file_for_arg(arg);
+
+                if file.has_uri_scheme(position::SCHEME) {
+                    self.open_link(&window, &file);
+                } else {
+                    window.open_file_with(file, opts.page, opts.zoom);
+                }
+            }
         }
+
+        if opts.fullscreen {
+            if let Some(window) = self.obj().active_window() {
+                window.fullscreen();
+            }
+        }
+
+        glib::ExitCode::SUCCESS
     }
 }
 
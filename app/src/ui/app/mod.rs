@@ -1,5 +1,12 @@
+use gtk::subclass::prelude::ObjectSubclassIsExt;
 use gtk::{gio, glib};
 
+use crate::core::exec::{Executor, JoinHandle, TaskPriority};
+use crate::core::position::PositionLink;
+use crate::core::session::SessionStore;
+use crate::core::storage::Storage;
+use crate::ui::appwindow::AppWindow;
+
 mod imp;
 
 glib::wrapper! {
@@ -16,11 +23,54 @@ impl Default for App {
 
 impl App {
     pub fn new() -> Self {
-        let flags = gio::ApplicationFlags::HANDLES_OPEN;
+        let flags =
+            gio::ApplicationFlags::HANDLES_OPEN | gio::ApplicationFlags::HANDLES_COMMAND_LINE;
 
         glib::Object::builder()
             .property("application-id", "io.mxnluz.Paper")
             .property("flags", flags)
             .build()
     }
+
+    pub fn storage(&self) -> Storage {
+        self.imp().storage().clone()
+    }
+
+    pub fn session(&self) -> SessionStore {
+        self.imp().session()
+    }
+
+    /// Save the positions of all open documents for recovery after a crash,
+    /// coalescing frequent changes.
+    pub fn schedule_recovery_save(&self) {
+        self.imp().schedule_recovery_save()
+    }
+
+    /// Open the given positions left open by a crashed run in tabs of the
+    /// given window.
+    pub fn restore(&self, window: &AppWindow, positions: Vec<PositionLink>) {
+        self.imp().restore(window, positions)
+    }
+
+    /// The thread pool for background work.
+    pub fn executor(&self) -> &Executor {
+        self.imp().executor()
+    }
+
+    /// Run the given blocking closure on the background thread pool and
+    /// return a future resolving to its result, e.g. for awaiting it from the
+    /// main loop.
+    pub fn spawn_blocking_with_priority<F, R>(
+        &self,
+        priority: TaskPriority,
+        closure: F,
+    ) -> JoinHandle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        self.imp()
+            .executor()
+            .spawn_blocking_with_priority(priority, closure)
+    }
 }
@@ -1,20 +1,83 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
+use adw::prelude::{ActionRowExt, ExpanderRowExt, MessageDialogExt, MessageDialogExtManual};
 use adw::subclass::prelude::AdwApplicationWindowImpl;
+use executor::exec::Monitor;
 use gtk::gio::{File, ListStore, SimpleAction};
 use gtk::glib::clone;
 use gtk::glib::subclass::InitializingObject;
-use gtk::prelude::{ActionMapExt, FileExt, StaticType};
+use gtk::prelude::{
+    ActionExt, ActionMapExt, Cast, CastNone, DisplayExt, FileExt, ListModelExt, ObjectExt,
+    StaticType, TextBufferExtManual, ToVariant, ToplevelExt,
+};
 use gtk::subclass::prelude::{
     ApplicationWindowImpl, CompositeTemplateClass, CompositeTemplateInitializingExt, ObjectImpl,
-    ObjectImplExt, ObjectSubclass, ObjectSubclassExt, WidgetImpl, WindowImpl,
+    ObjectImplExt, ObjectSubclass, ObjectSubclassExt, ObjectSubclassIsExt, WidgetImpl, WindowImpl,
+    WindowImplExt,
 };
 use gtk::subclass::widget::WidgetClassSubclassExt;
-use gtk::traits::GtkWindowExt;
-use gtk::{glib, CompositeTemplate, FileDialog, FileFilter, TemplateChild};
-use nalgebra::vector;
+use gtk::traits::{
+    AdjustmentExt, BoxExt, ButtonExt, EditableExt, EntryExt, EventControllerExt, GestureExt,
+    GestureSingleExt, GridExt, GtkApplicationExt, GtkWindowExt, NativeExt, PrintOperationExt,
+    ScrollableExt, TextBufferExt, TextTagExt, TextViewExt, WidgetExt,
+};
+use gtk::{gdk, glib, CompositeTemplate, FileDialog, FileFilter, TemplateChild};
+use nalgebra::{point, vector};
+use pdfium::bitmap::Color;
+use pdfium::doc::{Bookmark, Document, PageRotation};
+use pdfium::Capabilities;
 
+use crate::core::accessible;
+use crate::core::appearance::{NightLight, PageStyle};
+use crate::core::blocks::{self, Step};
+use crate::core::caret::{Caret, CaretMarks, CaretText, Motion};
+use crate::core::citations::{self, Location};
+use crate::core::cli::Zoom;
+use crate::core::columns::{self, TextColumn};
+use crate::core::exec::TaskPriority;
+use crate::core::export;
+use crate::core::fonts::FontReport;
+use crate::core::history::History;
+use crate::core::ink::Pen;
+use crate::core::markup::MarkupKind;
+use crate::core::pin::{Anchor, Pin};
+use crate::core::position::PositionLink;
+use crate::core::power::PowerMonitor;
+use crate::core::progress::ReadingProgress;
+use crate::core::reflow;
+use crate::core::render::disk::DiskCache;
+use crate::core::render::layout::LayoutKind;
+use crate::core::render::print::{self, PrintScaling};
+use crate::core::render::rotation;
+use crate::core::search::{Search, SearchMatch};
+use crate::core::session::{DocumentState, SessionStore};
+use crate::core::signatures::{self, Signatures};
+use crate::core::stamps::StampContent;
+use crate::core::stats::{self, DocumentStats};
+use crate::core::textlayer;
+use crate::core::timeline::ViewingTimeline;
+use crate::core::trim;
+use crate::core::trust::ExternalAction;
+use crate::core::watch::FileWatch;
+use crate::core::xfdf;
+use crate::types::Rect;
+use crate::ui::actions;
+use crate::ui::app::App;
+use crate::ui::calibration::{self, CalibrationDialog};
 use crate::ui::canvas::CanvasWidget;
+use crate::ui::document::{DocumentView, TabState};
+use crate::ui::inspect::InspectWindow;
+use crate::ui::outline::OutlineView;
+use crate::ui::palette::CommandPalette;
+use crate::ui::preferences::PreferencesWindow;
+use crate::ui::properties::PropertiesDialog;
+use crate::ui::scrollmarks::{HeatRegion, ScrollMark, ScrollMarkKind};
+use crate::ui::statistics::StatisticsDialog;
+use crate::ui::thumbnails::ThumbnailsView;
 use crate::ui::viewport::ViewportWidget;
 
 #[derive(CompositeTemplate, Default)]
@@ -24,24 +87,189 @@ pub struct AppWindow {
     overlay: TemplateChild<adw::ToastOverlay>,
 
     #[template_child]
-    viewport: TemplateChild<ViewportWidget>,
+    header_bar: TemplateChild<adw::HeaderBar>,
+
+    #[template_child]
+    tab_bar: TemplateChild<adw::TabBar>,
+
+    #[template_child]
+    flap: TemplateChild<adw::Flap>,
+
+    #[template_child]
+    sidebar: TemplateChild<gtk::Stack>,
+
+    #[template_child]
+    outline_view: TemplateChild<OutlineView>,
+
+    #[template_child]
+    thumbnails_view: TemplateChild<ThumbnailsView>,
+
+    #[template_child]
+    content_stack: TemplateChild<gtk::Stack>,
+
+    #[template_child]
+    recent_list: TemplateChild<gtk::ListBox>,
 
     #[template_child]
-    canvas: TemplateChild<CanvasWidget>,
+    tab_view: TemplateChild<adw::TabView>,
 
     #[template_child]
     window_title: TemplateChild<adw::WindowTitle>,
 
+    #[template_child]
+    popovermenu: TemplateChild<gtk::PopoverMenu>,
+
+    #[template_child]
+    markup_color: TemplateChild<gtk::ColorDialogButton>,
+
+    #[template_child]
+    pin_badge: TemplateChild<gtk::Label>,
+
+    #[template_child]
+    page_nav: TemplateChild<gtk::Box>,
+
+    #[template_child]
+    page_entry: TemplateChild<gtk::Entry>,
+
+    #[template_child]
+    page_count_label: TemplateChild<gtk::Label>,
+
+    #[template_child]
+    signature_banner: TemplateChild<gtk::Revealer>,
+
+    #[template_child]
+    signature_label: TemplateChild<gtk::Label>,
+
+    #[template_child]
+    signature_dismiss: TemplateChild<gtk::Button>,
+
+    #[template_child]
+    search_bar: TemplateChild<gtk::SearchBar>,
+
+    #[template_child]
+    search_entry: TemplateChild<gtk::SearchEntry>,
+
+    #[template_child]
+    search_label: TemplateChild<gtk::Label>,
+
+    #[template_child]
+    search_results: TemplateChild<gtk::ListBox>,
+
+    #[template_child]
+    footer: TemplateChild<gtk::Box>,
+
+    #[template_child]
+    progress_bar: TemplateChild<gtk::ProgressBar>,
+
+    #[template_child]
+    progress_label: TemplateChild<gtk::Label>,
+
+    #[template_child]
+    reader_view: TemplateChild<gtk::TextView>,
+
+    #[template_child]
+    reader_font_size: TemplateChild<gtk::SpinButton>,
+
     pdflib: RefCell<Option<pdfium::Library>>,
+
+    // view of the selected tab, or an empty one if there are no tabs, the
+    // state below is kept with the view while its tab is not selected
+    view: RefCell<DocumentView>,
+
+    // current document
+    path: RefCell<Option<PathBuf>>,
+    labels: RefCell<Vec<Option<String>>>,
+
+    // outline of the current document
+    outline: RefCell<Vec<Bookmark>>,
+
+    // main text column of the current document, if detected
+    text_column: Cell<Option<TextColumn>>,
+
+    // whether page margins of the current document are trimmed, and the
+    // content boxes of its pages, if detected
+    trim_margins: Cell<bool>,
+    content_boxes: RefCell<Option<Vec<Option<Rect<f64>>>>>,
+
+    // whether documents are shown as reflowed text instead of their pages
+    reader_mode: Cell<bool>,
+
+    // window state to restore once the presentation of the current document
+    // ends, if presenting, and the timer advancing pages automatically
+    presentation: Cell<Option<PresentationState>>,
+    presentation_timer: RefCell<Option<glib::SourceId>>,
+
+    // whether the current document may run external actions, if decided
+    trusted: Cell<Option<bool>>,
+
+    // whether pages of the current document are shown with dark colors, and
+    // the night light state used for deciding so automatically
+    page_style: Cell<PageStyle>,
+    night_light: RefCell<Option<NightLight>>,
+
+    // power state, used to throttle background rendering on battery
+    power: RefCell<Option<PowerMonitor>>,
+
+    // text blocks in reading order per page, computed on demand, and the
+    // page and index of the current block
+    blocks: RefCell<HashMap<usize, Rc<Vec<Rect<f64>>>>>,
+    block_cursor: Cell<Option<(usize, usize)>>,
+
+    // page for which substituted fonts are shown, if enabled
+    font_page: Cell<Option<usize>>,
+
+    // page whose text is exposed to assistive technologies via the canvas
+    accessible_page: Cell<Option<usize>>,
+
+    // text of pages for caret navigation, loaded on demand, and the caret
+    caret_texts: RefCell<HashMap<usize, Rc<CaretText>>>,
+    caret: Cell<Option<Caret>>,
+
+    // current search and the result being shown, given by its page and its
+    // index among the results on that page
+    search: RefCell<Option<Search>>,
+    search_current: Cell<Option<(usize, usize)>>,
+
+    // search through all open documents, listed per document in the sidebar
+    search_all: Cell<bool>,
+    global_search: RefCell<Vec<(DocumentView, Search)>>,
+
+    // pinned position, re-resolved when the same file is loaded again
+    pin: RefCell<Option<(PathBuf, Pin)>>,
+
+    // content hash of the current document and statistics of documents
+    // computed so far, by content hash
+    doc_hash: Cell<Option<u64>>,
+    stats: RefCell<HashMap<u64, Rc<DocumentStats>>>,
+
+    // disk cache for renders of the current document, used if enabled
+    disk_cache: RefCell<Option<DiskCache>>,
+
+    // print settings and page scaling last used, kept for the next print
+    print_settings: RefCell<Option<gtk::PrintSettings>>,
+    print_scaling: Cell<PrintScaling>,
+
+    // reading progress of the current document
+    reading: RefCell<Option<ReadingProgress>>,
+
+    // time spent viewing each page of the current document
+    timeline: RefCell<Option<ViewingTimeline>>,
+
+    // navigation history for following links
+    history: RefCell<History<Location>>,
+
+    // horizontal distance scrolled in the current touchpad swipe, or `None`
+    // if the swipe has already flipped the page
+    swipe_dx: Cell<Option<f64>>,
 }
 
 impl AppWindow {
-    pub fn viewport(&self) -> &ViewportWidget {
-        &self.viewport
+    pub fn viewport(&self) -> ViewportWidget {
+        self.view.borrow().viewport()
     }
 
-    pub fn canvas(&self) -> &CanvasWidget {
-        &self.canvas
+    pub fn canvas(&self) -> CanvasWidget {
+        self.view.borrow().canvas()
     }
 
     fn pdflib(&self) -> Result<pdfium::Library, pdfium::Error> {
@@ -63,6 +291,7 @@ impl AppWindow {
                 tracing::debug!("libpdfium loaded successfully");
 
                 *self.pdflib.borrow_mut() = Some(lib.clone());
+                self.check_capabilities(&lib);
                 lib
             }
         };
@@ -70,59 +299,261 @@ impl AppWindow {
         Ok(lib)
     }
 
-    pub fn open_file(&self, file: File) {
-        glib::MainContext::default().spawn_local(clone!(@weak self as win => async move {
-            let path = file.path().unwrap_or_default();
+    /// Disable features that are not supported by the installed pdfium
+    /// library and tell the user about it.
+    fn check_capabilities(&self, lib: &pdfium::Library) {
+        // only report what this build makes use of
+        let missing = Capabilities::enabled() - lib.capabilities();
+        if missing.is_empty() {
+            return;
+        }
 
-            tracing::info!(file=?path, "loading file");
+        tracing::warn!(?missing, "libpdfium lacks optional features");
 
-            // load file to buffer
-            let result = file.load_bytes_future().await;
-            let (data, _etag) = match result {
-                Ok(res) => res,
-                Err(err) => {
-                    tracing::warn!(file=?path, error=?err.message(), "failed to load file");
+        for name in [
+            "zoom-fit-text",
+            "export-text-layer",
+            "caret-navigation",
+            "copy",
+            "search",
+            "search-next",
+            "search-previous",
+            "search-all-documents",
+        ] {
+            if let Some(action) = self.obj().lookup_action(name) {
+                if let Ok(action) = action.downcast::<SimpleAction>() {
+                    action.set_enabled(lib.capabilities().contains(Capabilities::Text));
+                }
+            }
+        }
 
-                    let toast = adw::Toast::new(&format!("{err}"));
-                    toast.set_priority(adw::ToastPriority::High);
-                    win.overlay.add_toast(toast);
-                    return;
-                },
-            };
+        for name in ["highlight", "underline", "strike-out"] {
+            if let Some(action) = self.obj().lookup_action(name) {
+                if let Ok(action) = action.downcast::<SimpleAction>() {
+                    let required = Capabilities::Text | Capabilities::Annotations;
+                    action.set_enabled(lib.capabilities().contains(required));
+                }
+            }
+        }
+
+        for name in ["annotations-export", "annotations-import"] {
+            if let Some(action) = self.obj().lookup_action(name) {
+                if let Ok(action) = action.downcast::<SimpleAction>() {
+                    action.set_enabled(lib.capabilities().contains(Capabilities::Annotations));
+                }
+            }
+        }
+
+        if let Some(action) = self.obj().lookup_action("font-diagnostics") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_enabled(lib.capabilities().contains(Capabilities::Fonts));
+            }
+        }
 
-            let data = data.to_vec();
+        let names: Vec<_> = missing
+            .iter()
+            .map(|cap| match cap {
+                Capabilities::Text => "text extraction",
+                Capabilities::Annotations => "annotations",
+                Capabilities::Signatures => "digital signatures",
+                Capabilities::JavaScript => "script inspection",
+                Capabilities::Fonts => "font inspection",
+                _ => "other features",
+            })
+            .collect();
 
-            // load pdfium library
-            let pdflib = match win.pdflib() {
-                Ok(pdflib) => pdflib,
-                Err(_) => {
-                    let dialog = gtk::AlertDialog::builder()
-                        .message("Error loading pdfium")
-                        .detail(
-                            "Failed to load shared libraries for pdfium. \
-                            Please ensure that the pdfium library is installed."
-                        )
-                        .build();
+        let toast = adw::Toast::new(&format!(
+            "The installed libpdfium does not support {}. Related features are unavailable.",
+            names.join(", ")
+        ));
+        toast.set_priority(adw::ToastPriority::High);
+        self.overlay.add_toast(toast);
+    }
 
-                    let _ = dialog.choose_future(Some(&*win.obj())).await;
+    /// Load and parse the given file. Returns the document and the hash of
+    /// the file content.
+    async fn load_document(&self, file: &File) -> Option<(Document, u64)> {
+        let path = file.path().unwrap_or_default();
 
-                    win.obj().destroy();
-                    return;
+        // ask for a password and retry if the document requires one
+        let mut password = None;
+
+        loop {
+            match self.load_document_with_password(file, password).await? {
+                Ok(result) => return Some(result),
+                Err(retry) => password = Some(self.ask_password(&path, retry).await?),
+            }
+        }
+    }
+
+    /// Load the given document with the given password. Returns an error
+    /// with whether a password has been given if a correct one is required.
+    async fn load_document_with_password(
+        &self,
+        file: &File,
+        password: Option<String>,
+    ) -> Option<Result<(Document, u64), bool>> {
+        let path = file.path().unwrap_or_default();
+
+        tracing::info!(file=?path, "loading file");
+
+        // load file to buffer
+        let result = file.load_bytes_future().await;
+        let (data, _etag) = match result {
+            Ok(res) => res,
+            Err(err) => {
+                tracing::warn!(file=?path, error=?err.message(), "failed to load file");
+
+                let toast = adw::Toast::new(&format!("{err}"));
+                toast.set_priority(adw::ToastPriority::High);
+                self.overlay.add_toast(toast);
+                return None;
+            }
+        };
+
+        let data = data.to_vec();
+
+        // load pdfium library
+        let pdflib = match self.pdflib() {
+            Ok(pdflib) => pdflib,
+            Err(_) => {
+                let dialog = gtk::AlertDialog::builder()
+                    .message("Error loading pdfium")
+                    .detail(
+                        "Failed to load shared libraries for pdfium. \
+                        Please ensure that the pdfium library is installed.",
+                    )
+                    .build();
+
+                let _ = dialog.choose_future(Some(&*self.obj())).await;
+
+                self.obj().destroy();
+                return None;
+            }
+        };
+
+        // parse document in the background, fall back to parsing it here if we
+        // are not attached to an application (yet)
+        let app = self
+            .obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok());
+
+        let retry = password.is_some();
+        let load = move || {
+            let hash = stats::content_hash(&data);
+            pdflib
+                .load_buffer(data, password.as_deref())
+                .map(|doc| (doc, hash))
+        };
+
+        let result = match app {
+            Some(app) => {
+                app.spawn_blocking_with_priority(TaskPriority::High, load)
+                    .await
+            }
+            None => load(),
+        };
+
+        match result {
+            Ok(result) => Some(Ok(result)),
+            Err(pdfium::Error::ErrorCode(pdfium::ErrorCode::Password)) => {
+                tracing::info!(file=?path, retry, "document requires a password");
+                Some(Err(retry))
+            }
+            Err(err) => {
+                tracing::warn!(file=?path, error=%err, "failed to parse document");
+
+                let toast = adw::Toast::new(&format!("Error: {err}"));
+                toast.set_priority(adw::ToastPriority::High);
+                self.overlay.add_toast(toast);
+                None
+            }
+        }
+    }
+
+    /// Ask for the password of the given document, telling that the previous
+    /// one was incorrect if retrying. Returns `None` if canceled.
+    async fn ask_password(&self, path: &Path, retry: bool) -> Option<String> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        let body = if retry {
+            format!("The password for “{name}” is incorrect, please try again.")
+        } else {
+            format!("“{name}” is protected with a password.")
+        };
+
+        let dialog =
+            adw::MessageDialog::new(Some(&*self.obj()), Some("Password Required"), Some(&body));
+
+        let entry = gtk::PasswordEntry::builder()
+            .show_peek_icon(true)
+            .activates_default(true)
+            .build();
+
+        dialog.set_extra_child(Some(&entry));
+        dialog.add_responses(&[("cancel", "Cancel"), ("unlock", "Unlock")]);
+        dialog.set_response_appearance("unlock", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("unlock"));
+        dialog.set_close_response("cancel");
+
+        let response = gtk::gio::GioFuture::new(&dialog, |dialog, _, result| {
+            let result = RefCell::new(Some(result));
+
+            dialog.connect_response(None, move |_, response| {
+                if let Some(result) = result.take() {
+                    result.resolve(response.to_owned());
                 }
-            };
+            });
 
-            // parse document
-            let result = pdflib.load_buffer(data, None);
-            let doc = match result {
-                Ok(doc) => doc,
-                Err(err) => {
-                    tracing::warn!(file=?path, error=%err, "failed to parse document");
+            dialog.present();
+        })
+        .await;
 
-                    let toast = adw::Toast::new(&format!("Error: {err}"));
-                    toast.set_priority(adw::ToastPriority::High);
-                    win.overlay.add_toast(toast);
-                    return;
-                },
+        (response == "unlock").then(|| entry.text().to_string())
+    }
+
+    pub fn open_file(&self, file: File) {
+        self.open_file_at(file, None, false)
+    }
+
+    /// Open the given file at the given page and zoom, e.g. as requested on
+    /// the command line, instead of the position it has last been viewed at.
+    pub fn open_file_with(&self, file: File, page: Option<usize>, zoom: Option<Zoom>) {
+        self.open_file_at(file, Some(OpenTarget::View { page, zoom }), false)
+    }
+
+    /// Open the file of the given position link and go to its position. If
+    /// the file is already open, only select its tab and go to the position.
+    pub fn open_position(&self, link: PositionLink) {
+        let page = match self.find_tab(&link.file) {
+            Some(page) => page,
+            None => {
+                let file = File::for_path(&link.file);
+                return self.open_file_at(file, Some(OpenTarget::Position(link)), false);
+            }
+        };
+
+        self.tab_view.set_selected_page(&page);
+
+        if let Some(current) = self.current_location() {
+            self.history.borrow_mut().push(current);
+            self.update_history_actions();
+        }
+
+        self.go_to_position(&link);
+    }
+
+    /// Load the given file and show it, going to the given target if any.
+    /// Documents that are reloaded after having been changed keep the current
+    /// view instead of restoring the position they have last been viewed at.
+    fn open_file_at(&self, file: File, target: Option<OpenTarget>, reload: bool) {
+        glib::MainContext::default().spawn_local(clone!(@weak self as win => async move {
+            let path = file.path().unwrap_or_default();
+
+            let (doc, hash) = match win.load_document(&file).await {
+                Some(result) => result,
+                None => return,
             };
 
             // get metadata for titlebar
@@ -135,94 +566,4781 @@ impl AppWindow {
                 .unwrap_or_default()
                 .to_string_lossy();
 
+            // show the document in its tab if it is already open, e.g. when
+            // reloading it after it has been changed, or in a new one
+            let page = match win.find_tab(&path) {
+                Some(page) => {
+                    win.tab_view.set_selected_page(&page);
+                    page
+                }
+                None => win.add_tab(),
+            };
+
+            page.set_title(&filename);
+            page.set_tooltip(&glib::markup_escape_text(&title));
+
             win.window_title.set_title(&title);
             win.window_title.set_subtitle(&filename);
 
-            // update canvas
-            win.canvas().set_document(doc);
-            win.viewport().set_offset_and_scale(vector![0.0, 0.0], 1.0);
-            win.viewport().fit_width();
+            // get page labels for resolving pins
+            let labels = (0..doc.pages().count())
+                .map(|i| doc.pages().get_label(i).ok().flatten())
+                .collect();
+
+            // store state of the document being reloaded, if any
+            win.save_reading_progress();
+
+            let state = win.session()
+                .map(|session| session.document(&path))
+                .unwrap_or_default();
+
+            win.record_opened(&path);
+
+            win.set_trusted(state.trusted);
+            win.set_page_style(state.page_style.unwrap_or_default());
+
+            let reading = ReadingProgress::new(
+                doc.pages().count() as _,
+                state.progress,
+                state.reading_speed,
+            );
+            let timeline = ViewingTimeline::new(doc.pages().count() as _, state.page_times.clone());
+
+            // get outline for chapter marks
+            let outline = doc.outline().unwrap_or_else(|err| {
+                tracing::warn!(file=?path, error=%err, "failed to load document outline");
+                Vec::new()
+            });
+
+            win.path.replace(Some(path.clone()));
+            win.watch_file(&path);
+            win.doc_hash.set(Some(hash));
+            win.labels.replace(labels);
+            win.outline.replace(outline);
+            win.reading.replace(Some(reading));
+            win.timeline.replace(Some(timeline));
+
+            win.text_column.set(None);
+            win.content_boxes.replace(None);
+            win.trim_margins.set(state.trim_margins.unwrap_or(false));
+            win.blocks.borrow_mut().clear();
+            win.block_cursor.set(None);
+            win.font_page.set(None);
+            win.accessible_page.set(None);
+            win.caret_texts.borrow_mut().clear();
+            win.caret.set(None);
+
+            win.history.borrow_mut().clear();
+            win.update_history_actions();
+
+            win.signature_banner.set_reveal_child(false);
+            win.detect_signatures(&path, doc.clone());
+
+            // analyze document text in the background
+            if doc.library().capabilities().contains(Capabilities::Text) {
+                win.synthesize_links(&path, doc.clone());
+                win.detect_text_column(&path, doc.clone());
+            }
+
+            // update canvas, restoring the layout the document has last been
+            // viewed with unless reloading it
+            let size_issues = if reload {
+                win.canvas().swap_document(doc.clone())
+            } else {
+                let layout = state.layout
+                    .or_else(|| win.session().map(|session| session.page_layout()))
+                    .unwrap_or_default();
+                win.set_page_layout(layout);
+
+                win.set_single_page(false);
+                win.canvas().set_document(doc.clone())
+            };
+
+            win.set_trim_margins(win.trim_margins.get());
+
+            if !reload {
+                win.viewport().set_offset_and_scale(vector![0.0, 0.0], 1.0);
+                win.viewport().fit_width();
+            }
+            win.update_scroll_marks();
+            win.update_scroll_heat();
+            win.outline_view.set_outline(&win.outline.borrow());
+
+            let page_labels: Vec<_> = (0..doc.pages().count() as usize)
+                .map(|page| win.page_label(page))
+                .collect();
+            win.thumbnails_view.set_document(Some(&doc), &page_labels);
+            win.update_current_thumbnail();
+            win.update_page_indicator();
+            win.open_disk_cache(hash);
+
+            // restore pinned position if we have re-loaded the same file,
+            // either manually or after it has been changed on disk
+            let pin = win.pin.borrow()
+                .as_ref()
+                .filter(|(pin_path, _)| *pin_path == path)
+                .map(|(_, pin)| pin.clone());
 
-            tracing::info!(file=?path, title, "file loaded");
+            // otherwise, restore the position the document has last been viewed at
+            match pin {
+                Some(pin) => win.restore_pin(&pin),
+                None if reload => {}
+                None => {
+                    win.pin_badge.set_visible(false);
+                    win.restore_last_position(&path, &state);
+                }
+            }
+
+            // go to the requested position, taking precedence over the pin
+            match target {
+                Some(OpenTarget::Position(link)) => win.go_to_position(&link),
+                Some(OpenTarget::View { page, zoom }) => win.go_to_view(page, zoom),
+                None => {}
+            }
+
+            // search the new document for the current query
+            if win.search_bar.is_search_mode() {
+                win.start_search();
+            } else {
+                win.stop_search();
+            }
+            win.start_global_search();
+
+            win.update_content_page();
+            win.footer.set_visible(win.presentation.get().is_none());
+            win.page_nav.set_visible(true);
+            win.schedule_recovery_save();
+
+            tracing::info!(file=?path, title, reload, "file loaded");
 
             // notify user
-            let toast = adw::Toast::new(&format!("File loaded: \"{}\"", filename));
+            let action = if reload { "reloaded" } else { "loaded" };
+            let toast = adw::Toast::new(&format!("File {action}: \"{}\"", filename));
             win.overlay.add_toast(toast);
+
+            // warn about pages that cannot be shown at their actual size
+            if !size_issues.is_empty() {
+                let n = size_issues.len();
+                let pages = if n == 1 { "page" } else { "pages" };
+
+                let toast = adw::Toast::new(&format!(
+                    "{n} {pages} with invalid size, shown at corrected size"
+                ));
+                win.overlay.add_toast(toast);
+            }
         }));
     }
 
-    pub fn close_file(&self) {
-        self.canvas().clear();
-        self.window_title.set_title("PDF Annotator Prototype");
-        self.window_title.set_subtitle("No Document Selected");
-    }
-}
+    pub fn compare_file(&self, file: File) {
+        glib::MainContext::default().spawn_local(clone!(@weak self as win => async move {
+            let path = file.path().unwrap_or_default();
 
-#[glib::object_subclass]
-impl ObjectSubclass for AppWindow {
-    const NAME: &'static str = "AppWindow";
-    type Type = super::AppWindow;
-    type ParentType = adw::ApplicationWindow;
+            let doc = match win.load_document(&file).await {
+                Some((doc, _)) => doc,
+                None => return,
+            };
 
-    fn class_init(klass: &mut Self::Class) {
-        klass.bind_template();
-    }
+            win.canvas().set_compare_document(Some(doc));
 
-    fn instance_init(obj: &InitializingObject<Self>) {
-        obj.init_template();
-    }
-}
+            tracing::info!(file=?path, "comparing with file");
 
-impl ObjectImpl for AppWindow {
-    fn constructed(&self) {
-        self.parent_constructed();
+            let filename = path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy();
 
-        let action_doc_open = SimpleAction::new("document-open", None);
-        action_doc_open.connect_activate(clone!(@weak self as win => move |_, _| {
-            let filter_pdf = FileFilter::new();
-            filter_pdf.add_mime_type("application/pdf");
-            filter_pdf.add_suffix("pdf");
-            filter_pdf.set_name(Some("PDF Documents"));
-
-            let filter_all = FileFilter::new();
-            filter_all.set_name(Some("All Files"));
-            filter_all.add_mime_type("*/*");
-            filter_all.add_suffix("*");
-
-            let filters = ListStore::new(FileFilter::static_type());
-            filters.append(&filter_pdf);
-            filters.append(&filter_all);
-
-            let filechooser = FileDialog::builder()
-                .title("Open Document")
-                .modal(true)
-                .accept_label("Open")
-                .filters(&filters)
-                .default_filter(&filter_pdf)
-                .build();
+            // pages past the end of the current document cannot be shown,
+            // list them instead
+            let added = win.canvas().compare_added_pages();
 
-            filechooser.open(
-                Some(&*win.obj()),
-                None::<&gtk::gio::Cancellable>,
-                clone!(@weak win => move |result| {
-                    if let Ok(file) = result {
-                        win.open_file(file);
-                    }
-                }),
-            );
-        }));
+            let message = match added.len() {
+                0 => format!("Comparing with \"{}\"", filename),
+                1 => format!(
+                    "Comparing with \"{}\", whose page {} is past the end and not shown",
+                    filename,
+                    added.start + 1,
+                ),
+                _ => format!(
+                    "Comparing with \"{}\", whose pages {}–{} are past the end and not shown",
+                    filename,
+                    added.start + 1,
+                    added.end,
+                ),
+            };
 
-        let action_doc_close = SimpleAction::new("document-close", None);
-        action_doc_close.connect_activate(clone!(@weak self as win => move |_, _| {
-            win.close_file();
+            let toast = adw::Toast::new(&message);
+            win.overlay.add_toast(toast);
         }));
+    }
 
-        self.obj().add_action(&action_doc_open);
-        self.obj().add_action(&action_doc_close);
+    pub fn export_text_layer(&self, file: File) {
+        let path = match file.path() {
+            Some(path) => path,
+            None => return,
+        };
+
+        let doc = match self.canvas().document() {
+            Some(doc) => doc,
+            None => return,
+        };
+
+        let app = self
+            .obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok());
+
+        let app = match app {
+            Some(app) => app,
+            None => return,
+        };
+
+        let format = textlayer::Format::from_path(&path).unwrap_or(textlayer::Format::Json);
+
+        let target = path.clone();
+        let task = app.spawn_blocking_with_priority(TaskPriority::Normal, move || {
+            let mut out = std::io::BufWriter::new(std::fs::File::create(&target)?);
+            textlayer::export(&doc, format, &mut out)
+        });
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as win => async move {
+            let message = match task.await {
+                Ok(()) => {
+                    tracing::info!(file=?path, ?format, "text layer exported");
+                    format!("Text layer exported to \"{}\"", path.display())
+                }
+                Err(err) => {
+                    tracing::warn!(file=?path, error=%err, "failed to export text layer");
+                    format!("Error: {err}")
+                }
+            };
+
+            win.overlay.add_toast(adw::Toast::new(&message));
+        }));
     }
-}
 
-impl WidgetImpl for AppWindow {}
-impl WindowImpl for AppWindow {}
-impl ApplicationWindowImpl for AppWindow {}
-impl AdwApplicationWindowImpl for AppWindow {}
+    /// Ask which pages to export as images and at which resolution, then
+    /// for the file to export them to.
+    fn show_export_images_dialog(&self) {
+        let count = self.canvas().page_count();
+        if count == 0 {
+            return;
+        }
+
+        let current = match self.canvas().viewport_page_rect() {
+            Some((page, _)) => page,
+            None => 0,
+        };
+
+        let pages = gtk::Entry::builder()
+            .text((current + 1).to_string())
+            .activates_default(true)
+            .hexpand(true)
+            .build();
+
+        let dpi = gtk::SpinButton::with_range(export::MIN_DPI, export::MAX_DPI, 1.0);
+        dpi.set_value(export::DEFAULT_DPI);
+
+        let label = |text| gtk::Label::builder().label(text).xalign(0.0).build();
+
+        let grid = gtk::Grid::builder()
+            .row_spacing(6)
+            .column_spacing(12)
+            .build();
+
+        grid.attach(&label("Pages"), 0, 0, 1, 1);
+        grid.attach(&pages, 1, 0, 1, 1);
+        grid.attach(&label("Resolution (DPI)"), 0, 1, 1, 1);
+        grid.attach(&dpi, 1, 1, 1, 1);
+
+        let dialog = adw::MessageDialog::new(
+            Some(&*self.obj()),
+            Some("Export Pages as Images"),
+            Some(&format!(
+                "Select pages by their numbers or ranges, e.g. “1-3, 5”, out of {count} pages."
+            )),
+        );
+
+        dialog.set_extra_child(Some(&grid));
+        dialog.add_responses(&[("cancel", "Cancel"), ("export", "Export…")]);
+        dialog.set_response_appearance("export", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("export"));
+        dialog.set_close_response("cancel");
+
+        dialog.connect_response(
+            None,
+            clone!(@weak self as win, @weak pages, @weak dpi => move |_, response| {
+                if response != "export" {
+                    return;
+                }
+
+                let text = pages.text();
+
+                match export::parse_pages(&text, count) {
+                    Some(selection) => win.choose_image_export_file(selection, dpi.value()),
+                    None => {
+                        let message = format!("Invalid page selection “{text}”");
+                        win.overlay.add_toast(adw::Toast::new(&message));
+                    }
+                }
+            }),
+        );
+
+        dialog.present();
+    }
+
+    fn choose_image_export_file(&self, pages: Vec<usize>, dpi: f64) {
+        let path = self.path.borrow().clone();
+        let stem = path.as_ref().and_then(|p| p.file_stem());
+        let stem = stem.map_or("page".into(), |s| s.to_string_lossy());
+
+        // single pages are named by their number, multiple pages get it
+        // appended when exporting
+        let name = match &pages[..] {
+            [page] => format!("{stem}-{}.png", page + 1),
+            _ => format!("{stem}.png"),
+        };
+
+        let filechooser = png_file_dialog();
+        filechooser.set_initial_name(Some(&name));
+
+        filechooser.save(
+            Some(&*self.obj()),
+            None::<&gtk::gio::Cancellable>,
+            clone!(@weak self as win => move |result| {
+                if let Ok(file) = result {
+                    win.export_images(file, pages, dpi);
+                }
+            }),
+        );
+    }
+
+    fn export_images(&self, file: File, pages: Vec<usize>, dpi: f64) {
+        let path = match file.path() {
+            Some(path) => path,
+            None => return,
+        };
+
+        let doc = match self.canvas().document() {
+            Some(doc) => doc,
+            None => return,
+        };
+
+        let app = self
+            .obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok());
+
+        let app = match app {
+            Some(app) => app,
+            None => return,
+        };
+
+        let target = path.clone();
+        let task = app.spawn_blocking_with_priority(TaskPriority::Normal, move || {
+            export::export_pages(&doc, &pages, dpi, &target)
+        });
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as win => async move {
+            let message = match task.await {
+                Ok(count) => {
+                    tracing::info!(file=?path, count, dpi, "pages exported as images");
+
+                    if count == 1 {
+                        format!("Page exported to \"{}\"", path.display())
+                    } else {
+                        let dir = path.parent().unwrap_or(&path);
+                        format!("{} pages exported to \"{}\"", count, dir.display())
+                    }
+                }
+                Err(err) => {
+                    tracing::warn!(file=?path, error=%err, "failed to export pages as images");
+                    format!("Error: {err}")
+                }
+            };
+
+            win.overlay.add_toast(adw::Toast::new(&message));
+        }));
+    }
+
+    pub fn export_annotations(&self, file: File) {
+        let path = match file.path() {
+            Some(path) => path,
+            None => return,
+        };
+
+        let doc = match self.canvas().document() {
+            Some(doc) => doc,
+            None => return,
+        };
+
+        let app = self
+            .obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok());
+
+        let app = match app {
+            Some(app) => app,
+            None => return,
+        };
+
+        let target = path.clone();
+        let task = app.spawn_blocking_with_priority(TaskPriority::Normal, move || {
+            let mut out = std::io::BufWriter::new(std::fs::File::create(&target)?);
+            xfdf::export(&doc, &mut out)
+        });
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as win => async move {
+            let message = match task.await {
+                Ok(count) => {
+                    tracing::info!(file=?path, count, "annotations exported");
+                    format!("{} annotations exported to \"{}\"", count, path.display())
+                }
+                Err(err) => {
+                    tracing::warn!(file=?path, error=%err, "failed to export annotations");
+                    format!("Error: {err}")
+                }
+            };
+
+            win.overlay.add_toast(adw::Toast::new(&message));
+        }));
+    }
+
+    /// Add the annotations of the given XFDF file to the current document.
+    /// The document file itself is not modified.
+    pub fn import_annotations(&self, file: File) {
+        let source = match file.path() {
+            Some(path) => path,
+            None => return,
+        };
+
+        let path = match self.path.borrow().clone() {
+            Some(path) => path,
+            None => return,
+        };
+
+        let doc = match self.canvas().document() {
+            Some(doc) => doc,
+            None => return,
+        };
+
+        let app = self
+            .obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok());
+
+        let app = match app {
+            Some(app) => app,
+            None => return,
+        };
+
+        let file = source.clone();
+        let task = app.spawn_blocking_with_priority(TaskPriority::High, move || {
+            let input = std::fs::read_to_string(&file)?;
+            xfdf::import(&doc, &input)
+        });
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as win => async move {
+            let message = match task.await {
+                Ok(summary) => {
+                    tracing::info!(file=?source, ?summary, "annotations imported");
+
+                    // the document has been replaced in the meantime
+                    if win.path.borrow().as_ref() != Some(&path) {
+                        return;
+                    }
+
+                    if summary.added + summary.replaced > 0 {
+                        win.canvas().reload_pages();
+                    }
+
+                    format!(
+                        "Annotations imported: {} added, {} replaced, {} skipped",
+                        summary.added, summary.replaced, summary.skipped
+                    )
+                }
+                Err(err) => {
+                    tracing::warn!(file=?source, error=%err, "failed to import annotations");
+                    format!("Error: {err}")
+                }
+            };
+
+            win.overlay.add_toast(adw::Toast::new(&message));
+        }));
+    }
+
+    /// Show the metadata and security properties of the current document.
+    fn show_properties(&self) {
+        let doc = match self.canvas().document() {
+            Some(doc) => doc,
+            None => return,
+        };
+
+        let dialog = PropertiesDialog::new(&*self.obj());
+        dialog.set_document(&doc, self.path.borrow().as_deref());
+        dialog.present();
+    }
+
+    /// Show statistics of the current document, computing them in the
+    /// background unless they are known for a document of the same content.
+    fn show_statistics(&self) {
+        let (doc, hash) = match (self.canvas().document(), self.doc_hash.get()) {
+            (Some(doc), Some(hash)) => (doc, hash),
+            _ => return,
+        };
+
+        let dialog = StatisticsDialog::new(&*self.obj());
+        dialog.present();
+
+        if let Some(stats) = self.stats.borrow().get(&hash) {
+            dialog.set_stats(stats);
+            return;
+        }
+
+        let app = self
+            .obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok());
+
+        let app = match app {
+            Some(app) => app,
+            None => return,
+        };
+
+        // report progress from the worker thread to the dialog
+        let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+        receiver.attach(
+            None,
+            clone!(@weak dialog => @default-return glib::Continue(false), move |(done, total)| {
+                dialog.set_progress(done, total);
+                glib::Continue(true)
+            }),
+        );
+
+        let canceled = dialog.canceled();
+        let task = app.spawn_blocking_with_priority(TaskPriority::Low, move || {
+            DocumentStats::compute(&doc, &canceled, |done, total| {
+                let _ = sender.send((done, total));
+            })
+        });
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as win => async move {
+            let stats = match task.await {
+                Some(stats) => Rc::new(stats),
+                None => return,
+            };
+
+            tracing::debug!(?stats, "document statistics computed");
+
+            win.stats.borrow_mut().insert(hash, stats.clone());
+            dialog.set_stats(&stats);
+        }));
+    }
+
+    /// Print the current document. Pages are rendered in the background as
+    /// they are requested by the print operation.
+    fn print(&self) {
+        let doc = match self.canvas().document() {
+            Some(doc) => doc,
+            None => return,
+        };
+
+        let app = self
+            .obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok());
+
+        let app = match app {
+            Some(app) => app,
+            None => return,
+        };
+
+        let op = gtk::PrintOperation::new();
+        op.set_allow_async(true);
+        op.set_embed_page_setup(true);
+        op.set_unit(gtk::Unit::Points);
+        op.set_n_pages(doc.pages().count() as _);
+        op.set_print_settings(self.print_settings.borrow().as_ref());
+        op.set_custom_tab_label(Some("Scaling"));
+
+        if let Some((page, _)) = self.canvas().viewport_page_rect() {
+            op.set_current_page(page as _);
+        }
+
+        if let Some(name) = self.path.borrow().as_ref().and_then(|p| p.file_name()) {
+            op.set_job_name(&name.to_string_lossy());
+        }
+
+        op.connect_create_custom_widget(
+            clone!(@weak self as win => @default-return None, move |_| {
+                let labels: Vec<_> = PrintScaling::ALL.iter().map(|s| s.label()).collect();
+                let scaling = win.print_scaling.get();
+                let selected = PrintScaling::ALL.iter().position(|s| *s == scaling);
+
+                let dropdown = gtk::DropDown::from_strings(&labels);
+                dropdown.set_selected(selected.unwrap_or_default() as _);
+
+                let label = gtk::Label::new(Some("Page Scaling:"));
+
+                let content = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+                content.set_margin_top(12);
+                content.set_margin_bottom(12);
+                content.set_margin_start(12);
+                content.set_margin_end(12);
+                content.append(&label);
+                content.append(&dropdown);
+
+                Some(content.upcast())
+            }),
+        );
+
+        op.connect_custom_widget_apply(clone!(@weak self as win => move |_, content| {
+            let dropdown = content.last_child().and_downcast::<gtk::DropDown>();
+            let selected = dropdown.map(|d| d.selected() as usize);
+
+            if let Some(scaling) = selected.and_then(|i| PrintScaling::ALL.get(i)) {
+                win.print_scaling.set(*scaling);
+            }
+        }));
+
+        op.connect_draw_page(clone!(@weak self as win => move |op, ctx, index| {
+            // render in the background and finish the page once done
+            op.set_defer_drawing();
+
+            let doc = doc.clone();
+            let area = vector![ctx.width(), ctx.height()];
+            let dpi = ctx.dpi_x().min(ctx.dpi_y());
+            let scaling = win.print_scaling.get();
+
+            let task = app.spawn_blocking_with_priority(TaskPriority::High, move || {
+                print::render_page(&doc, index as _, area, dpi, scaling)
+            });
+
+            let op = op.clone();
+            let ctx = ctx.clone();
+
+            glib::MainContext::default().spawn_local(async move {
+                match task.await {
+                    Ok(page) => {
+                        if let Err(err) = print::draw(&ctx.cairo_context(), &page) {
+                            tracing::warn!(page=index, error=%err, "failed to draw page");
+                        }
+                    }
+                    Err(err) => {
+                        tracing::warn!(page=index, error=%err, "failed to render page");
+                    }
+                }
+
+                op.draw_page_finish();
+            });
+        }));
+
+        op.connect_done(clone!(@weak self as win => move |op, result| {
+            match result {
+                gtk::PrintOperationResult::Apply => {
+                    win.print_settings.replace(op.print_settings());
+                }
+                gtk::PrintOperationResult::Error => {
+                    let message = match op.error() {
+                        Some(err) => format!("Error: {err}"),
+                        None => "Printing failed".to_owned(),
+                    };
+
+                    win.overlay.add_toast(adw::Toast::new(&message));
+                }
+                _ => {}
+            }
+        }));
+
+        let result = op.run(gtk::PrintOperationAction::PrintDialog, Some(&*self.obj()));
+
+        if let Err(err) = result {
+            tracing::warn!(error=%err, "failed to start printing");
+
+            let message = format!("Error: {err}");
+            self.overlay.add_toast(adw::Toast::new(&message));
+        }
+    }
+
+    /// Close the tab of the current document.
+    pub fn close_file(&self) {
+        if let Some(page) = self.tab_view.selected_page() {
+            self.tab_view.close_page(&page);
+        }
+    }
+
+    /// All tabs, in order.
+    fn tab_pages(&self) -> Vec<adw::TabPage> {
+        let pages = self.tab_view.pages();
+
+        (0..pages.n_items())
+            .filter_map(|i| pages.item(i).and_downcast::<adw::TabPage>())
+            .collect()
+    }
+
+    /// Views of all tabs, and the empty view if there are no tabs.
+    fn views(&self) -> Vec<DocumentView> {
+        let mut views: Vec<DocumentView> = self
+            .tab_pages()
+            .iter()
+            .filter_map(|page| page.child().downcast().ok())
+            .collect();
+
+        let current = self.view.borrow().clone();
+        if !views.contains(&current) {
+            views.push(current);
+        }
+
+        views
+    }
+
+    /// The tab showing the given file, if any.
+    fn find_tab(&self, path: &Path) -> Option<adw::TabPage> {
+        let current = self.view.borrow().clone();
+
+        self.tab_pages().into_iter().find(|page| {
+            let view = match page.child().downcast::<DocumentView>() {
+                Ok(view) => view,
+                Err(_) => return false,
+            };
+
+            if view == current {
+                self.path.borrow().as_deref() == Some(path)
+            } else {
+                view.path().as_deref() == Some(path)
+            }
+        })
+    }
+
+    /// Add a tab for showing a document and select it. The view shown while
+    /// there are no tabs becomes the first tab.
+    fn add_tab(&self) -> adw::TabPage {
+        let view = if self.tab_view.n_pages() == 0 {
+            self.view.borrow().clone()
+        } else {
+            DocumentView::new()
+        };
+
+        let page = self.tab_view.append(&view);
+        self.tab_view.set_selected_page(&page);
+        page
+    }
+
+    /// Connect the viewport and canvas of the given view to this window,
+    /// e.g. when it has been added as tab or moved here from another window.
+    fn attach_view(&self, view: &DocumentView) {
+        let viewport = view.viewport();
+        let canvas = view.canvas();
+
+        let mut handlers = vec![
+            (
+                viewport.clone().upcast(),
+                viewport.connect_page_preview(clone!(@weak self as win => move |_, position| {
+                    win.show_page_preview(position);
+                })),
+            ),
+            (
+                viewport.clone().upcast(),
+                viewport.connect_search_result_activated(
+                    clone!(@weak self as win => move |_, position| {
+                        win.show_search_result_at(position);
+                    }),
+                ),
+            ),
+            (
+                canvas.clone().upcast(),
+                canvas.connect_link_activated(clone!(@weak self as win => move |_, target| {
+                    win.follow_link(target);
+                })),
+            ),
+            (
+                canvas.clone().upcast(),
+                canvas.connect_uri_activated(clone!(@weak self as win => move |_, uri| {
+                    win.open_uri(uri);
+                })),
+            ),
+            (
+                canvas.clone().upcast(),
+                canvas.connect_region_selected(
+                    clone!(@weak self as win => move |_, page, region| {
+                        win.inspect_region(page, region);
+                    }),
+                ),
+            ),
+            (
+                canvas.clone().upcast(),
+                canvas.connect_color_picked(clone!(@weak self as win => move |_, color| {
+                    win.color_picked(color);
+                })),
+            ),
+            // the canvas leaves the pen tool on escape
+            (
+                canvas.clone().upcast(),
+                canvas.connect_tool_canceled(clone!(@weak self as win => move |_| {
+                    win.set_pen(false);
+                })),
+            ),
+            (
+                canvas.clone().upcast(),
+                canvas.connect_notify_local(
+                    Some("offset-y"),
+                    clone!(@weak self as win => move |_, _| {
+                        win.update_reading_progress();
+                        win.update_font_diagnostics();
+                        win.update_current_thumbnail();
+                        win.update_page_indicator();
+                        win.schedule_recovery_save();
+                    }),
+                ),
+            ),
+            // pages are side by side in horizontal layouts
+            (
+                canvas.clone().upcast(),
+                canvas.connect_notify_local(
+                    Some("offset-x"),
+                    clone!(@weak self as win => move |_, _| {
+                        win.update_current_thumbnail();
+                        win.update_page_indicator();
+                    }),
+                ),
+            ),
+            (
+                canvas.clone().upcast(),
+                canvas.connect_notify_local(
+                    Some("scale"),
+                    clone!(@weak self as win => move |_, _| {
+                        win.schedule_recovery_save();
+                    }),
+                ),
+            ),
+        ];
+
+        // keep the page fitted to the screen while presenting, e.g. once the
+        // window has become fullscreen, after the canvas has been allocated
+        let adjustments = [canvas.hadjustment(), canvas.vadjustment()];
+
+        for adj in adjustments.into_iter().flatten() {
+            let handler = adj.connect_page_size_notify(clone!(@weak self as win => move |_| {
+                if win.presentation.get().is_none() {
+                    return;
+                }
+
+                glib::idle_add_local_once(clone!(@weak win => move || {
+                    if win.presentation.get().is_some() {
+                        win.viewport().fit_page();
+                    }
+                }));
+            }));
+
+            handlers.push((adj.upcast(), handler));
+        }
+
+        let mut controllers = self.setup_page_swipe(&viewport);
+        controllers.push(self.setup_caret_navigation(&canvas));
+        controllers.extend(setup_page_shortcuts(&viewport));
+        controllers.extend(self.setup_presentation_controls(&viewport));
+
+        view.set_window_handlers(handlers, controllers);
+    }
+
+    /// Show the document of the selected tab, keeping the state of the
+    /// previously selected one with its view.
+    fn select_view(&self) {
+        let selected = self
+            .tab_view
+            .selected_page()
+            .and_then(|page| page.child().downcast::<DocumentView>().ok());
+
+        let previous = self.view.borrow().clone();
+        if selected.as_ref() == Some(&previous) {
+            return;
+        }
+
+        self.save_reading_progress();
+        self.viewport().stop_autoscroll();
+        self.set_presentation(false);
+
+        previous.set_state(self.take_tab_state());
+
+        // keep caches for quickly switching back, but release them once the
+        // tab has not been shown for a while
+        previous.schedule_suspend(AUTOSUSPEND_DELAY);
+
+        let view = selected.unwrap_or_default();
+        self.view.replace(view.clone());
+
+        self.restore_tab_state(view.take_state());
+        self.apply_trim_margins();
+        self.accessible_page.set(None);
+        view.resume();
+
+        self.apply_layout_settings();
+        self.apply_tiling_settings();
+        self.update_power_mode();
+        self.update_document_ui();
+
+        // the document has been changed while the tab has not been selected
+        if view.take_reload_pending() {
+            self.reload_document();
+        }
+    }
+
+    /// Watch the file of the document shown in the current view, reloading
+    /// the document once it has been changed by another program.
+    fn watch_file(&self, path: &Path) {
+        let view = self.view.borrow().clone();
+
+        let watch = FileWatch::connect(
+            path,
+            clone!(@weak self as win, @weak view => move || {
+                // reload documents of other tabs once they are selected
+                if *win.view.borrow() == view {
+                    win.reload_document();
+                } else {
+                    view.set_reload_pending(true);
+                }
+            }),
+        );
+
+        match watch {
+            Ok(watch) => view.set_file_watch(Some(watch)),
+            Err(err) => {
+                tracing::warn!(file=?path, error=%err, "failed to watch file for changes");
+                view.set_file_watch(None);
+            }
+        }
+    }
+
+    /// Load the current document again, keeping the current view, e.g. after
+    /// it has been changed on disk.
+    fn reload_document(&self) {
+        let path = self.path.borrow().clone();
+
+        if let Some(path) = path {
+            tracing::debug!(file=?path, "reloading changed file");
+            self.open_file_at(File::for_path(path), None, true);
+        }
+    }
+
+    /// Take the state of the current document, leaving the window without
+    /// document.
+    fn take_tab_state(&self) -> TabState {
+        let signatures = self.signature_banner.reveals_child().then(|| {
+            let text = self.signature_label.text().to_string();
+            let tooltip = self.signature_label.tooltip_text().map(String::from);
+            (text, tooltip)
+        });
+
+        TabState {
+            path: self.path.take(),
+            title: Some(self.window_title.title().to_string()),
+            labels: self.labels.take(),
+            outline: self.outline.take(),
+            text_column: self.text_column.take(),
+            content_boxes: self.content_boxes.take(),
+            trim_margins: self.trim_margins.take(),
+            trusted: self.trusted.take(),
+            page_style: self.page_style.take(),
+            blocks: self.blocks.take(),
+            block_cursor: self.block_cursor.take(),
+            font_page: self.font_page.take(),
+            caret_texts: self.caret_texts.take(),
+            caret: self.caret.take(),
+            doc_hash: self.doc_hash.take(),
+            disk_cache: self.disk_cache.take(),
+            reading: self.reading.take(),
+            timeline: self.timeline.take(),
+            history: self.history.take(),
+            signatures,
+        }
+    }
+
+    /// Restore the state of a document previously taken from a window.
+    fn restore_tab_state(&self, state: TabState) {
+        self.path.replace(state.path);
+        self.labels.replace(state.labels);
+        self.outline.replace(state.outline);
+        self.text_column.set(state.text_column);
+        self.content_boxes.replace(state.content_boxes);
+        self.trim_margins.set(state.trim_margins);
+        self.blocks.replace(state.blocks);
+        self.block_cursor.set(state.block_cursor);
+        self.font_page.set(state.font_page);
+        self.caret_texts.replace(state.caret_texts);
+        self.caret.set(state.caret);
+        self.doc_hash.set(state.doc_hash);
+        self.disk_cache.replace(state.disk_cache);
+        self.reading.replace(state.reading);
+        self.timeline.replace(state.timeline);
+        self.history.replace(state.history);
+
+        self.set_trusted(state.trusted);
+        self.set_page_style(state.page_style);
+
+        match state.signatures {
+            Some((text, tooltip)) => {
+                self.signature_label.set_text(&text);
+                self.signature_label.set_tooltip_text(tooltip.as_deref());
+                self.signature_banner.set_reveal_child(true);
+            }
+            None => self.signature_banner.set_reveal_child(false),
+        }
+
+        match (&*self.path.borrow(), state.title) {
+            (Some(path), Some(title)) => {
+                let filename = path.file_name().unwrap_or_default().to_string_lossy();
+
+                self.window_title.set_title(&title);
+                self.window_title.set_subtitle(&filename);
+            }
+            _ => {
+                self.window_title.set_title("PDF Annotator Prototype");
+                self.window_title.set_subtitle("No Document Selected");
+            }
+        }
+    }
+
+    /// Update the window for the current document, e.g. after switching
+    /// tabs.
+    fn update_document_ui(&self) {
+        let has_document = self.path.borrow().is_some();
+        let doc = self.canvas().document();
+
+        self.update_history_actions();
+        self.update_scroll_marks();
+        self.update_scroll_heat();
+        self.outline_view.set_outline(&self.outline.borrow());
+
+        let labels: Vec<_> = (0..self.canvas().page_count())
+            .map(|page| self.page_label(page))
+            .collect();
+        self.thumbnails_view.set_document(doc.as_ref(), &labels);
+        self.update_current_thumbnail();
+        self.update_page_indicator();
+        self.update_reading_progress();
+
+        // actions reflect the state of the canvas
+        let single_page = self.canvas().single_page().is_some();
+        let layout = self.canvas().layout();
+
+        if let Some(action) = self.obj().lookup_action("single-page") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_state(single_page.to_variant());
+            }
+        }
+
+        if let Some(action) = self.obj().lookup_action("trim-margins") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_state(self.trim_margins.get().to_variant());
+            }
+        }
+
+        if let Some(action) = self.obj().lookup_action("layout") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_state(layout.as_str().to_variant());
+            }
+        }
+
+        // search the selected document for the current query
+        if self.search_bar.is_search_mode() {
+            self.start_search();
+        } else {
+            self.stop_search();
+        }
+
+        self.pin_badge.set_visible(false);
+        self.footer
+            .set_visible(has_document && self.presentation.get().is_none());
+        self.page_nav.set_visible(has_document);
+
+        self.update_content_page();
+        self.schedule_recovery_save();
+    }
+
+    /// Run the given analysis of the document on the thread pool and apply
+    /// its result, unless the document has been closed or replaced in the
+    /// meantime.
+    fn analyze_document<R, F, G>(&self, path: &Path, doc: Document, analyze: F, apply: G)
+    where
+        F: FnOnce(&Document) -> R + Send + 'static,
+        R: Send + 'static,
+        G: FnOnce(&Self, R) + 'static,
+    {
+        let app = self
+            .obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok());
+
+        let app = match app {
+            Some(app) => app,
+            None => return,
+        };
+
+        let path = path.to_owned();
+        let task = app.spawn_blocking_with_priority(TaskPriority::Low, move || analyze(&doc));
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as win => async move {
+            let result = task.await;
+
+            if win.path.borrow().as_ref() == Some(&path) {
+                apply(&win, result);
+            }
+        }));
+    }
+
+    fn synthesize_links(&self, path: &Path, doc: Document) {
+        let file = path.to_owned();
+
+        self.analyze_document(path, doc, citations::synthesize_links, move |win, links| {
+            tracing::debug!(file=?file, links = links.len(), "synthesized links");
+            win.canvas().set_links(links);
+        });
+    }
+
+    fn detect_text_column(&self, path: &Path, doc: Document) {
+        let file = path.to_owned();
+
+        self.analyze_document(path, doc, columns::detect, move |win, column| {
+            tracing::debug!(file=?file, ?column, "detected text column");
+            win.text_column.set(column);
+        });
+    }
+
+    fn detect_content_boxes(&self, path: &Path, doc: Document) {
+        let file = path.to_owned();
+
+        self.analyze_document(path, doc, trim::detect, move |win, boxes| {
+            tracing::debug!(file=?file, pages = boxes.len(), "detected page content");
+            win.content_boxes.replace(Some(boxes));
+            win.apply_trim_margins();
+        });
+    }
+
+    fn detect_signatures(&self, path: &Path, doc: Document) {
+        let file = path.to_owned();
+
+        self.analyze_document(path, doc, signatures::detect, move |win, signatures| {
+            tracing::debug!(file=?file, ?signatures, "detected signatures");
+            win.show_signatures(&signatures);
+        });
+    }
+
+    /// Tell the user that the document is signed, or contains signature
+    /// fields, as edits may invalidate signatures.
+    fn show_signatures(&self, signatures: &Signatures) {
+        if signatures.is_empty() {
+            self.signature_banner.set_reveal_child(false);
+            return;
+        }
+
+        let signers = signatures.signers();
+
+        let message = if !signatures.is_signed() {
+            let n = signatures.unsigned_fields;
+            let fields = if n == 1 {
+                "a signature field"
+            } else {
+                "signature fields"
+            };
+
+            format!("This document contains {fields}. Edits may prevent it from being signed.")
+        } else if signers.is_empty() {
+            "This document is digitally signed. Edits may invalidate its signatures.".into()
+        } else {
+            format!(
+                "This document is digitally signed by {}. Edits may invalidate its signatures.",
+                signers.join(", ")
+            )
+        };
+
+        // details of each signature, where available
+        let details: Vec<_> = signatures
+            .signatures
+            .iter()
+            .map(|sig| {
+                let signer = sig.signer.as_deref().unwrap_or("Unknown signer");
+                let mut line = signer.to_owned();
+
+                if let Some(time) = &sig.time {
+                    line.push_str(&format!(", signed {time}"));
+                }
+                if let Some(reason) = &sig.reason {
+                    line.push_str(&format!(": {reason}"));
+                }
+
+                line
+            })
+            .collect();
+
+        let tooltip = (!details.is_empty()).then(|| details.join("\n"));
+
+        self.signature_label.set_text(&message);
+        self.signature_label.set_tooltip_text(tooltip.as_deref());
+        self.signature_banner.set_reveal_child(true);
+    }
+
+    /// Zoom to fit the main text column of the document to the viewport
+    /// width, falling back to the page width if no column has been detected.
+    pub fn zoom_fit_text_column(&self) {
+        let page_rect = self
+            .canvas()
+            .viewport_page_rect()
+            .and_then(|(page, _)| self.canvas().page_rect(page));
+
+        // the column has been detected on unrotated pages
+        let column = self
+            .text_column
+            .get()
+            .filter(|_| self.canvas().rotation() == PageRotation::None);
+
+        match (column, page_rect) {
+            (Some(column), Some(page_rect)) => {
+                let x_min = page_rect.offs.x + column.left;
+                self.viewport().fit_horizontal(x_min, x_min + column.width);
+            }
+            _ => self.viewport().fit_width(),
+        }
+    }
+
+    /// Zoom to fit the current page to the viewport.
+    pub fn zoom_fit_page(&self) {
+        let canvas = self.canvas();
+        let rect = canvas
+            .viewport_page_rect()
+            .and_then(|(page, _)| canvas.page_rect(page));
+
+        if let Some(rect) = rect {
+            self.viewport().zoom_to_rect(&rect);
+        }
+    }
+
+    fn is_single_page(&self) -> bool {
+        self.obj()
+            .lookup_action("single-page")
+            .and_then(|action| action.state())
+            .and_then(|state| state.get::<bool>())
+            .unwrap_or(false)
+    }
+
+    /// Show only the current page, fitted to the viewport, or go back to
+    /// showing all pages.
+    fn set_single_page(&self, enabled: bool) {
+        if enabled {
+            let page = self.canvas().viewport_page_rect().map(|(page, _)| page);
+
+            self.canvas().set_single_page(page);
+            self.viewport().fit_page();
+        } else {
+            self.canvas().set_single_page(None);
+        }
+
+        if let Some(action) = self.obj().lookup_action("single-page") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_state(enabled.to_variant());
+            }
+        }
+    }
+
+    /// Trim the margins of all pages of the current document, showing only
+    /// their content, or show pages in full again.
+    fn set_trim_margins(&self, enabled: bool) {
+        self.trim_margins.set(enabled);
+        self.apply_trim_margins();
+
+        if let Some(action) = self.obj().lookup_action("trim-margins") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_state(enabled.to_variant());
+            }
+        }
+    }
+
+    /// Crop pages to their content boxes if margins are trimmed, detecting
+    /// the content boxes first if needed.
+    fn apply_trim_margins(&self) {
+        let canvas = self.canvas();
+
+        if !self.trim_margins.get() {
+            if canvas.is_cropped() {
+                canvas.set_crops(&[]);
+            }
+            return;
+        }
+
+        if let Some(boxes) = &*self.content_boxes.borrow() {
+            if !canvas.is_cropped() {
+                canvas.set_crops(boxes);
+            }
+            return;
+        }
+
+        let path = self.path.borrow().clone();
+        if let (Some(path), Some(doc)) = (path, canvas.document()) {
+            self.detect_content_boxes(&path, doc);
+        }
+    }
+
+    /// Show documents as reflowed text instead of their pages, or show pages
+    /// again.
+    fn set_reader_mode(&self, enabled: bool) {
+        self.reader_mode.set(enabled);
+        self.update_content_page();
+
+        if let Some(action) = self.obj().lookup_action("reader-mode") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_state(enabled.to_variant());
+            }
+        }
+    }
+
+    /// Show the start page, the pages of the current document, or its text
+    /// in reader mode, extracting the text first if needed.
+    fn update_content_page(&self) {
+        if self.path.borrow().is_none() {
+            self.content_stack.set_visible_child_name("start");
+            self.update_recent_documents();
+            return;
+        }
+
+        if !self.reader_mode.get() {
+            self.content_stack.set_visible_child_name("document");
+            return;
+        }
+
+        self.reader_view.buffer().set_text("");
+        self.content_stack.set_visible_child_name("reader");
+
+        let path = self.path.borrow().clone();
+        if let (Some(path), Some(doc)) = (path, self.canvas().document()) {
+            let file = path.clone();
+
+            self.analyze_document(&path, doc, reflow::document_text, move |win, pages| {
+                tracing::debug!(file=?file, pages = pages.len(), "extracted reader text");
+
+                if win.reader_mode.get() {
+                    win.show_reader_text(&pages);
+                }
+            });
+        }
+    }
+
+    /// Fill the reader view with the given paragraphs per page, each page
+    /// headed by its label.
+    fn show_reader_text(&self, pages: &[Vec<String>]) {
+        let buffer = self.reader_view.buffer();
+        buffer.set_text("");
+
+        let mut iter = buffer.end_iter();
+        for (page, paragraphs) in pages.iter().enumerate() {
+            if paragraphs.is_empty() {
+                continue;
+            }
+
+            let heading = format!("Page {}\n", self.page_label(page));
+            buffer.insert_with_tags_by_name(&mut iter, &heading, &["reader-text", "reader-page"]);
+
+            for para in paragraphs {
+                buffer.insert_with_tags_by_name(&mut iter, &format!("{para}\n"), &["reader-text"]);
+            }
+        }
+
+        if buffer.char_count() == 0 {
+            buffer.set_text("This document contains no text.");
+        }
+
+        buffer.place_cursor(&buffer.start_iter());
+    }
+
+    /// Set the size of the text shown in reader mode, in points.
+    fn set_reader_font_size(&self, size: f64) {
+        let tag = self.reader_view.buffer().tag_table().lookup("reader-text");
+
+        if let Some(tag) = tag {
+            tag.set_size_points(size);
+        }
+    }
+
+    /// Present the current document: fullscreen, one page at a time fitted
+    /// to the screen, without any other controls. Leaving the presentation
+    /// restores the previous state of the window.
+    fn set_presentation(&self, enabled: bool) {
+        if enabled == self.presentation.get().is_some() {
+            return;
+        }
+
+        let chrome: [gtk::Widget; 4] = [
+            self.header_bar.get().upcast(),
+            self.tab_bar.get().upcast(),
+            self.search_bar.get().upcast(),
+            self.signature_banner.get().upcast(),
+        ];
+
+        if enabled {
+            if self.path.borrow().is_none() {
+                self.update_presentation_action();
+                return;
+            }
+
+            self.presentation.set(Some(PresentationState {
+                fullscreen: self.obj().is_fullscreen(),
+                single_page: self.is_single_page(),
+                sidebar: self.flap.reveals_flap(),
+                scale: self.viewport().scale().unwrap_or(1.0),
+            }));
+
+            self.set_reader_mode(false);
+            self.flap.set_reveal_flap(false);
+            self.footer.set_visible(false);
+
+            for widget in &chrome {
+                widget.set_visible(false);
+            }
+
+            // show pages as large as possible, with the neighboring ones
+            // rendered at the same resolution for flipping to them
+            self.apply_layout_settings();
+            self.canvas().set_prerender_neighbors(true);
+            self.set_single_page(true);
+
+            self.obj().fullscreen();
+            self.canvas().grab_focus();
+            self.restart_presentation_timer();
+        } else {
+            let state = match self.presentation.take() {
+                Some(state) => state,
+                None => return,
+            };
+
+            if let Some(timer) = self.presentation_timer.take() {
+                timer.remove();
+            }
+
+            self.footer.set_visible(true);
+
+            for widget in &chrome {
+                widget.set_visible(true);
+            }
+
+            self.apply_layout_settings();
+            self.canvas().set_prerender_neighbors(false);
+
+            if !state.fullscreen {
+                self.obj().unfullscreen();
+            }
+
+            self.flap.set_reveal_flap(state.sidebar);
+
+            // stay at the presented page
+            if state.single_page {
+                self.viewport().fit_page();
+            } else {
+                let page = self.canvas().single_page();
+
+                self.set_single_page(false);
+                self.viewport().set_scale(state.scale);
+
+                if let Some(page) = page {
+                    self.canvas().scroll_to_page(page);
+                }
+            }
+        }
+
+        self.update_presentation_action();
+    }
+
+    fn update_presentation_action(&self) {
+        let enabled = self.presentation.get().is_some();
+
+        if let Some(action) = self.obj().lookup_action("presentation") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_state(enabled.to_variant());
+            }
+        }
+    }
+
+    /// Show the given page of the presented document.
+    fn present_page(&self, page: usize) {
+        if page >= self.canvas().page_count() {
+            return;
+        }
+
+        self.canvas().set_single_page(Some(page));
+        self.viewport().fit_page();
+        self.restart_presentation_timer();
+    }
+
+    /// Advance to the next page once the interval set in the preferences has
+    /// passed, replacing any pending advance. Pages are only advanced
+    /// manually if no interval has been set.
+    fn restart_presentation_timer(&self) {
+        if let Some(timer) = self.presentation_timer.take() {
+            timer.remove();
+        }
+
+        let interval = self
+            .session()
+            .and_then(|session| session.presentation_interval());
+
+        let interval = match interval {
+            Some(interval) if self.presentation.get().is_some() => interval,
+            _ => return,
+        };
+
+        let timer = glib::timeout_add_local_once(
+            interval,
+            clone!(@weak self as win => move || {
+                win.presentation_timer.replace(None);
+                win.flip_page(true);
+            }),
+        );
+
+        self.presentation_timer.replace(Some(timer));
+    }
+
+    /// Navigate presentations with the keyboard and mouse, i.e. flip pages
+    /// with the arrow keys, space, and clicks, and leave them with escape.
+    fn setup_presentation_controls(&self, viewport: &ViewportWidget) -> Vec<gtk::EventController> {
+        let keys = gtk::EventControllerKey::new();
+        keys.set_name(Some("presentation_key_controller"));
+        keys.set_propagation_phase(gtk::PropagationPhase::Capture);
+
+        keys.connect_key_pressed(
+            clone!(@weak self as win => @default-return gtk::Inhibit(false),
+                move |_, key, _, state| {
+                    if win.presentation.get().is_none() {
+                        return gtk::Inhibit(false);
+                    }
+
+                    let shift = state.contains(gdk::ModifierType::SHIFT_MASK);
+
+                    match key {
+                        gdk::Key::space => win.flip_page(!shift),
+                        gdk::Key::Right
+                        | gdk::Key::Down
+                        | gdk::Key::Page_Down
+                        | gdk::Key::Return
+                        | gdk::Key::KP_Enter => win.flip_page(true),
+                        gdk::Key::Left
+                        | gdk::Key::Up
+                        | gdk::Key::Page_Up
+                        | gdk::Key::BackSpace => win.flip_page(false),
+                        gdk::Key::Home => win.present_page(0),
+                        gdk::Key::End => {
+                            win.present_page(win.canvas().page_count().saturating_sub(1))
+                        }
+                        gdk::Key::Escape => win.set_presentation(false),
+                        _ => return gtk::Inhibit(false),
+                    }
+
+                    gtk::Inhibit(true)
+                }
+            ),
+        );
+
+        viewport.add_controller(keys.clone());
+
+        // clicks flip pages instead of following links or selecting text
+        let click = gtk::GestureClick::new();
+        click.set_name(Some("presentation_click_controller"));
+        click.set_propagation_phase(gtk::PropagationPhase::Capture);
+        click.set_button(0);
+
+        click.connect_pressed(clone!(@weak self as win => move |gesture, _, _, _| {
+            if win.presentation.get().is_none() {
+                gesture.set_state(gtk::EventSequenceState::Denied);
+                return;
+            }
+
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+
+            match gesture.current_button() {
+                gdk::BUTTON_PRIMARY => win.flip_page(true),
+                gdk::BUTTON_SECONDARY => win.flip_page(false),
+                _ => {}
+            }
+        }));
+
+        viewport.add_controller(click.clone());
+
+        vec![keys.upcast(), click.upcast()]
+    }
+
+    /// Flip to the next or previous page in single-page mode, sliding it in
+    /// from the respective side.
+    fn flip_page(&self, forward: bool) {
+        if !self.is_single_page() {
+            return;
+        }
+
+        let current = match self.canvas().viewport_page_rect() {
+            Some((page, _)) => page,
+            None => return,
+        };
+
+        let target = if forward {
+            current + 1
+        } else {
+            current.wrapping_sub(1)
+        };
+
+        if target >= self.canvas().page_count() {
+            return;
+        }
+
+        // make sure that there is something to show while sliding in
+        self.canvas().prefetch_page(target);
+
+        self.canvas().set_single_page(Some(target));
+        self.viewport().fit_page();
+
+        let offset = self.viewport().width() as f64 * SLIDE_DISTANCE;
+        self.canvas()
+            .slide_in(if forward { offset } else { -offset });
+
+        // give the audience the full time to look at the new page
+        if self.presentation.get().is_some() {
+            self.restart_presentation_timer();
+        }
+    }
+
+    /// Flip pages in single-page mode with horizontal swipes on touchscreens
+    /// and touchpads.
+    fn setup_page_swipe(&self, viewport: &ViewportWidget) -> Vec<gtk::EventController> {
+        let swipe = gtk::GestureSwipe::builder()
+            .name("page_swipe_gesture")
+            .propagation_phase(gtk::PropagationPhase::Capture)
+            .touch_only(true)
+            .build();
+
+        swipe.connect_swipe(clone!(@weak self as win => move |_, vx, vy| {
+            if vx.abs() > SWIPE_MIN_VELOCITY && vx.abs() > 2.0 * vy.abs() {
+                win.flip_page(vx < 0.0);
+            }
+        }));
+
+        viewport.add_controller(swipe.clone());
+
+        let scroll = gtk::EventControllerScroll::builder()
+            .name("page_swipe_scroll_controller")
+            .propagation_phase(gtk::PropagationPhase::Capture)
+            .flags(gtk::EventControllerScrollFlags::HORIZONTAL)
+            .build();
+
+        scroll.connect_scroll_begin(clone!(@weak self as win => move |_| {
+            win.swipe_dx.set(Some(0.0));
+        }));
+
+        scroll.connect_scroll(
+            clone!(@weak self as win => @default-return gtk::Inhibit(false),
+                move |ctrl, dx, _| {
+                    // only handle touchpad swipes, not scroll wheels
+                    if !win.is_single_page() || ctrl.unit() != gdk::ScrollUnit::Surface {
+                        return gtk::Inhibit(false);
+                    }
+
+                    if let Some(acc) = win.swipe_dx.get() {
+                        let acc = acc + dx;
+
+                        if acc.abs() > SWIPE_MIN_DISTANCE {
+                            win.swipe_dx.set(None);
+                            win.flip_page(acc > 0.0);
+                        } else {
+                            win.swipe_dx.set(Some(acc));
+                        }
+                    }
+
+                    gtk::Inhibit(true)
+                }
+            ),
+        );
+
+        scroll.connect_scroll_end(clone!(@weak self as win => move |_| {
+            win.swipe_dx.set(None);
+        }));
+
+        viewport.add_controller(scroll.clone());
+
+        vec![swipe.upcast(), scroll.upcast()]
+    }
+
+    /// Search the document for the text of the search entry, replacing the
+    /// current search. Pages are searched starting at the current one.
+    fn start_search(&self) {
+        self.search_current.set(None);
+
+        let query = self.search_entry.text();
+        let doc = self.canvas().document();
+        let executor = self.canvas().executor();
+
+        let (doc, executor) = match (doc, executor) {
+            (Some(doc), Some(executor)) if !query.is_empty() => (doc, executor),
+            _ => {
+                self.stop_search();
+                return;
+            }
+        };
+
+        let first = match self.canvas().viewport_page_rect() {
+            Some((page, _)) => page,
+            None => 0,
+        };
+
+        // update results as pages are being searched
+        let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+
+        receiver.attach(
+            None,
+            clone!(@weak self as win => @default-return glib::Continue(false), move |_| {
+                win.update_search_results();
+                glib::Continue(true)
+            }),
+        );
+
+        let monitor = SearchMonitor { sender };
+        let search = Search::start(&executor, &doc, &query, first, monitor);
+
+        self.search.replace(Some(search));
+        self.update_search_results();
+    }
+
+    /// Cancel the current search and remove its results.
+    fn stop_search(&self) {
+        self.search.replace(None);
+        self.search_current.set(None);
+        self.update_search_results();
+    }
+
+    fn update_search_results(&self) {
+        let matches = match self.search.borrow().as_ref() {
+            Some(search) => search.matches(),
+            None => {
+                self.search_label.set_label("");
+                self.canvas().set_search_results(Vec::new(), None);
+                self.canvas().set_accessible_focus(None);
+                self.update_scroll_marks();
+                return;
+            }
+        };
+
+        // show the first match once found
+        if self.search_current.get().is_none() && !matches.is_empty() {
+            self.search_step(true);
+            return;
+        }
+
+        let n = matches.len();
+        let current = self
+            .search_current
+            .get()
+            .and_then(|c| search_index(&matches, c));
+        let done = self.search.borrow().as_ref().map(|s| s.is_done()) == Some(true);
+
+        let label = match current {
+            Some(i) => format!("{} of {n}", i + 1),
+            None if n == 1 => "1 match".to_owned(),
+            None => format!("{n} matches"),
+        };
+        let label = if done { label } else { format!("{label}…") };
+
+        self.search_label.set_label(&label);
+
+        // announce the result being shown
+        let focus = current.map(|i| {
+            let page = self.page_label(matches[i].page);
+            let query = self.search_entry.text();
+
+            format!("Result {} of {n} on page {page}: {query}", i + 1)
+        });
+        self.canvas().set_accessible_focus(focus);
+
+        self.canvas().set_search_results(matches, current);
+        self.update_scroll_marks();
+    }
+
+    /// Show the next or previous search result, starting at the current page
+    /// if no result is shown yet.
+    fn search_step(&self, forward: bool) {
+        let matches = match self.search.borrow().as_ref() {
+            Some(search) => search.matches(),
+            None => return,
+        };
+
+        let n = matches.len();
+        if n == 0 {
+            return;
+        }
+
+        let current = self
+            .search_current
+            .get()
+            .and_then(|c| search_index(&matches, c));
+        let page = match self.canvas().viewport_page_rect() {
+            Some((page, _)) => page,
+            None => 0,
+        };
+
+        let index = match current {
+            Some(i) if forward => (i + 1) % n,
+            Some(i) => (i + n - 1) % n,
+            None if forward => matches.iter().position(|m| m.page >= page).unwrap_or(0),
+            None => matches
+                .iter()
+                .rposition(|m| m.page <= page)
+                .unwrap_or(n - 1),
+        };
+
+        self.show_search_result(&matches, index);
+    }
+
+    /// Show the search result closest to the given vertical position in
+    /// canvas coordinates, e.g. of its mark along the scrollbar.
+    fn show_search_result_at(&self, position: f64) {
+        let matches = match self.search.borrow().as_ref() {
+            Some(search) => search.matches(),
+            None => return,
+        };
+
+        let index = matches
+            .iter()
+            .enumerate()
+            .filter_map(|(i, m)| Some((i, self.search_result_position(m)?)))
+            .min_by(|(_, a), (_, b)| (a - position).abs().total_cmp(&(b - position).abs()))
+            .map(|(i, _)| i);
+
+        if let Some(index) = index {
+            self.show_search_result(&matches, index);
+        }
+    }
+
+    /// Show the search result with the given index, revealing it on the
+    /// canvas.
+    fn show_search_result(&self, matches: &[SearchMatch], index: usize) {
+        let m = &matches[index];
+        let nth = matches[..index].iter().filter(|o| o.page == m.page).count();
+        self.search_current.set(Some((m.page, nth)));
+
+        if let Some(rect) = m.bounds() {
+            self.canvas().reveal_page_rect(m.page, &rect);
+        }
+
+        self.update_search_results();
+    }
+
+    /// Vertical position of the center of the given search result, in canvas
+    /// coordinates.
+    fn search_result_position(&self, m: &SearchMatch) -> Option<f64> {
+        let rect = self.canvas().page_to_canvas(m.page, &m.bounds()?)?;
+        Some(rect.offs.y + rect.size.y / 2.0)
+    }
+
+    /// Search all open documents in addition to the current one, listing the
+    /// results per document in the sidebar.
+    fn set_search_all(&self, enabled: bool) {
+        self.search_all.set(enabled);
+
+        if let Some(action) = self.obj().lookup_action("search-all-documents") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_state(enabled.to_variant());
+            }
+        }
+
+        if enabled {
+            self.search_bar.set_search_mode(true);
+            self.sidebar.set_visible_child_name("search");
+            self.flap.set_reveal_flap(true);
+        }
+
+        self.start_global_search();
+    }
+
+    /// Search all open documents for the text of the search entry if enabled,
+    /// replacing the current search through them. Each document is searched
+    /// on its own executor.
+    fn start_global_search(&self) {
+        self.global_search.borrow_mut().clear();
+
+        let query = self.search_entry.text();
+        if !self.search_all.get() || !self.search_bar.is_search_mode() || query.is_empty() {
+            self.update_global_search_results();
+            return;
+        }
+
+        // update results as pages are being searched
+        let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+
+        receiver.attach(
+            None,
+            clone!(@weak self as win => @default-return glib::Continue(false), move |_| {
+                win.update_global_search_results();
+                glib::Continue(true)
+            }),
+        );
+
+        let current = self.view.borrow().clone();
+
+        let searches = self
+            .views()
+            .into_iter()
+            .filter_map(|view| {
+                let canvas = view.canvas();
+                let doc = canvas.document()?;
+                let executor = canvas.executor()?;
+
+                // executors of hidden views are parked once suspended, keep
+                // them running until their documents have been searched
+                if view != current {
+                    view.resume();
+                }
+
+                let monitor = SearchMonitor {
+                    sender: sender.clone(),
+                };
+                let search = Search::start(&executor, &doc, &query, 0, monitor);
+
+                Some((view, search))
+            })
+            .collect();
+
+        self.global_search.replace(searches);
+        self.update_global_search_results();
+    }
+
+    /// Stop searching the given view as part of the search through all open
+    /// documents, e.g. after its tab has been closed.
+    fn remove_global_search(&self, view: &DocumentView) {
+        self.global_search.borrow_mut().retain(|(v, _)| v != view);
+        self.update_global_search_results();
+    }
+
+    /// List the results of the search through all open documents in the
+    /// sidebar, grouped per document.
+    fn update_global_search_results(&self) {
+        while let Some(row) = self.search_results.first_child() {
+            self.search_results.remove(&row);
+        }
+
+        let current = self.view.borrow().clone();
+        let tabs = self.tab_pages();
+
+        for (view, search) in self.global_search.borrow().iter() {
+            let done = search.is_done();
+
+            // release hidden views again once searched
+            if done && *view != current {
+                view.schedule_suspend(AUTOSUSPEND_DELAY);
+            }
+
+            let matches = search.matches();
+            if matches.is_empty() {
+                continue;
+            }
+
+            let title = tabs
+                .iter()
+                .find(|tab| tab.child() == *view.upcast_ref::<gtk::Widget>())
+                .map(|tab| tab.title().to_string())
+                .unwrap_or_default();
+
+            let n = matches.len();
+            let subtitle = match n {
+                1 => "1 match".to_owned(),
+                n => format!("{n} matches"),
+            };
+            let subtitle = if done {
+                subtitle
+            } else {
+                format!("{subtitle}…")
+            };
+
+            let group = adw::ExpanderRow::builder()
+                .title(glib::markup_escape_text(&title))
+                .subtitle(subtitle)
+                .expanded(true)
+                .build();
+
+            for (i, m) in matches.iter().enumerate().take(GLOBAL_SEARCH_RESULTS_LIMIT) {
+                let nth = matches[..i].iter().filter(|o| o.page == m.page).count();
+
+                let label = if *view == current {
+                    self.page_label(m.page)
+                } else {
+                    view.page_label(m.page)
+                        .unwrap_or_else(|| format!("{}", m.page + 1))
+                };
+
+                let row = adw::ActionRow::builder()
+                    .title(glib::markup_escape_text(&format!("Page {label}")))
+                    .activatable(true)
+                    .build();
+
+                let (page, rect) = (m.page, m.bounds());
+                row.connect_activated(clone!(@weak self as win, @weak view => move |_| {
+                    win.show_global_search_result(&view, page, nth, rect);
+                }));
+
+                group.add_row(&row);
+            }
+
+            self.search_results.append(&group);
+        }
+    }
+
+    /// Show the result of the search through all open documents given by its
+    /// page and its index among the results on that page, switching to the
+    /// tab of its document first.
+    fn show_global_search_result(
+        &self,
+        view: &DocumentView,
+        page: usize,
+        nth: usize,
+        rect: Option<Rect<f64>>,
+    ) {
+        let tab = self
+            .tab_pages()
+            .into_iter()
+            .find(|tab| tab.child() == *view.upcast_ref::<gtk::Widget>());
+
+        // selecting the tab shows its document right away
+        if let Some(tab) = tab {
+            self.tab_view.set_selected_page(&tab);
+        }
+
+        if *self.view.borrow() != *view {
+            return;
+        }
+
+        self.search_current.set(Some((page, nth)));
+
+        if let Some(rect) = rect {
+            self.canvas().reveal_page_rect(page, &rect);
+        }
+
+        self.update_search_results();
+    }
+
+    /// Move a caret through the text with the arrow keys in caret navigation
+    /// mode, selecting text while Shift is held.
+    fn setup_caret_navigation(&self, canvas: &CanvasWidget) -> gtk::EventController {
+        let keys = gtk::EventControllerKey::new();
+
+        keys.connect_key_pressed(
+            clone!(@weak self as win => @default-return gtk::Inhibit(false),
+                move |_, key, _, state| {
+                    if !win.is_caret_navigation_enabled() {
+                        return gtk::Inhibit(false);
+                    }
+
+                    let word = state.contains(gdk::ModifierType::CONTROL_MASK);
+                    let extend = state.contains(gdk::ModifierType::SHIFT_MASK);
+
+                    let motion = match key {
+                        gdk::Key::Left if word => Motion::WordLeft,
+                        gdk::Key::Right if word => Motion::WordRight,
+                        gdk::Key::Home if word => Motion::PageStart,
+                        gdk::Key::End if word => Motion::PageEnd,
+                        gdk::Key::Left => Motion::Left,
+                        gdk::Key::Right => Motion::Right,
+                        gdk::Key::Up => Motion::Up,
+                        gdk::Key::Down => Motion::Down,
+                        gdk::Key::Home => Motion::LineStart,
+                        gdk::Key::End => Motion::LineEnd,
+                        gdk::Key::c | gdk::Key::C if word => {
+                            return gtk::Inhibit(win.copy_caret_selection());
+                        }
+                        gdk::Key::Escape => return gtk::Inhibit(win.clear_caret_selection()),
+                        _ => return gtk::Inhibit(false),
+                    };
+
+                    win.move_caret(motion, extend);
+                    gtk::Inhibit(true)
+                }
+            ),
+        );
+
+        canvas.add_controller(keys.clone());
+        keys.upcast()
+    }
+
+    fn is_caret_navigation_enabled(&self) -> bool {
+        self.obj()
+            .lookup_action("caret-navigation")
+            .and_then(|action| action.state())
+            .and_then(|state| state.get::<bool>())
+            .unwrap_or(false)
+    }
+
+    fn set_caret_navigation(&self, enabled: bool) {
+        if let Some(action) = self.obj().lookup_action("caret-navigation") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_state(enabled.to_variant());
+            }
+        }
+
+        self.caret.set(None);
+        self.canvas().set_caret(None);
+
+        // place the caret at the start of the current page
+        if enabled {
+            self.canvas().grab_focus();
+            self.move_caret(Motion::PageStart, false);
+        }
+    }
+
+    /// Move the caret, continuing on the adjacent page if it leaves the
+    /// current one. Without a caret, place it at the start of the current
+    /// page instead.
+    ///
+    /// Selections are limited to a single page: Moving the caret to another
+    /// page while extending the selection starts a new one there.
+    fn move_caret(&self, motion: Motion, extend: bool) {
+        let app = self
+            .obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok());
+
+        let (app, doc) = match (app, self.canvas().document()) {
+            (Some(app), Some(doc)) => (app, doc),
+            _ => return,
+        };
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as win => async move {
+            let current = win.caret.get();
+
+            let caret = match current {
+                Some(caret) => caret,
+                None => match win.canvas().viewport_page_rect() {
+                    Some((page, _)) => Caret { page, pos: 0, anchor: None },
+                    None => return,
+                },
+            };
+
+            let text = match win.page_caret_text(&app, &doc, caret.page).await {
+                Some(text) => text,
+                None => return,
+            };
+
+            let step = match current {
+                Some(_) => text.step(caret.pos, motion),
+                None => Some(0),
+            };
+
+            let (page, pos, anchor, text) = match step {
+                Some(pos) => {
+                    let anchor = extend.then(|| caret.anchor.unwrap_or(caret.pos));
+                    (caret.page, pos, anchor, text)
+                }
+                None => {
+                    let page = if motion.is_forward() {
+                        caret.page + 1
+                    } else {
+                        match caret.page.checked_sub(1) {
+                            Some(page) => page,
+                            None => return,
+                        }
+                    };
+
+                    if page >= win.canvas().page_count() {
+                        return;
+                    }
+
+                    let text = match win.page_caret_text(&app, &doc, page).await {
+                        Some(text) => text,
+                        None => return,
+                    };
+
+                    let pos = if motion.is_forward() { 0 } else { text.len() };
+                    (page, pos, extend.then_some(pos), text)
+                }
+            };
+
+            // drop outdated moves, e.g. when keys are pressed faster than
+            // pages are loaded
+            if win.caret.get() != current {
+                return;
+            }
+
+            let caret = Caret { page, pos, anchor };
+            win.caret.set(Some(caret));
+            win.show_caret(&caret, &text);
+        }));
+    }
+
+    fn show_caret(&self, caret: &Caret, text: &CaretText) {
+        let rect = text.caret_rect(caret.pos);
+
+        let selection = caret
+            .selection()
+            .map(|range| text.selection_rects(&range))
+            .unwrap_or_default();
+
+        // show the caret on pages without text at the top left corner
+        let rect = rect.unwrap_or_else(|| Rect::new(point![0.0, 0.0], vector![0.0, 0.0]));
+
+        self.canvas().set_caret(Some(CaretMarks {
+            page: caret.page,
+            caret: rect,
+            selection,
+        }));
+
+        let focus = caret.selection().map(|range| text.text(&range));
+        self.canvas().set_accessible_focus(focus);
+
+        self.canvas().reveal_page_rect(caret.page, &rect);
+    }
+
+    /// Copy the text selected in caret navigation mode to the clipboard.
+    /// Returns `false` if no text is selected.
+    fn copy_caret_selection(&self) -> bool {
+        let caret = match self.caret.get() {
+            Some(caret) => caret,
+            None => return false,
+        };
+
+        let (range, text) = match (
+            caret.selection(),
+            self.caret_texts.borrow().get(&caret.page),
+        ) {
+            (Some(range), Some(text)) => (range, text.clone()),
+            _ => return false,
+        };
+
+        self.obj().clipboard().set_text(&text.text(&range));
+        true
+    }
+
+    /// Copy the selected text to the clipboard, i.e. the text selected in
+    /// caret navigation mode or, if there is none, with the pointer.
+    fn copy_selection(&self) {
+        if self.copy_caret_selection() {
+            return;
+        }
+
+        if let Some(text) = self.canvas().selected_text() {
+            self.obj().clipboard().set_text(&text);
+        }
+    }
+
+    /// Clear the selection of caret navigation mode. Returns `false` if no
+    /// text is selected.
+    fn clear_caret_selection(&self) -> bool {
+        let caret = match self.caret.get() {
+            Some(caret) if caret.selection().is_some() => caret,
+            _ => return false,
+        };
+
+        let caret = Caret {
+            anchor: None,
+            ..caret
+        };
+        self.caret.set(Some(caret));
+
+        let text = self.caret_texts.borrow().get(&caret.page).cloned();
+        if let Some(text) = text {
+            self.show_caret(&caret, &text);
+        }
+
+        true
+    }
+
+    /// Text of the given page for caret navigation. Returns `None` if the
+    /// document has changed in the meantime.
+    async fn page_caret_text(
+        &self,
+        app: &App,
+        doc: &Document,
+        page: usize,
+    ) -> Option<Rc<CaretText>> {
+        if let Some(text) = self.caret_texts.borrow().get(&page) {
+            return Some(text.clone());
+        }
+
+        let path = self.path.borrow().clone();
+
+        let doc = doc.clone();
+        let text = app
+            .spawn_blocking_with_priority(TaskPriority::High, move || CaretText::load(&doc, page))
+            .await;
+
+        if *self.path.borrow() != path {
+            return None;
+        }
+
+        let text = Rc::new(text);
+        self.caret_texts.borrow_mut().insert(page, text.clone());
+
+        Some(text)
+    }
+
+    fn current_location(&self) -> Option<Location> {
+        self.canvas()
+            .viewport_page_rect()
+            .map(|(page, rect)| Location { page, rect })
+    }
+
+    pub fn follow_link(&self, target: Location) {
+        if let Some(current) = self.current_location() {
+            self.history.borrow_mut().push(current);
+        }
+
+        self.canvas().scroll_to_page_rect(target.page, &target.rect);
+        self.update_history_actions();
+    }
+
+    /// Show the target of the given outline entry, at the zoom factor of the
+    /// entry, if it specifies one.
+    fn go_to_bookmark(&self, entry: &Bookmark) {
+        let dest = match entry.dest {
+            Some(dest) => dest,
+            None => return,
+        };
+
+        let page = dest.page as usize;
+
+        let page_size = match self.canvas().page_size(page) {
+            Some(size) => size,
+            None => return,
+        };
+
+        if let Some(current) = self.current_location() {
+            self.history.borrow_mut().push(current);
+        }
+
+        if let (Some(zoom), Some(scale)) = (dest.zoom, self.actual_size_scale()) {
+            self.viewport().set_scale(scale * zoom as f64);
+        }
+
+        // PDF coordinates have their origin at the bottom left corner
+        let x = dest.left.map(|left| left as f64);
+        let y = match dest.top {
+            Some(top) => page_size.y - top as f64,
+            None => 0.0,
+        };
+
+        self.canvas().scroll_to_page_point(page, x, y);
+        self.update_history_actions();
+
+        // get the sidebar out of the way if it covers the document
+        if self.flap.is_folded() {
+            self.flap.set_reveal_flap(false);
+        }
+    }
+
+    /// Show the top of the given page, e.g. after its thumbnail has been
+    /// clicked.
+    fn go_to_page(&self, page: usize) {
+        if let Some(current) = self.current_location() {
+            self.history.borrow_mut().push(current);
+        }
+
+        if self.is_single_page() {
+            self.canvas().set_single_page(Some(page));
+            self.viewport().fit_page();
+        } else {
+            self.canvas().scroll_to_page(page);
+        }
+
+        self.update_history_actions();
+
+        if self.flap.is_folded() {
+            self.flap.set_reveal_flap(false);
+        }
+    }
+
+    /// Show page thumbnails in the sidebar, or hide the sidebar if they are
+    /// already shown.
+    fn toggle_thumbnails(&self) {
+        let shown = self.flap.reveals_flap()
+            && self.sidebar.visible_child_name().as_deref() == Some("pages");
+
+        self.sidebar.set_visible_child_name("pages");
+        self.flap.set_reveal_flap(!shown);
+    }
+
+    /// Highlight the thumbnail of the page closest to the viewport center.
+    fn update_current_thumbnail(&self) {
+        if let Some((page, _)) = self.canvas().viewport_page_rect() {
+            self.thumbnails_view.set_current_page(page);
+        }
+    }
+
+    /// Show the page closest to the viewport center in the header bar.
+    fn update_page_indicator(&self) {
+        let page = match self.canvas().viewport_page_rect() {
+            Some((page, _)) => page,
+            None => return,
+        };
+
+        let count = self.canvas().page_count();
+        let label = self.page_label(page);
+        let number = format!("{}", page + 1);
+
+        // page labels may differ from page numbers, e.g. for front matter
+        let text = if label == number {
+            format!("/ {count}")
+        } else {
+            format!("({number} / {count})")
+        };
+        self.page_count_label.set_text(&text);
+
+        // don't replace what is being typed
+        if !self
+            .page_entry
+            .state_flags()
+            .contains(gtk::StateFlags::FOCUS_WITHIN)
+        {
+            self.page_entry.set_text(&label);
+        }
+
+        self.update_accessible_page(page);
+    }
+
+    /// Expose the text of the given page to assistive technologies, loading
+    /// it in the background unless already exposed.
+    fn update_accessible_page(&self, page: usize) {
+        if self.accessible_page.replace(Some(page)) == Some(page) {
+            return;
+        }
+
+        let path = self.path.borrow().clone();
+        let doc = self.canvas().document();
+
+        let app = self
+            .obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok());
+
+        let (path, doc, app) = match (path, doc, app) {
+            (Some(path), Some(doc), Some(app)) => (path, doc, app),
+            _ => return,
+        };
+
+        let task = app.spawn_blocking_with_priority(TaskPriority::High, move || {
+            accessible::page_text(&doc, page)
+        });
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as win => async move {
+            let text = task.await;
+
+            // the document or page may have changed in the meantime
+            let current = win.path.borrow().as_ref() == Some(&path);
+            if !current || win.accessible_page.get() != Some(page) {
+                return;
+            }
+
+            let text = text.unwrap_or_else(|err| {
+                tracing::warn!(page, error=%err, "failed to extract page text");
+                String::new()
+            });
+
+            let count = win.canvas().page_count();
+            let label = format!("Page {} of {count}", win.page_label(page));
+
+            win.canvas().set_accessible_page(label, text);
+        }));
+    }
+
+    /// Find the page with the given label or, failing that, page number.
+    fn find_page(&self, text: &str) -> Option<usize> {
+        let text = text.trim();
+        let count = self.canvas().page_count();
+
+        let labels: Vec<_> = (0..count).map(|page| self.page_label(page)).collect();
+
+        // prefer exact matches, then ignore case, e.g. "IV" for "iv"
+        let exact = labels.iter().position(|label| label == text);
+        let folded = || {
+            let text = text.to_lowercase();
+            labels.iter().position(|label| label.to_lowercase() == text)
+        };
+
+        if let Some(page) = exact.or_else(folded) {
+            return Some(page);
+        }
+
+        text.parse::<usize>()
+            .ok()
+            .filter(|number| (1..=count).contains(number))
+            .map(|number| number - 1)
+    }
+
+    /// Go to the page entered in the header bar.
+    fn go_to_entered_page(&self) {
+        let text = self.page_entry.text();
+
+        match self.find_page(&text) {
+            Some(page) => {
+                self.go_to_page(page);
+                self.canvas().grab_focus();
+            }
+            None => {
+                let toast = adw::Toast::new(&format!("No page “{}”", text.trim()));
+                self.overlay.add_toast(toast);
+            }
+        }
+
+        self.update_page_indicator();
+    }
+
+    /// Go to the top of the next or previous row of pages, or to the top of
+    /// the current page if it has been scrolled into. Pages are flipped in
+    /// single-page mode.
+    fn step_page(&self, forward: bool) {
+        if self.is_single_page() {
+            self.flip_page(forward);
+            return;
+        }
+
+        let canvas = self.canvas();
+        let (current, visible) = match canvas.viewport_page_rect() {
+            Some(found) => found,
+            None => return,
+        };
+
+        let rect = match canvas.page_rect(current) {
+            Some(rect) => rect,
+            None => return,
+        };
+
+        let count = canvas.page_count();
+        let target = if forward {
+            // first page below the current one, the next page in layouts
+            // without rows below each other
+            (current + 1..count)
+                .find(|i| {
+                    canvas
+                        .page_rect(*i)
+                        .is_some_and(|r| r.offs.y >= rect.offs.y + rect.size.y)
+                })
+                .unwrap_or(current + 1)
+        } else if visible.offs.y > PAGE_STEP_TOLERANCE {
+            current
+        } else {
+            // last page above the current one
+            (0..current)
+                .rev()
+                .find(|i| {
+                    canvas
+                        .page_rect(*i)
+                        .is_some_and(|r| r.offs.y + r.size.y <= rect.offs.y)
+                })
+                .unwrap_or(current.wrapping_sub(1))
+        };
+
+        if target < count {
+            canvas.scroll_to_page(target);
+        }
+    }
+
+    /// Label all visible links for following them via keyboard.
+    fn show_link_hints(&self) {
+        self.canvas().grab_focus();
+
+        if !self.canvas().show_link_hints() {
+            self.overlay.add_toast(adw::Toast::new("No links visible"));
+        }
+    }
+
+    /// Open a link of the document to an external resource.
+    fn open_uri(&self, uri: String) {
+        let action = ExternalAction::OpenUri(uri.clone());
+
+        self.run_external(action, move |win| {
+            let launcher = gtk::UriLauncher::new(&uri);
+
+            launcher.launch(
+                Some(&*win.obj()),
+                None::<&gtk::gio::Cancellable>,
+                |result| {
+                    if let Err(err) = result {
+                        tracing::warn!(error=%err, "failed to open link");
+                    }
+                },
+            );
+        });
+    }
+
+    /// Run an action of the document reaching outside of it, asking the user
+    /// whether to trust the document first. The decision is remembered for
+    /// the document.
+    fn run_external<F>(&self, action: ExternalAction, run: F)
+    where
+        F: FnOnce(&Self) + 'static,
+    {
+        let message = format!("This document wants to {}", action.description());
+
+        match self.trusted.get() {
+            Some(true) => run(self),
+            Some(false) => {
+                let toast = adw::Toast::new(&format!("{message}, blocked as it is not trusted"));
+                self.overlay.add_toast(toast);
+            }
+            None => {
+                let dialog = adw::MessageDialog::new(
+                    Some(&*self.obj()),
+                    Some("Trust This Document?"),
+                    Some(&format!(
+                        "{message}. Only allow this for documents from sources you trust. \
+                         Your decision is remembered for this document."
+                    )),
+                );
+
+                dialog.add_responses(&[("block", "Block"), ("allow", "Allow")]);
+                dialog.set_response_appearance("allow", adw::ResponseAppearance::Suggested);
+                dialog.set_default_response(Some("block"));
+                dialog.set_close_response("block");
+
+                let run = RefCell::new(Some(run));
+
+                dialog.connect_response(
+                    None,
+                    clone!(@weak self as win => move |_, response| {
+                        let trusted = response == "allow";
+                        win.decide_trust(trusted);
+
+                        if let Some(run) = run.take().filter(|_| trusted) {
+                            run(&win);
+                        }
+                    }),
+                );
+
+                dialog.present();
+            }
+        }
+    }
+
+    /// Update the trust state of the current document, without asking.
+    fn set_trusted(&self, trusted: Option<bool>) {
+        self.trusted.set(trusted);
+
+        if let Some(action) = self.obj().lookup_action("trust-document") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_state(trusted.unwrap_or(false).to_variant());
+            }
+        }
+    }
+
+    /// Trust or distrust the current document and remember the decision.
+    fn decide_trust(&self, trusted: bool) {
+        self.set_trusted(Some(trusted));
+
+        let (path, session) = match (self.path.borrow().clone(), self.session()) {
+            (Some(path), Some(session)) => (path, session),
+            _ => return,
+        };
+
+        let state = DocumentState {
+            trusted: Some(trusted),
+            ..Default::default()
+        };
+
+        session.set_document(&path, &state);
+
+        if let Err(err) = session.save() {
+            tracing::warn!(error=%err, "failed to save session state");
+        }
+    }
+
+    /// Whether pages should be dark unless overridden for the document, i.e.
+    /// if the desktop prefers dark styles or, if enabled, the night light is
+    /// active.
+    fn auto_dark_pages(&self) -> bool {
+        let follow_night_light = self
+            .session()
+            .map(|session| session.follow_night_light())
+            .unwrap_or(false);
+
+        let night_light = self
+            .night_light
+            .borrow()
+            .as_ref()
+            .map(|night_light| night_light.is_active())
+            .unwrap_or(false);
+
+        adw::StyleManager::default().is_dark() || (follow_night_light && night_light)
+    }
+
+    fn set_page_style(&self, style: PageStyle) {
+        self.page_style.set(style);
+        self.update_page_style();
+    }
+
+    fn update_page_style(&self) {
+        let dark = self.page_style.get().is_dark(self.auto_dark_pages());
+        let night_colors = self
+            .session()
+            .map(|session| session.night_colors())
+            .unwrap_or(false);
+
+        // dark pages are either rendered with night colors or shown inverted
+        self.canvas().set_dark_mode(dark && !night_colors);
+        self.canvas().set_night_colors(dark && night_colors);
+
+        if let Some(action) = self.obj().lookup_action("dark-pages") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_state(dark.to_variant());
+            }
+        }
+    }
+
+    /// Throttle background rendering while running on battery or with power
+    /// saving enabled, and restore it otherwise.
+    fn update_power_mode(&self) {
+        let low_power = self
+            .power
+            .borrow()
+            .as_ref()
+            .map(|power| power.is_low_power())
+            .unwrap_or(false);
+
+        tracing::debug!(low_power, "updating power mode");
+
+        for view in self.views() {
+            view.canvas().set_low_power(low_power);
+        }
+    }
+
+    /// Suspend rendering once the window has been hidden or minimized for a
+    /// while, releasing its caches, and resume it when shown again.
+    fn update_autosuspend(&self) {
+        let obj = self.obj();
+
+        let minimized = obj.is_realized()
+            && obj
+                .surface()
+                .downcast::<gdk::Toplevel>()
+                .map(|toplevel| toplevel.state().contains(gdk::ToplevelState::MINIMIZED))
+                .unwrap_or(false);
+
+        let view = self.view.borrow().clone();
+
+        if !obj.is_visible() || minimized {
+            view.schedule_suspend(AUTOSUSPEND_DELAY);
+        } else {
+            view.resume();
+        }
+    }
+
+    fn is_font_diagnostics_enabled(&self) -> bool {
+        self.obj()
+            .lookup_action("font-diagnostics")
+            .and_then(|action| action.state())
+            .and_then(|state| state.get::<bool>())
+            .unwrap_or(false)
+    }
+
+    fn set_font_diagnostics(&self, enabled: bool) {
+        if let Some(action) = self.obj().lookup_action("font-diagnostics") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_state(enabled.to_variant());
+            }
+        }
+
+        self.font_page.set(None);
+        self.canvas().set_font_report(None);
+        self.update_font_diagnostics();
+    }
+
+    /// Show the substituted fonts of the current page, if enabled and not
+    /// shown already.
+    fn update_font_diagnostics(&self) {
+        if !self.is_font_diagnostics_enabled() {
+            return;
+        }
+
+        let page = match self.canvas().viewport_page_rect() {
+            Some((page, _)) => page,
+            None => return,
+        };
+
+        if self.font_page.replace(Some(page)) == Some(page) {
+            return;
+        }
+
+        let app = self
+            .obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok());
+
+        let (app, doc) = match (app, self.canvas().document()) {
+            (Some(app), Some(doc)) => (app, doc),
+            _ => return,
+        };
+
+        let path = self.path.borrow().clone();
+
+        let task = app.spawn_blocking_with_priority(TaskPriority::High, move || {
+            FontReport::analyze(&doc, page)
+        });
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as win => async move {
+            let report = task.await;
+
+            // drop outdated results, e.g. after scrolling to another page
+            if *win.path.borrow() != path || win.font_page.get() != Some(page) {
+                return;
+            }
+
+            match report {
+                Ok(report) => win.canvas().set_font_report(Some((page, report))),
+                Err(err) => {
+                    tracing::warn!(page, error=%err, "failed to inspect page fonts");
+                    win.canvas().set_font_report(None);
+                }
+            }
+        }));
+    }
+
+    /// Show the pages of the current document with light or dark colors,
+    /// overriding the automatic choice only if it differs.
+    fn decide_page_style(&self, dark: bool) {
+        let style = match (dark, self.auto_dark_pages()) {
+            (dark, auto) if dark == auto => PageStyle::Auto,
+            (true, _) => PageStyle::Dark,
+            (false, _) => PageStyle::Light,
+        };
+
+        self.set_page_style(style);
+    }
+
+    fn set_night_colors(&self, enabled: bool) {
+        if let Some(session) = self.session() {
+            session.set_night_colors(enabled);
+        }
+
+        if let Some(action) = self.obj().lookup_action("night-colors") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_state(enabled.to_variant());
+            }
+        }
+
+        self.update_page_style();
+    }
+
+    fn set_page_layout(&self, kind: LayoutKind) {
+        if let Some(session) = self.session() {
+            session.set_page_layout(kind);
+        }
+
+        if let Some(action) = self.obj().lookup_action("layout") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_state(kind.as_str().to_variant());
+            }
+        }
+
+        self.canvas().set_layout(kind);
+    }
+
+    fn set_follow_night_light(&self, follow: bool) {
+        if let Some(session) = self.session() {
+            session.set_follow_night_light(follow);
+        }
+
+        if let Some(action) = self.obj().lookup_action("follow-night-light") {
+            if let Ok(action) = action.downcast::<SimpleAction>() {
+                action.set_state(follow.to_variant());
+            }
+        }
+
+        self.update_page_style();
+    }
+
+    pub fn go_back(&self) {
+        let current = match self.current_location() {
+            Some(current) => current,
+            None => return,
+        };
+
+        let target = self.history.borrow_mut().back(current);
+        if let Some(target) = target {
+            self.canvas().scroll_to_page_rect(target.page, &target.rect);
+        }
+
+        self.update_history_actions();
+    }
+
+    pub fn go_forward(&self) {
+        let current = match self.current_location() {
+            Some(current) => current,
+            None => return,
+        };
+
+        let target = self.history.borrow_mut().forward(current);
+        if let Some(target) = target {
+            self.canvas().scroll_to_page_rect(target.page, &target.rect);
+        }
+
+        self.update_history_actions();
+    }
+
+    fn update_history_actions(&self) {
+        let history = self.history.borrow();
+
+        let actions = [
+            ("go-back", history.can_go_back()),
+            ("go-forward", history.can_go_forward()),
+        ];
+
+        for (name, enabled) in actions {
+            let action = self
+                .obj()
+                .lookup_action(name)
+                .and_then(|a| a.downcast::<SimpleAction>().ok());
+
+            if let Some(action) = action {
+                action.set_enabled(enabled);
+            }
+        }
+    }
+
+    fn update_scroll_marks(&self) {
+        let outline = self.outline.borrow();
+        let mut marks = Vec::new();
+
+        let mark = |entry: &Bookmark, kind| {
+            let rect = self.canvas().page_rect(entry.dest?.page as _)?;
+
+            Some(ScrollMark {
+                position: rect.offs.y,
+                kind,
+            })
+        };
+
+        // mark chapters and their direct sections
+        for chapter in outline.iter() {
+            marks.extend(mark(chapter, ScrollMarkKind::Chapter));
+
+            for section in chapter.children.iter() {
+                marks.extend(mark(section, ScrollMarkKind::Section));
+            }
+        }
+
+        // mark search results, drawn on top of outline entries
+        if let Some(search) = self.search.borrow().as_ref() {
+            let results = search.matches().into_iter().filter_map(|m| {
+                Some(ScrollMark {
+                    position: self.search_result_position(&m)?,
+                    kind: ScrollMarkKind::SearchResult,
+                })
+            });
+
+            marks.extend(results);
+        }
+
+        self.viewport().set_scroll_marks(marks);
+    }
+
+    fn update_scroll_heat(&self) {
+        let timeline = self.timeline.borrow();
+
+        let heat = timeline
+            .iter()
+            .flat_map(|timeline| timeline.intensities().into_iter().enumerate())
+            .filter_map(|(page, intensity)| {
+                let rect = self.canvas().page_rect(page)?;
+
+                Some(HeatRegion {
+                    start: rect.offs.y,
+                    end: rect.offs.y + rect.size.y,
+                    intensity,
+                })
+            })
+            .collect();
+
+        self.viewport().set_scroll_heat(heat);
+    }
+
+    /// Record the currently visible pages in the viewing timeline.
+    fn sample_timeline(&self) {
+        // only count time the user is likely looking at the document
+        if !self.obj().is_active() || !self.obj().is_visible() {
+            return;
+        }
+
+        let pages = self.canvas().visible_pages();
+
+        match self.timeline.borrow_mut().as_mut() {
+            Some(timeline) => timeline.record(pages, ViewingTimeline::SAMPLE_INTERVAL),
+            None => return,
+        }
+
+        self.update_scroll_heat();
+    }
+
+    fn set_eyedropper(&self, enabled: bool) {
+        if let Some(action) = self.obj().lookup_action("eyedropper") {
+            action.change_state(&enabled.to_variant());
+        }
+    }
+
+    fn set_pen(&self, enabled: bool) {
+        if let Some(action) = self.obj().lookup_action("pen") {
+            action.change_state(&enabled.to_variant());
+        }
+    }
+
+    /// Copy a color picked with the eyedropper to the clipboard as hex code.
+    fn color_picked(&self, color: gdk::RGBA) {
+        let to_u8 = |c: f32| (c * 255.0).round() as u8;
+        let (r, g, b) = (
+            to_u8(color.red()),
+            to_u8(color.green()),
+            to_u8(color.blue()),
+        );
+
+        let hex = format!("#{r:02x}{g:02x}{b:02x}");
+        self.obj().clipboard().set_text(&hex);
+
+        let toast = adw::Toast::new(&format!("Copied {hex} (rgb({r}, {g}, {b})) to clipboard"));
+        self.overlay.add_toast(toast);
+
+        // the eyedropper picks a single color
+        self.set_eyedropper(false);
+    }
+
+    /// Mark the selected text with annotations of the given kind, in the color
+    /// chosen in the header bar.
+    fn markup_selection(&self, kind: MarkupKind) {
+        let rgba = self.markup_color.rgba();
+        let to_u8 = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let color = Color::new_rgb(to_u8(rgba.red()), to_u8(rgba.green()), to_u8(rgba.blue()));
+
+        match self.canvas().markup_selection(kind, color) {
+            Ok(true) => {}
+            Ok(false) => {
+                let toast = adw::Toast::new("Select text to mark it up");
+                self.overlay.add_toast(toast);
+            }
+            Err(err) => {
+                tracing::warn!(?kind, error=%err, "failed to add markup annotation");
+
+                let toast = adw::Toast::new(&format!("Failed to add annotation: {err}"));
+                self.overlay.add_toast(toast);
+            }
+        }
+    }
+
+    /// Place a stamp with the given content on the next click on a page.
+    fn start_stamp(&self, content: StampContent) {
+        if self.canvas().document().is_none() {
+            return;
+        }
+
+        self.set_eyedropper(false);
+        self.set_pen(false);
+        self.canvas().set_stamp_tool(Some(content));
+
+        let toast = adw::Toast::new("Click on a page to place the stamp");
+        self.overlay.add_toast(toast);
+    }
+
+    /// Place an image stamp, e.g. of a signature, from the given file.
+    fn start_image_stamp(&self, file: File) {
+        let path = match file.path() {
+            Some(path) => path,
+            None => return,
+        };
+
+        // check the image upfront, the canvas only loads it when drawing
+        if let Err(err) = gdk::Texture::from_file(&file) {
+            let toast = adw::Toast::new(&format!("Failed to load image: {err}"));
+            toast.set_priority(adw::ToastPriority::High);
+            self.overlay.add_toast(toast);
+            return;
+        }
+
+        self.start_stamp(StampContent::Image(path));
+    }
+
+    /// Step to the next or previous text block in reading order, scrolling
+    /// through blocks larger than the viewport.
+    pub fn navigate_block(&self, forward: bool) {
+        let app = self
+            .obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok());
+
+        let (app, doc) = match (app, self.canvas().document()) {
+            (Some(app), Some(doc)) => (app, doc),
+            _ => return,
+        };
+
+        let page = match self.canvas().viewport_page_rect() {
+            Some((page, _)) => page,
+            None => return,
+        };
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as win => async move {
+            let blocks = match win.page_blocks(&app, &doc, page).await {
+                Some(blocks) => blocks,
+                None => return,
+            };
+
+            let view = match win.canvas().viewport_rect(page) {
+                Some(view) => view,
+                None => return,
+            };
+
+            let current = win
+                .block_cursor
+                .get()
+                .filter(|(p, _)| *p == page)
+                .map(|(_, index)| index);
+
+            let (page, index, rect) = match blocks::step(&blocks, current, &view, forward) {
+                Step::Show(index, rect) => (page, index, rect),
+                Step::AdjacentPage => {
+                    let page = if forward {
+                        page + 1
+                    } else {
+                        match page.checked_sub(1) {
+                            Some(page) => page,
+                            None => return,
+                        }
+                    };
+
+                    if page >= win.canvas().page_count() {
+                        return;
+                    }
+
+                    let blocks = match win.page_blocks(&app, &doc, page).await {
+                        Some(blocks) => blocks,
+                        None => return,
+                    };
+
+                    let index = if forward { 0 } else { blocks.len().saturating_sub(1) };
+                    let rect = match blocks.get(index) {
+                        Some(block) => blocks::align(block, &view.size, forward),
+                        None => return,
+                    };
+
+                    (page, index, rect)
+                }
+            };
+
+            win.block_cursor.set(Some((page, index)));
+            win.canvas().scroll_to_page_rect(page, &rect);
+        }));
+    }
+
+    /// Text blocks of the given page in reading order. Returns `None` if the
+    /// document has changed in the meantime.
+    async fn page_blocks(
+        &self,
+        app: &App,
+        doc: &Document,
+        page: usize,
+    ) -> Option<Rc<Vec<Rect<f64>>>> {
+        if let Some(blocks) = self.blocks.borrow().get(&page) {
+            return Some(blocks.clone());
+        }
+
+        let path = self.path.borrow().clone();
+
+        let doc = doc.clone();
+        let blocks = app
+            .spawn_blocking_with_priority(TaskPriority::High, move || blocks::detect(&doc, page))
+            .await;
+
+        if *self.path.borrow() != path {
+            return None;
+        }
+
+        let blocks = Rc::new(blocks);
+        self.blocks.borrow_mut().insert(page, blocks.clone());
+
+        Some(blocks)
+    }
+
+    /// Open a window showing the given region of a page at high resolution.
+    fn inspect_region(&self, page: usize, region: Rect<f64>) {
+        let doc = match self.canvas().document() {
+            Some(doc) => doc,
+            None => return,
+        };
+
+        let app = self
+            .obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok());
+
+        if let Some(app) = app {
+            InspectWindow::new(&*self.obj(), &app, doc, page, region).present();
+        }
+    }
+
+    pub fn show_preferences(&self) {
+        let app = match self.obj().application() {
+            Some(app) => app.downcast::<App>().ok(),
+            None => None,
+        };
+
+        if let Some(app) = app {
+            let prefs = PreferencesWindow::new(&*self.obj(), app.storage(), app.session());
+
+            // apply layout changes to all windows
+            prefs.connect_layout_changed(clone!(@weak app => move |_| {
+                for window in app.windows() {
+                    if let Ok(win) = window.downcast::<super::AppWindow>() {
+                        win.apply_layout_settings();
+                    }
+                }
+            }));
+
+            // apply tiling changes to all windows
+            prefs.connect_tiling_changed(clone!(@weak app => move |_| {
+                for window in app.windows() {
+                    if let Ok(win) = window.downcast::<super::AppWindow>() {
+                        win.apply_tiling_settings();
+                    }
+                }
+            }));
+
+            prefs.present();
+        }
+    }
+
+    /// Apply the page spacing and margins stored in the session to all
+    /// documents.
+    pub fn apply_layout_settings(&self) {
+        let layout = self
+            .session()
+            .map(|session| session.layout())
+            .unwrap_or_default();
+
+        // show pages as large as possible while presenting
+        let (margin_x, margin_y) = match self.presentation.get() {
+            Some(_) => (0.0, 0.0),
+            None => (layout.margin_x, layout.margin_y),
+        };
+
+        for view in self.views() {
+            let canvas = view.canvas();
+            canvas.set_property("page-spacing", layout.page_spacing);
+            canvas.set_property("margin-left", margin_x);
+            canvas.set_property("margin-right", margin_x);
+            canvas.set_property("margin-top", margin_y);
+            canvas.set_property("margin-bottom", margin_y);
+        }
+    }
+
+    /// Apply the tiling scheme and tile size stored in the session to all
+    /// documents.
+    pub fn apply_tiling_settings(&self) {
+        let tiling = self
+            .session()
+            .map(|session| session.tiling())
+            .unwrap_or_default();
+
+        for view in self.views() {
+            view.canvas().set_tiling(tiling.scheme, tiling.tile_size);
+            view.canvas().set_gl_upload(tiling.gl_upload);
+
+            // documents of other tabs use their cache again once selected
+            if !tiling.disk_cache {
+                view.canvas().set_disk_cache(None);
+            }
+        }
+
+        let cache = self
+            .disk_cache
+            .borrow()
+            .clone()
+            .filter(|_| tiling.disk_cache);
+        self.canvas().set_disk_cache(cache.clone());
+        self.thumbnails_view.set_disk_cache(cache);
+    }
+
+    /// Set up the disk cache for renders of the document with the given
+    /// content hash, evicting old renders in the background.
+    fn open_disk_cache(&self, hash: u64) {
+        let app = self
+            .obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok());
+
+        let cache = app.map(|app| {
+            let cache = DiskCache::new(app.storage(), hash, DISK_CACHE_MAX_SIZE);
+
+            let evict = cache.clone();
+            app.executor()
+                .submit(TaskPriority::Low, move || evict.evict());
+
+            cache
+        });
+
+        self.disk_cache.replace(cache);
+        self.apply_tiling_settings();
+    }
+
+    /// Offer restoring the given positions left open by a crashed run.
+    pub fn offer_restore(&self, positions: Vec<PositionLink>) {
+        let n = positions.len();
+        let documents = if n == 1 { "document" } else { "documents" };
+
+        let toast = adw::Toast::new(&format!(
+            "Closed unexpectedly, restore {n} {documents} where you left off?"
+        ));
+        toast.set_button_label(Some("Restore"));
+        toast.set_timeout(0);
+
+        toast.connect_button_clicked(clone!(@weak self as win => move |_| {
+            let app = win.obj()
+                .application()
+                .and_then(|app| app.downcast::<App>().ok());
+
+            if let Some(app) = app {
+                app.restore(&win.obj(), positions.clone());
+            }
+        }));
+
+        self.overlay.add_toast(toast);
+    }
+
+    /// Save the position in the open document for recovery after a crash.
+    fn schedule_recovery_save(&self) {
+        let app = self
+            .obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok());
+
+        if let Some(app) = app {
+            app.schedule_recovery_save();
+        }
+    }
+
+    fn session(&self) -> Option<SessionStore> {
+        self.obj()
+            .application()
+            .and_then(|app| app.downcast::<App>().ok())
+            .map(|app| app.session())
+    }
+
+    fn update_reading_progress(&self) {
+        let adj = match self.canvas().vadjustment() {
+            Some(adj) => adj,
+            None => return,
+        };
+
+        // fraction of the document scrolled through
+        let range = adj.upper() - adj.lower() - adj.page_size();
+        let fraction = if range > 0.0 {
+            (adj.value() - adj.lower()) / range
+        } else {
+            1.0
+        };
+
+        let mut reading = self.reading.borrow_mut();
+        let reading = match reading.as_mut() {
+            Some(reading) => reading,
+            None => return,
+        };
+
+        reading.update(Instant::now(), fraction);
+
+        let percent = format!("{:.0}%", reading.progress() * 100.0);
+        let text = match reading.remaining() {
+            Some(remaining) => format!("{percent} · {}", format_remaining(remaining)),
+            None => percent,
+        };
+
+        self.progress_bar.set_fraction(reading.progress());
+        self.progress_label.set_label(&text);
+    }
+
+    fn save_reading_progress(&self) {
+        let (path, reading) = match (&*self.path.borrow(), &*self.reading.borrow()) {
+            (Some(path), Some(reading)) => (path.clone(), reading.clone()),
+            _ => return,
+        };
+
+        let session = match self.session() {
+            Some(session) => session,
+            None => return,
+        };
+
+        let page_times = self
+            .timeline
+            .borrow()
+            .as_ref()
+            .map(|t| t.seconds().to_vec());
+
+        let position = self.current_position();
+
+        let state = DocumentState {
+            progress: Some(reading.progress()),
+            reading_speed: reading.speed(),
+            trusted: self.trusted.get(),
+            page_times,
+            page_style: Some(self.page_style.get()),
+            page: position.as_ref().map(|p| p.page),
+            center: position.as_ref().map(|p| p.center),
+            zoom: position.as_ref().map(|p| p.zoom),
+            layout: Some(self.canvas().layout()),
+            trim_margins: Some(self.trim_margins.get()),
+            last_opened: None,
+        };
+
+        session.set_document(&path, &state);
+
+        if let Err(err) = session.save() {
+            tracing::warn!(error=%err, "failed to save session state");
+        }
+    }
+
+    /// Remember that the given document has been opened just now, for
+    /// listing it among the recent documents.
+    fn record_opened(&self, path: &Path) {
+        let session = match self.session() {
+            Some(session) => session,
+            None => return,
+        };
+
+        let state = DocumentState {
+            last_opened: Some(glib::real_time() / 1_000_000),
+            ..Default::default()
+        };
+
+        session.set_document(path, &state);
+
+        if let Err(err) = session.save() {
+            tracing::warn!(error=%err, "failed to save session state");
+        }
+    }
+
+    /// Go to the position the given document has last been viewed at, if
+    /// known, falling back to the saved reading progress otherwise.
+    fn restore_last_position(&self, path: &Path, state: &DocumentState) {
+        let (page, center, zoom) = match (state.page, state.center, state.zoom) {
+            (Some(page), Some(center), Some(zoom)) => (page, center, zoom),
+            _ => {
+                if let Some(progress) = state.progress {
+                    self.scroll_to_progress(progress);
+                }
+                return;
+            }
+        };
+
+        let link = PositionLink {
+            file: path.to_owned(),
+            page,
+            center,
+            zoom,
+        };
+
+        self.go_to_position(&link);
+    }
+
+    /// Scroll to the given fraction of the document.
+    fn scroll_to_progress(&self, progress: f64) {
+        let adj = match self.canvas().vadjustment() {
+            Some(adj) => adj,
+            None => return,
+        };
+
+        let range = adj.upper() - adj.lower() - adj.page_size();
+        if range > 0.0 {
+            adj.set_value(adj.lower() + progress.clamp(0.0, 1.0) * range);
+        }
+    }
+
+    /// List the recently opened documents on the start page.
+    fn update_recent_documents(&self) {
+        while let Some(row) = self.recent_list.first_child() {
+            self.recent_list.remove(&row);
+        }
+
+        let recent = self
+            .session()
+            .map(|session| session.recent_documents(RECENT_DOCUMENTS_LIMIT))
+            .unwrap_or_default();
+
+        for doc in &recent {
+            let name = doc.path.file_name().unwrap_or_default().to_string_lossy();
+            let dir = doc.path.parent().unwrap_or(&doc.path).display().to_string();
+
+            let subtitle = match doc.progress {
+                Some(progress) => format!("{dir} · {:.0}% read", progress * 100.0),
+                None => dir,
+            };
+
+            let row = adw::ActionRow::builder()
+                .title(glib::markup_escape_text(&name))
+                .subtitle(glib::markup_escape_text(&subtitle))
+                .activatable(true)
+                .build();
+
+            row.add_suffix(&gtk::Image::from_icon_name("go-next-symbolic"));
+
+            let path = doc.path.clone();
+            row.connect_activated(clone!(@weak self as win => move |_| {
+                win.open_file(File::for_path(&path));
+            }));
+
+            self.recent_list.append(&row);
+        }
+
+        self.recent_list.set_visible(!recent.is_empty());
+    }
+
+    fn monitor(&self) -> Option<gdk::Monitor> {
+        let obj = self.obj();
+        obj.display().monitor_at_surface(&obj.surface())
+    }
+
+    fn display_dpi(&self, monitor: &gdk::Monitor) -> f64 {
+        let id = calibration::monitor_id(monitor);
+
+        self.session()
+            .and_then(|session| session.display_dpi(&id))
+            .unwrap_or_else(|| calibration::estimate_dpi(monitor))
+    }
+
+    pub fn zoom_actual_size(&self) {
+        if let Some(scale) = self.actual_size_scale() {
+            self.viewport().set_scale_centered(scale);
+        }
+    }
+
+    /// Scale at which pages are shown at their physical size.
+    fn actual_size_scale(&self) -> Option<f64> {
+        let dpi = self.display_dpi(&self.monitor()?);
+
+        // canvas scale is in pixels per PDF point (1/72 inch)
+        Some(dpi / 72.0)
+    }
+
+    pub fn calibrate_display(&self) {
+        let monitor = match self.monitor() {
+            Some(monitor) => monitor,
+            None => return,
+        };
+
+        let dpi = self.display_dpi(&monitor);
+        let dialog = CalibrationDialog::new(&*self.obj(), &monitor, dpi);
+
+        dialog.connect_calibrated(clone!(@weak self as win, @strong monitor => move |_, dpi| {
+            let id = calibration::monitor_id(&monitor);
+
+            tracing::debug!(monitor=id, dpi, "display calibrated");
+
+            if let Some(session) = win.session() {
+                session.set_display_dpi(&id, dpi);
+
+                if let Err(err) = session.save() {
+                    tracing::warn!(error=%err, "failed to save session state");
+                }
+            }
+
+            let toast = adw::Toast::new(&format!("Display calibrated to {dpi:.1} pixels per inch"));
+            win.overlay.add_toast(toast);
+        }));
+
+        dialog.present();
+    }
+
+    /// Show a preview of the page at the given vertical position in canvas
+    /// coordinates, as hovered on the scrollbar.
+    fn show_page_preview(&self, position: f64) {
+        let page = match self.canvas().page_at_y(position) {
+            Some(page) => page,
+            None => {
+                self.viewport().hide_page_preview();
+                return;
+            }
+        };
+
+        let texture = self.canvas().page_preview(page);
+        let label = format!("Page {}", self.page_label(page));
+
+        self.viewport().show_page_preview(texture.as_ref(), &label);
+    }
+
+    fn page_label(&self, page: usize) -> String {
+        self.labels
+            .borrow()
+            .get(page)
+            .cloned()
+            .flatten()
+            .unwrap_or_else(|| format!("{}", page + 1))
+    }
+
+    pub fn pin_position(&self) {
+        let path = match self.path.borrow().clone() {
+            Some(path) => path,
+            None => return,
+        };
+
+        let (page, rect) = match self.canvas().viewport_page_rect() {
+            Some(region) => region,
+            None => return,
+        };
+
+        let pin = Pin {
+            page,
+            label: self.labels.borrow().get(page).cloned().flatten(),
+            anchor: Anchor::find(&self.named_destinations(), page),
+            rect,
+            page_count: self.canvas().page_count(),
+        };
+
+        tracing::debug!(file=?path, ?pin, "pinning position");
+
+        self.pin.replace(Some((path, pin)));
+        self.pin_badge.set_visible(false);
+
+        let toast = adw::Toast::new(&format!(
+            "Pinned position on page {}",
+            self.page_label(page)
+        ));
+        self.overlay.add_toast(toast);
+    }
+
+    pub fn has_document(&self) -> bool {
+        self.path.borrow().is_some()
+    }
+
+    /// The current position in the open document, if any.
+    pub fn current_position(&self) -> Option<PositionLink> {
+        let file = self.path.borrow().clone()?;
+        let view = self.view.borrow().clone();
+
+        view_position(&view, file)
+    }
+
+    /// The positions in all open documents, in the order of their tabs.
+    pub fn open_positions(&self) -> Vec<PositionLink> {
+        let current = self.view.borrow().clone();
+
+        self.views()
+            .iter()
+            .filter_map(|view| {
+                let file = if *view == current {
+                    self.path.borrow().clone()
+                } else {
+                    view.path()
+                };
+
+                view_position(view, file?)
+            })
+            .collect()
+    }
+
+    /// Copy a link to the current position to the clipboard.
+    pub fn copy_position_link(&self) {
+        let link = match self.current_position() {
+            Some(link) => link,
+            None => return,
+        };
+        let page = link.page;
+
+        self.obj().clipboard().set_text(&link.to_uri());
+
+        let toast = adw::Toast::new(&format!("Copied link to page {}", self.page_label(page)));
+        self.overlay.add_toast(toast);
+    }
+
+    fn go_to_position(&self, link: &PositionLink) {
+        let page_count = self.canvas().page_count();
+        if page_count == 0 {
+            return;
+        }
+
+        tracing::debug!(?link, "going to linked position");
+
+        let page = link.page.min(page_count - 1);
+        let rect = Rect::new(link.center, vector![0.0, 0.0]);
+
+        self.viewport().set_scale(link.zoom);
+        self.canvas().scroll_to_page_rect(page, &rect);
+    }
+
+    /// Go to the top of the given page, zooming as given beforehand, or
+    /// fitting that page to the viewport afterwards.
+    fn go_to_view(&self, page: Option<usize>, zoom: Option<Zoom>) {
+        let page_count = self.canvas().page_count();
+        if page_count == 0 {
+            return;
+        }
+
+        tracing::debug!(?page, ?zoom, "going to requested view");
+
+        match zoom {
+            Some(Zoom::FitWidth) => self.viewport().fit_width(),
+            Some(Zoom::Scale(scale)) => self.viewport().set_scale(scale),
+            Some(Zoom::FitPage) | None => {}
+        }
+
+        if let Some(page) = page {
+            self.canvas().scroll_to_page(page.min(page_count - 1));
+        }
+
+        if zoom == Some(Zoom::FitPage) {
+            self.zoom_fit_page();
+        }
+    }
+
+    /// The named destinations of the current document as pairs of name and
+    /// page index, for anchoring pins.
+    fn named_destinations(&self) -> Vec<(String, usize)> {
+        let doc = match self.canvas().document() {
+            Some(doc) => doc,
+            None => return Vec::new(),
+        };
+
+        let dests = doc.named_destinations().unwrap_or_else(|err| {
+            tracing::warn!(error=%err, "failed to load named destinations");
+            Vec::new()
+        });
+
+        dests
+            .into_iter()
+            .map(|(name, dest)| (name, dest.page as usize))
+            .collect()
+    }
+
+    fn restore_pin(&self, pin: &Pin) {
+        let destinations = self.named_destinations();
+
+        let resolved = match pin.resolve(&self.labels.borrow(), &destinations) {
+            Some(resolved) => resolved,
+            None => return,
+        };
+
+        tracing::debug!(?pin, ?resolved, "restoring pinned position");
+
+        self.canvas()
+            .scroll_to_page_rect(resolved.page, &resolved.rect);
+
+        // show how the document changed since the pin has been created
+        let delta = resolved.page_count_delta;
+        if delta != 0 {
+            let pages = if delta.abs() == 1 { "page" } else { "pages" };
+
+            self.pin_badge.set_label(&format!("{delta:+} {pages}"));
+            self.pin_badge.set_tooltip_text(Some(&format!(
+                "Page count changed from {} to {}",
+                pin.page_count,
+                self.canvas().page_count()
+            )));
+        }
+        self.pin_badge.set_visible(delta != 0);
+
+        // follow the pin: later reloads are compared against this version
+        if let Some((_, pin)) = self.pin.borrow_mut().as_mut() {
+            pin.page = resolved.page;
+            pin.label = self.labels.borrow().get(resolved.page).cloned().flatten();
+            pin.anchor = Anchor::find(&destinations, resolved.page);
+            pin.page_count = self.canvas().page_count();
+        }
+
+        let toast = adw::Toast::new(&format!(
+            "Restored pinned position on page {}",
+            self.page_label(resolved.page)
+        ));
+        self.overlay.add_toast(toast);
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for AppWindow {
+    const NAME: &'static str = "AppWindow";
+    type Type = super::AppWindow;
+    type ParentType = adw::ApplicationWindow;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+    }
+
+    fn instance_init(obj: &InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+impl ObjectImpl for AppWindow {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        let action_doc_open = SimpleAction::new("document-open", None);
+        action_doc_open.connect_activate(clone!(@weak self as win => move |_, _| {
+            let filechooser = pdf_file_dialog("Open Document", "Open");
+
+            filechooser.open(
+                Some(&*win.obj()),
+                None::<&gtk::gio::Cancellable>,
+                clone!(@weak win => move |result| {
+                    if let Ok(file) = result {
+                        win.open_file(file);
+                    }
+                }),
+            );
+        }));
+
+        let action_doc_compare = SimpleAction::new("document-compare", None);
+        action_doc_compare.connect_activate(clone!(@weak self as win => move |_, _| {
+            let filechooser = pdf_file_dialog("Compare With Document", "Compare");
+
+            filechooser.open(
+                Some(&*win.obj()),
+                None::<&gtk::gio::Cancellable>,
+                clone!(@weak win => move |result| {
+                    if let Ok(file) = result {
+                        win.compare_file(file);
+                    }
+                }),
+            );
+        }));
+
+        let action_print = SimpleAction::new("print", None);
+        action_print.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.print();
+        }));
+
+        let action_export_images = SimpleAction::new("export-images", None);
+        action_export_images.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.show_export_images_dialog();
+        }));
+
+        let action_export_text = SimpleAction::new("export-text-layer", None);
+        action_export_text.connect_activate(clone!(@weak self as win => move |_, _| {
+            let filechooser = text_layer_file_dialog();
+
+            filechooser.save(
+                Some(&*win.obj()),
+                None::<&gtk::gio::Cancellable>,
+                clone!(@weak win => move |result| {
+                    if let Ok(file) = result {
+                        win.export_text_layer(file);
+                    }
+                }),
+            );
+        }));
+
+        let action_annots_export = SimpleAction::new("annotations-export", None);
+        action_annots_export.connect_activate(clone!(@weak self as win => move |_, _| {
+            let filechooser = xfdf_file_dialog("Export Annotations", "Export");
+            filechooser.set_initial_name(Some("annotations.xfdf"));
+
+            filechooser.save(
+                Some(&*win.obj()),
+                None::<&gtk::gio::Cancellable>,
+                clone!(@weak win => move |result| {
+                    if let Ok(file) = result {
+                        win.export_annotations(file);
+                    }
+                }),
+            );
+        }));
+
+        let action_annots_import = SimpleAction::new("annotations-import", None);
+        action_annots_import.connect_activate(clone!(@weak self as win => move |_, _| {
+            let filechooser = xfdf_file_dialog("Import Annotations", "Import");
+
+            filechooser.open(
+                Some(&*win.obj()),
+                None::<&gtk::gio::Cancellable>,
+                clone!(@weak win => move |result| {
+                    if let Ok(file) = result {
+                        win.import_annotations(file);
+                    }
+                }),
+            );
+        }));
+
+        let action_doc_properties = SimpleAction::new("document-properties", None);
+        action_doc_properties.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.show_properties();
+        }));
+
+        let action_doc_stats = SimpleAction::new("document-statistics", None);
+        action_doc_stats.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.show_statistics();
+        }));
+
+        let action_doc_compare_stop = SimpleAction::new("document-compare-stop", None);
+        action_doc_compare_stop.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.canvas().set_compare_document(None);
+        }));
+
+        let action_doc_close = SimpleAction::new("document-close", None);
+        action_doc_close.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.close_file();
+        }));
+
+        let action_pin_position = SimpleAction::new("pin-position", None);
+        action_pin_position.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.pin_position();
+        }));
+
+        let action_search = SimpleAction::new("search", None);
+        action_search.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.search_bar.set_search_mode(true);
+            win.search_entry.grab_focus();
+        }));
+
+        let action_search_next = SimpleAction::new("search-next", None);
+        action_search_next.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.search_step(true);
+        }));
+
+        let action_search_prev = SimpleAction::new("search-previous", None);
+        action_search_prev.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.search_step(false);
+        }));
+
+        let action_search_all =
+            SimpleAction::new_stateful("search-all-documents", None, false.to_variant());
+        action_search_all.connect_activate(|action, _| {
+            let enabled = !action
+                .state()
+                .and_then(|s| s.get::<bool>())
+                .unwrap_or(false);
+            action.change_state(&enabled.to_variant());
+        });
+        action_search_all.connect_change_state(clone!(@weak self as win => move |_, state| {
+            let enabled = state.and_then(|s| s.get::<bool>()).unwrap_or(false);
+            win.set_search_all(enabled);
+        }));
+
+        let action_outline = SimpleAction::new_stateful("outline", None, false.to_variant());
+        action_outline.connect_activate(clone!(@weak self as win => move |action, _| {
+            let enabled = !action.state().and_then(|s| s.get::<bool>()).unwrap_or(false);
+            win.flap.set_reveal_flap(enabled);
+        }));
+
+        let action_thumbnails = SimpleAction::new("thumbnails", None);
+        action_thumbnails.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.toggle_thumbnails();
+        }));
+
+        let action_link_hints = SimpleAction::new("link-hints", None);
+        action_link_hints.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.show_link_hints();
+        }));
+
+        let action_copy = SimpleAction::new("copy", None);
+        action_copy.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.copy_selection();
+        }));
+
+        let action_copy_position_link = SimpleAction::new("copy-position-link", None);
+        action_copy_position_link.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.copy_position_link();
+        }));
+
+        let action_autoscroll = SimpleAction::new("autoscroll", None);
+        action_autoscroll.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.viewport().toggle_autoscroll();
+        }));
+
+        let action_zoom_fit_width = SimpleAction::new("zoom-fit-width", None);
+        action_zoom_fit_width.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.viewport().fit_width();
+        }));
+
+        let action_zoom_fit_page = SimpleAction::new("zoom-fit-page", None);
+        action_zoom_fit_page.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.zoom_fit_page();
+        }));
+
+        let action_zoom_fit_text = SimpleAction::new("zoom-fit-text", None);
+        action_zoom_fit_text.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.zoom_fit_text_column();
+        }));
+
+        let action_single_page =
+            SimpleAction::new_stateful("single-page", None, false.to_variant());
+        action_single_page.connect_activate(|action, _| {
+            let enabled = !action
+                .state()
+                .and_then(|s| s.get::<bool>())
+                .unwrap_or(false);
+            action.change_state(&enabled.to_variant());
+        });
+        action_single_page.connect_change_state(clone!(@weak self as win => move |_, state| {
+            let enabled = state.and_then(|s| s.get::<bool>()).unwrap_or(false);
+            win.set_single_page(enabled);
+        }));
+
+        let action_trim_margins =
+            SimpleAction::new_stateful("trim-margins", None, false.to_variant());
+        action_trim_margins.connect_activate(|action, _| {
+            let enabled = !action
+                .state()
+                .and_then(|s| s.get::<bool>())
+                .unwrap_or(false);
+            action.change_state(&enabled.to_variant());
+        });
+        action_trim_margins.connect_change_state(clone!(@weak self as win => move |_, state| {
+            let enabled = state.and_then(|s| s.get::<bool>()).unwrap_or(false);
+            win.set_trim_margins(enabled);
+        }));
+
+        let action_reader_mode =
+            SimpleAction::new_stateful("reader-mode", None, false.to_variant());
+        action_reader_mode.connect_activate(|action, _| {
+            let enabled = !action
+                .state()
+                .and_then(|s| s.get::<bool>())
+                .unwrap_or(false);
+            action.change_state(&enabled.to_variant());
+        });
+        action_reader_mode.connect_change_state(clone!(@weak self as win => move |_, state| {
+            let enabled = state.and_then(|s| s.get::<bool>()).unwrap_or(false);
+            win.set_reader_mode(enabled);
+        }));
+
+        let action_presentation =
+            SimpleAction::new_stateful("presentation", None, false.to_variant());
+        action_presentation.connect_activate(|action, _| {
+            let enabled = !action
+                .state()
+                .and_then(|s| s.get::<bool>())
+                .unwrap_or(false);
+            action.change_state(&enabled.to_variant());
+        });
+        action_presentation.connect_change_state(clone!(@weak self as win => move |_, state| {
+            let enabled = state.and_then(|s| s.get::<bool>()).unwrap_or(false);
+            win.set_presentation(enabled);
+        }));
+
+        let action_rotate_cw = SimpleAction::new("rotate-clockwise", None);
+        action_rotate_cw.connect_activate(clone!(@weak self as win => move |_, _| {
+            let canvas = win.canvas();
+            canvas.set_rotation(rotation::clockwise(canvas.rotation()));
+        }));
+
+        let action_rotate_ccw = SimpleAction::new("rotate-counter-clockwise", None);
+        action_rotate_ccw.connect_activate(clone!(@weak self as win => move |_, _| {
+            let canvas = win.canvas();
+            canvas.set_rotation(rotation::counter_clockwise(canvas.rotation()));
+        }));
+
+        let action_zoom_actual_size = SimpleAction::new("zoom-actual-size", None);
+        action_zoom_actual_size.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.zoom_actual_size();
+        }));
+
+        let action_preferences = SimpleAction::new("preferences", None);
+        action_preferences.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.show_preferences();
+        }));
+
+        let action_calibrate_display = SimpleAction::new("calibrate-display", None);
+        action_calibrate_display.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.calibrate_display();
+        }));
+
+        let action_next_block = SimpleAction::new("next-block", None);
+        action_next_block.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.navigate_block(true);
+        }));
+
+        let action_prev_block = SimpleAction::new("previous-block", None);
+        action_prev_block.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.navigate_block(false);
+        }));
+
+        let action_go_to_page = SimpleAction::new("go-to-page", None);
+        action_go_to_page.connect_activate(clone!(@weak self as win => move |_, _| {
+            if win.page_nav.is_visible() {
+                win.page_entry.grab_focus();
+            }
+        }));
+
+        let action_next_page = SimpleAction::new("next-page", None);
+        action_next_page.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.step_page(true);
+        }));
+
+        let action_prev_page = SimpleAction::new("previous-page", None);
+        action_prev_page.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.step_page(false);
+        }));
+
+        self.page_entry
+            .connect_activate(clone!(@weak self as win => move |_| {
+                win.go_to_entered_page();
+            }));
+
+        let action_go_back = SimpleAction::new("go-back", None);
+        action_go_back.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.go_back();
+        }));
+
+        let action_go_forward = SimpleAction::new("go-forward", None);
+        action_go_forward.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.go_forward();
+        }));
+
+        let action_debug_overlay =
+            SimpleAction::new_stateful("debug-overlay", None, false.to_variant());
+        action_debug_overlay.connect_activate(clone!(@weak self as win => move |action, _| {
+            let enabled = !action.state().and_then(|s| s.get::<bool>()).unwrap_or(false);
+
+            action.set_state(enabled.to_variant());
+            win.canvas().set_debug_overlay(enabled);
+        }));
+
+        let action_eyedropper = SimpleAction::new_stateful("eyedropper", None, false.to_variant());
+        action_eyedropper.connect_activate(|action, _| {
+            let enabled = !action
+                .state()
+                .and_then(|s| s.get::<bool>())
+                .unwrap_or(false);
+            action.change_state(&enabled.to_variant());
+        });
+        action_eyedropper.connect_change_state(clone!(@weak self as win => move |action, state| {
+            let enabled = state.and_then(|s| s.get::<bool>()).unwrap_or(false);
+
+            if enabled {
+                win.set_pen(false);
+            }
+
+            action.set_state(enabled.to_variant());
+            win.canvas().set_eyedropper(enabled);
+        }));
+
+        let action_highlight = SimpleAction::new("highlight", None);
+        action_highlight.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.markup_selection(MarkupKind::Highlight);
+        }));
+
+        let action_underline = SimpleAction::new("underline", None);
+        action_underline.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.markup_selection(MarkupKind::Underline);
+        }));
+
+        let action_strike_out = SimpleAction::new("strike-out", None);
+        action_strike_out.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.markup_selection(MarkupKind::StrikeOut);
+        }));
+
+        let action_pen = SimpleAction::new_stateful("pen", None, false.to_variant());
+        action_pen.connect_activate(|action, _| {
+            let enabled = !action
+                .state()
+                .and_then(|s| s.get::<bool>())
+                .unwrap_or(false);
+            action.change_state(&enabled.to_variant());
+        });
+        action_pen.connect_change_state(clone!(@weak self as win => move |action, state| {
+            let enabled = state.and_then(|s| s.get::<bool>()).unwrap_or(false);
+
+            if enabled {
+                win.set_eyedropper(false);
+            }
+
+            action.set_state(enabled.to_variant());
+            win.canvas().set_pen_tool(enabled.then(Pen::default));
+        }));
+
+        let action_stamp_date = SimpleAction::new("stamp-date", None);
+        action_stamp_date.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.start_stamp(StampContent::date());
+        }));
+
+        let action_stamp_approved = SimpleAction::new("stamp-approved", None);
+        action_stamp_approved.connect_activate(clone!(@weak self as win => move |_, _| {
+            win.start_stamp(StampContent::Text("APPROVED".into()));
+        }));
+
+        let action_stamp_image = SimpleAction::new("stamp-image", None);
+        action_stamp_image.connect_activate(clone!(@weak self as win => move |_, _| {
+            let filechooser = image_file_dialog();
+
+            filechooser.open(
+                Some(&*win.obj()),
+                None::<&gtk::gio::Cancellable>,
+                clone!(@weak win => move |result| {
+                    if let Ok(file) = result {
+                        win.start_image_stamp(file);
+                    }
+                }),
+            );
+        }));
+
+        let action_trust_document =
+            SimpleAction::new_stateful("trust-document", None, false.to_variant());
+        action_trust_document.connect_activate(|action, _| {
+            let trusted = !action
+                .state()
+                .and_then(|s| s.get::<bool>())
+                .unwrap_or(false);
+            action.change_state(&trusted.to_variant());
+        });
+        action_trust_document.connect_change_state(clone!(@weak self as win => move |_, state| {
+            let trusted = state.and_then(|s| s.get::<bool>()).unwrap_or(false);
+            win.decide_trust(trusted);
+        }));
+
+        let action_dark_pages = SimpleAction::new_stateful("dark-pages", None, false.to_variant());
+        action_dark_pages.connect_activate(|action, _| {
+            let dark = !action
+                .state()
+                .and_then(|s| s.get::<bool>())
+                .unwrap_or(false);
+            action.change_state(&dark.to_variant());
+        });
+        action_dark_pages.connect_change_state(clone!(@weak self as win => move |_, state| {
+            let dark = state.and_then(|s| s.get::<bool>()).unwrap_or(false);
+            win.decide_page_style(dark);
+        }));
+
+        let follow_night_light = self
+            .session()
+            .map(|session| session.follow_night_light())
+            .unwrap_or(false);
+
+        let action_follow_night_light =
+            SimpleAction::new_stateful("follow-night-light", None, follow_night_light.to_variant());
+        action_follow_night_light.connect_activate(|action, _| {
+            let follow = !action
+                .state()
+                .and_then(|s| s.get::<bool>())
+                .unwrap_or(false);
+            action.change_state(&follow.to_variant());
+        });
+        action_follow_night_light.connect_change_state(
+            clone!(@weak self as win => move |_, state| {
+                let follow = state.and_then(|s| s.get::<bool>()).unwrap_or(false);
+                win.set_follow_night_light(follow);
+            }),
+        );
+
+        let night_colors = self
+            .session()
+            .map(|session| session.night_colors())
+            .unwrap_or(false);
+
+        let action_night_colors =
+            SimpleAction::new_stateful("night-colors", None, night_colors.to_variant());
+        action_night_colors.connect_activate(|action, _| {
+            let enabled = !action
+                .state()
+                .and_then(|s| s.get::<bool>())
+                .unwrap_or(false);
+            action.change_state(&enabled.to_variant());
+        });
+        action_night_colors.connect_change_state(clone!(@weak self as win => move |_, state| {
+            let enabled = state.and_then(|s| s.get::<bool>()).unwrap_or(false);
+            win.set_night_colors(enabled);
+        }));
+
+        let layout = self
+            .session()
+            .map(|session| session.page_layout())
+            .unwrap_or_default();
+
+        let action_layout = SimpleAction::new_stateful(
+            "layout",
+            Some(glib::VariantTy::STRING),
+            layout.as_str().to_variant(),
+        );
+        action_layout.connect_activate(|action, param| {
+            if let Some(param) = param {
+                action.change_state(param);
+            }
+        });
+        action_layout.connect_change_state(clone!(@weak self as win => move |_, state| {
+            let kind = state.and_then(|s| s.str()).and_then(LayoutKind::parse);
+            if let Some(kind) = kind {
+                win.set_page_layout(kind);
+            }
+        }));
+
+        let action_font_diagnostics =
+            SimpleAction::new_stateful("font-diagnostics", None, false.to_variant());
+        action_font_diagnostics.connect_activate(|action, _| {
+            let enabled = !action
+                .state()
+                .and_then(|s| s.get::<bool>())
+                .unwrap_or(false);
+            action.change_state(&enabled.to_variant());
+        });
+        action_font_diagnostics.connect_change_state(clone!(@weak self as win => move |_, state| {
+            let enabled = state.and_then(|s| s.get::<bool>()).unwrap_or(false);
+            win.set_font_diagnostics(enabled);
+        }));
+
+        let action_caret_navigation =
+            SimpleAction::new_stateful("caret-navigation", None, false.to_variant());
+        action_caret_navigation.connect_activate(|action, _| {
+            let enabled = !action
+                .state()
+                .and_then(|s| s.get::<bool>())
+                .unwrap_or(false);
+            action.change_state(&enabled.to_variant());
+        });
+        action_caret_navigation.connect_change_state(clone!(@weak self as win => move |_, state| {
+            let enabled = state.and_then(|s| s.get::<bool>()).unwrap_or(false);
+            win.set_caret_navigation(enabled);
+        }));
+
+        let action_command_palette = SimpleAction::new("command-palette", None);
+        action_command_palette.connect_activate(clone!(@weak self as win => move |_, _| {
+            CommandPalette::new(&*win.obj()).present();
+        }));
+
+        self.outline_view
+            .connect_entry_activated(clone!(@weak self as win => move |_, entry| {
+                win.go_to_bookmark(entry);
+            }));
+
+        self.thumbnails_view
+            .connect_page_activated(clone!(@weak self as win => move |_, page| {
+                win.go_to_page(page);
+            }));
+
+        // keep the state of the outline action in sync, the sidebar may also
+        // be hidden by clicking outside of it
+        self.flap
+            .connect_reveal_flap_notify(clone!(@weak self as win => move |flap| {
+                if let Some(action) = win.obj().lookup_action("outline") {
+                    action.change_state(&flap.reveals_flap().to_variant());
+                }
+            }));
+
+        self.signature_dismiss
+            .connect_clicked(clone!(@weak self as win => move |_| {
+                win.signature_banner.set_reveal_child(false);
+            }));
+
+        // text in reader mode, with page headings and a selectable font size
+        let tags = self.reader_view.buffer().tag_table();
+
+        tags.add(
+            &gtk::TextTag::builder()
+                .name("reader-text")
+                .size_points(self.reader_font_size.value())
+                .build(),
+        );
+        tags.add(
+            &gtk::TextTag::builder()
+                .name("reader-page")
+                .weight(700)
+                .scale(0.8)
+                .pixels_above_lines(24)
+                .build(),
+        );
+
+        self.reader_font_size
+            .connect_value_changed(clone!(@weak self as win => move |button| {
+                win.set_reader_font_size(button.value());
+            }));
+
+        // search as the query is typed, go to the next result on enter, and
+        // stop searching when the search bar is closed
+        self.search_bar.connect_entry(&*self.search_entry);
+
+        self.search_entry
+            .connect_search_changed(clone!(@weak self as win => move |_| {
+                win.start_search();
+                win.start_global_search();
+            }));
+
+        self.search_entry
+            .connect_activate(clone!(@weak self as win => move |_| {
+                win.search_step(true);
+            }));
+
+        self.search_bar.connect_search_mode_enabled_notify(
+            clone!(@weak self as win => move |bar| {
+                if !bar.is_search_mode() {
+                    win.stop_search();
+                    win.start_global_search();
+                }
+            }),
+        );
+
+        self.popovermenu
+            .set_menu_model(Some(&actions::menu_model()));
+
+        // switch page colors along with the desktop style and night light
+        adw::StyleManager::default().connect_dark_notify(clone!(@weak self as win => move |_| {
+            win.update_page_style();
+        }));
+
+        let night_light = NightLight::connect(clone!(@weak self as win => move || {
+            win.update_page_style();
+        }));
+        self.night_light.replace(Some(night_light));
+
+        let power = PowerMonitor::connect(clone!(@weak self as win => move || {
+            win.update_power_mode();
+        }));
+        self.power.replace(Some(power));
+        self.update_power_mode();
+
+        // documents are shown in tabs, each with its own viewport and canvas,
+        // which can be moved between windows
+        self.tab_view
+            .connect_page_attached(clone!(@weak self as win => move |_, page, _| {
+                if let Ok(view) = page.child().downcast::<DocumentView>() {
+                    win.attach_view(&view);
+                }
+            }));
+
+        self.tab_view
+            .connect_page_detached(clone!(@weak self as win => move |_, page, _| {
+                if let Ok(view) = page.child().downcast::<DocumentView>() {
+                    view.disconnect_window();
+                    win.remove_global_search(&view);
+                }
+            }));
+
+        self.tab_view
+            .connect_selected_page_notify(clone!(@weak self as win => move |_| {
+                win.select_view();
+            }));
+
+        self.tab_view.connect_create_window(
+            clone!(@weak self as win => @default-return None, move |_| {
+                let app = win.obj().application()?;
+                let window = super::AppWindow::new(app.downcast_ref()?);
+                window.present();
+
+                Some(window.imp().tab_view.get())
+            }),
+        );
+
+        self.apply_layout_settings();
+        self.apply_tiling_settings();
+        self.update_recent_documents();
+
+        // release resources of windows that have been hidden for a while
+        self.obj()
+            .connect_visible_notify(clone!(@weak self as win => move |_| {
+                win.update_autosuspend();
+            }));
+
+        self.obj()
+            .connect_realize(clone!(@weak self as win => move |obj| {
+                if let Ok(toplevel) = obj.surface().downcast::<gdk::Toplevel>() {
+                    toplevel.connect_state_notify(clone!(@weak win => move |_| {
+                        win.update_autosuspend();
+                    }));
+                }
+            }));
+
+        glib::timeout_add_local(
+            ViewingTimeline::SAMPLE_INTERVAL,
+            clone!(@weak self as win => @default-return glib::Continue(false), move || {
+                win.sample_timeline();
+                glib::Continue(true)
+            }),
+        );
+
+        self.obj().add_action(&action_doc_open);
+        self.obj().add_action(&action_doc_close);
+        self.obj().add_action(&action_doc_compare);
+        self.obj().add_action(&action_doc_compare_stop);
+        self.obj().add_action(&action_print);
+        self.obj().add_action(&action_export_images);
+        self.obj().add_action(&action_export_text);
+        self.obj().add_action(&action_annots_export);
+        self.obj().add_action(&action_annots_import);
+        self.obj().add_action(&action_doc_properties);
+        self.obj().add_action(&action_doc_stats);
+        self.obj().add_action(&action_trust_document);
+        self.obj().add_action(&action_pin_position);
+        self.obj().add_action(&action_search);
+        self.obj().add_action(&action_search_next);
+        self.obj().add_action(&action_search_prev);
+        self.obj().add_action(&action_search_all);
+        self.obj().add_action(&action_outline);
+        self.obj().add_action(&action_thumbnails);
+        self.obj().add_action(&action_link_hints);
+        self.obj().add_action(&action_copy);
+        self.obj().add_action(&action_copy_position_link);
+        self.obj().add_action(&action_autoscroll);
+        self.obj().add_action(&action_zoom_fit_width);
+        self.obj().add_action(&action_zoom_fit_page);
+        self.obj().add_action(&action_zoom_fit_text);
+        self.obj().add_action(&action_zoom_actual_size);
+        self.obj().add_action(&action_single_page);
+        self.obj().add_action(&action_trim_margins);
+        self.obj().add_action(&action_reader_mode);
+        self.obj().add_action(&action_presentation);
+        self.obj().add_action(&action_rotate_cw);
+        self.obj().add_action(&action_rotate_ccw);
+        self.obj().add_action(&action_calibrate_display);
+        self.obj().add_action(&action_dark_pages);
+        self.obj().add_action(&action_follow_night_light);
+        self.obj().add_action(&action_night_colors);
+        self.obj().add_action(&action_layout);
+        self.obj().add_action(&action_preferences);
+        self.obj().add_action(&action_next_block);
+        self.obj().add_action(&action_prev_block);
+        self.obj().add_action(&action_go_to_page);
+        self.obj().add_action(&action_next_page);
+        self.obj().add_action(&action_prev_page);
+        self.obj().add_action(&action_go_back);
+        self.obj().add_action(&action_go_forward);
+        self.obj().add_action(&action_eyedropper);
+        self.obj().add_action(&action_highlight);
+        self.obj().add_action(&action_underline);
+        self.obj().add_action(&action_strike_out);
+        self.obj().add_action(&action_pen);
+        self.obj().add_action(&action_stamp_date);
+        self.obj().add_action(&action_stamp_approved);
+        self.obj().add_action(&action_stamp_image);
+        self.obj().add_action(&action_debug_overlay);
+        self.obj().add_action(&action_font_diagnostics);
+        self.obj().add_action(&action_caret_navigation);
+        self.obj().add_action(&action_command_palette);
+
+        self.update_history_actions();
+        self.update_page_style();
+    }
+}
+
+impl WidgetImpl for AppWindow {}
+impl WindowImpl for AppWindow {
+    fn close_request(&self) -> glib::signal::Inhibit {
+        self.save_reading_progress();
+        self.parent_close_request()
+    }
+}
+impl ApplicationWindowImpl for AppWindow {}
+impl AdwApplicationWindowImpl for AppWindow {}
+
+/// Maximum size of page renders stored in the disk cache, in pixels. This
+/// covers thumbnails and the lowest fallback levels.
+const DISK_CACHE_MAX_SIZE: i64 = 256;
+
+/// Maximum number of recently opened documents listed on the start page.
+const RECENT_DOCUMENTS_LIMIT: usize = 8;
+
+/// Time after which rendering of a hidden or minimized window, or of a tab
+/// that is not selected, is suspended.
+const AUTOSUSPEND_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// Maximum number of results listed per document when searching all open
+/// documents.
+const GLOBAL_SEARCH_RESULTS_LIMIT: usize = 100;
+
+/// Distance from which flipped pages slide in, relative to the viewport width.
+const SLIDE_DISTANCE: f64 = 0.25;
+
+/// Minimum horizontal velocity of a touch swipe for flipping pages, in pixels
+/// per second.
+const SWIPE_MIN_VELOCITY: f64 = 300.0;
+
+/// Minimum horizontal distance of a touchpad swipe for flipping pages, in
+/// pixels.
+const SWIPE_MIN_DISTANCE: f64 = 60.0;
+
+/// Distance the current page has to be scrolled into for stepping back to
+/// its top instead of the previous page, in PDF points.
+const PAGE_STEP_TOLERANCE: f64 = 1.0;
+
+/// State of the window before presenting a document, restored afterwards.
+#[derive(Debug, Clone, Copy)]
+struct PresentationState {
+    fullscreen: bool,
+    single_page: bool,
+    sidebar: bool,
+    scale: f64,
+}
+
+/// Where to show a document once it has been opened, instead of the position
+/// it has last been viewed at.
+enum OpenTarget {
+    /// The position of a link, e.g. from another document.
+    Position(PositionLink),
+
+    /// The given page and zoom, e.g. requested on the command line.
+    View {
+        page: Option<usize>,
+        zoom: Option<Zoom>,
+    },
+}
+
+/// Notifies the window whenever a page has been searched.
+#[derive(Clone)]
+struct SearchMonitor {
+    sender: glib::Sender<usize>,
+}
+
+impl Monitor<usize> for SearchMonitor {
+    fn on_complete(&self, page: &usize) {
+        // the receiver is gone if the window has been closed in the meantime
+        let _ = self.sender.send(*page);
+    }
+}
+
+/// Index of the search result given by its page and its index among the
+/// results on that page.
+fn search_index(matches: &[SearchMatch], (page, nth): (usize, usize)) -> Option<usize> {
+    let first = matches.iter().position(|m| m.page == page)?;
+    let index = first + nth;
+
+    matches.get(index).filter(|m| m.page == page).map(|_| index)
+}
+
+fn format_remaining(remaining: Duration) -> String {
+    let minutes = remaining.as_secs() / 60;
+
+    match minutes {
+        0 => "less than a minute left".into(),
+        1..=59 => format!("{minutes} min left"),
+        _ => format!("{} h {} min left", minutes / 60, minutes % 60),
+    }
+}
+
+/// Add shortcuts for stepping through text blocks and pages to the given
+/// viewport, only handled when the document has focus.
+fn setup_page_shortcuts(viewport: &ViewportWidget) -> Vec<gtk::EventController> {
+    // step through text blocks with space, so that spaces can still be typed
+    // elsewhere
+    let blocks = gtk::ShortcutController::new();
+
+    for (mods, action) in [
+        (gdk::ModifierType::empty(), "win.next-block"),
+        (gdk::ModifierType::SHIFT_MASK, "win.previous-block"),
+    ] {
+        let trigger = gtk::KeyvalTrigger::new(gdk::Key::space, mods);
+        let action = gtk::NamedAction::new(action);
+        blocks.add_shortcut(gtk::Shortcut::new(Some(trigger), Some(action)));
+    }
+
+    viewport.add_controller(blocks.clone());
+
+    // jump whole pages with page up and down, taking precedence over
+    // scrolling by the viewport height
+    let pages = gtk::ShortcutController::new();
+    pages.set_name(Some("page_step_shortcut_controller"));
+    pages.set_propagation_phase(gtk::PropagationPhase::Capture);
+
+    for (key, action) in [
+        (gdk::Key::Page_Down, "win.next-page"),
+        (gdk::Key::Page_Up, "win.previous-page"),
+    ] {
+        let trigger = gtk::KeyvalTrigger::new(key, gdk::ModifierType::empty());
+        let action = gtk::NamedAction::new(action);
+        pages.add_shortcut(gtk::Shortcut::new(Some(trigger), Some(action)));
+    }
+
+    viewport.add_controller(pages.clone());
+
+    vec![blocks.upcast(), pages.upcast()]
+}
+
+/// The position shown by the given view of the given file, if any.
+fn view_position(view: &DocumentView, file: PathBuf) -> Option<PositionLink> {
+    let canvas = view.canvas();
+
+    let (page, _) = canvas.viewport_page_rect()?;
+    let rect = canvas.viewport_rect(page)?;
+    let zoom = view.viewport().scale()?;
+
+    Some(PositionLink {
+        file,
+        page,
+        center: rect.offs + rect.size / 2.0,
+        zoom,
+    })
+}
+
+fn pdf_file_dialog(title: &str, accept_label: &str) -> FileDialog {
+    let filter_pdf = FileFilter::new();
+    filter_pdf.add_mime_type("application/pdf");
+    filter_pdf.add_suffix("pdf");
+    filter_pdf.set_name(Some("PDF Documents"));
+
+    let filter_all = FileFilter::new();
+    filter_all.set_name(Some("All Files"));
+    filter_all.add_mime_type("*/*");
+    filter_all.add_suffix("*");
+
+    let filters = ListStore::new(FileFilter::static_type());
+    filters.append(&filter_pdf);
+    filters.append(&filter_all);
+
+    FileDialog::builder()
+        .title(title)
+        .modal(true)
+        .accept_label(accept_label)
+        .filters(&filters)
+        .default_filter(&filter_pdf)
+        .build()
+}
+
+fn text_layer_file_dialog() -> FileDialog {
+    let filter_json = FileFilter::new();
+    filter_json.add_mime_type("application/json");
+    filter_json.add_suffix("json");
+    filter_json.set_name(Some("JSON"));
+
+    let filter_hocr = FileFilter::new();
+    filter_hocr.add_suffix("hocr");
+    filter_hocr.add_suffix("html");
+    filter_hocr.set_name(Some("hOCR"));
+
+    let filters = ListStore::new(FileFilter::static_type());
+    filters.append(&filter_json);
+    filters.append(&filter_hocr);
+
+    FileDialog::builder()
+        .title("Export Text Layer")
+        .modal(true)
+        .accept_label("Export")
+        .initial_name("text.json")
+        .filters(&filters)
+        .default_filter(&filter_json)
+        .build()
+}
+
+fn png_file_dialog() -> FileDialog {
+    let filter_png = FileFilter::new();
+    filter_png.add_mime_type("image/png");
+    filter_png.add_suffix("png");
+    filter_png.set_name(Some("PNG Images"));
+
+    let filters = ListStore::new(FileFilter::static_type());
+    filters.append(&filter_png);
+
+    FileDialog::builder()
+        .title("Export Pages as Images")
+        .modal(true)
+        .accept_label("Export")
+        .filters(&filters)
+        .default_filter(&filter_png)
+        .build()
+}
+
+fn xfdf_file_dialog(title: &str, accept_label: &str) -> FileDialog {
+    let filter_xfdf = FileFilter::new();
+    filter_xfdf.add_mime_type("application/vnd.adobe.xfdf");
+    filter_xfdf.add_suffix("xfdf");
+    filter_xfdf.set_name(Some("XFDF"));
+
+    let filters = ListStore::new(FileFilter::static_type());
+    filters.append(&filter_xfdf);
+
+    FileDialog::builder()
+        .title(title)
+        .modal(true)
+        .accept_label(accept_label)
+        .filters(&filters)
+        .default_filter(&filter_xfdf)
+        .build()
+}
+
+fn image_file_dialog() -> FileDialog {
+    let filter_image = FileFilter::new();
+    filter_image.add_mime_type("image/png");
+    filter_image.add_mime_type("image/jpeg");
+    filter_image.add_suffix("png");
+    filter_image.add_suffix("jpg");
+    filter_image.add_suffix("jpeg");
+    filter_image.set_name(Some("Images"));
+
+    let filters = ListStore::new(FileFilter::static_type());
+    filters.append(&filter_image);
+
+    FileDialog::builder()
+        .title("Stamp Image")
+        .modal(true)
+        .accept_label("Select")
+        .filters(&filters)
+        .default_filter(&filter_image)
+        .build()
+}
@@ -1,6 +1,9 @@
 use gtk::subclass::prelude::ObjectSubclassIsExt;
 use gtk::{gio, glib};
 
+use crate::core::cli::Zoom;
+use crate::core::position::PositionLink;
+
 mod imp;
 
 glib::wrapper! {
@@ -18,4 +21,36 @@ impl AppWindow {
     pub fn open_file(&self, file: gio::File) {
         self.imp().open_file(file)
     }
+
+    /// Open the given file at the given page and zoom instead of the position
+    /// it has last been viewed at.
+    pub fn open_file_with(&self, file: gio::File, page: Option<usize>, zoom: Option<Zoom>) {
+        self.imp().open_file_with(file, page, zoom)
+    }
+
+    pub fn open_position(&self, link: PositionLink) {
+        self.imp().open_position(link)
+    }
+
+    pub fn has_document(&self) -> bool {
+        self.imp().has_document()
+    }
+
+    /// The positions in all open documents, in the order of their tabs.
+    pub fn open_positions(&self) -> Vec<PositionLink> {
+        self.imp().open_positions()
+    }
+
+    /// Offer restoring the given positions left open by a crashed run.
+    pub fn offer_restore(&self, positions: Vec<PositionLink>) {
+        self.imp().offer_restore(positions)
+    }
+
+    pub fn apply_layout_settings(&self) {
+        self.imp().apply_layout_settings()
+    }
+
+    pub fn apply_tiling_settings(&self) {
+        self.imp().apply_tiling_settings()
+    }
 }
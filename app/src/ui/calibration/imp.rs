@@ -0,0 +1,219 @@
+use std::cell::{Cell, RefCell};
+
+use adw::subclass::prelude::AdwWindowImpl;
+use gtk::glib::subclass::{InitializingObject, Signal};
+use gtk::glib::{clone, once_cell::sync::Lazy};
+use gtk::prelude::{DrawingAreaExtManual, MonitorExt, ObjectExt, StaticType};
+use gtk::subclass::prelude::{
+    CompositeTemplateClass, CompositeTemplateInitializingExt, ObjectImpl, ObjectImplExt,
+    ObjectSubclass, ObjectSubclassExt, ObjectSubclassIsExt, WidgetImpl, WindowImpl,
+};
+use gtk::subclass::widget::WidgetClassSubclassExt;
+use gtk::traits::{ButtonExt, GtkWindowExt, RangeExt, WidgetExt};
+use gtk::{cairo, gdk, glib, CompositeTemplate, TemplateChild};
+
+/// Size of an ID-1 card (e.g. credit card), in millimeters.
+const CARD_SIZE_MM: (f64, f64) = (85.60, 53.98);
+
+/// Corner radius of an ID-1 card, in millimeters.
+const CARD_RADIUS_MM: f64 = 3.18;
+
+const MM_PER_INCH: f64 = 25.4;
+
+#[derive(Debug, Default, CompositeTemplate)]
+#[template(resource = "/io/mxnluz/papr/ui/calibration.ui")]
+pub struct CalibrationDialog {
+    #[template_child]
+    ruler: TemplateChild<gtk::DrawingArea>,
+
+    #[template_child]
+    dpi_scale: TemplateChild<gtk::Scale>,
+
+    #[template_child]
+    diagonal: TemplateChild<gtk::SpinButton>,
+
+    #[template_child]
+    apply_button: TemplateChild<gtk::Button>,
+
+    #[template_child]
+    cancel_button: TemplateChild<gtk::Button>,
+
+    // diagonal of the monitor, in (logical) pixels
+    diagonal_px: Cell<Option<f64>>,
+
+    // guard against recursive updates between slider and diagonal
+    updating: Cell<bool>,
+
+    monitor: RefCell<Option<gdk::Monitor>>,
+}
+
+impl CalibrationDialog {
+    pub fn setup(&self, monitor: &gdk::Monitor, dpi: f64) {
+        let geometry = monitor.geometry();
+        let diagonal = (geometry.width() as f64).hypot(geometry.height() as f64);
+
+        self.monitor.replace(Some(monitor.clone()));
+        self.diagonal_px.set(Some(diagonal));
+        self.set_dpi(dpi);
+    }
+
+    pub fn dpi(&self) -> f64 {
+        self.dpi_scale.value()
+    }
+
+    fn set_dpi(&self, dpi: f64) {
+        self.dpi_scale.set_value(dpi);
+        self.ruler.queue_draw();
+        self.update_diagonal();
+    }
+
+    fn update_diagonal(&self) {
+        if self.updating.replace(true) {
+            return;
+        }
+
+        if let Some(diagonal) = self.diagonal_px.get() {
+            self.diagonal.set_value(diagonal / self.dpi());
+        }
+
+        self.updating.set(false);
+    }
+
+    fn update_from_diagonal(&self) {
+        if self.updating.replace(true) {
+            return;
+        }
+
+        if let Some(diagonal) = self.diagonal_px.get() {
+            self.dpi_scale.set_value(diagonal / self.diagonal.value());
+            self.ruler.queue_draw();
+        }
+
+        self.updating.set(false);
+    }
+
+    fn draw_ruler(&self, cr: &cairo::Context, width: i32, height: i32) {
+        let px_per_mm = self.dpi() / MM_PER_INCH;
+
+        let w = CARD_SIZE_MM.0 * px_per_mm;
+        let h = CARD_SIZE_MM.1 * px_per_mm;
+        let r = CARD_RADIUS_MM * px_per_mm;
+
+        // center card outline in drawing area
+        let x = (width as f64 - w) / 2.0;
+        let y = (height as f64 - h) / 2.0;
+
+        let color = self.ruler.color();
+        cr.set_source_rgba(
+            color.red() as _,
+            color.green() as _,
+            color.blue() as _,
+            color.alpha() as _,
+        );
+
+        // card outline
+        cr.new_sub_path();
+        cr.arc(x + w - r, y + r, r, -std::f64::consts::FRAC_PI_2, 0.0);
+        cr.arc(x + w - r, y + h - r, r, 0.0, std::f64::consts::FRAC_PI_2);
+        cr.arc(
+            x + r,
+            y + h - r,
+            r,
+            std::f64::consts::FRAC_PI_2,
+            std::f64::consts::PI,
+        );
+        cr.arc(
+            x + r,
+            y + r,
+            r,
+            std::f64::consts::PI,
+            1.5 * std::f64::consts::PI,
+        );
+        cr.close_path();
+
+        cr.set_line_width(2.0);
+        let _ = cr.stroke();
+
+        // millimeter ticks along the top edge
+        cr.set_line_width(1.0);
+
+        for mm in 0..=(CARD_SIZE_MM.0 as i32) {
+            let len = if mm % 10 == 0 {
+                3.0
+            } else if mm % 5 == 0 {
+                2.0
+            } else {
+                1.0
+            };
+
+            let tx = (x + mm as f64 * px_per_mm).round() + 0.5;
+            cr.move_to(tx, y);
+            cr.line_to(tx, y + len * px_per_mm);
+        }
+
+        let _ = cr.stroke();
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for CalibrationDialog {
+    const NAME: &'static str = "CalibrationDialog";
+    type Type = super::CalibrationDialog;
+    type ParentType = adw::Window;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+    }
+
+    fn instance_init(obj: &InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+impl ObjectImpl for CalibrationDialog {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        let obj = self.obj();
+
+        self.ruler
+            .set_draw_func(clone!(@weak obj => move |_, cr, width, height| {
+                obj.imp().draw_ruler(cr, width, height);
+            }));
+
+        self.dpi_scale
+            .connect_value_changed(clone!(@weak obj => move |_| {
+                obj.imp().ruler.queue_draw();
+                obj.imp().update_diagonal();
+            }));
+
+        self.diagonal
+            .connect_value_changed(clone!(@weak obj => move |_| {
+                obj.imp().update_from_diagonal();
+            }));
+
+        self.apply_button
+            .connect_clicked(clone!(@weak obj => move |_| {
+                obj.emit_by_name::<()>("calibrated", &[&obj.imp().dpi()]);
+                obj.close();
+            }));
+
+        self.cancel_button
+            .connect_clicked(clone!(@weak obj => move |_| {
+                obj.close();
+            }));
+    }
+
+    fn signals() -> &'static [Signal] {
+        static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+            vec![Signal::builder("calibrated")
+                .param_types([f64::static_type()])
+                .build()]
+        });
+        SIGNALS.as_ref()
+    }
+}
+
+impl WidgetImpl for CalibrationDialog {}
+impl WindowImpl for CalibrationDialog {}
+impl AdwWindowImpl for CalibrationDialog {}
@@ -0,0 +1,59 @@
+use gtk::prelude::{IsA, MonitorExt, ObjectExt};
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::traits::GtkWindowExt;
+use gtk::{gdk, glib};
+
+mod imp;
+
+glib::wrapper! {
+    pub struct CalibrationDialog(ObjectSubclass<imp::CalibrationDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native,
+                gtk::Root, gtk::ShortcutManager;
+}
+
+impl CalibrationDialog {
+    pub fn new(parent: &impl IsA<gtk::Window>, monitor: &gdk::Monitor, dpi: f64) -> Self {
+        let dialog: Self = glib::Object::new();
+        dialog.set_transient_for(Some(parent));
+        dialog.imp().setup(monitor, dpi);
+        dialog
+    }
+
+    /// Connect to the signal emitted when the user applies a calibration,
+    /// providing the physical display resolution in (logical) pixels per inch.
+    pub fn connect_calibrated<F: Fn(&Self, f64) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "calibrated",
+            false,
+            glib::closure_local!(move |dialog: &Self, dpi: f64| f(dialog, dpi)),
+        )
+    }
+}
+
+/// Stable identifier for a monitor, used to store per-monitor settings.
+pub fn monitor_id(monitor: &gdk::Monitor) -> String {
+    let id = monitor
+        .connector()
+        .or_else(|| monitor.model())
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| "default".into());
+
+    // restrict to characters that are valid in key-file keys
+    id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// Estimate the physical resolution of a monitor, in (logical) pixels per
+/// inch, based on the size it reports.
+pub fn estimate_dpi(monitor: &gdk::Monitor) -> f64 {
+    let width_px = monitor.geometry().width() as f64;
+    let width_mm = monitor.width_mm() as f64;
+
+    if width_px > 0.0 && width_mm > 0.0 {
+        width_px / (width_mm / 25.4)
+    } else {
+        96.0
+    }
+}
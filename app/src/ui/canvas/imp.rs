@@ -1,30 +1,56 @@
 use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
 use executor::exec::Monitor;
 
 use gtk::{
-    gdk,
-    glib::{self, once_cell::sync::Lazy, ParamSpec, Value},
-    graphene,
-    prelude::{ObjectExt, ParamSpecBuilderExt, ToValue},
+    accessible, gdk,
+    glib::{self, once_cell::sync::Lazy, subclass::Signal, ParamSpec, Value},
+    graphene, gsk, pango,
+    prelude::{
+        AccessibleExtManual, Cast, ObjectExt, ParamSpecBuilderExt, SnapshotExtManual, StaticType,
+        TextureExt, TextureExtManual, ToValue, WidgetExtManual,
+    },
     subclass::{
         prelude::{ObjectImpl, ObjectSubclass, ObjectSubclassExt, ObjectSubclassIsExt, ObjectImplExt},
         scrollable::ScrollableImpl,
-        widget::WidgetImpl,
+        widget::{WidgetClassSubclassExt, WidgetImpl, WidgetImplExt},
+    },
+    traits::{
+        AdjustmentExt, EventControllerExt, GestureDragExt, GestureExt, NativeExt, PopoverExt,
+        ScrollableExt, SnapshotExt, TextBufferExt, TextViewExt, WidgetExt,
     },
-    traits::{AdjustmentExt, ScrollableExt, SnapshotExt, WidgetExt},
     Adjustment, ScrollablePolicy,
 };
 
-use nalgebra::{point, vector, Point2, Similarity2, Translation2};
+use nalgebra::{point, vector, Point2, Vector2};
 
 use pdfium::bitmap::Color;
-use pdfium::doc::{Document, RenderFlags};
-
-use crate::core::render::core::{FallbackManager, FallbackSpec, HybridTilingScheme, TileManager};
+use pdfium::doc::{Annotation, Document, PageRotation, RenderFlags};
+
+use crate::core::appearance::{NIGHT_BACKGROUND, NIGHT_COLORS};
+use crate::core::caret::CaretMarks;
+use crate::core::citations::Link;
+use crate::core::fling::FlingPredictor;
+use crate::core::fonts::FontReport;
+use crate::core::ink::{Pen, Stroke};
+use crate::core::markup::MarkupKind;
+use crate::core::render::core::{FallbackLimits, FallbackSpec, TilingSchemeKind};
+use crate::core::render::diff::{DiffOptions, PdfDiffProvider};
+use crate::core::render::disk::DiskCache;
+use crate::core::render::gl::GlUploader;
 use crate::core::render::interop::{Bitmap, TileFactory};
-use crate::core::render::layout::Layout;
-use crate::core::render::pdfium::{Executor, Handle, PdfTileProvider, RenderOptions};
+use crate::core::render::layout::{LayoutKind, PageSizeIssue};
+use crate::core::render::pdfium::{render_threads, Damage, Executor, RenderOptions};
+use crate::core::render::telemetry::{FrameWatchdog, TileSizePolicy};
+use crate::core::search::SearchMatch;
+use crate::core::stamps::{self, Corner, Stamp, StampContent};
+use crate::core::view::{DocumentView, Event, FrameHints, Input, PageFrame, ViewOptions};
 use crate::types::{Bounds, Margin, Rect, Viewport};
 
 pub struct CanvasWidget {
@@ -34,301 +60,2499 @@ pub struct CanvasWidget {
     hadjustment: RefCell<Option<Adjustment>>,
     vadjustment: RefCell<Option<Adjustment>>,
 
-    // handlers for scrolling
-    hadjustment_handler: Cell<Option<glib::SignalHandlerId>>,
-    vadjustment_handler: Cell<Option<glib::SignalHandlerId>>,
+    // handlers for scrolling
+    hadjustment_handler: Cell<Option<glib::SignalHandlerId>>,
+    vadjustment_handler: Cell<Option<glib::SignalHandlerId>>,
+
+    // properties for canvas
+    margin: RefCell<Margin<f64>>,
+    page_spacing: Cell<f64>,
+    layout: Cell<LayoutKind>,
+    rotation: Cell<PageRotation>,
+    single_page: Cell<Option<usize>>,
+
+    // properties for viewport
+    offset: RefCell<Point2<f64>>,
+    scale: Cell<f64>,
+
+    // render options, halos and limits are applied when opening documents
+    view_opts: ViewOptions,
+
+    // render state
+    viewport: RefCell<Viewport>,
+    scale_factor: Cell<f64>,
+    skeleton_visible: Cell<bool>,
+    skeleton_animating: Cell<bool>,
+    idle: Cell<bool>,
+    idle_timer: RefCell<Option<glib::SourceId>>,
+    debug_overlay: Cell<bool>,
+    low_power: Cell<bool>,
+    prerender_neighbors: Cell<bool>,
+    suspended: Cell<bool>,
+    tiling_scheme: Cell<TilingSchemeKind>,
+    tile_size: Cell<i64>,
+    gl_upload: Cell<bool>,
+    texture_factory: TextureFactory,
+    fling: RefCell<FlingPredictor>,
+    slide: Cell<Option<(f64, i64)>>,
+    slide_offset: Cell<f64>,
+
+    // time taken to build frames, for shedding load if frames are too slow
+    watchdog: RefCell<FrameWatchdog>,
+
+    // whether pages are shown with dark colors, and the current state of the
+    // transition between light (0.0) and dark (1.0) colors
+    dark_mode: Cell<bool>,
+    dark_fade: Cell<f64>,
+    dark_fade_time: Cell<Option<i64>>,
+
+    // whether pages are rendered with night colors
+    night_colors: Cell<bool>,
+
+    // tool handling clicks and drags on pages
+    tool: RefCell<Tool>,
+
+    // stroke being drawn with the pen tool
+    stroke: RefCell<Option<Stroke>>,
+
+    // selected stamp, and stamp being moved or resized
+    selected_stamp: Cell<Option<usize>>,
+    stamp_drag: Cell<Option<StampDrag>>,
+    stamp_images: RefCell<HashMap<PathBuf, Option<gdk::Texture>>>,
+
+    // whether text is being selected via drag gesture
+    selecting_text: Cell<bool>,
+
+    // text exposed to assistive technologies, i.e. the label of the current
+    // page, its text, and the focused search result or selection, if any
+    accessible_page: RefCell<(String, String)>,
+    accessible_focus: RefCell<Option<String>>,
+
+    // document data
+    data: RefCell<Option<DocumentData>>,
+
+    // currently open annotation note
+    note: RefCell<Option<Note>>,
+}
+
+/// Popover showing the contents of a text annotation.
+struct Note {
+    popover: gtk::Popover,
+    page: usize,
+
+    // annotation bounds in page coordinates
+    rect: Rect<f64>,
+}
+
+/// Tool handling clicks and drags on pages.
+#[derive(Debug, Clone)]
+enum Tool {
+    /// Follow links and open notes on click, select text and move stamps via
+    /// drag.
+    Pointer,
+
+    /// Pick the rendered color on click.
+    Eyedropper,
+
+    /// Place a stamp with the given content on the next click.
+    Stamp(StampContent),
+
+    /// Draw freehand strokes, written to the document as ink annotations.
+    Pen(Pen),
+}
+
+impl Tool {
+    fn cursor(&self) -> Option<&'static str> {
+        match self {
+            Tool::Pointer => None,
+            Tool::Eyedropper | Tool::Stamp(_) | Tool::Pen(_) => Some("crosshair"),
+        }
+    }
+}
+
+/// Interaction with a stamp via drag gesture.
+#[derive(Debug, Clone, Copy)]
+enum StampDrag {
+    /// Move the stamp, keeping the grabbed point, relative to the stamp
+    /// origin, under the pointer.
+    Move {
+        index: usize,
+        start: Point2<f64>,
+        grab: Vector2<f64>,
+    },
+
+    /// Move the given corner of the stamp to the pointer.
+    Resize {
+        index: usize,
+        start: Point2<f64>,
+        corner: Corner,
+    },
+}
+
+struct DocumentData {
+    view: DocumentView<TaskMonitor, TextureFactory>,
+    diff_provider: Option<PdfDiffProvider<TaskMonitor, HeatMapFactory>>,
+
+    // stamps placed on pages but not yet written to the document, i.e. the
+    // selected one and ones that could not be written
+    stamps: Vec<Stamp>,
+
+    // strokes written to the document, drawn on top of the pages until these
+    // have been rendered again
+    strokes: Vec<Stroke>,
+}
+
+impl CanvasWidget {
+    fn new() -> Self {
+        Self {
+            hscroll_policy: Cell::new(ScrollablePolicy::Minimum),
+            vscroll_policy: Cell::new(ScrollablePolicy::Minimum),
+            hadjustment: RefCell::new(None),
+            vadjustment: RefCell::new(None),
+
+            hadjustment_handler: Cell::new(None),
+            vadjustment_handler: Cell::new(None),
+
+            margin: RefCell::new(Margin {
+                left: 50.0,
+                right: 50.0,
+                top: 100.0,
+                bottom: 100.0,
+            }),
+            page_spacing: Cell::new(10.0),
+            layout: Cell::new(LayoutKind::Vertical),
+            rotation: Cell::new(PageRotation::None),
+            single_page: Cell::new(None),
+            offset: RefCell::new(point![0.0, 0.0]),
+            scale: Cell::new(1.0),
+
+            viewport: RefCell::new(Viewport {
+                r: Rect {
+                    offs: point![0.0, 0.0],
+                    size: vector![600.0, 800.0],
+                },
+                scale: 1.0,
+            }),
+            scale_factor: Cell::new(1.0),
+            skeleton_visible: Cell::new(false),
+            skeleton_animating: Cell::new(false),
+            idle: Cell::new(false),
+            idle_timer: RefCell::new(None),
+            debug_overlay: Cell::new(false),
+            low_power: Cell::new(false),
+            prerender_neighbors: Cell::new(false),
+            suspended: Cell::new(false),
+            tiling_scheme: Cell::new(TilingSchemeKind::Hybrid),
+            tile_size: Cell::new(1024),
+            gl_upload: Cell::new(false),
+            texture_factory: TextureFactory::default(),
+            fling: RefCell::new(FlingPredictor::new()),
+            slide: Cell::new(None),
+            slide_offset: Cell::new(0.0),
+            watchdog: RefCell::new(FrameWatchdog::new(FRAME_TIME_BUDGET)),
+            dark_mode: Cell::new(false),
+            dark_fade: Cell::new(0.0),
+            dark_fade_time: Cell::new(None),
+            night_colors: Cell::new(false),
+            tool: RefCell::new(Tool::Pointer),
+            stroke: RefCell::new(None),
+            selected_stamp: Cell::new(None),
+            stamp_drag: Cell::new(None),
+            stamp_images: RefCell::new(HashMap::new()),
+            selecting_text: Cell::new(false),
+            accessible_page: RefCell::new((String::new(), String::new())),
+            accessible_focus: RefCell::new(None),
+
+            view_opts: ViewOptions {
+                page_spacing: 10.0,
+                layout: LayoutKind::Vertical,
+                fallback_specs: vec![
+                    FallbackSpec {
+                        halo: usize::MAX,
+                        render_threshold: vector![0.0, 0.0],
+                        render_limits: vector![128, 128],
+                    },
+                    FallbackSpec {
+                        halo: 24,
+                        render_threshold: vector![256.0, 256.0],
+                        render_limits: vector![256, 256],
+                    },
+                    FallbackSpec {
+                        halo: 1,
+                        render_threshold: vector![1024.0, 1024.0],
+                        render_limits: vector![1024, 1024],
+                    },
+                    FallbackSpec {
+                        halo: 0,
+                        render_threshold: vector![2048.0, 2048.0],
+                        render_limits: vector![2048, 2048],
+                    },
+                    FallbackSpec {
+                        halo: 0,
+                        render_threshold: vector![3072.0, 3072.0],
+                        render_limits: vector![3072, 3072],
+                    },
+                ],
+                render_main: RenderOptions {
+                    flags: RenderFlags::LcdText | RenderFlags::Annotations,
+                    background: Color::WHITE,
+                    color_scheme: None,
+                    rotation: PageRotation::None,
+                },
+                render_fallback: RenderOptions {
+                    flags: RenderFlags::Annotations,
+                    background: Color::WHITE,
+                    color_scheme: None,
+                    rotation: PageRotation::None,
+                },
+                page_cache_capacity: 16,
+                render_threads: render_threads(),
+                render_thread_limit: None,
+                tile_halo: TILE_HALO,
+                fallback_halo_limit: None,
+                fallback_halo_minimum: 0,
+                fallback_limits: Some(FallbackLimits {
+                    pending: FALLBACK_PENDING_LIMIT,
+                    requests: FALLBACK_REQUEST_LIMIT,
+                }),
+                upload_limit: None,
+
+                // one zoom step in and out, matching the viewport's zoom step
+                warm_factors: vec![1.1, 0.9],
+                warm_budget: 8 * 1024 * 1024,
+            },
+
+            data: RefCell::new(None),
+            note: RefCell::new(None),
+        }
+    }
+
+    fn bounds(&self) -> Bounds<f64> {
+        match self.data.borrow().as_ref() {
+            Some(data) => data.view.bounds(),
+            None => Bounds::zero(),
+        }
+    }
+
+    fn scale_bounds(&self) -> (f64, f64) {
+        (1e-2, 5e3)
+    }
+
+    /// Options for documents opened with the current settings.
+    fn view_options(&self) -> ViewOptions {
+        let mut opts = self.view_opts.clone();
+        opts.page_spacing = self.page_spacing.get();
+        opts.layout = self.layout.get();
+
+        if self.low_power.get() {
+            opts.tile_halo = vector![0, 0];
+            opts.fallback_halo_limit = Some(LOW_POWER_FALLBACK_HALO);
+            opts.render_thread_limit = Some(LOW_POWER_RENDER_THREADS);
+        }
+
+        if self.prerender_neighbors.get() {
+            opts.fallback_halo_minimum = NEIGHBOR_FALLBACK_HALO;
+        }
+
+        if self.watchdog.borrow().is_shedding() {
+            opts.upload_limit = Some(SHED_UPLOAD_LIMIT);
+        }
+
+        if self.night_colors.get() {
+            for render in [&mut opts.render_main, &mut opts.render_fallback] {
+                render.background = NIGHT_BACKGROUND;
+                render.color_scheme = Some(NIGHT_COLORS);
+            }
+        }
+
+        opts.render_main.rotation = self.rotation.get();
+        opts.render_fallback.rotation = self.rotation.get();
+
+        opts
+    }
+
+    pub fn set_document(&self, doc: Document) -> Vec<(usize, PageSizeIssue)> {
+        let issues = self.open_document(doc);
+        self.obj().grab_focus();
+
+        issues
+    }
+
+    /// Replace the document with a new version of it, e.g. after it has been
+    /// changed on disk, keeping offset and scale. Rendered tiles are dropped
+    /// along with the previous document.
+    pub fn swap_document(&self, doc: Document) -> Vec<(usize, PageSizeIssue)> {
+        // the shown page may have been removed
+        let count = doc.pages().count() as usize;
+        if let Some(page) = self.single_page.get() {
+            self.single_page
+                .set(Some(page.min(count.saturating_sub(1))));
+        }
+
+        // offsets are kept as properties and get clipped on allocation
+        self.open_document(doc)
+    }
+
+    fn open_document(&self, doc: Document) -> Vec<(usize, PageSizeIssue)> {
+        // frame times of the previous document say nothing about this one
+        self.watchdog.borrow_mut().reset();
+
+        // set up the view, computing the layout with corrected page sizes
+        let monitor = TaskMonitor::new(self.obj().clone());
+        let mut view = DocumentView::open(
+            doc,
+            monitor,
+            self.texture_factory.clone(),
+            self.tile_policy(),
+            self.view_options(),
+        );
+
+        view.set_single_page(self.single_page.get());
+
+        if self.suspended.get() {
+            view.suspend();
+        }
+
+        let issues = view.issues().to_vec();
+
+        let data = DocumentData {
+            view,
+            diff_provider: None,
+            stamps: Vec::new(),
+            strokes: Vec::new(),
+        };
+
+        self.close_note();
+        self.stroke.replace(None);
+        self.selected_stamp.set(None);
+        self.stamp_drag.set(None);
+
+        *self.data.borrow_mut() = Some(data);
+        self.sync_viewport();
+        self.obj().queue_allocate();
+
+        issues
+    }
+
+    /// Recompute the page layout with the current spacing, keeping the first
+    /// visible page in place.
+    fn relayout(&self) {
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            let shift = data.view.set_page_spacing(self.page_spacing.get());
+            *self.offset.borrow_mut() += shift * self.scale.get();
+        }
+
+        self.obj().queue_resize();
+    }
+
+    pub fn layout(&self) -> LayoutKind {
+        self.layout.get()
+    }
+
+    pub fn set_layout(&self, kind: LayoutKind) {
+        if self.layout.replace(kind) == kind {
+            return;
+        }
+
+        // keep the page at the center of the viewport in place
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            let shift = data.view.set_layout(kind);
+            *self.offset.borrow_mut() += shift * self.scale.get();
+        }
+
+        self.obj().queue_resize();
+    }
+
+    pub fn set_crops(&self, crops: &[Option<Rect<f64>>]) {
+        // keep the page at the center of the viewport in place
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            let shift = data.view.set_crops(crops);
+            *self.offset.borrow_mut() += shift * self.scale.get();
+        }
+
+        self.obj().queue_resize();
+    }
+
+    pub fn is_cropped(&self) -> bool {
+        let data = self.data.borrow();
+        data.as_ref().is_some_and(|data| data.view.is_cropped())
+    }
+
+    pub fn rotation(&self) -> PageRotation {
+        self.rotation.get()
+    }
+
+    pub fn set_rotation(&self, rotation: PageRotation) {
+        if self.rotation.replace(rotation) == rotation {
+            return;
+        }
+
+        // keep the page at the center of the viewport in place
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            let shift = data.view.set_rotation(rotation);
+            *self.offset.borrow_mut() += shift * self.scale.get();
+        }
+
+        self.obj().queue_resize();
+    }
+
+    pub fn clear(&self) {
+        self.close_note();
+        self.stroke.replace(None);
+        self.selected_stamp.set(None);
+        self.stamp_drag.set(None);
+        self.set_accessible_page(String::new(), String::new());
+        self.set_accessible_focus(None);
+
+        *self.data.borrow_mut() = None;
+        self.obj().queue_allocate();
+    }
+
+    pub fn set_compare_document(&self, doc: Option<Document>) {
+        let mut data = self.data.borrow_mut();
+        let data = match data.as_mut() {
+            Some(data) => data,
+            None => return,
+        };
+
+        data.diff_provider = doc.map(|doc| {
+            let opts = DiffOptions {
+                threshold: 32,
+                render_limits: vector![1024, 1024],
+                render: RenderOptions {
+                    rotation: self.rotation.get(),
+                    ..self.view_opts.render_fallback.clone()
+                },
+            };
+
+            let executor = data.view.executor().clone();
+            let monitor = TaskMonitor::new(self.obj().clone());
+            let base = data.view.document().clone();
+
+            PdfDiffProvider::new(executor, monitor, HeatMapFactory, base, doc, opts)
+        });
+
+        self.obj().queue_draw();
+    }
+
+    pub fn compare_added_pages(&self) -> Range<usize> {
+        let data = self.data.borrow();
+        let diff = data.as_ref().and_then(|data| data.diff_provider.as_ref());
+
+        diff.map(|diff| diff.added_pages()).unwrap_or(0..0)
+    }
+
+    pub fn set_links(&self, links: Vec<Link>) {
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.view.set_links(links);
+        }
+
+        self.obj().queue_draw();
+    }
+
+    pub fn set_font_report(&self, report: Option<(usize, FontReport)>) {
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.view.set_font_report(report);
+        }
+
+        self.obj().queue_draw();
+    }
+
+    pub fn set_caret(&self, marks: Option<CaretMarks>) {
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.view.set_caret(marks);
+        }
+
+        self.obj().queue_draw();
+    }
+
+    pub fn set_search_results(&self, matches: Vec<SearchMatch>, current: Option<usize>) {
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.view.set_search_results(matches, current);
+        }
+
+        self.obj().queue_draw();
+    }
+
+    pub fn set_accessible_page(&self, label: String, text: String) {
+        self.accessible_page.replace((label, text));
+        self.update_accessible();
+    }
+
+    pub fn set_accessible_focus(&self, focus: Option<String>) {
+        self.accessible_focus.replace(focus);
+        self.update_accessible();
+    }
+
+    /// Expose the current page to assistive technologies. The text of the
+    /// focused search result or selection takes precedence over the text of
+    /// the page, so that it is announced when it changes.
+    fn update_accessible(&self) {
+        let (label, text) = &*self.accessible_page.borrow();
+        let focus = self.accessible_focus.borrow();
+
+        let description = match focus.as_deref() {
+            Some(focus) => focus,
+            None => text,
+        };
+
+        self.obj().update_property(&[
+            accessible::Property::Label(label),
+            accessible::Property::Description(description),
+        ]);
+    }
+
+    /// Pass the current viewport on to the view of the document.
+    fn sync_viewport(&self) {
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.view
+                .set_viewport(&self.viewport.borrow(), self.scale_factor.get());
+        }
+    }
+
+    /// Transform a point in viewport coordinates to the index of the page it
+    /// is on and the respective page coordinates.
+    fn page_at(&self, pos: Point2<f64>) -> Option<(usize, Point2<f64>)> {
+        self.data.borrow().as_ref()?.view.page_at(pos)
+    }
+
+    /// Pass the given input on to the view of the document.
+    fn handle_input(&self, input: Input) -> Option<Event> {
+        self.data.borrow_mut().as_mut()?.view.handle_input(input)
+    }
+
+    /// Follow activated links, open activated text annotations.
+    fn activate(&self, event: Option<Event>) {
+        let obj = self.obj();
+
+        match event {
+            Some(Event::LinkActivated(target)) => {
+                let r = target.rect;
+
+                obj.emit_by_name::<()>(
+                    "link-activated",
+                    &[
+                        &(target.page as u32),
+                        &r.offs.x,
+                        &r.offs.y,
+                        &r.size.x,
+                        &r.size.y,
+                    ],
+                );
+            }
+            Some(Event::UriActivated(uri)) => {
+                obj.emit_by_name::<()>("uri-activated", &[&uri]);
+            }
+            Some(Event::NoteActivated { page, annot, rect }) => {
+                self.open_note(page, annot, rect);
+            }
+            _ => {}
+        }
+    }
+
+    /// The text selected with the pointer, if any.
+    pub fn selected_text(&self) -> Option<String> {
+        self.data.borrow().as_ref()?.view.selected_text()
+    }
+
+    /// Label all visible links, for following them by typing their label.
+    /// Returns `false` if no links are visible.
+    pub fn show_link_hints(&self) -> bool {
+        self.handle_input(Input::ShowLinkHints);
+        self.obj().queue_draw();
+
+        self.is_showing_link_hints()
+    }
+
+    fn is_showing_link_hints(&self) -> bool {
+        match self.data.borrow().as_ref() {
+            Some(data) => data.view.is_showing_link_hints(),
+            None => false,
+        }
+    }
+
+    /// Handle a key pressed while link hints are shown. Letters select the
+    /// link to follow, any other key hides the hints. Returns `true` if the
+    /// key has been consumed.
+    fn link_hint_key(&self, key: gdk::Key) -> bool {
+        let c = key.to_unicode().filter(|c| c.is_ascii_alphabetic());
+
+        let event = match c {
+            Some(c) => self.handle_input(Input::LinkHintKey(c)),
+            None => self.handle_input(Input::HideLinkHints),
+        };
+
+        self.obj().queue_draw();
+        self.activate(event);
+
+        c.is_some() || key == gdk::Key::Escape
+    }
+
+    /// Check if there is a link at the given point in viewport coordinates.
+    fn is_link_at(&self, pos: Point2<f64>) -> bool {
+        match self.data.borrow().as_ref() {
+            Some(data) => data.view.link_at(pos).is_some(),
+            None => false,
+        }
+    }
+
+    /// Switch to the given tool, dropping any unfinished stroke.
+    fn set_tool(&self, tool: Tool) {
+        self.obj().set_cursor_from_name(tool.cursor());
+        self.tool.replace(tool);
+
+        if self.stroke.take().is_some() {
+            self.obj().queue_draw();
+        }
+    }
+
+    /// Switch back to the pointer if the current tool matches the given
+    /// predicate.
+    fn reset_tool(&self, pred: impl Fn(&Tool) -> bool) {
+        let active = pred(&self.tool.borrow());
+
+        if active {
+            self.set_tool(Tool::Pointer);
+        }
+    }
+
+    fn is_pointer(&self) -> bool {
+        matches!(*self.tool.borrow(), Tool::Pointer)
+    }
+
+    /// Pick colors on click instead of following links and opening notes.
+    pub fn set_eyedropper(&self, enabled: bool) {
+        if enabled {
+            self.set_tool(Tool::Eyedropper);
+        } else {
+            self.reset_tool(|tool| matches!(tool, Tool::Eyedropper));
+        }
+    }
+
+    /// Sample the rendered color at the given point in viewport coordinates.
+    ///
+    /// The color is read back from the cached tile textures at the current
+    /// zoom level, or from the page fallback if no tile covers the point yet.
+    /// Overlays, e.g. the difference heat map, are not included.
+    pub fn color_at(&self, pos: Point2<f64>) -> Option<gdk::RGBA> {
+        let data = self.data.borrow();
+        let (rect, tex) = data.as_ref()?.view.tile_at(pos)?;
+
+        texture_color_at(tex, &rect, &pos)
+    }
+
+    /// Place a stamp with the given content on the next click on a page, or
+    /// stop placing stamps.
+    pub fn set_stamp_tool(&self, content: Option<StampContent>) {
+        match content {
+            Some(content) => self.set_tool(Tool::Stamp(content)),
+            None => self.reset_tool(|tool| matches!(tool, Tool::Stamp(_))),
+        }
+    }
+
+    /// Draw freehand strokes with the given pen instead of interacting with
+    /// the page, or stop drawing.
+    pub fn set_pen_tool(&self, pen: Option<Pen>) {
+        match pen {
+            Some(pen) => self.set_tool(Tool::Pen(pen)),
+            None => self.reset_tool(|tool| matches!(tool, Tool::Pen(_))),
+        }
+    }
+
+    /// Start a stroke of the pen tool at the given point in viewport
+    /// coordinates. Returns `false` if the point is not on a page.
+    fn begin_stroke(&self, pos: Point2<f64>) -> bool {
+        let pen = match &*self.tool.borrow() {
+            Tool::Pen(pen) => *pen,
+            _ => return false,
+        };
+
+        let (page, point) = match self.page_at(pos) {
+            Some(found) => found,
+            None => return false,
+        };
+
+        self.stroke.replace(Some(Stroke::new(page, pen, point)));
+        true
+    }
+
+    /// Extend the stroke being drawn to the given point in viewport
+    /// coordinates, clamped to its page.
+    fn update_stroke(&self, pos: Point2<f64>) {
+        let page = match self.stroke.borrow().as_ref() {
+            Some(stroke) => stroke.page,
+            None => return,
+        };
+
+        let (point, size) = match (self.viewport_to_page(page, pos), self.page_size(page)) {
+            (Some(point), Some(size)) => (point, size),
+            _ => return,
+        };
+
+        let point = point![point.x.clamp(0.0, size.x), point.y.clamp(0.0, size.y)];
+
+        if let Some(stroke) = self.stroke.borrow_mut().as_mut() {
+            if stroke.push(point) {
+                self.obj().queue_draw();
+            }
+        }
+    }
+
+    /// Mark the selected text with annotations of the given kind. Returns
+    /// `false` if no text is selected.
+    pub fn markup_selection(&self, kind: MarkupKind, color: Color) -> pdfium::Result<bool> {
+        let result = match self.data.borrow_mut().as_mut() {
+            Some(data) => data.view.add_markup(kind, color),
+            None => return Ok(false),
+        };
+
+        self.obj().queue_draw();
+        result
+    }
+
+    /// Write the stroke being drawn to the document.
+    fn end_stroke(&self) {
+        let stroke = match self.stroke.take() {
+            Some(stroke) if stroke.is_visible() => stroke,
+            _ => return,
+        };
+
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            match data.view.add_ink_stroke(&stroke) {
+                Ok(()) => data.strokes.push(stroke),
+                Err(err) => {
+                    tracing::warn!(page = stroke.page, error = %err, "failed to add ink annotation")
+                }
+            }
+        }
+
+        self.obj().queue_draw();
+    }
+
+    /// Transform a point in viewport coordinates to coordinates of the given
+    /// page, regardless of whether the point lies on that page.
+    fn viewport_to_page(&self, page: usize, pos: Point2<f64>) -> Option<Point2<f64>> {
+        let data = self.data.borrow();
+        data.as_ref()?.view.viewport_to_page(page, pos)
+    }
+
+    /// Find the topmost stamp at the given point in viewport coordinates.
+    fn stamp_at(&self, pos: Point2<f64>) -> Option<usize> {
+        let (index, point) = self.page_at(pos)?;
+
+        let data = self.data.borrow();
+        let data = data.as_ref()?;
+
+        data.stamps
+            .iter()
+            .rposition(|stamp| stamp.page == index && stamp.rect.contains_point(&point))
+    }
+
+    /// Find the resize handle of the selected stamp at the given point in
+    /// viewport coordinates.
+    fn stamp_handle_at(&self, pos: Point2<f64>) -> Option<(usize, Corner)> {
+        let index = self.selected_stamp.get()?;
+        let page = self.data.borrow().as_ref()?.stamps.get(index)?.page;
+        let point = self.viewport_to_page(page, pos)?;
+        let tolerance = STAMP_HANDLE_SIZE / self.viewport.borrow().scale;
+
+        let data = self.data.borrow();
+        let stamp = &data.as_ref()?.stamps[index];
+
+        stamp
+            .corner_at(&point, tolerance)
+            .map(|corner| (index, corner))
+    }
+
+    /// Select the stamp at the given point in viewport coordinates, or clear
+    /// the selection if there is none. Returns `true` if a stamp has been
+    /// selected.
+    fn select_stamp_at(&self, pos: Point2<f64>) -> bool {
+        let mut index = self.stamp_at(pos);
+
+        if self.selected_stamp.get() != index {
+            // writing the deselected stamp changes the indices of the others
+            if self.deselect_stamp() {
+                index = self.stamp_at(pos);
+            }
+
+            self.selected_stamp.set(index);
+            self.obj().queue_draw();
+        }
+
+        if index.is_some() {
+            self.obj().grab_focus();
+        }
+
+        index.is_some()
+    }
+
+    /// Clear the stamp selection, writing the deselected stamp to the
+    /// document. Returns `true` if a stamp has been deselected.
+    fn deselect_stamp(&self) -> bool {
+        if self.selected_stamp.take().is_none() {
+            return false;
+        }
+
+        self.write_stamps();
+        true
+    }
+
+    /// Write the stamps placed on pages to the document as stamp annotations,
+    /// keeping only those that could not be written as overlay.
+    fn write_stamps(&self) {
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            for stamp in std::mem::take(&mut data.stamps) {
+                if let Err(err) = data.view.add_stamp(&stamp) {
+                    tracing::warn!(page = stamp.page, error = %err, "failed to write stamp");
+                    data.stamps.push(stamp);
+                }
+            }
+        }
+
+        self.obj().queue_draw();
+    }
+
+    /// Place a stamp of the stamp tool centered at the given point in
+    /// viewport coordinates. Clicks outside of pages keep the tool active.
+    fn place_stamp(&self, pos: Point2<f64>) {
+        let content = match &*self.tool.borrow() {
+            Tool::Stamp(content) => content.clone(),
+            _ => return,
+        };
+
+        let (page, point) = match self.page_at(pos) {
+            Some(found) => found,
+            None => return,
+        };
+
+        self.set_stamp_tool(None);
+
+        let size = match self.stamp_size(&content) {
+            Some(size) => size,
+            None => return,
+        };
+
+        self.deselect_stamp();
+
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.stamps.push(Stamp::new(page, point, size, content));
+            self.selected_stamp.set(Some(data.stamps.len() - 1));
+        }
+
+        self.obj().grab_focus();
+        self.obj().queue_draw();
+    }
+
+    /// Remove the selected stamp. Returns `true` if a stamp has been removed.
+    fn remove_selected_stamp(&self) -> bool {
+        let index = match self.selected_stamp.take() {
+            Some(index) => index,
+            None => return false,
+        };
+
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            if index < data.stamps.len() {
+                data.stamps.remove(index);
+            }
+        }
+
+        self.obj().queue_draw();
+        true
+    }
+
+    /// Start moving or resizing the stamp at the given point in viewport
+    /// coordinates. Grabbing a stamp selects it.
+    fn begin_stamp_drag(&self, pos: Point2<f64>) -> bool {
+        if let Some((index, corner)) = self.stamp_handle_at(pos) {
+            self.stamp_drag.set(Some(StampDrag::Resize {
+                index,
+                start: pos,
+                corner,
+            }));
+            return true;
+        }
+
+        // selecting the stamp may write the previously selected one first,
+        // changing the index of the grabbed one
+        if self.stamp_at(pos).is_none() || !self.select_stamp_at(pos) {
+            return false;
+        }
+
+        let index = match self.selected_stamp.get() {
+            Some(index) => index,
+            None => return false,
+        };
+
+        let grab = {
+            let data = self.data.borrow();
+            let stamp = &data.as_ref().unwrap().stamps[index];
+
+            match self.viewport_to_page(stamp.page, pos) {
+                Some(point) => point - stamp.rect.offs,
+                None => return false,
+            }
+        };
+
+        self.stamp_drag.set(Some(StampDrag::Move {
+            index,
+            start: pos,
+            grab,
+        }));
+        true
+    }
+
+    /// Update the stamp being moved or resized for the given drag offset,
+    /// in viewport coordinates.
+    fn update_stamp_drag(&self, offset: Vector2<f64>) {
+        let (index, start) = match self.stamp_drag.get() {
+            Some(StampDrag::Move { index, start, .. }) => (index, start),
+            Some(StampDrag::Resize { index, start, .. }) => (index, start),
+            None => return,
+        };
+
+        let page = match self
+            .data
+            .borrow()
+            .as_ref()
+            .and_then(|d| d.stamps.get(index))
+        {
+            Some(stamp) => stamp.page,
+            None => return,
+        };
+
+        let point = match self.viewport_to_page(page, start + offset) {
+            Some(point) => point,
+            None => return,
+        };
+
+        if let Some(stamp) = self
+            .data
+            .borrow_mut()
+            .as_mut()
+            .map(|d| &mut d.stamps[index])
+        {
+            match self.stamp_drag.get() {
+                Some(StampDrag::Move { grab, .. }) => stamp.rect.offs = point - grab,
+                Some(StampDrag::Resize { corner, .. }) => stamp.resize(corner, point),
+                None => {}
+            }
+        }
+
+        self.obj().queue_draw();
+    }
+
+    /// Default size of a new stamp with the given content, in PDF points.
+    fn stamp_size(&self, content: &StampContent) -> Option<Vector2<f64>> {
+        match content {
+            StampContent::Text(text) => {
+                let (width, height) = self.stamp_layout(text).pixel_size();
+                let padding = vector![stamps::TEXT_PADDING, stamps::TEXT_PADDING];
+
+                Some(vector![width as f64, height as f64] + padding * 2.0)
+            }
+            StampContent::Image(path) => {
+                let tex = self.stamp_image(path)?;
+                let aspect = tex.height() as f64 / tex.width().max(1) as f64;
+
+                Some(vector![STAMP_IMAGE_WIDTH, STAMP_IMAGE_WIDTH * aspect])
+            }
+        }
+    }
+
+    fn stamp_layout(&self, text: &str) -> pango::Layout {
+        let font = pango::FontDescription::from_string(STAMP_FONT);
+
+        let layout = self.obj().create_pango_layout(Some(text));
+        layout.set_font_description(Some(&font));
+        layout
+    }
+
+    /// Image of an image stamp, loaded on first use.
+    fn stamp_image(&self, path: &Path) -> Option<gdk::Texture> {
+        let mut images = self.stamp_images.borrow_mut();
+
+        let image = images.entry(path.to_owned()).or_insert_with(|| {
+            match gdk::Texture::from_filename(path) {
+                Ok(tex) => Some(tex),
+                Err(err) => {
+                    tracing::warn!(path=%path.display(), error=%err, "failed to load stamp image");
+                    None
+                }
+            }
+        });
+
+        image.clone()
+    }
+
+    /// Draw a stamp with the given bounds in viewport coordinates.
+    fn draw_stamp(
+        &self,
+        snapshot: &gtk::Snapshot,
+        stamp: &Stamp,
+        rect: &Rect<f64>,
+        selected: bool,
+    ) {
+        match &stamp.content {
+            StampContent::Text(text) => {
+                let color = gdk::RGBA::new(
+                    stamps::TEXT_COLOR.r as f32 / 255.0,
+                    stamps::TEXT_COLOR.g as f32 / 255.0,
+                    stamps::TEXT_COLOR.b as f32 / 255.0,
+                    1.0,
+                );
+
+                // scale text to fit the stamp
+                let layout = self.stamp_layout(text);
+                let (width, height) = layout.pixel_size();
+                let text_size = vector![width as f64, height as f64];
+
+                let padding = vector![stamps::TEXT_PADDING, stamps::TEXT_PADDING];
+                let size = text_size + padding * 2.0;
+                let scale = (rect.size.x / size.x).min(rect.size.y / size.y);
+                let offs = rect.offs + (rect.size - text_size * scale) / 2.0;
+
+                let outline = gtk::gsk::RoundedRect::from_rect((*rect).into(), 0.0);
+                let border = (stamps::TEXT_BORDER * scale) as f32;
+                snapshot.append_border(&outline, &[border; 4], &[color; 4]);
+
+                snapshot.save();
+                snapshot.translate(&graphene::Point::new(offs.x as f32, offs.y as f32));
+                snapshot.scale(scale as f32, scale as f32);
+                snapshot.append_layout(&layout, &color);
+                snapshot.restore();
+            }
+            StampContent::Image(path) => {
+                if let Some(tex) = self.stamp_image(path) {
+                    snapshot.append_texture(&tex, &(*rect).into());
+                }
+            }
+        }
+
+        if !selected {
+            return;
+        }
+
+        // outline and resize handles
+        let color = gdk::RGBA::new(0.21, 0.52, 0.89, 1.0);
+        let outline = gtk::gsk::RoundedRect::from_rect((*rect).into(), 0.0);
+        snapshot.append_border(&outline, &[1.0; 4], &[color; 4]);
+
+        let corners = [
+            rect.offs,
+            rect.offs + vector![rect.size.x, 0.0],
+            rect.offs + vector![0.0, rect.size.y],
+            rect.offs + rect.size,
+        ];
+
+        for corner in corners {
+            let half = vector![STAMP_HANDLE_SIZE, STAMP_HANDLE_SIZE] / 2.0;
+            let handle = Rect::new(corner - half, half * 2.0);
+
+            snapshot.append_color(&color, &handle.round().into());
+        }
+    }
+
+    /// Check if the given region damaged by a render task is visible in the
+    /// current viewport.
+    fn is_damage_visible(&self, damage: &Damage) -> bool {
+        match self.data.borrow().as_ref() {
+            Some(data) => data.view.is_damage_visible(damage),
+            None => false,
+        }
+    }
+
+    fn has_deferred_fallbacks(&self) -> bool {
+        match self.data.borrow().as_ref() {
+            Some(data) => data.view.has_deferred_fallbacks(),
+            None => false,
+        }
+    }
+
+    pub fn document(&self) -> Option<Document> {
+        let data = self.data.borrow();
+        data.as_ref().map(|d| d.view.document().clone())
+    }
+
+    pub fn executor(&self) -> Option<Arc<Executor>> {
+        let data = self.data.borrow();
+        data.as_ref().map(|d| d.view.executor().clone())
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.data
+            .borrow()
+            .as_ref()
+            .map(|d| d.view.page_count())
+            .unwrap_or(0)
+    }
+
+    pub fn page_rect(&self, page: usize) -> Option<Rect<f64>> {
+        self.data.borrow().as_ref()?.view.page_rect(page)
+    }
+
+    pub fn page_size(&self, page: usize) -> Option<Vector2<f64>> {
+        self.data.borrow().as_ref()?.view.page_size(page)
+    }
+
+    pub fn page_to_canvas(&self, page: usize, rect: &Rect<f64>) -> Option<Rect<f64>> {
+        self.data.borrow().as_ref()?.view.page_to_canvas(page, rect)
+    }
+
+    pub fn page_at_y(&self, y: f64) -> Option<usize> {
+        self.data.borrow().as_ref()?.view.page_at_y(y)
+    }
+
+    pub fn page_preview(&self, page: usize) -> Option<gdk::Texture> {
+        let data = self.data.borrow();
+        let texture = data.as_ref()?.view.page_preview(page)?;
+
+        Some(texture.clone().upcast())
+    }
+
+    pub fn viewport_page_rect(&self) -> Option<(usize, Rect<f64>)> {
+        self.data.borrow().as_ref()?.view.viewport_page_rect()
+    }
+
+    pub fn visible_pages(&self) -> Range<usize> {
+        match self.data.borrow().as_ref() {
+            Some(data) => data.view.visible_pages(),
+            None => 0..0,
+        }
+    }
+
+    pub fn viewport_rect(&self, page: usize) -> Option<Rect<f64>> {
+        self.data.borrow().as_ref()?.view.viewport_rect(page)
+    }
+
+    pub fn scroll_to_page_rect(&self, page: usize, rect: &Rect<f64>) {
+        let offset = {
+            let rect = match self.data.borrow().as_ref() {
+                Some(data) => data.view.page_to_canvas(page, rect),
+                None => return,
+            };
+
+            let rect = match rect {
+                Some(rect) => rect,
+                None => return,
+            };
+
+            // center the viewport on the given rect
+            let scale = self.scale.get();
+            let size = self.viewport.borrow().r.size;
+            let center = rect.offs + rect.size / 2.0;
+
+            center.coords * scale - size / 2.0
+        };
+
+        // update properties, offsets will get clipped on allocation
+        let obj = self.obj();
+        obj.set_property("offset-x", offset.x);
+        obj.set_property("offset-y", offset.y);
+    }
+
+    pub fn scroll_to_page_point(&self, page: usize, x: Option<f64>, y: f64) {
+        let point = {
+            let point = Rect::new(point![x.unwrap_or(0.0), y], vector![0.0, 0.0]);
+
+            let rect = match self.data.borrow().as_ref() {
+                Some(data) => data.view.page_to_canvas(page, &point),
+                None => return,
+            };
+
+            match rect {
+                Some(rect) => rect.offs,
+                None => return,
+            }
+        };
+
+        // update properties, offsets will get clipped on allocation
+        let obj = self.obj();
+        let scale = self.scale.get();
+
+        if x.is_some() {
+            obj.set_property("offset-x", point.x * scale);
+        }
+        obj.set_property("offset-y", point.y * scale);
+    }
+
+    pub fn scroll_to_page(&self, page: usize) {
+        let page_rect = match self.page_rect(page) {
+            Some(page_rect) => page_rect,
+            None => return,
+        };
+
+        let scale = self.scale.get();
+        let rect = page_rect.scale(scale);
+
+        // keep the horizontal offset if the page is either fully visible or
+        // covers the viewport, move to the page otherwise, e.g. in horizontal
+        // layouts
+        let x = self.offset.borrow().x;
+        let width = self.viewport.borrow().r.size.x;
+        let (left, right) = (rect.offs.x, rect.offs.x + rect.size.x);
+        let keep = (left >= x && right <= x + width) || (left <= x && right >= x + width);
+
+        // update properties, offsets will get clipped on allocation
+        let obj = self.obj();
+        if !keep {
+            obj.set_property("offset-x", left);
+        }
+        obj.set_property("offset-y", rect.offs.y);
+    }
+
+    /// Scroll the viewport just far enough for the given rectangle on a page
+    /// to be visible, keeping a small margin around it.
+    pub fn reveal_page_rect(&self, page: usize, rect: &Rect<f64>) {
+        let view = match self.viewport_rect(page) {
+            Some(view) => view,
+            None => return,
+        };
+
+        let scale = self.scale.get();
+        let margin = REVEAL_MARGIN / scale;
+
+        // distance to scroll along one axis, in page coordinates
+        let shift = |start: f64, size: f64, view_start: f64, view_size: f64| {
+            if start - margin < view_start || size + 2.0 * margin > view_size {
+                start - margin - view_start
+            } else if start + size + margin > view_start + view_size {
+                start + size + margin - view_start - view_size
+            } else {
+                0.0
+            }
+        };
+
+        let dx = shift(rect.offs.x, rect.size.x, view.offs.x, view.size.x);
+        let dy = shift(rect.offs.y, rect.size.y, view.offs.y, view.size.y);
+
+        if dx == 0.0 && dy == 0.0 {
+            return;
+        }
+
+        // update properties, offsets will get clipped on allocation
+        let offset = *self.offset.borrow() + vector![dx, dy] * scale;
+
+        let obj = self.obj();
+        obj.set_property("offset-x", offset.x);
+        obj.set_property("offset-y", offset.y);
+    }
+
+    /// Show the contents of the given text annotation in a popover, for
+    /// editing them.
+    fn open_note(&self, page: usize, annot: Annotation, rect: Rect<f64>) {
+        self.close_note();
+
+        let contents = annot.contents().ok().flatten().unwrap_or_default();
+
+        let buffer = gtk::TextBuffer::new(None);
+        buffer.set_text(&contents);
+
+        let view = gtk::TextView::with_buffer(&buffer);
+        view.set_wrap_mode(gtk::WrapMode::WordChar);
+
+        let scroller = gtk::ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .min_content_width(240)
+            .min_content_height(120)
+            .max_content_height(320)
+            .propagate_natural_height(true)
+            .child(&view)
+            .build();
+
+        let popover = gtk::Popover::new();
+        popover.set_child(Some(&scroller));
+        popover.set_parent(&*self.obj());
+
+        // write changes back to the annotation when the note is closed
+        let obj = self.obj();
+        popover.connect_closed(glib::clone!(@weak obj => move |popover| {
+            let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+
+            if text != contents {
+                match annot.set_contents(&text) {
+                    Ok(()) => tracing::debug!(page, "updated annotation contents"),
+                    Err(err) => tracing::warn!(page, error=%err, "failed to update annotation"),
+                }
+            }
+
+            // the popover may already have been replaced by a new one
+            let mut note = obj.imp().note.borrow_mut();
+            if note.as_ref().map(|n| &n.popover == popover).unwrap_or(false) {
+                note.take();
+                popover.unparent();
+            }
+        }));
+
+        self.note.replace(Some(Note {
+            popover: popover.clone(),
+            page,
+            rect,
+        }));
+
+        self.update_note_position();
+        popover.popup();
+        view.grab_focus();
+    }
+
+    fn close_note(&self) {
+        // take the popover out first, the close handler needs to borrow it
+        let popover = self.note.borrow().as_ref().map(|n| n.popover.clone());
+
+        if let Some(popover) = popover {
+            popover.popdown();
+        }
+
+        if let Some(note) = self.note.take() {
+            note.popover.unparent();
+        }
+
+        if let Some(timer) = self.idle_timer.take() {
+            timer.remove();
+        }
+    }
+
+    fn update_note_position(&self) {
+        let note = self.note.borrow();
+        let note = match note.as_ref() {
+            Some(note) => note,
+            None => return,
+        };
+
+        // transform annotation bounds to viewport coordinates
+        let rect = match self.data.borrow().as_ref() {
+            Some(data) => data.view.page_to_viewport(note.page, &note.rect),
+            None => None,
+        };
+
+        let rect = match rect {
+            Some(rect) => rect.round(),
+            None => return,
+        };
+
+        let rect = gdk::Rectangle::new(
+            rect.offs.x as _,
+            rect.offs.y as _,
+            (rect.size.x as i32).max(1),
+            (rect.size.y as i32).max(1),
+        );
+
+        note.popover.set_pointing_to(Some(&rect));
+        note.popover.present();
+    }
+
+    /// Clamp the given viewport offset to the scrollable range.
+    fn clamp_offset(&self, offs: Point2<f64>) -> Point2<f64> {
+        let clamp = |adj: Option<&Adjustment>, value: f64| match adj {
+            Some(adj) => {
+                let max = (adj.upper() - adj.page_size()).max(adj.lower());
+                value.clamp(adj.lower(), max)
+            }
+            None => value,
+        };
+
+        point![
+            clamp(self.hadjustment.borrow().as_ref(), offs.x),
+            clamp(self.vadjustment.borrow().as_ref(), offs.y)
+        ]
+    }
+
+    pub fn render(&self, snapshot: &gtk::Snapshot) {
+        self.skeleton_visible.set(false);
+
+        let mut data = self.data.borrow_mut();
+        let data = match data.as_mut() {
+            Some(data) => data,
+            None => return,
+        };
+
+        // predict where the viewport comes to rest when flinging
+        let (rest, stopped) = match self.obj().frame_clock() {
+            Some(clock) if !self.low_power.get() => {
+                let mut fling = self.fling.borrow_mut();
+                let rest = fling.sample(clock.frame_time(), self.viewport.borrow().r.offs);
+
+                (rest.map(|offs| self.clamp_offset(offs)), fling.is_stopped())
+            }
+            _ => (None, true),
+        };
+
+        let hints = FrameHints {
+            idle: self.idle.get(),
+            stopped,
+            // shed load while frames take too long, see record_frame_time()
+            shedding: self.watchdog.borrow().is_shedding(),
+            low_power: self.low_power.get(),
+            rest,
+        };
+
+        // update page differences
+        if let Some(diff) = data.diff_provider.as_mut() {
+            diff.update(&data.view.layout().rects, &data.view.visible_pages());
+        }
+
+        let frame = data.view.render(&hints);
+
+        // show tiles held back due to the upload limit in the next frame
+        if frame.deferred {
+            self.obj().add_tick_callback(|obj, _clock| {
+                obj.queue_draw();
+                glib::Continue(false)
+            });
+        }
+
+        // phase of the shimmer animation for page placeholders
+        let shimmer =
+            (glib::monotonic_time() % SHIMMER_PERIOD_US) as f32 / SHIMMER_PERIOD_US as f32;
+        let mut skeleton_visible = false;
+
+        // render pages
+        for page in &frame.pages {
+            let i = page.index;
+            let page_rect = page.rect;
+            let page_clipped = page.clip;
+
+            // draw page shadow, skipped while shedding load
+            if !hints.shedding {
+                let bounds = page_rect.into();
+                let radius = gtk::gsk::graphene::Size::new(0.0, 0.0);
+                let outline = gtk::gsk::RoundedRect::new(bounds, radius, radius, radius, radius);
+
+                let color = gdk::RGBA::new(0.0, 0.0, 0.0, 0.5);
+
+                let shift = vector![0.0, 1.0];
+                let spread = 0.0;
+                let blur = 3.5;
+
+                snapshot.append_outset_shadow(&outline, &color, shift.x, shift.y, spread, blur)
+            }
+
+            // page contents are drawn with inverted lightness in dark mode
+            let dark = self.dark_fade.get();
+            if dark > 0.0 {
+                let (matrix, offset) = dark_color_matrix(dark as f32);
+                snapshot.push_color_matrix(&matrix, &offset);
+            }
+
+            // draw page background, or a placeholder if we have nothing to
+            // show for the page yet
+            if page.pending {
+                draw_skeleton(snapshot, &page_rect, &page_clipped, shimmer);
+                skeleton_visible = true;
+            } else {
+                let bg = if self.night_colors.get() {
+                    NIGHT_BACKGROUND
+                } else {
+                    Color::WHITE
+                };
+                let bg = gdk::RGBA::new(
+                    bg.r as f32 / 255.0,
+                    bg.g as f32 / 255.0,
+                    bg.b as f32 / 255.0,
+                    1.0,
+                );
+
+                snapshot.append_color(&bg, &page_clipped.into());
+            }
+
+            // draw fallback
+            if let Some(tex) = page.fallback {
+                snapshot.append_texture(tex, &page_rect.into());
+            }
+
+            // draw tiles
+            snapshot.push_clip(&page_clipped.into());
+            for (tile_rect, tex) in &page.tiles {
+                snapshot.append_texture(*tex, &(*tile_rect).into());
+            }
+            snapshot.pop();
+
+            if dark > 0.0 {
+                snapshot.pop();
+            }
+
+            // draw overlays, not affected by dark mode
+            snapshot.push_clip(&page_clipped.into());
+
+            // draw difference heat map
+            if let Some(tex) = data.diff_provider.as_ref().and_then(|d| d.diff(i)) {
+                snapshot.append_texture(tex, &page_rect.into());
+            }
+
+            let overlays = frame.overlays;
+
+            // underline synthesized links
+            let link_color = gdk::RGBA::new(0.21, 0.52, 0.89, 0.6);
+
+            for link in overlays.links.iter().filter(|link| link.source.page == i) {
+                let r = page.to_viewport(&link.source.rect);
+                let underline = Rect::new(r.offs + vector![0.0, r.size.y], vector![r.size.x, 1.0]);
+
+                snapshot.append_color(&link_color, &underline.round().into());
+            }
+
+            // mark text drawn with substituted fonts
+            if let Some((_, report)) = overlays.fonts.as_ref().filter(|(p, _)| *p == i) {
+                let color = gdk::RGBA::new(0.90, 0.38, 0.0, 1.0);
+                let fill = gdk::RGBA::new(0.90, 0.38, 0.0, 0.2);
+
+                for r in &report.regions {
+                    let rect = page.to_viewport(r).round();
+                    let outline = gtk::gsk::RoundedRect::from_rect(rect.into(), 0.0);
+
+                    snapshot.append_color(&fill, &rect.into());
+                    snapshot.append_border(&outline, &[1.0; 4], &[color; 4]);
+                }
+            }
+
+            // draw text selected and caret in caret navigation mode
+            if let Some(marks) = overlays.caret.as_ref().filter(|marks| marks.page == i) {
+                let fill = gdk::RGBA::new(0.21, 0.52, 0.89, 0.3);
+                let color = gdk::RGBA::new(0.21, 0.52, 0.89, 1.0);
+
+                for r in &marks.selection {
+                    snapshot.append_color(&fill, &page.to_viewport(r).round().into());
+                }
+
+                let r = page.to_viewport(&marks.caret);
+                let caret = Rect::new(
+                    r.offs - vector![CARET_WIDTH / 2.0, 0.0],
+                    vector![CARET_WIDTH, r.size.y],
+                );
+                snapshot.append_color(&color, &caret.round().into());
+            }
+
+            // draw search results, emphasizing the current one
+            for (j, m) in overlays.search.iter().enumerate() {
+                if m.page != i {
+                    continue;
+                }
+
+                let fill = if overlays.search_current == Some(j) {
+                    gdk::RGBA::new(0.96, 0.47, 0.0, 0.5)
+                } else {
+                    gdk::RGBA::new(0.96, 0.83, 0.18, 0.4)
+                };
+
+                for r in &m.rects {
+                    snapshot.append_color(&fill, &page.to_viewport(r).round().into());
+                }
+            }
+
+            // draw text selected with the pointer
+            if let Some(selection) = &overlays.text {
+                let fill = gdk::RGBA::new(0.21, 0.52, 0.89, 0.3);
+
+                for r in selection.rects(i) {
+                    snapshot.append_color(&fill, &page.to_viewport(r).round().into());
+                }
+            }
+
+            // draw stamps not yet written to the document
+            for (j, stamp) in data.stamps.iter().enumerate() {
+                if stamp.page != i {
+                    continue;
+                }
+
+                let rect = page.to_viewport(&stamp.rect);
+                self.draw_stamp(snapshot, stamp, &rect, self.selected_stamp.get() == Some(j));
+            }
+
+            // draw the stroke being drawn and ones not yet rendered
+            let drawing = self.stroke.borrow();
+            for stroke in data.strokes.iter().chain(drawing.as_ref()) {
+                if stroke.page == i {
+                    draw_stroke(snapshot, stroke, page);
+                }
+            }
+            snapshot.pop();
+        }
+
+        self.skeleton_visible.set(skeleton_visible);
+
+        // pages with new strokes have been rendered again
+        if frame.complete {
+            data.strokes.clear();
+        }
+
+        // draw labels of links to follow via keyboard
+        if let Some(hints) = &frame.overlays.hints {
+            for page in &frame.pages {
+                for (label, link) in hints.matching().filter(|(_, l)| l.page == page.index) {
+                    let rect = page.to_viewport(&link.rect);
+                    self.draw_link_hint(snapshot, label, hints.typed().len(), rect.offs);
+                }
+            }
+        }
+
+        // draw region being selected for inspection
+        if let Some(rect) = frame.selection {
+            let color = gdk::RGBA::new(0.21, 0.52, 0.89, 1.0);
+            let fill = gdk::RGBA::new(0.21, 0.52, 0.89, 0.15);
+            let outline = gtk::gsk::RoundedRect::from_rect(rect.into(), 0.0);
+
+            snapshot.append_color(&fill, &rect.into());
+            snapshot.append_border(&outline, &[1.0; 4], &[color; 4]);
+        }
+
+        let z = frame.z;
+
+        if self.debug_overlay.get() {
+            self.draw_debug_overlay(snapshot, &data.view, z);
+        }
+
+        if let Some((page, report)) = &data.view.overlays().fonts {
+            self.draw_font_report(snapshot, *page, report);
+        }
+    }
+
+    pub fn set_low_power(&self, low_power: bool) {
+        if self.low_power.replace(low_power) == low_power {
+            return;
+        }
+
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            if low_power {
+                data.view.set_tile_halo(vector![0, 0]);
+                data.view
+                    .set_fallback_halo_limit(Some(LOW_POWER_FALLBACK_HALO));
+                data.view
+                    .set_render_thread_limit(Some(LOW_POWER_RENDER_THREADS));
+            } else {
+                data.view.set_tile_halo(TILE_HALO);
+                data.view.set_fallback_halo_limit(None);
+                data.view.set_render_thread_limit(None);
+            }
+        }
+
+        self.obj().queue_draw();
+    }
+
+    pub fn set_prerender_neighbors(&self, enabled: bool) {
+        if self.prerender_neighbors.replace(enabled) == enabled {
+            return;
+        }
+
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            let minimum = if enabled { NEIGHBOR_FALLBACK_HALO } else { 0 };
+            data.view.set_fallback_halo_minimum(minimum);
+        }
+
+        self.obj().queue_draw();
+    }
+
+    /// Select the tiling scheme and the base tile size, rebuilding the tile
+    /// manager of the current document. This drops all cached and pending
+    /// tiles, as they are specific to the previous scheme.
+    pub fn set_tiling(&self, kind: TilingSchemeKind, tile_size: i64) {
+        let changed = self.tiling_scheme.replace(kind) != kind;
+        let changed = self.tile_size.replace(tile_size) != tile_size || changed;
+
+        if !changed {
+            return;
+        }
+
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.view.set_tile_policy(self.tile_policy());
+        }
+
+        self.obj().queue_draw();
+    }
+
+    /// Upload tiles to GL textures on the render threads, if supported by the
+    /// renderer.
+    pub fn set_gl_upload(&self, enabled: bool) {
+        if self.gl_upload.replace(enabled) != enabled {
+            self.update_gl_uploader();
+        }
+    }
+
+    /// Set up or drop the GL uploader of the texture factory, depending on
+    /// the setting and the renderer in use. Tiles rendered before are kept,
+    /// regardless of their kind of texture.
+    fn update_gl_uploader(&self) {
+        let obj = self.obj();
+
+        let surface = obj
+            .native()
+            .filter(|native| native.renderer().is::<gsk::GLRenderer>())
+            .map(|native| native.surface());
+
+        let surface = match surface {
+            Some(surface) if self.gl_upload.get() && obj.is_realized() => surface,
+            _ => {
+                self.texture_factory.set_gl_uploader(None);
+                return;
+            }
+        };
+
+        if self.texture_factory.has_gl_uploader() {
+            return;
+        }
+
+        match GlUploader::new(&surface, render_threads() as _) {
+            Ok(uploader) => {
+                tracing::debug!(?uploader, "uploading tiles to GL textures");
+                self.texture_factory.set_gl_uploader(Some(uploader));
+            }
+            Err(err) => {
+                tracing::warn!(error=%err, "failed to set up GL uploads, using memory textures");
+            }
+        }
+    }
+
+    fn tile_policy(&self) -> TileSizePolicy {
+        let size = self.tile_size.get();
+
+        TileSizePolicy::new(
+            self.tiling_scheme.get(),
+            size,
+            size * TILE_MIN_Z_FACTOR,
+            TILE_TIME_BUDGET,
+            2,
+        )
+    }
+
+    /// Record the time taken to build a frame, shedding load while frames
+    /// are consistently too slow and restoring full quality once they
+    /// recover.
+    fn record_frame_time(&self, duration: Duration) {
+        let mut watchdog = self.watchdog.borrow_mut();
+
+        if !watchdog.record(duration) {
+            return;
+        }
+
+        let shedding = watchdog.is_shedding();
+
+        if shedding {
+            tracing::info!(?duration, "frames too slow, shedding load");
+        } else {
+            tracing::info!("frame times recovered, restoring quality");
+        }
+
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.view
+                .set_upload_limit(shedding.then_some(SHED_UPLOAD_LIMIT));
+        }
+
+        self.obj().queue_draw();
+    }
+
+    /// Release cached tiles and pages, keeping only the lowest-resolution
+    /// fallbacks, and stop rendering until resumed, e.g. while the canvas has
+    /// not been shown for a while.
+    pub fn suspend(&self) {
+        if self.suspended.replace(true) {
+            return;
+        }
+
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.view.suspend();
+
+            if let Some(diff) = data.diff_provider.as_mut() {
+                diff.clear();
+            }
+        }
+    }
+
+    /// Resume rendering after [`suspend`](Self::suspend). Released tiles are
+    /// rendered again on the next draw.
+    pub fn resume(&self) {
+        if !self.suspended.replace(false) {
+            return;
+        }
+
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.view.resume();
+        }
+
+        self.obj().queue_draw();
+    }
+
+    pub fn set_disk_cache(&self, cache: Option<DiskCache>) {
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.view.set_disk_cache(cache);
+        }
+    }
+
+    /// Drop all rendered tiles, fallbacks, and loaded pages, e.g. after the
+    /// content of the document has been changed, so that pages are rendered
+    /// again from scratch.
+    pub fn reload_pages(&self) {
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.view.reload();
+        }
+
+        self.obj().queue_draw();
+    }
+
+    /// Show only the given page, or all pages if `None`.
+    pub fn single_page(&self) -> Option<usize> {
+        self.single_page.get()
+    }
+
+    pub fn set_single_page(&self, page: Option<usize>) {
+        if self.single_page.replace(page) == page {
+            return;
+        }
+
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.view.set_single_page(page);
+        }
+
+        self.obj().queue_resize();
+    }
+
+    /// Render the fallbacks of the given page with high priority, e.g. before
+    /// flipping to it.
+    pub fn prefetch_page(&self, page: usize) {
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.view.prefetch_page(page);
+        }
+    }
+
+    /// Slide the pages in from the side over a short animation, starting at
+    /// the given horizontal offset in pixels.
+    pub fn slide_in(&self, offset: f64) {
+        let clock = match self.obj().frame_clock() {
+            Some(clock) => clock,
+            None => return,
+        };
+
+        // restart a running animation instead of adding another callback
+        let running = self
+            .slide
+            .replace(Some((offset, clock.frame_time())))
+            .is_some();
+        self.slide_offset.set(offset);
+        self.obj().queue_draw();
+
+        if running {
+            return;
+        }
+
+        self.obj().add_tick_callback(|obj, clock| {
+            let imp = obj.imp();
+
+            let (offset, start) = match imp.slide.get() {
+                Some(slide) => slide,
+                None => return glib::Continue(false),
+            };
+
+            let t = (clock.frame_time() - start) as f64 / SLIDE_DURATION_US as f64;
+            let t = t.clamp(0.0, 1.0);
+
+            // ease out (cubic)
+            imp.slide_offset.set(offset * (1.0 - t).powi(3));
+            obj.queue_draw();
+
+            if t < 1.0 {
+                glib::Continue(true)
+            } else {
+                imp.slide.set(None);
+                glib::Continue(false)
+            }
+        });
+    }
+
+    pub fn set_dark_mode(&self, dark: bool) {
+        if self.dark_mode.replace(dark) == dark {
+            return;
+        }
+
+        // switch immediately if we are not being shown
+        let clock = match self.obj().frame_clock() {
+            Some(clock) => clock,
+            None => {
+                self.dark_fade.set(if dark { 1.0 } else { 0.0 });
+                return;
+            }
+        };
+
+        // fade between light and dark colors, unless we are already fading
+        if self.dark_fade_time.get().is_some() {
+            return;
+        }
+        self.dark_fade_time.set(Some(clock.frame_time()));
+
+        self.obj().add_tick_callback(|obj, clock| {
+            let imp = obj.imp();
+
+            let now = clock.frame_time();
+            let last = imp.dark_fade_time.replace(Some(now)).unwrap_or(now);
+
+            let step = (now - last) as f64 / DARK_FADE_DURATION_US as f64;
+            let target = if imp.dark_mode.get() { 1.0 } else { 0.0 };
+
+            let fade = imp.dark_fade.get();
+            let fade = if fade < target {
+                (fade + step).min(target)
+            } else {
+                (fade - step).max(target)
+            };
+
+            imp.dark_fade.set(fade);
+            obj.queue_draw();
+
+            if fade == target {
+                imp.dark_fade_time.set(None);
+                glib::Continue(false)
+            } else {
+                glib::Continue(true)
+            }
+        });
+    }
+
+    /// Render pages with night colors, i.e. light text on a dark background,
+    /// instead of the colors of the document. In contrast to dark mode, this
+    /// renders all pages again but keeps the colors of images.
+    pub fn set_night_colors(&self, enabled: bool) {
+        if self.night_colors.replace(enabled) == enabled {
+            return;
+        }
+
+        let opts = self.view_options();
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.view
+                .set_render_options(opts.render_main, opts.render_fallback);
+        }
+
+        self.obj().queue_draw();
+    }
+
+    pub fn set_debug_overlay(&self, enabled: bool) {
+        self.debug_overlay.set(enabled);
+        self.obj().queue_draw();
+    }
+
+    fn draw_debug_overlay<M, F>(
+        &self,
+        snapshot: &gtk::Snapshot,
+        view: &DocumentView<M, F>,
+        z: Option<i64>,
+    ) where
+        M: Monitor<Damage> + Send + Clone + 'static,
+        F: TileFactory + Send + Clone + 'static,
+        F::Data: Send,
+    {
+        let policy = view.tile_policy();
+
+        let median = z
+            .and_then(|z| view.stats().median(z, 1))
+            .map(|t| format!("{} ms", t.as_millis()))
+            .unwrap_or_else(|| "n/a".into());
+
+        // render tasks of all priorities, averaged since the document has been opened
+        let tasks = view.render_metrics().total();
+        let millis = |t: Option<Duration>| {
+            t.map(|t| format!("{} ms", t.as_millis()))
+                .unwrap_or_else(|| "n/a".into())
+        };
+
+        let text = format!(
+            "tiling scheme: {}\n\
+             tile size: {} px (reduced {}×)\n\
+             tiling threshold: {} px\n\
+             median tile time: {} (budget {} ms)\n\
+             z-level: {}\n\
+             pooled buffers: {} MiB\n\
+             tiles in flight: {} queued, {} rendering\n\
+             tile latency: {} queued, {} rendering\n\
+             canceled tiles: {}",
+            policy.kind().label(),
+            policy.tile_size(),
+            policy.level(),
+            policy.min_tile_z(),
+            median,
+            policy.budget().as_millis(),
+            z.map(|z| z.to_string()).unwrap_or_else(|| "n/a".into()),
+            view.buffers().bytes() >> 20,
+            tasks.queued,
+            tasks.running,
+            millis(tasks.mean_wait_time()),
+            millis(tasks.mean_exec_time()),
+            tasks.canceled,
+        );
+
+        let layout = self.obj().create_pango_layout(Some(&text));
+        let (width, height) = layout.pixel_size();
+
+        let padding = 8.0;
+        let background = Rect::new(
+            point![padding, padding],
+            vector![width as f64, height as f64] + vector![padding, padding] * 2.0,
+        );
+
+        snapshot.append_color(&gdk::RGBA::new(0.0, 0.0, 0.0, 0.7), &background.into());
+
+        let offset = 2.0 * padding as f32;
+
+        snapshot.save();
+        snapshot.translate(&graphene::Point::new(offset, offset));
+        snapshot.append_layout(&layout, &gdk::RGBA::new(1.0, 1.0, 1.0, 1.0));
+        snapshot.restore();
+    }
+
+    /// Draw the label of a link hint at the given position, dimming the
+    /// given number of characters already typed.
+    fn draw_link_hint(
+        &self,
+        snapshot: &gtk::Snapshot,
+        label: &str,
+        typed: usize,
+        pos: Point2<f64>,
+    ) {
+        let (typed, rest) = label.split_at(typed);
+        let markup = format!(
+            "<span alpha=\"50%\">{}</span>{}",
+            typed.to_uppercase(),
+            rest.to_uppercase()
+        );
+
+        let layout = self.obj().create_pango_layout(None);
+        layout.set_font_description(Some(&pango::FontDescription::from_string(LINK_HINT_FONT)));
+        layout.set_markup(&markup);
+
+        let (width, height) = layout.pixel_size();
+
+        let padding = 2.0;
+        let size = vector![width as f64, height as f64] + vector![padding, padding] * 2.0;
+        let background = Rect::new(pos, size).round();
+
+        let color = gdk::RGBA::new(0.0, 0.0, 0.0, 1.0);
+        let fill = gdk::RGBA::new(0.96, 0.83, 0.18, 1.0);
+        let outline = gtk::gsk::RoundedRect::from_rect(background.into(), 2.0);
+
+        snapshot.push_rounded_clip(&outline);
+        snapshot.append_color(&fill, &background.into());
+        snapshot.pop();
+        snapshot.append_border(&outline, &[1.0; 4], &[color; 4]);
+
+        snapshot.save();
+        snapshot.translate(&graphene::Point::new(
+            (background.offs.x + padding) as f32,
+            (background.offs.y + padding) as f32,
+        ));
+        snapshot.append_layout(&layout, &color);
+        snapshot.restore();
+    }
+
+    fn draw_font_report(&self, snapshot: &gtk::Snapshot, page: usize, report: &FontReport) {
+        let text = if report.substituted.is_empty() {
+            format!("page {}: all fonts embedded", page + 1)
+        } else {
+            format!(
+                "page {}: substituted fonts\n{}",
+                page + 1,
+                report.substituted.join("\n")
+            )
+        };
+
+        let layout = self.obj().create_pango_layout(Some(&text));
+        let (width, height) = layout.pixel_size();
+
+        // place in the bottom left corner, clear of the debug overlay
+        let padding = 8.0;
+        let size = vector![width as f64, height as f64] + vector![padding, padding] * 2.0;
+        let offs = point![padding, self.obj().height() as f64 - padding - size.y];
+        let background = Rect::new(offs, size);
+
+        snapshot.append_color(&gdk::RGBA::new(0.0, 0.0, 0.0, 0.7), &background.into());
+
+        snapshot.save();
+        snapshot.translate(&graphene::Point::new(
+            (offs.x + padding) as f32,
+            (offs.y + padding) as f32,
+        ));
+        snapshot.append_layout(&layout, &gdk::RGBA::new(1.0, 1.0, 1.0, 1.0));
+        snapshot.restore();
+    }
+
+    fn restart_idle_timer(&self) {
+        self.idle.set(false);
+
+        if let Some(timer) = self.idle_timer.take() {
+            timer.remove();
+        }
+
+        let obj = self.obj().downgrade();
+        let timer = glib::timeout_add_local_once(IDLE_DELAY, move || {
+            if let Some(obj) = obj.upgrade() {
+                let imp = obj.imp();
+
+                imp.idle_timer.take();
+                imp.idle.set(true);
+                obj.queue_draw();
+            }
+        });
+
+        self.idle_timer.replace(Some(timer));
+    }
+
+    fn update_skeleton_animation(&self) {
+        if !self.skeleton_visible.get() || self.skeleton_animating.replace(true) {
+            return;
+        }
+
+        // keep redrawing while placeholders are visible
+        self.obj().add_tick_callback(|obj, _clock| {
+            let imp = obj.imp();
+
+            if imp.skeleton_visible.get() {
+                obj.queue_draw();
+                glib::Continue(true)
+            } else {
+                imp.skeleton_animating.set(false);
+                glib::Continue(false)
+            }
+        });
+    }
+}
+
+impl Default for CanvasWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for CanvasWidget {
+    const NAME: &'static str = "Canvas";
+    type Type = super::CanvasWidget;
+    type ParentType = gtk::Widget;
+    type Interfaces = (gtk::Scrollable,);
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.set_accessible_role(gtk::AccessibleRole::Document);
+    }
+}
+
+impl ObjectImpl for CanvasWidget {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        self.obj().set_focusable(true);
+        self.obj().set_can_focus(true);
+
+        // show contents of text annotations on click
+        let ctrl = gtk::GestureClick::builder()
+            .name("canvas_note_controller")
+            .button(gdk::BUTTON_PRIMARY)
+            .build();
+
+        let obj = self.obj();
+        ctrl.connect_released(glib::clone!(@weak obj => move |_, n, x, y| {
+            if n != 1 {
+                return;
+            }
+
+            let tool = obj.imp().tool.borrow().clone();
+
+            match tool {
+                // pick color instead of interacting with the page
+                Tool::Eyedropper => {
+                    if let Some(color) = obj.imp().color_at(point![x, y]) {
+                        obj.emit_by_name::<()>("color-picked", &[&color]);
+                    }
+                    return;
+                }
+
+                // place stamp
+                Tool::Stamp(_) => {
+                    obj.imp().place_stamp(point![x, y]);
+                    return;
+                }
+
+                // strokes are drawn via the pen controller
+                Tool::Pen(_) => return,
+
+                Tool::Pointer => {}
+            }
+
+            // select the stamp at the clicked point
+            if obj.imp().select_stamp_at(point![x, y]) {
+                return;
+            }
 
-    // properties for canvas
-    margin: RefCell<Margin<f64>>,
+            // follow links, show contents of text annotations otherwise
+            let event = obj.imp().handle_input(Input::Click(point![x, y]));
+            obj.imp().activate(event);
+        }));
 
-    // properties for viewport
-    offset: RefCell<Point2<f64>>,
-    scale: Cell<f64>,
+        obj.add_controller(ctrl);
 
-    // render options
-    fallback_specs: Vec<FallbackSpec>,
-    render_opts_main: RenderOptions,
-    render_opts_fallback: RenderOptions,
+        // select a region for inspection with ctrl + drag
+        let ctrl = gtk::GestureDrag::builder()
+            .name("canvas_inspect_controller")
+            .button(gdk::BUTTON_PRIMARY)
+            .build();
 
-    // render state
-    viewport: RefCell<Viewport>,
+        ctrl.connect_drag_begin(glib::clone!(@weak obj => move |gesture, x, y| {
+            let state = gesture.current_event_state();
 
-    // document data
-    data: RefCell<Option<DocumentData>>,
-}
+            if !state.contains(gdk::ModifierType::CONTROL_MASK) {
+                gesture.set_state(gtk::EventSequenceState::Denied);
+                return;
+            }
 
-struct DocumentData {
-    layout: Layout,
-    tile_provider: PdfTileProvider<TaskMonitor, TextureFactory>,
-    tile_manager: TileManager<HybridTilingScheme, Handle<gdk::MemoryTexture>>,
-    fallback_manager: FallbackManager<Handle<gdk::MemoryTexture>>,
-}
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            obj.imp().handle_input(Input::SelectBegin(point![x, y]));
+        }));
 
-impl CanvasWidget {
-    fn new() -> Self {
-        Self {
-            hscroll_policy: Cell::new(ScrollablePolicy::Minimum),
-            vscroll_policy: Cell::new(ScrollablePolicy::Minimum),
-            hadjustment: RefCell::new(None),
-            vadjustment: RefCell::new(None),
+        ctrl.connect_drag_update(glib::clone!(@weak obj => move |_, dx, dy| {
+            obj.imp().handle_input(Input::SelectUpdate(vector![dx, dy]));
+            obj.queue_draw();
+        }));
 
-            hadjustment_handler: Cell::new(None),
-            vadjustment_handler: Cell::new(None),
+        ctrl.connect_drag_end(glib::clone!(@weak obj => move |_, dx, dy| {
+            let event = obj.imp().handle_input(Input::SelectEnd(vector![dx, dy]));
+            obj.queue_draw();
 
-            margin: RefCell::new(Margin {
-                left: 50.0,
-                right: 50.0,
-                top: 100.0,
-                bottom: 100.0,
-            }),
-            offset: RefCell::new(point![0.0, 0.0]),
-            scale: Cell::new(1.0),
+            if let Some(Event::RegionSelected { page, rect: r }) = event {
+                obj.emit_by_name::<()>(
+                    "region-selected",
+                    &[&(page as u32), &r.offs.x, &r.offs.y, &r.size.x, &r.size.y],
+                );
+            }
+        }));
 
-            viewport: RefCell::new(Viewport {
-                r: Rect {
-                    offs: point![0.0, 0.0],
-                    size: vector![600.0, 800.0],
-                },
-                scale: 1.0,
-            }),
+        obj.add_controller(ctrl);
 
-            fallback_specs: vec![
-                FallbackSpec {
-                    halo: usize::MAX,
-                    render_threshold: vector![0.0, 0.0],
-                    render_limits: vector![128, 128],
-                },
-                FallbackSpec {
-                    halo: 24,
-                    render_threshold: vector![256.0, 256.0],
-                    render_limits: vector![256, 256],
-                },
-                FallbackSpec {
-                    halo: 1,
-                    render_threshold: vector![1024.0, 1024.0],
-                    render_limits: vector![1024, 1024],
-                },
-                FallbackSpec {
-                    halo: 0,
-                    render_threshold: vector![2048.0, 2048.0],
-                    render_limits: vector![2048, 2048],
-                },
-                FallbackSpec {
-                    halo: 0,
-                    render_threshold: vector![3072.0, 3072.0],
-                    render_limits: vector![3072, 3072],
-                },
-            ],
-            render_opts_main: RenderOptions {
-                flags: RenderFlags::LcdText | RenderFlags::Annotations,
-                background: Color::WHITE,
-            },
-            render_opts_fallback: RenderOptions {
-                flags: RenderFlags::Annotations,
-                background: Color::WHITE,
-            },
+        // move stamps, or resize the selected one via its handles
+        let ctrl = gtk::GestureDrag::builder()
+            .name("canvas_stamp_controller")
+            .button(gdk::BUTTON_PRIMARY)
+            .build();
 
-            data: RefCell::new(None),
-        }
-    }
+        ctrl.connect_drag_begin(glib::clone!(@weak obj => move |gesture, x, y| {
+            let state = gesture.current_event_state();
+            let imp = obj.imp();
 
-    fn bounds(&self) -> Bounds<f64> {
-        self.data
-            .borrow()
-            .as_ref()
-            .map(|d| d.layout.bounds)
-            .unwrap_or_else(Bounds::zero)
-    }
+            let inspecting = state.contains(gdk::ModifierType::CONTROL_MASK);
 
-    fn scale_bounds(&self) -> (f64, f64) {
-        (1e-2, 5e3)
-    }
+            if !imp.is_pointer() || inspecting || !imp.begin_stamp_drag(point![x, y]) {
+                gesture.set_state(gtk::EventSequenceState::Denied);
+                return;
+            }
 
-    pub fn set_document(&self, doc: Document) {
-        use crate::core::render::layout::{LayoutProvider, VerticalLayout};
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+        }));
 
-        // compute layout
-        let page_sizes = (0..(doc.pages().count())).map(|i| doc.pages().get_size(i).unwrap());
-        let layout = VerticalLayout.compute(page_sizes, 10.0);
+        ctrl.connect_drag_update(glib::clone!(@weak obj => move |_, dx, dy| {
+            obj.imp().update_stamp_drag(vector![dx, dy]);
+        }));
 
-        // set up tile-manager
-        let scheme = HybridTilingScheme::new(vector![1024, 1024], 3072);
-        let tile_manager = TileManager::new(scheme, vector![1, 1], vector![25.0, 25.0]);
+        ctrl.connect_drag_end(glib::clone!(@weak obj => move |_, dx, dy| {
+            obj.imp().update_stamp_drag(vector![dx, dy]);
+            obj.imp().stamp_drag.set(None);
+        }));
 
-        // set up fallback-manager
-        let fallback_manager = FallbackManager::new(&self.fallback_specs);
+        obj.add_controller(ctrl);
 
-        // set up render task execution
-        let executor = Executor::new(1);
-        let monitor = TaskMonitor::new(self.obj().clone());
-        let factory = TextureFactory;
-        let tile_provider = PdfTileProvider::new(executor, monitor, factory, doc);
+        // select text by dragging over it, starting only once the pointer
+        // has moved so that clicks still follow links
+        let ctrl = gtk::GestureDrag::builder()
+            .name("canvas_text_controller")
+            .button(gdk::BUTTON_PRIMARY)
+            .build();
 
-        let data = DocumentData {
-            layout,
-            tile_provider,
-            tile_manager,
-            fallback_manager,
-        };
+        ctrl.connect_drag_begin(glib::clone!(@weak obj => move |gesture, x, y| {
+            let state = gesture.current_event_state();
+            let imp = obj.imp();
 
-        *self.data.borrow_mut() = Some(data);
-        self.obj().queue_allocate();
-        self.obj().grab_focus();
-    }
+            let inspecting = state.contains(gdk::ModifierType::CONTROL_MASK);
+            let on_stamp = imp.stamp_at(point![x, y]).is_some();
 
-    pub fn clear(&self) {
-        *self.data.borrow_mut() = None;
-        self.obj().queue_allocate();
-    }
+            if !imp.is_pointer() || inspecting || on_stamp {
+                gesture.set_state(gtk::EventSequenceState::Denied);
+            }
+        }));
 
-    pub fn render(&self, vp: &Viewport, snapshot: &gtk::Snapshot) {
-        use crate::core::render::core::{PageData, TileProvider};
+        ctrl.connect_drag_update(glib::clone!(@weak obj => move |gesture, dx, dy| {
+            let imp = obj.imp();
 
-        let mut data = self.data.borrow_mut();
-        let data = match data.as_mut() {
-            Some(data) => data,
-            None => return,
-        };
+            if !imp.selecting_text.get() {
+                if vector![dx, dy].norm() < TEXT_DRAG_THRESHOLD {
+                    return;
+                }
 
-        // We have 3 coordinate systems:
-        //
-        // - Viewport coordinates, in pixels relative to the screen with origin
-        //   (0, 0) as upper left corner of the widget.
-        //
-        // - Canvas coordinates, in PDF points. The relation between viewport
-        //   and canvas coordinates is defined by the scale and viewport
-        //   offset.
-        //
-        // - Page coordinates, in PDF points, relative to the page. The origin
-        //   (0, 0) is defined as the upper left corner of the respective page.
-        //   The relation between page coordinates and canvas coordinates is
-        //   defined by the page offset in the canvas.
+                let (x, y) = match gesture.start_point() {
+                    Some(start) => start,
+                    None => return,
+                };
 
-        // transformation matrix: canvas to viewport
-        let m_ctv = {
-            let m_scale = Similarity2::from_scaling(vp.scale);
-            let m_trans = Translation2::from(-vp.r.offs.coords);
-            m_trans * m_scale
-        };
+                gesture.set_state(gtk::EventSequenceState::Claimed);
+                imp.selecting_text.set(true);
+                imp.handle_input(Input::SelectTextBegin(point![x, y]));
+            }
 
-        // transformation: page (bounds) from canvas to viewport
-        let transform = move |page_rect: &Rect<f64>| {
-            // transformation matrix: page to canvas
-            let m_ptc = Translation2::from(page_rect.offs);
+            imp.handle_input(Input::SelectTextUpdate(vector![dx, dy]));
+            obj.queue_draw();
+        }));
 
-            // transformation matrix: page to viewport/screen
-            let m_ptv = m_ctv * m_ptc;
+        ctrl.connect_drag_end(glib::clone!(@weak obj => move |_, dx, dy| {
+            let imp = obj.imp();
 
-            // convert page bounds to screen coordinates
-            let page_rect = Rect::new(m_ptv * point![0.0, 0.0], m_ptv * page_rect.size);
+            if imp.selecting_text.replace(false) {
+                imp.handle_input(Input::SelectTextEnd(vector![dx, dy]));
+                imp.set_accessible_focus(imp.selected_text());
+                obj.queue_draw();
+            }
+        }));
 
-            // round coordinates for pixel-perfect rendering
-            page_rect.round()
-        };
+        obj.add_controller(ctrl);
 
-        // origin-aligned viewport
-        let screen_rect = Rect::new(point![0.0, 0.0], vp.r.size);
+        // draw strokes with the pen tool, for mouse, touch, and stylus input
+        // alike
+        let ctrl = gtk::GestureDrag::builder()
+            .name("canvas_pen_controller")
+            .button(gdk::BUTTON_PRIMARY)
+            .build();
 
-        // find visible pages
-        #[allow(clippy::reversed_empty_ranges)]
-        let mut visible = usize::MAX..0;
+        ctrl.connect_drag_begin(glib::clone!(@weak obj => move |gesture, x, y| {
+            let state = gesture.current_event_state();
+            let inspecting = state.contains(gdk::ModifierType::CONTROL_MASK);
+
+            if inspecting || !obj.imp().begin_stroke(point![x, y]) {
+                gesture.set_state(gtk::EventSequenceState::Denied);
+                return;
+            }
 
-        for (i, page_rect_pt) in data.layout.rects.iter().enumerate() {
-            // transform page bounds to viewport
-            let page_rect = transform(page_rect_pt);
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+        }));
 
-            // check if the page is visible
-            if page_rect.intersects(&screen_rect) {
-                visible.start = usize::min(visible.start, i);
-                visible.end = usize::max(visible.end, i + 1);
+        ctrl.connect_drag_update(glib::clone!(@weak obj => move |gesture, dx, dy| {
+            if let Some((x, y)) = gesture.start_point() {
+                obj.imp().update_stroke(point![x + dx, y + dy]);
             }
-        }
+        }));
 
-        // ensure that we have a valid range if there are no visible pages
-        if visible.start > visible.end {
-            visible = 0..0;
-        }
+        ctrl.connect_drag_end(glib::clone!(@weak obj => move |gesture, dx, dy| {
+            if let Some((x, y)) = gesture.start_point() {
+                obj.imp().update_stroke(point![x + dx, y + dy]);
+            }
+            obj.imp().end_stroke();
+        }));
 
-        // update fallback- and tile-caches
-        data.tile_provider.request(&visible, |source| {
-            let pages = PageData::new(&data.layout.rects, &visible, &transform);
+        obj.add_controller(ctrl);
 
-            data.fallback_manager
-                .update(source, &pages, vp, &self.render_opts_fallback);
+        // remove or write the selected stamp, leave tools, follow links via
+        // link hints
+        let ctrl = gtk::EventControllerKey::new();
 
-            data.tile_manager
-                .update(source, &pages, vp, &self.render_opts_main);
-        });
+        ctrl.connect_key_pressed(
+            glib::clone!(@weak obj => @default-return glib::signal::Inhibit(false), move |_, key, _, state| {
+                let imp = obj.imp();
+                let placing = matches!(*imp.tool.borrow(), Tool::Stamp(_) | Tool::Pen(_));
+                let modifiers = gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::ALT_MASK;
 
-        // render pages
-        let iter = visible.clone().zip(&data.layout.rects[visible]);
+                if imp.is_showing_link_hints() {
+                    return glib::signal::Inhibit(imp.link_hint_key(key));
+                }
 
-        for (i, page_rect_pt) in iter {
-            // transform page bounds to viewport
-            let page_rect = transform(page_rect_pt);
+                match key {
+                    gdk::Key::f if !placing && !state.intersects(modifiers) => {
+                        imp.show_link_hints();
+                        glib::signal::Inhibit(true)
+                    }
+                    gdk::Key::Delete | gdk::Key::BackSpace => {
+                        glib::signal::Inhibit(imp.remove_selected_stamp())
+                    }
+                    gdk::Key::Return | gdk::Key::KP_Enter => {
+                        glib::signal::Inhibit(imp.deselect_stamp())
+                    }
+                    gdk::Key::Escape if placing => {
+                        imp.set_tool(Tool::Pointer);
+                        obj.emit_by_name::<()>("tool-canceled", &[]);
+                        glib::signal::Inhibit(true)
+                    }
+                    _ => glib::signal::Inhibit(false),
+                }
+            }),
+        );
 
-            // clip page bounds to visible screen area (area on screen covered by page)
-            let page_clipped = page_rect.clip(&screen_rect);
+        obj.add_controller(ctrl);
 
-            // recompute scale for rounded page
-            let scale = page_rect.size.x / page_rect_pt.size.x;
-            let vp_adj = Viewport { r: vp.r, scale };
+        // indicate links, stamps, and tools via cursor
+        let ctrl = gtk::EventControllerMotion::new();
 
-            // draw page shadow
-            {
-                let bounds = page_rect.into();
-                let radius = gtk::gsk::graphene::Size::new(0.0, 0.0);
-                let outline = gtk::gsk::RoundedRect::new(bounds, radius, radius, radius, radius);
+        ctrl.connect_motion(glib::clone!(@weak obj => move |_, x, y| {
+            let imp = obj.imp();
+            let pos = point![x, y];
 
-                let color = gdk::RGBA::new(0.0, 0.0, 0.0, 0.5);
+            let tool_cursor = imp.tool.borrow().cursor();
 
-                let shift = vector![0.0, 1.0];
-                let spread = 0.0;
-                let blur = 3.5;
+            let cursor = if tool_cursor.is_some() {
+                tool_cursor
+            } else if let Some((_, corner)) = imp.stamp_handle_at(pos) {
+                match corner {
+                    Corner::TopLeft | Corner::BottomRight => Some("nwse-resize"),
+                    Corner::TopRight | Corner::BottomLeft => Some("nesw-resize"),
+                }
+            } else if imp.stamp_at(pos).is_some() {
+                Some("move")
+            } else {
+                imp.is_link_at(pos).then_some("pointer")
+            };
 
-                snapshot.append_outset_shadow(&outline, &color, shift.x, shift.y, spread, blur)
-            }
+            obj.set_cursor_from_name(cursor);
+        }));
 
-            // draw page background
-            snapshot.append_color(&gdk::RGBA::new(1.0, 1.0, 1.0, 1.0), &page_clipped.into());
+        obj.add_controller(ctrl);
 
-            // draw fallback
-            if let Some(tex) = data.fallback_manager.fallback(i) {
-                snapshot.append_texture(tex, &page_rect.into());
-            }
+        // re-render at the new resolution when moved to a monitor with a
+        // different scale factor
+        self.scale_factor.set(obj.scale_factor() as f64);
 
-            // draw tiles
-            let tile_list = data.tile_manager.tiles(&vp_adj, i, &page_rect);
+        obj.connect_scale_factor_notify(|obj| {
+            let factor = obj.scale_factor() as f64;
 
-            snapshot.push_clip(&page_clipped.into());
-            for (tile_rect, tex) in &tile_list {
-                snapshot.append_texture(*tex, &(*tile_rect).into());
+            if obj.imp().scale_factor.replace(factor) != factor {
+                tracing::debug!(factor, "scale factor changed");
+                obj.imp().sync_viewport();
+                obj.queue_draw();
             }
-            snapshot.pop();
-        }
+        });
     }
-}
 
-impl Default for CanvasWidget {
-    fn default() -> Self {
-        Self::new()
+    fn signals() -> &'static [Signal] {
+        static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+            vec![
+                Signal::builder("link-activated")
+                    .param_types([
+                        u32::static_type(),
+                        f64::static_type(),
+                        f64::static_type(),
+                        f64::static_type(),
+                        f64::static_type(),
+                    ])
+                    .build(),
+                Signal::builder("region-selected")
+                    .param_types([
+                        u32::static_type(),
+                        f64::static_type(),
+                        f64::static_type(),
+                        f64::static_type(),
+                        f64::static_type(),
+                    ])
+                    .build(),
+                Signal::builder("uri-activated")
+                    .param_types([String::static_type()])
+                    .build(),
+                Signal::builder("color-picked")
+                    .param_types([gdk::RGBA::static_type()])
+                    .build(),
+                Signal::builder("tool-canceled").build(),
+            ]
+        });
+        SIGNALS.as_ref()
     }
-}
 
-#[glib::object_subclass]
-impl ObjectSubclass for CanvasWidget {
-    const NAME: &'static str = "Canvas";
-    type Type = super::CanvasWidget;
-    type ParentType = gtk::Widget;
-    type Interfaces = (gtk::Scrollable,);
-}
-
-impl ObjectImpl for CanvasWidget {
-    fn constructed(&self) {
-        self.parent_constructed();
-
-        self.obj().set_focusable(true);
-        self.obj().set_can_focus(true);
+    fn dispose(&self) {
+        if let Some(note) = self.note.take() {
+            note.popover.unparent();
+        }
     }
 
     fn properties() -> &'static [ParamSpec] {
@@ -354,6 +2578,10 @@ impl ObjectImpl for CanvasWidget {
                 glib::ParamSpecDouble::builder("margin-right").build(),
                 glib::ParamSpecDouble::builder("margin-top").build(),
                 glib::ParamSpecDouble::builder("margin-bottom").build(),
+                glib::ParamSpecDouble::builder("page-spacing")
+                    .minimum(0.0)
+                    .default_value(10.0)
+                    .build(),
                 glib::ParamSpecDouble::builder("offset-x").build(),
                 glib::ParamSpecDouble::builder("offset-y").build(),
                 glib::ParamSpecDouble::builder("scale-min")
@@ -477,6 +2705,14 @@ impl ObjectImpl for CanvasWidget {
                 obj.queue_resize();
                 obj.notify_by_pspec(pspec);
             }
+            "page-spacing" => {
+                let spacing: f64 = value.get().unwrap();
+
+                if self.page_spacing.replace(spacing) != spacing {
+                    self.relayout();
+                    self.obj().notify_by_pspec(pspec);
+                }
+            }
             "offset-x" => {
                 self.offset.borrow_mut().x = value.get().unwrap();
 
@@ -524,6 +2760,7 @@ impl ObjectImpl for CanvasWidget {
             "margin-right" => self.margin.borrow().right.to_value(),
             "margin-top" => self.margin.borrow().top.to_value(),
             "margin-bottom" => self.margin.borrow().bottom.to_value(),
+            "page-spacing" => self.page_spacing.get().to_value(),
             "offset-x" => self.offset.borrow().x.to_value(),
             "offset-y" => self.offset.borrow().y.to_value(),
             "scale-min" => self.scale_bounds().0.to_value(),
@@ -535,6 +2772,16 @@ impl ObjectImpl for CanvasWidget {
 }
 
 impl WidgetImpl for CanvasWidget {
+    fn realize(&self) {
+        self.parent_realize();
+        self.update_gl_uploader();
+    }
+
+    fn unrealize(&self) {
+        self.texture_factory.set_gl_uploader(None);
+        self.parent_unrealize();
+    }
+
     fn request_mode(&self) -> gtk::SizeRequestMode {
         gtk::SizeRequestMode::ConstantSize
     }
@@ -643,10 +2890,26 @@ impl WidgetImpl for CanvasWidget {
         self.obj().notify("offset-y");
 
         // update render state
-        let mut viewport = self.viewport.borrow_mut();
-        viewport.r.offs = offset;
-        viewport.r.size = viewport_size;
-        viewport.scale = scale;
+        let changed = {
+            let mut viewport = self.viewport.borrow_mut();
+            let changed = viewport.r.offs != offset
+                || viewport.r.size != viewport_size
+                || viewport.scale != scale;
+
+            viewport.r.offs = offset;
+            viewport.r.size = viewport_size;
+            viewport.scale = scale;
+            changed
+        };
+
+        self.sync_viewport();
+
+        if changed {
+            self.restart_idle_timer();
+        }
+
+        // keep open notes attached to their annotation
+        self.update_note_position();
     }
 
     fn snapshot(&self, snapshot: &gtk::Snapshot) {
@@ -656,45 +2919,367 @@ impl WidgetImpl for CanvasWidget {
         let bounds = graphene::Rect::new(0.0, 0.0, obj.width() as _, obj.height() as _);
         snapshot.push_clip(&bounds);
 
-        // draw actual canvas
-        let viewport = self.viewport.borrow();
-        self.render(&viewport, snapshot);
+        // draw actual canvas, shifted while sliding in pages
+        let slide = self.slide_offset.get();
+        if slide != 0.0 {
+            snapshot.save();
+            snapshot.translate(&graphene::Point::new(slide as _, 0.0));
+        }
+
+        let start = Instant::now();
+
+        self.render(snapshot);
+
+        if slide != 0.0 {
+            snapshot.restore();
+        }
 
         // pop the clip
         snapshot.pop();
+
+        self.record_frame_time(start.elapsed());
+        self.update_skeleton_animation();
     }
 }
 
 impl ScrollableImpl for CanvasWidget {}
 
+/// Width of the caret in caret navigation mode, in pixels.
+const CARET_WIDTH: f64 = 2.0;
+
+/// Distance the pointer has to be dragged before text is selected, in
+/// pixels.
+const TEXT_DRAG_THRESHOLD: f64 = 4.0;
+
+/// Margin kept around the caret when scrolling to it, in pixels.
+const REVEAL_MARGIN: f64 = 48.0;
+
+/// Maximum median render time of a tile before the tile size is reduced.
+const TILE_TIME_BUDGET: std::time::Duration = std::time::Duration::from_millis(80);
+
+/// Minimum page size for tiling with the hybrid scheme, relative to the tile
+/// size. Smaller pages are rendered as a single tile.
+const TILE_MIN_Z_FACTOR: i64 = 3;
+
+/// Number of tiles around the viewport rendered ahead of time.
+const TILE_HALO: Vector2<i64> = vector![1, 1];
+
+/// Number of pages around the visible ones for which fallbacks are rendered
+/// in low-power mode.
+const LOW_POWER_FALLBACK_HALO: usize = 1;
+
+/// Number of threads rendering tiles and fallbacks in low-power mode.
+const LOW_POWER_RENDER_THREADS: u32 = 1;
+
+/// Number of pages around the visible ones for which fallbacks are rendered
+/// at full resolution when pre-rendering neighboring pages.
+const NEIGHBOR_FALLBACK_HALO: usize = 1;
+
+/// Maximum number of fallbacks pending at once. Further fallbacks are
+/// requested as pending ones complete, nearest to the viewport center first.
+const FALLBACK_PENDING_LIMIT: usize = 16;
+
+/// Maximum number of fallbacks requested per frame.
+const FALLBACK_REQUEST_LIMIT: usize = 4;
+
+/// Maximum time taken to build a frame before load is shed.
+const FRAME_TIME_BUDGET: Duration = Duration::from_millis(16);
+
+/// Maximum number of newly rendered tiles shown per frame while shedding
+/// load.
+const SHED_UPLOAD_LIMIT: usize = 4;
+
+/// Time without viewport changes after which the canvas is considered idle.
+const IDLE_DELAY: std::time::Duration = std::time::Duration::from_millis(300);
+
+/// Duration of one sweep of the placeholder shimmer, in microseconds.
+const SHIMMER_PERIOD_US: i64 = 1_500_000;
+
+/// Duration of the transition between light and dark page colors, in
+/// microseconds.
+const DARK_FADE_DURATION_US: i64 = 250_000;
+
+/// Duration of the slide animation when flipping pages, in microseconds.
+const SLIDE_DURATION_US: i64 = 200_000;
+
+/// Font of link hint labels.
+const LINK_HINT_FONT: &str = "Monospace Bold 9";
+
+/// Font of text stamps, at their default size.
+const STAMP_FONT: &str = "Sans Bold 24";
+
+/// Default width of image stamps, in PDF points.
+const STAMP_IMAGE_WIDTH: f64 = 144.0;
+
+/// Size of the resize handles of the selected stamp, in pixels.
+const STAMP_HANDLE_SIZE: f64 = 8.0;
+
+/// Draw a placeholder for a page that has not been rendered yet.
+/// Color matrix and offset for inverting the lightness of colors, blended
+/// with the identity by the given factor.
+///
+/// Colors are inverted and their hue rotated by 180 degrees, so that black
+/// text turns white while colored content keeps its hue.
+fn dark_color_matrix(factor: f32) -> (graphene::Matrix, graphene::Vec4) {
+    // hue rotation by 180 degrees, see the CSS filter effects specification
+    #[rustfmt::skip]
+    let hue = [
+        -0.574, 1.430,  0.144,
+         0.426, 0.430,  0.144,
+         0.426, 1.430, -0.856,
+    ];
+
+    // blend(c) = (1 - f) * c + f * (1 - hue * c)
+    let m = |row: usize, col: usize| {
+        let identity = if row == col { 1.0 } else { 0.0 };
+        (1.0 - factor) * identity - factor * hue[row * 3 + col]
+    };
+
+    // GSK multiplies colors as row vectors, so the matrix is transposed
+    #[rustfmt::skip]
+    let matrix = graphene::Matrix::from_float([
+        m(0, 0), m(1, 0), m(2, 0), 0.0,
+        m(0, 1), m(1, 1), m(2, 1), 0.0,
+        m(0, 2), m(1, 2), m(2, 2), 0.0,
+        0.0,     0.0,     0.0,     1.0,
+    ]);
+
+    let offset = graphene::Vec4::new(factor, factor, factor, 0.0);
+
+    (matrix, offset)
+}
+
+fn draw_skeleton(snapshot: &gtk::Snapshot, page_rect: &Rect<f64>, clip: &Rect<f64>, phase: f32) {
+    let base = gdk::RGBA::new(0.94, 0.94, 0.94, 1.0);
+    let highlight = gdk::RGBA::new(1.0, 1.0, 1.0, 0.8);
+    let transparent = gdk::RGBA::new(1.0, 1.0, 1.0, 0.0);
+
+    snapshot.append_color(&base, &(*clip).into());
+
+    // page outline
+    let bounds: graphene::Rect = (*page_rect).into();
+    let outline = gtk::gsk::RoundedRect::from_rect(bounds, 0.0);
+    let border = gdk::RGBA::new(0.0, 0.0, 0.0, 0.1);
+    snapshot.append_border(&outline, &[1.0; 4], &[border; 4]);
+
+    // highlight band sweeping diagonally across the page
+    let x = bounds.x();
+    let y = bounds.y();
+    let w = bounds.width();
+    let h = bounds.height();
+
+    let band = w.max(h) * 0.5;
+    let offset = -band + phase * (w + h + band);
+
+    let start = graphene::Point::new(x + offset, y);
+    let end = graphene::Point::new(x + offset + band, y + band);
+
+    let stops = [
+        gtk::gsk::ColorStop::new(0.0, transparent),
+        gtk::gsk::ColorStop::new(0.5, highlight),
+        gtk::gsk::ColorStop::new(1.0, transparent),
+    ];
+
+    snapshot.append_linear_gradient(&(*clip).into(), &start, &end, &stops);
+}
+
+/// Draw a stroke of the pen tool on the given page.
+fn draw_stroke<T>(snapshot: &gtk::Snapshot, stroke: &Stroke, page: &PageFrame<'_, T>) {
+    let bounds = page.to_viewport(&stroke.bounds()).round();
+    let cr = snapshot.append_cairo(&bounds.into());
+
+    let color = stroke.pen.color;
+    cr.set_source_rgba(
+        color.r as f64 / 255.0,
+        color.g as f64 / 255.0,
+        color.b as f64 / 255.0,
+        color.a as f64 / 255.0,
+    );
+    cr.set_line_width(stroke.pen.width * page.scale);
+    cr.set_line_cap(gtk::cairo::LineCap::Round);
+    cr.set_line_join(gtk::cairo::LineJoin::Round);
+
+    for p in &stroke.points {
+        let p = page.point_to_viewport(p);
+        cr.line_to(p.x, p.y);
+    }
+
+    let _ = cr.stroke();
+}
+
+/// Read back the color of a texture drawn to the given rectangle at the given
+/// point, all in viewport coordinates.
+fn texture_color_at(tex: &gdk::Texture, rect: &Rect<f64>, pos: &Point2<f64>) -> Option<gdk::RGBA> {
+    let width = tex.width() as usize;
+    let height = tex.height() as usize;
+
+    if width == 0 || height == 0 || rect.size.x <= 0.0 || rect.size.y <= 0.0 {
+        return None;
+    }
+
+    // textures are stretched to fill their rectangle
+    let x = ((pos.x - rect.offs.x) / rect.size.x * width as f64) as usize;
+    let y = ((pos.y - rect.offs.y) / rect.size.y * height as f64) as usize;
+    let (x, y) = (x.min(width - 1), y.min(height - 1));
+
+    // textures are always downloaded as premultiplied ARGB in native byte
+    // order, regardless of their memory format
+    let stride = width * 4;
+    let mut buffer = vec![0; stride * height];
+    tex.download(&mut buffer, stride);
+
+    let offset = y * stride + x * 4;
+    let pixel = u32::from_ne_bytes(buffer[offset..offset + 4].try_into().ok()?);
+
+    let a = ((pixel >> 24) & 0xff) as f32 / 255.0;
+    if a == 0.0 {
+        return Some(gdk::RGBA::new(0.0, 0.0, 0.0, 0.0));
+    }
+
+    let channel = |shift: u32| (((pixel >> shift) & 0xff) as f32 / 255.0 / a).min(1.0);
+
+    Some(gdk::RGBA::new(channel(16), channel(8), channel(0), a))
+}
+
+/// Monitor for render tasks, redrawing the canvas on completion.
+///
+/// Completions are coalesced: only the first completion after a redraw sends
+/// a notification to the main thread, which then checks all regions damaged in
+/// the meantime. If none of them are visible, the redraw is skipped entirely.
 #[derive(Clone)]
 struct TaskMonitor {
     sender: glib::Sender<()>,
+    pending: Arc<Mutex<PendingDamage>>,
+
+    /// Whether a notification has been sent but not yet been handled.
+    queued: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct PendingDamage {
+    regions: Vec<Damage>,
+    full: bool,
 }
 
 impl TaskMonitor {
     fn new(widget: super::CanvasWidget) -> Self {
         let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+        let pending = Arc::new(Mutex::new(PendingDamage::default()));
+        let queued = Arc::new(AtomicBool::new(false));
 
+        let damage = pending.clone();
+        let flag = queued.clone();
         receiver.attach(None, move |_| {
-            widget.queue_draw();
+            // reset the flag first, so that any completion after taking the
+            // damage below sends a new notification
+            flag.store(false, Ordering::SeqCst);
+
+            let damage = std::mem::take(&mut *damage.lock().unwrap());
+
+            // deferred fallbacks are requested with the next frame, so redraw
+            // as long as there are any, even if nothing visible has changed
+            let visible = damage.full
+                || widget.imp().has_deferred_fallbacks()
+                || damage
+                    .regions
+                    .iter()
+                    .any(|d| widget.imp().is_damage_visible(d));
+
+            if visible {
+                widget.queue_draw();
+            }
+
             glib::Continue(true)
         });
 
-        Self { sender }
+        Self {
+            sender,
+            pending,
+            queued,
+        }
+    }
+
+    fn notify(&self, f: impl FnOnce(&mut PendingDamage)) {
+        f(&mut self.pending.lock().unwrap());
+
+        // skip if a notification is already pending
+        if self.queued.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        // the receiver is gone if the canvas has been cleared while the task
+        // was running, in which case there is nothing left to redraw
+        if self.sender.send(()).is_err() {
+            tracing::trace!("render task completed after canvas has been cleared");
+        }
     }
 }
 
 impl Monitor for TaskMonitor {
-    fn on_complete(&self) {
-        self.sender.send(()).unwrap()
+    fn on_complete(&self, _ctx: &()) {
+        self.notify(|pending| pending.full = true)
     }
 }
 
-#[derive(Debug, Clone)]
-struct TextureFactory;
+impl Monitor<Damage> for TaskMonitor {
+    fn on_complete(&self, damage: &Damage) {
+        self.notify(|pending| pending.regions.push(*damage))
+    }
+}
+
+/// Factory uploading rendered bitmaps as textures, also used for thumbnails.
+///
+/// Bitmaps are uploaded to GL textures on the render threads if an uploader
+/// is set, falling back to memory textures otherwise. Clones share the
+/// uploader, so that it can be replaced while rendering.
+#[derive(Debug, Clone, Default)]
+pub struct TextureFactory {
+    gl: Arc<RwLock<Option<Arc<GlUploader>>>>,
+}
+
+impl TextureFactory {
+    fn set_gl_uploader(&self, uploader: Option<GlUploader>) {
+        *self.gl.write().unwrap() = uploader.map(Arc::new);
+    }
+
+    fn has_gl_uploader(&self) -> bool {
+        self.gl.read().unwrap().is_some()
+    }
+}
 
 impl TileFactory for TextureFactory {
+    type Data = gdk::Texture;
+
+    fn create(&self, bmp: Bitmap) -> gdk::Texture {
+        // don't hold the lock while uploading
+        let gl = self.gl.read().unwrap().clone();
+
+        let bmp = match gl {
+            Some(gl) => match gl.upload(bmp) {
+                Ok(texture) => return texture,
+                Err(bmp) => bmp,
+            },
+            None => bmp,
+        };
+
+        let bytes = glib::Bytes::from_owned(bmp.buffer);
+
+        let texture = gdk::MemoryTexture::new(
+            bmp.size.x as _,
+            bmp.size.y as _,
+            gdk::MemoryFormat::B8g8r8,
+            &bytes,
+            bmp.stride as _,
+        );
+
+        texture.upcast()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct HeatMapFactory;
+
+impl TileFactory for HeatMapFactory {
     type Data = gdk::MemoryTexture;
 
     fn create(&self, bmp: Bitmap) -> gdk::MemoryTexture {
@@ -703,7 +3288,7 @@ impl TileFactory for TextureFactory {
         gdk::MemoryTexture::new(
             bmp.size.x as _,
             bmp.size.y as _,
-            gdk::MemoryFormat::B8g8r8,
+            gdk::MemoryFormat::B8g8r8a8Premultiplied,
             &bytes,
             bmp.stride as _,
         )
@@ -1,14 +1,35 @@
-use gtk::glib;
+use std::ops::Range;
+use std::sync::Arc;
+
+use gtk::prelude::ObjectExt;
 use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::{gdk, glib};
+use nalgebra::{point, vector, Vector2};
+
+use pdfium::bitmap::Color;
+use pdfium::doc::{Document, PageRotation};
 
-use pdfium::doc::Document;
+use crate::core::caret::CaretMarks;
+use crate::core::citations::{Link, Location};
+use crate::core::fonts::FontReport;
+use crate::core::ink::Pen;
+use crate::core::markup::MarkupKind;
+use crate::core::render::core::TilingSchemeKind;
+use crate::core::render::disk::DiskCache;
+use crate::core::render::layout::{LayoutKind, PageSizeIssue};
+use crate::core::render::pdfium::Executor;
+use crate::core::search::SearchMatch;
+use crate::core::stamps::StampContent;
+use crate::types::Rect;
 
 mod imp;
 
+pub(crate) use imp::TextureFactory;
+
 glib::wrapper! {
     pub struct CanvasWidget(ObjectSubclass<imp::CanvasWidget>)
         @extends gtk::Widget,
-        @implements gtk::Scrollable, gtk::Buildable;
+        @implements gtk::Accessible, gtk::Scrollable, gtk::Buildable;
 }
 
 impl CanvasWidget {
@@ -16,13 +37,374 @@ impl CanvasWidget {
         glib::Object::new()
     }
 
-    pub fn set_document(&self, document: Document) {
+    /// Show the given document, returning the pages of which the size had
+    /// to be corrected for layout.
+    pub fn set_document(&self, document: Document) -> Vec<(usize, PageSizeIssue)> {
         self.imp().set_document(document)
     }
 
+    /// Replace the document with a new version of it, e.g. after it has been
+    /// changed on disk, keeping the current offset and scale. Returns the
+    /// pages of which the size had to be corrected for layout.
+    pub fn swap_document(&self, document: Document) -> Vec<(usize, PageSizeIssue)> {
+        self.imp().swap_document(document)
+    }
+
     pub fn clear(&self) {
         self.imp().clear()
     }
+
+    /// Compare the current document against the given one, showing page
+    /// differences as overlay. Pass `None` to stop comparing.
+    pub fn set_compare_document(&self, document: Option<Document>) {
+        self.imp().set_compare_document(document)
+    }
+
+    /// Pages only present in the document compared against, which cannot be
+    /// shown as overlay on the current document.
+    pub fn compare_added_pages(&self) -> Range<usize> {
+        self.imp().compare_added_pages()
+    }
+
+    /// Set links synthesized for the current document, e.g. from citations.
+    pub fn set_links(&self, links: Vec<Link>) {
+        self.imp().set_links(links)
+    }
+
+    /// Connect to the signal emitted when the user activates a link,
+    /// providing the link target.
+    pub fn connect_link_activated<F: Fn(&Self, Location) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "link-activated",
+            false,
+            glib::closure_local!(move |canvas: &Self, page: u32, x: f64, y: f64, w: f64, h: f64| {
+                let rect = Rect::new(point![x, y], vector![w, h]);
+                f(canvas, Location { page: page as _, rect })
+            }),
+        )
+    }
+
+    /// Connect to the signal emitted when the user activates a link to an
+    /// external resource, providing its URI.
+    pub fn connect_uri_activated<F: Fn(&Self, String) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "uri-activated",
+            false,
+            glib::closure_local!(move |canvas: &Self, uri: String| f(canvas, uri)),
+        )
+    }
+
+    /// Connect to the signal emitted when the user selects a region of a page
+    /// for inspection, providing the page index and the region in page
+    /// coordinates (PDF points).
+    pub fn connect_region_selected<F: Fn(&Self, usize, Rect<f64>) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "region-selected",
+            false,
+            glib::closure_local!(move |canvas: &Self, page: u32, x: f64, y: f64, w: f64, h: f64| {
+                f(canvas, page as _, Rect::new(point![x, y], vector![w, h]))
+            }),
+        )
+    }
+
+    /// Pick colors on click instead of following links and opening notes.
+    pub fn set_eyedropper(&self, enabled: bool) {
+        self.imp().set_eyedropper(enabled)
+    }
+
+    /// Connect to the signal emitted when the user picks a color with the
+    /// eyedropper, providing the rendered color at the picked point.
+    pub fn connect_color_picked<F: Fn(&Self, gdk::RGBA) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "color-picked",
+            false,
+            glib::closure_local!(move |canvas: &Self, color: gdk::RGBA| f(canvas, color)),
+        )
+    }
+
+    /// Place a stamp with the given content on the next click on a page, or
+    /// stop placing stamps if `None`.
+    pub fn set_stamp_tool(&self, content: Option<StampContent>) {
+        self.imp().set_stamp_tool(content)
+    }
+
+    /// Draw freehand strokes with the given pen, written to the document as
+    /// ink annotations, or stop drawing if `None`.
+    pub fn set_pen_tool(&self, pen: Option<Pen>) {
+        self.imp().set_pen_tool(pen)
+    }
+
+    /// Mark the selected text with annotations of the given kind and color.
+    /// Returns `false` if no text is selected.
+    pub fn markup_selection(&self, kind: MarkupKind, color: Color) -> pdfium::Result<bool> {
+        self.imp().markup_selection(kind, color)
+    }
+
+    /// Connect to the signal emitted when the user leaves the stamp or pen
+    /// tool, e.g. by pressing escape.
+    pub fn connect_tool_canceled<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "tool-canceled",
+            false,
+            glib::closure_local!(move |canvas: &Self| f(canvas)),
+        )
+    }
+
+    /// The page shown on its own, if any.
+    pub fn single_page(&self) -> Option<usize> {
+        self.imp().single_page()
+    }
+
+    /// Show only the given page, or all pages if `None`.
+    pub fn set_single_page(&self, page: Option<usize>) {
+        self.imp().set_single_page(page)
+    }
+
+    /// Render a low-resolution preview of the given page with high priority,
+    /// e.g. before flipping to it.
+    pub fn prefetch_page(&self, page: usize) {
+        self.imp().prefetch_page(page)
+    }
+
+    /// Slide the pages in from the side, starting at the given horizontal
+    /// offset in pixels.
+    pub fn slide_in(&self, offset: f64) {
+        self.imp().slide_in(offset)
+    }
+
+    /// Reduce background rendering to save power, i.e. render only what is
+    /// needed for the current view.
+    pub fn set_low_power(&self, low_power: bool) {
+        self.imp().set_low_power(low_power)
+    }
+
+    /// Render the pages next to the visible ones at the same resolution,
+    /// e.g. for flipping to them without showing a blurry preview first.
+    pub fn set_prerender_neighbors(&self, enabled: bool) {
+        self.imp().set_prerender_neighbors(enabled)
+    }
+
+    /// Select the tiling scheme and base tile size used for rendering.
+    pub fn set_tiling(&self, kind: TilingSchemeKind, tile_size: i64) {
+        self.imp().set_tiling(kind, tile_size)
+    }
+
+    /// Upload tiles to GL textures on the render threads instead of leaving
+    /// the upload to the renderer, if supported.
+    pub fn set_gl_upload(&self, enabled: bool) {
+        self.imp().set_gl_upload(enabled)
+    }
+
+    /// Release cached tiles and stop rendering until resumed, e.g. while the
+    /// window has not been shown for a while.
+    pub fn suspend(&self) {
+        self.imp().suspend()
+    }
+
+    /// Resume rendering after [`suspend`](Self::suspend).
+    pub fn resume(&self) {
+        self.imp().resume()
+    }
+
+    /// Load low-resolution renders of the current document from and store
+    /// them in the given disk cache.
+    pub fn set_disk_cache(&self, cache: Option<DiskCache>) {
+        self.imp().set_disk_cache(cache)
+    }
+
+    /// Render all pages again, e.g. after annotations have been added.
+    pub fn reload_pages(&self) {
+        self.imp().reload_pages()
+    }
+
+    /// Mark text drawn with substituted fonts on the given page and list
+    /// these fonts, or hide the marks if `None`.
+    pub fn set_font_report(&self, report: Option<(usize, FontReport)>) {
+        self.imp().set_font_report(report)
+    }
+
+    /// Show the caret and selected text of caret navigation, or hide them if
+    /// `None`.
+    pub fn set_caret(&self, marks: Option<CaretMarks>) {
+        self.imp().set_caret(marks)
+    }
+
+    /// Highlight the given search results, emphasizing the one with the given
+    /// index.
+    pub fn set_search_results(&self, matches: Vec<SearchMatch>, current: Option<usize>) {
+        self.imp().set_search_results(matches, current)
+    }
+
+    /// The text selected with the pointer, if any.
+    pub fn selected_text(&self) -> Option<String> {
+        self.imp().selected_text()
+    }
+
+    /// Expose the given page label and text of the current page to assistive
+    /// technologies, e.g. screen readers.
+    pub fn set_accessible_page(&self, label: String, text: String) {
+        self.imp().set_accessible_page(label, text)
+    }
+
+    /// Expose the text of the focused search result or selection to
+    /// assistive technologies instead of the text of the current page, or
+    /// stop doing so if `None`.
+    pub fn set_accessible_focus(&self, focus: Option<String>) {
+        self.imp().set_accessible_focus(focus)
+    }
+
+    /// Label all visible links, for following them by typing their label.
+    /// Returns `false` if no links are visible.
+    pub fn show_link_hints(&self) -> bool {
+        self.imp().show_link_hints()
+    }
+
+    /// Show pages with inverted lightness, e.g. for reading at night.
+    pub fn set_dark_mode(&self, dark: bool) {
+        self.imp().set_dark_mode(dark)
+    }
+
+    /// Render pages with light text on a dark background, keeping the colors
+    /// of images.
+    pub fn set_night_colors(&self, enabled: bool) {
+        self.imp().set_night_colors(enabled)
+    }
+
+    /// How pages are arranged.
+    pub fn layout(&self) -> LayoutKind {
+        self.imp().layout()
+    }
+
+    /// Arrange pages in the given layout, keeping the current page in view.
+    pub fn set_layout(&self, kind: LayoutKind) {
+        self.imp().set_layout(kind)
+    }
+
+    /// Show only the given part of each page, in page coordinates and
+    /// indexed by page, e.g. to trim page margins. Pages without crop, or
+    /// all pages if `crops` is empty, are shown in full.
+    pub fn set_crops(&self, crops: &[Option<Rect<f64>>]) {
+        self.imp().set_crops(crops)
+    }
+
+    /// Check if any page is cropped.
+    pub fn is_cropped(&self) -> bool {
+        self.imp().is_cropped()
+    }
+
+    /// Rotation of all pages, in addition to their own rotation.
+    pub fn rotation(&self) -> PageRotation {
+        self.imp().rotation()
+    }
+
+    /// Rotate all pages, keeping the current page in view.
+    pub fn set_rotation(&self, rotation: PageRotation) {
+        self.imp().set_rotation(rotation)
+    }
+
+    /// Show render statistics on top of the canvas.
+    pub fn set_debug_overlay(&self, enabled: bool) {
+        self.imp().set_debug_overlay(enabled)
+    }
+
+    /// The current document, if any.
+    pub fn document(&self) -> Option<Document> {
+        self.imp().document()
+    }
+
+    /// The executor rendering the current document, if any, e.g. for running
+    /// other tasks on the document.
+    pub fn executor(&self) -> Option<Arc<Executor>> {
+        self.imp().executor()
+    }
+
+    /// Number of pages of the current document.
+    pub fn page_count(&self) -> usize {
+        self.imp().page_count()
+    }
+
+    /// Bounds of the given page in canvas coordinates.
+    pub fn page_rect(&self, page: usize) -> Option<Rect<f64>> {
+        self.imp().page_rect(page)
+    }
+
+    /// Transform a rectangle in coordinates of the given page to canvas
+    /// coordinates.
+    pub fn page_to_canvas(&self, page: usize, rect: &Rect<f64>) -> Option<Rect<f64>> {
+        self.imp().page_to_canvas(page, rect)
+    }
+
+    /// Size of the given page in page coordinates, i.e. of the unrotated
+    /// page, in PDF points.
+    pub fn page_size(&self, page: usize) -> Option<Vector2<f64>> {
+        self.imp().page_size(page)
+    }
+
+    /// The page closest to the given vertical position in canvas
+    /// coordinates.
+    pub fn page_at_y(&self, y: f64) -> Option<usize> {
+        self.imp().page_at_y(y)
+    }
+
+    /// Small preview of the given page, if it has been rendered already.
+    pub fn page_preview(&self, page: usize) -> Option<gdk::Texture> {
+        self.imp().page_preview(page)
+    }
+
+    /// Page closest to the viewport center and its visible part, in page
+    /// coordinates (PDF points).
+    pub fn viewport_page_rect(&self) -> Option<(usize, Rect<f64>)> {
+        self.imp().viewport_page_rect()
+    }
+
+    /// Range of pages that are at least partially visible.
+    pub fn visible_pages(&self) -> Range<usize> {
+        self.imp().visible_pages()
+    }
+
+    /// The full viewport in page coordinates (PDF points) of the given page,
+    /// i.e. not clipped to the page.
+    pub fn viewport_rect(&self, page: usize) -> Option<Rect<f64>> {
+        self.imp().viewport_rect(page)
+    }
+
+    /// Center the viewport on the given rectangle of a page, specified in
+    /// page coordinates (PDF points).
+    pub fn scroll_to_page_rect(&self, page: usize, rect: &Rect<f64>) {
+        self.imp().scroll_to_page_rect(page, rect)
+    }
+
+    /// Scroll the viewport so that the given point of a page, specified in
+    /// page coordinates (PDF points), is at its upper left corner. Without
+    /// horizontal position, the horizontal offset is kept.
+    pub fn scroll_to_page_point(&self, page: usize, x: Option<f64>, y: f64) {
+        self.imp().scroll_to_page_point(page, x, y)
+    }
+
+    /// Scroll the viewport to the top of the given page as shown, i.e.
+    /// regardless of how pages are rotated. The horizontal offset is kept if
+    /// the page is fully visible with it or covers the viewport width.
+    pub fn scroll_to_page(&self, page: usize) {
+        self.imp().scroll_to_page(page)
+    }
+
+    /// Scroll the viewport just far enough for the given rectangle of a page,
+    /// specified in page coordinates, to be visible.
+    pub fn reveal_page_rect(&self, page: usize, rect: &Rect<f64>) {
+        self.imp().reveal_page_rect(page, rect)
+    }
 }
 
 impl Default for CanvasWidget {
@@ -0,0 +1,121 @@
+use std::cell::{Cell, RefCell};
+use std::time::Duration;
+
+use gtk::glib::subclass::InitializingObject;
+use gtk::glib::{self, clone, ObjectExt};
+use gtk::subclass::prelude::{ObjectImpl, ObjectSubclass, ObjectSubclassExt, ObjectSubclassIsExt};
+use gtk::subclass::widget::{
+    CompositeTemplateClass, CompositeTemplateDisposeExt, CompositeTemplateInitializingExt,
+    WidgetClassSubclassExt, WidgetImpl,
+};
+use gtk::traits::{EventControllerExt, WidgetExt};
+use gtk::{CompositeTemplate, TemplateChild};
+
+use crate::core::watch::FileWatch;
+use crate::ui::canvas::CanvasWidget;
+use crate::ui::viewport::ViewportWidget;
+
+use super::TabState;
+
+#[derive(Default, CompositeTemplate)]
+#[template(resource = "/io/mxnluz/papr/ui/document.ui")]
+pub struct DocumentView {
+    #[template_child]
+    pub viewport: TemplateChild<ViewportWidget>,
+
+    #[template_child]
+    pub canvas: TemplateChild<CanvasWidget>,
+
+    pub state: RefCell<TabState>,
+
+    // watch for changes of the document file, and whether it has been changed
+    // while the view has not been selected
+    pub watch: RefCell<Option<FileWatch>>,
+    pub reload_pending: Cell<bool>,
+
+    // timeout for suspending rendering while the view is not shown
+    suspend_timer: RefCell<Option<glib::SourceId>>,
+
+    // signal handlers and event controllers of the hosting window
+    handlers: RefCell<Vec<(glib::Object, glib::SignalHandlerId)>>,
+    controllers: RefCell<Vec<gtk::EventController>>,
+}
+
+impl DocumentView {
+    pub fn set_window_handlers(
+        &self,
+        handlers: Vec<(glib::Object, glib::SignalHandlerId)>,
+        controllers: Vec<gtk::EventController>,
+    ) {
+        self.disconnect_window();
+
+        self.handlers.replace(handlers);
+        self.controllers.replace(controllers);
+    }
+
+    pub fn disconnect_window(&self) {
+        for (obj, handler) in self.handlers.take() {
+            obj.disconnect(handler);
+        }
+
+        for ctrl in self.controllers.take() {
+            ctrl.widget().remove_controller(&ctrl);
+        }
+    }
+
+    pub fn schedule_suspend(&self, delay: Duration) {
+        if self.suspend_timer.borrow().is_some() {
+            return;
+        }
+
+        let obj = self.obj();
+        let timer = glib::timeout_add_local_once(
+            delay,
+            clone!(@weak obj => move || {
+                obj.imp().suspend_timer.replace(None);
+
+                tracing::debug!("suspending rendering of hidden view");
+                obj.imp().canvas.suspend();
+            }),
+        );
+
+        self.suspend_timer.replace(Some(timer));
+    }
+
+    pub fn resume(&self) {
+        if let Some(timer) = self.suspend_timer.take() {
+            timer.remove();
+        }
+
+        self.canvas.resume();
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DocumentView {
+    const NAME: &'static str = "DocumentView";
+    type Type = super::DocumentView;
+    type ParentType = gtk::Widget;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+        klass.set_layout_manager_type::<gtk::BinLayout>();
+    }
+
+    fn instance_init(obj: &InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+impl ObjectImpl for DocumentView {
+    fn dispose(&self) {
+        if let Some(timer) = self.suspend_timer.take() {
+            timer.remove();
+        }
+
+        self.disconnect_window();
+        self.dispose_template();
+    }
+}
+
+impl WidgetImpl for DocumentView {}
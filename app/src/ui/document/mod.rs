@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+use gtk::glib;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use pdfium::doc::Bookmark;
+
+use crate::core::appearance::PageStyle;
+use crate::core::caret::{Caret, CaretText};
+use crate::core::citations::Location;
+use crate::core::columns::TextColumn;
+use crate::core::history::History;
+use crate::core::progress::ReadingProgress;
+use crate::core::render::disk::DiskCache;
+use crate::core::timeline::ViewingTimeline;
+use crate::core::watch::FileWatch;
+use crate::types::Rect;
+use crate::ui::canvas::CanvasWidget;
+use crate::ui::viewport::ViewportWidget;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct DocumentView(ObjectSubclass<imp::DocumentView>)
+        @extends gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+/// State of the window for the document shown in a view, kept with the view
+/// while another document is selected.
+#[derive(Default)]
+pub struct TabState {
+    pub path: Option<PathBuf>,
+    pub title: Option<String>,
+    pub labels: Vec<Option<String>>,
+    pub outline: Vec<Bookmark>,
+    pub text_column: Option<TextColumn>,
+    pub content_boxes: Option<Vec<Option<Rect<f64>>>>,
+    pub trim_margins: bool,
+    pub trusted: Option<bool>,
+    pub page_style: PageStyle,
+    pub blocks: HashMap<usize, Rc<Vec<Rect<f64>>>>,
+    pub block_cursor: Option<(usize, usize)>,
+    pub font_page: Option<usize>,
+    pub caret_texts: HashMap<usize, Rc<CaretText>>,
+    pub caret: Option<Caret>,
+    pub doc_hash: Option<u64>,
+    pub disk_cache: Option<DiskCache>,
+    pub reading: Option<ReadingProgress>,
+    pub timeline: Option<ViewingTimeline>,
+    pub history: History<Location>,
+
+    /// Message of the signature banner, if shown.
+    pub signatures: Option<(String, Option<String>)>,
+}
+
+impl DocumentView {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    pub fn viewport(&self) -> ViewportWidget {
+        self.imp().viewport.get()
+    }
+
+    pub fn canvas(&self) -> CanvasWidget {
+        self.imp().canvas.get()
+    }
+
+    /// Path of the document, as stored while it has not been selected.
+    pub fn path(&self) -> Option<PathBuf> {
+        self.imp().state.borrow().path.clone()
+    }
+
+    /// Label of the given page, as stored while the document has not been
+    /// selected.
+    pub fn page_label(&self, page: usize) -> Option<String> {
+        self.imp()
+            .state
+            .borrow()
+            .labels
+            .get(page)
+            .cloned()
+            .flatten()
+    }
+
+    /// Take the state stored while the document has not been selected.
+    pub fn take_state(&self) -> TabState {
+        self.imp().state.take()
+    }
+
+    /// Store the state of the document while it is not selected.
+    pub fn set_state(&self, state: TabState) {
+        self.imp().state.replace(state);
+    }
+
+    /// Watch the document file for changes, replacing the previous watch.
+    pub fn set_file_watch(&self, watch: Option<FileWatch>) {
+        self.imp().watch.replace(watch);
+    }
+
+    /// Remember that the document file has been changed while the view has
+    /// not been selected, so that it gets reloaded once selected.
+    pub fn set_reload_pending(&self, pending: bool) {
+        self.imp().reload_pending.set(pending);
+    }
+
+    /// Whether the document needs to be reloaded, resetting the flag.
+    pub fn take_reload_pending(&self) -> bool {
+        self.imp().reload_pending.take()
+    }
+
+    /// Remember signal handlers and event controllers of the window hosting
+    /// this view, connected to its viewport and canvas.
+    pub fn set_window_handlers(
+        &self,
+        handlers: Vec<(glib::Object, glib::SignalHandlerId)>,
+        controllers: Vec<gtk::EventController>,
+    ) {
+        self.imp().set_window_handlers(handlers, controllers)
+    }
+
+    /// Disconnect the signal handlers and remove the event controllers of
+    /// the window hosting this view, e.g. when moving it to another window.
+    pub fn disconnect_window(&self) {
+        self.imp().disconnect_window()
+    }
+
+    /// Suspend rendering of the canvas once the given time has passed
+    /// without being resumed, e.g. while the view is not shown. Keeps an
+    /// already scheduled suspend.
+    pub fn schedule_suspend(&self, delay: Duration) {
+        self.imp().schedule_suspend(delay)
+    }
+
+    /// Resume rendering of the canvas, canceling a scheduled suspend.
+    pub fn resume(&self) {
+        self.imp().resume()
+    }
+}
+
+impl Default for DocumentView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,257 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use adw::subclass::prelude::AdwWindowImpl;
+use gtk::glib::clone;
+use gtk::glib::subclass::InitializingObject;
+use gtk::subclass::prelude::{
+    CompositeTemplateClass, CompositeTemplateInitializingExt, ObjectImpl, ObjectImplExt,
+    ObjectSubclass, ObjectSubclassExt, ObjectSubclassIsExt, WidgetImpl, WindowImpl,
+};
+use gtk::subclass::widget::WidgetClassSubclassExt;
+use gtk::traits::{AdjustmentExt, ButtonExt, EventControllerExt, GestureDragExt, WidgetExt};
+use gtk::{gdk, glib, CompositeTemplate, Inhibit, TemplateChild};
+
+use pdfium::bitmap::Color;
+use pdfium::doc::{Document, PageRotation, RenderFlags};
+
+use crate::core::exec::TaskPriority;
+use crate::core::render::inspect;
+use crate::core::render::interop::Bitmap;
+use crate::core::render::pdfium::RenderOptions;
+use crate::types::Rect;
+use crate::ui::app::App;
+
+/// Zoom factor per zoom step.
+const ZOOM_STEP: f64 = 1.25;
+
+/// Zoom limits, in pixels per PDF point.
+const ZOOM_MIN: f64 = 0.5;
+const ZOOM_MAX: f64 = 16.0;
+
+/// Size of the area the region is initially fitted to, in pixels.
+const FIT_SIZE: (f64, f64) = (760.0, 520.0);
+
+#[derive(Debug, Default, CompositeTemplate)]
+#[template(resource = "/io/mxnluz/papr/ui/inspect.ui")]
+pub struct InspectWindow {
+    #[template_child]
+    window_title: TemplateChild<adw::WindowTitle>,
+
+    #[template_child]
+    spinner: TemplateChild<gtk::Spinner>,
+
+    #[template_child]
+    zoom_in_button: TemplateChild<gtk::Button>,
+
+    #[template_child]
+    zoom_out_button: TemplateChild<gtk::Button>,
+
+    #[template_child]
+    scroller: TemplateChild<gtk::ScrolledWindow>,
+
+    #[template_child]
+    picture: TemplateChild<gtk::Picture>,
+
+    page: Cell<usize>,
+    region: Cell<Option<Rect<f64>>>,
+
+    // display scale, in pixels per PDF point
+    zoom: Cell<f64>,
+
+    // aborts pending renders once the window is gone
+    canceled: Arc<AtomicBool>,
+}
+
+impl InspectWindow {
+    pub fn setup(&self, app: &App, doc: Document, page: usize, region: Rect<f64>) {
+        self.page.set(page);
+        self.region.set(Some(region));
+        self.window_title
+            .set_subtitle(&format!("Page {}", page + 1));
+
+        // initially fit the region into the window
+        let fit = f64::min(FIT_SIZE.0 / region.size.x, FIT_SIZE.1 / region.size.y);
+        self.set_zoom(fit);
+
+        self.render(app, doc, page, region);
+    }
+
+    /// Render the region progressively at increasing resolutions, showing
+    /// each pass once done.
+    fn render(&self, app: &App, doc: Document, page_index: usize, region: Rect<f64>) {
+        let obj = self.obj().downgrade();
+        let app = app.clone();
+        let canceled = self.canceled.clone();
+
+        glib::MainContext::default().spawn_local(async move {
+            let mut rendered = 0.0;
+
+            for dpi in inspect::PASSES {
+                // skip passes that would not increase the resolution, e.g.
+                // because the region is too large
+                let dpi = inspect::effective_dpi(&region, dpi);
+                if dpi <= rendered {
+                    continue;
+                }
+
+                let doc = doc.clone();
+                let canceled = canceled.clone();
+
+                let task = app.spawn_blocking_with_priority(TaskPriority::Normal, move || {
+                    let page = match doc.pages().get(page_index as _) {
+                        Ok(page) => page,
+                        Err(err) => {
+                            tracing::warn!(page = page_index, error=%err, "failed to load page");
+                            return None;
+                        }
+                    };
+
+                    let opts = RenderOptions {
+                        flags: RenderFlags::Annotations,
+                        background: Color::WHITE,
+                        color_scheme: None,
+                        rotation: PageRotation::None,
+                    };
+
+                    inspect::render_region(&page, &region, dpi, &opts, &canceled)
+                });
+
+                let bmp = task.await;
+
+                let obj = match obj.upgrade() {
+                    Some(obj) => obj,
+                    None => return,
+                };
+
+                match bmp {
+                    Some(bmp) => obj.imp().set_image(bmp, dpi),
+                    None => break,
+                }
+
+                rendered = dpi;
+            }
+
+            if let Some(obj) = obj.upgrade() {
+                obj.imp().spinner.set_spinning(false);
+                obj.imp().spinner.set_visible(false);
+            }
+        });
+    }
+
+    fn set_image(&self, bmp: Bitmap, dpi: f64) {
+        let bytes = glib::Bytes::from_owned(bmp.buffer);
+
+        let texture = gdk::MemoryTexture::new(
+            bmp.size.x as _,
+            bmp.size.y as _,
+            gdk::MemoryFormat::B8g8r8,
+            &bytes,
+            bmp.stride as _,
+        );
+
+        self.picture.set_paintable(Some(&texture));
+
+        let subtitle = format!("Page {} at {:.0} DPI", self.page.get() + 1, dpi);
+        self.window_title.set_subtitle(&subtitle);
+    }
+
+    fn set_zoom(&self, zoom: f64) {
+        let region = match self.region.get() {
+            Some(region) => region,
+            None => return,
+        };
+
+        let zoom = zoom.clamp(ZOOM_MIN, ZOOM_MAX);
+        self.zoom.set(zoom);
+
+        let size = region.size * zoom;
+        self.picture
+            .set_size_request(size.x.round() as _, size.y.round() as _);
+
+        self.zoom_in_button.set_sensitive(zoom < ZOOM_MAX);
+        self.zoom_out_button.set_sensitive(zoom > ZOOM_MIN);
+    }
+
+    fn zoom_by(&self, factor: f64) {
+        self.set_zoom(self.zoom.get() * factor);
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for InspectWindow {
+    const NAME: &'static str = "InspectWindow";
+    type Type = super::InspectWindow;
+    type ParentType = adw::Window;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+    }
+
+    fn instance_init(obj: &InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+impl ObjectImpl for InspectWindow {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        let obj = self.obj();
+
+        self.zoom_in_button
+            .connect_clicked(clone!(@weak obj => move |_| {
+                obj.imp().zoom_by(ZOOM_STEP);
+            }));
+
+        self.zoom_out_button
+            .connect_clicked(clone!(@weak obj => move |_| {
+                obj.imp().zoom_by(1.0 / ZOOM_STEP);
+            }));
+
+        // zoom with ctrl + scroll-wheel
+        let ctrl = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+
+        ctrl.connect_scroll(
+            clone!(@weak obj => @default-return Inhibit(false), move |ctrl, _, dy| {
+                if !ctrl.current_event_state().contains(gdk::ModifierType::CONTROL_MASK) {
+                    return Inhibit(false);
+                }
+
+                obj.imp().zoom_by(ZOOM_STEP.powf(-dy));
+                Inhibit(true)
+            }),
+        );
+
+        self.scroller.add_controller(ctrl);
+
+        // pan with drag
+        let ctrl = gtk::GestureDrag::new();
+        let drag_start = Rc::new(Cell::new((0.0, 0.0)));
+
+        ctrl.connect_drag_begin(clone!(@strong drag_start, @weak obj => move |_, _, _| {
+            let scroller = &obj.imp().scroller;
+            drag_start.set((scroller.hadjustment().value(), scroller.vadjustment().value()));
+        }));
+
+        ctrl.connect_drag_update(clone!(@strong drag_start, @weak obj => move |_, dx, dy| {
+            let scroller = &obj.imp().scroller;
+
+            let (x, y) = drag_start.get();
+            scroller.hadjustment().set_value(x - dx);
+            scroller.vadjustment().set_value(y - dy);
+        }));
+
+        self.scroller.add_controller(ctrl);
+    }
+
+    fn dispose(&self) {
+        self.canceled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl WidgetImpl for InspectWindow {}
+impl WindowImpl for InspectWindow {}
+impl AdwWindowImpl for InspectWindow {}
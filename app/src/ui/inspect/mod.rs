@@ -0,0 +1,35 @@
+use gtk::glib;
+use gtk::prelude::IsA;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::traits::GtkWindowExt;
+
+use pdfium::doc::Document;
+
+use crate::types::Rect;
+use crate::ui::app::App;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct InspectWindow(ObjectSubclass<imp::InspectWindow>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native,
+                gtk::Root, gtk::ShortcutManager;
+}
+
+impl InspectWindow {
+    /// Create a window showing the given region of a page, in page
+    /// coordinates (PDF points), rendered at high resolution.
+    pub fn new(
+        parent: &impl IsA<gtk::Window>,
+        app: &App,
+        doc: Document,
+        page: usize,
+        region: Rect<f64>,
+    ) -> Self {
+        let window: Self = glib::Object::new();
+        window.set_transient_for(Some(parent));
+        window.imp().setup(app, doc, page, region);
+        window
+    }
+}
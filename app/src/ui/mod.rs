@@ -1,4 +1,15 @@
+pub mod actions;
 pub mod app;
 pub mod appwindow;
+pub mod calibration;
 pub mod canvas;
+pub mod document;
+pub mod inspect;
+pub mod outline;
+pub mod palette;
+pub mod preferences;
+pub mod properties;
+pub mod scrollmarks;
+pub mod statistics;
+pub mod thumbnails;
 pub mod viewport;
@@ -0,0 +1,164 @@
+use gtk::gio::ListStore;
+use gtk::glib::subclass::InitializingObject;
+use gtk::glib::{self, clone, once_cell::sync::Lazy, subclass::Signal, BoxedAnyObject};
+use gtk::prelude::{Cast, CastNone, ListModelExt, ObjectExt, StaticType};
+use gtk::subclass::prelude::{
+    ObjectImpl, ObjectImplExt, ObjectSubclass, ObjectSubclassExt, ObjectSubclassIsExt,
+};
+use gtk::subclass::widget::{
+    CompositeTemplateClass, CompositeTemplateDisposeExt, CompositeTemplateInitializingExt,
+    WidgetClassSubclassExt, WidgetImpl,
+};
+use gtk::traits::{BoxExt, WidgetExt};
+use gtk::{pango, CompositeTemplate, TemplateChild};
+
+use pdfium::doc::Bookmark;
+
+#[derive(Debug, Default, CompositeTemplate)]
+#[template(resource = "/io/mxnluz/papr/ui/outline.ui")]
+pub struct OutlineView {
+    #[template_child]
+    stack: TemplateChild<gtk::Stack>,
+
+    #[template_child]
+    list: TemplateChild<gtk::ListView>,
+}
+
+impl OutlineView {
+    pub fn set_outline(&self, outline: &[Bookmark]) {
+        let model = gtk::TreeListModel::new(entries(outline), false, false, |item| {
+            let item = item.downcast_ref::<BoxedAnyObject>()?;
+            let entry = item.borrow::<Bookmark>();
+
+            (!entry.children.is_empty()).then(|| entries(&entry.children).upcast())
+        });
+
+        let page = if outline.is_empty() {
+            "empty"
+        } else {
+            "outline"
+        };
+
+        self.list
+            .set_model(Some(&gtk::NoSelection::new(Some(model))));
+        self.stack.set_visible_child_name(page);
+    }
+
+    fn activate(&self, position: u32) {
+        let row = self
+            .list
+            .model()
+            .and_then(|model| model.item(position))
+            .and_then(|item| item.downcast::<gtk::TreeListRow>().ok())
+            .and_then(|row| row.item());
+
+        if let Some(item) = row {
+            self.obj().emit_by_name::<()>("entry-activated", &[&item]);
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for OutlineView {
+    const NAME: &'static str = "OutlineView";
+    type Type = super::OutlineView;
+    type ParentType = gtk::Widget;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+        klass.set_layout_manager_type::<gtk::BinLayout>();
+    }
+
+    fn instance_init(obj: &InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+impl ObjectImpl for OutlineView {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        let obj = self.obj();
+
+        // rows consist of an expander for nested entries, the title, and the
+        // page number
+        let factory = gtk::SignalListItemFactory::new();
+
+        factory.connect_setup(|_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+
+            let title = gtk::Label::builder()
+                .xalign(0.0)
+                .hexpand(true)
+                .ellipsize(pango::EllipsizeMode::End)
+                .build();
+
+            let page = gtk::Label::new(None);
+            page.add_css_class("dim-label");
+            page.add_css_class("numeric");
+
+            let content = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+            content.append(&title);
+            content.append(&page);
+
+            let expander = gtk::TreeExpander::new();
+            expander.set_child(Some(&content));
+
+            item.set_child(Some(&expander));
+        });
+
+        factory.connect_bind(|_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+
+            let expander = item.child().and_downcast::<gtk::TreeExpander>().unwrap();
+            let row = item.item().and_downcast::<gtk::TreeListRow>().unwrap();
+            let entry = row.item().and_downcast::<BoxedAnyObject>().unwrap();
+
+            let content = expander.child().unwrap();
+            let title = content.first_child().and_downcast::<gtk::Label>().unwrap();
+            let page = content.last_child().and_downcast::<gtk::Label>().unwrap();
+
+            let entry = entry.borrow::<Bookmark>();
+            let number = entry.dest.map(|d| d.page + 1);
+            let number = number.map(|n| n.to_string()).unwrap_or_default();
+
+            title.set_text(&entry.title);
+            title.set_tooltip_text(Some(&entry.title));
+            page.set_text(&number);
+            expander.set_list_row(Some(&row));
+        });
+
+        self.list.set_factory(Some(&factory));
+
+        self.list
+            .connect_activate(clone!(@weak obj => move |_, position| {
+                obj.imp().activate(position);
+            }));
+    }
+
+    fn dispose(&self) {
+        self.dispose_template();
+    }
+
+    fn signals() -> &'static [Signal] {
+        static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+            vec![Signal::builder("entry-activated")
+                .param_types([BoxedAnyObject::static_type()])
+                .build()]
+        });
+        SIGNALS.as_ref()
+    }
+}
+
+impl WidgetImpl for OutlineView {}
+
+/// List model of the given outline entries, without their children.
+fn entries(outline: &[Bookmark]) -> ListStore {
+    let store = ListStore::new(BoxedAnyObject::static_type());
+
+    for entry in outline {
+        store.append(&BoxedAnyObject::new(entry.clone()));
+    }
+
+    store
+}
@@ -0,0 +1,45 @@
+use gtk::glib::{self, BoxedAnyObject};
+use gtk::prelude::ObjectExt;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+
+use pdfium::doc::Bookmark;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct OutlineView(ObjectSubclass<imp::OutlineView>)
+        @extends gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl OutlineView {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    /// Show the given outline, or a placeholder if it is empty.
+    pub fn set_outline(&self, outline: &[Bookmark]) {
+        self.imp().set_outline(outline)
+    }
+
+    /// Connect to the signal emitted when the user activates an entry of the
+    /// outline.
+    pub fn connect_entry_activated<F: Fn(&Self, &Bookmark) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "entry-activated",
+            false,
+            glib::closure_local!(move |view: &Self, entry: BoxedAnyObject| {
+                f(view, &entry.borrow::<Bookmark>())
+            }),
+        )
+    }
+}
+
+impl Default for OutlineView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
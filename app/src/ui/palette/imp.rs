@@ -0,0 +1,204 @@
+use std::cell::RefCell;
+
+use adw::subclass::prelude::AdwWindowImpl;
+use gtk::glib::clone;
+use gtk::glib::subclass::InitializingObject;
+use gtk::prelude::{ActionExt, ActionMapExt, EditableExt};
+use gtk::subclass::prelude::{
+    CompositeTemplateClass, CompositeTemplateInitializingExt, ObjectImpl, ObjectImplExt,
+    ObjectSubclass, ObjectSubclassExt, ObjectSubclassIsExt, WidgetImpl, WindowImpl,
+};
+use gtk::subclass::widget::WidgetClassSubclassExt;
+use gtk::traits::{BoxExt, GtkWindowExt, ListBoxRowExt, WidgetExt};
+use gtk::{gio, glib, CompositeTemplate, TemplateChild};
+
+use crate::core::fuzzy;
+use crate::ui::actions::{self, ActionInfo};
+
+#[derive(Debug, Default, CompositeTemplate)]
+#[template(resource = "/io/mxnluz/papr/ui/palette.ui")]
+pub struct CommandPalette {
+    #[template_child]
+    entry: TemplateChild<gtk::SearchEntry>,
+
+    #[template_child]
+    list: TemplateChild<gtk::ListBox>,
+
+    // window providing the actions
+    window: glib::WeakRef<gtk::ApplicationWindow>,
+
+    // actions currently shown, in order of the list rows
+    matches: RefCell<Vec<&'static ActionInfo>>,
+}
+
+impl CommandPalette {
+    pub fn setup(&self, window: &gtk::ApplicationWindow) {
+        self.window.set(Some(window));
+        self.update();
+    }
+
+    fn is_enabled(&self, action: &ActionInfo) -> bool {
+        let window = match self.window.upgrade() {
+            Some(window) => window,
+            None => return false,
+        };
+
+        let (name, _) = match gio::Action::parse_detailed_name(action.name) {
+            Ok(parsed) => parsed,
+            Err(_) => return false,
+        };
+
+        // we only know how to look up window actions
+        let name = match name.strip_prefix("win.") {
+            Some(name) => name,
+            None => return true,
+        };
+
+        window
+            .lookup_action(name)
+            .map(|a| a.is_enabled())
+            .unwrap_or(false)
+    }
+
+    fn update(&self) {
+        let pattern = self.entry.text();
+
+        // find matching actions, best matches first
+        let mut matches: Vec<_> = actions::all()
+            .filter(|a| a.name != actions::COMMAND_PALETTE)
+            .filter(|a| self.is_enabled(a))
+            .filter_map(|a| fuzzy::score(&pattern, a.label).map(|score| (score, a)))
+            .collect();
+
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        // rebuild list
+        while let Some(row) = self.list.row_at_index(0) {
+            self.list.remove(&row);
+        }
+
+        for (_, action) in matches.iter() {
+            self.list.append(&action_row(action));
+        }
+
+        self.list.select_row(self.list.row_at_index(0).as_ref());
+        self.matches
+            .replace(matches.into_iter().map(|(_, a)| a).collect());
+    }
+
+    fn activate(&self, index: usize) {
+        let action = match self.matches.borrow().get(index) {
+            Some(action) => *action,
+            None => return,
+        };
+
+        // close first so that dialogs opened by the action are not stacked on
+        // top of the palette
+        self.obj().close();
+
+        if let Some(window) = self.window.upgrade() {
+            tracing::debug!(
+                action = action.name,
+                "activating action from command palette"
+            );
+
+            // actions with a target, e.g. `win.layout::grid`, are given as
+            // detailed name
+            let (name, target) = match gio::Action::parse_detailed_name(action.name) {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    tracing::warn!(action = action.name, error=%err, "invalid action name");
+                    return;
+                }
+            };
+
+            if let Err(err) = window.activate_action(&name, target.as_ref()) {
+                tracing::warn!(action = action.name, error=%err, "failed to activate action");
+            }
+        }
+    }
+
+    fn activate_selected(&self) {
+        let index = self.list.selected_row().map(|row| row.index()).unwrap_or(0);
+
+        self.activate(index.max(0) as _);
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for CommandPalette {
+    const NAME: &'static str = "CommandPalette";
+    type Type = super::CommandPalette;
+    type ParentType = adw::Window;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+    }
+
+    fn instance_init(obj: &InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+impl ObjectImpl for CommandPalette {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        let obj = self.obj();
+
+        self.entry
+            .connect_search_changed(clone!(@weak obj => move |_| {
+                obj.imp().update();
+            }));
+
+        self.entry.connect_activate(clone!(@weak obj => move |_| {
+            obj.imp().activate_selected();
+        }));
+
+        self.entry
+            .connect_stop_search(clone!(@weak obj => move |_| {
+                obj.close();
+            }));
+
+        self.list
+            .connect_row_activated(clone!(@weak obj => move |_, row| {
+                obj.imp().activate(row.index().max(0) as _);
+            }));
+
+        // allow moving through the list while typing
+        self.entry.set_key_capture_widget(Some(&*obj));
+        self.entry.grab_focus();
+    }
+}
+
+impl WidgetImpl for CommandPalette {}
+impl WindowImpl for CommandPalette {}
+impl AdwWindowImpl for CommandPalette {}
+
+fn action_row(action: &ActionInfo) -> gtk::ListBoxRow {
+    let content = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+    content.set_margin_start(6);
+    content.set_margin_end(6);
+
+    let label = gtk::Label::new(Some(action.label));
+    label.set_hexpand(true);
+    label.set_xalign(0.0);
+    content.append(&label);
+
+    // show the primary shortcut, if any
+    let accel = action
+        .accels
+        .first()
+        .and_then(|accel| gtk::accelerator_parse(*accel))
+        .map(|(key, mods)| gtk::accelerator_get_label(key, mods));
+
+    if let Some(accel) = accel {
+        let label = gtk::Label::new(Some(&accel));
+        label.add_css_class("dim-label");
+        content.append(&label);
+    }
+
+    let row = gtk::ListBoxRow::new();
+    row.set_child(Some(&content));
+    row
+}
@@ -0,0 +1,25 @@
+use gtk::glib;
+use gtk::prelude::IsA;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::traits::GtkWindowExt;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct CommandPalette(ObjectSubclass<imp::CommandPalette>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native,
+                gtk::Root, gtk::ShortcutManager;
+}
+
+impl CommandPalette {
+    /// Create a new command palette for the actions of the given window.
+    pub fn new(parent: &impl IsA<gtk::ApplicationWindow>) -> Self {
+        let parent = parent.as_ref();
+
+        let palette: Self = glib::Object::new();
+        palette.set_transient_for(Some(parent));
+        palette.imp().setup(parent);
+        palette
+    }
+}
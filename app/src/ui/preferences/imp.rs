@@ -0,0 +1,265 @@
+use std::cell::RefCell;
+use std::time::Duration;
+
+use adw::prelude::{ActionRowExt, ComboRowExt, PreferencesGroupExt, PreferencesRowExt};
+use adw::subclass::prelude::{AdwWindowImpl, PreferencesWindowImpl};
+use gtk::glib::clone;
+use gtk::glib::once_cell::sync::Lazy;
+use gtk::glib::subclass::{InitializingObject, Signal};
+use gtk::prelude::ObjectExt;
+use gtk::subclass::prelude::{
+    CompositeTemplateClass, CompositeTemplateInitializingExt, ObjectImpl, ObjectImplExt,
+    ObjectSubclass, ObjectSubclassExt, ObjectSubclassIsExt, WidgetImpl, WindowImpl,
+};
+use gtk::subclass::widget::WidgetClassSubclassExt;
+use gtk::traits::{ButtonExt, WidgetExt};
+use gtk::{glib, CompositeTemplate, TemplateChild};
+
+use crate::core::render::core::TilingSchemeKind;
+use crate::core::session::{LayoutSettings, SessionStore, TilingSettings};
+use crate::core::storage::{Area, Storage};
+
+#[derive(Debug, Default, CompositeTemplate)]
+#[template(resource = "/io/mxnluz/papr/ui/preferences.ui")]
+pub struct PreferencesWindow {
+    #[template_child]
+    page_spacing_spin: TemplateChild<gtk::SpinButton>,
+
+    #[template_child]
+    margin_x_spin: TemplateChild<gtk::SpinButton>,
+
+    #[template_child]
+    margin_y_spin: TemplateChild<gtk::SpinButton>,
+
+    #[template_child]
+    advance_interval_spin: TemplateChild<gtk::SpinButton>,
+
+    #[template_child]
+    tiling_scheme_row: TemplateChild<adw::ComboRow>,
+
+    #[template_child]
+    tile_size_row: TemplateChild<adw::ComboRow>,
+
+    #[template_child]
+    disk_cache_switch: TemplateChild<gtk::Switch>,
+
+    #[template_child]
+    gl_upload_switch: TemplateChild<gtk::Switch>,
+
+    #[template_child]
+    storage_group: TemplateChild<adw::PreferencesGroup>,
+
+    #[template_child]
+    clear_caches_button: TemplateChild<gtk::Button>,
+
+    storage: RefCell<Option<Storage>>,
+    session: RefCell<Option<SessionStore>>,
+
+    // labels showing the disk usage of each storage area
+    usage_labels: RefCell<Vec<(Area, gtk::Label)>>,
+}
+
+impl PreferencesWindow {
+    pub fn setup(&self, storage: Storage, session: SessionStore) {
+        let layout = session.layout();
+        self.page_spacing_spin.set_value(layout.page_spacing);
+        self.margin_x_spin.set_value(layout.margin_x);
+        self.margin_y_spin.set_value(layout.margin_y);
+
+        let interval = session.presentation_interval().unwrap_or_default();
+        self.advance_interval_spin.set_value(interval.as_secs_f64());
+
+        let schemes = TilingSchemeKind::ALL.map(|kind| kind.label());
+        let sizes = TilingSettings::TILE_SIZES.map(|size| format!("{size} px"));
+        let sizes: Vec<&str> = sizes.iter().map(String::as_str).collect();
+
+        let tiling = session.tiling();
+        let scheme = TilingSchemeKind::ALL
+            .iter()
+            .position(|kind| *kind == tiling.scheme);
+        let size = TilingSettings::TILE_SIZES
+            .iter()
+            .position(|size| *size == tiling.tile_size);
+
+        self.tiling_scheme_row
+            .set_model(Some(&gtk::StringList::new(&schemes)));
+        self.tiling_scheme_row
+            .set_selected(scheme.unwrap_or(0) as u32);
+
+        self.tile_size_row
+            .set_model(Some(&gtk::StringList::new(&sizes)));
+        self.tile_size_row.set_selected(size.unwrap_or(0) as u32);
+        self.disk_cache_switch.set_active(tiling.disk_cache);
+        self.gl_upload_switch.set_active(tiling.gl_upload);
+
+        self.session.replace(Some(session));
+
+        // connect only after setting the stored values
+        let obj = self.obj();
+        for spin in [
+            &*self.page_spacing_spin,
+            &*self.margin_x_spin,
+            &*self.margin_y_spin,
+        ] {
+            spin.connect_value_changed(clone!(@weak obj => move |_| {
+                obj.imp().update_layout();
+            }));
+        }
+
+        self.advance_interval_spin
+            .connect_value_changed(clone!(@weak obj => move |_| {
+                obj.imp().update_presentation();
+            }));
+
+        for row in [&*self.tiling_scheme_row, &*self.tile_size_row] {
+            row.connect_selected_notify(clone!(@weak obj => move |_| {
+                obj.imp().update_tiling();
+            }));
+        }
+
+        for switch in [&*self.disk_cache_switch, &*self.gl_upload_switch] {
+            switch.connect_active_notify(clone!(@weak obj => move |_| {
+                obj.imp().update_tiling();
+            }));
+        }
+
+        for area in Area::ALL {
+            let usage = gtk::Label::new(None);
+            usage.add_css_class("dim-label");
+
+            let row = adw::ActionRow::new();
+            row.set_title(area.label());
+            row.set_subtitle(&storage.dir(area).to_string_lossy());
+            row.add_suffix(&usage);
+
+            self.storage_group.add(&row);
+            self.usage_labels.borrow_mut().push((area, usage));
+        }
+
+        self.storage.replace(Some(storage));
+        self.update_usage();
+    }
+
+    fn update_layout(&self) {
+        let layout = LayoutSettings {
+            page_spacing: self.page_spacing_spin.value(),
+            margin_x: self.margin_x_spin.value(),
+            margin_y: self.margin_y_spin.value(),
+        };
+
+        if let Some(session) = self.session.borrow().as_ref() {
+            session.set_layout(&layout);
+
+            if let Err(err) = session.save() {
+                tracing::warn!(error=%err, "failed to save session state");
+            }
+        }
+
+        self.obj().emit_by_name::<()>("layout-changed", &[]);
+    }
+
+    fn update_presentation(&self) {
+        let secs = self.advance_interval_spin.value();
+        let interval = (secs > 0.0).then(|| Duration::from_secs_f64(secs));
+
+        if let Some(session) = self.session.borrow().as_ref() {
+            session.set_presentation_interval(interval);
+
+            if let Err(err) = session.save() {
+                tracing::warn!(error=%err, "failed to save session state");
+            }
+        }
+    }
+
+    fn update_tiling(&self) {
+        let scheme = TilingSchemeKind::ALL.get(self.tiling_scheme_row.selected() as usize);
+        let size = TilingSettings::TILE_SIZES.get(self.tile_size_row.selected() as usize);
+
+        let tiling = TilingSettings {
+            scheme: scheme.copied().unwrap_or_default(),
+            tile_size: size.copied().unwrap_or(TilingSettings::default().tile_size),
+            disk_cache: self.disk_cache_switch.is_active(),
+            gl_upload: self.gl_upload_switch.is_active(),
+        };
+
+        if let Some(session) = self.session.borrow().as_ref() {
+            session.set_tiling(&tiling);
+
+            if let Err(err) = session.save() {
+                tracing::warn!(error=%err, "failed to save session state");
+            }
+        }
+
+        self.obj().emit_by_name::<()>("tiling-changed", &[]);
+    }
+
+    fn update_usage(&self) {
+        let storage = self.storage.borrow();
+        let storage = match storage.as_ref() {
+            Some(storage) => storage,
+            None => return,
+        };
+
+        for (area, label) in self.usage_labels.borrow().iter() {
+            let usage = glib::format_size(storage.usage(*area));
+
+            match area.limit() {
+                Some(limit) => label.set_label(&format!("{usage} of {}", glib::format_size(limit))),
+                None => label.set_label(&usage),
+            }
+        }
+    }
+
+    fn clear_caches(&self) {
+        if let Some(storage) = self.storage.borrow().as_ref() {
+            if let Err(err) = storage.clear_caches() {
+                tracing::warn!(error=%err, "failed to clear caches");
+            }
+        }
+
+        self.update_usage();
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for PreferencesWindow {
+    const NAME: &'static str = "PreferencesWindow";
+    type Type = super::PreferencesWindow;
+    type ParentType = adw::PreferencesWindow;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+    }
+
+    fn instance_init(obj: &InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+impl ObjectImpl for PreferencesWindow {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        let obj = self.obj();
+
+        self.clear_caches_button
+            .connect_clicked(clone!(@weak obj => move |_| {
+                obj.imp().clear_caches();
+            }));
+    }
+
+    fn signals() -> &'static [Signal] {
+        static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+            vec![
+                Signal::builder("layout-changed").build(),
+                Signal::builder("tiling-changed").build(),
+            ]
+        });
+        SIGNALS.as_ref()
+    }
+}
+
+impl WidgetImpl for PreferencesWindow {}
+impl WindowImpl for PreferencesWindow {}
+impl AdwWindowImpl for PreferencesWindow {}
+impl PreferencesWindowImpl for PreferencesWindow {}
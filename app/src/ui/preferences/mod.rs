@@ -0,0 +1,45 @@
+use gtk::glib;
+use gtk::prelude::{IsA, ObjectExt};
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::traits::GtkWindowExt;
+
+use crate::core::session::SessionStore;
+use crate::core::storage::Storage;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct PreferencesWindow(ObjectSubclass<imp::PreferencesWindow>)
+        @extends gtk::Widget, gtk::Window, adw::Window, adw::PreferencesWindow,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native,
+                gtk::Root, gtk::ShortcutManager;
+}
+
+impl PreferencesWindow {
+    pub fn new(parent: &impl IsA<gtk::Window>, storage: Storage, session: SessionStore) -> Self {
+        let window: Self = glib::Object::new();
+        window.set_transient_for(Some(parent));
+        window.imp().setup(storage, session);
+        window
+    }
+
+    /// Connect to changes of the page layout settings, which have already
+    /// been stored in the session when this is called.
+    pub fn connect_layout_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "layout-changed",
+            false,
+            glib::closure_local!(move |window: &Self| f(window)),
+        )
+    }
+
+    /// Connect to changes of the tiling settings, which have already been
+    /// stored in the session when this is called.
+    pub fn connect_tiling_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "tiling-changed",
+            false,
+            glib::closure_local!(move |window: &Self| f(window)),
+        )
+    }
+}
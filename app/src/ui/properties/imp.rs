@@ -0,0 +1,173 @@
+use std::path::Path;
+
+use adw::prelude::{ActionRowExt, PreferencesGroupExt, PreferencesRowExt};
+use adw::subclass::prelude::AdwWindowImpl;
+use gtk::glib::subclass::InitializingObject;
+use gtk::subclass::prelude::{
+    CompositeTemplateClass, CompositeTemplateInitializingExt, ObjectImpl, ObjectSubclass,
+    WidgetImpl, WindowImpl,
+};
+use gtk::subclass::widget::WidgetClassSubclassExt;
+use gtk::traits::WidgetExt;
+use gtk::{glib, CompositeTemplate, TemplateChild};
+
+use pdfium::doc::{Document, MetadataTag, Permissions};
+
+#[derive(Debug, Default, CompositeTemplate)]
+#[template(resource = "/io/mxnluz/papr/ui/properties.ui")]
+pub struct PropertiesDialog {
+    #[template_child]
+    document_group: TemplateChild<adw::PreferencesGroup>,
+
+    #[template_child]
+    security_group: TemplateChild<adw::PreferencesGroup>,
+}
+
+impl PropertiesDialog {
+    pub fn set_document(&self, doc: &Document, path: Option<&Path>) {
+        if let Some(name) = path.and_then(|p| p.file_name()) {
+            add_row(&self.document_group, "File", &name.to_string_lossy());
+        }
+
+        let entries = doc.metadata().entries().unwrap_or_else(|err| {
+            tracing::warn!(error=%err, "failed to load document metadata");
+            Vec::new()
+        });
+
+        for (tag, value) in entries {
+            let value = match tag {
+                MetadataTag::CreationDate | MetadataTag::ModDate => format_date(&value),
+                _ => value,
+            };
+
+            add_row(&self.document_group, tag_label(tag), &value);
+        }
+
+        let version = doc.version().to_string();
+        let pages = doc.pages().count().to_string();
+
+        add_row(&self.document_group, "PDF Version", &version);
+        add_row(&self.document_group, "Pages", &pages);
+
+        let security = doc.security();
+
+        let encryption = match security.revision {
+            Some(revision) => format!("Yes, revision {revision}"),
+            None => "No".into(),
+        };
+
+        add_row(&self.security_group, "Encrypted", &encryption);
+
+        let permissions = [
+            ("Printing", Permissions::Print),
+            ("High-Quality Printing", Permissions::PrintHighQuality),
+            ("Copying Content", Permissions::Copy),
+            ("Modifying Content", Permissions::Modify),
+            ("Adding Annotations", Permissions::Annotate),
+            ("Filling in Forms", Permissions::FillForms),
+            ("Assembling Pages", Permissions::Assemble),
+        ];
+
+        for (title, permission) in permissions {
+            let value = if security.permissions.contains(permission) {
+                "Allowed"
+            } else {
+                "Not allowed"
+            };
+
+            add_row(&self.security_group, title, value);
+        }
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for PropertiesDialog {
+    const NAME: &'static str = "PropertiesDialog";
+    type Type = super::PropertiesDialog;
+    type ParentType = adw::Window;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+    }
+
+    fn instance_init(obj: &InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+impl ObjectImpl for PropertiesDialog {}
+impl WidgetImpl for PropertiesDialog {}
+impl WindowImpl for PropertiesDialog {}
+impl AdwWindowImpl for PropertiesDialog {}
+
+fn add_row(group: &adw::PreferencesGroup, title: &str, value: &str) {
+    let label = gtk::Label::new(Some(value));
+    label.set_selectable(true);
+    label.set_wrap(true);
+    label.set_xalign(1.0);
+    label.add_css_class("dim-label");
+
+    let row = adw::ActionRow::new();
+    row.set_title(title);
+    row.add_suffix(&label);
+
+    group.add(&row);
+}
+
+fn tag_label(tag: MetadataTag) -> &'static str {
+    match tag {
+        MetadataTag::Title => "Title",
+        MetadataTag::Author => "Author",
+        MetadataTag::Subject => "Subject",
+        MetadataTag::Keywords => "Keywords",
+        MetadataTag::Creator => "Creator",
+        MetadataTag::Producer => "Producer",
+        MetadataTag::CreationDate => "Created",
+        MetadataTag::ModDate => "Modified",
+        MetadataTag::Trapped => "Trapped",
+    }
+}
+
+/// Format a PDF date (PDF 32000-1, section 7.9.4), e.g.
+/// `D:20230412093000+02'00'`, as `2023-04-12 09:30:00 +02:00`. Values not
+/// following the format are returned as they are.
+fn format_date(value: &str) -> String {
+    let date = value.strip_prefix("D:").unwrap_or(value);
+
+    let digits = date.bytes().take_while(u8::is_ascii_digit).count();
+    if !(4..=14).contains(&digits) {
+        return value.to_owned();
+    }
+
+    let (digits, zone) = date.split_at(digits);
+
+    // year, followed by month, day, hour, minute, and second where present
+    let mut out = digits[..4].to_owned();
+    for (i, sep) in ["-", "-", " ", ":", ":"].iter().enumerate() {
+        match digits.get(4 + 2 * i..6 + 2 * i) {
+            Some(part) => {
+                out.push_str(sep);
+                out.push_str(part);
+            }
+            None => break,
+        }
+    }
+
+    let zone = zone.replace('\'', "");
+    match zone.as_str() {
+        "" => {}
+        _ if zone.starts_with('Z') => out.push_str(" UTC"),
+        _ if zone.len() == 5 && zone.is_ascii() => {
+            out.push(' ');
+            out.push_str(&zone[..3]);
+            out.push(':');
+            out.push_str(&zone[3..]);
+        }
+        _ => {
+            out.push(' ');
+            out.push_str(&zone);
+        }
+    }
+
+    out
+}
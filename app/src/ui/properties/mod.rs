@@ -0,0 +1,30 @@
+use std::path::Path;
+
+use gtk::glib;
+use gtk::prelude::IsA;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::traits::GtkWindowExt;
+
+use pdfium::doc::Document;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct PropertiesDialog(ObjectSubclass<imp::PropertiesDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native,
+                gtk::Root, gtk::ShortcutManager;
+}
+
+impl PropertiesDialog {
+    pub fn new(parent: &impl IsA<gtk::Window>) -> Self {
+        let dialog: Self = glib::Object::new();
+        dialog.set_transient_for(Some(parent));
+        dialog
+    }
+
+    /// Show the properties of the given document, loaded from the given file.
+    pub fn set_document(&self, doc: &Document, path: Option<&Path>) {
+        self.imp().set_document(doc, path)
+    }
+}
@@ -0,0 +1,154 @@
+use std::cell::RefCell;
+
+use gtk::glib::{self, clone, SignalHandlerId};
+use gtk::prelude::{Cast, ObjectExt};
+use gtk::subclass::prelude::{ObjectImpl, ObjectSubclass, ObjectSubclassExt};
+use gtk::subclass::widget::WidgetImpl;
+use gtk::traits::{AdjustmentExt, ScrollableExt, SnapshotExt, WidgetExt};
+use gtk::{gdk, graphene, Adjustment};
+
+use super::{HeatRegion, ScrollMark, ScrollMarkKind};
+
+/// Width of the heat strip, in pixels.
+const HEAT_WIDTH: f32 = 3.0;
+
+/// Opacity of the heat strip at full intensity.
+const HEAT_ALPHA: f32 = 0.5;
+
+/// Maximum distance of a click to a search result mark for activating it, in
+/// pixels.
+const SEARCH_RESULT_CLICK_DISTANCE: f64 = 4.0;
+
+#[derive(Debug, Default)]
+pub struct ScrollMarks {
+    target: RefCell<Option<gtk::Widget>>,
+    handler: RefCell<Option<(Adjustment, SignalHandlerId)>>,
+    marks: RefCell<Vec<ScrollMark>>,
+    heat: RefCell<Vec<HeatRegion>>,
+}
+
+impl ScrollMarks {
+    pub fn set_target(&self, target: Option<gtk::Widget>) {
+        if let Some((adj, handler)) = self.handler.take() {
+            adj.disconnect(handler);
+        }
+
+        // redraw whenever the scrollable range changes, e.g. due to zooming
+        let adj = target
+            .as_ref()
+            .and_then(|t| t.dynamic_cast_ref::<gtk::Scrollable>())
+            .and_then(|t| t.vadjustment());
+
+        if let Some(adj) = adj {
+            let obj = self.obj();
+            let handler = adj.connect_changed(clone!(@weak obj => move |_| obj.queue_draw()));
+
+            self.handler.replace(Some((adj, handler)));
+        }
+
+        self.target.replace(target);
+        self.obj().queue_draw();
+    }
+
+    pub fn set_marks(&self, marks: Vec<ScrollMark>) {
+        self.marks.replace(marks);
+        self.obj().queue_draw();
+    }
+
+    pub fn set_heat(&self, heat: Vec<HeatRegion>) {
+        self.heat.replace(heat);
+        self.obj().queue_draw();
+    }
+
+    pub fn search_result_at(&self, y: f64) -> Option<ScrollMark> {
+        let map = self.position_map()?;
+
+        self.marks
+            .borrow()
+            .iter()
+            .filter(|mark| mark.kind == ScrollMarkKind::SearchResult)
+            .map(|mark| (mark, (map(mark.position) - y).abs()))
+            .filter(|(_, dist)| *dist <= SEARCH_RESULT_CLICK_DISTANCE)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(mark, _)| *mark)
+    }
+
+    /// Mapping from canvas positions to positions along the scrollbar, in
+    /// pixels.
+    fn position_map(&self) -> Option<impl Fn(f64) -> f64> {
+        let target = self.target.borrow().clone()?;
+        let adj = self.handler.borrow().as_ref()?.0.clone();
+
+        let range = adj.upper() - adj.lower();
+        if range <= 0.0 {
+            return None;
+        }
+
+        let scale: f64 = target.property("scale");
+        let height = self.obj().height() as f64;
+
+        Some(move |position: f64| (position * scale - adj.lower()) / range * height)
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for ScrollMarks {
+    const NAME: &'static str = "ScrollMarks";
+    type Type = super::ScrollMarks;
+    type ParentType = gtk::Widget;
+}
+
+impl ObjectImpl for ScrollMarks {
+    fn dispose(&self) {
+        self.set_target(None);
+    }
+}
+
+impl WidgetImpl for ScrollMarks {
+    fn snapshot(&self, snapshot: &gtk::Snapshot) {
+        let marks = self.marks.borrow();
+        let heat = self.heat.borrow();
+
+        if marks.is_empty() && heat.is_empty() {
+            return;
+        }
+
+        // map canvas position to position along the scrollbar
+        let map = match self.position_map() {
+            Some(map) => map,
+            None => return,
+        };
+
+        let obj = self.obj();
+        let width = obj.width() as f32;
+        let color = obj.color();
+
+        for region in heat.iter().filter(|r| r.intensity > 0.0) {
+            let (y0, y1) = (map(region.start), map(region.end));
+
+            let color = with_alpha(&color, region.intensity as f32 * HEAT_ALPHA);
+            let rect = graphene::Rect::new(0.0, y0 as f32, HEAT_WIDTH, (y1 - y0).max(1.0) as f32);
+
+            snapshot.append_color(&color, &rect);
+        }
+
+        for mark in marks.iter() {
+            let y = map(mark.position);
+
+            let (w, color) = match mark.kind {
+                ScrollMarkKind::Chapter => (width, with_alpha(&color, 0.7)),
+                ScrollMarkKind::Section => (width / 2.0, with_alpha(&color, 0.35)),
+                // same color as search results on the canvas
+                ScrollMarkKind::SearchResult => (width, gdk::RGBA::new(0.96, 0.83, 0.18, 0.9)),
+            };
+
+            let rect = graphene::Rect::new(width - w, y.floor() as f32, w, 2.0);
+
+            snapshot.append_color(&color, &rect);
+        }
+    }
+}
+
+fn with_alpha(color: &gdk::RGBA, alpha: f32) -> gdk::RGBA {
+    gdk::RGBA::new(color.red(), color.green(), color.blue(), alpha)
+}
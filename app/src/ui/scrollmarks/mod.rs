@@ -0,0 +1,76 @@
+use gtk::glib;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct ScrollMarks(ObjectSubclass<imp::ScrollMarks>)
+        @extends gtk::Widget,
+        @implements gtk::Buildable;
+}
+
+/// A mark for a position of interest along the vertical scroll direction.
+#[derive(Debug, Clone, Copy)]
+pub struct ScrollMark {
+    /// Position in unscaled canvas coordinates.
+    pub position: f64,
+    pub kind: ScrollMarkKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollMarkKind {
+    /// Start of a top-level outline entry.
+    Chapter,
+
+    /// Start of a nested outline entry.
+    Section,
+
+    /// A search result, at the center of the matched text.
+    SearchResult,
+}
+
+/// A region along the vertical scroll direction, shaded by how much it has
+/// been looked at.
+#[derive(Debug, Clone, Copy)]
+pub struct HeatRegion {
+    /// Start position in unscaled canvas coordinates.
+    pub start: f64,
+
+    /// End position in unscaled canvas coordinates.
+    pub end: f64,
+
+    /// Relative intensity, in the range `0.0..=1.0`.
+    pub intensity: f64,
+}
+
+impl ScrollMarks {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    /// Set the scrollable widget whose vertical adjustment the marks are
+    /// drawn for. The widget must provide a `scale` property.
+    pub fn set_target(&self, target: Option<&gtk::Widget>) {
+        self.imp().set_target(target.cloned())
+    }
+
+    pub fn set_marks(&self, marks: Vec<ScrollMark>) {
+        self.imp().set_marks(marks)
+    }
+
+    pub fn set_heat(&self, heat: Vec<HeatRegion>) {
+        self.imp().set_heat(heat)
+    }
+
+    /// The search result mark closest to the given vertical position in
+    /// widget coordinates, if any is close enough to be clicked.
+    pub fn search_result_at(&self, y: f64) -> Option<ScrollMark> {
+        self.imp().search_result_at(y)
+    }
+}
+
+impl Default for ScrollMarks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
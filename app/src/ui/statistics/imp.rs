@@ -0,0 +1,107 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use adw::subclass::prelude::AdwWindowImpl;
+use gtk::glib::subclass::InitializingObject;
+use gtk::subclass::prelude::{
+    CompositeTemplateClass, CompositeTemplateInitializingExt, ObjectImpl, ObjectSubclass,
+    WidgetImpl, WindowImpl,
+};
+use gtk::subclass::widget::WidgetClassSubclassExt;
+use gtk::traits::WidgetExt;
+use gtk::{glib, CompositeTemplate, TemplateChild};
+
+use crate::core::stats::DocumentStats;
+
+#[derive(Debug, Default, CompositeTemplate)]
+#[template(resource = "/io/mxnluz/papr/ui/statistics.ui")]
+pub struct StatisticsDialog {
+    #[template_child]
+    progress_bar: TemplateChild<gtk::ProgressBar>,
+
+    #[template_child]
+    pages_label: TemplateChild<gtk::Label>,
+
+    #[template_child]
+    words_label: TemplateChild<gtk::Label>,
+
+    #[template_child]
+    characters_label: TemplateChild<gtk::Label>,
+
+    #[template_child]
+    images_label: TemplateChild<gtk::Label>,
+
+    #[template_child]
+    annotations_label: TemplateChild<gtk::Label>,
+
+    #[template_child]
+    complexity_label: TemplateChild<gtk::Label>,
+
+    // aborts the computation once the dialog is gone
+    canceled: Arc<AtomicBool>,
+}
+
+impl StatisticsDialog {
+    pub fn set_progress(&self, done: usize, total: usize) {
+        let fraction = if total > 0 {
+            done as f64 / total as f64
+        } else {
+            1.0
+        };
+
+        self.progress_bar.set_fraction(fraction);
+        self.progress_bar
+            .set_text(Some(&format!("Page {done} of {total}")));
+    }
+
+    pub fn set_stats(&self, stats: &DocumentStats) {
+        let count = |n: Option<usize>| match n {
+            Some(n) => n.to_string(),
+            None => "Not available".into(),
+        };
+
+        let complexity = match stats.objects_per_page() {
+            Some(n) => format!("{n:.1}"),
+            None => "Not available".into(),
+        };
+
+        self.pages_label.set_label(&stats.pages.to_string());
+        self.words_label.set_label(&count(stats.words));
+        self.characters_label.set_label(&count(stats.characters));
+        self.images_label.set_label(&count(stats.images));
+        self.annotations_label
+            .set_label(&stats.annotations.to_string());
+        self.complexity_label.set_label(&complexity);
+
+        self.progress_bar.set_visible(false);
+    }
+
+    pub fn canceled(&self) -> Arc<AtomicBool> {
+        self.canceled.clone()
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for StatisticsDialog {
+    const NAME: &'static str = "StatisticsDialog";
+    type Type = super::StatisticsDialog;
+    type ParentType = adw::Window;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+    }
+
+    fn instance_init(obj: &InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+impl ObjectImpl for StatisticsDialog {
+    fn dispose(&self) {
+        self.canceled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl WidgetImpl for StatisticsDialog {}
+impl WindowImpl for StatisticsDialog {}
+impl AdwWindowImpl for StatisticsDialog {}
@@ -0,0 +1,42 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use gtk::glib;
+use gtk::prelude::IsA;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+use gtk::traits::GtkWindowExt;
+
+use crate::core::stats::DocumentStats;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct StatisticsDialog(ObjectSubclass<imp::StatisticsDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Native,
+                gtk::Root, gtk::ShortcutManager;
+}
+
+impl StatisticsDialog {
+    pub fn new(parent: &impl IsA<gtk::Window>) -> Self {
+        let dialog: Self = glib::Object::new();
+        dialog.set_transient_for(Some(parent));
+        dialog
+    }
+
+    /// Show the progress of computing the statistics.
+    pub fn set_progress(&self, done: usize, total: usize) {
+        self.imp().set_progress(done, total)
+    }
+
+    /// Show the computed statistics.
+    pub fn set_stats(&self, stats: &DocumentStats) {
+        self.imp().set_stats(stats)
+    }
+
+    /// Flag set once the dialog has been closed, for canceling the
+    /// computation.
+    pub fn canceled(&self) -> Arc<AtomicBool> {
+        self.imp().canceled()
+    }
+}
@@ -0,0 +1,343 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use executor::exec::Monitor;
+use gtk::glib::subclass::InitializingObject;
+use gtk::glib::{self, clone, once_cell::sync::Lazy, subclass::Signal};
+use gtk::prelude::{Cast, CastNone, ObjectExt, StaticType, ToVariant};
+use gtk::subclass::prelude::{
+    ObjectImpl, ObjectImplExt, ObjectSubclass, ObjectSubclassExt, ObjectSubclassIsExt,
+};
+use gtk::subclass::widget::{
+    CompositeTemplateClass, CompositeTemplateDisposeExt, CompositeTemplateInitializingExt,
+    WidgetClassSubclassExt, WidgetImpl,
+};
+use gtk::traits::{BoxExt, GestureSingleExt, WidgetExt};
+use gtk::{gdk, CompositeTemplate, TemplateChild};
+
+use pdfium::bitmap::Color;
+use pdfium::doc::{Document, PageRotation, RenderFlags};
+
+use crate::core::render::disk::DiskCache;
+use crate::core::render::pdfium::{Damage, RenderOptions};
+use crate::core::thumbnails::Thumbnails;
+use crate::ui::canvas::TextureFactory;
+
+/// Size of the square thumbnails fit into, in logical pixels.
+const THUMBNAIL_SIZE: i32 = 128;
+
+#[derive(Debug, Default, CompositeTemplate)]
+#[template(resource = "/io/mxnluz/papr/ui/thumbnails.ui")]
+pub struct ThumbnailsView {
+    #[template_child]
+    list: TemplateChild<gtk::ListView>,
+
+    data: RefCell<Option<ThumbnailData>>,
+    current: Cell<Option<usize>>,
+}
+
+struct ThumbnailData {
+    thumbnails: Thumbnails<RenderMonitor, TextureFactory>,
+    selection: gtk::SingleSelection,
+
+    // pictures of the pages currently bound to list items
+    bound: HashMap<usize, gtk::Picture>,
+}
+
+impl std::fmt::Debug for ThumbnailData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ThumbnailData")
+            .field("pages", &self.thumbnails.page_count())
+            .field("bound", &self.bound.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ThumbnailData {
+    /// Show the thumbnail of the given page in the given picture, returning
+    /// whether it still has to be rendered.
+    fn bind(&mut self, page: usize, picture: &gtk::Picture) -> bool {
+        // reserve the space of the thumbnail before it has been rendered
+        if let Some(size) = self.thumbnails.page_size(page) {
+            let scale = THUMBNAIL_SIZE as f64 / size.x.max(size.y);
+            let width = (size.x * scale).round().max(1.0);
+            let height = (size.y * scale).round().max(1.0);
+
+            picture.set_size_request(width as _, height as _);
+        }
+
+        let texture = self.thumbnails.get(page);
+        picture.set_paintable(texture);
+        self.bound.insert(page, picture.clone());
+
+        texture.is_none()
+    }
+}
+
+impl ThumbnailsView {
+    pub fn set_document(&self, doc: Option<&Document>, labels: &[String]) {
+        self.current.set(None);
+
+        let doc = match doc {
+            Some(doc) => doc,
+            None => {
+                self.data.replace(None);
+                self.list.set_model(None::<&gtk::SingleSelection>);
+                return;
+            }
+        };
+
+        let monitor = RenderMonitor::new(&self.obj());
+        let opts = RenderOptions {
+            flags: RenderFlags::Annotations,
+            background: Color::WHITE,
+            color_scheme: None,
+            rotation: PageRotation::None,
+        };
+
+        // render at the resolution of the monitor the thumbnails are shown on
+        let size = THUMBNAIL_SIZE * self.obj().scale_factor();
+        let factory = TextureFactory::default();
+        let thumbnails = Thumbnails::new(doc.clone(), monitor, factory, size as _, opts);
+
+        let labels: Vec<_> = labels.iter().map(String::as_str).collect();
+        let selection = gtk::SingleSelection::new(Some(gtk::StringList::new(&labels)));
+        selection.set_autoselect(false);
+        selection.set_can_unselect(true);
+        selection.set_selected(gtk::INVALID_LIST_POSITION);
+
+        // drop the previous document before binding any item of the new one
+        self.data.replace(None);
+        self.list.set_model(Some(&selection));
+
+        self.data.replace(Some(ThumbnailData {
+            thumbnails,
+            selection,
+            bound: HashMap::new(),
+        }));
+    }
+
+    pub fn set_disk_cache(&self, cache: Option<DiskCache>) {
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.thumbnails.set_disk_cache(cache);
+        }
+    }
+
+    pub fn set_current_page(&self, page: usize) {
+        if self.current.get() == Some(page) {
+            return;
+        }
+
+        if let Some(data) = self.data.borrow().as_ref() {
+            data.selection.set_selected(page as _);
+        }
+
+        self.current.set(Some(page));
+
+        if let Err(err) = self
+            .list
+            .activate_action("list.scroll-to-item", Some(&(page as u32).to_variant()))
+        {
+            tracing::warn!(page, error=%err, "failed to scroll to thumbnail");
+        }
+    }
+
+    fn bind(&self, page: usize, picture: &gtk::Picture) {
+        let missing = match self.data.borrow_mut().as_mut() {
+            Some(data) => data.bind(page, picture),
+            None => return,
+        };
+
+        if missing {
+            self.refresh();
+        }
+    }
+
+    fn unbind(&self, picture: &gtk::Picture) {
+        // the position of the item may already be invalid
+        if let Some(data) = self.data.borrow_mut().as_mut() {
+            data.bound.retain(|_, p| p != picture);
+        }
+
+        picture.set_paintable(None::<&gdk::Paintable>);
+    }
+
+    /// Request thumbnails of bound pages first and show the ones rendered in
+    /// the meantime.
+    fn refresh(&self) {
+        let mut data = self.data.borrow_mut();
+        let data = match data.as_mut() {
+            Some(data) => data,
+            None => return,
+        };
+
+        let shown = match (data.bound.keys().min(), data.bound.keys().max()) {
+            (Some(first), Some(last)) => *first..(*last + 1),
+            _ => 0..0,
+        };
+
+        data.thumbnails.update(shown);
+
+        for (page, picture) in &data.bound {
+            if picture.paintable().is_none() {
+                picture.set_paintable(data.thumbnails.get(*page));
+            }
+        }
+    }
+
+    fn activate(&self, position: u32) {
+        self.obj()
+            .emit_by_name::<()>("page-activated", &[&position]);
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for ThumbnailsView {
+    const NAME: &'static str = "ThumbnailsView";
+    type Type = super::ThumbnailsView;
+    type ParentType = gtk::Widget;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+        klass.set_layout_manager_type::<gtk::BinLayout>();
+    }
+
+    fn instance_init(obj: &InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+impl ObjectImpl for ThumbnailsView {
+    fn constructed(&self) {
+        self.parent_constructed();
+
+        let obj = self.obj();
+
+        // items consist of the thumbnail and the page label below it
+        let factory = gtk::SignalListItemFactory::new();
+
+        factory.connect_setup(clone!(@weak obj => move |_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+
+            let picture = gtk::Picture::new();
+            picture.set_halign(gtk::Align::Center);
+            picture.add_css_class("card");
+
+            let label = gtk::Label::new(None);
+            label.add_css_class("caption");
+            label.add_css_class("numeric");
+
+            let content = gtk::Box::new(gtk::Orientation::Vertical, 6);
+            content.set_margin_top(6);
+            content.set_margin_bottom(6);
+            content.append(&picture);
+            content.append(&label);
+
+            // go to the page on a single click, without waiting for the
+            // list to activate the item on double click
+            let click = gtk::GestureClick::new();
+            click.set_button(gdk::BUTTON_PRIMARY);
+            click.connect_released(clone!(@weak obj, @weak item => move |_, _, _, _| {
+                obj.imp().activate(item.position());
+            }));
+            content.add_controller(click);
+
+            item.set_child(Some(&content));
+        }));
+
+        factory.connect_bind(clone!(@weak obj => move |_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+
+            let content = item.child().unwrap();
+            let picture = content.first_child().and_downcast::<gtk::Picture>().unwrap();
+            let label = content.last_child().and_downcast::<gtk::Label>().unwrap();
+            let text = item.item().and_downcast::<gtk::StringObject>().unwrap();
+
+            label.set_text(&text.string());
+            obj.imp().bind(item.position() as _, &picture);
+        }));
+
+        factory.connect_unbind(clone!(@weak obj => move |_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+
+            let content = item.child().unwrap();
+            let picture = content.first_child().and_downcast::<gtk::Picture>().unwrap();
+
+            obj.imp().unbind(&picture);
+        }));
+
+        self.list.set_factory(Some(&factory));
+
+        self.list
+            .connect_activate(clone!(@weak obj => move |_, position| {
+                obj.imp().activate(position);
+            }));
+    }
+
+    fn dispose(&self) {
+        self.dispose_template();
+    }
+
+    fn signals() -> &'static [Signal] {
+        static SIGNALS: Lazy<Vec<Signal>> = Lazy::new(|| {
+            vec![Signal::builder("page-activated")
+                .param_types([u32::static_type()])
+                .build()]
+        });
+        SIGNALS.as_ref()
+    }
+}
+
+impl WidgetImpl for ThumbnailsView {}
+
+/// Monitor for thumbnail render tasks, refreshing the shown thumbnails on
+/// completion.
+///
+/// Completions are coalesced: only the first completion after a refresh sends
+/// a notification to the main thread.
+#[derive(Clone)]
+struct RenderMonitor {
+    sender: glib::Sender<()>,
+
+    /// Whether a notification has been sent but not yet been handled.
+    queued: Arc<AtomicBool>,
+}
+
+impl RenderMonitor {
+    fn new(widget: &super::ThumbnailsView) -> Self {
+        let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT_IDLE);
+        let queued = Arc::new(AtomicBool::new(false));
+
+        let flag = queued.clone();
+        receiver.attach(
+            None,
+            clone!(@weak widget => @default-return glib::Continue(false), move |_| {
+                // reset the flag first, so that any completion during the
+                // refresh sends a new notification
+                flag.store(false, Ordering::SeqCst);
+                widget.imp().refresh();
+
+                glib::Continue(true)
+            }),
+        );
+
+        Self { sender, queued }
+    }
+}
+
+impl Monitor<Damage> for RenderMonitor {
+    fn on_complete(&self, _damage: &Damage) {
+        // skip if a notification is already pending
+        if self.queued.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        // the receiver is gone if the document has been replaced while the
+        // task was running, in which case there is nothing left to refresh
+        if self.sender.send(()).is_err() {
+            tracing::trace!("thumbnail rendered after document has been closed");
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use gtk::glib;
+use gtk::prelude::ObjectExt;
+use gtk::subclass::prelude::ObjectSubclassIsExt;
+
+use pdfium::doc::Document;
+
+use crate::core::render::disk::DiskCache;
+
+mod imp;
+
+glib::wrapper! {
+    pub struct ThumbnailsView(ObjectSubclass<imp::ThumbnailsView>)
+        @extends gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget;
+}
+
+impl ThumbnailsView {
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    /// Show thumbnails of all pages of the given document, labeled with the
+    /// given page labels, or nothing if `None`.
+    pub fn set_document(&self, doc: Option<&Document>, labels: &[String]) {
+        self.imp().set_document(doc, labels)
+    }
+
+    /// Load thumbnails of the current document from and store them in the
+    /// given disk cache.
+    pub fn set_disk_cache(&self, cache: Option<DiskCache>) {
+        self.imp().set_disk_cache(cache)
+    }
+
+    /// Highlight the thumbnail of the given page and scroll it into view.
+    pub fn set_current_page(&self, page: usize) {
+        self.imp().set_current_page(page)
+    }
+
+    /// Connect to the signal emitted when the user activates the thumbnail of
+    /// a page.
+    pub fn connect_page_activated<F: Fn(&Self, usize) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "page-activated",
+            false,
+            glib::closure_local!(move |view: &Self, page: u32| f(view, page as _)),
+        )
+    }
+}
+
+impl Default for ThumbnailsView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,11 +1,14 @@
-use std::{cell::Cell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
 
 use gtk::{
     gdk::{self, Key, ModifierType},
     glib::{
         self, clone, closure_local, once_cell::sync::Lazy, subclass::Signal,
     },
-    prelude::{Cast, DisplayExt, ObjectExt, SeatExt, StaticType, SurfaceExt},
+    prelude::{Cast, DisplayExt, ObjectExt, SeatExt, StaticType, SurfaceExt, WidgetExtManual},
     subclass::{
         prelude::{
             BuildableImpl, BuildableImplExt, ObjectImpl, ObjectImplExt, ObjectSubclass,
@@ -16,14 +19,19 @@ use gtk::{
             WidgetClassSubclassExt, WidgetImpl,
         },
     },
-    traits::{EventControllerExt, GestureDragExt, GestureExt, NativeExt, WidgetExt},
+    traits::{
+        AdjustmentExt, BoxExt, EventControllerExt, GestureDragExt, GestureExt, NativeExt,
+        PopoverExt, WidgetExt,
+    },
     CompositeTemplate, EventControllerScroll, EventControllerScrollFlags,
     EventSequenceState, GestureDrag, GestureZoom, Inhibit, PropagationPhase, ScrollType,
     TemplateChild,
 };
 use nalgebra::{vector, Vector2};
 
-use crate::types::{Bounds, Margin};
+use crate::core::fling::{KineticPan, VelocityTracker};
+use crate::types::{Bounds, Margin, Rect};
+use crate::ui::scrollmarks::ScrollMarks;
 
 #[derive(Debug, CompositeTemplate)]
 #[template(resource = "/io/mxnluz/papr/ui/viewport.ui")]
@@ -32,13 +40,101 @@ pub struct ViewportWidget {
 
     #[template_child]
     scroller: TemplateChild<gtk::ScrolledWindow>,
+
+    #[template_child]
+    marks: TemplateChild<ScrollMarks>,
+
+    // auto-scroll state
+    autoscroll: RefCell<Option<gtk::TickCallbackId>>,
+    autoscroll_speed: Cell<f64>,
+    autoscroll_frame: Cell<Option<i64>>,
+    autoscroll_resume: Cell<i64>,
+
+    // zoom animation state
+    zoom_anim: RefCell<Option<gtk::TickCallbackId>>,
+    zoom_target: Cell<Option<ZoomAnimation>>,
+
+    // kinetic panning state, continuing touch gestures once released
+    kinetic: RefCell<Option<gtk::TickCallbackId>>,
+    kinetic_pan: Cell<Option<KineticPan>>,
+    kinetic_offset: Cell<Option<Vector2<f64>>>,
+
+    // preview of the page at the hovered position of the vertical scrollbar
+    preview: RefCell<Option<PagePreview>>,
+}
+
+/// Interpolation between two viewport transforms.
+#[derive(Debug, Clone, Copy)]
+struct ZoomAnimation {
+    offset: (Vector2<f64>, Vector2<f64>),
+    scale: (f64, f64),
+
+    // frame time of the first animation frame, in µs
+    start: Option<i64>,
+}
+
+impl ZoomAnimation {
+    /// Offset and scale at the given progress, between zero and one.
+    fn interpolate(&self, t: f64) -> (Vector2<f64>, f64) {
+        let (o0, o1) = self.offset;
+        let (s0, s1) = self.scale;
+
+        // interpolate scale geometrically so that zooming feels uniform
+        let scale = s0 * (s1 / s0).powf(t);
+
+        // without a change in scale there is no fixpoint, so just pan
+        if (s1 - s0).abs() <= s0 * 1e-3 {
+            return (o0 + (o1 - o0) * t, scale);
+        }
+
+        // Zoom on the fixpoint of both transforms, i.e. the screen position
+        // showing the same document position before and after, so that it
+        // stays in place during the animation.
+        let focal_point = (o1 * s0 - o0 * s1) / (s1 - s0);
+        let fixp_doc = (o0 + focal_point) / s0;
+
+        (fixp_doc * scale - focal_point, scale)
+    }
+}
+
+#[derive(Debug)]
+struct PagePreview {
+    popover: gtk::Popover,
+    picture: gtk::Picture,
+    label: gtk::Label,
+
+    // hovered position along the scrollbar, in pixels
+    y: Cell<f64>,
 }
 
 impl ViewportWidget {
+    /// Auto-scroll speed limits, in pixels per second.
+    const AUTOSCROLL_SPEED_BOUNDS: (f64, f64) = (5.0, 2000.0);
+
+    /// Delay after user interaction before auto-scroll resumes, in µs.
+    const AUTOSCROLL_RESUME_DELAY: i64 = 2_000_000;
+
+    /// Size of page previews shown when hovering the scrollbar, in pixels.
+    const PREVIEW_SIZE: i32 = 160;
+
+    /// Duration of animated zoom steps, in µs.
+    const ZOOM_DURATION: i64 = 200_000;
+
     pub fn new() -> Self {
         Self {
             scale_step: 0.1,
             scroller: Default::default(),
+            marks: Default::default(),
+            autoscroll: RefCell::new(None),
+            autoscroll_speed: Cell::new(30.0),
+            autoscroll_frame: Cell::new(None),
+            autoscroll_resume: Cell::new(0),
+            zoom_anim: RefCell::new(None),
+            zoom_target: Cell::new(None),
+            kinetic: RefCell::new(None),
+            kinetic_pan: Cell::new(None),
+            kinetic_offset: Cell::new(None),
+            preview: RefCell::new(None),
         }
     }
 
@@ -46,6 +142,10 @@ impl ViewportWidget {
         self.scroller.get()
     }
 
+    pub fn marks(&self) -> ScrollMarks {
+        self.marks.get()
+    }
+
     pub fn canvas_offset(&self) -> Option<Vector2<f64>> {
         self.scroller
             .child()
@@ -53,6 +153,12 @@ impl ViewportWidget {
     }
 
     pub fn set_canvas_offset(&self, offset: Vector2<f64>) {
+        self.stop_zoom_animation();
+        self.stop_kinetic_pan();
+        self.apply_offset(offset);
+    }
+
+    fn apply_offset(&self, offset: Vector2<f64>) {
         if let Some(child) = self.scroller.child() {
             child.set_property("offset-x", offset.x);
             child.set_property("offset-y", offset.y);
@@ -64,12 +170,21 @@ impl ViewportWidget {
     }
 
     pub fn set_canvas_scale(&self, scale: f64) {
+        self.stop_zoom_animation();
+        self.stop_kinetic_pan();
+
         if let Some(child) = self.scroller.child() {
             child.set_property("scale", scale);
         }
     }
 
     pub fn set_canvas_offset_and_scale(&self, offset: Vector2<f64>, scale: f64) {
+        self.stop_zoom_animation();
+        self.stop_kinetic_pan();
+        self.apply_offset_and_scale(offset, scale);
+    }
+
+    fn apply_offset_and_scale(&self, offset: Vector2<f64>, scale: f64) {
         if let Some(child) = self.scroller.child() {
             child.set_property("offset-x", offset.x);
             child.set_property("offset-y", offset.y);
@@ -126,10 +241,87 @@ impl ViewportWidget {
         self.set_canvas_offset_and_scale(offset, scale);
     }
 
+    pub fn canvas_fit_page(&self) {
+        if self.scroller.child().is_none() {
+            return;
+        }
+
+        let margin = self.canvas_margin().unwrap();
+        let bounds = self.canvas_bounds().unwrap();
+
+        let canvas_size = vector![bounds.x_max - bounds.x_min, bounds.y_max - bounds.y_min];
+        let viewport_size = vector![
+            self.scroller.width() as f64 - margin.left - margin.right,
+            self.scroller.height() as f64 - margin.top - margin.bottom
+        ];
+
+        // see canvas_fit_width() for why we may not have a valid size here
+        if canvas_size.min() <= 0.0 || viewport_size.min() <= 0.0 {
+            return;
+        }
+
+        let scale = f64::min(
+            viewport_size.x / canvas_size.x,
+            viewport_size.y / canvas_size.y,
+        );
+        let offset = vector![bounds.x_min, bounds.y_min] * scale - vector![margin.left, margin.top];
+
+        self.set_canvas_offset_and_scale(offset, scale);
+    }
+
+    pub fn canvas_fit_horizontal(&self, x_min: f64, x_max: f64) {
+        if self.scroller.child().is_none() {
+            return;
+        }
+
+        let mut offset = self.canvas_offset().unwrap();
+        let margin = self.canvas_margin().unwrap();
+
+        let width = x_max - x_min;
+        let viewport_width = self.scroller.width() as f64 - margin.left - margin.right;
+
+        if width <= 0.0 || viewport_width <= 0.0 {
+            return;
+        }
+
+        let scale = viewport_width / width;
+        offset.x = x_min * scale - margin.left;
+
+        self.set_canvas_offset_and_scale(offset, scale);
+    }
+
+    /// Zoom to fit the given rectangle, in canvas coordinates, to the
+    /// viewport and center it, animated.
+    pub fn canvas_zoom_to_rect(&self, rect: &Rect<f64>) {
+        let margin = match self.canvas_margin() {
+            Some(margin) => margin,
+            None => return,
+        };
+
+        let viewport_size = vector![
+            self.scroller.width() as f64 - margin.left - margin.right,
+            self.scroller.height() as f64 - margin.top - margin.bottom
+        ];
+
+        // see canvas_fit_width() for why we may not have a valid size here
+        if rect.size.min() <= 0.0 || viewport_size.min() <= 0.0 {
+            return;
+        }
+
+        let (scale_min, scale_max) = self.canvas_scale_bounds().unwrap_or((1.0, 1.0));
+        let scale = f64::min(viewport_size.x / rect.size.x, viewport_size.y / rect.size.y);
+        let scale = scale.clamp(scale_min, scale_max);
+
+        let center = (rect.offs.coords + rect.size / 2.0) * scale;
+        let offset = center - viewport_size / 2.0 - vector![margin.left, margin.top];
+
+        self.animate_offset_and_scale(offset, scale);
+    }
+
     pub fn canvas_zoom_with_focus(&self, focal_point: Vector2<f64>, step: f64) {
-        // offset of the viewport in screen units
-        let offset = self.canvas_offset().unwrap_or_default();
-        let scale = self.canvas_scale().unwrap_or(1.0);
+        // offset of the viewport in screen units, continuing from where a
+        // running animation would end up
+        let (offset, scale) = self.zoom_target();
         let (scale_min, scale_max) = self.canvas_scale_bounds().unwrap_or((1.0, 1.0));
 
         // calculate fixpoint in document coordinates
@@ -143,7 +335,7 @@ impl ViewportWidget {
         let offset = fixp_doc * scale - focal_point;
 
         // update properties
-        self.set_canvas_offset_and_scale(offset, scale);
+        self.animate_offset_and_scale(offset, scale);
     }
 
     pub fn canvas_zoom_centered(&self, step: f64) {
@@ -151,6 +343,287 @@ impl ViewportWidget {
         self.canvas_zoom_with_focus(size / 2.0, step);
     }
 
+    pub fn canvas_set_scale_centered(&self, scale: f64) {
+        let (_, current) = self.zoom_target();
+        self.canvas_zoom_centered(scale / current - 1.0);
+    }
+
+    /// Offset and scale the viewport is at, or is being animated towards.
+    fn zoom_target(&self) -> (Vector2<f64>, f64) {
+        match self.zoom_target.get() {
+            Some(anim) => (anim.offset.1, anim.scale.1),
+            None => (
+                self.canvas_offset().unwrap_or_default(),
+                self.canvas_scale().unwrap_or(1.0),
+            ),
+        }
+    }
+
+    /// Smoothly move the viewport to the given offset and scale. Starts
+    /// from the current state if an animation is already running.
+    fn animate_offset_and_scale(&self, offset: Vector2<f64>, scale: f64) {
+        let (offset_now, scale_now) = match (self.canvas_offset(), self.canvas_scale()) {
+            (Some(offset), Some(scale)) => (offset, scale),
+            _ => return,
+        };
+
+        // the canvas clamps its scale, so animate towards what it will accept
+        let (scale_min, scale_max) = self.canvas_scale_bounds().unwrap_or((1.0, 1.0));
+        let scale = scale.clamp(scale_min, scale_max);
+
+        self.pause_autoscroll();
+        self.stop_kinetic_pan();
+
+        self.zoom_target.set(Some(ZoomAnimation {
+            offset: (offset_now, offset),
+            scale: (scale_now, scale),
+            start: None,
+        }));
+
+        if self.zoom_anim.borrow().is_some() {
+            return;
+        }
+
+        let id = self.obj().add_tick_callback(|obj, clock| {
+            let vp = obj.imp();
+
+            if vp.zoom_tick(clock.frame_time()) {
+                glib::Continue(true)
+            } else {
+                // callback gets removed when returning here, so just drop the id
+                vp.zoom_anim.replace(None);
+                vp.zoom_target.set(None);
+                glib::Continue(false)
+            }
+        });
+
+        self.zoom_anim.replace(Some(id));
+    }
+
+    fn stop_zoom_animation(&self) {
+        self.zoom_target.set(None);
+
+        if let Some(id) = self.zoom_anim.take() {
+            id.remove();
+        }
+    }
+
+    fn zoom_tick(&self, now: i64) -> bool {
+        let mut anim = match self.zoom_target.get() {
+            Some(anim) => anim,
+            None => return false,
+        };
+
+        let start = *anim.start.get_or_insert(now);
+        self.zoom_target.set(Some(anim));
+
+        let t = (now - start) as f64 / Self::ZOOM_DURATION as f64;
+        let t = t.clamp(0.0, 1.0);
+
+        // ease out (cubic)
+        let (offset, scale) = anim.interpolate(1.0 - (1.0 - t).powi(3));
+        self.apply_offset_and_scale(offset, scale);
+
+        t < 1.0
+    }
+
+    /// Continue panning with the given velocity of the viewport offset, in
+    /// pixels per second, decelerating until it comes to rest. Does nothing
+    /// if the velocity is too low.
+    fn start_kinetic_pan(&self, velocity: Vector2<f64>) {
+        self.stop_kinetic_pan();
+
+        let pan = match KineticPan::new(velocity) {
+            Some(pan) => pan,
+            None => return,
+        };
+
+        self.kinetic_pan.set(Some(pan));
+
+        let id = self.obj().add_tick_callback(|obj, clock| {
+            let vp = obj.imp();
+
+            if vp.kinetic_tick(clock.frame_time()) {
+                glib::Continue(true)
+            } else {
+                // callback gets removed when returning here, so just drop the id
+                vp.kinetic.replace(None);
+                vp.kinetic_pan.set(None);
+                vp.kinetic_offset.set(None);
+                glib::Continue(false)
+            }
+        });
+
+        self.kinetic.replace(Some(id));
+    }
+
+    fn stop_kinetic_pan(&self) {
+        self.kinetic_pan.set(None);
+        self.kinetic_offset.set(None);
+
+        if let Some(id) = self.kinetic.take() {
+            id.remove();
+        }
+    }
+
+    fn kinetic_tick(&self, now: i64) -> bool {
+        let (mut pan, offset) = match (self.kinetic_pan.get(), self.canvas_offset()) {
+            (Some(pan), Some(offset)) => (pan, offset),
+            _ => return false,
+        };
+
+        // the canvas clamps its offset, so stop along axes where we have
+        // reached the bounds of the document
+        if let Some(target) = self.kinetic_offset.get() {
+            let clamped = (target - offset).map(|d| d.abs() > 1e-3);
+            pan.halt(clamped.x, clamped.y);
+        }
+
+        let distance = match pan.step(now) {
+            Some(distance) => distance,
+            None => return false,
+        };
+
+        self.kinetic_pan.set(Some(pan));
+        self.kinetic_offset.set(Some(offset + distance));
+
+        self.pause_autoscroll();
+        self.apply_offset(offset + distance);
+
+        true
+    }
+
+    pub fn is_autoscrolling(&self) -> bool {
+        self.autoscroll.borrow().is_some()
+    }
+
+    pub fn start_autoscroll(&self) {
+        if self.is_autoscrolling() {
+            return;
+        }
+
+        self.autoscroll_frame.set(None);
+        self.autoscroll_resume.set(0);
+
+        let id = self.obj().add_tick_callback(|obj, clock| {
+            let vp = obj.imp();
+
+            if vp.autoscroll_tick(clock.frame_time()) {
+                glib::Continue(true)
+            } else {
+                // callback gets removed when returning here, so just drop the id
+                vp.autoscroll.replace(None);
+                glib::Continue(false)
+            }
+        });
+
+        self.autoscroll.replace(Some(id));
+    }
+
+    pub fn stop_autoscroll(&self) {
+        if let Some(id) = self.autoscroll.take() {
+            id.remove();
+        }
+    }
+
+    pub fn toggle_autoscroll(&self) {
+        if self.is_autoscrolling() {
+            self.stop_autoscroll();
+        } else {
+            self.start_autoscroll();
+        }
+    }
+
+    pub fn autoscroll_speed(&self) -> f64 {
+        self.autoscroll_speed.get()
+    }
+
+    pub fn set_autoscroll_speed(&self, speed: f64) {
+        let (min, max) = Self::AUTOSCROLL_SPEED_BOUNDS;
+        self.autoscroll_speed.set(speed.clamp(min, max));
+    }
+
+    /// Pause auto-scrolling due to user interaction. Auto-scrolling will be
+    /// resumed after a short delay.
+    fn pause_autoscroll(&self) {
+        self.autoscroll_resume
+            .set(glib::monotonic_time() + Self::AUTOSCROLL_RESUME_DELAY);
+    }
+
+    fn autoscroll_tick(&self, now: i64) -> bool {
+        let last = match self.autoscroll_frame.replace(Some(now)) {
+            Some(last) => last,
+            None => return true,
+        };
+
+        // skip while paused
+        if now < self.autoscroll_resume.get() {
+            return true;
+        }
+
+        // stop once we have reached the end of the document
+        let adj = self.scroller.vadjustment();
+        if adj.value() >= adj.upper() - adj.page_size() {
+            return false;
+        }
+
+        let offset = match self.canvas_offset() {
+            Some(offset) => offset,
+            None => return false,
+        };
+
+        let dt = (now - last) as f64 * 1e-6;
+        let dy = self.autoscroll_speed.get() * dt;
+
+        self.set_canvas_offset(offset + vector![0.0, dy]);
+        true
+    }
+
+    /// Request a preview of the page at the given position along the
+    /// vertical scrollbar, in pixels.
+    fn request_page_preview(&self, y: f64) {
+        let adj = self.scroller.vadjustment();
+        let height = self.scroller.vscrollbar().height() as f64;
+        let range = adj.upper() - adj.lower();
+
+        let scale = match self.canvas_scale() {
+            Some(scale) if height > 0.0 && range > 0.0 => scale,
+            _ => return,
+        };
+
+        if let Some(preview) = self.preview.borrow().as_ref() {
+            preview.y.set(y);
+        }
+
+        // map position along the scrollbar to canvas position, inverse of
+        // the mapping used for scroll marks
+        let position = (y / height * range + adj.lower()) / scale;
+
+        self.obj().emit_by_name::<()>("page-preview", &[&position]);
+    }
+
+    pub fn show_page_preview(&self, texture: Option<&gdk::Texture>, label: &str) {
+        let preview = self.preview.borrow();
+        let preview = match preview.as_ref() {
+            Some(preview) => preview,
+            None => return,
+        };
+
+        let scrollbar = self.scroller.vscrollbar();
+        let target = gdk::Rectangle::new(0, preview.y.get() as i32, scrollbar.width(), 1);
+
+        preview.picture.set_paintable(texture);
+        preview.label.set_text(label);
+        preview.popover.set_pointing_to(Some(&target));
+        preview.popover.popup();
+    }
+
+    pub fn hide_page_preview(&self) {
+        if let Some(preview) = self.preview.borrow().as_ref() {
+            preview.popover.popdown();
+        }
+    }
+
     pub fn focus_canvas(&self) -> bool {
         match self.scroller.child() {
             Some(canvas) => canvas.grab_focus(),
@@ -217,6 +690,22 @@ impl ObjectSubclass for ViewportWidget {
             "zoom",
             Some(&(-0.1,).into()),
         );
+
+        klass.add_binding_signal(Key::a, ModifierType::empty(), "autoscroll", None);
+
+        klass.add_binding_signal(
+            Key::bracketright,
+            ModifierType::empty(),
+            "autoscroll-speed",
+            Some(&(1.25,).into()),
+        );
+
+        klass.add_binding_signal(
+            Key::bracketleft,
+            ModifierType::empty(),
+            "autoscroll-speed",
+            Some(&(0.8,).into()),
+        );
     }
 
     fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -243,12 +732,16 @@ impl ObjectImpl for ViewportWidget {
 
             ctrl.connect_drag_begin(clone!(@strong drag_start, @weak obj => move |_, _, _| {
                 let vp = obj.imp();
+                vp.pause_autoscroll();
+                vp.stop_zoom_animation();
+                vp.stop_kinetic_pan();
                 vp.focus_canvas();
                 drag_start.set(vp.canvas_offset().unwrap_or_default());
             }));
 
             ctrl.connect_drag_update(clone!(@strong drag_start, @weak obj => move |_, dx, dy| {
                 let vp = obj.imp();
+                vp.pause_autoscroll();
                 vp.set_canvas_offset(drag_start.get() - vector![dx, dy]);
             }));
 
@@ -265,9 +758,10 @@ impl ObjectImpl for ViewportWidget {
 
             ctrl.connect_scroll(clone!(@weak obj => @default-return Inhibit(false),
                 move |ctrl, _, dy| {
-                    if ctrl.current_event_state() == gdk::ModifierType::CONTROL_MASK {
-                        let vp = obj.imp();
+                    let vp = obj.imp();
+                    vp.pause_autoscroll();
 
+                    if ctrl.current_event_state() == gdk::ModifierType::CONTROL_MASK {
                         // get mouse position on surface
                         //
                         // Note: Ideally, we would get the device from the
@@ -303,6 +797,8 @@ impl ObjectImpl for ViewportWidget {
 
                         Inhibit(true)
                     } else {
+                        vp.stop_zoom_animation();
+                        vp.stop_kinetic_pan();
                         Inhibit(false)
                     }
                 }
@@ -320,17 +816,24 @@ impl ObjectImpl for ViewportWidget {
 
             let fixpoint = Rc::new(Cell::new(vector![0.0, 0.0]));
             let scale_start = Rc::new(Cell::new(1.0));
+            let tracker = Rc::new(RefCell::new(VelocityTracker::new()));
 
             ctrl.connect_begin(clone!(
                     @strong fixpoint,
                     @strong scale_start,
+                    @strong tracker,
                     @weak obj
                 => move |ctrl, _seq| {
                     ctrl.set_state(EventSequenceState::Claimed);
 
                     let vp = obj.imp();
+                    vp.pause_autoscroll();
+                    vp.stop_zoom_animation();
+                    vp.stop_kinetic_pan();
                     vp.scroller.grab_focus();
 
+                    tracker.replace(VelocityTracker::new());
+
                     // initial fixpoint in screen coordinates (gesture center)
                     let center = ctrl
                         .bounding_box_center()
@@ -364,6 +867,7 @@ impl ObjectImpl for ViewportWidget {
             ctrl.connect_scale_changed(clone!(
                     @strong fixpoint,
                     @strong scale_start,
+                    @strong tracker,
                     @weak obj
                 => move |ctrl, gesture_scale| {
                     let vp = obj.imp();
@@ -384,6 +888,10 @@ impl ObjectImpl for ViewportWidget {
                             ]
                         });
 
+                    // track movement of the gesture center for kinetic panning
+                    let time = ctrl.current_event_time() as i64 * 1000;
+                    tracker.borrow_mut().sample(time, center.into());
+
                     // calculate viewport offset from fixpoint for new scale
                     let offset = fixpoint.get() * scale - center;
 
@@ -396,13 +904,92 @@ impl ObjectImpl for ViewportWidget {
                 ctrl.set_state(EventSequenceState::Denied);
             });
 
-            ctrl.connect_end(move |ctrl, _seq| {
+            ctrl.connect_end(clone!(@strong tracker, @weak obj => move |ctrl, _seq| {
                 ctrl.set_state(EventSequenceState::Denied);
-            });
+
+                // keep moving with the velocity of the released fingers, the
+                // viewport offset moves opposite to the gesture center
+                let time = ctrl.current_event_time() as i64 * 1000;
+                let velocity = tracker.borrow().velocity(time);
+
+                obj.imp().start_kinetic_pan(-velocity);
+            }));
 
             self.scroller.add_controller(ctrl);
         }
 
+        // preview pages when hovering the vertical scrollbar
+        {
+            let scrollbar = self.scroller.vscrollbar();
+
+            let picture = gtk::Picture::builder()
+                .width_request(Self::PREVIEW_SIZE)
+                .height_request(Self::PREVIEW_SIZE)
+                .build();
+
+            let label = gtk::Label::new(None);
+            label.add_css_class("caption");
+
+            let content = gtk::Box::new(gtk::Orientation::Vertical, 6);
+            content.append(&picture);
+            content.append(&label);
+
+            let popover = gtk::Popover::builder()
+                .child(&content)
+                .position(gtk::PositionType::Left)
+                .autohide(false)
+                .can_target(false)
+                .build();
+            popover.set_parent(&scrollbar);
+
+            let ctrl = gtk::EventControllerMotion::new();
+
+            ctrl.connect_motion(clone!(@weak obj => move |_, _, y| {
+                obj.imp().request_page_preview(y);
+            }));
+
+            ctrl.connect_leave(clone!(@weak obj => move |_| {
+                obj.imp().hide_page_preview();
+            }));
+
+            scrollbar.add_controller(ctrl);
+
+            // jump to search results when clicking their marks, before the
+            // scrollbar handles the click
+            let ctrl = gtk::GestureClick::builder()
+                .name("search_mark_click_controller")
+                .propagation_phase(PropagationPhase::Capture)
+                .build();
+
+            ctrl.connect_pressed(clone!(@weak obj => move |gesture, _n, x, y| {
+                let vp = obj.imp();
+                let scrollbar = vp.scroller.vscrollbar();
+
+                let mark = scrollbar
+                    .translate_coordinates(&*vp.marks, x, y)
+                    .and_then(|(_, y)| vp.marks.search_result_at(y));
+
+                match mark {
+                    Some(mark) => {
+                        gesture.set_state(EventSequenceState::Claimed);
+                        obj.emit_by_name::<()>("search-result-activated", &[&mark.position]);
+                    }
+                    None => {
+                        gesture.set_state(EventSequenceState::Denied);
+                    }
+                }
+            }));
+
+            scrollbar.add_controller(ctrl);
+
+            self.preview.replace(Some(PagePreview {
+                popover,
+                picture,
+                label,
+                y: Cell::new(0.0),
+            }));
+        }
+
         {
             let ctrl = gtk::GestureClick::builder()
                 .name("left_click_controller")
@@ -410,6 +997,8 @@ impl ObjectImpl for ViewportWidget {
                 .build();
 
             ctrl.connect_pressed(clone!(@weak obj => move |_gesture, _n, _x, _y| {
+                obj.imp().pause_autoscroll();
+                obj.imp().stop_kinetic_pan();
                 obj.imp().focus_canvas();
             }));
 
@@ -432,7 +1021,12 @@ impl ObjectImpl for ViewportWidget {
                     _ => panic!("unsupported scroll-type {:?}", ty),
                 };
 
-                vp.imp().scroller.emit_by_name("scroll-child", &[&ty, &horizontal])
+                let vp = vp.imp();
+                vp.pause_autoscroll();
+                vp.stop_zoom_animation();
+                vp.stop_kinetic_pan();
+                vp.scroller
+                    .emit_by_name("scroll-child", &[&ty, &horizontal])
             }),
         );
 
@@ -443,9 +1037,32 @@ impl ObjectImpl for ViewportWidget {
                 vp.imp().canvas_zoom_centered(step)
             }),
         );
+
+        obj.connect_closure(
+            "autoscroll",
+            false,
+            closure_local!(move |vp: super::ViewportWidget| vp.imp().toggle_autoscroll()),
+        );
+
+        obj.connect_closure(
+            "autoscroll-speed",
+            false,
+            closure_local!(move |vp: super::ViewportWidget, factor: f64| {
+                let vp = vp.imp();
+                vp.set_autoscroll_speed(vp.autoscroll_speed() * factor)
+            }),
+        );
     }
 
     fn dispose(&self) {
+        self.stop_autoscroll();
+        self.stop_zoom_animation();
+        self.stop_kinetic_pan();
+
+        if let Some(preview) = self.preview.take() {
+            preview.popover.unparent();
+        }
+
         self.dispose_template();
     }
 
@@ -463,6 +1080,18 @@ impl ObjectImpl for ViewportWidget {
                     .run_last()
                     .param_types([f64::static_type()])
                     .build(),
+                Signal::builder("autoscroll").action().run_last().build(),
+                Signal::builder("page-preview")
+                    .param_types([f64::static_type()])
+                    .build(),
+                Signal::builder("search-result-activated")
+                    .param_types([f64::static_type()])
+                    .build(),
+                Signal::builder("autoscroll-speed")
+                    .action()
+                    .run_last()
+                    .param_types([f64::static_type()])
+                    .build(),
             ]
         });
         SIGNALS.as_ref()
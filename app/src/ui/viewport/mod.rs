@@ -1,6 +1,9 @@
-use gtk::{glib, subclass::prelude::ObjectSubclassIsExt, prelude::IsA, Widget};
+use gtk::{gdk, glib, subclass::prelude::ObjectSubclassIsExt, prelude::{Cast, IsA, ObjectExt}, Widget};
 use nalgebra::Vector2;
 
+use crate::types::Rect;
+use crate::ui::scrollmarks::{HeatRegion, ScrollMark};
+
 mod imp;
 
 glib::wrapper! {
@@ -22,16 +25,50 @@ impl ViewportWidget {
 
     pub fn set_child(&self, child: Option<&impl IsA<Widget>>) {
         self.imp().scroller().set_child(child);
+        self.imp().marks().set_target(child.map(|c| c.upcast_ref()));
+    }
+
+    /// Set marks to display along the vertical scrollbar.
+    pub fn set_scroll_marks(&self, marks: Vec<ScrollMark>) {
+        self.imp().marks().set_marks(marks)
+    }
+
+    /// Set regions to shade along the vertical scrollbar, e.g. by how long
+    /// they have been viewed.
+    pub fn set_scroll_heat(&self, heat: Vec<HeatRegion>) {
+        self.imp().marks().set_heat(heat)
     }
 
     pub fn fit_width(&self) {
         self.imp().canvas_fit_width()
     }
 
+    /// Zoom to fit the full canvas, e.g. a single page, to the viewport.
+    pub fn fit_page(&self) {
+        self.imp().canvas_fit_page()
+    }
+
+    /// Smoothly zoom to fit the given rectangle, in canvas coordinates, to
+    /// the viewport, e.g. a single page of a continuous layout.
+    pub fn zoom_to_rect(&self, rect: &Rect<f64>) {
+        self.imp().canvas_zoom_to_rect(rect)
+    }
+
+    /// Zoom to fit the given horizontal range, in canvas coordinates, to the
+    /// viewport width.
+    pub fn fit_horizontal(&self, x_min: f64, x_max: f64) {
+        self.imp().canvas_fit_horizontal(x_min, x_max)
+    }
+
     pub fn set_offset(&self, offset: Vector2<f64>) {
         self.imp().set_canvas_offset(offset)
     }
 
+    /// Current scale of the canvas, if any.
+    pub fn scale(&self) -> Option<f64> {
+        self.imp().canvas_scale()
+    }
+
     pub fn set_scale(&self, scale: f64) {
         self.imp().set_canvas_scale(scale)
     }
@@ -39,4 +76,55 @@ impl ViewportWidget {
     pub fn set_offset_and_scale(&self, offset: Vector2<f64>, scale: f64) {
         self.imp().set_canvas_offset_and_scale(offset, scale)
     }
+
+    /// Smoothly zoom to the given scale, keeping the center of the viewport
+    /// fixed.
+    pub fn set_scale_centered(&self, scale: f64) {
+        self.imp().canvas_set_scale_centered(scale)
+    }
+
+    pub fn toggle_autoscroll(&self) {
+        self.imp().toggle_autoscroll()
+    }
+
+    pub fn stop_autoscroll(&self) {
+        self.imp().stop_autoscroll()
+    }
+
+    /// Connect to the signal emitted when the user hovers the vertical
+    /// scrollbar, providing the hovered position in unscaled canvas
+    /// coordinates. Respond with [`show_page_preview()`](Self::show_page_preview).
+    pub fn connect_page_preview<F: Fn(&Self, f64) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "page-preview",
+            false,
+            glib::closure_local!(move |vp: &Self, position: f64| f(vp, position)),
+        )
+    }
+
+    /// Connect to the signal emitted when the user clicks the mark of a
+    /// search result along the vertical scrollbar, providing the position of
+    /// the mark in unscaled canvas coordinates.
+    pub fn connect_search_result_activated<F: Fn(&Self, f64) + 'static>(
+        &self,
+        f: F,
+    ) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "search-result-activated",
+            false,
+            glib::closure_local!(move |vp: &Self, position: f64| f(vp, position)),
+        )
+    }
+
+    /// Show the given page preview next to the hovered scrollbar position.
+    pub fn show_page_preview(&self, texture: Option<&gdk::Texture>, label: &str) {
+        self.imp().show_page_preview(texture, label)
+    }
+
+    pub fn hide_page_preview(&self) {
+        self.imp().hide_page_preview()
+    }
 }
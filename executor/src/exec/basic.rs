@@ -39,10 +39,11 @@ struct Data {
     node: linked_list::Pointers<task::Header>,
 }
 
-struct Adapter<M> {
+struct Adapter<M, C> {
     data: Data,
     exec: Weak<ExecutorStruct>,
     monitor: M,
+    context: C,
 }
 
 impl Executor {
@@ -78,7 +79,19 @@ impl Executor {
         R: Send + 'static,
         M: Monitor + Send + 'static,
     {
-        let adapter = Adapter::new(Arc::downgrade(&self.inner), monitor);
+        self.submit_with_context(monitor, (), closure)
+    }
+
+    /// Submit a closure with a monitor and an opaque context value, which is
+    /// passed to all callbacks of the monitor.
+    pub fn submit_with_context<F, R, M, C>(&self, monitor: M, context: C, closure: F) -> Handle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+        M: Monitor<C> + Send + 'static,
+        C: Send + 'static,
+    {
+        let adapter = Adapter::new(Arc::downgrade(&self.inner), monitor, context);
         let (task, handle) = Task::new(adapter, closure);
 
         self.inner.push(task);
@@ -141,24 +154,27 @@ impl ExecutorStruct {
     }
 }
 
-impl<M> Adapter<M>
+impl<M, C> Adapter<M, C>
 where
-    M: Monitor + Send + 'static,
+    M: Monitor<C> + Send + 'static,
+    C: Send + 'static,
 {
-    fn new(exec: Weak<ExecutorStruct>, monitor: M) -> Self {
+    fn new(exec: Weak<ExecutorStruct>, monitor: M, context: C) -> Self {
         Adapter {
             data: Data {
                 node: linked_list::Pointers::new(),
             },
             exec,
             monitor,
+            context,
         }
     }
 }
 
-impl<M> task::Adapter for Adapter<M>
+impl<M, C> task::Adapter for Adapter<M, C>
 where
-    M: Monitor + Send + 'static,
+    M: Monitor<C> + Send + 'static,
+    C: Send + 'static,
 {
     type Data = Data;
 
@@ -175,15 +191,15 @@ where
             unsafe { queue.remove(task) };
         }
 
-        self.monitor.on_canceled();
+        self.monitor.on_canceled(&self.context);
     }
 
     fn on_complete(&self, _task: NonNull<task::Header>) {
-        self.monitor.on_complete();
+        self.monitor.on_complete(&self.context);
     }
 
     fn on_execute(&self, _task: NonNull<task::Header>) {
-        self.monitor.on_execute();
+        self.monitor.on_execute(&self.context);
     }
 }
 
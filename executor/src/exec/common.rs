@@ -1,16 +1,20 @@
 //! Common structs and traits across executors.
 
 /// Monitor trait to monitor the progress of a task.
-pub trait Monitor {
+///
+/// Each task carries an opaque context value, provided on submission, which
+/// is passed to all callbacks. This allows a single monitor to route
+/// notifications, e.g. per document or page, without wrapping every task.
+pub trait Monitor<C = ()> {
     /// Executed when the task starts executing its closure.
-    fn on_execute(&self) {}
+    fn on_execute(&self, _ctx: &C) {}
 
     /// Executed when the task finished executing its closure, either
     /// successfully or via a panic.
-    fn on_complete(&self) {}
+    fn on_complete(&self, _ctx: &C) {}
 
     /// Executed when the task has been canceled successfully.
-    fn on_canceled(&self) {}
+    fn on_canceled(&self, _ctx: &C) {}
 }
 
-impl Monitor for () {}
+impl<C> Monitor<C> for () {}
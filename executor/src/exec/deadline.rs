@@ -0,0 +1,399 @@
+//! A thread-pool based executor scheduling tasks by deadline.
+//!
+//! Tasks are executed earliest deadline first (EDF), e.g. so that tiles
+//! required for the next frame are rendered before tiles that are merely
+//! prefetched. Deadlines only determine the order of execution: tasks are
+//! still executed once their deadline has passed, and running tasks are not
+//! interrupted.
+
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::task::{self, Header};
+use crate::utils::linked_list;
+
+use super::{handle, Monitor};
+
+type Task = task::Task<Data>;
+type TaskList = linked_list::List<Task>;
+
+/// A thread-pool executor with a fixed number of threads, executing tasks
+/// earliest deadline first.
+pub struct Executor {
+    inner: Arc<ExecutorStruct>,
+
+    /// Handles to the execution threads
+    threads: Vec<JoinHandle<()>>,
+}
+
+/// Remote handle for a task.
+pub type Handle<R> = handle::Handle<Executor, R>;
+
+/// Remote handle for a task, canceling the task when being dropped.
+pub type DropHandle<R> = handle::DropHandle<Executor, R>;
+
+struct ExecutorStruct {
+    /// Linked list head for the task queue, ordered by deadline with the
+    /// earliest deadline at the back
+    queue: Mutex<TaskList>,
+
+    /// Condition variable for signaling arrival of new work items
+    signal: Condvar,
+
+    /// Whether to keep the queue running
+    running: AtomicBool,
+
+    /// Reference point for deadlines, i.e. the creation time of the executor
+    epoch: Instant,
+}
+
+struct Data {
+    node: linked_list::Pointers<task::Header>,
+    exec: Weak<ExecutorStruct>,
+
+    /// Reference point for deadlines, same as for the executor
+    epoch: Instant,
+
+    /// Deadline in nanoseconds since the epoch
+    deadline: AtomicU64,
+}
+
+struct Adapter<M, C> {
+    data: Data,
+    monitor: M,
+    context: C,
+}
+
+impl Executor {
+    pub fn new(num_threads: u32) -> Self {
+        let inner = ExecutorStruct {
+            queue: Mutex::new(TaskList::new()),
+            signal: Condvar::new(),
+            running: AtomicBool::new(true),
+            epoch: Instant::now(),
+        };
+        let inner = Arc::new(inner);
+
+        let threads = (0..num_threads)
+            .map(|_| {
+                let exec = inner.clone();
+                std::thread::spawn(move || exec.process())
+            })
+            .collect();
+
+        Executor { inner, threads }
+    }
+
+    pub fn submit<F, R>(&self, deadline: Instant, closure: F) -> Handle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        self.submit_with((), deadline, closure)
+    }
+
+    pub fn submit_with<F, R, M>(&self, monitor: M, deadline: Instant, closure: F) -> Handle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+        M: Monitor + Send + 'static,
+    {
+        self.submit_with_context(monitor, (), deadline, closure)
+    }
+
+    /// Submit a closure with a monitor and an opaque context value, which is
+    /// passed to all callbacks of the monitor.
+    ///
+    /// Tasks with equal deadlines are executed in order of submission.
+    pub fn submit_with_context<F, R, M, C>(
+        &self,
+        monitor: M,
+        context: C,
+        deadline: Instant,
+        closure: F,
+    ) -> Handle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+        M: Monitor<C> + Send + 'static,
+        C: Send + 'static,
+    {
+        let exec = Arc::downgrade(&self.inner);
+        let deadline = nanos_since(self.inner.epoch, deadline);
+
+        let adapter = Adapter::new(exec, self.inner.epoch, monitor, context, deadline);
+        let (task, handle) = Task::new(adapter, closure);
+
+        self.inner.push(task);
+
+        Handle::new(handle)
+    }
+
+    pub fn shutdown(&mut self) {
+        use std::sync::atomic::Ordering;
+
+        // tell all threads to shut down
+        self.inner.running.store(false, Ordering::SeqCst);
+        self.inner.signal.notify_all();
+
+        // wait for all threads to finish, ignore any panics
+        let threads = std::mem::take(&mut self.threads);
+        for handle in threads {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for Executor {
+    fn drop(&mut self) {
+        use std::sync::atomic::Ordering;
+
+        // tell all threads to shut down
+        self.inner.running.store(false, Ordering::Release);
+        self.inner.signal.notify_all();
+    }
+}
+
+impl ExecutorStruct {
+    fn push(&self, task: Task) {
+        let mut queue = self.queue.lock().unwrap();
+
+        Self::insert(&mut queue, task);
+        self.signal.notify_one();
+    }
+
+    /// Insert the task into the queue, keeping it ordered by deadline. This
+    /// must be called with the queue lock held.
+    fn insert(queue: &mut TaskList, task: Task) {
+        use std::sync::atomic::Ordering;
+
+        let data = unsafe { Task::get_adapter_data(task.as_raw()).as_ref() };
+        let deadline = data.deadline.load(Ordering::Acquire);
+
+        // insert before all tasks with the same or an earlier deadline
+        queue.insert_before(task, |node| {
+            let data = unsafe { Task::get_adapter_data(node).as_ref() };
+            data.deadline.load(Ordering::Acquire) <= deadline
+        });
+    }
+
+    fn pop(&self) -> Option<Task> {
+        use std::sync::atomic::Ordering;
+
+        let mut queue = self.queue.lock().unwrap();
+
+        while self.running.load(Ordering::SeqCst) {
+            match queue.pop_back() {
+                Some(task) => return Some(task),
+                None => queue = self.signal.wait(queue).unwrap(),
+            }
+        }
+
+        None
+    }
+
+    fn process(&self) {
+        while let Some(task) = self.pop() {
+            task.execute()
+        }
+    }
+}
+
+impl<R> Handle<R> {
+    /// Update the deadline of this task, moving it in the queue if it has not
+    /// been started yet.
+    pub fn set_deadline(&self, deadline: Instant) {
+        set_deadline(self.base.as_raw_task(), deadline)
+    }
+
+    /// Returns the current deadline of this task.
+    pub fn deadline(&self) -> Instant {
+        get_deadline(self.base.as_raw_task())
+    }
+}
+
+impl<R> DropHandle<R> {
+    /// Update the deadline of this task, moving it in the queue if it has not
+    /// been started yet.
+    pub fn set_deadline(&self, deadline: Instant) {
+        set_deadline(self.base.as_raw_task(), deadline)
+    }
+
+    /// Returns the current deadline of this task.
+    pub fn deadline(&self) -> Instant {
+        get_deadline(self.base.as_raw_task())
+    }
+}
+
+fn set_deadline(task: NonNull<Header>, deadline: Instant) {
+    use std::sync::atomic::Ordering;
+
+    // Get the executor-specific task data
+    let data = unsafe { Task::get_adapter_data(task).as_ref() };
+    let deadline = nanos_since(data.epoch, deadline);
+
+    let exec = match data.exec.upgrade() {
+        Some(exec) => exec,
+        None => return,
+    };
+    let mut queue = exec.queue.lock().unwrap();
+
+    // Update the stored deadline. If it is the same, do nothing.
+    if data.deadline.swap(deadline, Ordering::AcqRel) == deadline {
+        return;
+    }
+
+    // Try to remove the task from the queue and re-insert it at its new
+    // position. This may return None in case the task is executing or has
+    // been completed.
+    if let Some(task) = unsafe { queue.remove(task) } {
+        ExecutorStruct::insert(&mut queue, task);
+    }
+}
+
+fn get_deadline(task: NonNull<Header>) -> Instant {
+    use std::sync::atomic::Ordering;
+
+    let data = unsafe { Task::get_adapter_data(task).as_ref() };
+    let nanos = data.deadline.load(Ordering::Acquire);
+
+    data.epoch + Duration::from_nanos(nanos)
+}
+
+/// Deadline in nanoseconds since the given epoch, deadlines before the epoch
+/// are clamped to it.
+fn nanos_since(epoch: Instant, deadline: Instant) -> u64 {
+    let nanos = deadline.saturating_duration_since(epoch).as_nanos();
+    u64::try_from(nanos).unwrap_or(u64::MAX)
+}
+
+impl<M, C> Adapter<M, C>
+where
+    M: Monitor<C> + Send + 'static,
+    C: Send + 'static,
+{
+    fn new(
+        exec: Weak<ExecutorStruct>,
+        epoch: Instant,
+        monitor: M,
+        context: C,
+        deadline: u64,
+    ) -> Self {
+        Adapter {
+            data: Data {
+                node: linked_list::Pointers::new(),
+                exec,
+                epoch,
+                deadline: AtomicU64::new(deadline),
+            },
+            monitor,
+            context,
+        }
+    }
+}
+
+impl<M, C> task::Adapter for Adapter<M, C>
+where
+    M: Monitor<C> + Send + 'static,
+    C: Send + 'static,
+{
+    type Data = Data;
+
+    fn get_data_ptr(ptr: NonNull<Self>) -> NonNull<Self::Data> {
+        unsafe { NonNull::new_unchecked(std::ptr::addr_of_mut!((*ptr.as_ptr()).data)) }
+    }
+
+    fn on_cancel(&self, task: NonNull<task::Header>) {
+        // try to get a strong reference to the executor
+        if let Some(exec) = self.data.exec.upgrade() {
+            let mut queue = exec.queue.lock().unwrap();
+
+            // try to remove ourselves from the queue
+            unsafe { queue.remove(task) };
+        }
+
+        self.monitor.on_canceled(&self.context);
+    }
+
+    fn on_complete(&self, _task: NonNull<task::Header>) {
+        self.monitor.on_complete(&self.context);
+    }
+
+    fn on_execute(&self, _task: NonNull<task::Header>) {
+        self.monitor.on_execute(&self.context);
+    }
+}
+
+// Safety: Tasks are always pinned.
+unsafe impl linked_list::Link for Task {
+    type Node = task::Header;
+    type Pointer = Task;
+
+    fn into_raw(task: Self::Pointer) -> NonNull<Self::Node> {
+        task.into_raw()
+    }
+
+    unsafe fn from_raw(ptr: NonNull<Self::Node>) -> Self::Pointer {
+        Task::from_raw(ptr)
+    }
+
+    unsafe fn pointers(target: NonNull<Self::Node>) -> NonNull<linked_list::Pointers<Self::Node>> {
+        let ptr = Self::Pointer::get_adapter_data(target);
+        let ptr = std::ptr::addr_of_mut!((*ptr.as_ptr()).node);
+
+        NonNull::new_unchecked(ptr)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn earliest_deadline_first() {
+        use crate::utils::sync::Completion;
+
+        let mut exec = Executor::new(1);
+        let now = Instant::now();
+
+        let completion = Arc::new(Completion::new());
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        // block the worker thread until all tasks have been submitted
+        let compl = completion.clone();
+        let a = exec.submit(now, move || {
+            compl.wait();
+        });
+
+        let tasks: Vec<_> = [(30, 1), (10, 2), (20, 3), (10, 4), (40, 5)]
+            .into_iter()
+            .map(|(offset, value)| {
+                let ord = order.clone();
+                let deadline = now + Duration::from_millis(offset);
+
+                exec.submit(deadline, move || ord.lock().unwrap().push(value))
+            })
+            .collect();
+
+        // move the last task to the front, deadlines in the past are fine
+        tasks[4].set_deadline(now - Duration::from_secs(1));
+        assert!(tasks[4].deadline() <= now);
+        assert_eq!(tasks[0].deadline(), now + Duration::from_millis(30));
+
+        completion.set_completed();
+        a.join();
+
+        for task in tasks {
+            task.join();
+        }
+
+        // tasks with equal deadlines are executed in order of submission
+        let order = order.lock().unwrap();
+        assert_eq!(*order, [5, 2, 4, 3, 1]);
+
+        exec.shutdown();
+    }
+}
@@ -0,0 +1,216 @@
+//! Bridge between executor tasks and `async` code.
+//!
+//! Tasks submitted via this bridge can be awaited from any futures executor,
+//! e.g. the glib main loop, while the actual work runs on the thread pool.
+//! Completion of a task wakes the awaiting future.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use super::priority::{DropHandle, Executor, Priority};
+use super::Monitor;
+
+/// Future resolving to the result of a task submitted via
+/// [`Executor::spawn_blocking_with_priority()`].
+///
+/// Dropping this handle before the task has been completed cancels the task.
+pub struct JoinHandle<P, R> {
+    handle: Option<DropHandle<P, R>>,
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+/// Monitor waking the future associated with a task on completion.
+struct WakeMonitor {
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+impl<P: Priority> Executor<P> {
+    /// Submit a blocking closure for execution on the thread pool and return a
+    /// future resolving to its result.
+    pub fn spawn_blocking_with_priority<F, R>(&self, priority: P, closure: F) -> JoinHandle<P, R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let waker = Arc::new(Mutex::new(None));
+
+        let monitor = WakeMonitor {
+            waker: waker.clone(),
+        };
+
+        let handle = self.submit_with(monitor, priority, closure);
+
+        JoinHandle {
+            handle: Some(handle.cancel_on_drop()),
+            waker,
+        }
+    }
+}
+
+impl<P, R> JoinHandle<P, R> {
+    /// Check if the associated task has been completed.
+    pub fn is_finished(&self) -> bool {
+        match &self.handle {
+            Some(handle) => handle.is_finished(),
+            None => true,
+        }
+    }
+}
+
+impl<P: Priority, R> JoinHandle<P, R> {
+    /// Update the priority of the associated task.
+    pub fn set_priority(&self, priority: P) {
+        if let Some(handle) = &self.handle {
+            handle.set_priority(priority)
+        }
+    }
+}
+
+// the task handle is never pinned, so moving it is fine
+impl<P, R> Unpin for JoinHandle<P, R> {}
+
+impl<P, R: Send> Future for JoinHandle<P, R> {
+    type Output = R;
+
+    /// Poll for the result of the associated task.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the associated task function panicked during its execution
+    /// or if the future is polled again after it has been resolved.
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<R> {
+        let handle = self
+            .handle
+            .as_ref()
+            .expect("future polled after completion");
+
+        if !handle.is_finished() {
+            *self.waker.lock().unwrap() = Some(cx.waker().clone());
+
+            // the task may have completed before we have registered our waker,
+            // so check again
+            if !handle.is_finished() {
+                return Poll::Pending;
+            }
+        }
+
+        Poll::Ready(self.handle.take().unwrap().join())
+    }
+}
+
+impl Monitor for WakeMonitor {
+    fn on_complete(&self, _ctx: &()) {
+        if let Some(waker) = self.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Condvar;
+    use std::task::Wake;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum TaskPriority {
+        Low,
+        High,
+    }
+
+    impl Priority for TaskPriority {
+        fn count() -> u8 {
+            2
+        }
+
+        fn from_value(value: u8) -> Option<Self> {
+            match value {
+                0 => Some(Self::Low),
+                1 => Some(Self::High),
+                _ => None,
+            }
+        }
+
+        fn as_value(&self) -> u8 {
+            match self {
+                Self::Low => 0,
+                Self::High => 1,
+            }
+        }
+    }
+
+    type Executor = super::Executor<TaskPriority>;
+
+    #[derive(Default)]
+    struct Signal {
+        woken: Mutex<bool>,
+        cvar: Condvar,
+    }
+
+    impl Wake for Signal {
+        fn wake(self: Arc<Self>) {
+            *self.woken.lock().unwrap() = true;
+            self.cvar.notify_one();
+        }
+    }
+
+    fn block_on<F: Future + Unpin>(mut future: F) -> F::Output {
+        let signal = Arc::new(Signal::default());
+        let waker = Waker::from(signal.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        loop {
+            if let Poll::Ready(value) = Pin::new(&mut future).poll(&mut cx) {
+                return value;
+            }
+
+            let mut woken = signal.woken.lock().unwrap();
+            while !*woken {
+                woken = signal.cvar.wait(woken).unwrap();
+            }
+            *woken = false;
+        }
+    }
+
+    #[test]
+    fn basic() {
+        let exec = Executor::new(2);
+
+        let a = exec.spawn_blocking_with_priority(TaskPriority::Low, || 21);
+        let b = exec.spawn_blocking_with_priority(TaskPriority::High, || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            2
+        });
+
+        assert_eq!(block_on(a) * block_on(b), 42);
+    }
+
+    #[test]
+    fn cancel_on_drop() {
+        let exec = Executor::new(1);
+
+        let executed = Arc::new(AtomicBool::new(false));
+
+        // block the executor so that the next task will still be queued
+        let blocker = exec.spawn_blocking_with_priority(TaskPriority::High, || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        });
+
+        let flag = executed.clone();
+        let task = exec.spawn_blocking_with_priority(TaskPriority::Low, move || {
+            flag.store(true, Ordering::SeqCst);
+        });
+
+        drop(task);
+        block_on(blocker);
+
+        // submit another task to ensure that the queue has been processed
+        block_on(exec.spawn_blocking_with_priority(TaskPriority::Low, || ()));
+
+        assert!(!executed.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,156 @@
+//! Remote task handles shared across executors.
+//!
+//! Handles wrap the basic task handles with a marker type, allowing each
+//! executor to provide its own operations on tasks, e.g. updating their
+//! priority or deadline.
+
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+use std::time::Duration;
+
+use crate::task::{self, Header};
+
+/// Remote handle for a task.
+pub struct Handle<K, R> {
+    pub(super) base: task::Handle<R>,
+    _marker: PhantomData<K>,
+}
+
+/// Remote handle for a task, canceling the task when being dropped.
+pub struct DropHandle<K, R> {
+    pub(super) base: task::DropHandle<R>,
+    _marker: PhantomData<K>,
+}
+
+impl<K, R> Handle<K, R> {
+    pub(super) fn new(base: task::Handle<R>) -> Self {
+        Self {
+            base,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Check if the associated task has been completed.
+    pub fn is_finished(&self) -> bool {
+        self.base.is_finished()
+    }
+
+    /// Cancel the associated task.
+    ///
+    /// Cancels the associated task. Returns `Ok(())` if the task has been
+    /// canceled successfully, `Err(self)` if the task could not be canceled or
+    /// has already been completed successfully.
+    pub fn cancel(self) -> Result<(), Self> {
+        self.base.cancel().map_err(Self::new)
+    }
+
+    /// Transform into a handle that cancels the task when dropped.
+    pub fn cancel_on_drop(self) -> DropHandle<K, R> {
+        DropHandle::new(self.base.cancel_on_drop())
+    }
+
+    /// Return a pointer to the raw underlying task header.
+    ///
+    /// To be used with care.
+    pub fn as_raw_task(&self) -> NonNull<Header> {
+        self.base.as_raw_task()
+    }
+}
+
+impl<K, R: Send> Handle<K, R> {
+    /// Wait for the task to complete and return its result.
+    ///
+    /// This function will return immediately if the associated task has
+    /// already been completed. Non-blocking operations are supported by
+    /// checking [`is_finished()`][Self::is_finished()] and calling
+    /// [`join()`][Self::join()] only if that returns `true`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the associated task function panicked
+    /// during its execution.
+    pub fn join(self) -> R {
+        self.base.join()
+    }
+
+    /// Wait for the task to complete with a timeout and return its result if
+    /// successful.
+    ///
+    /// Returns `Ok(result)` if the task completed within the timeout,
+    /// `Err(self)` if this operation timed out.
+    ///
+    /// If the associated task has already been completed, this function will
+    /// return its result with `Ok` immediately.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the associated task function panicked
+    /// during its execution.
+    pub fn join_timeout(self, duration: Duration) -> Result<R, Self> {
+        self.base.join_timeout(duration).map_err(Self::new)
+    }
+}
+
+impl<K, R> DropHandle<K, R> {
+    pub(super) fn new(base: task::DropHandle<R>) -> Self {
+        Self {
+            base,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Check if the associated task has been completed.
+    pub fn is_finished(&self) -> bool {
+        self.base.is_finished()
+    }
+
+    /// Cancel the associated task.
+    ///
+    /// Cancels the associated task. Returns `Ok(())` if the task has been
+    /// canceled successfully, `Err(self)` if the task could not be canceled or
+    /// has already been completed successfully.
+    pub fn cancel(self) -> Result<(), Self> {
+        self.base.cancel().map_err(Self::new)
+    }
+
+    /// Return a pointer to the raw underlying task header.
+    ///
+    /// To be used with care.
+    pub fn as_raw_task(&self) -> NonNull<Header> {
+        self.base.as_raw_task()
+    }
+}
+
+impl<K, R: Send> DropHandle<K, R> {
+    /// Wait for the task to complete and return its result.
+    ///
+    /// This function will return immediately if the associated task has
+    /// already been completed. Non-blocking operations are supported by
+    /// checking [`is_finished()`][Self::is_finished()] and calling
+    /// [`join()`][Self::join()] only if that returns `true`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the associated task function panicked
+    /// during its execution.
+    pub fn join(self) -> R {
+        self.base.join()
+    }
+
+    /// Wait for the task to complete with a timeout and return its result if
+    /// successful.
+    ///
+    /// Returns `Ok(result)` if the task completed within the timeout,
+    /// `Err(self)` if this operation timed out.
+    ///
+    /// If the associated task has already been completed, this function will
+    /// return its result with `Ok` immediately.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the associated task function panicked
+    /// during its execution.
+    pub fn join_timeout(self, duration: Duration) -> Result<R, Self> {
+        self.base.join_timeout(duration).map_err(Self::new)
+    }
+}
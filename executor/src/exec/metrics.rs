@@ -0,0 +1,111 @@
+//! Runtime metrics of executors, e.g. for diagnosing latency.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Snapshot of the metrics of an executor, per priority.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Metrics for each priority, indexed by the priority value.
+    pub queues: Vec<QueueMetrics>,
+}
+
+/// Metrics of all tasks of a single priority.
+///
+/// Counts and durations are accumulated since the executor has been created,
+/// except for the number of queued and running tasks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueMetrics {
+    /// Number of tasks waiting for execution.
+    pub queued: usize,
+
+    /// Number of tasks currently being executed.
+    pub running: usize,
+
+    /// Number of tasks that have been executed.
+    pub completed: u64,
+
+    /// Number of tasks that have been canceled before being executed.
+    pub canceled: u64,
+
+    /// Total time executed tasks have spent waiting in the queue.
+    pub wait_time: Duration,
+
+    /// Total time spent executing tasks.
+    pub exec_time: Duration,
+}
+
+impl Metrics {
+    /// Metrics of the tasks of all priorities combined.
+    pub fn total(&self) -> QueueMetrics {
+        self.queues
+            .iter()
+            .fold(QueueMetrics::default(), |a, b| QueueMetrics {
+                queued: a.queued + b.queued,
+                running: a.running + b.running,
+                completed: a.completed + b.completed,
+                canceled: a.canceled + b.canceled,
+                wait_time: a.wait_time + b.wait_time,
+                exec_time: a.exec_time + b.exec_time,
+            })
+    }
+}
+
+impl QueueMetrics {
+    /// Average time executed tasks have spent waiting in the queue.
+    pub fn mean_wait_time(&self) -> Option<Duration> {
+        self.mean(self.wait_time)
+    }
+
+    /// Average time spent executing a task.
+    pub fn mean_exec_time(&self) -> Option<Duration> {
+        self.mean(self.exec_time)
+    }
+
+    fn mean(&self, total: Duration) -> Option<Duration> {
+        let count = u32::try_from(self.completed).ok().filter(|&n| n > 0)?;
+        Some(total / count)
+    }
+}
+
+/// Counters of a single priority, updated by the executor.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    running: AtomicUsize,
+    completed: AtomicU64,
+    canceled: AtomicU64,
+    wait_time: AtomicU64,
+    exec_time: AtomicU64,
+}
+
+impl Counters {
+    pub fn on_start(&self, wait: Duration) {
+        self.running.fetch_add(1, Ordering::Relaxed);
+        self.wait_time.fetch_add(nanos(wait), Ordering::Relaxed);
+    }
+
+    pub fn on_finish(&self, exec: Duration) {
+        self.running.fetch_sub(1, Ordering::Relaxed);
+        self.completed.fetch_add(1, Ordering::Relaxed);
+        self.exec_time.fetch_add(nanos(exec), Ordering::Relaxed);
+    }
+
+    pub fn on_cancel(&self) {
+        self.canceled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self, queued: usize) -> QueueMetrics {
+        QueueMetrics {
+            queued,
+            running: self.running.load(Ordering::Relaxed),
+            completed: self.completed.load(Ordering::Relaxed),
+            canceled: self.canceled.load(Ordering::Relaxed),
+            wait_time: Duration::from_nanos(self.wait_time.load(Ordering::Relaxed)),
+            exec_time: Duration::from_nanos(self.exec_time.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
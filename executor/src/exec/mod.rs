@@ -3,5 +3,15 @@
 mod common;
 pub use common::Monitor;
 
+mod handle;
+
+mod metrics;
+pub use metrics::{Metrics, QueueMetrics};
+
 pub mod basic;
+pub mod deadline;
 pub mod priority;
+pub mod scope;
+
+#[cfg(feature = "futures")]
+pub mod future;
@@ -1,17 +1,25 @@
 //! A thread-pool based executor with support for task priorities.
+//!
+//! Each worker thread has its own task queue, guarded by its own lock, to
+//! reduce contention with many threads. Submitted tasks are distributed
+//! across these queues and idle workers steal tasks from the queues of other
+//! workers, always picking the task with the highest priority across all
+//! queues. Tasks stay in the queue they have been submitted to until they are
+//! executed or canceled, so they can be removed from it in constant time.
 
 use std::ptr::NonNull;
-use std::sync::atomic::{AtomicBool, AtomicU8};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize};
 use std::sync::{Arc, Condvar, Mutex, Weak};
 use std::thread::JoinHandle;
-use std::time::Duration;
+use std::time::Instant;
 
 use crate::task::{self, Header};
 use crate::utils::linked_list;
 
-use super::Monitor;
+use super::metrics::Counters;
+use super::{Metrics, Monitor};
 
-use task::{DropHandle as BaseDropHandle, Handle as BaseHandle};
+pub use super::handle::{DropHandle, Handle};
 
 type Task = task::Task<Data>;
 type TaskList = linked_list::List<Task>;
@@ -43,55 +51,86 @@ pub struct Executor<P> {
     _marker: std::marker::PhantomData<P>,
 }
 
-/// Remote handle for a task.
-pub struct Handle<P, R> {
-    base: BaseHandle<R>,
-    _marker: std::marker::PhantomData<P>,
-}
+struct ExecutorStruct {
+    /// Task queues, one per worker thread
+    workers: Vec<WorkerQueue>,
 
-/// Remote handle for a task, canceling the task when being dropped.
-pub struct DropHandle<P, R> {
-    base: BaseDropHandle<R>,
-    _marker: std::marker::PhantomData<P>,
-}
+    /// Counter for distributing submitted tasks across worker queues
+    next_worker: AtomicUsize,
 
-struct ExecutorStruct {
-    /// Linked list heads for the task queue, one per priority
-    queues: Mutex<Vec<TaskList>>,
+    /// Lock for idle workers waiting on the signal below
+    idle: Mutex<()>,
 
     /// Condition variable for signaling arrival of new work items
     signal: Condvar,
 
     /// Whether to keep the queue running
     running: AtomicBool,
+
+    /// Whether to hold off processing queued tasks
+    parked: AtomicBool,
+
+    /// Number of workers processing tasks, the remaining ones stay idle
+    active: AtomicUsize,
+
+    /// Counter for allocating task generations
+    generation: AtomicU64,
+
+    /// Metrics counters, one per priority
+    counters: Vec<Counters>,
+}
+
+struct WorkerQueue {
+    /// Linked list heads for the task queue, one per priority
+    queues: Mutex<Vec<TaskList>>,
+
+    /// Number of queued tasks per priority, for skipping empty queues
+    /// without taking their lock. Only modified with the lock held.
+    lens: Vec<AtomicUsize>,
 }
 
 struct Data {
     node: linked_list::Pointers<task::Header>,
     exec: Weak<ExecutorStruct>,
+
+    /// Index of the worker queue the task has been submitted to
+    worker: usize,
+
+    /// Priority of the task, only modified with the queue lock held
     priority: AtomicU8,
+    generation: Option<u64>,
+    submitted: Instant,
 }
 
-struct Adapter<M> {
+struct Adapter<M, C> {
     data: Data,
     monitor: M,
+    context: C,
 }
 
 impl<P: Priority> Executor<P> {
     pub fn new(num_threads: u32) -> Self {
-        let queues = (0..P::count()).map(|_| TaskList::new()).collect();
+        let workers = (0..num_threads.max(1))
+            .map(|_| WorkerQueue::new(P::count()))
+            .collect();
 
         let inner = ExecutorStruct {
-            queues: Mutex::new(queues),
+            workers,
+            next_worker: AtomicUsize::new(0),
+            idle: Mutex::new(()),
             signal: Condvar::new(),
             running: AtomicBool::new(true),
+            parked: AtomicBool::new(false),
+            active: AtomicUsize::new(num_threads.max(1) as usize),
+            generation: AtomicU64::new(0),
+            counters: (0..P::count()).map(|_| Counters::default()).collect(),
         };
         let inner = Arc::new(inner);
 
-        let threads = (0..num_threads)
-            .map(|_| {
+        let threads = (0..num_threads as usize)
+            .map(|worker| {
                 let exec = inner.clone();
-                std::thread::spawn(move || exec.process())
+                std::thread::spawn(move || exec.process(worker))
             })
             .collect();
 
@@ -116,122 +155,238 @@ impl<P: Priority> Executor<P> {
         R: Send + 'static,
         M: Monitor + Send + 'static,
     {
+        self.submit_with_context(monitor, (), priority, closure)
+    }
+
+    /// Submit a closure with a monitor and an opaque context value, which is
+    /// passed to all callbacks of the monitor.
+    pub fn submit_with_context<F, R, M, C>(
+        &self,
+        monitor: M,
+        context: C,
+        priority: P,
+        closure: F,
+    ) -> Handle<P, R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+        M: Monitor<C> + Send + 'static,
+        C: Send + 'static,
+    {
+        self.submit_task(monitor, context, None, priority, closure)
+    }
+
+    /// Submit a closure as part of the given generation, allocated via
+    /// [`new_generation`](Self::new_generation). All queued tasks of a
+    /// generation can be canceled at once via
+    /// [`cancel_generation`](Self::cancel_generation).
+    pub fn submit_with_generation<F, R, M, C>(
+        &self,
+        monitor: M,
+        context: C,
+        generation: u64,
+        priority: P,
+        closure: F,
+    ) -> Handle<P, R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+        M: Monitor<C> + Send + 'static,
+        C: Send + 'static,
+    {
+        self.submit_task(monitor, context, Some(generation), priority, closure)
+    }
+
+    fn submit_task<F, R, M, C>(
+        &self,
+        monitor: M,
+        context: C,
+        generation: Option<u64>,
+        priority: P,
+        closure: F,
+    ) -> Handle<P, R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+        M: Monitor<C> + Send + 'static,
+        C: Send + 'static,
+    {
+        use std::sync::atomic::Ordering;
+
         let priority = priority.as_value();
 
-        let adapter = Adapter::new(Arc::downgrade(&self.inner), monitor, priority);
+        // distribute tasks evenly, idle workers steal from busy ones
+        let worker = self.inner.next_worker.fetch_add(1, Ordering::Relaxed);
+        let worker = worker % self.inner.workers.len();
+
+        let exec = Arc::downgrade(&self.inner);
+        let adapter = Adapter::new(exec, worker, monitor, context, priority, generation);
         let (task, handle) = Task::new(adapter, closure);
 
-        self.inner.push(task, priority);
+        self.inner.push(task, worker, priority);
 
         Handle::new(handle)
     }
 
-    pub fn shutdown(&mut self) {
+    /// Allocate a new task generation.
+    ///
+    /// Generations are unique per executor, so that different users of the
+    /// same executor do not interfere with each other.
+    pub fn new_generation(&self) -> u64 {
         use std::sync::atomic::Ordering;
 
-        // tell all threads to shut down
-        self.inner.running.store(false, Ordering::SeqCst);
-        self.inner.signal.notify_all();
+        self.inner.generation.fetch_add(1, Ordering::Relaxed)
+    }
 
-        // wait for all threads to finish, ignore any panics
-        let threads = std::mem::take(&mut self.threads);
-        for handle in threads {
-            let _ = handle.join();
+    /// Cancel all queued tasks of the given generation at once, returning
+    /// the number of canceled tasks.
+    ///
+    /// Tasks that are already running are not affected.
+    pub fn cancel_generation(&self, generation: u64) -> usize {
+        let tasks = self.inner.take_generation(generation);
+        let count = tasks.len();
+
+        // cancel outside of the queue lock, as cancellation callbacks need it
+        for task in tasks {
+            task.cancel();
         }
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(generation, count, "canceled task generation");
+
+        count
     }
-}
 
-impl<P> Drop for Executor<P> {
-    fn drop(&mut self) {
+    /// Return a snapshot of the metrics of this executor.
+    pub fn metrics(&self) -> Metrics {
         use std::sync::atomic::Ordering;
 
-        // tell all threads to shut down
-        self.inner.running.store(false, Ordering::Release);
-        self.inner.signal.notify_all();
+        let queues = self
+            .inner
+            .counters
+            .iter()
+            .enumerate()
+            .map(|(priority, counters)| {
+                let workers = self.inner.workers.iter();
+                let queued = workers
+                    .map(|w| w.lens[priority].load(Ordering::SeqCst))
+                    .sum();
+
+                counters.snapshot(queued)
+            })
+            .collect();
+
+        Metrics { queues }
     }
-}
 
-impl ExecutorStruct {
-    fn push(&self, task: Task, priority: u8) {
-        let mut queues = self.queues.lock().unwrap();
+    /// Stop processing queued tasks until [`unpark`](Self::unpark) is called.
+    ///
+    /// Tasks that are already running are completed. Tasks can still be
+    /// submitted while parked, but will only start after unparking. Worker
+    /// threads stay alive but idle in the meantime.
+    pub fn park(&self) {
+        use std::sync::atomic::Ordering;
 
-        queues[priority as usize].push_front(task);
-        self.signal.notify_one();
+        self.inner.parked.store(true, Ordering::SeqCst);
     }
 
-    fn pop(&self) -> Option<Task> {
+    /// Resume processing queued tasks after [`park`](Self::park).
+    pub fn unpark(&self) {
         use std::sync::atomic::Ordering;
 
-        let mut queues = self.queues.lock().unwrap();
-
-        while self.running.load(Ordering::SeqCst) {
-            for queue in queues.iter_mut().rev() {
-                if let Some(task) = queue.pop_back() {
-                    return Some(task);
-                }
-            }
+        self.inner.parked.store(false, Ordering::SeqCst);
+        self.inner.notify_all();
+    }
 
-            queues = self.signal.wait(queues).unwrap();
-        }
+    pub fn is_parked(&self) -> bool {
+        use std::sync::atomic::Ordering;
 
-        None
+        self.inner.parked.load(Ordering::SeqCst)
     }
 
-    fn process(&self) {
-        while let Some(task) = self.pop() {
-            task.execute()
-        }
-    }
-}
+    /// Limit the number of threads processing tasks, e.g. to save power,
+    /// clamped to at least one and at most the number of threads of this
+    /// executor.
+    ///
+    /// The remaining threads stay alive but idle until the limit is raised
+    /// again. Tasks queued for idle threads are taken over by active ones.
+    /// Tasks that are already running are completed.
+    pub fn set_active_threads(&self, count: u32) {
+        use std::sync::atomic::Ordering;
 
-impl<P, R> Handle<P, R> {
-    fn new(base: BaseHandle<R>) -> Self {
-        Self {
-            base,
-            _marker: std::marker::PhantomData,
-        }
-    }
+        let count = (count as usize).clamp(1, self.inner.workers.len());
 
-    /// Check if the ssociated task has been completed.
-    pub fn is_finished(&self) -> bool {
-        self.base.is_finished()
+        self.inner.active.store(count, Ordering::SeqCst);
+        self.inner.notify_all();
     }
 
-    /// Cancel the associated task.
-    ///
-    /// Cancels the associated task. Returns `Ok(())` if the task has been
-    /// canceled successfully, `Err(self)` if the task could not be canceled or
-    /// has already been completed successfully.
-    pub fn cancel(self) -> Result<(), Self> {
-        self.base.cancel().map_err(Self::new)
+    /// The number of threads processing tasks, see
+    /// [`set_active_threads`](Self::set_active_threads).
+    pub fn active_threads(&self) -> u32 {
+        use std::sync::atomic::Ordering;
+
+        self.inner.active.load(Ordering::SeqCst) as u32
     }
 
-    /// Transform into a handle that cancels the task when dropped.
-    pub fn cancel_on_drop(self) -> DropHandle<P, R> {
-        DropHandle::new(self.base.cancel_on_drop())
+    pub fn shutdown(&mut self) {
+        use std::sync::atomic::Ordering;
+
+        // tell all threads to shut down
+        self.inner.running.store(false, Ordering::SeqCst);
+        self.inner.notify_all();
+
+        // wait for all threads to finish, ignore any panics
+        let threads = std::mem::take(&mut self.threads);
+        for handle in threads {
+            let _ = handle.join();
+        }
     }
+}
 
-    /// Return a pointer to the raw underlying task header.
-    ///
-    /// To be used with care.
-    pub fn as_raw_task(&self) -> NonNull<Header> {
-        self.base.as_raw_task()
+impl<P> Drop for Executor<P> {
+    fn drop(&mut self) {
+        use std::sync::atomic::Ordering;
+
+        // tell all threads to shut down
+        self.inner.running.store(false, Ordering::SeqCst);
+        self.inner.notify_all();
     }
 }
 
-impl<P: Priority, R> Handle<P, R> {
-    /// Update the priority of this task.
-    pub fn set_priority(&self, priority: P) {
+impl ExecutorStruct {
+    fn push(&self, task: Task, worker: usize, priority: u8) {
         use std::sync::atomic::Ordering;
 
-        let priority = priority.as_value();
+        let worker = &self.workers[worker];
 
-        // Get the executor-specific task data
-        let task = self.base.as_raw_task();
-        let data = unsafe { Task::get_adapter_data(task).as_ref() };
+        let mut queues = worker.queues.lock().unwrap();
+        queues[priority as usize].push_front(task);
+        worker.lens[priority as usize].fetch_add(1, Ordering::SeqCst);
+        drop(queues);
+
+        // take the lock so that no thread misses the signal between checking
+        // for queued tasks and waiting
+        let _idle = self.idle.lock().unwrap();
+
+        // a single signal might only wake up an inactive worker
+        if self.active.load(Ordering::SeqCst) < self.workers.len() {
+            self.signal.notify_all();
+        } else {
+            self.signal.notify_one();
+        }
+    }
 
-        let exec = data.exec.upgrade().unwrap();
-        let mut queues = exec.queues.lock().unwrap();
+    fn notify_all(&self) {
+        let _idle = self.idle.lock().unwrap();
+        self.signal.notify_all();
+    }
+
+    /// Move a queued task to the queue of the given priority.
+    fn set_priority(&self, task: NonNull<Header>, data: &Data, priority: u8) {
+        use std::sync::atomic::Ordering;
+
+        let worker = &self.workers[data.worker];
+        let mut queues = worker.queues.lock().unwrap();
 
         // Update the stored task priority
         let old_priority = data.priority.swap(priority, Ordering::SeqCst);
@@ -248,116 +403,166 @@ impl<P: Priority, R> Handle<P, R> {
         // Add task to the new queue
         if let Some(task) = task {
             queues[priority as usize].push_front(task);
+
+            worker.lens[old_priority as usize].fetch_sub(1, Ordering::SeqCst);
+            worker.lens[priority as usize].fetch_add(1, Ordering::SeqCst);
         }
     }
 
-    /// Returns the current priority of this task.
-    pub fn priority(&self) -> P {
+    /// Remove all queued tasks of the given generation, keeping the order of
+    /// the remaining ones.
+    fn take_generation(&self, generation: u64) -> Vec<Task> {
         use std::sync::atomic::Ordering;
 
-        // get the executor-specific task data
-        let task = self.base.as_raw_task();
-        let data = unsafe { Task::get_adapter_data(task).as_ref() };
+        let mut taken = Vec::new();
 
-        let value = data.priority.load(Ordering::SeqCst);
-        P::from_value(value).unwrap()
-    }
-}
+        for worker in &self.workers {
+            let mut queues = worker.queues.lock().unwrap();
 
-impl<P, R: Send> Handle<P, R> {
-    /// Wait for the task to complete and return its result.
-    ///
-    /// This function will return immediately if the associated task has
-    /// already been completed. Non-blocking operations are supported by
-    /// checking [`is_finished()`][Self::is_finished()] and calling
-    /// [`join()`][Self::join()] only if that returns `true`.
-    ///
-    /// # Panics
-    ///
-    /// This function will panic if the associated task function panicked
-    /// during its execution.
-    pub fn join(self) -> R {
-        self.base.join()
-    }
+            let iter = queues.iter_mut().zip(&self.counters).zip(&worker.lens);
+            for ((queue, counters), len) in iter {
+                let mut kept = Vec::new();
 
-    /// Wait for the task to complete with a timeout and return its result if
-    /// successful.
-    ///
-    /// Returns `Ok(result)` if the task completed within the timeout,
-    /// `Err(self)` if this operation timed out.
-    ///
-    /// If the associated task has already been completed, this function will
-    /// return its result with `Ok` immediately.
-    ///
-    /// # Panics
-    ///
-    /// This function will panic if the associated task function panicked
-    /// during its execution.
-    pub fn join_timeout(self, duration: Duration) -> Result<R, Self> {
-        self.base.join_timeout(duration).map_err(Self::new)
+                while let Some(task) = queue.pop_back() {
+                    let data = unsafe { Task::get_adapter_data(task.as_raw()).as_ref() };
+
+                    if data.generation == Some(generation) {
+                        counters.on_cancel();
+                        len.fetch_sub(1, Ordering::SeqCst);
+                        taken.push(task);
+                    } else {
+                        kept.push(task);
+                    }
+                }
+
+                for task in kept {
+                    queue.push_front(task);
+                }
+            }
+        }
+
+        taken
     }
-}
 
-impl<P, R> DropHandle<P, R> {
-    fn new(base: BaseDropHandle<R>) -> Self {
-        Self {
-            base,
-            _marker: std::marker::PhantomData,
+    /// Take the queued task with the highest priority, preferring the queue
+    /// of the given worker over those of other workers for equal priorities.
+    fn steal(&self, worker: usize) -> Option<(Task, usize)> {
+        use std::sync::atomic::Ordering;
+
+        let count = self.workers.len();
+
+        for priority in (0..self.counters.len()).rev() {
+            for i in 0..count {
+                let victim = &self.workers[(worker + i) % count];
+
+                if victim.lens[priority].load(Ordering::SeqCst) == 0 {
+                    continue;
+                }
+
+                let mut queues = victim.queues.lock().unwrap();
+                if let Some(task) = queues[priority].pop_back() {
+                    victim.lens[priority].fetch_sub(1, Ordering::SeqCst);
+                    return Some((task, priority));
+                }
+            }
         }
+
+        None
     }
 
-    /// Check if the ssociated task has been completed.
-    pub fn is_finished(&self) -> bool {
-        self.base.is_finished()
+    fn has_queued(&self) -> bool {
+        use std::sync::atomic::Ordering;
+
+        let mut lens = self.workers.iter().flat_map(|w| w.lens.iter());
+        lens.any(|len| len.load(Ordering::SeqCst) > 0)
     }
 
-    /// Cancel the associated task.
-    ///
-    /// Cancels the associated task. Returns `Ok(())` if the task has been
-    /// canceled successfully, `Err(self)` if the task could not be canceled or
-    /// has already been completed successfully.
-    pub fn cancel(self) -> Result<(), Self> {
-        self.base.cancel().map_err(Self::new)
+    /// Whether the given worker should hold off processing tasks, either
+    /// because the executor is parked or the worker is not active.
+    fn is_parked(&self, worker: usize) -> bool {
+        use std::sync::atomic::Ordering;
+
+        self.parked.load(Ordering::SeqCst) || worker >= self.active.load(Ordering::SeqCst)
     }
 
-    /// Return a pointer to the raw underlying task header.
-    ///
-    /// To be used with care.
-    pub fn as_raw_task(&self) -> NonNull<Header> {
-        self.base.as_raw_task()
+    /// Wait for the next task, returning it along with its priority.
+    fn pop(&self, worker: usize) -> Option<(Task, usize)> {
+        use std::sync::atomic::Ordering;
+
+        while self.running.load(Ordering::SeqCst) {
+            if !self.is_parked(worker) {
+                if let Some(task) = self.steal(worker) {
+                    return Some(task);
+                }
+            }
+
+            // check again with the lock held, tasks submitted after that
+            // will signal us once we are waiting
+            let idle = self.idle.lock().unwrap();
+
+            let runnable = self.has_queued() && !self.is_parked(worker);
+            if runnable || !self.running.load(Ordering::SeqCst) {
+                continue;
+            }
+
+            drop(self.signal.wait(idle).unwrap());
+        }
+
+        None
+    }
+
+    fn process(&self, worker: usize) {
+        while let Some((task, priority)) = self.pop(worker) {
+            let data = unsafe { Task::get_adapter_data(task.as_raw()).as_ref() };
+            let wait = data.submitted.elapsed();
+
+            #[cfg(feature = "tracing")]
+            let _span = tracing::trace_span!("task", priority, ?wait).entered();
+
+            let counters = &self.counters[priority];
+            counters.on_start(wait);
+
+            let start = Instant::now();
+            task.execute();
+
+            counters.on_finish(start.elapsed());
+        }
     }
 }
 
-impl<P: Priority, R> DropHandle<P, R> {
+impl<P: Priority, R> Handle<P, R> {
     /// Update the priority of this task.
     pub fn set_priority(&self, priority: P) {
-        use std::sync::atomic::Ordering;
-
-        let priority = priority.as_value();
-
         // Get the executor-specific task data
         let task = self.base.as_raw_task();
         let data = unsafe { Task::get_adapter_data(task).as_ref() };
 
         let exec = data.exec.upgrade().unwrap();
-        let mut queues = exec.queues.lock().unwrap();
+        exec.set_priority(task, data, priority.as_value());
+    }
 
-        // Update the stored task priority
-        let old_priority = data.priority.swap(priority, Ordering::SeqCst);
+    /// Returns the current priority of this task.
+    pub fn priority(&self) -> P {
+        use std::sync::atomic::Ordering;
 
-        // If priorities are the same, do nothing
-        if old_priority == priority {
-            return;
-        }
+        // get the executor-specific task data
+        let task = self.base.as_raw_task();
+        let data = unsafe { Task::get_adapter_data(task).as_ref() };
 
-        // Try to remove the task from the queue. This may return None in case
-        // the task is executing or has been completed
-        let task = unsafe { queues[old_priority as usize].remove(task) };
+        let value = data.priority.load(Ordering::SeqCst);
+        P::from_value(value).unwrap()
+    }
+}
 
-        // Add task to the new queue
-        if let Some(task) = task {
-            queues[priority as usize].push_front(task);
-        }
+impl<P: Priority, R> DropHandle<P, R> {
+    /// Update the priority of this task.
+    pub fn set_priority(&self, priority: P) {
+        // Get the executor-specific task data
+        let task = self.base.as_raw_task();
+        let data = unsafe { Task::get_adapter_data(task).as_ref() };
+
+        let exec = data.exec.upgrade().unwrap();
+        exec.set_priority(task, data, priority.as_value());
     }
 
     /// Returns the current priority of this task.
@@ -372,59 +577,38 @@ impl<P: Priority, R> DropHandle<P, R> {
     }
 }
 
-impl<P, R: Send> DropHandle<P, R> {
-    /// Wait for the task to complete and return its result.
-    ///
-    /// This function will return immediately if the associated task has
-    /// already been completed. Non-blocking operations are supported by
-    /// checking [`is_finished()`][Self::is_finished()] and calling
-    /// [`join()`][Self::join()] only if that returns `true`.
-    ///
-    /// # Panics
-    ///
-    /// This function will panic if the associated task function panicked
-    /// during its execution.
-    pub fn join(self) -> R {
-        self.base.join()
-    }
-
-    /// Wait for the task to complete with a timeout and return its result if
-    /// successful.
-    ///
-    /// Returns `Ok(result)` if the task completed within the timeout,
-    /// `Err(self)` if this operation timed out.
-    ///
-    /// If the associated task has already been completed, this function will
-    /// return its result with `Ok` immediately.
-    ///
-    /// # Panics
-    ///
-    /// This function will panic if the associated task function panicked
-    /// during its execution.
-    pub fn join_timeout(self, duration: Duration) -> Result<R, Self> {
-        self.base.join_timeout(duration).map_err(Self::new)
-    }
-}
-
-impl<M> Adapter<M>
+impl<M, C> Adapter<M, C>
 where
-    M: Monitor + Send + 'static,
+    M: Monitor<C> + Send + 'static,
+    C: Send + 'static,
 {
-    fn new(exec: Weak<ExecutorStruct>, monitor: M, priority: u8) -> Self {
+    fn new(
+        exec: Weak<ExecutorStruct>,
+        worker: usize,
+        monitor: M,
+        context: C,
+        priority: u8,
+        generation: Option<u64>,
+    ) -> Self {
         Adapter {
             data: Data {
                 node: linked_list::Pointers::new(),
                 exec,
+                worker,
                 priority: AtomicU8::new(priority),
+                generation,
+                submitted: Instant::now(),
             },
             monitor,
+            context,
         }
     }
 }
 
-impl<M> task::Adapter for Adapter<M>
+impl<M, C> task::Adapter for Adapter<M, C>
 where
-    M: Monitor + Send + 'static,
+    M: Monitor<C> + Send + 'static,
+    C: Send + 'static,
 {
     type Data = Data;
 
@@ -437,24 +621,38 @@ where
         if let Some(exec) = self.data.exec.upgrade() {
             use std::sync::atomic::Ordering;
 
-            let mut queues = exec.queues.lock().unwrap();
+            let worker = &exec.workers[self.data.worker];
+            let mut queues = worker.queues.lock().unwrap();
 
             // note: priority may only be accessed when we have the queue lock
-            let priority = self.data.priority.load(Ordering::Acquire);
+            let priority = self.data.priority.load(Ordering::Acquire) as usize;
 
-            // try to remove ourselves from the queue
-            unsafe { queues[priority as usize].remove(task) };
+            // try to remove ourselves from the queue, tasks that have
+            // already been taken from it are not counted
+            if unsafe { queues[priority].remove(task) }.is_some() {
+                worker.lens[priority].fetch_sub(1, Ordering::SeqCst);
+                exec.counters[priority].on_cancel();
+            }
         }
 
-        self.monitor.on_canceled();
+        self.monitor.on_canceled(&self.context);
     }
 
     fn on_complete(&self, _task: NonNull<task::Header>) {
-        self.monitor.on_complete();
+        self.monitor.on_complete(&self.context);
     }
 
     fn on_execute(&self, _task: NonNull<task::Header>) {
-        self.monitor.on_execute();
+        self.monitor.on_execute(&self.context);
+    }
+}
+
+impl WorkerQueue {
+    fn new(priorities: u8) -> Self {
+        WorkerQueue {
+            queues: Mutex::new((0..priorities).map(|_| TaskList::new()).collect()),
+            lens: (0..priorities).map(|_| AtomicUsize::new(0)).collect(),
+        }
     }
 }
 
@@ -547,6 +745,37 @@ mod test {
         exec.shutdown();
     }
 
+    #[test]
+    fn context() {
+        #[derive(Clone, Default)]
+        struct PageMonitor {
+            completed: Arc<Mutex<Vec<usize>>>,
+        }
+
+        impl Monitor<usize> for PageMonitor {
+            fn on_complete(&self, page: &usize) {
+                self.completed.lock().unwrap().push(*page);
+            }
+        }
+
+        let mut exec = Executor::new(1);
+        let monitor = PageMonitor::default();
+
+        let a = exec.submit_with_context(monitor.clone(), 3, TaskPriority::High, || ());
+        let b = exec.submit_with_context(monitor.clone(), 7, TaskPriority::High, || ());
+
+        a.join();
+        b.join();
+
+        // monitors are notified after the result has been made available, so
+        // wait for the worker thread to be done
+        exec.shutdown();
+
+        let mut completed = monitor.completed.lock().unwrap().clone();
+        completed.sort_unstable();
+        assert_eq!(completed, [3, 7]);
+    }
+
     #[test]
     fn priority() {
         use crate::utils::sync::Completion;
@@ -594,4 +823,159 @@ mod test {
 
         exec.shutdown();
     }
+
+    #[test]
+    fn park() {
+        use std::time::Duration;
+
+        let mut exec = Executor::new(2);
+        exec.park();
+
+        // tasks submitted while parked must not run
+        let task = exec.submit(TaskPriority::High, || 42);
+        let task = task.join_timeout(Duration::from_millis(100)).unwrap_err();
+
+        // ... but do run once the executor has been unparked
+        exec.unpark();
+        assert_eq!(task.join(), 42);
+
+        exec.shutdown();
+    }
+
+    #[test]
+    fn active_threads() {
+        use crate::utils::sync::Completion;
+        use std::time::Duration;
+
+        let mut exec = Executor::new(2);
+        exec.set_active_threads(1);
+        assert_eq!(exec.active_threads(), 1);
+
+        // block the only active thread, the inactive one must not take over
+        let completion = Arc::new(Completion::new());
+        let compl = completion.clone();
+        let a = exec.submit(TaskPriority::High, move || compl.wait());
+
+        let task = exec.submit(TaskPriority::Low, || 42);
+        let task = task.join_timeout(Duration::from_millis(100)).unwrap_err();
+
+        // ... until the limit has been raised again
+        exec.set_active_threads(4);
+        assert_eq!(exec.active_threads(), 2);
+        assert_eq!(task.join_timeout(Duration::from_secs(5)).ok(), Some(42));
+
+        completion.set_completed();
+        a.join();
+
+        exec.shutdown();
+    }
+
+    #[test]
+    fn stealing() {
+        use crate::utils::sync::Completion;
+        use std::time::Duration;
+
+        let mut exec = Executor::new(2);
+        let completion = Arc::new(Completion::new());
+
+        // block one worker thread, its queue is drained by the other one
+        let compl = completion.clone();
+        let a = exec.submit(TaskPriority::High, move || {
+            compl.wait();
+        });
+
+        let tasks: Vec<_> = (0..4)
+            .map(|i| exec.submit(TaskPriority::Low, move || i))
+            .collect();
+
+        for (i, task) in tasks.into_iter().enumerate() {
+            let task = task.join_timeout(Duration::from_secs(5));
+            assert_eq!(task.ok(), Some(i));
+        }
+
+        completion.set_completed();
+        a.join();
+
+        exec.shutdown();
+    }
+
+    #[test]
+    fn generation() {
+        use crate::utils::sync::Completion;
+
+        let mut exec = Executor::new(1);
+
+        let completion = Arc::new(Completion::new());
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        // block the worker thread until all tasks have been submitted
+        let compl = completion.clone();
+        let a = exec.submit(TaskPriority::High, move || {
+            compl.wait();
+        });
+
+        let old = exec.new_generation();
+        let new = exec.new_generation();
+
+        let tasks: Vec<_> = [(old, 1), (new, 2), (old, 3), (new, 4)]
+            .into_iter()
+            .map(|(generation, value)| {
+                let ord = order.clone();
+                let task = move || ord.lock().unwrap().push(value);
+
+                exec.submit_with_generation((), (), generation, TaskPriority::Low, task)
+            })
+            .collect();
+
+        // tasks of the old generation are canceled, all others are kept in order
+        assert_eq!(exec.cancel_generation(old), 2);
+
+        completion.set_completed();
+        a.join();
+
+        let finished: Vec<_> = tasks.into_iter().map(|t| t.cancel().is_err()).collect();
+        assert_eq!(finished, [false, true, false, true]);
+
+        exec.shutdown();
+
+        let order = order.lock().unwrap();
+        assert_eq!(*order, [2, 4]);
+    }
+
+    #[test]
+    fn metrics() {
+        use crate::utils::sync::Completion;
+        use std::time::Duration;
+
+        let mut exec = Executor::new(1);
+        let completion = Arc::new(Completion::new());
+
+        // block the worker thread until all tasks have been submitted
+        let compl = completion.clone();
+        let a = exec.submit(TaskPriority::High, move || {
+            compl.wait();
+        });
+
+        let b = exec.submit(TaskPriority::Low, || ());
+        let c = exec.submit(TaskPriority::Low, || ());
+        assert!(c.cancel().is_ok());
+
+        let low = exec.metrics().queues[TaskPriority::Low.as_value() as usize];
+        assert_eq!(low.queued, 1);
+        assert_eq!(low.canceled, 1);
+
+        completion.set_completed();
+        a.join();
+        b.join();
+
+        // counters are updated after the result has been made available, so
+        // wait for the worker thread to be done
+        exec.shutdown();
+
+        let metrics = exec.metrics();
+        let total = metrics.total();
+        assert_eq!((total.queued, total.running), (0, 0));
+        assert_eq!((total.completed, total.canceled), (2, 1));
+        assert!(metrics.queues[TaskPriority::High.as_value() as usize].exec_time > Duration::ZERO);
+    }
 }
@@ -0,0 +1,222 @@
+//! Scoped task submission, allowing tasks to borrow from the stack.
+//!
+//! Similar to [`std::thread::scope`], tasks submitted via a [`Scope`] may
+//! borrow non-`'static` data from the enclosing stack frame. This is sound as
+//! the scope cancels all of its queued tasks on exit and waits for the
+//! running ones to complete before returning, so no task can outlive the data
+//! it borrows.
+
+use std::marker::PhantomData;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+
+use super::priority::{Executor, Handle, Priority};
+
+/// A scope for submitting tasks that borrow from the enclosing stack frame.
+///
+/// See [`Executor::scope`].
+pub struct Scope<'scope, 'env: 'scope, P> {
+    exec: &'env Executor<P>,
+    generation: u64,
+    tasks: Arc<ScopeTasks>,
+
+    /// Invariance over the lifetimes, as for [`std::thread::Scope`].
+    _scope: PhantomData<&'scope mut &'scope ()>,
+    _env: PhantomData<&'env mut &'env ()>,
+}
+
+/// Number of closures of a scope which have not been dropped yet.
+struct ScopeTasks {
+    pending: Mutex<usize>,
+    signal: Condvar,
+}
+
+/// Wrapper around the closure of a scoped task, signaling the scope once the
+/// closure has been dropped, either after running or when being canceled.
+struct ScopedClosure<F> {
+    closure: Option<F>,
+    tasks: Arc<ScopeTasks>,
+}
+
+impl<P: Priority> Executor<P> {
+    /// Create a scope for submitting tasks that borrow from the enclosing
+    /// stack frame.
+    ///
+    /// All tasks submitted via the scope that have not been started yet are
+    /// canceled once the given function returns. This function then waits
+    /// for all running tasks of the scope to complete before returning, even
+    /// if the given function panics.
+    pub fn scope<'env, F, T>(&'env self, f: F) -> T
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env, P>) -> T,
+    {
+        let tasks = ScopeTasks {
+            pending: Mutex::new(0),
+            signal: Condvar::new(),
+        };
+
+        let scope = Scope {
+            exec: self,
+            generation: self.new_generation(),
+            tasks: Arc::new(tasks),
+            _scope: PhantomData,
+            _env: PhantomData,
+        };
+
+        let result = catch_unwind(AssertUnwindSafe(|| f(&scope)));
+
+        // cancel outstanding tasks and wait for running ones
+        self.cancel_generation(scope.generation);
+        scope.tasks.wait();
+
+        match result {
+            Ok(result) => result,
+            Err(panic) => resume_unwind(panic),
+        }
+    }
+}
+
+impl<'scope, 'env, P: Priority> Scope<'scope, 'env, P> {
+    /// Submit a closure which may borrow from outside the scope.
+    ///
+    /// Results must not borrow and are only available until the scope ends:
+    /// tasks that have not been started by then are canceled, and joining
+    /// them will panic.
+    pub fn submit<F, R>(&'scope self, priority: P, closure: F) -> Handle<P, R>
+    where
+        F: FnOnce() -> R + Send + 'scope,
+        R: Send + 'static,
+    {
+        *self.tasks.pending.lock().unwrap() += 1;
+
+        let mut closure = ScopedClosure {
+            closure: Some(closure),
+            tasks: self.tasks.clone(),
+        };
+
+        let task: Box<dyn FnOnce() -> R + Send + 'scope> = Box::new(move || closure.run());
+
+        // Safety: The scope waits for the closure to be dropped before
+        // returning, so anything borrowed for 'scope outlives the closure.
+        let task: Box<dyn FnOnce() -> R + Send + 'static> = unsafe { std::mem::transmute(task) };
+
+        self.exec
+            .submit_with_generation((), (), self.generation, priority, task)
+    }
+}
+
+impl ScopeTasks {
+    fn done(&self) {
+        let mut pending = self.pending.lock().unwrap();
+
+        *pending -= 1;
+        if *pending == 0 {
+            self.signal.notify_all();
+        }
+    }
+
+    fn wait(&self) {
+        let pending = self.pending.lock().unwrap();
+        let _pending = self.signal.wait_while(pending, |n| *n > 0).unwrap();
+    }
+}
+
+impl<F: FnOnce() -> R, R> ScopedClosure<F> {
+    fn run(&mut self) -> R {
+        (self.closure.take().unwrap())()
+    }
+}
+
+impl<F> Drop for ScopedClosure<F> {
+    fn drop(&mut self) {
+        // drop the closure, along with everything it borrows, before
+        // signaling the scope
+        drop(self.closure.take());
+        self.tasks.done();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::utils::sync::Completion;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    struct Single;
+
+    impl Priority for Single {
+        fn count() -> u8 {
+            1
+        }
+
+        fn from_value(value: u8) -> Option<Self> {
+            (value == 0).then_some(Single)
+        }
+
+        fn as_value(&self) -> u8 {
+            0
+        }
+    }
+
+    #[test]
+    fn borrowing() {
+        let mut exec = Executor::new(2);
+
+        let values = [1, 2, 3, 4];
+        let sum = Mutex::new(0);
+
+        let total = exec.scope(|s| {
+            let tasks: Vec<_> = values
+                .chunks(2)
+                .map(|chunk| s.submit(Single, || chunk.iter().sum::<i32>()))
+                .collect();
+
+            s.submit(Single, || *sum.lock().unwrap() += values.len())
+                .join();
+
+            tasks.into_iter().map(Handle::join).sum::<i32>()
+        });
+
+        assert_eq!(total, 10);
+        assert_eq!(*sum.lock().unwrap(), 4);
+
+        exec.shutdown();
+    }
+
+    #[test]
+    fn cancel_on_exit() {
+        use std::time::Duration;
+
+        let mut exec = Executor::new(1);
+
+        let started = Completion::new();
+        let completion = Arc::new(Completion::new());
+        let executed = Mutex::new(Vec::new());
+
+        exec.scope(|s| {
+            s.submit(Single, || {
+                started.set_completed();
+                completion.wait();
+                executed.lock().unwrap().push(1);
+            });
+
+            // queued behind the first task, canceled once the scope ends
+            s.submit(Single, || executed.lock().unwrap().push(2));
+
+            // unblock the worker thread only after the scope has ended
+            started.wait();
+
+            let compl = completion.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(100));
+                compl.set_completed();
+            });
+        });
+
+        // the running task has been waited for
+        assert_eq!(*executed.lock().unwrap(), [1]);
+
+        exec.shutdown();
+    }
+}
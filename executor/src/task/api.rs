@@ -123,6 +123,14 @@ impl<T> Task<T> {
     pub fn execute(self) {
         self.raw.execute();
     }
+
+    /// Cancel the task without executing it, consuming this handle.
+    ///
+    /// Returns `true` if the task has been canceled, `false` if it is being
+    /// executed or has already been completed.
+    pub fn cancel(self) -> bool {
+        self.raw.cancel()
+    }
 }
 
 impl<R> Handle<R> {
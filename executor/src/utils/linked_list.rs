@@ -144,6 +144,47 @@ impl<L: Link> List<L> {
         }
     }
 
+    /// Inserts an element before the first element, starting from the front,
+    /// for which `pred` returns `true`, or last in the list if there is none.
+    ///
+    /// This walks the list, e.g. for keeping it sorted, so it is linear in
+    /// the number of elements.
+    pub fn insert_before<F>(&mut self, val: L::Pointer, mut pred: F)
+    where
+        F: FnMut(NonNull<L::Node>) -> bool,
+    {
+        let ptr = L::into_raw(val);
+
+        unsafe {
+            let mut next = self.head;
+            while let Some(node) = next {
+                if pred(node) {
+                    break;
+                }
+                next = L::pointers(node).as_ref().get_next();
+            }
+
+            // insert between `prev` and `next`
+            let prev = match next {
+                Some(next) => L::pointers(next).as_ref().get_prev(),
+                None => self.tail,
+            };
+
+            L::pointers(ptr).as_mut().set_prev(prev);
+            L::pointers(ptr).as_mut().set_next(next);
+
+            match prev {
+                Some(prev) => L::pointers(prev).as_mut().set_next(Some(ptr)),
+                None => self.head = Some(ptr),
+            }
+
+            match next {
+                Some(next) => L::pointers(next).as_mut().set_prev(Some(ptr)),
+                None => self.tail = Some(ptr),
+            }
+        }
+    }
+
     /// Removes the last element from a list and returns it, or None if it is
     /// empty.
     pub fn pop_back(&mut self) -> Option<L::Pointer> {
@@ -174,6 +215,21 @@ impl<L: Link> List<L> {
         true
     }
 
+    /// Returns the number of elements in the list.
+    ///
+    /// This walks the full list, so it should not be used on hot paths.
+    pub fn len(&self) -> usize {
+        let mut len = 0;
+        let mut node = self.head;
+
+        while let Some(ptr) = node {
+            node = unsafe { L::pointers(ptr).as_ref().get_next() };
+            len += 1;
+        }
+
+        len
+    }
+
     /// Removes the specified node from the list
     ///
     /// # Safety
@@ -433,6 +489,45 @@ mod test {
         assert!(list.is_empty());
     }
 
+    #[test]
+    fn len() {
+        let a = entry(5);
+        let b = entry(7);
+        let c = entry(31);
+
+        let mut list = List::new();
+        assert_eq!(list.len(), 0);
+
+        push_all(&mut list, &[a.as_ref(), b.as_ref(), c.as_ref()]);
+        assert_eq!(list.len(), 3);
+
+        assert!(unsafe { list.remove(ptr(&b)) }.is_some());
+        assert_eq!(list.len(), 2);
+
+        let items: Vec<i32> = collect(&mut list);
+        assert_eq!([5, 31].to_vec(), items);
+        assert_eq!(list.len(), 0);
+    }
+
+    #[test]
+    fn insert_sorted() {
+        let entries: Vec<_> = [7, 3, 9, 5, 3].into_iter().map(entry).collect();
+
+        // keep the list sorted in descending order from the front, i.e.
+        // popping from the back yields ascending values
+        let mut list = List::new();
+        for entry in &entries {
+            let val = entry.val;
+            let before = |node: NonNull<Entry>| unsafe { node.as_ref().val } <= val;
+
+            list.insert_before(entry.as_ref(), before);
+        }
+        assert_eq!(list.len(), 5);
+
+        let items: Vec<i32> = collect(&mut list);
+        assert_eq!([3, 3, 5, 7, 9].to_vec(), items);
+    }
+
     #[test]
     fn push_pop_push_pop() {
         let a = entry(5);
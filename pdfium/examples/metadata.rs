@@ -11,20 +11,13 @@ fn main() -> Result<()> {
     println!("  version: {}", doc.version());
     println!("  pages: {:?}", doc.pages().count());
 
-    let tags = [
-        MetadataTag::Title,
-        MetadataTag::Subject,
-        MetadataTag::Author,
-        MetadataTag::CreationDate,
-        MetadataTag::Creator,
-        MetadataTag::Keywords,
-        MetadataTag::ModDate,
-        MetadataTag::Producer,
-    ];
+    let security = doc.security();
+    println!("  encrypted: {}", security.is_encrypted());
+    println!("  permissions: {:?}", security.permissions);
 
     println!();
     println!("Metadata:");
-    for tag in tags {
+    for tag in MetadataTag::ALL {
         let key = tag.as_str();
         let value = doc.metadata().get(tag)?.unwrap_or_else(|| "<unset>".into());
 
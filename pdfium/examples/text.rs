@@ -0,0 +1,43 @@
+use pdfium::doc::SearchFlags;
+use pdfium::{Library, Result};
+
+fn main() -> Result<()> {
+    let mut args = std::env::args_os().skip(1);
+    let file = args.next().unwrap();
+    let query = args.next().and_then(|q| q.into_string().ok());
+
+    let lib = Library::init()?;
+    let doc = lib.load_file(file, None)?;
+
+    let pages = doc.pages();
+
+    for i in 0..pages.count() {
+        let page = pages.get(i)?;
+        let text = page.text()?;
+
+        // Without query, print the text of each page
+        let query = match &query {
+            Some(query) => query,
+            None => {
+                println!("Page {i}:");
+                println!("{}", text.text()?);
+                println!();
+                continue;
+            }
+        };
+
+        // Otherwise, print the bounds of all occurrences of the query
+        for range in text.find(query, SearchFlags::empty())? {
+            let rects = text.rects(range.start, range.end - range.start)?;
+
+            for r in rects {
+                println!(
+                    "page {i}, chars {range:?}: left: {}pt, top: {}pt, right: {}pt, bottom: {}pt",
+                    r.left, r.top, r.right, r.bottom
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,5 +1,7 @@
 use std::path::Path;
 
+use crate::Capabilities;
+
 pub use crate::utils::sync::Handle;
 
 /// Raw pdfium function pointer table.
@@ -8,6 +10,7 @@ pub type FnTable = pdfium_sys::libpdfium;
 /// Pdfium library function bindings.
 pub struct Bindings {
     pub(crate) ftable: FnTable,
+    pub(crate) capabilities: Capabilities,
 }
 
 impl Bindings {
@@ -18,20 +21,27 @@ impl Bindings {
     }
 
     pub fn load_from_path(path: impl AsRef<Path>) -> Result<Bindings, libloading::Error> {
-        let ftable = unsafe { pdfium_sys::libpdfium::new(path.as_ref()) }?;
-
-        let library = Bindings { ftable };
-        Ok(library)
+        let lib = unsafe { libloading::Library::new(path.as_ref()) }?;
+        Self::load_from_library(lib)
     }
 
     pub fn load_from_library(lib: libloading::Library) -> Result<Bindings, libloading::Error> {
+        let capabilities = Capabilities::probe(&lib);
         let ftable = unsafe { pdfium_sys::libpdfium::from_library(lib) }?;
 
-        let library = Bindings { ftable };
+        let library = Bindings {
+            ftable,
+            capabilities,
+        };
         Ok(library)
     }
 
     pub fn ftable(&self) -> &FnTable {
         &self.ftable
     }
+
+    /// Optional feature sets supported by the loaded library.
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
 }
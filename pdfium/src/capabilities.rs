@@ -0,0 +1,217 @@
+bitflags::bitflags! {
+    /// Optional feature sets supported by the loaded pdfium library.
+    ///
+    /// Older or stripped-down builds of pdfium may lack some of the exported
+    /// functions. Unless all functions are required at load time (see the
+    /// `dylib-require-all` feature), calling a missing function panics. The
+    /// wrappers provided by this crate check the respective capability before
+    /// use and fail with [`Error::Unsupported`](crate::Error::Unsupported)
+    /// instead.
+    ///
+    /// Each capability is also gated behind the cargo feature of the same
+    /// name (e.g. `text` or `annotations`). Capabilities of disabled features
+    /// are never reported as supported, see [`Capabilities::enabled()`].
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct Capabilities: u32 {
+        /// Text extraction, see [`TextPage`](crate::doc::TextPage).
+        const Text = 1 << 0;
+
+        /// Reading and editing annotations, see
+        /// [`Annotation`](crate::doc::Annotation).
+        const Annotations = 1 << 1;
+
+        /// Reading digital signatures, see
+        /// [`Signature`](crate::doc::Signature).
+        const Signatures = 1 << 2;
+
+        /// Reading document-level JavaScript actions, see
+        /// [`JavaScriptAction`](crate::doc::JavaScriptAction).
+        const JavaScript = 1 << 3;
+
+        /// Inspecting page objects and the fonts used by text objects, see
+        /// [`TextObject`](crate::doc::TextObject) and
+        /// [`PageObjectType`](crate::doc::PageObjectType).
+        const Fonts = 1 << 4;
+
+        /// Reading the structure tree of tagged documents, see
+        /// [`StructTree`](crate::doc::StructTree).
+        const Structure = 1 << 5;
+    }
+}
+
+/// Cargo feature enabling each capability, and whether it is enabled.
+const FEATURES: &[(Capabilities, &str, bool)] = &[
+    (Capabilities::Text, "text", cfg!(feature = "text")),
+    (
+        Capabilities::Annotations,
+        "annotations",
+        cfg!(feature = "annotations"),
+    ),
+    (
+        Capabilities::Signatures,
+        "signatures",
+        cfg!(feature = "signatures"),
+    ),
+    (
+        Capabilities::JavaScript,
+        "javascript",
+        cfg!(feature = "javascript"),
+    ),
+    (Capabilities::Fonts, "fonts", cfg!(feature = "fonts")),
+    (
+        Capabilities::Structure,
+        "structure",
+        cfg!(feature = "structure"),
+    ),
+];
+
+/// Functions required for each capability.
+const SYMBOLS: &[(Capabilities, &[&[u8]])] = &[
+    (
+        Capabilities::Text,
+        &[
+            b"FPDFText_LoadPage\0",
+            b"FPDFText_ClosePage\0",
+            b"FPDFText_CountChars\0",
+            b"FPDFText_GetText\0",
+            b"FPDFText_GetCharBox\0",
+            b"FPDFText_GetLooseCharBox\0",
+            b"FPDFText_GetCharOrigin\0",
+            b"FPDFText_GetCharAngle\0",
+            b"FPDFText_GetCharIndexAtPos\0",
+            b"FPDFText_CountRects\0",
+            b"FPDFText_GetRect\0",
+            b"FPDFText_FindStart\0",
+            b"FPDFText_FindNext\0",
+            b"FPDFText_GetSchResultIndex\0",
+            b"FPDFText_GetSchCount\0",
+            b"FPDFText_FindClose\0",
+        ],
+    ),
+    (
+        Capabilities::Annotations,
+        &[
+            b"FPDFPage_GetAnnotCount\0",
+            b"FPDFPage_GetAnnot\0",
+            b"FPDFPage_CloseAnnot\0",
+            b"FPDFAnnot_GetSubtype\0",
+            b"FPDFAnnot_GetFlags\0",
+            b"FPDFAnnot_GetRect\0",
+            b"FPDFAnnot_GetStringValue\0",
+            b"FPDFAnnot_SetStringValue\0",
+            b"FPDFPage_CreateAnnot\0",
+            b"FPDFPage_RemoveAnnot\0",
+            b"FPDFAnnot_SetRect\0",
+            b"FPDFAnnot_SetFlags\0",
+            b"FPDFAnnot_GetColor\0",
+            b"FPDFAnnot_SetColor\0",
+            b"FPDFAnnot_CountAttachmentPoints\0",
+            b"FPDFAnnot_GetAttachmentPoints\0",
+            b"FPDFAnnot_SetAttachmentPoints\0",
+            b"FPDFAnnot_AppendAttachmentPoints\0",
+            b"FPDFAnnot_GetInkListCount\0",
+            b"FPDFAnnot_GetInkListPath\0",
+            b"FPDFAnnot_AddInkStroke\0",
+            b"FPDFAnnot_RemoveInkList\0",
+            b"FPDFAnnot_AppendObject\0",
+            b"FPDFPageObj_CreateNewPath\0",
+            b"FPDFPageObj_Destroy\0",
+            b"FPDFPath_LineTo\0",
+            b"FPDFPath_SetDrawMode\0",
+            b"FPDFPageObj_NewTextObj\0",
+            b"FPDFText_SetText\0",
+            b"FPDFPageObj_SetFillColor\0",
+            b"FPDFPageObj_GetBounds\0",
+            b"FPDFPageObj_Transform\0",
+            b"FPDFPageObj_NewImageObj\0",
+            b"FPDFImageObj_SetBitmap\0",
+        ],
+    ),
+    (
+        Capabilities::Signatures,
+        &[
+            b"FPDF_GetSignatureCount\0",
+            b"FPDF_GetSignatureObject\0",
+            b"FPDFSignatureObj_GetContents\0",
+            b"FPDFSignatureObj_GetSubFilter\0",
+            b"FPDFSignatureObj_GetReason\0",
+            b"FPDFSignatureObj_GetTime\0",
+        ],
+    ),
+    (
+        Capabilities::JavaScript,
+        &[
+            b"FPDFDoc_GetJavaScriptActionCount\0",
+            b"FPDFDoc_GetJavaScriptAction\0",
+            b"FPDFDoc_CloseJavaScriptAction\0",
+            b"FPDFJavaScriptAction_GetName\0",
+            b"FPDFJavaScriptAction_GetScript\0",
+        ],
+    ),
+    (
+        Capabilities::Fonts,
+        &[
+            b"FPDFPage_CountObjects\0",
+            b"FPDFPage_GetObject\0",
+            b"FPDFPageObj_GetType\0",
+            b"FPDFPageObj_GetBounds\0",
+            b"FPDFTextObj_GetFont\0",
+            b"FPDFFont_GetFontName\0",
+            b"FPDFFont_GetIsEmbedded\0",
+        ],
+    ),
+    (
+        Capabilities::Structure,
+        &[
+            b"FPDF_StructTree_GetForPage\0",
+            b"FPDF_StructTree_Close\0",
+            b"FPDF_StructTree_CountChildren\0",
+            b"FPDF_StructTree_GetChildAtIndex\0",
+            b"FPDF_StructElement_GetType\0",
+            b"FPDF_StructElement_GetObjType\0",
+            b"FPDF_StructElement_GetTitle\0",
+            b"FPDF_StructElement_GetAltText\0",
+            b"FPDF_StructElement_GetActualText\0",
+            b"FPDF_StructElement_GetID\0",
+            b"FPDF_StructElement_GetLang\0",
+            b"FPDF_StructElement_GetStringAttribute\0",
+            b"FPDF_StructElement_GetMarkedContentIdCount\0",
+            b"FPDF_StructElement_GetMarkedContentIdAtIndex\0",
+            b"FPDF_StructElement_GetParent\0",
+            b"FPDF_StructElement_CountChildren\0",
+            b"FPDF_StructElement_GetChildAtIndex\0",
+        ],
+    ),
+];
+
+impl Capabilities {
+    /// The capabilities enabled at build time via cargo features.
+    pub fn enabled() -> Self {
+        FEATURES
+            .iter()
+            .filter(|(_, _, enabled)| *enabled)
+            .fold(Capabilities::empty(), |caps, (cap, _, _)| caps | *cap)
+    }
+
+    /// Names of the cargo features enabling these capabilities.
+    pub fn features(&self) -> Vec<&'static str> {
+        FEATURES
+            .iter()
+            .filter(|(cap, _, _)| self.contains(*cap))
+            .map(|(_, name, _)| *name)
+            .collect()
+    }
+
+    /// Determine the capabilities of the given library by probing for the
+    /// required functions of all enabled capabilities.
+    pub(crate) fn probe(lib: &libloading::Library) -> Self {
+        let has_symbol = |name: &[u8]| unsafe { lib.get::<unsafe extern "C" fn()>(name).is_ok() };
+        let enabled = Capabilities::enabled();
+
+        SYMBOLS
+            .iter()
+            .filter(|(cap, _)| enabled.contains(*cap))
+            .filter(|(_, symbols)| symbols.iter().all(|name| has_symbol(name)))
+            .fold(Capabilities::empty(), |caps, (cap, _)| caps | *cap)
+    }
+}
@@ -0,0 +1,139 @@
+use std::ffi::{c_long, c_void, CString};
+
+use crate::doc::Document;
+use crate::Result;
+
+/// A location in the document, e.g. the target of a bookmark or link.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Destination {
+    /// Index of the page.
+    pub page: u32,
+
+    /// Horizontal position on the page, in PDF page coordinates, if
+    /// specified.
+    pub left: Option<f32>,
+
+    /// Vertical position on the page, in PDF page coordinates, if specified.
+    pub top: Option<f32>,
+
+    /// Zoom factor to show the page at, if specified.
+    pub zoom: Option<f32>,
+}
+
+/// Resolve the given destination handle to a location in the document, if
+/// it points to a page of the document.
+pub(crate) fn resolve(doc: &Document, dest: pdfium_sys::FPDF_DEST) -> Option<Destination> {
+    let lib = doc.library();
+    let handle = doc.handle().get();
+
+    let index = unsafe { lib.ftable().FPDFDest_GetDestPageIndex(handle, dest) };
+    let page = u32::try_from(index).ok()?;
+
+    let (left, top, zoom) = location(doc, dest);
+
+    Some(Destination {
+        page,
+        left,
+        top,
+        zoom,
+    })
+}
+
+/// Look up the destination with the given name.
+pub(crate) fn named(doc: &Document, name: &str) -> Option<Destination> {
+    let lib = doc.library();
+    let handle = doc.handle().get();
+
+    let name = CString::new(name).ok()?;
+
+    let dest = unsafe { lib.ftable().FPDF_GetNamedDestByName(handle, name.as_ptr()) };
+    if dest.is_null() {
+        return None;
+    }
+
+    resolve(doc, dest)
+}
+
+/// All named destinations of the document, skipping those that do not point
+/// to a page of the document.
+pub(crate) fn load_named(doc: &Document) -> Result<Vec<(String, Destination)>> {
+    let lib = doc.library();
+    let handle = doc.handle().get();
+
+    let count = unsafe { lib.ftable().FPDF_CountNamedDests(handle) };
+
+    let mut entries = Vec::new();
+
+    for index in 0..count as i32 {
+        // get length in bytes, including trailing zeros
+        let mut len: c_long = 0;
+        let dest = unsafe {
+            lib.ftable()
+                .FPDF_GetNamedDest(handle, index, std::ptr::null_mut(), &mut len)
+        };
+
+        if dest.is_null() || len <= 0 {
+            continue;
+        }
+
+        // get actual name as bytes
+        let mut buffer: Vec<u8> = vec![0; len as usize];
+        let buffer_p = buffer.as_mut_ptr() as *mut c_void;
+
+        let dest = unsafe {
+            lib.ftable()
+                .FPDF_GetNamedDest(handle, index, buffer_p, &mut len)
+        };
+
+        if dest.is_null() || len <= 0 {
+            continue;
+        }
+
+        let name = crate::utils::utf16le::from_bytes(&buffer[..len as usize])?;
+
+        if let Some(dest) = resolve(doc, dest) {
+            entries.push((name, dest));
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Horizontal and vertical position and zoom factor of the given
+/// destination, where specified.
+fn location(
+    doc: &Document,
+    dest: pdfium_sys::FPDF_DEST,
+) -> (Option<f32>, Option<f32>, Option<f32>) {
+    let lib = doc.library();
+
+    let mut has_x = 0;
+    let mut has_y = 0;
+    let mut has_zoom = 0;
+    let mut x = 0.0;
+    let mut y = 0.0;
+    let mut zoom = 0.0;
+
+    let status = unsafe {
+        lib.ftable().FPDFDest_GetLocationInPage(
+            dest,
+            &mut has_x,
+            &mut has_y,
+            &mut has_zoom,
+            &mut x,
+            &mut y,
+            &mut zoom,
+        )
+    };
+
+    if status == 0 {
+        return (None, None, None);
+    }
+
+    // a zoom factor of zero means that the current zoom should be kept
+    (
+        (has_x != 0).then_some(x),
+        (has_y != 0).then_some(y),
+        (has_zoom != 0 && zoom > 0.0).then_some(zoom),
+    )
+}
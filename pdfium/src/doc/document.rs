@@ -1,12 +1,19 @@
-use super::{Metadata, Pages, Version};
+use super::{Bookmark, Destination, Metadata, Pages, Permissions, Security, Version};
+
+#[cfg(feature = "javascript")]
+use super::JavaScriptAction;
+
+#[cfg(feature = "signatures")]
+use super::Signature;
 
 use crate::bindings::Handle;
 use crate::io::fileaccess::ReaderAccess;
 use crate::utils::sync::{Rc, Unused};
-use crate::Library;
+use crate::{Library, Result};
 
 pub type DocumentHandle = Handle<pdfium_sys::fpdf_document_t__>;
 
+/// A loaded PDF document. Clones refer to the same document.
 #[derive(Clone)]
 pub struct Document {
     inner: Rc<DocumentInner>,
@@ -75,6 +82,50 @@ impl Document {
     pub fn pages(&self) -> Pages {
         Pages::new(self.library(), self)
     }
+
+    /// Whether the document is encrypted and which operations it permits.
+    pub fn security(&self) -> Security {
+        super::security::load(self)
+    }
+
+    /// Whether the document is encrypted, e.g. protected with a password.
+    pub fn is_encrypted(&self) -> bool {
+        self.security().is_encrypted()
+    }
+
+    /// The operations permitted on this document.
+    pub fn permissions(&self) -> Permissions {
+        self.security().permissions
+    }
+
+    /// The document outline (table of contents) as a tree of bookmarks.
+    pub fn outline(&self) -> Result<Vec<Bookmark>> {
+        super::outline::load(self)
+    }
+
+    /// Look up the destination with the given name, e.g. as referenced by
+    /// links from other documents.
+    pub fn named_destination(&self, name: &str) -> Option<Destination> {
+        super::dest::named(self, name)
+    }
+
+    /// All named destinations of this document with their names.
+    pub fn named_destinations(&self) -> Result<Vec<(String, Destination)>> {
+        super::dest::load_named(self)
+    }
+
+    /// The digital signatures of this document.
+    #[cfg(feature = "signatures")]
+    pub fn signatures(&self) -> Result<Vec<Signature>> {
+        super::signature::load(self)
+    }
+
+    /// The document-level JavaScript actions of this document, e.g. for
+    /// warning about documents containing scripts.
+    #[cfg(feature = "javascript")]
+    pub fn javascript_actions(&self) -> Result<Vec<JavaScriptAction>> {
+        super::javascript::load(self)
+    }
 }
 
 impl Drop for DocumentInner {
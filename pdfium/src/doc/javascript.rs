@@ -0,0 +1,88 @@
+use crate::doc::Document;
+use crate::{Capabilities, Result};
+
+/// A document-level JavaScript action, run by viewers supporting scripts
+/// when the document is opened.
+///
+/// This is provided for inspection only, pdfium is not asked to execute it.
+#[derive(Debug, Clone)]
+pub struct JavaScriptAction {
+    /// The name of the action in the document's JavaScript name tree.
+    pub name: String,
+
+    /// The source code of the script.
+    pub script: String,
+}
+
+pub(crate) fn load(doc: &Document) -> Result<Vec<JavaScriptAction>> {
+    let lib = doc.library();
+    lib.require(Capabilities::JavaScript)?;
+
+    let handle = doc.handle().get();
+
+    let count = unsafe { lib.ftable().FPDFDoc_GetJavaScriptActionCount(handle) };
+    lib.assert(count >= 0)?;
+
+    let mut actions = Vec::with_capacity(count as usize);
+
+    for index in 0..count {
+        let action = unsafe { lib.ftable().FPDFDoc_GetJavaScriptAction(handle, index) };
+
+        // entries may be skipped by pdfium if they are malformed, e.g. do not
+        // contain a script
+        if action.is_null() {
+            continue;
+        }
+
+        let name = string(doc, action, Field::Name);
+        let script = string(doc, action, Field::Script);
+
+        unsafe { lib.ftable().FPDFDoc_CloseJavaScriptAction(action) };
+
+        actions.push(JavaScriptAction {
+            name: name?,
+            script: script?,
+        });
+    }
+
+    Ok(actions)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Name,
+    Script,
+}
+
+fn string(
+    doc: &Document,
+    action: pdfium_sys::FPDF_JAVASCRIPT_ACTION,
+    field: Field,
+) -> Result<String> {
+    let lib = doc.library();
+    let ftable = lib.ftable();
+
+    let get = |buffer: *mut pdfium_sys::FPDF_WCHAR, len: _| unsafe {
+        match field {
+            Field::Name => ftable.FPDFJavaScriptAction_GetName(action, buffer, len),
+            Field::Script => ftable.FPDFJavaScriptAction_GetScript(action, buffer, len),
+        }
+    };
+
+    // get length, including trailing zeros
+    let len = get(std::ptr::null_mut(), 0);
+
+    // zero-length or null-terminator only: return empty string
+    if len <= 2 {
+        return Ok(String::new());
+    }
+
+    // get actual string as bytes
+    let mut buffer: Vec<u8> = vec![0; len as usize];
+    let res = get(buffer.as_mut_ptr() as *mut _, buffer.len() as _);
+
+    assert_eq!(res, len);
+
+    // convert bytes to string
+    crate::utils::utf16le::from_bytes(&buffer)
+}
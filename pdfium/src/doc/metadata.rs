@@ -17,6 +17,22 @@ impl<'a> Metadata<'a> {
         self.get_raw(tag.as_str())
     }
 
+    /// All standard entries present in the document information dictionary.
+    ///
+    /// Custom entries and XMP metadata cannot be enumerated via pdfium, but
+    /// custom entries of known name can be retrieved via [`Self::get_raw`].
+    pub fn entries(&self) -> Result<Vec<(MetadataTag, String)>> {
+        let mut entries = Vec::new();
+
+        for tag in MetadataTag::ALL {
+            if let Some(value) = self.get(tag)? {
+                entries.push((tag, value));
+            }
+        }
+
+        Ok(entries)
+    }
+
     pub fn get_raw(&self, tag: &str) -> Result<Option<String>> {
         let doc = self.doc.handle().get();
         let tag = CString::new(tag).unwrap();
@@ -63,9 +79,22 @@ pub enum MetadataTag {
     Producer,
     CreationDate,
     ModDate,
+    Trapped,
 }
 
 impl MetadataTag {
+    pub const ALL: [MetadataTag; 9] = [
+        MetadataTag::Title,
+        MetadataTag::Author,
+        MetadataTag::Subject,
+        MetadataTag::Keywords,
+        MetadataTag::Creator,
+        MetadataTag::Producer,
+        MetadataTag::CreationDate,
+        MetadataTag::ModDate,
+        MetadataTag::Trapped,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             MetadataTag::Title => "Title",
@@ -76,6 +105,7 @@ impl MetadataTag {
             MetadataTag::Producer => "Producer",
             MetadataTag::CreationDate => "CreationDate",
             MetadataTag::ModDate => "ModDate",
+            MetadataTag::Trapped => "Trapped",
         }
     }
 }
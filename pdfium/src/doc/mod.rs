@@ -1,16 +1,48 @@
+mod dest;
 mod document;
 mod metadata;
+mod outline;
 mod page;
 mod pages;
+mod security;
 mod version;
 
+#[cfg(feature = "javascript")]
+mod javascript;
+
+#[cfg(feature = "signatures")]
+mod signature;
+
+#[cfg(feature = "structure")]
+pub mod structure;
+
+pub use dest::Destination;
 pub use document::{Document, DocumentHandle};
 pub use metadata::{Metadata, MetadataTag};
+pub use outline::Bookmark;
 pub use page::{
-    Page, PageHandle, PageRenderLayout, PageRotation, ProgressiveRender, ProgressiveRenderStatus,
-    RenderFlags,
+    Link, LinkTarget, Page, PageHandle, PageRenderLayout, PageRotation, ProgressiveRender,
+    ProgressiveRenderStatus, RenderFlags,
 };
 pub use pages::Pages;
+pub use security::{Permissions, Security};
 pub use version::Version;
 
+#[cfg(feature = "annotations")]
+pub use page::{Annotation, AnnotationHandle, AnnotationSubtype};
+
+#[cfg(feature = "fonts")]
+pub use page::{Font, PageObjectType, TextObject};
+
+#[cfg(feature = "javascript")]
+pub use javascript::JavaScriptAction;
+
+#[cfg(feature = "signatures")]
+pub use signature::Signature;
+
+#[cfg(feature = "text")]
+pub use page::{
+    SearchFlags, TextLine, TextPage, TextPageHandle, TextParagraph, TextStructure, TextWord,
+};
+
 pub(crate) use document::DocumentBacking;
@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+use std::ffi::c_void;
+
+use crate::doc::{dest, Destination, Document};
+use crate::Result;
+
+/// An entry of the document outline, also known as bookmark.
+#[derive(Debug, Clone)]
+pub struct Bookmark {
+    /// The title of this entry.
+    pub title: String,
+
+    /// The location this entry points to, if it points into the document.
+    pub dest: Option<Destination>,
+
+    /// Child entries, e.g. sections of a chapter.
+    pub children: Vec<Bookmark>,
+}
+
+pub(crate) fn load(doc: &Document) -> Result<Vec<Bookmark>> {
+    // outlines of malformed documents may contain cycles, so keep track of
+    // what we have already visited
+    let mut visited = HashSet::new();
+
+    load_children(doc, std::ptr::null_mut(), &mut visited)
+}
+
+fn load_children(
+    doc: &Document,
+    parent: pdfium_sys::FPDF_BOOKMARK,
+    visited: &mut HashSet<pdfium_sys::FPDF_BOOKMARK>,
+) -> Result<Vec<Bookmark>> {
+    let lib = doc.library();
+    let handle = doc.handle().get();
+
+    let mut entries = Vec::new();
+
+    let mut bookmark = unsafe { lib.ftable().FPDFBookmark_GetFirstChild(handle, parent) };
+
+    while !bookmark.is_null() && visited.insert(bookmark) {
+        let dest = target(doc, bookmark).and_then(|d| dest::resolve(doc, d));
+
+        entries.push(Bookmark {
+            title: title(doc, bookmark)?,
+            dest,
+            children: load_children(doc, bookmark, visited)?,
+        });
+
+        bookmark = unsafe { lib.ftable().FPDFBookmark_GetNextSibling(handle, bookmark) };
+    }
+
+    Ok(entries)
+}
+
+fn title(doc: &Document, bookmark: pdfium_sys::FPDF_BOOKMARK) -> Result<String> {
+    let lib = doc.library();
+
+    // get length, including trailing zeros
+    let len = unsafe {
+        lib.ftable()
+            .FPDFBookmark_GetTitle(bookmark, std::ptr::null_mut(), 0)
+    };
+
+    // zero-length or null-terminator only: return empty string
+    if len <= 2 {
+        return Ok(String::new());
+    }
+
+    // get actual string as bytes
+    let mut buffer: Vec<u8> = vec![0; len as usize];
+    let buffer_p = buffer.as_mut_ptr() as *mut c_void;
+
+    let res = unsafe {
+        lib.ftable()
+            .FPDFBookmark_GetTitle(bookmark, buffer_p, buffer.len() as _)
+    };
+
+    assert_eq!(res, len);
+
+    // convert bytes to string
+    crate::utils::utf16le::from_bytes(&buffer)
+}
+
+fn target(doc: &Document, bookmark: pdfium_sys::FPDF_BOOKMARK) -> Option<pdfium_sys::FPDF_DEST> {
+    let lib = doc.library();
+    let handle = doc.handle().get();
+
+    // the destination can be specified directly or via a "go to" action
+    let mut dest = unsafe { lib.ftable().FPDFBookmark_GetDest(handle, bookmark) };
+
+    if dest.is_null() {
+        let action = unsafe { lib.ftable().FPDFBookmark_GetAction(bookmark) };
+        if action.is_null() {
+            return None;
+        }
+
+        let ty = unsafe { lib.ftable().FPDFAction_GetType(action) };
+        if ty != pdfium_sys::PDFACTION_GOTO as _ {
+            return None;
+        }
+
+        dest = unsafe { lib.ftable().FPDFAction_GetDest(handle, action) };
+        if dest.is_null() {
+            return None;
+        }
+    }
+
+    Some(dest)
+}
@@ -0,0 +1,662 @@
+use std::ffi::{c_void, CString};
+
+use crate::bindings::Handle;
+use crate::bitmap::{Bitmap, Color};
+use crate::doc::Page;
+use crate::types::{Point2, Quad, Rect};
+use crate::{Capabilities, Result};
+
+pub type AnnotationHandle = Handle<pdfium_sys::fpdf_annotation_t__>;
+
+type PageObjectHandle = Handle<pdfium_sys::fpdf_pageobject_t__>;
+
+/// Subtype of an annotation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AnnotationSubtype {
+    /// Text annotation, i.e. a "sticky note".
+    Text,
+    Link,
+    FreeText,
+    Line,
+    Square,
+    Circle,
+    Highlight,
+    Underline,
+    Squiggly,
+    StrikeOut,
+    Stamp,
+    Ink,
+    Popup,
+    Widget,
+
+    /// Any other or unknown subtype.
+    Other(i32),
+}
+
+/// An annotation of a page.
+pub struct Annotation {
+    page: Page,
+    handle: AnnotationHandle,
+}
+
+impl AnnotationSubtype {
+    pub(crate) fn from_i32(value: i32) -> Self {
+        match value as u32 {
+            pdfium_sys::FPDF_ANNOT_TEXT => Self::Text,
+            pdfium_sys::FPDF_ANNOT_LINK => Self::Link,
+            pdfium_sys::FPDF_ANNOT_FREETEXT => Self::FreeText,
+            pdfium_sys::FPDF_ANNOT_LINE => Self::Line,
+            pdfium_sys::FPDF_ANNOT_SQUARE => Self::Square,
+            pdfium_sys::FPDF_ANNOT_CIRCLE => Self::Circle,
+            pdfium_sys::FPDF_ANNOT_HIGHLIGHT => Self::Highlight,
+            pdfium_sys::FPDF_ANNOT_UNDERLINE => Self::Underline,
+            pdfium_sys::FPDF_ANNOT_SQUIGGLY => Self::Squiggly,
+            pdfium_sys::FPDF_ANNOT_STRIKEOUT => Self::StrikeOut,
+            pdfium_sys::FPDF_ANNOT_STAMP => Self::Stamp,
+            pdfium_sys::FPDF_ANNOT_INK => Self::Ink,
+            pdfium_sys::FPDF_ANNOT_POPUP => Self::Popup,
+            pdfium_sys::FPDF_ANNOT_WIDGET => Self::Widget,
+            _ => Self::Other(value),
+        }
+    }
+
+    pub(crate) fn as_i32(&self) -> i32 {
+        let value = match self {
+            Self::Text => pdfium_sys::FPDF_ANNOT_TEXT,
+            Self::Link => pdfium_sys::FPDF_ANNOT_LINK,
+            Self::FreeText => pdfium_sys::FPDF_ANNOT_FREETEXT,
+            Self::Line => pdfium_sys::FPDF_ANNOT_LINE,
+            Self::Square => pdfium_sys::FPDF_ANNOT_SQUARE,
+            Self::Circle => pdfium_sys::FPDF_ANNOT_CIRCLE,
+            Self::Highlight => pdfium_sys::FPDF_ANNOT_HIGHLIGHT,
+            Self::Underline => pdfium_sys::FPDF_ANNOT_UNDERLINE,
+            Self::Squiggly => pdfium_sys::FPDF_ANNOT_SQUIGGLY,
+            Self::StrikeOut => pdfium_sys::FPDF_ANNOT_STRIKEOUT,
+            Self::Stamp => pdfium_sys::FPDF_ANNOT_STAMP,
+            Self::Ink => pdfium_sys::FPDF_ANNOT_INK,
+            Self::Popup => pdfium_sys::FPDF_ANNOT_POPUP,
+            Self::Widget => pdfium_sys::FPDF_ANNOT_WIDGET,
+            Self::Other(value) => return *value,
+        };
+
+        value as i32
+    }
+}
+
+impl Annotation {
+    pub(crate) fn new(page: Page, index: u32) -> Result<Self> {
+        let lib = page.library();
+        lib.require(Capabilities::Annotations)?;
+
+        let handle = unsafe {
+            lib.ftable()
+                .FPDFPage_GetAnnot(page.handle().get(), index as _)
+        };
+        let handle = lib.assert_handle(handle)?;
+
+        Ok(Self { page, handle })
+    }
+
+    pub(crate) fn create(page: Page, subtype: AnnotationSubtype) -> Result<Self> {
+        let lib = page.library();
+        lib.require(Capabilities::Annotations)?;
+
+        let handle = unsafe {
+            lib.ftable()
+                .FPDFPage_CreateAnnot(page.handle().get(), subtype.as_i32() as _)
+        };
+        let handle = lib.assert_handle(handle)?;
+
+        Ok(Self { page, handle })
+    }
+
+    pub fn handle(&self) -> &AnnotationHandle {
+        &self.handle
+    }
+
+    pub fn page(&self) -> &Page {
+        &self.page
+    }
+
+    pub fn subtype(&self) -> AnnotationSubtype {
+        let ty = unsafe {
+            self.page
+                .library()
+                .ftable()
+                .FPDFAnnot_GetSubtype(self.handle.get())
+        };
+
+        AnnotationSubtype::from_i32(ty as _)
+    }
+
+    /// The annotation flags, see `FPDF_ANNOT_FLAG_*`.
+    pub fn flags(&self) -> u32 {
+        let flags = unsafe {
+            self.page
+                .library()
+                .ftable()
+                .FPDFAnnot_GetFlags(self.handle.get())
+        };
+
+        flags as u32
+    }
+
+    /// Set the annotation flags, see `FPDF_ANNOT_FLAG_*`.
+    pub fn set_flags(&self, flags: u32) -> Result<()> {
+        let lib = self.page.library();
+
+        let status = unsafe {
+            lib.ftable()
+                .FPDFAnnot_SetFlags(self.handle.get(), flags as _)
+        };
+        lib.assert(status != 0)
+    }
+
+    /// Check if this annotation should not be displayed.
+    pub fn is_hidden(&self) -> bool {
+        let flags = unsafe {
+            self.page
+                .library()
+                .ftable()
+                .FPDFAnnot_GetFlags(self.handle.get())
+        };
+
+        flags as u32 & pdfium_sys::FPDF_ANNOT_FLAG_HIDDEN != 0
+    }
+
+    /// The bounds of this annotation, in PDF page coordinates.
+    pub fn rect(&self) -> Result<Rect> {
+        let lib = self.page.library();
+
+        let mut rect = pdfium_sys::FS_RECTF {
+            left: 0.0,
+            top: 0.0,
+            right: 0.0,
+            bottom: 0.0,
+        };
+
+        let status = unsafe { lib.ftable().FPDFAnnot_GetRect(self.handle.get(), &mut rect) };
+        lib.assert(status != 0)?;
+
+        Ok(Rect::from(rect))
+    }
+
+    /// Set the bounds of this annotation, in PDF page coordinates.
+    pub fn set_rect(&self, rect: &Rect) -> Result<()> {
+        let lib = self.page.library();
+        let rect = pdfium_sys::FS_RECTF::from(rect);
+
+        let status = unsafe { lib.ftable().FPDFAnnot_SetRect(self.handle.get(), &rect) };
+        lib.assert(status != 0)
+    }
+
+    /// The color of this annotation, e.g. of its border or of the icon of a
+    /// note, if set.
+    pub fn color(&self) -> Option<Color> {
+        self.color_of(pdfium_sys::FPDFANNOT_COLORTYPE_FPDFANNOT_COLORTYPE_Color)
+    }
+
+    /// Set the color of this annotation.
+    pub fn set_color(&self, color: Color) -> Result<()> {
+        self.set_color_of(
+            pdfium_sys::FPDFANNOT_COLORTYPE_FPDFANNOT_COLORTYPE_Color,
+            color,
+        )
+    }
+
+    /// The interior color of this annotation, e.g. the fill of a square, if
+    /// set.
+    pub fn interior_color(&self) -> Option<Color> {
+        self.color_of(pdfium_sys::FPDFANNOT_COLORTYPE_FPDFANNOT_COLORTYPE_InteriorColor)
+    }
+
+    /// Set the interior color of this annotation.
+    pub fn set_interior_color(&self, color: Color) -> Result<()> {
+        self.set_color_of(
+            pdfium_sys::FPDFANNOT_COLORTYPE_FPDFANNOT_COLORTYPE_InteriorColor,
+            color,
+        )
+    }
+
+    fn color_of(&self, ty: pdfium_sys::FPDFANNOT_COLORTYPE) -> Option<Color> {
+        let (mut r, mut g, mut b, mut a) = (0, 0, 0, 0);
+
+        let status = unsafe {
+            self.page.library().ftable().FPDFAnnot_GetColor(
+                self.handle.get(),
+                ty,
+                &mut r,
+                &mut g,
+                &mut b,
+                &mut a,
+            )
+        };
+
+        (status != 0).then(|| Color::new_rgba(r as _, g as _, b as _, a as _))
+    }
+
+    fn set_color_of(&self, ty: pdfium_sys::FPDFANNOT_COLORTYPE, color: Color) -> Result<()> {
+        let lib = self.page.library();
+
+        let status = unsafe {
+            lib.ftable().FPDFAnnot_SetColor(
+                self.handle.get(),
+                ty,
+                color.r as _,
+                color.g as _,
+                color.b as _,
+                color.a as _,
+            )
+        };
+
+        lib.assert(status != 0)
+    }
+
+    /// The quadrilaterals covered by this annotation, e.g. the lines of text
+    /// marked by a highlight.
+    ///
+    /// Only markup annotations (highlight, underline, squiggly, strike-out)
+    /// and links have attachment points.
+    pub fn attachment_points(&self) -> Result<Vec<Quad>> {
+        let lib = self.page.library();
+        let annot = self.handle.get();
+
+        let count = unsafe { lib.ftable().FPDFAnnot_CountAttachmentPoints(annot) };
+
+        (0..count)
+            .map(|index| {
+                let mut quad = pdfium_sys::FS_QUADPOINTSF {
+                    x1: 0.0,
+                    y1: 0.0,
+                    x2: 0.0,
+                    y2: 0.0,
+                    x3: 0.0,
+                    y3: 0.0,
+                    x4: 0.0,
+                    y4: 0.0,
+                };
+
+                let status = unsafe {
+                    lib.ftable()
+                        .FPDFAnnot_GetAttachmentPoints(annot, index, &mut quad)
+                };
+                lib.assert(status != 0)?;
+
+                Ok(Quad::from(quad))
+            })
+            .collect()
+    }
+
+    /// Replace the quadrilateral at the given index.
+    pub fn set_attachment_points(&self, index: usize, quad: &Quad) -> Result<()> {
+        let lib = self.page.library();
+        let quad = pdfium_sys::FS_QUADPOINTSF::from(quad);
+
+        let status = unsafe {
+            lib.ftable()
+                .FPDFAnnot_SetAttachmentPoints(self.handle.get(), index, &quad)
+        };
+        lib.assert(status != 0)
+    }
+
+    /// Add a quadrilateral to the area covered by this annotation, e.g. for
+    /// each line of a highlighted text selection.
+    ///
+    /// Note that this does not update the bounds of the annotation, which
+    /// should be set to enclose all quadrilaterals via
+    /// [`set_rect()`](Self::set_rect).
+    pub fn append_attachment_points(&self, quad: &Quad) -> Result<()> {
+        let lib = self.page.library();
+        let quad = pdfium_sys::FS_QUADPOINTSF::from(quad);
+
+        let status = unsafe {
+            lib.ftable()
+                .FPDFAnnot_AppendAttachmentPoints(self.handle.get(), &quad)
+        };
+        lib.assert(status != 0)
+    }
+
+    /// The strokes of this ink annotation, in PDF page coordinates.
+    pub fn ink_strokes(&self) -> Result<Vec<Vec<Point2<f32>>>> {
+        let lib = self.page.library();
+        let annot = self.handle.get();
+
+        let count = unsafe { lib.ftable().FPDFAnnot_GetInkListCount(annot) };
+
+        (0..count)
+            .map(|index| {
+                // get number of points
+                let len = unsafe {
+                    lib.ftable()
+                        .FPDFAnnot_GetInkListPath(annot, index, std::ptr::null_mut(), 0)
+                };
+
+                // get actual points
+                let mut buffer = vec![pdfium_sys::FS_POINTF { x: 0.0, y: 0.0 }; len as usize];
+
+                let res = unsafe {
+                    lib.ftable().FPDFAnnot_GetInkListPath(
+                        annot,
+                        index,
+                        buffer.as_mut_ptr(),
+                        buffer.len() as _,
+                    )
+                };
+                lib.assert(res == len)?;
+
+                Ok(buffer.iter().map(|p| Point2::new(p.x, p.y)).collect())
+            })
+            .collect()
+    }
+
+    /// Add a stroke through the given points, in PDF page coordinates, to this
+    /// ink annotation, returning its index.
+    ///
+    /// This only adds the stroke to the ink list of the annotation, which
+    /// viewers may use to regenerate its appearance. Use
+    /// [`append_path()`](Self::append_path) to make the stroke visible when
+    /// rendering the page via pdfium.
+    pub fn add_ink_stroke(&self, points: &[Point2<f32>]) -> Result<u32> {
+        if points.is_empty() {
+            return Err(crate::Error::InvalidArgument);
+        }
+
+        let lib = self.page.library();
+
+        let points: Vec<_> = points
+            .iter()
+            .map(|p| pdfium_sys::FS_POINTF { x: p.x, y: p.y })
+            .collect();
+
+        let index = unsafe {
+            lib.ftable()
+                .FPDFAnnot_AddInkStroke(self.handle.get(), points.as_ptr(), points.len())
+        };
+        lib.assert(index >= 0)?;
+
+        Ok(index as u32)
+    }
+
+    /// Remove all strokes from this ink annotation.
+    pub fn remove_ink_strokes(&self) -> Result<()> {
+        let lib = self.page.library();
+
+        let status = unsafe { lib.ftable().FPDFAnnot_RemoveInkList(self.handle.get()) };
+        lib.assert(status != 0)
+    }
+
+    /// Add a line through the given points, in PDF page coordinates, to the
+    /// appearance of this annotation.
+    ///
+    /// Only ink and stamp annotations support page objects in their
+    /// appearance, fails with [`InvalidOperation`](crate::Error::InvalidOperation)
+    /// for any other subtype.
+    pub fn append_path(&self, points: &[Point2<f32>], color: Color, width: f32) -> Result<()> {
+        let (first, rest) = points.split_first().ok_or(crate::Error::InvalidArgument)?;
+
+        self.require_appearance_objects()?;
+
+        let lib = self.page.library();
+
+        let path = unsafe { lib.ftable().FPDFPageObj_CreateNewPath(first.x, first.y) };
+        let path = lib.assert_handle(path)?;
+
+        self.append_object(&path, || {
+            for p in rest {
+                let status = unsafe { lib.ftable().FPDFPath_LineTo(path.get(), p.x, p.y) };
+                lib.assert(status != 0)?;
+            }
+
+            let status = unsafe {
+                lib.ftable().FPDFPageObj_SetStrokeColor(
+                    path.get(),
+                    color.r as _,
+                    color.g as _,
+                    color.b as _,
+                    color.a as _,
+                )
+            };
+            lib.assert(status != 0)?;
+
+            let status = unsafe { lib.ftable().FPDFPageObj_SetStrokeWidth(path.get(), width) };
+            lib.assert(status != 0)?;
+
+            let status = unsafe {
+                lib.ftable().FPDFPath_SetDrawMode(
+                    path.get(),
+                    pdfium_sys::FPDF_FILLMODE_NONE as _,
+                    1,
+                )
+            };
+            lib.assert(status != 0)
+        })
+    }
+
+    /// Add the given text in one of the standard 14 fonts, e.g.
+    /// `Helvetica-Bold`, to the appearance of this annotation, scaled to fit
+    /// and centered in the given rectangle in PDF page coordinates.
+    ///
+    /// Only ink and stamp annotations support page objects in their
+    /// appearance, fails with [`InvalidOperation`](crate::Error::InvalidOperation)
+    /// for any other subtype.
+    pub fn append_text(&self, text: &str, font: &str, color: Color, rect: &Rect) -> Result<()> {
+        if text.is_empty() {
+            return Err(crate::Error::InvalidArgument);
+        }
+
+        self.require_appearance_objects()?;
+
+        let lib = self.page.library();
+        let doc = self.page.document().handle().get();
+        let font = CString::new(font).map_err(|_| crate::Error::InvalidArgument)?;
+
+        // lay out at unit size, then scale to fit
+        let obj = unsafe { lib.ftable().FPDFPageObj_NewTextObj(doc, font.as_ptr(), 1.0) };
+        let obj = lib.assert_handle(obj)?;
+
+        self.append_object(&obj, || {
+            let text = crate::utils::utf16le::to_wide(text);
+
+            let status = unsafe { lib.ftable().FPDFText_SetText(obj.get(), text.as_ptr()) };
+            lib.assert(status != 0)?;
+
+            let status = unsafe {
+                lib.ftable().FPDFPageObj_SetFillColor(
+                    obj.get(),
+                    color.r as _,
+                    color.g as _,
+                    color.b as _,
+                    color.a as _,
+                )
+            };
+            lib.assert(status != 0)?;
+
+            let (mut left, mut bottom, mut right, mut top) = (0.0, 0.0, 0.0, 0.0);
+            let status = unsafe {
+                lib.ftable().FPDFPageObj_GetBounds(
+                    obj.get(),
+                    &mut left,
+                    &mut bottom,
+                    &mut right,
+                    &mut top,
+                )
+            };
+            lib.assert(status != 0)?;
+
+            let width = (rect.right - rect.left).abs();
+            let height = (rect.top - rect.bottom).abs();
+            let scale = (width / (right - left).max(f32::EPSILON))
+                .min(height / (top - bottom).max(f32::EPSILON));
+
+            let dx = (rect.left + rect.right) / 2.0 - scale * (left + right) / 2.0;
+            let dy = (rect.bottom + rect.top) / 2.0 - scale * (bottom + top) / 2.0;
+
+            unsafe {
+                lib.ftable().FPDFPageObj_Transform(
+                    obj.get(),
+                    scale as _,
+                    0.0,
+                    0.0,
+                    scale as _,
+                    dx as _,
+                    dy as _,
+                )
+            };
+
+            Ok(())
+        })
+    }
+
+    /// Add the given image to the appearance of this annotation, stretched to
+    /// the given rectangle in PDF page coordinates.
+    ///
+    /// Only ink and stamp annotations support page objects in their
+    /// appearance, fails with [`InvalidOperation`](crate::Error::InvalidOperation)
+    /// for any other subtype.
+    pub fn append_image<C>(&self, bitmap: &Bitmap<C>, rect: &Rect) -> Result<()> {
+        self.require_appearance_objects()?;
+
+        let lib = self.page.library();
+        let doc = self.page.document().handle().get();
+
+        let obj = unsafe { lib.ftable().FPDFPageObj_NewImageObj(doc) };
+        let obj = lib.assert_handle(obj)?;
+
+        self.append_object(&obj, || {
+            let status = unsafe {
+                lib.ftable().FPDFImageObj_SetBitmap(
+                    std::ptr::null_mut(),
+                    0,
+                    obj.get(),
+                    bitmap.handle().get(),
+                )
+            };
+            lib.assert(status != 0)?;
+
+            // images are drawn into the unit square
+            let left = rect.left.min(rect.right);
+            let bottom = rect.bottom.min(rect.top);
+            let width = (rect.right - rect.left).abs();
+            let height = (rect.top - rect.bottom).abs();
+
+            unsafe {
+                lib.ftable().FPDFPageObj_Transform(
+                    obj.get(),
+                    width as _,
+                    0.0,
+                    0.0,
+                    height as _,
+                    left as _,
+                    bottom as _,
+                )
+            };
+
+            Ok(())
+        })
+    }
+
+    /// Fail unless the appearance of this annotation can be extended by page
+    /// objects.
+    fn require_appearance_objects(&self) -> Result<()> {
+        match self.subtype() {
+            AnnotationSubtype::Ink | AnnotationSubtype::Stamp => Ok(()),
+            _ => Err(crate::Error::InvalidOperation),
+        }
+    }
+
+    /// Set up the given newly created page object via `build` and append it
+    /// to the appearance of this annotation, destroying it on failure.
+    fn append_object<F>(&self, object: &PageObjectHandle, build: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        let lib = self.page.library();
+
+        let result = build().and_then(|_| {
+            // on success, the annotation takes ownership of the object
+            let status = unsafe {
+                lib.ftable()
+                    .FPDFAnnot_AppendObject(self.handle.get(), object.get())
+            };
+            lib.assert(status != 0)
+        });
+
+        if result.is_err() {
+            unsafe { lib.ftable().FPDFPageObj_Destroy(object.get()) };
+        }
+
+        result
+    }
+
+    /// The text content of this annotation, e.g. the text of a note.
+    pub fn contents(&self) -> Result<Option<String>> {
+        self.string_value("Contents")
+    }
+
+    /// Set the text content of this annotation.
+    pub fn set_contents(&self, value: &str) -> Result<()> {
+        self.set_string_value("Contents", value)
+    }
+
+    /// The value of the given string entry of the annotation dictionary.
+    pub fn string_value(&self, key: &str) -> Result<Option<String>> {
+        let lib = self.page.library();
+        let annot = self.handle.get();
+        let key = CString::new(key).map_err(|_| crate::Error::InvalidArgument)?;
+
+        // get length, including trailing zeros
+        let len = unsafe {
+            lib.ftable()
+                .FPDFAnnot_GetStringValue(annot, key.as_ptr(), std::ptr::null_mut(), 0)
+        };
+
+        // zero-length or null-terminator only means the value is not present
+        if len <= 2 {
+            return Ok(None);
+        }
+
+        // get actual string as bytes
+        let mut buffer: Vec<u8> = vec![0; len as usize];
+        let buffer_p = buffer.as_mut_ptr() as *mut c_void;
+
+        let res = unsafe {
+            lib.ftable().FPDFAnnot_GetStringValue(
+                annot,
+                key.as_ptr(),
+                buffer_p as *mut _,
+                buffer.len() as _,
+            )
+        };
+
+        assert_eq!(res, len);
+
+        // convert bytes to string
+        let value = crate::utils::utf16le::from_bytes(&buffer)?;
+        Ok(Some(value))
+    }
+
+    /// Set the value of the given string entry of the annotation dictionary.
+    pub fn set_string_value(&self, key: &str, value: &str) -> Result<()> {
+        let lib = self.page.library();
+        let key = CString::new(key).map_err(|_| crate::Error::InvalidArgument)?;
+        let value = crate::utils::utf16le::to_wide(value);
+
+        let status = unsafe {
+            lib.ftable()
+                .FPDFAnnot_SetStringValue(self.handle.get(), key.as_ptr(), value.as_ptr())
+        };
+
+        lib.assert(status != 0)
+    }
+}
+
+impl Drop for Annotation {
+    fn drop(&mut self) {
+        unsafe {
+            self.page
+                .library()
+                .ftable()
+                .FPDFPage_CloseAnnot(self.handle.get())
+        };
+    }
+}
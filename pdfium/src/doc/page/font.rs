@@ -0,0 +1,113 @@
+use std::ffi::c_char;
+
+use crate::doc::Page;
+use crate::types::Rect;
+use crate::{Capabilities, Result};
+
+/// A font used by a page.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Font {
+    /// The name of the font, e.g. `Helvetica-Bold`.
+    pub name: String,
+
+    /// Whether the font program is embedded in the document. Fonts that are
+    /// not embedded are substituted by a system font when rendering.
+    pub is_embedded: bool,
+}
+
+/// A text object of a page, i.e. a run of text drawn with a single font.
+#[derive(Debug, Clone)]
+pub struct TextObject {
+    /// The font used for drawing the text.
+    pub font: Font,
+
+    /// The bounds of the text, in PDF page coordinates.
+    pub bounds: Rect,
+}
+
+pub(crate) fn text_objects(page: &Page) -> Result<Vec<TextObject>> {
+    let lib = page.library();
+    lib.require(Capabilities::Fonts)?;
+
+    let handle = page.handle().get();
+
+    let count = unsafe { lib.ftable().FPDFPage_CountObjects(handle) };
+    lib.assert(count >= 0)?;
+
+    let mut objects = Vec::new();
+
+    // Note: This only covers objects directly placed on the page, not ones
+    // nested in form XObjects.
+    for index in 0..count {
+        let obj = unsafe { lib.ftable().FPDFPage_GetObject(handle, index) };
+        let obj = lib.assert_handle(obj)?;
+
+        let ty = unsafe { lib.ftable().FPDFPageObj_GetType(obj.get()) };
+        if ty as u32 != pdfium_sys::FPDF_PAGEOBJ_TEXT {
+            continue;
+        }
+
+        // the font is owned by the text object, so it must not be closed
+        let font = unsafe { lib.ftable().FPDFTextObj_GetFont(obj.get()) };
+        let font = lib.assert_handle(font)?;
+
+        let embedded = unsafe { lib.ftable().FPDFFont_GetIsEmbedded(font.get()) };
+        lib.assert(embedded >= 0)?;
+
+        let mut bounds = pdfium_sys::FS_RECTF {
+            left: 0.0,
+            top: 0.0,
+            right: 0.0,
+            bottom: 0.0,
+        };
+
+        let status = unsafe {
+            lib.ftable().FPDFPageObj_GetBounds(
+                obj.get(),
+                &mut bounds.left,
+                &mut bounds.bottom,
+                &mut bounds.right,
+                &mut bounds.top,
+            )
+        };
+        lib.assert(status != 0)?;
+
+        objects.push(TextObject {
+            font: Font {
+                name: font_name(page, font.get()),
+                is_embedded: embedded != 0,
+            },
+            bounds: Rect::from(bounds),
+        });
+    }
+
+    Ok(objects)
+}
+
+fn font_name(page: &Page, font: pdfium_sys::FPDF_FONT) -> String {
+    let lib = page.library();
+
+    // get length, including trailing zero
+    let len = unsafe {
+        lib.ftable()
+            .FPDFFont_GetFontName(font, std::ptr::null_mut(), 0)
+    };
+
+    // zero-length or null-terminator only: return empty string
+    if len <= 1 {
+        return String::new();
+    }
+
+    // get actual string as bytes
+    let mut buffer: Vec<u8> = vec![0; len as usize];
+    let res = unsafe {
+        lib.ftable()
+            .FPDFFont_GetFontName(font, buffer.as_mut_ptr() as *mut c_char, len)
+    };
+
+    assert_eq!(res, len);
+
+    // strip trailing zero, the name is always UTF-8
+    buffer.truncate(len as usize - 1);
+    String::from_utf8_lossy(&buffer).into_owned()
+}
@@ -0,0 +1,148 @@
+use std::ffi::{c_int, c_void};
+
+use crate::doc::{dest, Destination, Document};
+use crate::types::Rect;
+
+/// A link of a page, i.e. an area pointing to another location.
+#[derive(Debug, Clone)]
+pub struct Link {
+    /// Area of the link, in PDF page coordinates.
+    pub rect: Rect,
+
+    /// The location this link points to.
+    pub target: LinkTarget,
+}
+
+/// The location a link points to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkTarget {
+    /// A position on a page of the same document.
+    Page(Destination),
+
+    /// An external resource, e.g. a web page.
+    Uri(String),
+
+    /// Anything else, e.g. another document or an application to launch.
+    Other,
+}
+
+/// Enumerate all links of the given page.
+pub(super) fn links(doc: &Document, page: pdfium_sys::FPDF_PAGE) -> Vec<Link> {
+    let lib = doc.library();
+
+    let mut links = Vec::new();
+    let mut pos: c_int = 0;
+    let mut link: pdfium_sys::FPDF_LINK = std::ptr::null_mut();
+
+    while unsafe { lib.ftable().FPDFLink_Enumerate(page, &mut pos, &mut link) } != 0 {
+        let mut rect = pdfium_sys::FS_RECTF {
+            left: 0.0,
+            top: 0.0,
+            right: 0.0,
+            bottom: 0.0,
+        };
+
+        if unsafe { lib.ftable().FPDFLink_GetAnnotRect(link, &mut rect) } == 0 {
+            continue;
+        }
+
+        links.push(Link {
+            rect: Rect::from(rect),
+            target: target(doc, link),
+        });
+    }
+
+    links
+}
+
+/// The link at the given point in PDF page coordinates, if any.
+pub(super) fn link_at(doc: &Document, page: pdfium_sys::FPDF_PAGE, x: f64, y: f64) -> Option<Link> {
+    let lib = doc.library();
+
+    let link = unsafe { lib.ftable().FPDFLink_GetLinkAtPoint(page, x, y) };
+    if link.is_null() {
+        return None;
+    }
+
+    let mut rect = pdfium_sys::FS_RECTF {
+        left: 0.0,
+        top: 0.0,
+        right: 0.0,
+        bottom: 0.0,
+    };
+
+    if unsafe { lib.ftable().FPDFLink_GetAnnotRect(link, &mut rect) } == 0 {
+        return None;
+    }
+
+    Some(Link {
+        rect: Rect::from(rect),
+        target: target(doc, link),
+    })
+}
+
+fn target(doc: &Document, link: pdfium_sys::FPDF_LINK) -> LinkTarget {
+    let lib = doc.library();
+    let handle = doc.handle().get();
+
+    // links either have a destination or an action
+    let mut dest = unsafe { lib.ftable().FPDFLink_GetDest(handle, link) };
+
+    if dest.is_null() {
+        let action = unsafe { lib.ftable().FPDFLink_GetAction(link) };
+        if action.is_null() {
+            return LinkTarget::Other;
+        }
+
+        let ty = unsafe { lib.ftable().FPDFAction_GetType(action) };
+
+        if ty == pdfium_sys::PDFACTION_URI as _ {
+            return match uri(doc, action) {
+                Some(uri) => LinkTarget::Uri(uri),
+                None => LinkTarget::Other,
+            };
+        } else if ty == pdfium_sys::PDFACTION_GOTO as _ {
+            dest = unsafe { lib.ftable().FPDFAction_GetDest(handle, action) };
+        }
+
+        if dest.is_null() {
+            return LinkTarget::Other;
+        }
+    }
+
+    match dest::resolve(doc, dest) {
+        Some(dest) => LinkTarget::Page(dest),
+        None => LinkTarget::Other,
+    }
+}
+
+/// The URI of the given URI action.
+fn uri(doc: &Document, action: pdfium_sys::FPDF_ACTION) -> Option<String> {
+    let lib = doc.library();
+    let doc = doc.handle().get();
+
+    // get length, including trailing zero
+    let len = unsafe {
+        lib.ftable()
+            .FPDFAction_GetURIPath(doc, action, std::ptr::null_mut(), 0)
+    };
+
+    if len <= 1 {
+        return None;
+    }
+
+    // get actual string as bytes
+    let mut buffer: Vec<u8> = vec![0; len as usize];
+    let buffer_p = buffer.as_mut_ptr() as *mut c_void;
+
+    let res = unsafe {
+        lib.ftable()
+            .FPDFAction_GetURIPath(doc, action, buffer_p, buffer.len() as _)
+    };
+
+    assert_eq!(res, len);
+
+    // strip trailing zero, the path may be badly encoded
+    buffer.truncate(len as usize - 1);
+    String::from_utf8(buffer).ok()
+}
@@ -1,6 +1,34 @@
+#[cfg(feature = "annotations")]
+mod annot;
+#[cfg(feature = "annotations")]
+pub use annot::{Annotation, AnnotationHandle, AnnotationSubtype};
+
+#[cfg(feature = "fonts")]
+mod font;
+#[cfg(feature = "fonts")]
+pub use font::{Font, TextObject};
+
+#[cfg(feature = "fonts")]
+mod object;
+#[cfg(feature = "fonts")]
+pub use object::PageObjectType;
+
 mod render;
 pub use render::progressive::{ProgressiveRender, ProgressiveRenderStatus};
 pub use render::{PageRenderLayout, PageRotation, RenderFlags};
 
+mod link;
+pub use link::{Link, LinkTarget};
+
 mod page;
 pub use page::{Page, PageHandle};
+
+#[cfg(feature = "text")]
+mod text;
+#[cfg(feature = "text")]
+pub use text::{SearchFlags, TextPage, TextPageHandle};
+
+#[cfg(feature = "text")]
+mod words;
+#[cfg(feature = "text")]
+pub use words::{TextLine, TextParagraph, TextStructure, TextWord};
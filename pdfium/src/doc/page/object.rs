@@ -0,0 +1,52 @@
+use crate::doc::Page;
+use crate::{Capabilities, Result};
+
+/// Type of a page object.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PageObjectType {
+    Text,
+    Path,
+    Image,
+    Shading,
+
+    /// Form XObject, i.e. a group of nested page objects.
+    Form,
+
+    /// Any other or unknown type.
+    Other(i32),
+}
+
+impl PageObjectType {
+    fn from_i32(value: i32) -> Self {
+        match value as u32 {
+            pdfium_sys::FPDF_PAGEOBJ_TEXT => Self::Text,
+            pdfium_sys::FPDF_PAGEOBJ_PATH => Self::Path,
+            pdfium_sys::FPDF_PAGEOBJ_IMAGE => Self::Image,
+            pdfium_sys::FPDF_PAGEOBJ_SHADING => Self::Shading,
+            pdfium_sys::FPDF_PAGEOBJ_FORM => Self::Form,
+            _ => Self::Other(value),
+        }
+    }
+}
+
+pub(crate) fn object_types(page: &Page) -> Result<Vec<PageObjectType>> {
+    let lib = page.library();
+    lib.require(Capabilities::Fonts)?;
+
+    let handle = page.handle().get();
+
+    let count = unsafe { lib.ftable().FPDFPage_CountObjects(handle) };
+    lib.assert(count >= 0)?;
+
+    // Note: This only covers objects directly placed on the page, not ones
+    // nested in form XObjects.
+    (0..count)
+        .map(|index| {
+            let obj = unsafe { lib.ftable().FPDFPage_GetObject(handle, index) };
+            let obj = lib.assert_handle(obj)?;
+
+            let ty = unsafe { lib.ftable().FPDFPageObj_GetType(obj.get()) };
+            Ok(PageObjectType::from_i32(ty as _))
+        })
+        .collect()
+}
@@ -5,8 +5,23 @@ use crate::types::{Point2, Rect, Vector2};
 use crate::utils::sync::Rc;
 use crate::{Library, Result};
 
-use super::render;
-use super::{PageRenderLayout, PageRotation, ProgressiveRender, RenderFlags};
+use super::{link, render};
+use super::{Link, LinkTarget, PageRenderLayout, PageRotation, ProgressiveRender, RenderFlags};
+
+#[cfg(feature = "structure")]
+use crate::doc::structure::StructTree;
+
+#[cfg(feature = "text")]
+use super::TextPage;
+
+#[cfg(feature = "fonts")]
+use super::{PageObjectType, TextObject};
+
+#[cfg(feature = "annotations")]
+use super::{Annotation, AnnotationSubtype};
+
+#[cfg(feature = "annotations")]
+use crate::Capabilities;
 
 use std::ffi::{c_double, c_int};
 
@@ -15,6 +30,7 @@ use simba::scalar::SupersetOf;
 
 pub type PageHandle = Handle<pdfium_sys::fpdf_page_t__>;
 
+/// A loaded page of a document. Clones refer to the same page.
 #[derive(Clone)]
 pub struct Page {
     inner: Rc<PageInner>,
@@ -199,6 +215,27 @@ impl Page {
         nalgebra::try_convert(m).unwrap()
     }
 
+    /// All links of this page.
+    pub fn links(&self) -> Vec<Link> {
+        link::links(self.document(), self.handle().get())
+    }
+
+    /// The link at the given point in page space (PDF points, origin at the
+    /// bottom left), if any.
+    pub fn link_at(&self, x: f64, y: f64) -> Option<Link> {
+        link::link_at(self.document(), self.handle().get(), x, y)
+    }
+
+    /// The URI of the link at the given point in page space (PDF points,
+    /// origin at the bottom left), if there is a link pointing to an external
+    /// resource.
+    pub fn uri_at(&self, x: f64, y: f64) -> Option<String> {
+        match self.link_at(x, y)?.target {
+            LinkTarget::Uri(uri) => Some(uri),
+            _ => None,
+        }
+    }
+
     /// Render this page to a bitmap, using the specified layout and options.
     ///
     /// Translation, scaling, and rotation (90° steps) can be specified via
@@ -346,6 +383,89 @@ impl Page {
     }
 }
 
+#[cfg(feature = "structure")]
+impl Page {
+    /// Get the structure tree of this page.
+    ///
+    /// Fails if the document is not tagged, or with
+    /// [`Error::Unsupported`](crate::Error::Unsupported) if the library does
+    /// not provide access to structure trees.
+    pub fn struct_tree(&self) -> Result<StructTree> {
+        StructTree::new(self.clone())
+    }
+}
+
+#[cfg(feature = "text")]
+impl Page {
+    /// Load the text content of this page.
+    pub fn text(&self) -> Result<TextPage> {
+        TextPage::new(self.clone())
+    }
+}
+
+#[cfg(feature = "fonts")]
+impl Page {
+    /// The text objects of this page with their fonts, e.g. for finding text
+    /// drawn with substituted fonts.
+    pub fn text_objects(&self) -> Result<Vec<TextObject>> {
+        super::font::text_objects(self)
+    }
+
+    /// The types of all objects of this page, e.g. for counting images.
+    pub fn object_types(&self) -> Result<Vec<PageObjectType>> {
+        super::object::object_types(self)
+    }
+}
+
+#[cfg(feature = "annotations")]
+impl Page {
+    /// Number of annotations on this page.
+    ///
+    /// Returns zero if annotations are not supported by the library.
+    pub fn annotation_count(&self) -> u32 {
+        let lib = self.library();
+
+        if !lib.capabilities().contains(Capabilities::Annotations) {
+            return 0;
+        }
+
+        let n = unsafe { lib.ftable().FPDFPage_GetAnnotCount(self.handle().get()) };
+
+        n.max(0) as u32
+    }
+
+    /// Get the annotation at the given index.
+    pub fn annotation(&self, index: u32) -> Result<Annotation> {
+        Annotation::new(self.clone(), index)
+    }
+
+    /// Add a new annotation of the given subtype to this page.
+    ///
+    /// The annotation is added to the document in memory only, i.e. the file
+    /// is not modified unless the document is saved.
+    pub fn create_annotation(&self, subtype: AnnotationSubtype) -> Result<Annotation> {
+        Annotation::create(self.clone(), subtype)
+    }
+
+    /// Remove the annotation at the given index from this page.
+    pub fn remove_annotation(&self, index: u32) -> Result<()> {
+        let lib = self.library();
+        lib.require(Capabilities::Annotations)?;
+
+        let status = unsafe {
+            lib.ftable()
+                .FPDFPage_RemoveAnnot(self.handle().get(), index as _)
+        };
+
+        lib.assert(status != 0)
+    }
+
+    /// Iterate over all annotations of this page.
+    pub fn annotations(&self) -> impl Iterator<Item = Annotation> + '_ {
+        (0..self.annotation_count()).filter_map(|i| self.annotation(i).ok())
+    }
+}
+
 impl Drop for PageInner {
     fn drop(&mut self) {
         unsafe { self.lib.ftable().FPDF_ClosePage(self.handle.get()) };
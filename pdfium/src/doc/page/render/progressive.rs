@@ -37,10 +37,18 @@ impl<'a, 'b, C, F> ProgressiveRender<'a, 'b, C, F> {
         self.bitmap
     }
 
+    /// Status of the render after the last render call.
+    ///
+    /// Failed renders are not represented by a status, instead the render
+    /// call fails with [`Error::InvalidOperation`].
     pub fn status(&self) -> ProgressiveRenderStatus {
         self.status
     }
 
+    /// Continue a paused render, until paused again or completed.
+    ///
+    /// Fails with [`Error::InvalidOperation`] if pdfium reports the render as
+    /// failed, in which case the bitmap may only be partly drawn.
     pub fn render_continue(&mut self) -> Result<ProgressiveRenderStatus>
     where
         F: FnMut() -> bool,
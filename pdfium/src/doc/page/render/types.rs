@@ -1,7 +1,7 @@
 use nalgebra::{Point2, Vector2};
 
 /// Page rotation used for rendering.
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum PageRotation {
     /// Do not rotate.
     None,
@@ -0,0 +1,305 @@
+use std::ops::Range;
+
+use crate::bindings::Handle;
+use crate::doc::Page;
+use crate::types::{Point2, Rect, Vector2};
+use crate::{Capabilities, Result};
+
+pub type TextPageHandle = Handle<pdfium_sys::fpdf_textpage_t__>;
+
+bitflags::bitflags! {
+    /// Options for searching text, see [`TextPage::find()`].
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct SearchFlags: u32 {
+        /// Match case.
+        const MatchCase = pdfium_sys::FPDF_MATCHCASE;
+
+        /// Only match whole words.
+        const MatchWholeWord = pdfium_sys::FPDF_MATCHWHOLEWORD;
+
+        /// Match consecutive occurrences, i.e. search for the next match
+        /// directly behind the start of the previous one instead of its end.
+        const Consecutive = pdfium_sys::FPDF_CONSECUTIVE;
+    }
+}
+
+/// The text content of a page.
+///
+/// Characters are addressed by their index. Indices correspond to UTF-16 code
+/// units of the text returned by [`TextPage::text()`].
+pub struct TextPage {
+    page: Page,
+    handle: TextPageHandle,
+}
+
+impl TextPage {
+    pub(crate) fn new(page: Page) -> Result<Self> {
+        let lib = page.library();
+        lib.require(Capabilities::Text)?;
+
+        let handle = unsafe { lib.ftable().FPDFText_LoadPage(page.handle().get()) };
+        let handle = lib.assert_handle(handle)?;
+
+        Ok(Self { page, handle })
+    }
+
+    pub fn handle(&self) -> &TextPageHandle {
+        &self.handle
+    }
+
+    pub fn page(&self) -> &Page {
+        &self.page
+    }
+
+    /// Number of characters on the page.
+    pub fn count(&self) -> u32 {
+        let n = unsafe {
+            self.page
+                .library()
+                .ftable()
+                .FPDFText_CountChars(self.handle.get())
+        };
+
+        n.max(0) as u32
+    }
+
+    /// The full text of the page.
+    pub fn text(&self) -> Result<String> {
+        self.text_range(0, self.count())
+    }
+
+    /// The text of `count` characters, starting at the character with index
+    /// `start`.
+    pub fn text_range(&self, start: u32, count: u32) -> Result<String> {
+        let lib = self.page.library();
+
+        if count == 0 {
+            return Ok(String::new());
+        }
+
+        // buffer needs to include space for the null-terminator
+        let mut buffer: Vec<u16> = vec![0; count as usize + 1];
+
+        let n = unsafe {
+            lib.ftable().FPDFText_GetText(
+                self.handle.get(),
+                start as _,
+                count as _,
+                buffer.as_mut_ptr(),
+            )
+        };
+        lib.assert(n > 0)?;
+
+        // strip null-terminator
+        buffer.truncate(n as usize - 1);
+
+        String::from_utf16(&buffer).map_err(|_| crate::Error::InvalidEncoding)
+    }
+
+    /// Bounding box of the character with the given index, in PDF page
+    /// coordinates.
+    pub fn char_box(&self, index: u32) -> Result<Rect> {
+        let lib = self.page.library();
+
+        let mut left = 0.0;
+        let mut right = 0.0;
+        let mut bottom = 0.0;
+        let mut top = 0.0;
+
+        let status = unsafe {
+            lib.ftable().FPDFText_GetCharBox(
+                self.handle.get(),
+                index as _,
+                &mut left,
+                &mut right,
+                &mut bottom,
+                &mut top,
+            )
+        };
+        lib.assert(status != 0)?;
+
+        Ok(Rect {
+            left: left as _,
+            top: top as _,
+            right: right as _,
+            bottom: bottom as _,
+        })
+    }
+
+    /// Loose bounding box of the character with the given index, in PDF page
+    /// coordinates.
+    ///
+    /// In contrast to [`char_box()`](Self::char_box), which tightly fits the
+    /// glyph, this covers the full height of the font, i.e. from its descent
+    /// to its ascent, so that boxes of characters on the same line line up.
+    /// For rotated characters, this is the axis-aligned bounding box of the
+    /// rotated font box.
+    pub fn loose_char_box(&self, index: u32) -> Result<Rect> {
+        let lib = self.page.library();
+
+        let mut rect = pdfium_sys::FS_RECTF {
+            left: 0.0,
+            top: 0.0,
+            right: 0.0,
+            bottom: 0.0,
+        };
+
+        let status = unsafe {
+            lib.ftable()
+                .FPDFText_GetLooseCharBox(self.handle.get(), index as _, &mut rect)
+        };
+        lib.assert(status != 0)?;
+
+        Ok(Rect::from(rect))
+    }
+
+    /// Origin of the character with the given index, i.e. the point on its
+    /// baseline where the glyph starts, in PDF page coordinates.
+    pub fn char_origin(&self, index: u32) -> Result<Point2<f64>> {
+        let lib = self.page.library();
+
+        let mut x = 0.0;
+        let mut y = 0.0;
+
+        let status = unsafe {
+            lib.ftable()
+                .FPDFText_GetCharOrigin(self.handle.get(), index as _, &mut x, &mut y)
+        };
+        lib.assert(status != 0)?;
+
+        Ok(Point2::new(x, y))
+    }
+
+    /// Rotation of the character with the given index, i.e. the angle of its
+    /// baseline, counter-clockwise in radians from zero to 2π.
+    pub fn char_angle(&self, index: u32) -> Result<f32> {
+        let lib = self.page.library();
+
+        let angle = unsafe {
+            lib.ftable()
+                .FPDFText_GetCharAngle(self.handle.get(), index as _)
+        };
+
+        // -1 indicates an error
+        lib.assert(angle >= 0.0)?;
+
+        Ok(angle)
+    }
+
+    /// Index of the character at or near the given point, in PDF page
+    /// coordinates, or `None` if there is no character within the given
+    /// tolerance (in PDF points).
+    pub fn char_index_at(
+        &self,
+        point: Point2<f64>,
+        tolerance: Vector2<f64>,
+    ) -> Result<Option<u32>> {
+        let lib = self.page.library();
+
+        let index = unsafe {
+            lib.ftable().FPDFText_GetCharIndexAtPos(
+                self.handle.get(),
+                point.x,
+                point.y,
+                tolerance.x,
+                tolerance.y,
+            )
+        };
+
+        // -1 means no character has been found, -3 indicates an error
+        lib.assert(index >= -1)?;
+
+        Ok((index >= 0).then_some(index as u32))
+    }
+
+    /// Areas covered by `count` characters, starting at the character with
+    /// index `start`, in PDF page coordinates.
+    ///
+    /// Boxes of adjacent characters on the same line and with the same font
+    /// are merged, i.e. this returns roughly one rectangle per line, e.g. for
+    /// highlighting selected text.
+    pub fn rects(&self, start: u32, count: u32) -> Result<Vec<Rect>> {
+        let lib = self.page.library();
+        let handle = self.handle.get();
+
+        let n = unsafe {
+            lib.ftable()
+                .FPDFText_CountRects(handle, start as _, count as _)
+        };
+        lib.assert(n >= 0)?;
+
+        let mut rects = Vec::with_capacity(n as usize);
+
+        for i in 0..n {
+            let mut left = 0.0;
+            let mut top = 0.0;
+            let mut right = 0.0;
+            let mut bottom = 0.0;
+
+            let status = unsafe {
+                lib.ftable().FPDFText_GetRect(
+                    handle,
+                    i,
+                    &mut left,
+                    &mut top,
+                    &mut right,
+                    &mut bottom,
+                )
+            };
+            lib.assert(status != 0)?;
+
+            rects.push(Rect {
+                left: left as _,
+                top: top as _,
+                right: right as _,
+                bottom: bottom as _,
+            });
+        }
+
+        Ok(rects)
+    }
+
+    /// Find all occurrences of the given text, returning the range of
+    /// character indices of each match, in order.
+    pub fn find(&self, query: &str, flags: SearchFlags) -> Result<Vec<Range<u32>>> {
+        let lib = self.page.library();
+
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let query = crate::utils::utf16le::to_wide(query);
+
+        let handle = unsafe {
+            lib.ftable()
+                .FPDFText_FindStart(self.handle.get(), query.as_ptr(), flags.bits() as _, 0)
+        };
+        lib.assert(!handle.is_null())?;
+
+        let mut matches = Vec::new();
+
+        while unsafe { lib.ftable().FPDFText_FindNext(handle) } != 0 {
+            let start = unsafe { lib.ftable().FPDFText_GetSchResultIndex(handle) };
+            let count = unsafe { lib.ftable().FPDFText_GetSchCount(handle) };
+
+            if start >= 0 && count > 0 {
+                matches.push(start as u32..(start + count) as u32);
+            }
+        }
+
+        unsafe { lib.ftable().FPDFText_FindClose(handle) };
+
+        Ok(matches)
+    }
+}
+
+impl Drop for TextPage {
+    fn drop(&mut self) {
+        unsafe {
+            self.page
+                .library()
+                .ftable()
+                .FPDFText_ClosePage(self.handle.get())
+        };
+    }
+}
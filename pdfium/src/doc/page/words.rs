@@ -0,0 +1,294 @@
+//! Structure of the text of a page, i.e. its characters grouped into words,
+//! lines, and paragraphs, each with bounding boxes.
+//!
+//! Grouping relies on the whitespace and line breaks pdfium generates when
+//! extracting text, so words and lines follow pdfium's reading order.
+//! Paragraphs are separated by vertical gaps between lines.
+
+use std::ops::Range;
+
+use crate::doc::TextPage;
+use crate::types::{Point2, Rect};
+use crate::Result;
+
+/// Minimum vertical gap between two lines to start a new paragraph, relative
+/// to the height of the upper line.
+const PARAGRAPH_GAP: f32 = 0.6;
+
+/// A word, i.e. a run of characters without whitespace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextWord {
+    pub text: String,
+
+    /// Indices of the characters of the word on the text page.
+    pub chars: Range<u32>,
+
+    /// Bounds of the word, in PDF page coordinates.
+    pub rect: Rect,
+}
+
+/// A line of words.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    pub words: Vec<TextWord>,
+
+    /// Bounds of the line, in PDF page coordinates.
+    pub rect: Rect,
+}
+
+/// A paragraph, i.e. consecutive lines without larger vertical gap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextParagraph {
+    /// Indices of the lines of the paragraph.
+    pub lines: Range<usize>,
+
+    /// Bounds of the paragraph, in PDF page coordinates.
+    pub rect: Rect,
+}
+
+/// The text of a page grouped into words, lines, and paragraphs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextStructure {
+    pub lines: Vec<TextLine>,
+    pub paragraphs: Vec<TextParagraph>,
+}
+
+impl TextLine {
+    /// Text of the line, with words separated by single spaces.
+    pub fn text(&self) -> String {
+        let words: Vec<_> = self.words.iter().map(|w| w.text.as_str()).collect();
+        words.join(" ")
+    }
+
+    /// Indices of the characters of the line on the text page.
+    pub fn chars(&self) -> Range<u32> {
+        match (self.words.first(), self.words.last()) {
+            (Some(first), Some(last)) => first.chars.start..last.chars.end,
+            _ => 0..0,
+        }
+    }
+}
+
+impl TextStructure {
+    /// All words of the page, in reading order.
+    pub fn words(&self) -> impl Iterator<Item = &TextWord> {
+        self.lines.iter().flat_map(|line| line.words.iter())
+    }
+
+    /// The word containing the character with the given index, e.g. for
+    /// snapping a selection to word boundaries.
+    pub fn word_containing(&self, index: u32) -> Option<&TextWord> {
+        self.words().find(|word| word.chars.contains(&index))
+    }
+
+    /// The word at the given point, in PDF page coordinates.
+    pub fn word_at(&self, point: Point2<f32>) -> Option<&TextWord> {
+        self.words().find(|word| contains(&word.rect, &point))
+    }
+
+    /// The line at the given point, in PDF page coordinates.
+    pub fn line_at(&self, point: Point2<f32>) -> Option<&TextLine> {
+        self.lines.iter().find(|line| contains(&line.rect, &point))
+    }
+
+    /// Group the given characters, with their index on the text page and
+    /// their bounds, if any, into words, lines, and paragraphs. Characters
+    /// without bounds, e.g. generated ones, only separate words and lines.
+    pub fn from_chars(chars: impl IntoIterator<Item = (u32, char, Option<Rect>)>) -> Self {
+        let mut lines = Vec::new();
+        let mut words = Vec::new();
+        let mut word: Option<TextWord> = None;
+
+        for (index, c, rect) in chars {
+            // whitespace ends the current word, line breaks the current line
+            if c.is_whitespace() {
+                words.extend(word.take());
+
+                if c == '\n' && !words.is_empty() {
+                    lines.push(line(std::mem::take(&mut words)));
+                }
+                continue;
+            }
+
+            let rect = match rect {
+                Some(rect) => rect,
+                None => {
+                    words.extend(word.take());
+                    continue;
+                }
+            };
+
+            match &mut word {
+                Some(word) => {
+                    word.text.push(c);
+                    word.chars.end = index + c.len_utf16() as u32;
+                    word.rect = union(&word.rect, &rect);
+                }
+                None => {
+                    word = Some(TextWord {
+                        text: c.into(),
+                        chars: index..index + c.len_utf16() as u32,
+                        rect,
+                    })
+                }
+            }
+        }
+
+        words.extend(word);
+        if !words.is_empty() {
+            lines.push(line(words));
+        }
+
+        let paragraphs = paragraphs(&lines);
+
+        Self { lines, paragraphs }
+    }
+}
+
+impl TextPage {
+    /// The text of this page grouped into words, lines, and paragraphs.
+    pub fn structure(&self) -> Result<TextStructure> {
+        let text = self.text()?;
+
+        let mut chars = Vec::with_capacity(text.len());
+
+        // character indices correspond to UTF-16 code units
+        let mut index = 0;
+
+        for c in text.chars() {
+            // generated characters, e.g. line breaks, do not have bounds
+            let rect = self.char_box(index).ok();
+
+            chars.push((index, c, rect));
+            index += c.len_utf16() as u32;
+        }
+
+        Ok(TextStructure::from_chars(chars))
+    }
+}
+
+fn line(words: Vec<TextWord>) -> TextLine {
+    let rect = words
+        .iter()
+        .skip(1)
+        .fold(words[0].rect, |rect, word| union(&rect, &word.rect));
+
+    TextLine { words, rect }
+}
+
+/// Group lines into paragraphs by the vertical gaps between them.
+fn paragraphs(lines: &[TextLine]) -> Vec<TextParagraph> {
+    let mut paragraphs: Vec<TextParagraph> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(para) = paragraphs.last_mut() {
+            let prev = &lines[i - 1].rect;
+            let height = prev.top - prev.bottom;
+
+            // PDF coordinates have their origin at the bottom left corner, so
+            // lines further down the page have smaller coordinates, a line
+            // above the previous one usually starts a new column
+            let gap = prev.bottom - line.rect.top;
+            let above = line.rect.bottom > prev.top;
+
+            if gap <= height * PARAGRAPH_GAP && !above {
+                para.lines.end = i + 1;
+                para.rect = union(&para.rect, &line.rect);
+                continue;
+            }
+        }
+
+        paragraphs.push(TextParagraph {
+            lines: i..i + 1,
+            rect: line.rect,
+        });
+    }
+
+    paragraphs
+}
+
+fn union(a: &Rect, b: &Rect) -> Rect {
+    Rect {
+        left: a.left.min(b.left),
+        top: a.top.max(b.top),
+        right: a.right.max(b.right),
+        bottom: a.bottom.min(b.bottom),
+    }
+}
+
+fn contains(rect: &Rect, point: &Point2<f32>) -> bool {
+    (rect.left..=rect.right).contains(&point.x) && (rect.bottom..=rect.top).contains(&point.y)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Characters of the given lines, set in a monospace grid with the given
+    /// vertical position of each line.
+    fn chars(lines: &[(&str, f32)]) -> Vec<(u32, char, Option<Rect>)> {
+        let mut chars = Vec::new();
+        let mut index = 0;
+
+        for (text, top) in lines {
+            for (x, c) in text.chars().enumerate() {
+                let rect = Rect {
+                    left: x as f32 * 10.0,
+                    top: *top,
+                    right: x as f32 * 10.0 + 8.0,
+                    bottom: top - 10.0,
+                };
+
+                chars.push((index, c, (!c.is_whitespace()).then_some(rect)));
+                index += 1;
+            }
+
+            chars.push((index, '\r', None));
+            chars.push((index + 1, '\n', None));
+            index += 2;
+        }
+
+        chars
+    }
+
+    #[test]
+    fn words_and_lines() {
+        let text = TextStructure::from_chars(chars(&[("one two", 700.0), ("three", 688.0)]));
+
+        assert_eq!(text.lines.len(), 2);
+        assert_eq!(text.lines[0].text(), "one two");
+        assert_eq!(text.lines[1].text(), "three");
+
+        let two = &text.lines[0].words[1];
+        assert_eq!(two.text, "two");
+        assert_eq!(two.chars, 4..7);
+        assert_eq!(
+            two.rect,
+            Rect {
+                left: 40.0,
+                top: 700.0,
+                right: 68.0,
+                bottom: 690.0
+            }
+        );
+
+        assert_eq!(text.word_containing(5), Some(two));
+        assert_eq!(text.word_at(Point2::new(45.0, 695.0)), Some(two));
+        assert_eq!(text.word_at(Point2::new(35.0, 695.0)), None);
+        assert_eq!(text.lines[1].chars(), 9..14);
+    }
+
+    #[test]
+    fn paragraphs_by_gaps() {
+        let text = TextStructure::from_chars(chars(&[
+            ("a", 700.0),
+            ("b", 688.0),
+            ("c", 660.0),
+            ("d", 800.0),
+        ]));
+
+        let lines: Vec<_> = text.paragraphs.iter().map(|p| p.lines.clone()).collect();
+        assert_eq!(lines, vec![0..2, 2..3, 3..4]);
+        assert_eq!(text.paragraphs[0].rect.bottom, 678.0);
+    }
+}
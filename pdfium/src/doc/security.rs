@@ -0,0 +1,64 @@
+use crate::doc::Document;
+
+bitflags::bitflags! {
+    /// Operations permitted on a document by its author (PDF 32000-1, section
+    /// 7.6.3.2). Unencrypted documents permit everything.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct Permissions: u32 {
+        /// Print the document, possibly at low quality only.
+        const Print = 1 << 2;
+
+        /// Modify the contents of the document.
+        const Modify = 1 << 3;
+
+        /// Copy or otherwise extract text and graphics.
+        const Copy = 1 << 4;
+
+        /// Add or modify annotations and fill in form fields.
+        const Annotate = 1 << 5;
+
+        /// Fill in form fields, even if annotations may not be modified.
+        const FillForms = 1 << 8;
+
+        /// Extract text and graphics for accessibility purposes.
+        const ExtractForAccessibility = 1 << 9;
+
+        /// Insert, rotate, or delete pages and create bookmarks.
+        const Assemble = 1 << 10;
+
+        /// Print the document at full quality.
+        const PrintHighQuality = 1 << 11;
+    }
+}
+
+/// Encryption state of a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Security {
+    /// Revision of the standard security handler, if the document is
+    /// encrypted.
+    pub revision: Option<u32>,
+
+    /// Operations permitted on the document.
+    pub permissions: Permissions,
+}
+
+impl Security {
+    /// Whether the document is encrypted, e.g. to restrict permissions.
+    pub fn is_encrypted(&self) -> bool {
+        self.revision.is_some()
+    }
+}
+
+pub(crate) fn load(doc: &Document) -> Security {
+    let lib = doc.library();
+    let handle = doc.handle().get();
+
+    // the revision is -1 if the document has no security handler
+    let revision = unsafe { lib.ftable().FPDF_GetSecurityHandlerRevision(handle) };
+    let permissions = unsafe { lib.ftable().FPDF_GetDocPermissions(handle) };
+
+    Security {
+        revision: u32::try_from(revision).ok(),
+        permissions: Permissions::from_bits_truncate(permissions as u32),
+    }
+}
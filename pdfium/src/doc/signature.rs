@@ -0,0 +1,136 @@
+use std::ffi::c_void;
+
+use crate::doc::Document;
+use crate::{Capabilities, Result};
+
+/// A digital signature of a document.
+#[derive(Debug, Clone)]
+pub struct Signature {
+    /// The raw signature value, for public-key signatures usually a
+    /// DER-encoded PKCS#7 binary.
+    pub contents: Vec<u8>,
+
+    /// The encoding of the signature value, e.g. `adbe.pkcs7.detached`.
+    pub sub_filter: Option<String>,
+
+    /// The reason for signing, as given by the signer.
+    pub reason: Option<String>,
+
+    /// The time of signing, as PDF date string (e.g. `D:20230102150405+01'00'`).
+    pub time: Option<String>,
+}
+
+pub(crate) fn load(doc: &Document) -> Result<Vec<Signature>> {
+    let lib = doc.library();
+    lib.require(Capabilities::Signatures)?;
+
+    let handle = doc.handle().get();
+
+    let count = unsafe { lib.ftable().FPDF_GetSignatureCount(handle) };
+    lib.assert(count >= 0)?;
+
+    let mut signatures = Vec::with_capacity(count as usize);
+
+    for index in 0..count {
+        let sig = unsafe { lib.ftable().FPDF_GetSignatureObject(handle, index) };
+        let sig = lib.assert_handle(sig)?;
+
+        signatures.push(Signature {
+            contents: contents(doc, sig.get()),
+            sub_filter: ascii(doc, sig.get(), AsciiField::SubFilter),
+            reason: reason(doc, sig.get())?,
+            time: ascii(doc, sig.get(), AsciiField::Time),
+        });
+    }
+
+    Ok(signatures)
+}
+
+fn contents(doc: &Document, sig: pdfium_sys::FPDF_SIGNATURE) -> Vec<u8> {
+    let lib = doc.library();
+
+    let len = unsafe {
+        lib.ftable()
+            .FPDFSignatureObj_GetContents(sig, std::ptr::null_mut(), 0)
+    };
+
+    let mut buffer: Vec<u8> = vec![0; len as usize];
+    let buffer_p = buffer.as_mut_ptr() as *mut c_void;
+
+    if len > 0 {
+        let res = unsafe {
+            lib.ftable()
+                .FPDFSignatureObj_GetContents(sig, buffer_p, buffer.len() as _)
+        };
+
+        assert_eq!(res, len);
+    }
+
+    buffer
+}
+
+fn reason(doc: &Document, sig: pdfium_sys::FPDF_SIGNATURE) -> Result<Option<String>> {
+    let lib = doc.library();
+
+    // get length, including trailing zeros
+    let len = unsafe {
+        lib.ftable()
+            .FPDFSignatureObj_GetReason(sig, std::ptr::null_mut(), 0)
+    };
+
+    // zero-length or null-terminator only means the reason is not present
+    if len <= 2 {
+        return Ok(None);
+    }
+
+    // get actual string as bytes
+    let mut buffer: Vec<u8> = vec![0; len as usize];
+    let buffer_p = buffer.as_mut_ptr() as *mut c_void;
+
+    let res = unsafe {
+        lib.ftable()
+            .FPDFSignatureObj_GetReason(sig, buffer_p, buffer.len() as _)
+    };
+
+    assert_eq!(res, len);
+
+    // convert bytes to string
+    let value = crate::utils::utf16le::from_bytes(&buffer)?;
+    Ok(Some(value))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum AsciiField {
+    SubFilter,
+    Time,
+}
+
+fn ascii(doc: &Document, sig: pdfium_sys::FPDF_SIGNATURE, field: AsciiField) -> Option<String> {
+    let lib = doc.library();
+    let ftable = lib.ftable();
+
+    let get = |buffer: *mut std::ffi::c_char, len: _| unsafe {
+        match field {
+            AsciiField::SubFilter => ftable.FPDFSignatureObj_GetSubFilter(sig, buffer, len),
+            AsciiField::Time => ftable.FPDFSignatureObj_GetTime(sig, buffer, len),
+        }
+    };
+
+    // get length, including trailing zero
+    let len = get(std::ptr::null_mut(), 0);
+
+    // zero-length or null-terminator only means the value is not present
+    if len <= 1 {
+        return None;
+    }
+
+    // get actual string as bytes
+    let mut buffer: Vec<u8> = vec![0; len as usize];
+    let res = get(buffer.as_mut_ptr() as *mut _, buffer.len() as _);
+
+    assert_eq!(res, len);
+
+    // strip trailing zero, the value is always 7-bit ASCII
+    buffer.truncate(len as usize - 1);
+    String::from_utf8(buffer).ok()
+}
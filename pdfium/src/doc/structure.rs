@@ -0,0 +1,350 @@
+//! Access to the logical structure of tagged PDF documents.
+//!
+//! The structure tree describes the logical organization of the page content
+//! (e.g. paragraphs, headings, figures) and carries information such as
+//! alternate descriptions of figures. Elements reference the page content via
+//! marked-content IDs (MCIDs).
+
+use std::collections::HashSet;
+use std::ffi::{c_int, c_ulong, c_void, CString};
+
+use crate::bindings::{FnTable, Handle};
+use crate::doc::Page;
+use crate::types::Rect;
+use crate::{Capabilities, Result};
+
+pub type StructTreeHandle = Handle<pdfium_sys::fpdf_structtree_t__>;
+pub type StructElementHandle = Handle<pdfium_sys::fpdf_structelement_t__>;
+
+/// The structure tree of a single page.
+pub struct StructTree {
+    page: Page,
+    handle: StructTreeHandle,
+}
+
+/// An element of a structure tree.
+///
+/// Elements are owned by the tree and can only be accessed while the tree is
+/// alive.
+#[derive(Clone)]
+pub struct StructElement<'a> {
+    tree: &'a StructTree,
+    handle: StructElementHandle,
+}
+
+impl StructTree {
+    pub(crate) fn new(page: Page) -> Result<Self> {
+        let lib = page.library();
+        lib.require(Capabilities::Structure)?;
+
+        let handle = unsafe { lib.ftable().FPDF_StructTree_GetForPage(page.handle().get()) };
+        let handle = lib.assert_handle(handle)?;
+
+        Ok(Self { page, handle })
+    }
+
+    pub fn handle(&self) -> &StructTreeHandle {
+        &self.handle
+    }
+
+    pub fn page(&self) -> &Page {
+        &self.page
+    }
+
+    /// Number of top-level elements.
+    pub fn count(&self) -> u32 {
+        let n = unsafe {
+            self.page
+                .library()
+                .ftable()
+                .FPDF_StructTree_CountChildren(self.handle.get())
+        };
+
+        n.max(0) as u32
+    }
+
+    /// Get the top-level element at the given index.
+    pub fn get(&self, index: u32) -> Result<StructElement<'_>> {
+        let lib = self.page.library();
+
+        let elem = unsafe {
+            lib.ftable()
+                .FPDF_StructTree_GetChildAtIndex(self.handle.get(), index as _)
+        };
+        let elem = lib.assert_handle(elem)?;
+
+        Ok(StructElement::new(self, elem))
+    }
+
+    /// Iterate over all top-level elements.
+    pub fn children(&self) -> impl Iterator<Item = StructElement<'_>> + '_ {
+        (0..self.count()).filter_map(|i| self.get(i).ok())
+    }
+}
+
+impl Drop for StructTree {
+    fn drop(&mut self) {
+        unsafe {
+            self.page
+                .library()
+                .ftable()
+                .FPDF_StructTree_Close(self.handle.get())
+        };
+    }
+}
+
+impl<'a> StructElement<'a> {
+    fn new(tree: &'a StructTree, handle: StructElementHandle) -> Self {
+        Self { tree, handle }
+    }
+
+    pub fn handle(&self) -> &StructElementHandle {
+        &self.handle
+    }
+
+    pub fn tree(&self) -> &'a StructTree {
+        self.tree
+    }
+
+    /// The structure type of this element, e.g. `P`, `H1`, or `Figure`.
+    pub fn element_type(&self) -> Result<Option<String>> {
+        self.get_string(|ftable, elem, buf, len| unsafe {
+            ftable.FPDF_StructElement_GetType(elem, buf, len)
+        })
+    }
+
+    /// The object type of this element, usually `StructElem`.
+    pub fn object_type(&self) -> Result<Option<String>> {
+        self.get_string(|ftable, elem, buf, len| unsafe {
+            ftable.FPDF_StructElement_GetObjType(elem, buf, len)
+        })
+    }
+
+    /// The title of this element.
+    pub fn title(&self) -> Result<Option<String>> {
+        self.get_string(|ftable, elem, buf, len| unsafe {
+            ftable.FPDF_StructElement_GetTitle(elem, buf, len)
+        })
+    }
+
+    /// The alternate description of this element, e.g. for figures.
+    pub fn alt_text(&self) -> Result<Option<String>> {
+        self.get_string(|ftable, elem, buf, len| unsafe {
+            ftable.FPDF_StructElement_GetAltText(elem, buf, len)
+        })
+    }
+
+    /// The replacement text of this element.
+    pub fn actual_text(&self) -> Result<Option<String>> {
+        self.get_string(|ftable, elem, buf, len| unsafe {
+            ftable.FPDF_StructElement_GetActualText(elem, buf, len)
+        })
+    }
+
+    /// The ID of this element.
+    pub fn id(&self) -> Result<Option<String>> {
+        self.get_string(|ftable, elem, buf, len| unsafe {
+            ftable.FPDF_StructElement_GetID(elem, buf, len)
+        })
+    }
+
+    /// The natural language of this element, e.g. `en-US`.
+    pub fn lang(&self) -> Result<Option<String>> {
+        self.get_string(|ftable, elem, buf, len| unsafe {
+            ftable.FPDF_StructElement_GetLang(elem, buf, len)
+        })
+    }
+
+    /// The value of a string attribute, e.g. `Role`.
+    ///
+    /// Fails with [`InvalidEncoding`](crate::Error::InvalidEncoding) if the
+    /// name contains a null byte.
+    pub fn string_attribute(&self, name: &str) -> Result<Option<String>> {
+        let name = CString::new(name).map_err(|_| crate::Error::InvalidEncoding)?;
+
+        self.get_string(|ftable, elem, buf, len| unsafe {
+            ftable.FPDF_StructElement_GetStringAttribute(elem, name.as_ptr(), buf, len)
+        })
+    }
+
+    /// The marked-content IDs directly referenced by this element.
+    pub fn marked_content_ids(&self) -> Vec<i32> {
+        let ftable = self.tree.page.library().ftable();
+        let elem = self.handle.get();
+
+        let n = unsafe { ftable.FPDF_StructElement_GetMarkedContentIdCount(elem) };
+
+        (0..n)
+            .map(|i| unsafe { ftable.FPDF_StructElement_GetMarkedContentIdAtIndex(elem, i) })
+            .filter(|id| *id >= 0)
+            .collect()
+    }
+
+    /// The parent of this element, or `None` for top-level elements.
+    pub fn parent(&self) -> Option<StructElement<'a>> {
+        let parent = unsafe {
+            self.tree
+                .page
+                .library()
+                .ftable()
+                .FPDF_StructElement_GetParent(self.handle.get())
+        };
+
+        std::ptr::NonNull::new(parent).map(|p| StructElement::new(self.tree, Handle::new(p)))
+    }
+
+    /// Number of child elements.
+    pub fn count(&self) -> u32 {
+        let n = unsafe {
+            self.tree
+                .page
+                .library()
+                .ftable()
+                .FPDF_StructElement_CountChildren(self.handle.get())
+        };
+
+        n.max(0) as u32
+    }
+
+    /// Get the child element at the given index.
+    ///
+    /// Note that children may also be content items or object references
+    /// instead of elements. These cannot be accessed and result in an error.
+    pub fn get(&self, index: u32) -> Result<StructElement<'a>> {
+        let lib = self.tree.page.library();
+
+        let elem = unsafe {
+            lib.ftable()
+                .FPDF_StructElement_GetChildAtIndex(self.handle.get(), index as _)
+        };
+        let elem = lib.assert_handle(elem)?;
+
+        Ok(StructElement::new(self.tree, elem))
+    }
+
+    /// Iterate over all child elements.
+    pub fn children(&self) -> impl Iterator<Item = StructElement<'a>> + '_ {
+        (0..self.count()).filter_map(|i| self.get(i).ok())
+    }
+
+    /// Compute the bounds of the content covered by this element and all of
+    /// its descendants, in page coordinates.
+    ///
+    /// Returns `None` if the element does not reference any page content.
+    pub fn bounds(&self) -> Option<Rect> {
+        let mut ids = HashSet::new();
+        self.collect_marked_content_ids(&mut ids);
+
+        if ids.is_empty() {
+            return None;
+        }
+
+        content_bounds(&self.tree.page, &ids)
+    }
+
+    fn collect_marked_content_ids(&self, ids: &mut HashSet<i32>) {
+        ids.extend(self.marked_content_ids());
+
+        for child in self.children() {
+            child.collect_marked_content_ids(ids);
+        }
+    }
+
+    fn get_string<F>(&self, get: F) -> Result<Option<String>>
+    where
+        F: Fn(&FnTable, pdfium_sys::FPDF_STRUCTELEMENT, *mut c_void, c_ulong) -> c_ulong,
+    {
+        let lib = self.tree.page.library();
+        let elem = self.handle.get();
+
+        // get length, including trailing zeros
+        let len = get(&lib.ftable(), elem, std::ptr::null_mut(), 0);
+
+        // zero-length or null-terminator only means the value is not present
+        if len <= 2 {
+            return Ok(None);
+        }
+
+        // get actual string as bytes
+        let mut buffer: Vec<u8> = vec![0; len as usize];
+        let buffer_p = buffer.as_mut_ptr() as *mut c_void;
+
+        let res = get(&lib.ftable(), elem, buffer_p, buffer.len() as _);
+
+        // the value must not change between both calls
+        if res != len {
+            return Err(crate::Error::InvalidOperation);
+        }
+
+        // convert bytes to string
+        let value = crate::utils::utf16le::from_bytes(&buffer)?;
+        Ok(Some(value))
+    }
+}
+
+/// Compute the union of the bounds of all page objects tagged with any of the
+/// given marked-content IDs.
+fn content_bounds(page: &Page, ids: &HashSet<i32>) -> Option<Rect> {
+    let ftable = page.library().ftable();
+    let page = page.handle().get();
+
+    let key = CString::new("MCID").unwrap();
+    let mut bounds: Option<Rect> = None;
+
+    let n = unsafe { ftable.FPDFPage_CountObjects(page) };
+
+    for i in 0..n {
+        let obj = unsafe { ftable.FPDFPage_GetObject(page, i) };
+        if obj.is_null() {
+            continue;
+        }
+
+        // check if any of the marks of this object references one of our IDs
+        let n_marks = unsafe { ftable.FPDFPageObj_CountMarks(obj) };
+
+        let tagged = (0..n_marks).any(|j| {
+            let mark = unsafe { ftable.FPDFPageObj_GetMark(obj, j as _) };
+            if mark.is_null() {
+                return false;
+            }
+
+            let mut id: c_int = -1;
+            let status =
+                unsafe { ftable.FPDFPageObjMark_GetParamIntValue(mark, key.as_ptr(), &mut id) };
+
+            status != 0 && ids.contains(&id)
+        });
+
+        if !tagged {
+            continue;
+        }
+
+        // get object bounds and merge them
+        let mut r = Rect {
+            left: 0.0,
+            top: 0.0,
+            right: 0.0,
+            bottom: 0.0,
+        };
+
+        let status = unsafe {
+            ftable.FPDFPageObj_GetBounds(obj, &mut r.left, &mut r.bottom, &mut r.right, &mut r.top)
+        };
+
+        if status == 0 {
+            continue;
+        }
+
+        bounds = Some(match bounds {
+            Some(b) => Rect {
+                left: b.left.min(r.left),
+                top: b.top.max(r.top),
+                right: b.right.max(r.right),
+                bottom: b.bottom.min(r.bottom),
+            },
+            None => r,
+        });
+    }
+
+    bounds
+}
@@ -2,6 +2,8 @@ use std::ffi::c_ulong;
 
 use thiserror::Error;
 
+use crate::Capabilities;
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Invalid encoding")]
@@ -13,6 +15,12 @@ pub enum Error {
     #[error("Invalid operation")]
     InvalidOperation,
 
+    #[error("Not supported by the loaded pdfium library: {0:?}")]
+    Unsupported(Capabilities),
+
+    #[error("Not enabled at build time, requires cargo features: {}", .0.features().join(", "))]
+    Disabled(Capabilities),
+
     #[error("Error accessing shared library")]
     LibraryError(#[from] libloading::Error),
 
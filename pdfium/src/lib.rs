@@ -1,5 +1,50 @@
+//! Safe bindings for [pdfium], the PDF library used by Chromium.
+//!
+//! The pdfium shared library is loaded at runtime, i.e. it has to be in the
+//! library search path when calling [`Library::init()`]. Bindings are
+//! generated for pdfium `chromium/5541`, newer builds generally work as well.
+//! Prebuilt binaries are available at
+//! <https://github.com/bblanchon/pdfium-binaries>.
+//!
+//! Rendering and basic document access are always available. Optional parts
+//! of the pdfium API, e.g. text extraction or annotations, are enabled by the
+//! respective cargo features, see [`Capabilities`]. The `sync` feature makes
+//! all types `Send` and `Sync`, serializing calls into the library.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use pdfium::bitmap::{Bitmap, BitmapFormat, Color};
+//! use pdfium::doc::{PageRenderLayout, PageRotation, RenderFlags};
+//! use pdfium::types::{Point2, Vector2};
+//! use pdfium::Library;
+//!
+//! # fn main() -> pdfium::Result<()> {
+//! let lib = Library::init()?;
+//! let doc = lib.load_file("document.pdf", None)?;
+//!
+//! let page = doc.pages().get(0)?;
+//! let (width, height) = (page.width() as u32, page.height() as u32);
+//!
+//! let mut bmp = Bitmap::uninitialized(lib.clone(), width, height, BitmapFormat::Bgra)?;
+//! bmp.fill_rect(0, 0, width, height, Color::WHITE);
+//!
+//! let layout = PageRenderLayout {
+//!     start: Point2::new(0, 0),
+//!     size: Vector2::new(width as _, height as _),
+//!     rotate: PageRotation::None,
+//! };
+//!
+//! page.render(&mut bmp, &layout, RenderFlags::Annotations);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [pdfium]: https://pdfium.googlesource.com/pdfium/
+
 #![allow(clippy::module_inception)]
 
+mod capabilities;
 mod error;
 mod library;
 
@@ -11,8 +56,9 @@ pub mod types;
 pub(crate) mod io;
 pub(crate) mod utils;
 
+pub use capabilities::Capabilities;
 pub use error::{Error, ErrorCode, Result};
-pub use library::{Config, Library};
+pub use library::{Config, FnTableGuard, Library};
 
 #[cfg(test)]
 mod test {
@@ -2,7 +2,7 @@ use crate::bindings::{Bindings, FnTable, Handle};
 use crate::doc::{Document, DocumentBacking};
 use crate::io::fileaccess::ReaderAccess;
 use crate::utils::sync::Rc;
-use crate::{Error, ErrorCode, Result};
+use crate::{Capabilities, Error, ErrorCode, Result};
 
 use std::ffi::{c_void, CString};
 use std::fs::File;
@@ -10,8 +10,10 @@ use std::io::{Read, Seek};
 use std::path::{Path, PathBuf};
 use std::ptr::NonNull;
 
+/// Options for initializing the library.
 #[derive(Debug, Default, Clone)]
 pub struct Config {
+    /// Directories to search for fonts, in addition to the system fonts.
     pub user_font_paths: Option<Vec<PathBuf>>,
 }
 
@@ -23,6 +25,7 @@ pub struct Library {
 
 struct LibraryGuard {
     ftable: FnTableWrapper,
+    capabilities: Capabilities,
 }
 
 #[cfg(not(feature = "sync"))]
@@ -38,6 +41,7 @@ type FnTableWrapper = std::sync::Mutex<FnTable>;
 pub type FnTableGuard<'a> = std::sync::MutexGuard<'a, FnTable>;
 
 impl Library {
+    /// Initialize the library with the given, already loaded bindings.
     pub fn init_with_bindings(bindings: Bindings, config: &Config) -> Result<Library> {
         // convert user font paths to null-terminated array of C-string pointers
         let paths = config
@@ -76,6 +80,7 @@ impl Library {
         // build library struct
         let inner = LibraryGuard {
             ftable: FnTableWrapper::new(bindings.ftable),
+            capabilities: bindings.capabilities,
         };
 
         let lib = Library {
@@ -87,18 +92,50 @@ impl Library {
         Ok(lib)
     }
 
+    /// Load the pdfium shared library from the search path and initialize
+    /// it with the given options.
     pub fn init_with_config(config: &Config) -> Result<Library> {
         Self::init_with_bindings(Bindings::load()?, config)
     }
 
+    /// Load the pdfium shared library from the search path and initialize
+    /// it with default options.
     pub fn init() -> Result<Library> {
         Self::init_with_config(&Config::default())
     }
 
+    /// Raw function table of the bindings, e.g. for calling functions not
+    /// (yet) wrapped by this crate. Locks the library while held.
     pub fn ftable(&self) -> FnTableGuard {
         self.inner.ftable()
     }
 
+    /// Optional feature sets supported by this library.
+    pub fn capabilities(&self) -> Capabilities {
+        self.inner.capabilities
+    }
+
+    /// Fail with [`Error::Disabled`] if any of the given capabilities has not
+    /// been enabled at build time, or with [`Error::Unsupported`] if it is
+    /// not supported by this library.
+    ///
+    /// The wrappers of each optional part of the API check their capability
+    /// this way before calling into the library.
+    pub fn require(&self, capabilities: Capabilities) -> Result<()> {
+        let disabled = capabilities - Capabilities::enabled();
+        if !disabled.is_empty() {
+            return Err(Error::Disabled(disabled));
+        }
+
+        let missing = capabilities - self.capabilities();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Unsupported(missing))
+        }
+    }
+
     pub(crate) fn assert_status(&self) -> Result<()> {
         let err = unsafe { self.ftable().FPDF_GetLastError() };
         crate::error::error_code_to_result(err)?;
@@ -124,6 +161,8 @@ impl Library {
         }
     }
 
+    /// Load the document from the given file, decrypting it with the given
+    /// password if required.
     pub fn load_file<P>(&self, path: P, password: Option<&str>) -> Result<Document>
     where
         P: AsRef<Path>,
@@ -135,6 +174,9 @@ impl Library {
         self.load_reader(file, password)
     }
 
+    /// Load the document from the given reader, decrypting it with the given
+    /// password if required. The reader is kept for loading parts of the
+    /// document on demand.
     pub fn load_reader<R>(&self, reader: R, password: Option<&str>) -> Result<Document>
     where
         R: Read + Seek + 'static,
@@ -171,6 +213,8 @@ impl Library {
         Ok(document)
     }
 
+    /// Load the document from the given buffer, decrypting it with the given
+    /// password if required.
     pub fn load_buffer(&self, buffer: Vec<u8>, password: Option<&str>) -> Result<Document> {
         // convert password to null-terminated C-string
         let password = password
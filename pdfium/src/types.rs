@@ -32,6 +32,68 @@ impl From<&Rect> for pdfium_sys::FS_RECTF {
     }
 }
 
+/// A quadrilateral, e.g. the area of a highlight annotation covering a
+/// single line of text.
+///
+/// The corners are given in the order used by PDF: top-left, top-right,
+/// bottom-left, bottom-right.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quad {
+    pub x1: f32,
+    pub y1: f32,
+    pub x2: f32,
+    pub y2: f32,
+    pub x3: f32,
+    pub y3: f32,
+    pub x4: f32,
+    pub y4: f32,
+}
+
+impl From<&Rect> for Quad {
+    fn from(rect: &Rect) -> Self {
+        Self {
+            x1: rect.left,
+            y1: rect.top,
+            x2: rect.right,
+            y2: rect.top,
+            x3: rect.left,
+            y3: rect.bottom,
+            x4: rect.right,
+            y4: rect.bottom,
+        }
+    }
+}
+
+impl From<pdfium_sys::FS_QUADPOINTSF> for Quad {
+    fn from(other: pdfium_sys::FS_QUADPOINTSF) -> Self {
+        Self {
+            x1: other.x1,
+            y1: other.y1,
+            x2: other.x2,
+            y2: other.y2,
+            x3: other.x3,
+            y3: other.y3,
+            x4: other.x4,
+            y4: other.y4,
+        }
+    }
+}
+
+impl From<&Quad> for pdfium_sys::FS_QUADPOINTSF {
+    fn from(other: &Quad) -> Self {
+        Self {
+            x1: other.x1,
+            y1: other.y1,
+            x2: other.x2,
+            y2: other.y2,
+            x3: other.x3,
+            y3: other.y3,
+            x4: other.x4,
+            y4: other.y4,
+        }
+    }
+}
+
 pub fn affine_from_pdfmatrix(m: &pdfium_sys::FS_MATRIX) -> Affine2<f32> {
     nalgebra::try_convert(matrix![
         m.a, m.c, m.e;
@@ -20,3 +20,9 @@ pub fn from_bytes(bytes: &[u8]) -> Result<String> {
     let value = String::from_utf16(&chars).map_err(|_| Error::InvalidEncoding)?;
     Ok(value)
 }
+
+/// Encode a string as null-terminated UTF-16, e.g. for use as `FPDF_WIDESTRING`.
+#[cfg(any(feature = "annotations", feature = "text"))]
+pub fn to_wide(value: &str) -> Vec<u16> {
+    value.encode_utf16().chain(std::iter::once(0)).collect()
+}
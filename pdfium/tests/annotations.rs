@@ -0,0 +1,114 @@
+#![cfg(feature = "annotations")]
+
+use pdfium::bitmap::{Bitmap, BitmapFormat, Color};
+use pdfium::doc::AnnotationSubtype;
+use pdfium::types::{Point2, Quad, Rect};
+use pdfium::Library;
+
+mod common;
+
+#[test]
+fn test_highlight() {
+    let lib = Library::init().unwrap();
+    let doc = lib.load_buffer(common::document(&["Hello"]), None).unwrap();
+    let page = doc.pages().get(0).unwrap();
+
+    let rect = Rect {
+        left: 72.0,
+        top: 744.0,
+        right: 144.0,
+        bottom: 716.0,
+    };
+    let color = Color::new_rgba(255, 255, 0, 128);
+
+    let annot = page
+        .create_annotation(AnnotationSubtype::Highlight)
+        .unwrap();
+    annot.set_rect(&rect).unwrap();
+    annot.set_color(color).unwrap();
+    annot.append_attachment_points(&Quad::from(&rect)).unwrap();
+    drop(annot);
+
+    assert_eq!(page.annotation_count(), 1);
+
+    let annot = page.annotation(0).unwrap();
+    assert_eq!(annot.subtype(), AnnotationSubtype::Highlight);
+    assert_eq!(annot.color(), Some(color));
+    assert_eq!(annot.attachment_points().unwrap(), vec![Quad::from(&rect)]);
+}
+
+#[test]
+fn test_ink() {
+    let lib = Library::init().unwrap();
+    let doc = lib.load_buffer(common::document(&["Hello"]), None).unwrap();
+    let page = doc.pages().get(0).unwrap();
+
+    let stroke = [
+        Point2::new(100.0, 100.0),
+        Point2::new(150.0, 120.0),
+        Point2::new(200.0, 100.0),
+    ];
+
+    let annot = page.create_annotation(AnnotationSubtype::Ink).unwrap();
+    assert_eq!(annot.add_ink_stroke(&stroke).unwrap(), 0);
+    annot.append_path(&stroke, Color::BLACK, 2.0).unwrap();
+
+    assert_eq!(annot.ink_strokes().unwrap(), vec![stroke.to_vec()]);
+
+    annot.remove_ink_strokes().unwrap();
+    assert!(annot.ink_strokes().unwrap().is_empty());
+}
+
+#[test]
+fn test_path_unsupported() {
+    let lib = Library::init().unwrap();
+    let doc = lib.load_buffer(common::document(&["Hello"]), None).unwrap();
+    let page = doc.pages().get(0).unwrap();
+
+    let annot = page.create_annotation(AnnotationSubtype::Text).unwrap();
+    annot.set_contents("Note").unwrap();
+
+    let points = [Point2::new(0.0, 0.0), Point2::new(1.0, 1.0)];
+    assert!(annot.append_path(&points, Color::BLACK, 1.0).is_err());
+    assert_eq!(annot.contents().unwrap().as_deref(), Some("Note"));
+}
+
+#[test]
+fn test_stamp() {
+    let lib = Library::init().unwrap();
+    let doc = lib.load_buffer(common::document(&["Hello"]), None).unwrap();
+    let page = doc.pages().get(0).unwrap();
+
+    let rect = Rect {
+        left: 72.0,
+        top: 744.0,
+        right: 216.0,
+        bottom: 700.0,
+    };
+    let red = Color::new_rgb(204, 26, 26);
+
+    let mut image = Bitmap::uninitialized(lib.clone(), 4, 4, BitmapFormat::Bgra).unwrap();
+    image.fill_rect(0, 0, 4, 4, red);
+
+    let annot = page.create_annotation(AnnotationSubtype::Stamp).unwrap();
+    annot.set_rect(&rect).unwrap();
+    annot
+        .append_text("APPROVED", "Helvetica-Bold", red, &rect)
+        .unwrap();
+    annot.append_image(&image, &rect).unwrap();
+    annot.set_contents("APPROVED").unwrap();
+    drop(annot);
+
+    let annot = page.annotation(0).unwrap();
+    assert_eq!(annot.subtype(), AnnotationSubtype::Stamp);
+    assert_eq!(annot.contents().unwrap().as_deref(), Some("APPROVED"));
+
+    // text must not be empty
+    let annot = page.create_annotation(AnnotationSubtype::Stamp).unwrap();
+    assert!(annot.append_text("", "Helvetica", red, &rect).is_err());
+
+    // other subtypes do not support appearance objects
+    let annot = page.create_annotation(AnnotationSubtype::Text).unwrap();
+    assert!(annot.append_text("Note", "Helvetica", red, &rect).is_err());
+    assert!(annot.append_image(&image, &rect).is_err());
+}
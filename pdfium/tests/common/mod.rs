@@ -0,0 +1,83 @@
+//! Helpers shared between integration tests.
+//!
+//! Integration tests require the pdfium shared library in the library search
+//! path, in the version the bindings have been generated for (see the
+//! pdfium-sys crate).
+
+/// Size of the pages of test documents, in PDF points (US Letter).
+pub const PAGE_SIZE: (f32, f32) = (612.0, 792.0);
+
+/// Build a document with one page per given text, each showing the text in a
+/// single line at the top left of the page.
+pub fn document(pages: &[&str]) -> Vec<u8> {
+    document_with_dests(pages, &[])
+}
+
+/// Build a document like [`document`], with named destinations pointing to
+/// the top left of the page with the given index.
+pub fn document_with_dests(pages: &[&str], dests: &[(&str, usize)]) -> Vec<u8> {
+    let mut objects = Vec::new();
+
+    // catalog and page tree, pages and their contents follow
+    let kids: Vec<_> = (0..pages.len())
+        .map(|i| format!("{} 0 R", 4 + 2 * i))
+        .collect();
+
+    let dests: Vec<_> = dests
+        .iter()
+        .map(|(name, page)| format!("/{name} [{} 0 R /XYZ 72 720 0]", 4 + 2 * page))
+        .collect();
+
+    objects.push(format!(
+        "<< /Type /Catalog /Pages 2 0 R /Dests << {} >> >>",
+        dests.join(" ")
+    ));
+    objects.push(format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        kids.join(" "),
+        pages.len()
+    ));
+    objects.push("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_owned());
+
+    for (i, text) in pages.iter().enumerate() {
+        let content = format!("BT /F1 24 Tf 72 720 Td ({text}) Tj ET");
+
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            PAGE_SIZE.0,
+            PAGE_SIZE.1,
+            5 + 2 * i
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{content}\nendstream",
+            content.len()
+        ));
+    }
+
+    // write objects, remembering their offsets for the cross-reference table
+    let mut buffer = b"%PDF-1.7\n".to_vec();
+    let mut offsets = Vec::new();
+
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(buffer.len());
+        buffer.extend(format!("{} 0 obj\n{object}\nendobj\n", i + 1).bytes());
+    }
+
+    let xref = buffer.len();
+    buffer.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+
+    for offset in offsets {
+        buffer.extend(format!("{offset:010} 00000 n \n").bytes());
+    }
+
+    buffer.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        )
+        .bytes(),
+    );
+
+    buffer
+}
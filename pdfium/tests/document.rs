@@ -0,0 +1,125 @@
+use pdfium::bitmap::{Bitmap, BitmapFormat, Color};
+use pdfium::doc::{PageRenderLayout, PageRotation, Permissions, RenderFlags};
+use pdfium::types::{Point2, Vector2};
+use pdfium::{Capabilities, Error, Library};
+
+mod common;
+
+#[test]
+fn test_load_buffer() {
+    let lib = Library::init().unwrap();
+    let doc = lib
+        .load_buffer(common::document(&["first", "second"]), None)
+        .unwrap();
+
+    assert_eq!(doc.pages().count(), 2);
+    assert!(doc.pages().get(2).is_err());
+}
+
+#[test]
+fn test_load_invalid() {
+    let lib = Library::init().unwrap();
+
+    assert!(lib.load_buffer(b"not a document".to_vec(), None).is_err());
+}
+
+#[test]
+fn test_require_capabilities() {
+    let lib = Library::init().unwrap();
+
+    // capabilities of disabled features are never reported as supported
+    assert!(Capabilities::enabled().contains(lib.capabilities()));
+    assert!(lib.require(lib.capabilities()).is_ok());
+
+    // requiring them fails and names the features to enable instead
+    let disabled = Capabilities::all() - Capabilities::enabled();
+    if !disabled.is_empty() {
+        match lib.require(Capabilities::all()) {
+            Err(Error::Disabled(caps)) => assert_eq!(caps, disabled),
+            _ => panic!("disabled capabilities have been accepted"),
+        }
+    }
+}
+
+#[test]
+fn test_page_size() {
+    let lib = Library::init().unwrap();
+    let doc = lib.load_buffer(common::document(&["page"]), None).unwrap();
+
+    let page = doc.pages().get(0).unwrap();
+    let (width, height) = common::PAGE_SIZE;
+
+    assert_eq!((page.width(), page.height()), (width, height));
+    assert_eq!(
+        doc.pages().get_size(0).unwrap(),
+        (width as f64, height as f64)
+    );
+}
+
+#[test]
+fn test_metadata_and_security() {
+    let lib = Library::init().unwrap();
+    let doc = lib.load_buffer(common::document(&["page"]), None).unwrap();
+
+    // test documents have neither an information dictionary nor encryption
+    assert!(doc.metadata().entries().unwrap().is_empty());
+
+    let security = doc.security();
+    assert!(!security.is_encrypted());
+    assert_eq!(security.permissions, Permissions::all());
+}
+
+#[test]
+fn test_named_destinations() {
+    let lib = Library::init().unwrap();
+    let pages = ["first", "second"];
+    let dests = [("intro", 0), ("details", 1)];
+    let doc = lib
+        .load_buffer(common::document_with_dests(&pages, &dests), None)
+        .unwrap();
+
+    let dest = doc.named_destination("details").unwrap();
+
+    assert_eq!(dest.page, 1);
+    assert_eq!((dest.left, dest.top), (Some(72.0), Some(720.0)));
+    assert_eq!(dest.zoom, None);
+    assert!(doc.named_destination("missing").is_none());
+
+    let mut names: Vec<_> = doc
+        .named_destinations()
+        .unwrap()
+        .into_iter()
+        .map(|(name, dest)| (name, dest.page))
+        .collect();
+    names.sort();
+
+    assert_eq!(names, [("details".to_owned(), 1), ("intro".to_owned(), 0)]);
+}
+
+#[test]
+fn test_render() {
+    let lib = Library::init().unwrap();
+    let doc = lib.load_buffer(common::document(&["page"]), None).unwrap();
+    let page = doc.pages().get(0).unwrap();
+
+    // render at a quarter of the page size
+    let (width, height) = (153, 198);
+
+    let mut bmp = Bitmap::uninitialized(lib.clone(), width, height, BitmapFormat::Bgra).unwrap();
+    bmp.fill_rect(0, 0, width, height, Color::TRANSPARENT);
+
+    let layout = PageRenderLayout {
+        start: Point2::new(0, 0),
+        size: Vector2::new(width as _, height as _),
+        rotate: PageRotation::None,
+    };
+
+    page.render(&mut bmp, &layout, RenderFlags::empty());
+
+    // the text is drawn near the top of the page, the rest stays untouched
+    let stride = bmp.stride() as usize;
+    let (top, bottom) = bmp.buf().split_at(stride * height as usize / 4);
+
+    assert!(top.iter().any(|b| *b != 0));
+    assert!(bottom.iter().all(|b| *b == 0));
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "text")]
+
+use pdfium::doc::SearchFlags;
+use pdfium::Library;
+
+mod common;
+
+#[test]
+fn test_text() {
+    let lib = Library::init().unwrap();
+    let doc = lib
+        .load_buffer(common::document(&["Hello World"]), None)
+        .unwrap();
+
+    let text = doc.pages().get(0).unwrap().text().unwrap();
+
+    assert_eq!(text.text().unwrap().trim(), "Hello World");
+    assert_eq!(text.text_range(6, 5).unwrap(), "World");
+}
+
+#[test]
+fn test_find() {
+    let lib = Library::init().unwrap();
+    let doc = lib
+        .load_buffer(common::document(&["one two one"]), None)
+        .unwrap();
+
+    let text = doc.pages().get(0).unwrap().text().unwrap();
+
+    let matches = text.find("one", SearchFlags::empty()).unwrap();
+    assert_eq!(matches, vec![0..3, 8..11]);
+
+    let matches = text.find("ONE", SearchFlags::MatchCase).unwrap();
+    assert!(matches.is_empty());
+
+    // the matched text lies within the top half of the page
+    let rects = text.rects(0, 3).unwrap();
+    assert!(!rects.is_empty());
+    assert!(rects.iter().all(|r| r.bottom > 396.0));
+}
+
+#[test]
+fn test_structure() {
+    let lib = Library::init().unwrap();
+    let doc = lib
+        .load_buffer(common::document(&["Hello World"]), None)
+        .unwrap();
+
+    let text = doc.pages().get(0).unwrap().text().unwrap();
+    let structure = text.structure().unwrap();
+
+    assert_eq!(structure.lines.len(), 1);
+    assert_eq!(structure.paragraphs.len(), 1);
+
+    let words: Vec<_> = structure.words().map(|w| w.text.as_str()).collect();
+    assert_eq!(words, vec!["Hello", "World"]);
+
+    // words follow each other on the same line at the top of the page
+    let (hello, world) = (&structure.lines[0].words[0], &structure.lines[0].words[1]);
+    assert_eq!(world.chars, 6..11);
+    assert!(hello.rect.right < world.rect.left);
+    assert!(world.rect.bottom > 396.0);
+}
+
+#[test]
+fn test_char_geometry() {
+    let lib = Library::init().unwrap();
+    let doc = lib
+        .load_buffer(common::document(&["Hello World"]), None)
+        .unwrap();
+
+    let text = doc.pages().get(0).unwrap().text().unwrap();
+
+    let tight = text.char_box(0).unwrap();
+    let loose = text.loose_char_box(0).unwrap();
+    let origin = text.char_origin(0).unwrap();
+
+    // the loose box covers the glyph and the line, starting at the origin
+    assert!(loose.bottom <= tight.bottom && loose.top >= tight.top);
+    assert!((origin.x as f32 - loose.left).abs() < 1.0);
+    assert!(origin.y as f32 > loose.bottom && (origin.y as f32) < loose.top);
+
+    // text is not rotated
+    assert_eq!(text.char_angle(0).unwrap(), 0.0);
+    assert!(text.char_angle(text.count()).is_err());
+}